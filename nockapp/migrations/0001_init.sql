@@ -0,0 +1,30 @@
+-- Registered documents, models, and verified queries
+
+CREATE TABLE documents (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    commitment TEXT NOT NULL,
+    owner TEXT NOT NULL,
+    registered_at INTEGER NOT NULL
+);
+
+CREATE TABLE models (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    model_hash TEXT NOT NULL,
+    model_name TEXT NOT NULL,
+    registered_at INTEGER NOT NULL
+);
+
+CREATE TABLE queries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    proof_digest TEXT NOT NULL,
+    document_commitment TEXT NOT NULL,
+    model_hash TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    verified INTEGER NOT NULL,
+    -- JSON VerificationFailure, NULL when verified
+    reason TEXT,
+    verified_at INTEGER NOT NULL
+);
+
+CREATE INDEX documents_commitment ON documents (commitment);
+CREATE INDEX models_model_hash ON models (model_hash);
@@ -0,0 +1,6 @@
+-- Document hashes in the server-maintained Merkle tree
+
+CREATE TABLE document_hashes (
+    hash TEXT PRIMARY KEY,
+    document_id INTEGER NOT NULL REFERENCES documents (id)
+);
@@ -0,0 +1,5 @@
+-- Indexes for filtering the query listing
+
+CREATE INDEX queries_document_commitment ON queries (document_commitment COLLATE NOCASE);
+CREATE INDEX queries_model_hash ON queries (model_hash COLLATE NOCASE);
+CREATE INDEX queries_verified_at ON queries (verified_at);
@@ -0,0 +1,13 @@
+-- Webhooks notified of verification outcomes
+
+CREATE TABLE webhooks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    url TEXT NOT NULL,
+    -- HMAC key payloads are signed with
+    secret TEXT NOT NULL,
+    -- Subject of the token that registered it
+    owner TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX webhooks_owner ON webhooks (owner);
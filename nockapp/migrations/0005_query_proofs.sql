@@ -0,0 +1,4 @@
+-- Proofs as submitted, for auditors to verify again
+
+-- JSON ProofEnvelope; NULL for queries recorded before proofs were kept
+ALTER TABLE queries ADD COLUMN envelope TEXT;
@@ -0,0 +1,4 @@
+-- Revoked documents and models, whose queries no longer verify
+
+ALTER TABLE documents ADD COLUMN revoked_at INTEGER;
+ALTER TABLE models ADD COLUMN revoked_at INTEGER;
@@ -0,0 +1,16 @@
+-- Responses recorded under clients' Idempotency-Key headers
+
+CREATE TABLE idempotency_keys (
+    -- Subject of the caller; keys are per caller
+    scope TEXT NOT NULL,
+    key TEXT NOT NULL,
+    -- SHA-256 of the method, path and body
+    request_digest TEXT NOT NULL,
+    -- NULL while the request is running
+    status INTEGER,
+    body BLOB,
+    created_at INTEGER NOT NULL,
+    PRIMARY KEY (scope, key)
+);
+
+CREATE INDEX idempotency_keys_created_at ON idempotency_keys (created_at);
@@ -0,0 +1,4 @@
+-- Media type of responses kept under idempotency keys, now that proof
+-- routes also answer in CBOR and MessagePack; NULL is JSON
+
+ALTER TABLE idempotency_keys ADD COLUMN content_type TEXT;
@@ -0,0 +1,15 @@
+-- Verifying keys uploaded or rescheduled through the key rotation API,
+-- loaded into the verifier at startup on top of the key directory's
+
+CREATE TABLE verifying_keys (
+    -- Hex SHA-256 of the compressed key
+    key_id TEXT PRIMARY KEY,
+    circuit_id TEXT NOT NULL,
+    curve TEXT NOT NULL,
+    key BLOB NOT NULL,
+    activates_at INTEGER NOT NULL,
+    deprecated_at INTEGER,
+    retires_at INTEGER,
+    -- When the key or its schedule was last changed
+    updated_at INTEGER NOT NULL
+);
@@ -0,0 +1,4 @@
+-- Signed receipts of verifications, for clients to present offline
+
+-- JSON VerificationReceipt; NULL when the server had no receipt key
+ALTER TABLE queries ADD COLUMN receipt TEXT;
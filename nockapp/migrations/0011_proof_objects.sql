@@ -0,0 +1,5 @@
+-- Proofs archived in an object store
+
+-- Key of the proof bytes in the archive; the envelope then holds an empty
+-- proof. NULL when the proof is kept in the envelope.
+ALTER TABLE queries ADD COLUMN proof_key TEXT;
@@ -0,0 +1,12 @@
+-- Nonces issued by POST /api/v1/challenge, deleted when a verification
+-- spends them
+
+CREATE TABLE challenges (
+    -- Hex of 32 random bytes
+    nonce TEXT PRIMARY KEY,
+    -- Subject of the caller it was issued to
+    subject TEXT NOT NULL,
+    expires_at INTEGER NOT NULL
+);
+
+CREATE INDEX challenges_expires_at ON challenges (expires_at);
@@ -0,0 +1,14 @@
+-- Nullifiers of accepted proofs, recorded with their query when the
+-- service refuses replays
+
+CREATE TABLE nullifiers (
+    -- Hex SHA-256 of the proof's public inputs (zkrag_verifier::nullifier)
+    nullifier TEXT PRIMARY KEY,
+    -- Proof timestamp divided by the configured epoch length
+    epoch INTEGER NOT NULL,
+    -- Query that spent it; kept when retention deletes the query
+    query_id INTEGER NOT NULL,
+    recorded_at INTEGER NOT NULL
+);
+
+CREATE INDEX nullifiers_epoch ON nullifiers (epoch, query_id);
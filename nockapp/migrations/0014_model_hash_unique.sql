@@ -0,0 +1,16 @@
+-- Model hashes are stored in lowercase, one row per model. Duplicates left
+-- by earlier case-sensitive registration keep the first id, revoked if any
+-- copy was.
+
+UPDATE models SET model_hash = lower(model_hash);
+
+UPDATE models
+   SET revoked_at = (SELECT MIN(revoked_at) FROM models AS copy
+                      WHERE copy.model_hash = models.model_hash)
+ WHERE revoked_at IS NULL;
+
+DELETE FROM models
+ WHERE id NOT IN (SELECT MIN(id) FROM models GROUP BY model_hash);
+
+DROP INDEX models_model_hash;
+CREATE UNIQUE INDEX models_model_hash ON models (model_hash);
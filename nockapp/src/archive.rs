@@ -0,0 +1,180 @@
+// Proof archive
+//
+// Proofs are several kilobytes each, too many to keep in the database of a
+// busy deployment. Given an object store, the server writes each proof's
+// bytes there and keeps only the envelope's metadata and the object key in
+// the query's row; `/api/v1/query/:id/proof` puts the two back together.
+//
+// Objects are named by the proof's digest (`proofs/<sha256>` under the
+// archive URL), so a proof verified twice is stored once, and the bytes read
+// back are checked against the name. If the archive can't be written the
+// proof is kept in the database instead and a warning logged. Proofs recorded
+// before the archive was configured stay in the database.
+//
+// Query retention (see retention.rs) doesn't delete objects, as queries may
+// share one; give the bucket a lifecycle rule expiring them after the longest
+// retention. A proof fetched after its object expired is `not_found`.
+//
+// Configuration (the `[archive]` table; see config.rs):
+// - url (ZKRAG_ARCHIVE_URL): `s3://bucket/prefix`, `gs://bucket/prefix` or
+//   `file:///var/lib/zkrag/proofs`; unset keeps proofs in the database.
+//   Credentials and endpoints come from the usual AWS_* and GOOGLE_*
+//   environment variables, e.g. AWS_ENDPOINT and AWS_ALLOW_HTTP for MinIO.
+
+use anyhow::Context;
+use object_store::path::Path;
+use object_store::{parse_url_opts, ObjectStore};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use url::Url;
+use zkrag_verifier::ProofEnvelope;
+
+use crate::error::{ApiError, ErrorCode};
+use crate::store::StoredProof;
+
+/// Where proofs are archived
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ArchiveConfig {
+    pub url: Option<String>,
+}
+
+impl ArchiveConfig {
+    /// The archive, or `None` when proofs stay in the database
+    pub fn open(&self) -> anyhow::Result<Option<ProofArchive>> {
+        self.url.as_deref().map(ProofArchive::open).transpose()
+    }
+}
+
+/// Proof bytes in an object store
+pub struct ProofArchive {
+    objects: Box<dyn ObjectStore>,
+    root: Path,
+}
+
+impl ProofArchive {
+    /// Open the archive at `url`, taking credentials from the environment
+    pub fn open(url: &str) -> anyhow::Result<Self> {
+        let url = Url::parse(url).with_context(|| format!("Invalid archive URL {}", url))?;
+        let options = std::env::vars()
+            .filter(|(name, _)| name.starts_with("AWS_") || name.starts_with("GOOGLE_"))
+            .map(|(name, value)| (name.to_ascii_lowercase(), value));
+        let (objects, root) =
+            parse_url_opts(&url, options).with_context(|| format!("Opening archive {}", url))?;
+        Ok(Self { objects, root })
+    }
+
+    fn path(&self, key: &str) -> Path {
+        key.split('/')
+            .fold(self.root.clone(), |path, part| path.child(part))
+    }
+
+    /// Store `proof`, returning its key
+    pub async fn put(&self, proof: &[u8]) -> anyhow::Result<String> {
+        let key = format!("proofs/{}", hex::encode(Sha256::digest(proof)));
+        self.objects
+            .put(&self.path(&key), proof.to_vec().into())
+            .await
+            .with_context(|| format!("Archiving {}", key))?;
+        Ok(key)
+    }
+
+    /// Proof bytes stored under `key`, or `None` if they're gone
+    pub async fn get(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let bytes = match self.objects.get(&self.path(key)).await {
+            Ok(object) => object.bytes().await,
+            Err(object_store::Error::NotFound { .. }) => return Ok(None),
+            Err(e) => Err(e),
+        }
+        .with_context(|| format!("Reading archived {}", key))?;
+        let digest = hex::encode(Sha256::digest(&bytes));
+        if !key.ends_with(&digest) {
+            anyhow::bail!("Archived {} doesn't match its digest", key);
+        }
+        Ok(Some(bytes.to_vec()))
+    }
+}
+
+/// The envelope recorded with query `id`, its proof read back from the
+/// archive if it was moved there
+pub async fn restore(
+    archive: Option<&ProofArchive>,
+    id: u64,
+    stored: StoredProof,
+) -> Result<ProofEnvelope, ApiError> {
+    let Some(key) = stored.archive_key else {
+        return Ok(stored.envelope);
+    };
+    let Some(archive) = archive else {
+        return Err(ApiError::internal(
+            "Reading archived proof",
+            format!(
+                "query {} proof is archived but no archive is configured",
+                id
+            ),
+        ));
+    };
+    let proof = archive
+        .get(&key)
+        .await
+        .map_err(|e| ApiError::internal("Reading archived proof", format!("{:#}", e)))?
+        .ok_or_else(|| {
+            ApiError::new(
+                ErrorCode::NotFound,
+                format!("Proof of query {} is no longer archived", id),
+            )
+        })?;
+    Ok(ProofEnvelope {
+        proof,
+        ..stored.envelope
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zkrag_verifier::PublicInputs;
+
+    #[tokio::test]
+    async fn test_archive_round_trip() {
+        let archive = ProofArchive::open("memory:///proofs").unwrap();
+        let proof = vec![7; 128];
+        let key = archive.put(&proof).await.unwrap();
+        assert_eq!(
+            key,
+            format!("proofs/{}", zkrag_verifier::proof_digest(&proof))
+        );
+        assert_eq!(archive.get(&key).await.unwrap(), Some(proof.clone()));
+        assert_eq!(archive.get("proofs/00").await.unwrap(), None);
+
+        // Restoring fills the proof back into the recorded envelope
+        let envelope = ProofEnvelope::new(
+            proof.clone(),
+            PublicInputs {
+                document_commitment: "cd".repeat(32),
+                model_hash: "ef".repeat(32),
+                timestamp: 1_700_000_000,
+                nonce: None,
+            },
+        );
+        let stored = StoredProof {
+            envelope: ProofEnvelope {
+                proof: Vec::new(),
+                ..envelope.clone()
+            },
+            archive_key: Some(key),
+        };
+        let restored = restore(Some(&archive), 1, stored.clone()).await.unwrap();
+        assert_eq!(restored, envelope);
+        assert!(restore(None, 1, stored).await.is_err());
+
+        // Bytes that don't match their name are refused
+        let forged = format!("proofs/{}", "ab".repeat(32));
+        archive
+            .objects
+            .put(&archive.path(&forged), vec![1].into())
+            .await
+            .unwrap();
+        assert!(archive.get(&forged).await.is_err());
+    }
+}
@@ -0,0 +1,300 @@
+// Model artifact upload
+//
+// `POST /api/v1/model/upload` registers a model from its weights rather than
+// a hash the client asserts: the server computes the canonical `model_hash`
+// (see `zkrag_prover::model`) from the bytes it receives, so a registered
+// hash is known to be that of the uploaded artifact. The body is either
+// - a single weight file (any other content type), named by `filename` so
+//   its extension can be checked, hashed as it streams in, or
+// - a model directory as a tar archive (`application/x-tar`, e.g. from
+//   `tar -cf - -C model .`). Its regular weight files are extracted to a
+//   temporary directory under $TMPDIR and hashed as `compute_model_hash`
+//   hashes a directory, then deleted; other entries are skipped.
+//
+// A manifest of shard digests isn't accepted: the canonical hash covers the
+// shards' contents, so registering from digests would again take the
+// client's word for them.
+//
+// Uploads skip the request timeout, the body cap and idempotency keys, which
+// would buffer the body, and are limited to `max_upload_bytes` instead.
+//
+// Configuration (the `[models]` table; see config.rs):
+// - max_upload_bytes (ZKRAG_MAX_MODEL_UPLOAD_BYTES): largest upload
+//   (default 32 GiB); 0 turns the limit off
+
+use axum::body::{Body, Bytes};
+use futures_util::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio_util::io::{StreamReader, SyncIoBridge};
+use utoipa::{IntoParams, ToSchema};
+use zkrag_prover::compute_model_hash;
+use zkrag_prover::model::WEIGHT_EXTENSIONS;
+
+use crate::error::{ApiError, ErrorCode};
+
+/// Limits on model uploads
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModelsConfig {
+    pub max_upload_bytes: u64,
+}
+
+impl Default for ModelsConfig {
+    fn default() -> Self {
+        Self {
+            max_upload_bytes: 32 << 30,
+        }
+    }
+}
+
+/// Query of `POST /api/v1/model/upload`
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+#[serde(deny_unknown_fields)]
+pub struct ModelUploadParams {
+    pub model_name: String,
+    /// Name of a single weight file, e.g. `model.safetensors`; not used for
+    /// tar archives
+    pub filename: Option<String>,
+}
+
+/// Weight file included in a model hash
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct ArtifactFile {
+    /// Path within the model directory, `/`-separated
+    pub path: String,
+    pub size: u64,
+}
+
+/// Response of `POST /api/v1/model/upload`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ModelUploadResponse {
+    pub id: u64,
+    /// Canonical hash computed from the upload
+    pub model_hash: String,
+    pub model_name: String,
+    /// Files hashed, in hashing order
+    pub files: Vec<ArtifactFile>,
+}
+
+/// Hashed upload
+#[derive(Debug)]
+pub struct HashedModel {
+    pub model_hash: String,
+    pub files: Vec<ArtifactFile>,
+}
+
+/// Whether `name` has a weight file extension
+fn is_weight_file(name: &Path) -> bool {
+    name.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| WEIGHT_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// Body of a request, failing once it passes `max` bytes (0 for no limit)
+/// and setting `too_large`
+fn capped(
+    body: Body,
+    max: u64,
+    too_large: Arc<AtomicBool>,
+) -> impl Stream<Item = io::Result<Bytes>> + Send + Unpin + 'static {
+    let mut read = 0u64;
+    body.into_data_stream().map(move |chunk| {
+        let chunk = chunk.map_err(io::Error::other)?;
+        read += chunk.len() as u64;
+        if max > 0 && read > max {
+            too_large.store(true, Ordering::Relaxed);
+            return Err(io::Error::other("model upload is too large"));
+        }
+        Ok(chunk)
+    })
+}
+
+/// Error for a failed read of an upload of at most `max` bytes
+fn read_error(error: impl std::fmt::Display, too_large: &AtomicBool, max: u64) -> ApiError {
+    if too_large.load(Ordering::Relaxed) {
+        ApiError::new(
+            ErrorCode::PayloadTooLarge,
+            format!("Model uploads are limited to {} bytes", max),
+        )
+    } else {
+        ApiError::invalid_request(format!("Failed to read model upload: {}", error))
+    }
+}
+
+/// Hash a single weight file named `filename`
+pub async fn hash_weight_file(
+    body: Body,
+    filename: &str,
+    config: &ModelsConfig,
+) -> Result<HashedModel, ApiError> {
+    if !is_weight_file(Path::new(filename)) {
+        return Err(ApiError::invalid_request(format!(
+            "{} is not a weight file (expected one of: {})",
+            filename,
+            WEIGHT_EXTENSIONS.join(", ")
+        )));
+    }
+    let too_large = Arc::new(AtomicBool::new(false));
+    let mut body = capped(body, config.max_upload_bytes, too_large.clone());
+    let mut hasher = Sha256::new();
+    let mut size = 0;
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|e| read_error(e, &too_large, config.max_upload_bytes))?;
+        hasher.update(&chunk);
+        size += chunk.len() as u64;
+    }
+    Ok(HashedModel {
+        model_hash: hex::encode(hasher.finalize()),
+        files: vec![ArtifactFile {
+            path: filename.to_string(),
+            size,
+        }],
+    })
+}
+
+/// Temporary directory deleted when dropped
+struct Spool(PathBuf);
+
+impl Drop for Spool {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Hash the model directory in a tar archive
+pub async fn hash_model_archive(
+    body: Body,
+    config: &ModelsConfig,
+) -> Result<HashedModel, ApiError> {
+    let too_large = Arc::new(AtomicBool::new(false));
+    let body = capped(body, config.max_upload_bytes, too_large.clone());
+    let reader = SyncIoBridge::new(StreamReader::new(body));
+    let spool =
+        Spool(std::env::temp_dir().join(format!("zkrag-model-{:016x}", rand::random::<u64>())));
+    let extracted = tokio::task::spawn_blocking(move || {
+        std::fs::create_dir_all(&spool.0)?;
+        let files = extract_weight_files(reader, &spool.0)?;
+        Ok::<_, io::Error>((spool, files))
+    })
+    .await
+    .map_err(|e| ApiError::internal("Extracting model upload", e))?;
+    let (spool, files) =
+        extracted.map_err(|e| read_error(e, &too_large, config.max_upload_bytes))?;
+    if files.is_empty() {
+        return Err(ApiError::invalid_request(format!(
+            "No weight files in the archive (expected one of: {})",
+            WEIGHT_EXTENSIONS.join(", ")
+        )));
+    }
+
+    let model_hash = tokio::task::spawn_blocking(move || compute_model_hash(&spool.0))
+        .await
+        .map_err(|e| ApiError::internal("Hashing model upload", e))?
+        .map_err(|e| ApiError::internal("Hashing model upload", format!("{:#}", e)))?;
+    Ok(HashedModel {
+        model_hash,
+        files: files
+            .into_iter()
+            .map(|(path, size)| ArtifactFile {
+                path: path
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/"),
+                size,
+            })
+            .collect(),
+    })
+}
+
+/// Unpack the regular weight files of the tar archive `reader` into `dir`,
+/// returning their paths and sizes in hashing order
+fn extract_weight_files(reader: impl io::Read, dir: &Path) -> io::Result<BTreeMap<PathBuf, u64>> {
+    let mut archive = tar::Archive::new(reader);
+    let mut files = BTreeMap::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.header().entry_type() != tar::EntryType::Regular {
+            continue;
+        }
+        let path = entry.path()?.into_owned();
+        if !is_weight_file(&path) {
+            continue;
+        }
+        let size = entry.size();
+        if !entry.unpack_in(dir)? {
+            return Err(io::Error::other(format!(
+                "{} is outside the archive",
+                path.display()
+            )));
+        }
+        // Normalized as `unpack_in` does, so `./a` and `a` are one file
+        let path: PathBuf = path
+            .components()
+            .filter(|c| matches!(c, std::path::Component::Normal(_)))
+            .collect();
+        files.insert(path, size);
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_hash_uploads() {
+        let config = ModelsConfig::default();
+
+        // A single file hashes as `compute_model_hash` hashes it on disk
+        let dir = std::env::temp_dir().join(format!("zkrag-artifacts-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("shards")).unwrap();
+        std::fs::write(dir.join("model.gguf"), b"weights").unwrap();
+        let hashed = hash_weight_file(Body::from("weights"), "model.gguf", &config)
+            .await
+            .unwrap();
+        assert_eq!(
+            hashed.model_hash,
+            compute_model_hash(&dir.join("model.gguf")).unwrap()
+        );
+        assert!(
+            hash_weight_file(Body::from("weights"), "model.txt", &config)
+                .await
+                .is_err()
+        );
+
+        // So does a directory, archived with files that aren't weights
+        std::fs::remove_file(dir.join("model.gguf")).unwrap();
+        std::fs::write(dir.join("shards/b.safetensors"), b"second").unwrap();
+        std::fs::write(dir.join("a.safetensors"), b"first").unwrap();
+        std::fs::write(dir.join("README.md"), b"docs").unwrap();
+        let mut archive = tar::Builder::new(Vec::new());
+        archive.append_dir_all(".", &dir).unwrap();
+        let archive = archive.into_inner().unwrap();
+        let hashed = hash_model_archive(Body::from(archive.clone()), &config)
+            .await
+            .unwrap();
+        assert_eq!(hashed.model_hash, compute_model_hash(&dir).unwrap());
+        let paths: Vec<&str> = hashed.files.iter().map(|file| file.path.as_str()).collect();
+        assert_eq!(paths, ["a.safetensors", "shards/b.safetensors"]);
+
+        // Uploads over the limit are refused
+        let small = ModelsConfig {
+            max_upload_bytes: 16,
+        };
+        let error = hash_model_archive(Body::from(archive), &small)
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, ErrorCode::PayloadTooLarge);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,244 @@
+// Audit trail
+//
+// `GET /api/v1/audit` lists every recorded verification, oldest first, for
+// compliance reviews. Each entry is the query record with the tenant (the
+// owner of the registered document the query names) and the verifying key
+// that checked the proof, which auditors can fetch from
+// `/api/v1/keys/verifying-key?fingerprint=` to check the stored proof again.
+//
+// Pages are cursor-based: each page carries a `next_cursor` to pass as
+// `cursor` for the following one, and ends the trail when absent. Entries
+// recorded while paging appear on later pages, never shift earlier ones.
+//
+// With `format=ndjson` every matching entry after the cursor is streamed as
+// newline-delimited JSON instead, read from the store a batch at a time.
+
+use axum::body::{Body, Bytes};
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::response::{IntoResponse, Response};
+use futures_util::stream::{self, Stream};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::blank_as_none;
+use crate::store::{AuditEntry, AuditFilter, Store, StoreError};
+
+/// Entries read from the store per NDJSON chunk
+const EXPORT_BATCH: u64 = 500;
+
+/// Outcome of a verification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Verified,
+    Failed,
+}
+
+impl FromStr for Outcome {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "verified" => Ok(Self::Verified),
+            "failed" => Ok(Self::Failed),
+            _ => Err(format!(
+                "unknown outcome {:?}, expected verified or failed",
+                s
+            )),
+        }
+    }
+}
+
+/// Response body of `GET /api/v1/audit`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditFormat {
+    Json,
+    Ndjson,
+}
+
+impl FromStr for AuditFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Self::Json),
+            "ndjson" => Ok(Self::Ndjson),
+            _ => Err(format!("unknown format {:?}, expected json or ndjson", s)),
+        }
+    }
+}
+
+/// Filters and cursor of `/api/v1/audit`; blank parameters are ignored
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditParams {
+    /// Only verifications at or after this Unix time
+    #[serde(default, deserialize_with = "blank_as_none")]
+    pub since: Option<u64>,
+    #[serde(default, deserialize_with = "blank_as_none")]
+    pub outcome: Option<Outcome>,
+    /// Owner of the document queried
+    #[serde(default, deserialize_with = "blank_as_none")]
+    pub tenant: Option<String>,
+    /// `next_cursor` of the previous page; omit for the first
+    #[serde(default, deserialize_with = "blank_as_none")]
+    pub cursor: Option<u64>,
+    /// Entries per page; ignored by the NDJSON export
+    #[serde(default, deserialize_with = "blank_as_none")]
+    pub limit: Option<u64>,
+    /// `ndjson` to export every matching entry after the cursor
+    #[serde(default, deserialize_with = "blank_as_none")]
+    pub format: Option<AuditFormat>,
+}
+
+impl AuditParams {
+    pub fn filter(&self) -> AuditFilter {
+        AuditFilter {
+            since: self.since,
+            verified: self.outcome.map(|outcome| outcome == Outcome::Verified),
+            tenant: self.tenant.clone(),
+        }
+    }
+}
+
+/// Page of the audit trail
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuditPage {
+    pub entries: Vec<AuditEntry>,
+    /// Cursor of the next page; absent on the last
+    pub next_cursor: Option<u64>,
+}
+
+/// Page of up to `limit` entries after `cursor`
+pub async fn page(
+    store: &dyn Store,
+    filter: &AuditFilter,
+    cursor: u64,
+    limit: u64,
+) -> Result<AuditPage, StoreError> {
+    // One extra entry tells whether there's another page
+    let mut entries = store.audit_trail(filter, cursor, limit + 1).await?;
+    let next_cursor = if entries.len() as u64 > limit {
+        entries.truncate(limit as usize);
+        entries.last().map(|entry| entry.query.id)
+    } else {
+        None
+    };
+    Ok(AuditPage {
+        entries,
+        next_cursor,
+    })
+}
+
+/// NDJSON lines of every entry after `cursor`, a batch per chunk
+fn ndjson(
+    store: Arc<dyn Store>,
+    filter: AuditFilter,
+    cursor: u64,
+) -> impl Stream<Item = Result<Bytes, StoreError>> {
+    stream::try_unfold(Some(cursor), move |cursor| {
+        let store = store.clone();
+        let filter = filter.clone();
+        async move {
+            let Some(cursor) = cursor else {
+                return Ok(None);
+            };
+            let entries = store.audit_trail(&filter, cursor, EXPORT_BATCH).await?;
+            let Some(last) = entries.last() else {
+                return Ok(None);
+            };
+            let next = (entries.len() as u64 == EXPORT_BATCH).then_some(last.query.id);
+            let mut chunk = Vec::new();
+            for entry in &entries {
+                serde_json::to_writer(&mut chunk, entry)
+                    .map_err(|e| StoreError::Corrupt(e.to_string()))?;
+                chunk.push(b'\n');
+            }
+            Ok(Some((Bytes::from(chunk), next)))
+        }
+    })
+}
+
+/// Streamed NDJSON export of every entry after `cursor`
+pub fn export(store: Arc<dyn Store>, filter: AuditFilter, cursor: u64) -> Response {
+    (
+        [
+            (CONTENT_TYPE, "application/x-ndjson"),
+            (CONTENT_DISPOSITION, "attachment; filename=\"audit.ndjson\""),
+        ],
+        Body::from_stream(ndjson(store, filter, cursor)),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{test_store, QueryRecord};
+    use futures_util::TryStreamExt;
+    use zkrag_verifier::{ProofEnvelope, PublicInputs};
+
+    #[tokio::test]
+    async fn test_cursor_pages_and_export() {
+        let store = Arc::new(test_store().await);
+        let inputs = PublicInputs {
+            document_commitment: "cd".repeat(32),
+            model_hash: "ef".repeat(32),
+            timestamp: 1,
+            nonce: None,
+        };
+        let proof = ProofEnvelope::new(vec![7; 128], inputs.clone());
+        for verified in [true, false, true, true, false] {
+            let record = QueryRecord {
+                id: 0,
+                proof_digest: "ab".repeat(32),
+                document_commitment: inputs.document_commitment.clone(),
+                model_hash: inputs.model_hash.clone(),
+                timestamp: 1,
+                verified,
+                reason: None,
+                verified_at: 2,
+            };
+            store
+                .record_query(&record, &proof, None, None)
+                .await
+                .unwrap();
+        }
+
+        // Walk the verified entries two at a time
+        let filter = AuditParams {
+            since: None,
+            outcome: Some("verified".parse().unwrap()),
+            tenant: None,
+            cursor: None,
+            limit: None,
+            format: None,
+        }
+        .filter();
+        let first = page(store.as_ref(), &filter, 0, 2).await.unwrap();
+        let ids: Vec<_> = first.entries.iter().map(|entry| entry.query.id).collect();
+        assert_eq!((ids, first.next_cursor), (vec![1, 3], Some(3)));
+        let second = page(store.as_ref(), &filter, 3, 2).await.unwrap();
+        assert_eq!(second.entries.len(), 1);
+        assert_eq!(second.next_cursor, None);
+        assert!("passed".parse::<Outcome>().is_err());
+
+        let chunks: Vec<Bytes> = ndjson(store.clone(), AuditFilter::default(), 1)
+            .try_collect()
+            .await
+            .unwrap();
+        let lines: Vec<serde_json::Value> = chunks
+            .concat()
+            .split(|&b| b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0]["id"], 2);
+        assert_eq!(lines[0]["verified"], false);
+        assert!(lines[0]["tenant"].is_null());
+    }
+}
@@ -0,0 +1,533 @@
+// Bearer token authentication
+//
+// Requests carry an OIDC access token (a JWT) signed by the configured
+// issuer, whose keys are fetched from its JWKS URL. Roles come from a claim
+// in the token and are ordered: an admin may do anything a submitter may,
+// and a submitter anything an auditor may.
+//
+// - admin: register models
+// - submitter: register documents, verify queries
+// - auditor: read queries and the document commitment
+//
+// Each handler states the role it needs by taking an `Auth<R>` argument.
+// Routes over one owner's records also check the caller acts for the owner:
+// is the token's subject, or an admin.
+// Authentication is enabled by configuring an issuer; without one every
+// request is let through, for local development.
+//
+// Configuration (the `[auth]` table; see config.rs):
+// - issuer (ZKRAG_AUTH_ISSUER): expected `iss`, e.g.
+//   https://login.example.com/realms/zkrag
+// - jwks_url (ZKRAG_AUTH_JWKS_URL): signing keys; discovered from the
+//   issuer's /.well-known/openid-configuration when unset
+// - audience (ZKRAG_AUTH_AUDIENCE): expected `aud`, if tokens carry one to
+//   check
+// - roles_claim (ZKRAG_AUTH_ROLES_CLAIM): claim holding the roles (default
+//   "roles")
+//
+// A token must be signed with the algorithm its key's `alg` names, or when
+// the key names none, one that fits the key type; the token's own `alg`
+// header is only checked against these, never trusted.
+
+use axum::async_trait;
+use axum::extract::FromRequestParts;
+use axum::http::header::AUTHORIZATION;
+use axum::http::request::Parts;
+use axum::http::HeaderMap;
+use axum::response::{IntoResponse, Response};
+use jsonwebtoken::jwk::{AlgorithmParameters, EllipticCurve, Jwk, JwkSet};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::marker::PhantomData;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock};
+use tracing::warn;
+
+use crate::error::{ApiError, ErrorCode};
+use crate::SharedState;
+
+/// Shortest wait between JWKS refetches triggered by an unknown key id
+const JWKS_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Authorization role, ordered by privilege
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    Auditor,
+    Submitter,
+    Admin,
+}
+
+impl Role {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "auditor" => Some(Self::Auditor),
+            "submitter" => Some(Self::Submitter),
+            "admin" => Some(Self::Admin),
+            _ => None,
+        }
+    }
+}
+
+/// Authenticated caller
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    pub subject: String,
+    /// Highest role granted by the token
+    pub role: Role,
+}
+
+/// Why a request was refused
+#[derive(Debug, thiserror::Error)]
+pub enum AuthError {
+    #[error("missing bearer token")]
+    MissingToken,
+    #[error("invalid token: {0}")]
+    InvalidToken(String),
+    #[error("{required:?} role required")]
+    Forbidden { required: Role },
+    #[error("failed to fetch signing keys: {0}")]
+    Jwks(String),
+}
+
+impl From<AuthError> for ApiError {
+    fn from(error: AuthError) -> Self {
+        let code = match &error {
+            AuthError::MissingToken | AuthError::InvalidToken(_) => ErrorCode::Unauthorized,
+            AuthError::Forbidden { .. } => ErrorCode::Forbidden,
+            AuthError::Jwks(_) => ErrorCode::AuthUnavailable,
+        };
+        Self::new(code, error)
+    }
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        ApiError::from(self).into_response()
+    }
+}
+
+/// Token validation settings
+#[derive(Debug, Clone)]
+pub struct AuthConfig {
+    pub issuer: String,
+    pub jwks_url: Option<String>,
+    pub audience: Option<String>,
+    pub roles_claim: String,
+}
+
+#[derive(Deserialize)]
+struct OidcConfiguration {
+    jwks_uri: String,
+}
+
+/// GET a JSON document
+async fn fetch_json<T: serde::de::DeserializeOwned + Send + 'static>(
+    url: String,
+) -> Result<T, AuthError> {
+    tokio::task::spawn_blocking(move || {
+        ureq::get(&url)
+            .timeout(Duration::from_secs(10))
+            .call()
+            .map_err(|e| AuthError::Jwks(format!("{}: {}", url, e)))?
+            .into_json()
+            .map_err(|e| AuthError::Jwks(format!("{}: {}", url, e)))
+    })
+    .await
+    .map_err(|e| AuthError::Jwks(e.to_string()))?
+}
+
+/// Validates bearer tokens against the issuer's signing keys
+pub struct Authenticator {
+    config: AuthConfig,
+    jwks_url: String,
+    keys: RwLock<JwkSet>,
+    /// When the keys were last fetched, or a refetch last tried; held for
+    /// the length of a refetch
+    refreshed: Mutex<Instant>,
+}
+
+impl Authenticator {
+    /// Resolve the JWKS URL and fetch the issuer's current keys
+    pub async fn new(config: AuthConfig) -> Result<Self, AuthError> {
+        let jwks_url = match &config.jwks_url {
+            Some(url) => url.clone(),
+            None => {
+                let discovery = format!(
+                    "{}/.well-known/openid-configuration",
+                    config.issuer.trim_end_matches('/')
+                );
+                fetch_json::<OidcConfiguration>(discovery).await?.jwks_uri
+            }
+        };
+        let keys = fetch_json(jwks_url.clone()).await?;
+        Ok(Self::with_keys(config, jwks_url, keys))
+    }
+
+    fn with_keys(config: AuthConfig, jwks_url: String, keys: JwkSet) -> Self {
+        Self {
+            config,
+            jwks_url,
+            keys: RwLock::new(keys),
+            refreshed: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Validate a token and read its subject and role
+    pub async fn authenticate(&self, token: &str) -> Result<Principal, AuthError> {
+        let invalid = |e: jsonwebtoken::errors::Error| AuthError::InvalidToken(e.to_string());
+        let header = decode_header(token).map_err(invalid)?;
+        let (key, algorithms) = self.decoding_key(header.kid.as_deref()).await?;
+        if !algorithms.contains(&header.alg) {
+            return Err(AuthError::InvalidToken(format!(
+                "{:?} is not an algorithm of key {:?}",
+                header.alg, header.kid
+            )));
+        }
+
+        let mut validation = Validation::default();
+        validation.algorithms = algorithms;
+        validation.set_issuer(&[&self.config.issuer]);
+        match &self.config.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+        let claims = decode::<Map<String, Value>>(token, &key, &validation)
+            .map_err(invalid)?
+            .claims;
+
+        // Ownership checks compare subjects, so every caller needs one
+        let subject = claims
+            .get("sub")
+            .and_then(Value::as_str)
+            .filter(|subject| !subject.is_empty())
+            .ok_or_else(|| AuthError::InvalidToken("token has no subject".into()))?
+            .to_string();
+        // Accept a JSON array of role names or a single name
+        let role = match claims.get(&self.config.roles_claim) {
+            Some(Value::Array(roles)) => roles
+                .iter()
+                .filter_map(|role| role.as_str().and_then(Role::parse))
+                .max(),
+            Some(Value::String(role)) => Role::parse(role),
+            _ => None,
+        };
+        let role = role.ok_or_else(|| {
+            AuthError::InvalidToken(format!("no role in '{}' claim", self.config.roles_claim))
+        })?;
+        Ok(Principal { subject, role })
+    }
+
+    /// Key for `kid` and the algorithms it signs with, refetching the JWKS
+    /// (at most once a minute) when the issuer has rotated to a key we
+    /// haven't seen
+    async fn decoding_key(
+        &self,
+        kid: Option<&str>,
+    ) -> Result<(DecodingKey, Vec<Algorithm>), AuthError> {
+        if let Some(key) = find_key(&*self.keys.read().await, kid)? {
+            return Ok(key);
+        }
+
+        // Only tokens naming an unknown key wait on a refetch; the keys are
+        // locked just to swap in the fresh set
+        let mut refreshed = self.refreshed.lock().await;
+        if refreshed.elapsed() >= JWKS_REFRESH_INTERVAL {
+            *refreshed = Instant::now();
+            match fetch_json(self.jwks_url.clone()).await {
+                Ok(fresh) => *self.keys.write().await = fresh,
+                Err(e) => warn!("JWKS refresh failed: {}", e),
+            }
+        }
+        drop(refreshed);
+        find_key(&*self.keys.read().await, kid)?
+            .ok_or_else(|| AuthError::InvalidToken(format!("unknown key id {:?}", kid)))
+    }
+}
+
+/// Decoding key for `kid`, or the only key if the token names none, with
+/// its algorithms
+fn find_key(
+    keys: &JwkSet,
+    kid: Option<&str>,
+) -> Result<Option<(DecodingKey, Vec<Algorithm>)>, AuthError> {
+    let jwk = match kid {
+        Some(kid) => keys.find(kid),
+        None if keys.keys.len() == 1 => keys.keys.first(),
+        None => None,
+    };
+    let Some(jwk) = jwk else {
+        return Ok(None);
+    };
+    // A symmetric key in a public key set would let anyone mint tokens
+    if matches!(jwk.algorithm, AlgorithmParameters::OctetKey(_)) {
+        return Err(AuthError::InvalidToken(
+            "symmetric keys are not accepted".into(),
+        ));
+    }
+    DecodingKey::from_jwk(jwk)
+        .map(|key| Some((key, key_algorithms(jwk))))
+        .map_err(|e| AuthError::InvalidToken(e.to_string()))
+}
+
+/// Algorithms tokens signed with `jwk` may use: the one its `alg` names,
+/// else those of its key type
+fn key_algorithms(jwk: &Jwk) -> Vec<Algorithm> {
+    if let Some(alg) = jwk.common.key_algorithm {
+        // Encryption algorithms don't parse, leaving the key unusable
+        return Algorithm::from_str(&alg.to_string()).into_iter().collect();
+    }
+    match &jwk.algorithm {
+        AlgorithmParameters::EllipticCurve(params) => match params.curve {
+            EllipticCurve::P256 => vec![Algorithm::ES256],
+            EllipticCurve::P384 => vec![Algorithm::ES384],
+            _ => Vec::new(),
+        },
+        AlgorithmParameters::RSA(_) => vec![
+            Algorithm::RS256,
+            Algorithm::RS384,
+            Algorithm::RS512,
+            Algorithm::PS256,
+            Algorithm::PS384,
+            Algorithm::PS512,
+        ],
+        AlgorithmParameters::OctetKeyPair(_) => vec![Algorithm::EdDSA],
+        AlgorithmParameters::OctetKey(_) => Vec::new(),
+    }
+}
+
+/// Bearer token of a request, if it carries one
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+/// Role a route requires
+pub trait RequiredRole {
+    const ROLE: Role;
+}
+
+pub struct Admin;
+pub struct Submitter;
+pub struct Auditor;
+
+impl RequiredRole for Admin {
+    const ROLE: Role = Role::Admin;
+}
+
+impl RequiredRole for Submitter {
+    const ROLE: Role = Role::Submitter;
+}
+
+impl RequiredRole for Auditor {
+    const ROLE: Role = Role::Auditor;
+}
+
+/// Extractor admitting callers with at least role `R`
+pub struct Auth<R> {
+    /// `None` when authentication is off
+    principal: Option<Principal>,
+    role: PhantomData<R>,
+}
+
+impl<R> Auth<R> {
+    /// Caller's subject, for logging
+    pub fn subject(&self) -> &str {
+        self.principal
+            .as_ref()
+            .map_or("anonymous", |principal| &principal.subject)
+    }
+
+    /// Whether the caller may act for `owner`: they are `owner`, or an
+    /// admin, or authentication is off
+    pub fn acts_for(&self, owner: &str) -> bool {
+        self.principal
+            .as_ref()
+            .is_none_or(|principal| principal.subject == owner || principal.role == Role::Admin)
+    }
+}
+
+#[async_trait]
+impl<R: RequiredRole> FromRequestParts<SharedState> for Auth<R> {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &SharedState,
+    ) -> Result<Self, Self::Rejection> {
+        let Some(auth) = &state.auth else {
+            return Ok(Self {
+                principal: None,
+                role: PhantomData,
+            });
+        };
+
+        // The rate limiter may have authenticated the token already
+        let principal = match parts.extensions.get::<Principal>().cloned() {
+            Some(principal) => principal,
+            None => {
+                let token = bearer_token(&parts.headers).ok_or(AuthError::MissingToken)?;
+                auth.authenticate(token).await?
+            }
+        };
+        if principal.role < R::ROLE {
+            return Err(AuthError::Forbidden { required: R::ROLE });
+        }
+        Ok(Self {
+            principal: Some(principal),
+            role: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    use jsonwebtoken::jwk::KeyAlgorithm;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use ring::rand::SystemRandom;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    pub(crate) const ISSUER: &str = "https://issuer.example";
+
+    pub(crate) fn authenticator() -> (Authenticator, EncodingKey) {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let keys: JwkSet = serde_json::from_value(json!({ "keys": [{
+            "kty": "OKP",
+            "crv": "Ed25519",
+            "kid": "k1",
+            "x": URL_SAFE_NO_PAD.encode(pair.public_key().as_ref()),
+        }]}))
+        .unwrap();
+        let config = AuthConfig {
+            issuer: ISSUER.to_string(),
+            jwks_url: None,
+            audience: None,
+            roles_claim: "roles".to_string(),
+        };
+        (
+            Authenticator::with_keys(config, String::new(), keys),
+            EncodingKey::from_ed_der(pkcs8.as_ref()),
+        )
+    }
+
+    pub(crate) fn token(key: &EncodingKey, issuer: &str, roles: Value) -> String {
+        let mut header = Header::new(Algorithm::EdDSA);
+        header.kid = Some("k1".to_string());
+        let claims = json!({
+            "sub": "alice",
+            "iss": issuer,
+            "exp": 4_000_000_000u64,
+            "roles": roles,
+        });
+        encode(&header, &claims, key).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_authenticate() {
+        let (auth, key) = authenticator();
+        let principal = auth
+            .authenticate(&token(&key, ISSUER, json!(["auditor", "submitter"])))
+            .await
+            .unwrap();
+        assert_eq!(
+            principal,
+            Principal {
+                subject: "alice".to_string(),
+                role: Role::Submitter,
+            }
+        );
+
+        let wrong_issuer = token(&key, "https://other.example", json!(["admin"]));
+        assert!(auth.authenticate(&wrong_issuer).await.is_err());
+        let unknown_role = token(&key, ISSUER, json!(["viewer"]));
+        assert!(auth.authenticate(&unknown_role).await.is_err());
+
+        let mut header = Header::new(Algorithm::EdDSA);
+        header.kid = Some("k1".to_string());
+        let claims = json!({ "iss": ISSUER, "exp": 4_000_000_000u64, "roles": ["admin"] });
+        let no_subject = encode(&header, &claims, &key).unwrap();
+        assert!(matches!(
+            auth.authenticate(&no_subject).await,
+            Err(AuthError::InvalidToken(reason)) if reason == "token has no subject"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_algorithm_from_key() {
+        let (auth, key) = authenticator();
+        let good = token(&key, ISSUER, json!(["admin"]));
+
+        // The header claims another algorithm than the key signs with
+        let (_, rest) = good.split_once('.').unwrap();
+        let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"ES256","kid":"k1"}"#);
+        let forged = format!("{}.{}", header, rest);
+        assert!(matches!(
+            auth.authenticate(&forged).await,
+            Err(AuthError::InvalidToken(reason)) if reason.contains("ES256")
+        ));
+
+        // A key naming its algorithm admits only that one
+        auth.keys.write().await.keys[0].common.key_algorithm = Some(KeyAlgorithm::EdDSA);
+        assert!(auth.authenticate(&good).await.is_ok());
+        auth.keys.write().await.keys[0].common.key_algorithm = Some(KeyAlgorithm::ES256);
+        assert!(auth.authenticate(&good).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_refetch_doesnt_block_known_keys() {
+        // A JWKS endpoint that holds each connection for a second, then
+        // drops it unanswered
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let jwks_url = format!("http://{}/jwks", listener.local_addr().unwrap());
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let counted = fetches.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                counted.fetch_add(1, Ordering::SeqCst);
+                let stream = stream.unwrap();
+                std::thread::spawn(move || {
+                    std::thread::sleep(Duration::from_secs(1));
+                    drop(stream);
+                });
+            }
+        });
+
+        let (auth, key) = authenticator();
+        let auth = Arc::new(Authenticator {
+            jwks_url,
+            refreshed: Mutex::new(Instant::now() - JWKS_REFRESH_INTERVAL),
+            ..auth
+        });
+        let mut header = Header::new(Algorithm::EdDSA);
+        header.kid = Some("k2".to_string());
+        let claims = json!({ "sub": "alice", "iss": ISSUER, "exp": 4_000_000_000u64 });
+        let unknown = encode(&header, &claims, &key).unwrap();
+        let refetching = tokio::spawn({
+            let (auth, unknown) = (auth.clone(), unknown.clone());
+            async move { auth.authenticate(&unknown).await }
+        });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        // Tokens for a known key don't wait on the refetch
+        let known = token(&key, ISSUER, json!(["auditor"]));
+        let started = Instant::now();
+        assert!(auth.authenticate(&known).await.is_ok());
+        assert!(started.elapsed() < Duration::from_millis(500));
+
+        assert!(refetching.await.unwrap().is_err());
+        // and the failed refetch isn't retried within the interval
+        assert!(auth.authenticate(&unknown).await.is_err());
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,197 @@
+// Verifier challenges
+//
+// A timestamp only bounds how old a replayed proof may be: within the
+// freshness window the same proof verifies as often as it is sent. A client
+// closes that window by asking `POST /api/v1/challenge` for a nonce and
+// binding it into its proof, as the witness's `nonce`; the prover folds it
+// into the document commitment input (see
+// `zkrag_circuits::utils::bound_commitment`), so the proof verifies only
+// alongside that nonce. The nonce then goes with the public inputs to
+// `/api/v1/query/verify` or `/api/v2/query/verify`, which spend it before
+// checking the proof.
+//
+// A nonce is spent by the first verification naming it, whether the proof
+// verifies or not, and may only be spent by the caller it was issued to. A
+// verification that fails on the server's side (a 5xx, say the store or the
+// kernel being unavailable) gives the nonce back, so the proof can be sent
+// again. Verifying
+// with a nonce that's unknown, expired, spent or someone else's is refused
+// with `challenge_invalid`, without checking the proof. Nonces are kept in
+// the database, so any replica may spend one another issued; expired ones
+// are deleted by the retention task (see retention.rs).
+//
+// Proofs without a nonce keep verifying as before unless challenges are
+// required, in which case they're refused with `challenge_invalid` too.
+//
+// Configuration (the `[challenge]` table; see config.rs):
+// - ttl_secs (ZKRAG_CHALLENGE_TTL_SECS): how long a nonce may be spent
+//   (default 300)
+// - required (ZKRAG_REQUIRE_CHALLENGE): refuse proofs without a nonce
+//   (default false)
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use tracing::warn;
+
+use crate::error::{ApiError, ErrorCode};
+use crate::store::Store;
+use crate::validate::Validator;
+
+/// Nonce lifetime, and whether proofs need one
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChallengeConfig {
+    pub ttl_secs: u64,
+    pub required: bool,
+}
+
+impl Default for ChallengeConfig {
+    fn default() -> Self {
+        Self {
+            ttl_secs: 300,
+            required: false,
+        }
+    }
+}
+
+/// Response of `POST /api/v1/challenge`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Challenge {
+    /// Hex of 32 random bytes, to bind into the proof as the witness's `nonce`
+    pub nonce: String,
+    /// Unix time after which the nonce is refused
+    pub expires_at: u64,
+}
+
+/// Nonce spent by a verification, kept to give it back
+#[derive(Debug)]
+pub struct Redeemed {
+    nonce: String,
+    subject: String,
+    expires_at: u64,
+}
+
+/// Issue a nonce to `subject` at `now`
+pub async fn issue(
+    store: &dyn Store,
+    config: &ChallengeConfig,
+    subject: &str,
+    now: u64,
+) -> Result<Challenge, ApiError> {
+    let challenge = Challenge {
+        nonce: hex::encode(rand::random::<[u8; 32]>()),
+        expires_at: now.saturating_add(config.ttl_secs),
+    };
+    store
+        .issue_challenge(&challenge.nonce, subject, challenge.expires_at)
+        .await?;
+    Ok(challenge)
+}
+
+/// Spend the nonce a proof from `subject` is bound to, if it has one;
+/// `field` names it in validation errors
+pub async fn redeem(
+    store: &dyn Store,
+    config: &ChallengeConfig,
+    subject: &str,
+    field: &str,
+    nonce: Option<&str>,
+    now: u64,
+) -> Result<Option<Redeemed>, ApiError> {
+    let Some(nonce) = nonce else {
+        if config.required {
+            return Err(ApiError::new(
+                ErrorCode::ChallengeInvalid,
+                "Proofs must be bound to a nonce from /api/v1/challenge",
+            ));
+        }
+        return Ok(None);
+    };
+    let mut validator = Validator::new();
+    validator.digest(field, nonce);
+    validator.finish()?;
+    let Some(expires_at) = store.consume_challenge(nonce, subject, now).await? else {
+        return Err(ApiError::new(
+            ErrorCode::ChallengeInvalid,
+            "Challenge nonce is unknown, expired or already spent",
+        ));
+    };
+    Ok(Some(Redeemed {
+        nonce: nonce.to_string(),
+        subject: subject.to_string(),
+        expires_at,
+    }))
+}
+
+/// Pass on the `outcome` of verifying a proof bound to `redeemed`, giving
+/// the nonce back if verification failed on the server's side
+pub async fn settle<T>(
+    store: &dyn Store,
+    redeemed: Option<Redeemed>,
+    outcome: Result<T, ApiError>,
+) -> Result<T, ApiError> {
+    if let (Err(error), Some(redeemed)) = (&outcome, redeemed) {
+        if error.code.status().is_server_error() {
+            let restored = store
+                .issue_challenge(&redeemed.nonce, &redeemed.subject, redeemed.expires_at)
+                .await;
+            if let Err(e) = restored {
+                warn!("Couldn't give back challenge nonce: {}", e);
+            }
+        }
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::test_store;
+
+    #[tokio::test]
+    async fn test_challenge_spent_once() {
+        let store = test_store().await;
+        let config = ChallengeConfig::default();
+        let now = 1_700_000_000;
+        let redeem = |subject, nonce, now| redeem(&store, &config, subject, "nonce", nonce, now);
+
+        let challenge = issue(&store, &config, "alice", now).await.unwrap();
+        assert_eq!(challenge.expires_at, now + 300);
+        // Only alice may spend it, and only once
+        let error = redeem("bob", Some(&challenge.nonce), now)
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, ErrorCode::ChallengeInvalid);
+        // A server error gives it back; a client error keeps it spent
+        let redeemed = redeem("alice", Some(&challenge.nonce), now).await.unwrap();
+        let failed = Err::<(), _>(ApiError::internal("Storage error", "disk full"));
+        settle(&store, redeemed, failed).await.unwrap_err();
+        let redeemed = redeem("alice", Some(&challenge.nonce), now).await.unwrap();
+        let refused = Err::<(), _>(ApiError::new(ErrorCode::KernelRejected, "refused"));
+        settle(&store, redeemed, refused).await.unwrap_err();
+        let error = redeem("alice", Some(&challenge.nonce), now)
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, ErrorCode::ChallengeInvalid);
+
+        let challenge = issue(&store, &config, "alice", now).await.unwrap();
+        let error = redeem("alice", Some(&challenge.nonce), now + 301)
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, ErrorCode::ChallengeInvalid);
+        let error = redeem("alice", Some("not hex"), now).await.unwrap_err();
+        assert_eq!(error.code, ErrorCode::InvalidRequest);
+
+        // Proofs without a nonce pass unless one is required
+        redeem("alice", None, now).await.unwrap();
+        let required = ChallengeConfig {
+            required: true,
+            ..config.clone()
+        };
+        let error = super::redeem(&store, &required, "alice", "nonce", None, now)
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, ErrorCode::ChallengeInvalid);
+    }
+}
@@ -0,0 +1,114 @@
+// Running several replicas
+//
+// Replicas behind a load balancer must agree on the state clients see:
+// - records, their ids and idempotency keys live in the database, so
+//   replicas share it. With SQLite that means processes on one host opening
+//   the same file.
+// - rate limit budgets are per process unless the replicas are given a
+//   Redis server, where `ClusterState` keeps the counts they share (see
+//   ratelimit.rs). A client then has one budget however its requests are
+//   spread.
+// - the nullifiers of accepted proofs, when the service refuses replays,
+//   live in the database with the queries that spent them (see
+//   nullifiers.rs), so a proof accepted by one replica is refused by all.
+// - proof generation runs on each replica's own worker pool and queue (see
+//   prover.rs), so the balancer spreads generations across replicas.
+//
+// Configuration (the `[cluster]` table; see config.rs):
+// - redis_url (ZKRAG_REDIS_URL): e.g. `redis://cache:6379/0`; unset keeps
+//   all state in the process
+// - key_prefix (ZKRAG_REDIS_PREFIX): prefix of every key written, so
+//   deployments can share a server (default `zkrag`)
+
+use anyhow::Context;
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// Where replicas keep shared state
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClusterConfig {
+    pub redis_url: Option<String>,
+    pub key_prefix: String,
+}
+
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        Self {
+            redis_url: None,
+            key_prefix: "zkrag".to_string(),
+        }
+    }
+}
+
+impl ClusterConfig {
+    /// Shared state, or `None` when every replica keeps its own
+    pub async fn connect(&self) -> anyhow::Result<Option<Arc<dyn ClusterState>>> {
+        let Some(url) = &self.redis_url else {
+            return Ok(None);
+        };
+        let state = RedisState::connect(url, &self.key_prefix).await?;
+        Ok(Some(Arc::new(state)))
+    }
+}
+
+/// A count within a fixed time window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowCount {
+    /// Counted so far in the window, including this one
+    pub count: u64,
+    /// Seconds until the window ends
+    pub remaining_secs: u64,
+}
+
+/// State shared by every replica
+#[async_trait]
+pub trait ClusterState: Send + Sync {
+    /// Count one event under `key` in the current window of `window_secs`
+    /// seconds, aligned to the Unix epoch
+    async fn count(&self, key: &str, window_secs: u64, now: u64) -> anyhow::Result<WindowCount>;
+}
+
+/// State kept in Redis
+pub struct RedisState {
+    connection: ConnectionManager,
+    prefix: String,
+}
+
+impl RedisState {
+    /// Connect to the server at `url`, prefixing keys with `prefix`
+    pub async fn connect(url: &str, prefix: &str) -> anyhow::Result<Self> {
+        let client = redis::Client::open(url).context("Invalid Redis URL")?;
+        let connection = ConnectionManager::new(client)
+            .await
+            .with_context(|| format!("Connecting to Redis at {}", url))?;
+        Ok(Self {
+            connection,
+            prefix: prefix.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl ClusterState for RedisState {
+    async fn count(&self, key: &str, window_secs: u64, now: u64) -> anyhow::Result<WindowCount> {
+        let window = now / window_secs;
+        let key = format!("{}:{}:{}", self.prefix, key, window);
+        // Counters outlive their window a little so clock skew between
+        // replicas doesn't restart one
+        let (count,): (u64,) = redis::pipe()
+            .atomic()
+            .incr(&key, 1)
+            .expire(&key, (2 * window_secs) as i64)
+            .ignore()
+            .query_async(&mut self.connection.clone())
+            .await
+            .context("Counting in Redis")?;
+        Ok(WindowCount {
+            count,
+            remaining_secs: (window + 1) * window_secs - now,
+        })
+    }
+}
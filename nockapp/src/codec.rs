@@ -0,0 +1,1021 @@
+// Binary request and response bodies
+//
+// Proof submission and retrieval speak CBOR (`application/cbor`) and
+// MessagePack (`application/msgpack`) as well as JSON. The binary formats
+// carry proofs as byte strings instead of hex, halving their size:
+// - `POST /api/v1/query/verify` and `/api/v1/proof/generate` read their body
+//   in the format its Content-Type names. A verification's `proof` may be a
+//   byte string or hex text.
+// - Those routes and `GET /api/v1/query/{id}/proof` answer in the format the
+//   Accept header prefers, JSON if it names none of the three. Proof
+//   envelopes' `proof` is a byte string in the binary formats.
+//
+// Fields and shapes are those of the JSON bodies. Maps are keyed by strings;
+// CBOR tags and indefinite lengths and MessagePack extensions are refused.
+// Errors are always JSON.
+
+use axum::async_trait;
+use axum::body::Bytes;
+use axum::extract::{FromRequest, FromRequestParts, Request};
+use axum::http::header::{ACCEPT, CONTENT_TYPE, VARY};
+use axum::http::request::Parts;
+use axum::http::HeaderMap;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use serde::ser::{self, Serialize};
+use std::convert::Infallible;
+use std::fmt;
+
+use crate::error::{ApiError, ApiJson};
+
+/// Deepest nesting of arrays and maps decoded
+const MAX_DEPTH: usize = 32;
+
+/// Body format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Cbor,
+    MessagePack,
+}
+
+impl Format {
+    /// Format of a media type, ignoring its parameters
+    fn from_media_type(media_type: &str) -> Option<Self> {
+        let essence = media_type.split(';').next()?.trim().to_ascii_lowercase();
+        match essence.as_str() {
+            "application/json" => Some(Self::Json),
+            "application/cbor" => Some(Self::Cbor),
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => {
+                Some(Self::MessagePack)
+            }
+            _ => None,
+        }
+    }
+
+    /// Format of a request body, from its Content-Type
+    pub fn of_body(headers: &HeaderMap) -> Option<Self> {
+        let content_type = headers.get(CONTENT_TYPE)?.to_str().ok()?;
+        Self::from_media_type(content_type)
+    }
+
+    /// Format the Accept header prefers, by quality and then order
+    pub fn accepted(headers: &HeaderMap) -> Self {
+        let mut ranges: Vec<(Option<Self>, f32)> = headers
+            .get_all(ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|range| {
+                let quality = range
+                    .split(';')
+                    .skip(1)
+                    .filter_map(|param| param.trim().strip_prefix("q="))
+                    .find_map(|q| q.parse().ok())
+                    .unwrap_or(1.0);
+                (Self::from_media_type(range), quality)
+            })
+            .filter(|&(_, quality)| quality > 0.0)
+            .collect();
+        // Stable, so equally preferred ranges keep their order
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranges
+            .into_iter()
+            .find_map(|(format, _)| format)
+            .unwrap_or(Self::Json)
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Cbor => "application/cbor",
+            Self::MessagePack => "application/msgpack",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Json => "JSON",
+            Self::Cbor => "CBOR",
+            Self::MessagePack => "MessagePack",
+        }
+    }
+
+    /// Serialize `value`; byte fields become byte strings in the binary
+    /// formats
+    pub fn encode<T: Serialize>(self, value: &T) -> Result<Vec<u8>, CodecError> {
+        let mut out = Vec::new();
+        match self {
+            Self::Json => {
+                serde_json::to_writer(&mut out, value).map_err(|e| CodecError(e.to_string()))?
+            }
+            Self::Cbor => cbor::encode(&value.serialize(ValueSerializer)?, &mut out),
+            Self::MessagePack => msgpack::encode(&value.serialize(ValueSerializer)?, &mut out),
+        }
+        Ok(out)
+    }
+
+    /// Parse a body; byte strings become hex text, as JSON bodies carry them
+    pub fn decode<T: DeserializeOwned>(self, bytes: &[u8]) -> Result<T, CodecError> {
+        let value = match self {
+            Self::Json => {
+                return serde_json::from_slice(bytes).map_err(|e| CodecError(e.to_string()))
+            }
+            Self::Cbor => cbor::decode(bytes)?,
+            Self::MessagePack => msgpack::decode(bytes)?,
+        };
+        serde_json::from_value(value.into_json()?).map_err(|e| CodecError(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Format {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Infallible> {
+        Ok(Self::accepted(&parts.headers))
+    }
+}
+
+/// Body extractor reading JSON, CBOR or MessagePack by Content-Type
+pub struct ApiBody<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ApiBody<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, ApiError> {
+        let format = match Format::of_body(request.headers()) {
+            Some(format @ (Format::Cbor | Format::MessagePack)) => format,
+            // The JSON extractor explains a missing or unknown Content-Type
+            _ => {
+                return ApiJson::from_request(request, state)
+                    .await
+                    .map(|ApiJson(body)| Self(body))
+            }
+        };
+        let bytes = Bytes::from_request(request, state).await?;
+        format.decode(&bytes).map(Self).map_err(|e| {
+            ApiError::invalid_request(format!("Failed to parse the {} body: {}", format.name(), e))
+        })
+    }
+}
+
+/// Response body in the format the client accepts
+pub struct Negotiated<T>(pub Format, pub T);
+
+impl<T: Serialize> IntoResponse for Negotiated<T> {
+    fn into_response(self) -> Response {
+        let Self(format, body) = self;
+        let mut response = match format {
+            Format::Json => Json(body).into_response(),
+            format => match format.encode(&body) {
+                Ok(bytes) => ([(CONTENT_TYPE, format.content_type())], bytes).into_response(),
+                Err(e) => return ApiError::internal("Encoding response", e).into_response(),
+            },
+        };
+        response.headers_mut().insert(VARY, ACCEPT.into());
+        response
+    }
+}
+
+/// Error encoding or decoding a body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodecError(String);
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+impl ser::Error for CodecError {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        Self(message.to_string())
+    }
+}
+
+fn invalid(message: impl ToString) -> CodecError {
+    CodecError(message.to_string())
+}
+
+/// Data model shared by the binary formats
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    /// Every integer either format can carry, from -2^64 to 2^64 - 1
+    Integer(i128),
+    Float(f64),
+    Text(String),
+    Bytes(Vec<u8>),
+    Array(Vec<Value>),
+    Map(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// The JSON equivalent, with byte strings as hex
+    fn into_json(self) -> Result<serde_json::Value, CodecError> {
+        use serde_json::Value as Json;
+        Ok(match self {
+            Self::Null => Json::Null,
+            Self::Bool(b) => Json::Bool(b),
+            Self::Integer(i) => match (u64::try_from(i), i64::try_from(i)) {
+                (Ok(u), _) => u.into(),
+                (_, Ok(i)) => i.into(),
+                _ => return Err(invalid(format!("integer {} is out of range", i))),
+            },
+            Self::Float(f) => serde_json::Number::from_f64(f)
+                .map(Json::Number)
+                .ok_or_else(|| invalid("floats must be finite"))?,
+            Self::Text(s) => Json::String(s),
+            Self::Bytes(bytes) => Json::String(hex::encode(bytes)),
+            Self::Array(items) => Json::Array(
+                items
+                    .into_iter()
+                    .map(Self::into_json)
+                    .collect::<Result<_, _>>()?,
+            ),
+            Self::Map(entries) => Json::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| Ok((key, value.into_json()?)))
+                    .collect::<Result<_, CodecError>>()?,
+            ),
+        })
+    }
+}
+
+/// Cursor over an encoded body
+struct Reader<'a> {
+    bytes: &'a [u8],
+    depth: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], CodecError> {
+        if n > self.bytes.len() {
+            return Err(invalid("body ends early"));
+        }
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, CodecError> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Big-endian unsigned integer of `n` bytes
+    fn uint(&mut self, n: usize) -> Result<u64, CodecError> {
+        Ok(self
+            .take(n)?
+            .iter()
+            .fold(0, |acc, &b| (acc << 8) | u64::from(b)))
+    }
+
+    /// `len` items follow; each takes at least a byte, so a length beyond
+    /// the rest of the body is refused before allocating for it
+    fn items(&self, len: u64) -> Result<usize, CodecError> {
+        usize::try_from(len)
+            .ok()
+            .filter(|&len| len <= self.bytes.len())
+            .ok_or_else(|| invalid("length exceeds the body"))
+    }
+
+    fn text(&mut self, len: u64) -> Result<String, CodecError> {
+        let len = self.items(len)?;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| invalid("text is not UTF-8"))
+    }
+
+    fn bytes(&mut self, len: u64) -> Result<Vec<u8>, CodecError> {
+        let len = self.items(len)?;
+        Ok(self.take(len)?.to_vec())
+    }
+
+    fn nested<T>(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> Result<T, CodecError>,
+    ) -> Result<T, CodecError> {
+        if self.depth == MAX_DEPTH {
+            return Err(invalid("nested too deeply"));
+        }
+        self.depth += 1;
+        let value = read(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn array(
+        &mut self,
+        len: u64,
+        item: fn(&mut Self) -> Result<Value, CodecError>,
+    ) -> Result<Value, CodecError> {
+        let len = self.items(len)?;
+        self.nested(|reader| {
+            (0..len)
+                .map(|_| item(reader))
+                .collect::<Result<_, _>>()
+                .map(Value::Array)
+        })
+    }
+
+    fn map(
+        &mut self,
+        len: u64,
+        item: fn(&mut Self) -> Result<Value, CodecError>,
+    ) -> Result<Value, CodecError> {
+        let len = self.items(len)?;
+        self.nested(|reader| {
+            (0..len)
+                .map(|_| match item(reader)? {
+                    Value::Text(key) => Ok((key, item(reader)?)),
+                    _ => Err(invalid("map keys must be text")),
+                })
+                .collect::<Result<_, _>>()
+                .map(Value::Map)
+        })
+    }
+
+    /// Read a whole body with `item`
+    fn finish(
+        bytes: &'a [u8],
+        item: fn(&mut Self) -> Result<Value, CodecError>,
+    ) -> Result<Value, CodecError> {
+        let mut reader = Self { bytes, depth: 0 };
+        let value = item(&mut reader)?;
+        if !reader.bytes.is_empty() {
+            return Err(invalid("trailing bytes after the body"));
+        }
+        Ok(value)
+    }
+}
+
+/// CBOR (RFC 8949), definite lengths only
+mod cbor {
+    use super::{invalid, CodecError, Reader, Value};
+
+    fn head(out: &mut Vec<u8>, major: u8, n: u64) {
+        let major = major << 5;
+        match n {
+            0..=23 => out.push(major | n as u8),
+            24..=0xff => out.extend([major | 24, n as u8]),
+            0x100..=0xffff => {
+                out.push(major | 25);
+                out.extend((n as u16).to_be_bytes());
+            }
+            0x1_0000..=0xffff_ffff => {
+                out.push(major | 26);
+                out.extend((n as u32).to_be_bytes());
+            }
+            _ => {
+                out.push(major | 27);
+                out.extend(n.to_be_bytes());
+            }
+        }
+    }
+
+    pub fn encode(value: &Value, out: &mut Vec<u8>) {
+        match value {
+            Value::Null => out.push(0xf6),
+            Value::Bool(false) => out.push(0xf4),
+            Value::Bool(true) => out.push(0xf5),
+            Value::Integer(i) if *i >= 0 => head(out, 0, *i as u64),
+            Value::Integer(i) => head(out, 1, (-1 - *i) as u64),
+            Value::Float(f) => {
+                out.push(0xfb);
+                out.extend(f.to_be_bytes());
+            }
+            Value::Text(s) => {
+                head(out, 3, s.len() as u64);
+                out.extend(s.as_bytes());
+            }
+            Value::Bytes(bytes) => {
+                head(out, 2, bytes.len() as u64);
+                out.extend(bytes);
+            }
+            Value::Array(items) => {
+                head(out, 4, items.len() as u64);
+                items.iter().for_each(|item| encode(item, out));
+            }
+            Value::Map(entries) => {
+                head(out, 5, entries.len() as u64);
+                for (key, value) in entries {
+                    head(out, 3, key.len() as u64);
+                    out.extend(key.as_bytes());
+                    encode(value, out);
+                }
+            }
+        }
+    }
+
+    /// IEEE 754 half-precision float
+    fn half(bits: u16) -> f64 {
+        let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+        let exponent = i32::from((bits >> 10) & 0x1f);
+        let mantissa = f64::from(bits & 0x3ff);
+        sign * match exponent {
+            0 => mantissa * 2f64.powi(-24),
+            31 if mantissa == 0.0 => f64::INFINITY,
+            31 => f64::NAN,
+            _ => (1.0 + mantissa / 1024.0) * 2f64.powi(exponent - 15),
+        }
+    }
+
+    fn item(reader: &mut Reader) -> Result<Value, CodecError> {
+        let initial = reader.byte()?;
+        let (major, info) = (initial >> 5, initial & 0x1f);
+        if major == 7 {
+            return match info {
+                20 => Ok(Value::Bool(false)),
+                21 => Ok(Value::Bool(true)),
+                22 => Ok(Value::Null),
+                25 => Ok(Value::Float(half(reader.uint(2)? as u16))),
+                26 => Ok(Value::Float(f64::from(f32::from_bits(
+                    reader.uint(4)? as u32
+                )))),
+                27 => Ok(Value::Float(f64::from_bits(reader.uint(8)?))),
+                _ => Err(invalid(format!("unsupported CBOR simple value {}", info))),
+            };
+        }
+        let n = match info {
+            0..=23 => u64::from(info),
+            24 => reader.uint(1)?,
+            25 => reader.uint(2)?,
+            26 => reader.uint(4)?,
+            27 => reader.uint(8)?,
+            31 => return Err(invalid("indefinite-length CBOR items are not supported")),
+            _ => {
+                return Err(invalid(format!(
+                    "invalid CBOR initial byte {:#04x}",
+                    initial
+                )))
+            }
+        };
+        match major {
+            0 => Ok(Value::Integer(i128::from(n))),
+            1 => Ok(Value::Integer(-1 - i128::from(n))),
+            2 => reader.bytes(n).map(Value::Bytes),
+            3 => reader.text(n).map(Value::Text),
+            4 => reader.array(n, item),
+            5 => reader.map(n, item),
+            _ => Err(invalid("CBOR tags are not supported")),
+        }
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Value, CodecError> {
+        Reader::finish(bytes, item)
+    }
+}
+
+/// MessagePack, without extension types
+mod msgpack {
+    use super::{invalid, CodecError, Reader, Value};
+
+    /// Write `marker` and `n` in the smallest of the 1, 2 or 4 byte forms
+    /// whose markers follow it
+    fn sized(out: &mut Vec<u8>, markers: [u8; 3], n: usize) {
+        if let Ok(n) = u8::try_from(n) {
+            out.extend([markers[0], n]);
+        } else if let Ok(n) = u16::try_from(n) {
+            out.push(markers[1]);
+            out.extend(n.to_be_bytes());
+        } else {
+            out.push(markers[2]);
+            out.extend((n as u32).to_be_bytes());
+        }
+    }
+
+    fn text(out: &mut Vec<u8>, s: &str) {
+        match s.len() {
+            len @ 0..=31 => out.push(0xa0 | len as u8),
+            len => sized(out, [0xd9, 0xda, 0xdb], len),
+        }
+        out.extend(s.as_bytes());
+    }
+
+    /// Fix form below 16 items, else the 2 or 4 byte form
+    fn container(out: &mut Vec<u8>, fix: u8, markers: [u8; 2], len: usize) {
+        if len < 16 {
+            out.push(fix | len as u8);
+        } else if let Ok(len) = u16::try_from(len) {
+            out.push(markers[0]);
+            out.extend(len.to_be_bytes());
+        } else {
+            out.push(markers[1]);
+            out.extend((len as u32).to_be_bytes());
+        }
+    }
+
+    pub fn encode(value: &Value, out: &mut Vec<u8>) {
+        match value {
+            Value::Null => out.push(0xc0),
+            Value::Bool(false) => out.push(0xc2),
+            Value::Bool(true) => out.push(0xc3),
+            Value::Integer(i) => match *i {
+                i @ 0..=0x7f => out.push(i as u8),
+                i @ -32..=-1 => out.push(i as i8 as u8),
+                i @ 0x80..=0xff => out.extend([0xcc, i as u8]),
+                i @ 0x100..=0xffff => {
+                    out.push(0xcd);
+                    out.extend((i as u16).to_be_bytes());
+                }
+                i @ 0x1_0000..=0xffff_ffff => {
+                    out.push(0xce);
+                    out.extend((i as u32).to_be_bytes());
+                }
+                i if i > 0 => {
+                    out.push(0xcf);
+                    out.extend((i as u64).to_be_bytes());
+                }
+                i => {
+                    out.push(0xd3);
+                    out.extend((i as i64).to_be_bytes());
+                }
+            },
+            Value::Float(f) => {
+                out.push(0xcb);
+                out.extend(f.to_be_bytes());
+            }
+            Value::Text(s) => text(out, s),
+            Value::Bytes(bytes) => {
+                sized(out, [0xc4, 0xc5, 0xc6], bytes.len());
+                out.extend(bytes);
+            }
+            Value::Array(items) => {
+                container(out, 0x90, [0xdc, 0xdd], items.len());
+                items.iter().for_each(|item| encode(item, out));
+            }
+            Value::Map(entries) => {
+                container(out, 0x80, [0xde, 0xdf], entries.len());
+                for (key, value) in entries {
+                    text(out, key);
+                    encode(value, out);
+                }
+            }
+        }
+    }
+
+    fn item(reader: &mut Reader) -> Result<Value, CodecError> {
+        let marker = reader.byte()?;
+        match marker {
+            0x00..=0x7f => Ok(Value::Integer(i128::from(marker))),
+            0x80..=0x8f => reader.map(u64::from(marker & 0x0f), item),
+            0x90..=0x9f => reader.array(u64::from(marker & 0x0f), item),
+            0xa0..=0xbf => reader.text(u64::from(marker & 0x1f)).map(Value::Text),
+            0xc0 => Ok(Value::Null),
+            0xc2 => Ok(Value::Bool(false)),
+            0xc3 => Ok(Value::Bool(true)),
+            0xc4 => reader
+                .uint(1)
+                .and_then(|n| reader.bytes(n))
+                .map(Value::Bytes),
+            0xc5 => reader
+                .uint(2)
+                .and_then(|n| reader.bytes(n))
+                .map(Value::Bytes),
+            0xc6 => reader
+                .uint(4)
+                .and_then(|n| reader.bytes(n))
+                .map(Value::Bytes),
+            0xca => Ok(Value::Float(f64::from(f32::from_bits(
+                reader.uint(4)? as u32
+            )))),
+            0xcb => Ok(Value::Float(f64::from_bits(reader.uint(8)?))),
+            0xcc => reader.uint(1).map(|n| Value::Integer(i128::from(n))),
+            0xcd => reader.uint(2).map(|n| Value::Integer(i128::from(n))),
+            0xce => reader.uint(4).map(|n| Value::Integer(i128::from(n))),
+            0xcf => reader.uint(8).map(|n| Value::Integer(i128::from(n))),
+            0xd0 => reader
+                .uint(1)
+                .map(|n| Value::Integer(i128::from(n as u8 as i8))),
+            0xd1 => reader
+                .uint(2)
+                .map(|n| Value::Integer(i128::from(n as u16 as i16))),
+            0xd2 => reader
+                .uint(4)
+                .map(|n| Value::Integer(i128::from(n as u32 as i32))),
+            0xd3 => reader.uint(8).map(|n| Value::Integer(i128::from(n as i64))),
+            0xd9 => reader.uint(1).and_then(|n| reader.text(n)).map(Value::Text),
+            0xda => reader.uint(2).and_then(|n| reader.text(n)).map(Value::Text),
+            0xdb => reader.uint(4).and_then(|n| reader.text(n)).map(Value::Text),
+            0xdc => reader.uint(2).and_then(|n| reader.array(n, item)),
+            0xdd => reader.uint(4).and_then(|n| reader.array(n, item)),
+            0xde => reader.uint(2).and_then(|n| reader.map(n, item)),
+            0xdf => reader.uint(4).and_then(|n| reader.map(n, item)),
+            0xe0..=0xff => Ok(Value::Integer(i128::from(marker as i8))),
+            0xc7..=0xc9 | 0xd4..=0xd8 => Err(invalid("MessagePack extensions are not supported")),
+            _ => Err(invalid(format!(
+                "invalid MessagePack marker {:#04x}",
+                marker
+            ))),
+        }
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Value, CodecError> {
+        Reader::finish(bytes, item)
+    }
+}
+
+/// Serializer building a [`Value`]. It isn't human-readable, so byte fields
+/// serialize as bytes rather than hex.
+struct ValueSerializer;
+
+/// Array being serialized, wrapped in a map of its variant's name when it's
+/// an enum variant's fields
+struct ArrayBuilder {
+    items: Vec<Value>,
+    variant: Option<&'static str>,
+}
+
+/// Map being serialized, wrapped like [`ArrayBuilder`]
+struct MapBuilder {
+    entries: Vec<(String, Value)>,
+    key: Option<String>,
+    variant: Option<&'static str>,
+}
+
+/// `{variant: value}`, as JSON represents enum variants with data
+fn tagged(variant: Option<&'static str>, value: Value) -> Value {
+    match variant {
+        Some(variant) => Value::Map(vec![(variant.to_string(), value)]),
+        None => value,
+    }
+}
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = CodecError;
+    type SerializeSeq = ArrayBuilder;
+    type SerializeTuple = ArrayBuilder;
+    type SerializeTupleStruct = ArrayBuilder;
+    type SerializeTupleVariant = ArrayBuilder;
+    type SerializeMap = MapBuilder;
+    type SerializeStruct = MapBuilder;
+    type SerializeStructVariant = MapBuilder;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<Value, CodecError> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, CodecError> {
+        Ok(Value::Integer(v.into()))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, CodecError> {
+        Ok(Value::Integer(v.into()))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, CodecError> {
+        Ok(Value::Integer(v.into()))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value, CodecError> {
+        Ok(Value::Integer(v.into()))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, CodecError> {
+        Ok(Value::Integer(v.into()))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, CodecError> {
+        Ok(Value::Integer(v.into()))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, CodecError> {
+        Ok(Value::Integer(v.into()))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value, CodecError> {
+        Ok(Value::Integer(v.into()))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value, CodecError> {
+        Ok(Value::Float(v.into()))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value, CodecError> {
+        Ok(Value::Float(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, CodecError> {
+        Ok(Value::Text(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, CodecError> {
+        Ok(Value::Text(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, CodecError> {
+        Ok(Value::Bytes(v.to_vec()))
+    }
+
+    fn serialize_none(self) -> Result<Value, CodecError> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, CodecError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, CodecError> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, CodecError> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Value, CodecError> {
+        Ok(Value::Text(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value, CodecError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value, CodecError> {
+        Ok(tagged(Some(variant), value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<ArrayBuilder, CodecError> {
+        Ok(ArrayBuilder {
+            items: Vec::with_capacity(len.unwrap_or(0)),
+            variant: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<ArrayBuilder, CodecError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<ArrayBuilder, CodecError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<ArrayBuilder, CodecError> {
+        Ok(ArrayBuilder {
+            items: Vec::with_capacity(len),
+            variant: Some(variant),
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<MapBuilder, CodecError> {
+        Ok(MapBuilder {
+            entries: Vec::with_capacity(len.unwrap_or(0)),
+            key: None,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<MapBuilder, CodecError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<MapBuilder, CodecError> {
+        Ok(MapBuilder {
+            entries: Vec::with_capacity(len),
+            key: None,
+            variant: Some(variant),
+        })
+    }
+}
+
+impl ArrayBuilder {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), CodecError> {
+        self.items.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Value, CodecError> {
+        Ok(tagged(self.variant, Value::Array(self.items)))
+    }
+}
+
+impl ser::SerializeSeq for ArrayBuilder {
+    type Ok = Value;
+    type Error = CodecError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), CodecError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, CodecError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for ArrayBuilder {
+    type Ok = Value;
+    type Error = CodecError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), CodecError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, CodecError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for ArrayBuilder {
+    type Ok = Value;
+    type Error = CodecError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), CodecError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, CodecError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for ArrayBuilder {
+    type Ok = Value;
+    type Error = CodecError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), CodecError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, CodecError> {
+        self.finish()
+    }
+}
+
+impl MapBuilder {
+    fn entry<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<(), CodecError> {
+        let value = value.serialize(ValueSerializer)?;
+        self.entries.push((key.to_string(), value));
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Value, CodecError> {
+        Ok(tagged(self.variant, Value::Map(self.entries)))
+    }
+}
+
+impl ser::SerializeMap for MapBuilder {
+    type Ok = Value;
+    type Error = CodecError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), CodecError> {
+        // Keys are text, with integers written out as JSON does
+        self.key = Some(match key.serialize(ValueSerializer)? {
+            Value::Text(key) => key,
+            Value::Integer(key) => key.to_string(),
+            _ => return Err(invalid("map keys must be text")),
+        });
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), CodecError> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| invalid("map value without a key"))?;
+        self.entry(&key, value)
+    }
+
+    fn end(self) -> Result<Value, CodecError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for MapBuilder {
+    type Ok = Value;
+    type Error = CodecError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), CodecError> {
+        self.entry(key, value)
+    }
+
+    fn end(self) -> Result<Value, CodecError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for MapBuilder {
+    type Ok = Value;
+    type Error = CodecError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), CodecError> {
+        self.entry(key, value)
+    }
+
+    fn end(self) -> Result<Value, CodecError> {
+        self.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+    use zkrag_verifier::{ProofEnvelope, PublicInputs};
+
+    #[test]
+    fn test_binary_envelopes() {
+        let inputs = PublicInputs {
+            document_commitment: "cd".repeat(32),
+            model_hash: "ef".repeat(32),
+            timestamp: 1_700_000_000,
+            nonce: None,
+        };
+        let envelope = ProofEnvelope::new(vec![7; 256], inputs).with_key_id("ab".repeat(32));
+        let json = Format::Json.encode(&envelope).unwrap();
+
+        for format in [Format::Cbor, Format::MessagePack] {
+            let bytes = format.encode(&envelope).unwrap();
+            // The proof is raw bytes rather than hex
+            assert!(bytes.len() < json.len() * 3 / 4, "{:?}", format);
+            assert_eq!(format.decode::<ProofEnvelope>(&bytes).unwrap(), envelope);
+            assert!(format
+                .decode::<ProofEnvelope>(&bytes[..bytes.len() - 1])
+                .is_err());
+        }
+
+        // RFC 8949 appendix A and MessagePack spec examples
+        let cbor = [
+            0xa2, 0x61, 0x61, 0x39, 0x01, 0xf3, 0x61, 0x62, 0xf9, 0x3c, 0x00,
+        ];
+        let value: serde_json::Value = Format::Cbor.decode(&cbor).unwrap();
+        assert_eq!(value, serde_json::json!({"a": -500, "b": 1.0}));
+        let msgpack = [
+            0x82, 0xa1, 0x61, 0xd1, 0xfe, 0x0c, 0xa1, 0x62, 0xc4, 0x01, 0xff,
+        ];
+        let value: serde_json::Value = Format::MessagePack.decode(&msgpack).unwrap();
+        assert_eq!(value, serde_json::json!({"a": -500, "b": "ff"}));
+        // Lengths past the end of the body are refused before allocating
+        assert!(Format::Cbor
+            .decode::<Vec<u8>>(&[0x9b, 0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0])
+            .is_err());
+        assert!(Format::MessagePack
+            .decode::<Vec<u8>>(&[0xdd, 0xff, 0xff, 0xff, 0xff])
+            .is_err());
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(Format::accepted(&headers), Format::Json);
+        headers.insert(
+            ACCEPT,
+            HeaderValue::from_static("application/json;q=0.5, application/cbor, */*;q=0.1"),
+        );
+        assert_eq!(Format::accepted(&headers), Format::Cbor);
+        headers.insert(
+            ACCEPT,
+            HeaderValue::from_static("application/msgpack;q=0, text/html"),
+        );
+        assert_eq!(Format::accepted(&headers), Format::Json);
+    }
+}
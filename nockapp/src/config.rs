@@ -0,0 +1,639 @@
+// Server configuration
+//
+// Settings are layered, each source overriding the ones before it:
+// 1. built-in defaults
+// 2. a TOML file, named by `--config` or $ZKRAG_CONFIG
+// 3. environment variables
+// 4. command-line flags (see `--help`)
+//
+// The file's tables, with the environment variable for each setting:
+//
+//     [server]
+//     bind = "0.0.0.0:8080"                 # ZKRAG_BIND
+//     shutdown_timeout_secs = 30            # ZKRAG_SHUTDOWN_TIMEOUT
+//
+//     [cors]                                # see cors.rs; lists comma-separated
+//     origins = ["https://app.example.com"] # ZKRAG_CORS_ORIGINS (default none)
+//     methods = ["GET", "POST", "DELETE"]   # ZKRAG_CORS_METHODS
+//     headers = ["authorization", "content-type", "idempotency-key"]
+//                                           # ZKRAG_CORS_HEADERS
+//     allow_credentials = false             # ZKRAG_CORS_ALLOW_CREDENTIALS
+//     max_age_secs = 600                    # ZKRAG_CORS_MAX_AGE
+//
+//     [tls]                                 # both or neither; see tls.rs
+//     cert = "/etc/zkrag/cert.pem"          # ZKRAG_TLS_CERT
+//     key = "/etc/zkrag/key.pem"            # ZKRAG_TLS_KEY
+//
+//     [keys]
+//     dir = "/var/lib/zkrag/keys"           # ZKRAG_KEY_DIR (default ~/.zkrag/keys)
+//     verifying_key = "..."                 # ZKRAG_VERIFYING_KEY (default in dir)
+//     proving_key = "..."                   # ZKRAG_PROVING_KEY (default in dir)
+//     receipt_key = "..."                   # ZKRAG_RECEIPT_KEY: signs receipts
+//
+//     [rotation]                            # see rotation.rs
+//     grace_secs = 604800                   # ZKRAG_KEY_GRACE_SECS
+//
+//     [database]
+//     url = "sqlite:zkrag-verifier.db"      # DATABASE_URL
+//
+//     [archive]                             # see archive.rs
+//     url = "s3://zkrag-proofs/prod"        # ZKRAG_ARCHIVE_URL (default none)
+//
+//     [auth]                                # off without an issuer; see auth.rs
+//     issuer = "https://login.example.com/realms/zkrag"  # ZKRAG_AUTH_ISSUER
+//     jwks_url = "..."                      # ZKRAG_AUTH_JWKS_URL
+//     audience = "..."                      # ZKRAG_AUTH_AUDIENCE
+//     roles_claim = "roles"                 # ZKRAG_AUTH_ROLES_CLAIM
+//
+//     [limits]                              # see ratelimit.rs
+//     verify_per_minute = 30                # ZKRAG_RATE_LIMIT_VERIFY
+//     read_per_minute = 600                 # ZKRAG_RATE_LIMIT_READ
+//     trust_proxy = false                   # ZKRAG_TRUST_PROXY
+//
+//     [guard]                               # see guard.rs
+//     max_body_bytes = 1048576              # ZKRAG_MAX_BODY_BYTES
+//     timeout_secs = 30                     # ZKRAG_REQUEST_TIMEOUT
+//     max_concurrent_verifications = 32     # ZKRAG_MAX_CONCURRENT_VERIFICATIONS
+//
+//     [prover]                              # see prover.rs
+//     workers = 4                           # ZKRAG_PROVER_WORKERS
+//     queue = 16                            # ZKRAG_PROVER_QUEUE
+//
+//     [models]                              # see artifacts.rs
+//     max_upload_bytes = 34359738368        # ZKRAG_MAX_MODEL_UPLOAD_BYTES
+//
+//     [kernel]                              # off without a socket; see kernel.rs
+//     socket = "/run/zkrag/kernel.sock"     # ZKRAG_KERNEL_SOCKET
+//     timeout_secs = 10                     # ZKRAG_KERNEL_TIMEOUT
+//
+//     [idempotency]                         # see idempotency.rs
+//     window_secs = 86400                   # ZKRAG_IDEMPOTENCY_WINDOW
+//
+//     [challenge]                           # see challenge.rs
+//     ttl_secs = 300                        # ZKRAG_CHALLENGE_TTL_SECS
+//     required = false                      # ZKRAG_REQUIRE_CHALLENGE
+//
+//     [nullifiers]                          # see nullifiers.rs
+//     reject_replays = false                # ZKRAG_REJECT_REPLAYS
+//     epoch_secs = 86400                    # ZKRAG_NULLIFIER_EPOCH_SECS
+//
+//     [retention]                           # see retention.rs
+//     interval_secs = 3600                  # ZKRAG_RETENTION_INTERVAL
+//     query_secs = 0                        # ZKRAG_QUERY_RETENTION_SECS (forever)
+//     tenants = { alice = 2592000 }         # per document owner
+//
+//     [cluster]                             # see cluster.rs
+//     redis_url = "redis://cache:6379/0"    # ZKRAG_REDIS_URL (default none)
+//     key_prefix = "zkrag"                  # ZKRAG_REDIS_PREFIX
+//
+//     [telemetry]                           # see telemetry.rs
+//     otlp_endpoint = "http://collector:4317"
+//                                           # ZKRAG_OTLP_ENDPOINT (default none)
+//     service_name = "zkrag-verifier"       # ZKRAG_SERVICE_NAME
+//     sample_percent = 100                  # ZKRAG_TRACE_SAMPLE_PERCENT
+//
+//     [policy]
+//     max_proof_age_secs = 86400            # ZKRAG_MAX_PROOF_AGE
+//     file = "/etc/zkrag/policy.toml"       # ZKRAG_POLICY_FILE: model allowlist
+//
+// Empty environment variables are ignored. Unknown keys in the file are
+// errors, so a misspelt setting doesn't silently keep its default.
+
+use anyhow::Context;
+use clap::Parser;
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use zkrag_prover::PROVING_KEY_FILE;
+use zkrag_verifier::keys::default_key_dir;
+
+use crate::archive::ArchiveConfig;
+use crate::artifacts::ModelsConfig;
+use crate::auth::AuthConfig;
+use crate::challenge::ChallengeConfig;
+use crate::cluster::ClusterConfig;
+use crate::cors::CorsConfig;
+use crate::guard::GuardConfig;
+use crate::idempotency::IdempotencyConfig;
+use crate::kernel::KernelConfig;
+use crate::nullifiers::NullifierConfig;
+use crate::prover::ProverConfig;
+use crate::ratelimit::RateLimitConfig;
+use crate::retention::RetentionConfig;
+use crate::rotation::RotationConfig;
+use crate::telemetry::TelemetryConfig;
+use crate::tls::TlsConfig;
+
+/// Command-line flags; each overrides the config file and environment
+#[derive(Debug, Default, Parser)]
+#[command(version, about = "ZK-RAG verifier HTTP server")]
+pub struct Cli {
+    /// TOML config file [env: ZKRAG_CONFIG]
+    #[arg(long, short)]
+    pub config: Option<PathBuf>,
+    /// Address to listen on
+    #[arg(long)]
+    pub bind: Option<SocketAddr>,
+    /// Origin allowed to call the API from a browser; repeat for several,
+    /// or `*` for any
+    #[arg(long = "cors-origin")]
+    pub cors_origins: Vec<String>,
+    /// Directory holding the verifying and proving keys
+    #[arg(long)]
+    pub key_dir: Option<PathBuf>,
+    /// Verifying key file, instead of the one in the key directory
+    #[arg(long)]
+    pub verifying_key: Option<PathBuf>,
+    /// Proving key file, instead of the one in the key directory
+    #[arg(long)]
+    pub proving_key: Option<PathBuf>,
+    /// ed25519 secret key to sign verification receipts with
+    #[arg(long)]
+    pub receipt_key: Option<PathBuf>,
+    /// SQLite database, e.g. `sqlite:zkrag-verifier.db`
+    #[arg(long)]
+    pub database_url: Option<String>,
+    /// Token issuer; enables authentication
+    #[arg(long)]
+    pub auth_issuer: Option<String>,
+    /// Proof verifications and generations per minute per client
+    #[arg(long)]
+    pub rate_limit_verify: Option<u32>,
+    /// Other API requests per minute per client
+    #[arg(long)]
+    pub rate_limit_read: Option<u32>,
+    /// Take client addresses from X-Forwarded-For
+    #[arg(long)]
+    pub trust_proxy: bool,
+    /// Threads generating proofs
+    #[arg(long)]
+    pub prover_workers: Option<usize>,
+    /// Proof generations that may wait for a worker
+    #[arg(long)]
+    pub prover_queue: Option<usize>,
+    /// PEM certificate chain to serve HTTPS with
+    #[arg(long)]
+    pub tls_cert: Option<PathBuf>,
+    /// PEM private key of the certificate
+    #[arg(long)]
+    pub tls_key: Option<PathBuf>,
+    /// Reject proofs older than this many seconds
+    #[arg(long)]
+    pub max_proof_age: Option<u64>,
+    /// Model allowlist, as TOML or JSON
+    #[arg(long)]
+    pub policy_file: Option<PathBuf>,
+    /// Unix socket of the NockApp kernel process
+    #[arg(long)]
+    pub kernel_socket: Option<PathBuf>,
+    /// Seconds to wait for requests in flight when shutting down
+    #[arg(long)]
+    pub shutdown_timeout: Option<u64>,
+}
+
+/// Every setting of the server
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub cors: CorsConfig,
+    pub tls: TlsSection,
+    pub keys: KeyConfig,
+    pub rotation: RotationConfig,
+    pub database: DatabaseConfig,
+    pub archive: ArchiveConfig,
+    pub auth: AuthSection,
+    pub limits: RateLimitConfig,
+    pub guard: GuardConfig,
+    pub prover: ProverConfig,
+    pub models: ModelsConfig,
+    pub kernel: KernelConfig,
+    pub idempotency: IdempotencyConfig,
+    pub challenge: ChallengeConfig,
+    pub nullifiers: NullifierConfig,
+    pub retention: RetentionConfig,
+    pub cluster: ClusterConfig,
+    pub telemetry: TelemetryConfig,
+    pub policy: PolicyConfig,
+}
+
+/// `[server]` table
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind: SocketAddr,
+    pub shutdown_timeout_secs: u64,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: SocketAddr::from(([0, 0, 0, 0], 8080)),
+            shutdown_timeout_secs: 30,
+        }
+    }
+}
+
+/// `[tls]` table; see [`Config::tls`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsSection {
+    pub cert: Option<PathBuf>,
+    pub key: Option<PathBuf>,
+}
+
+/// `[keys]` table
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeyConfig {
+    pub dir: Option<PathBuf>,
+    pub verifying_key: Option<PathBuf>,
+    pub proving_key: Option<PathBuf>,
+    /// ed25519 secret key signing verification receipts; see keys.rs
+    pub receipt_key: Option<PathBuf>,
+}
+
+/// `[database]` table
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: String,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: "sqlite:zkrag-verifier.db".to_string(),
+        }
+    }
+}
+
+/// `[auth]` table; see [`Config::auth`]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthSection {
+    pub issuer: Option<String>,
+    pub jwks_url: Option<String>,
+    pub audience: Option<String>,
+    pub roles_claim: String,
+}
+
+impl Default for AuthSection {
+    fn default() -> Self {
+        Self {
+            issuer: None,
+            jwks_url: None,
+            audience: None,
+            roles_claim: "roles".to_string(),
+        }
+    }
+}
+
+/// `[policy]` table
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PolicyConfig {
+    pub max_proof_age_secs: Option<u64>,
+    pub file: Option<PathBuf>,
+}
+
+/// Environment lookup; the process environment outside of tests
+type Env<'a> = &'a dyn Fn(&str) -> Option<String>;
+
+/// Value of variable `name`, parsed, if it's set and not empty
+fn var<T>(env: Env, name: &str) -> anyhow::Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    env(name)
+        .filter(|value| !value.is_empty())
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|e| anyhow::anyhow!("Invalid {}: {}", name, e))
+        })
+        .transpose()
+}
+
+impl Config {
+    /// Layer the config file, `env` and `cli` over the defaults
+    pub fn load(cli: &Cli, env: Env) -> anyhow::Result<Self> {
+        let file = match &cli.config {
+            Some(path) => Some(path.clone()),
+            None => var::<PathBuf>(env, "ZKRAG_CONFIG")?,
+        };
+        let mut config = match file {
+            Some(path) => Self::from_file(&path)?,
+            None => Self::default(),
+        };
+        config.apply_env(env)?;
+        config.apply_cli(cli);
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("Invalid config {}", path.display()))
+    }
+
+    fn apply_env(&mut self, env: Env) -> anyhow::Result<()> {
+        fn set<T>(env: Env, name: &str, target: &mut T) -> anyhow::Result<()>
+        where
+            T: FromStr,
+            T::Err: std::fmt::Display,
+        {
+            if let Some(value) = var(env, name)? {
+                *target = value;
+            }
+            Ok(())
+        }
+        fn set_optional<T>(env: Env, name: &str, target: &mut Option<T>) -> anyhow::Result<()>
+        where
+            T: FromStr,
+            T::Err: std::fmt::Display,
+        {
+            if let Some(value) = var(env, name)? {
+                *target = Some(value);
+            }
+            Ok(())
+        }
+        fn set_list(env: Env, name: &str, target: &mut Vec<String>) -> anyhow::Result<()> {
+            if let Some(values) = var::<String>(env, name)? {
+                *target = values.split(',').map(|v| v.trim().to_string()).collect();
+            }
+            Ok(())
+        }
+        fn set_flag(env: Env, name: &str, target: &mut bool) -> anyhow::Result<()> {
+            if let Some(value) = var::<String>(env, name)? {
+                *target = !matches!(value.as_str(), "0" | "false" | "no");
+            }
+            Ok(())
+        }
+
+        let server = &mut self.server;
+        set(env, "ZKRAG_BIND", &mut server.bind)?;
+        set(
+            env,
+            "ZKRAG_SHUTDOWN_TIMEOUT",
+            &mut server.shutdown_timeout_secs,
+        )?;
+        let cors = &mut self.cors;
+        set_list(env, "ZKRAG_CORS_ORIGINS", &mut cors.origins)?;
+        set_list(env, "ZKRAG_CORS_METHODS", &mut cors.methods)?;
+        set_list(env, "ZKRAG_CORS_HEADERS", &mut cors.headers)?;
+        set_flag(
+            env,
+            "ZKRAG_CORS_ALLOW_CREDENTIALS",
+            &mut cors.allow_credentials,
+        )?;
+        set(env, "ZKRAG_CORS_MAX_AGE", &mut cors.max_age_secs)?;
+        set_optional(env, "ZKRAG_TLS_CERT", &mut self.tls.cert)?;
+        set_optional(env, "ZKRAG_TLS_KEY", &mut self.tls.key)?;
+        set_optional(env, "ZKRAG_KEY_DIR", &mut self.keys.dir)?;
+        set_optional(env, "ZKRAG_VERIFYING_KEY", &mut self.keys.verifying_key)?;
+        set_optional(env, "ZKRAG_PROVING_KEY", &mut self.keys.proving_key)?;
+        set_optional(env, "ZKRAG_RECEIPT_KEY", &mut self.keys.receipt_key)?;
+        set(env, "ZKRAG_KEY_GRACE_SECS", &mut self.rotation.grace_secs)?;
+        set(env, "DATABASE_URL", &mut self.database.url)?;
+        set_optional(env, "ZKRAG_ARCHIVE_URL", &mut self.archive.url)?;
+        set_optional(env, "ZKRAG_AUTH_ISSUER", &mut self.auth.issuer)?;
+        set_optional(env, "ZKRAG_AUTH_JWKS_URL", &mut self.auth.jwks_url)?;
+        set_optional(env, "ZKRAG_AUTH_AUDIENCE", &mut self.auth.audience)?;
+        set(env, "ZKRAG_AUTH_ROLES_CLAIM", &mut self.auth.roles_claim)?;
+        let limits = &mut self.limits;
+        set(
+            env,
+            "ZKRAG_RATE_LIMIT_VERIFY",
+            &mut limits.verify_per_minute,
+        )?;
+        set(env, "ZKRAG_RATE_LIMIT_READ", &mut limits.read_per_minute)?;
+        set_flag(env, "ZKRAG_TRUST_PROXY", &mut limits.trust_proxy)?;
+        let guard = &mut self.guard;
+        set(env, "ZKRAG_MAX_BODY_BYTES", &mut guard.max_body_bytes)?;
+        set(env, "ZKRAG_REQUEST_TIMEOUT", &mut guard.timeout_secs)?;
+        set(
+            env,
+            "ZKRAG_MAX_CONCURRENT_VERIFICATIONS",
+            &mut guard.max_concurrent_verifications,
+        )?;
+        set(env, "ZKRAG_PROVER_WORKERS", &mut self.prover.workers)?;
+        set(env, "ZKRAG_PROVER_QUEUE", &mut self.prover.queue)?;
+        set(
+            env,
+            "ZKRAG_MAX_MODEL_UPLOAD_BYTES",
+            &mut self.models.max_upload_bytes,
+        )?;
+        set_optional(env, "ZKRAG_KERNEL_SOCKET", &mut self.kernel.socket)?;
+        set(env, "ZKRAG_KERNEL_TIMEOUT", &mut self.kernel.timeout_secs)?;
+        set(
+            env,
+            "ZKRAG_IDEMPOTENCY_WINDOW",
+            &mut self.idempotency.window_secs,
+        )?;
+        set(
+            env,
+            "ZKRAG_CHALLENGE_TTL_SECS",
+            &mut self.challenge.ttl_secs,
+        )?;
+        set_flag(env, "ZKRAG_REQUIRE_CHALLENGE", &mut self.challenge.required)?;
+        set_flag(
+            env,
+            "ZKRAG_REJECT_REPLAYS",
+            &mut self.nullifiers.reject_replays,
+        )?;
+        set(
+            env,
+            "ZKRAG_NULLIFIER_EPOCH_SECS",
+            &mut self.nullifiers.epoch_secs,
+        )?;
+        let retention = &mut self.retention;
+        set(
+            env,
+            "ZKRAG_RETENTION_INTERVAL",
+            &mut retention.interval_secs,
+        )?;
+        set(env, "ZKRAG_QUERY_RETENTION_SECS", &mut retention.query_secs)?;
+        set_optional(env, "ZKRAG_REDIS_URL", &mut self.cluster.redis_url)?;
+        set(env, "ZKRAG_REDIS_PREFIX", &mut self.cluster.key_prefix)?;
+        let telemetry = &mut self.telemetry;
+        set_optional(env, "ZKRAG_OTLP_ENDPOINT", &mut telemetry.otlp_endpoint)?;
+        set(env, "ZKRAG_SERVICE_NAME", &mut telemetry.service_name)?;
+        set(
+            env,
+            "ZKRAG_TRACE_SAMPLE_PERCENT",
+            &mut telemetry.sample_percent,
+        )?;
+        set_optional(
+            env,
+            "ZKRAG_MAX_PROOF_AGE",
+            &mut self.policy.max_proof_age_secs,
+        )?;
+        set_optional(env, "ZKRAG_POLICY_FILE", &mut self.policy.file)?;
+        Ok(())
+    }
+
+    fn apply_cli(&mut self, cli: &Cli) {
+        fn set<T: Clone>(flag: &Option<T>, target: &mut T) {
+            if let Some(value) = flag {
+                *target = value.clone();
+            }
+        }
+        fn set_optional<T: Clone>(flag: &Option<T>, target: &mut Option<T>) {
+            if flag.is_some() {
+                target.clone_from(flag);
+            }
+        }
+
+        set(&cli.bind, &mut self.server.bind);
+        if !cli.cors_origins.is_empty() {
+            self.cors.origins.clone_from(&cli.cors_origins);
+        }
+        set(
+            &cli.shutdown_timeout,
+            &mut self.server.shutdown_timeout_secs,
+        );
+        set_optional(&cli.tls_cert, &mut self.tls.cert);
+        set_optional(&cli.tls_key, &mut self.tls.key);
+        set_optional(&cli.key_dir, &mut self.keys.dir);
+        set_optional(&cli.verifying_key, &mut self.keys.verifying_key);
+        set_optional(&cli.proving_key, &mut self.keys.proving_key);
+        set_optional(&cli.receipt_key, &mut self.keys.receipt_key);
+        set(&cli.database_url, &mut self.database.url);
+        set_optional(&cli.auth_issuer, &mut self.auth.issuer);
+        set(&cli.rate_limit_verify, &mut self.limits.verify_per_minute);
+        set(&cli.rate_limit_read, &mut self.limits.read_per_minute);
+        self.limits.trust_proxy |= cli.trust_proxy;
+        set(&cli.prover_workers, &mut self.prover.workers);
+        set(&cli.prover_queue, &mut self.prover.queue);
+        set_optional(&cli.kernel_socket, &mut self.kernel.socket);
+        set_optional(&cli.max_proof_age, &mut self.policy.max_proof_age_secs);
+        set_optional(&cli.policy_file, &mut self.policy.file);
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.prover.workers == 0 || self.prover.queue == 0 {
+            anyhow::bail!("prover workers and queue must be positive");
+        }
+        if self.nullifiers.epoch_secs == 0 {
+            anyhow::bail!("nullifier epoch_secs must be positive");
+        }
+        self.tls()?;
+        let _ = self.cors.layer()?;
+        Ok(())
+    }
+
+    /// Certificate and key to serve HTTPS with, or `None` for plain HTTP
+    pub fn tls(&self) -> anyhow::Result<Option<TlsConfig>> {
+        match (&self.tls.cert, &self.tls.key) {
+            (Some(cert), Some(key)) => Ok(Some(TlsConfig {
+                cert: cert.clone(),
+                key: key.clone(),
+            })),
+            (None, None) => Ok(None),
+            _ => anyhow::bail!("Set both the TLS certificate and key to serve TLS"),
+        }
+    }
+
+    /// Token validation settings, or `None` if authentication is off
+    pub fn auth(&self) -> Option<AuthConfig> {
+        Some(AuthConfig {
+            issuer: self.auth.issuer.clone()?,
+            jwks_url: self.auth.jwks_url.clone(),
+            audience: self.auth.audience.clone(),
+            roles_claim: self.auth.roles_claim.clone(),
+        })
+    }
+
+    /// Directory the keys are looked up in
+    pub fn key_dir(&self) -> Option<PathBuf> {
+        self.keys.dir.clone().or_else(default_key_dir)
+    }
+
+    /// Proving key to generate proofs with, if one is configured or in the
+    /// key directory
+    pub fn proving_key(&self) -> Option<PathBuf> {
+        self.keys.proving_key.clone().or_else(|| {
+            self.key_dir()
+                .map(|dir| dir.join(PROVING_KEY_FILE))
+                .filter(|path| path.exists())
+        })
+    }
+
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_secs(self.server.shutdown_timeout_secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_layering() {
+        let path = std::env::temp_dir().join(format!("zkrag-config-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"
+            [server]
+            bind = "127.0.0.1:9000"
+
+            [cors]
+            origins = ["https://app.example.com"]
+
+            [limits]
+            verify_per_minute = 5
+            read_per_minute = 50
+
+            [auth]
+            issuer = "https://login.example.com"
+
+            [retention]
+            tenants = { alice = 60 }
+            "#,
+        )
+        .unwrap();
+
+        let vars: HashMap<&str, &str> = [
+            ("ZKRAG_CONFIG", path.to_str().unwrap()),
+            ("ZKRAG_RATE_LIMIT_READ", "60"),
+            ("ZKRAG_PROVER_QUEUE", "8"),
+            ("ZKRAG_AUTH_AUDIENCE", ""),
+            ("ZKRAG_QUERY_RETENTION_SECS", "3600"),
+        ]
+        .into_iter()
+        .collect();
+        let env = |name: &str| vars.get(name).map(|value| value.to_string());
+        let cli = Cli::parse_from(["zkrag", "--bind", "127.0.0.1:9100", "--prover-queue", "4"]);
+        let config = Config::load(&cli, &env).unwrap();
+
+        // Flags beat the environment, which beats the file
+        assert_eq!(config.server.bind, SocketAddr::from(([127, 0, 0, 1], 9100)));
+        assert_eq!(config.prover.queue, 4);
+        assert_eq!(config.limits.read_per_minute, 60);
+        assert_eq!(config.limits.verify_per_minute, 5);
+        assert_eq!(config.cors.origins, ["https://app.example.com"]);
+        let auth = config.auth().unwrap();
+        assert_eq!(auth.issuer, "https://login.example.com");
+        assert_eq!(auth.audience, None);
+        assert_eq!(config.database.url, DatabaseConfig::default().url);
+        assert!(config.cors.layer().is_ok());
+        assert_eq!(config.retention.query_secs, 3600);
+        assert_eq!(config.retention.tenants["alice"], 60);
+
+        std::fs::write(&path, "[limits]\nverify_per_minit = 5\n").unwrap();
+        let error = Config::load(&cli, &env).unwrap_err();
+        assert!(format!("{:#}", error).contains("verify_per_minit"));
+
+        std::fs::write(&path, "").unwrap();
+        let cli = Cli::parse_from(["zkrag", "--tls-cert", "cert.pem"]);
+        assert!(Config::load(&cli, &env).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
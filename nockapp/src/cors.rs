@@ -0,0 +1,216 @@
+// Cross-origin requests
+//
+// Browsers only let pages on other origins call the API when the server
+// names their origin, so the allowlist is explicit and empty by default:
+// same-origin pages such as the Swagger UI work, and nothing else does until
+// an origin is configured. `*` allows any origin and is meant for local
+// development; the server warns when it is set.
+//
+// Preflight requests are answered for the listed methods and request
+// headers. Responses expose the headers clients act on: Retry-After, the key
+// ETag and X-Zkrag-* headers, and Idempotent-Replayed. With
+// `allow_credentials`, browsers send cookies and HTTP authentication along,
+// which requires naming the origins, methods and headers rather than `*`.
+//
+// Configuration (the `[cors]` table; see config.rs):
+// - origins (ZKRAG_CORS_ORIGINS, comma-separated; --cors-origin): e.g.
+//   `https://app.example.com`, scheme and host without a path
+// - methods (ZKRAG_CORS_METHODS): default GET, POST and DELETE
+// - headers (ZKRAG_CORS_HEADERS): default Authorization, Content-Type and
+//   Idempotency-Key
+// - allow_credentials (ZKRAG_CORS_ALLOW_CREDENTIALS): default false
+// - max_age_secs (ZKRAG_CORS_MAX_AGE): how long browsers may cache a
+//   preflight answer (default 600)
+
+use anyhow::Context;
+use axum::http::header::{AUTHORIZATION, CONTENT_TYPE};
+use axum::http::{HeaderName, HeaderValue, Method};
+use serde::Deserialize;
+use std::time::Duration;
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, Any, CorsLayer};
+
+use crate::idempotency::{IDEMPOTENCY_KEY, REPLAYED};
+
+/// Response headers scripts on other origins may read
+const EXPOSED_HEADERS: [&str; 6] = [
+    "retry-after",
+    "etag",
+    "x-zkrag-key-fingerprint",
+    "x-zkrag-curve",
+    "x-zkrag-circuit-id",
+    REPLAYED,
+];
+
+/// Origins, methods and headers allowed across origins
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    /// `*` allows any origin
+    pub origins: Vec<String>,
+    pub methods: Vec<String>,
+    pub headers: Vec<String>,
+    pub allow_credentials: bool,
+    pub max_age_secs: u64,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            origins: Vec::new(),
+            methods: ["GET", "POST", "DELETE"].map(String::from).to_vec(),
+            headers: [
+                AUTHORIZATION.as_str(),
+                CONTENT_TYPE.as_str(),
+                IDEMPOTENCY_KEY,
+            ]
+            .map(String::from)
+            .to_vec(),
+            allow_credentials: false,
+            max_age_secs: 600,
+        }
+    }
+}
+
+fn is_any(values: &[String]) -> bool {
+    values.iter().any(|value| value == "*")
+}
+
+impl CorsConfig {
+    /// Whether any origin is allowed
+    pub fn allows_any_origin(&self) -> bool {
+        is_any(&self.origins)
+    }
+
+    /// Layer answering preflights and marking responses for the allowed
+    /// origins
+    pub fn layer(&self) -> anyhow::Result<CorsLayer> {
+        if self.allow_credentials
+            && (self.allows_any_origin() || is_any(&self.methods) || is_any(&self.headers))
+        {
+            anyhow::bail!("CORS credentials need explicit origins, methods and headers, not *");
+        }
+        let origins = if self.allows_any_origin() {
+            AllowOrigin::from(Any)
+        } else {
+            let origins = self
+                .origins
+                .iter()
+                .map(|origin| {
+                    // Browsers send the scheme and host only
+                    if !origin.contains("://") || origin.ends_with('/') {
+                        anyhow::bail!("Invalid CORS origin {:?}", origin);
+                    }
+                    origin
+                        .parse::<HeaderValue>()
+                        .with_context(|| format!("Invalid CORS origin {:?}", origin))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            AllowOrigin::list(origins)
+        };
+        let methods = if is_any(&self.methods) {
+            AllowMethods::from(Any)
+        } else {
+            let methods = self
+                .methods
+                .iter()
+                .map(|method| {
+                    Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+                        .with_context(|| format!("Invalid CORS method {:?}", method))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            AllowMethods::list(methods)
+        };
+        let headers = if is_any(&self.headers) {
+            AllowHeaders::from(Any)
+        } else {
+            let headers = self
+                .headers
+                .iter()
+                .map(|header| {
+                    HeaderName::try_from(header.as_str())
+                        .with_context(|| format!("Invalid CORS header {:?}", header))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            AllowHeaders::list(headers)
+        };
+        Ok(CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods(methods)
+            .allow_headers(headers)
+            .allow_credentials(self.allow_credentials)
+            .expose_headers(EXPOSED_HEADERS.map(HeaderName::from_static))
+            .max_age(Duration::from_secs(self.max_age_secs)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::header::{
+        ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS,
+        ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD,
+        ORIGIN,
+    };
+    use axum::http::Request;
+    use axum::routing::get;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_allowlist() {
+        let config = CorsConfig {
+            origins: vec!["https://app.example.com".to_string()],
+            allow_credentials: true,
+            ..CorsConfig::default()
+        };
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(config.layer().unwrap());
+        let preflight = |origin: &str| {
+            Request::builder()
+                .method(Method::OPTIONS)
+                .uri("/")
+                .header(ORIGIN, origin)
+                .header(ACCESS_CONTROL_REQUEST_METHOD, "POST")
+                .header(ACCESS_CONTROL_REQUEST_HEADERS, "authorization")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let allowed = app
+            .clone()
+            .oneshot(preflight("https://app.example.com"))
+            .await
+            .unwrap();
+        let headers = allowed.headers();
+        assert_eq!(
+            headers[ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://app.example.com"
+        );
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+        assert!(headers[ACCESS_CONTROL_ALLOW_HEADERS]
+            .to_str()
+            .unwrap()
+            .contains("idempotency-key"));
+
+        let refused = app
+            .oneshot(preflight("https://evil.example"))
+            .await
+            .unwrap();
+        assert!(!refused.headers().contains_key(ACCESS_CONTROL_ALLOW_ORIGIN));
+
+        // Nothing is allowed by default, and `*` can't carry credentials
+        assert!(!CorsConfig::default().allows_any_origin());
+        let any = CorsConfig {
+            origins: vec!["*".to_string()],
+            ..config.clone()
+        };
+        assert!(any.layer().is_err());
+        let path = CorsConfig {
+            origins: vec!["https://app.example.com/".to_string()],
+            ..config
+        };
+        assert!(path.layer().is_err());
+    }
+}
@@ -0,0 +1,284 @@
+// API errors
+//
+// Every error response has the same body, `{code, message, details}`.
+// Clients branch on `code`, which fixes the status code, and show `message`;
+// `details` carries structured context for some codes, such as the failure
+// reason of a malformed proof.
+//
+// Errors from the store, the verifier and the prover pool convert into
+// `ApiError` so handlers can use `?`. Failures on the server's side are
+// logged and reported as `internal` without their message.
+
+use axum::extract::rejection::{BytesRejection, JsonRejection, PathRejection, QueryRejection};
+use axum::extract::{FromRequest, FromRequestParts};
+use axum::http::header::{RETRY_AFTER, WWW_AUTHENTICATE};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{error, warn};
+use utoipa::ToSchema;
+use zkrag_verifier::VerifierError;
+
+use crate::kernel::KernelError;
+use crate::prover::ProveError;
+use crate::store::StoreError;
+
+/// Seconds a client refused by a full prover queue is told to wait
+const PROVER_BUSY_RETRY_AFTER: u64 = 5;
+
+/// Machine-readable error code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// The request body, path or parameters are invalid
+    InvalidRequest,
+    /// The request body is larger than the server accepts
+    PayloadTooLarge,
+    /// The proof isn't valid hex or doesn't parse
+    ProofMalformed,
+    /// The proof parsed but failed an integrity check
+    ProofRejected,
+    ModelNotRegistered,
+    NotFound,
+    Unauthorized,
+    Forbidden,
+    RateLimited,
+    ProvingDisabled,
+    ProverBusy,
+    /// Too many proofs are being checked at once
+    VerifierBusy,
+    /// The request wasn't answered in time
+    Timeout,
+    /// Signing keys for bearer tokens couldn't be fetched
+    AuthUnavailable,
+    /// The kernel process couldn't be reached
+    KernelUnavailable,
+    /// The kernel refused the request; `details` has its status and body
+    KernelRejected,
+    /// The Idempotency-Key was used with a different request
+    IdempotencyKeyReused,
+    /// A request with the same Idempotency-Key is still running
+    IdempotencyKeyInUse,
+    /// The challenge nonce is unknown, expired or spent, or a required one
+    /// is missing
+    ChallengeInvalid,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn status(self) -> StatusCode {
+        match self {
+            Self::InvalidRequest | Self::ProofMalformed => StatusCode::BAD_REQUEST,
+            Self::ProofRejected
+            | Self::ModelNotRegistered
+            | Self::KernelRejected
+            | Self::IdempotencyKeyReused
+            | Self::ChallengeInvalid => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::IdempotencyKeyInUse => StatusCode::CONFLICT,
+            Self::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::Unauthorized => StatusCode::UNAUTHORIZED,
+            Self::Forbidden => StatusCode::FORBIDDEN,
+            Self::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            Self::ProvingDisabled => StatusCode::NOT_IMPLEMENTED,
+            Self::ProverBusy
+            | Self::VerifierBusy
+            | Self::Timeout
+            | Self::AuthUnavailable
+            | Self::KernelUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            Self::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Body of every error response
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ErrorResponse {
+    pub code: ErrorCode,
+    pub message: String,
+    /// Structured context, depending on `code`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>, nullable = false)]
+    pub details: Option<Value>,
+}
+
+/// Error returned by a handler
+#[derive(Debug)]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub details: Option<Value>,
+    /// Seconds for the Retry-After header
+    pub retry_after: Option<u64>,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, message: impl ToString) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+            details: None,
+            retry_after: None,
+        }
+    }
+
+    pub fn invalid_request(message: impl ToString) -> Self {
+        Self::new(ErrorCode::InvalidRequest, message)
+    }
+
+    /// Log `error` and report an internal error
+    pub fn internal(context: &str, error: impl std::fmt::Display) -> Self {
+        error!("{}: {}", context, error);
+        Self::new(ErrorCode::Internal, context)
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    pub fn with_retry_after(mut self, seconds: u64) -> Self {
+        self.retry_after = Some(seconds);
+        self
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = self.code.status();
+        let mut response = (
+            status,
+            Json(ErrorResponse {
+                code: self.code,
+                message: self.message,
+                details: self.details,
+            }),
+        )
+            .into_response();
+        let headers = response.headers_mut();
+        if status == StatusCode::UNAUTHORIZED {
+            headers.insert(WWW_AUTHENTICATE, "Bearer".parse().unwrap());
+        }
+        if let Some(seconds) = self.retry_after {
+            headers.insert(RETRY_AFTER, seconds.into());
+        }
+        response
+    }
+}
+
+impl From<StoreError> for ApiError {
+    fn from(error: StoreError) -> Self {
+        Self::internal("Storage error", error)
+    }
+}
+
+impl From<VerifierError> for ApiError {
+    fn from(error: VerifierError) -> Self {
+        match error {
+            VerifierError::Malformed(message) => Self::new(ErrorCode::ProofMalformed, message),
+            VerifierError::Rejected(message) => Self::new(ErrorCode::ProofRejected, message),
+            error => Self::internal("Verification error", error),
+        }
+    }
+}
+
+impl From<ProveError> for ApiError {
+    fn from(error: ProveError) -> Self {
+        match error {
+            ProveError::Busy => Self::new(ErrorCode::ProverBusy, "Prover is busy; retry later")
+                .with_retry_after(PROVER_BUSY_RETRY_AFTER),
+            error => Self::internal("Proof generation error", error),
+        }
+    }
+}
+
+impl From<KernelError> for ApiError {
+    fn from(error: KernelError) -> Self {
+        match error {
+            KernelError::Io(_) | KernelError::Timeout(_) => {
+                warn!("{}", error);
+                Self::new(ErrorCode::KernelUnavailable, "Kernel is unavailable")
+            }
+            KernelError::Rejected { status, body } => {
+                // The kernel's bodies are JSON, but pass on any text
+                let body = serde_json::from_str(&body).unwrap_or(Value::String(body));
+                Self::new(ErrorCode::KernelRejected, "Kernel refused the request")
+                    .with_details(serde_json::json!({ "status": status, "body": body }))
+            }
+            error => Self::internal("Kernel error", error),
+        }
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        if rejection.status() == StatusCode::PAYLOAD_TOO_LARGE {
+            return Self::new(ErrorCode::PayloadTooLarge, "Request body is too large");
+        }
+        Self::invalid_request(rejection.body_text())
+    }
+}
+
+impl From<BytesRejection> for ApiError {
+    fn from(rejection: BytesRejection) -> Self {
+        if rejection.status() == StatusCode::PAYLOAD_TOO_LARGE {
+            return Self::new(ErrorCode::PayloadTooLarge, "Request body is too large");
+        }
+        Self::invalid_request(rejection.body_text())
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        Self::invalid_request(rejection.body_text())
+    }
+}
+
+impl From<PathRejection> for ApiError {
+    fn from(rejection: PathRejection) -> Self {
+        Self::invalid_request(rejection.body_text())
+    }
+}
+
+/// `Json` extractor whose rejections are `ApiError`s
+#[derive(FromRequest)]
+#[from_request(via(axum::Json), rejection(ApiError))]
+pub struct ApiJson<T>(pub T);
+
+/// `Query` extractor whose rejections are `ApiError`s
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(ApiError))]
+pub struct ApiQuery<T>(pub T);
+
+/// `Path` extractor whose rejections are `ApiError`s
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Path), rejection(ApiError))]
+pub struct ApiPath<T>(pub T);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::to_bytes;
+
+    #[tokio::test]
+    async fn test_error_body() {
+        let response = ApiError::from(ProveError::Busy).into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[RETRY_AFTER], "5");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({"code": "prover_busy", "message": "Prover is busy; retry later"})
+        );
+
+        let error = ApiError::from(VerifierError::Malformed("truncated".to_string()));
+        assert_eq!(error.code.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(serde_json::to_value(error.code).unwrap(), "proof_malformed");
+        let error = ApiError::from(VerifierError::Internal("disk".to_string()));
+        assert_eq!(error.code, ErrorCode::Internal);
+        assert_eq!(error.message, "Verification error");
+    }
+}
@@ -0,0 +1,459 @@
+// Live event stream
+//
+// Handlers publish registrations and verification outcomes on a broadcast
+// channel after storing them; `GET /api/v1/events/ws` upgrades to a
+// WebSocket that forwards them as JSON text messages tagged with `type`:
+// document_registered, model_registered, query_verified or query_failed.
+//
+// Query parameters narrow the stream:
+// - commitment: only events about this document commitment, ignoring case
+//   (model registrations concern no commitment and are left out)
+// - types: comma-separated event types to receive
+//
+// WebSocket events aren't replayed: a subscriber sees what happens after it
+// connects, and catches up through the REST routes. One that falls more than
+// EVENT_BUFFER events behind skips them and is sent
+// `{"type": "lagged", "missed": N}` instead.
+//
+// For clients without WebSockets, `GET /api/v1/events` streams the query
+// events (query_verified and query_failed) as Server-Sent Events, taking the
+// same parameters. Each event's id is its query id, and ids come from the
+// store in commit order, so a client reconnecting with `Last-Event-ID` is
+// first sent every query recorded since, read back from the store. The
+// store also fills in any events an SSE subscriber lagged behind on.
+//
+// Both streams end when the server shuts down; WebSocket subscribers are
+// sent a close frame with status 1001 (going away).
+
+use axum::extract::ws::{close_code, CloseFrame, Message, WebSocket};
+use axum::response::sse;
+use futures_util::stream::{self, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::warn;
+use utoipa::IntoParams;
+
+use crate::store::{QueryRecord, Store};
+
+/// Events a subscriber may fall behind by before missing some
+const EVENT_BUFFER: usize = 256;
+
+/// Queries read from the store at a time while an SSE subscriber catches up
+const CATCH_UP_BATCH: u64 = 500;
+
+/// Something that happened to the service's records
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    DocumentRegistered {
+        id: u64,
+        commitment: String,
+        owner: String,
+        /// Registry commitment after the registration
+        registry_commitment: String,
+    },
+    ModelRegistered {
+        id: u64,
+        model_hash: String,
+        model_name: String,
+    },
+    QueryVerified {
+        query: QueryRecord,
+    },
+    QueryFailed {
+        query: QueryRecord,
+    },
+}
+
+/// Type of an `Event`, as named in its `type` tag
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    DocumentRegistered,
+    ModelRegistered,
+    QueryVerified,
+    QueryFailed,
+}
+
+impl EventKind {
+    /// Name used in the `type` tag
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::DocumentRegistered => "document_registered",
+            Self::ModelRegistered => "model_registered",
+            Self::QueryVerified => "query_verified",
+            Self::QueryFailed => "query_failed",
+        }
+    }
+}
+
+impl FromStr for EventKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(serde_json::Value::String(name.to_string()))
+            .map_err(|_| format!("Unknown event type {:?}", name))
+    }
+}
+
+impl Event {
+    /// Outcome of verifying a query
+    pub fn query(query: QueryRecord) -> Self {
+        if query.verified {
+            Self::QueryVerified { query }
+        } else {
+            Self::QueryFailed { query }
+        }
+    }
+
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Self::DocumentRegistered { .. } => EventKind::DocumentRegistered,
+            Self::ModelRegistered { .. } => EventKind::ModelRegistered,
+            Self::QueryVerified { .. } => EventKind::QueryVerified,
+            Self::QueryFailed { .. } => EventKind::QueryFailed,
+        }
+    }
+
+    /// Whether the event concerns document commitment `commitment`
+    fn concerns(&self, commitment: &str) -> bool {
+        match self {
+            Self::DocumentRegistered {
+                commitment: document,
+                registry_commitment,
+                ..
+            } => {
+                document.eq_ignore_ascii_case(commitment)
+                    || registry_commitment.eq_ignore_ascii_case(commitment)
+            }
+            Self::ModelRegistered { .. } => false,
+            Self::QueryVerified { query } | Self::QueryFailed { query } => {
+                query.document_commitment.eq_ignore_ascii_case(commitment)
+            }
+        }
+    }
+}
+
+/// Query parameters of the event streams
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EventParams {
+    /// Only events about this document commitment
+    pub commitment: Option<String>,
+    /// Comma-separated event types to receive
+    pub types: Option<String>,
+}
+
+/// Which events a subscriber receives
+#[derive(Debug, Default)]
+pub struct EventFilter {
+    commitment: Option<String>,
+    /// `None` for every type
+    kinds: Option<HashSet<EventKind>>,
+}
+
+impl EventFilter {
+    pub fn from_params(params: EventParams) -> Result<Self, String> {
+        let kinds = params
+            .types
+            .filter(|types| !types.is_empty())
+            .map(|types| types.split(',').map(|name| name.trim().parse()).collect())
+            .transpose()?;
+        Ok(Self {
+            commitment: params
+                .commitment
+                .filter(|commitment| !commitment.is_empty()),
+            kinds,
+        })
+    }
+
+    fn matches(&self, event: &Event) -> bool {
+        self.kinds
+            .as_ref()
+            .is_none_or(|kinds| kinds.contains(&event.kind()))
+            && self
+                .commitment
+                .as_ref()
+                .is_none_or(|commitment| event.concerns(commitment))
+    }
+}
+
+/// Broadcast channel events are published on
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(EVENT_BUFFER).0,
+        }
+    }
+}
+
+impl EventBus {
+    /// Send `event` to current subscribers, if any
+    pub fn publish(&self, event: Event) {
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+}
+
+/// Forward events matching `filter` to `socket` until either side closes
+pub async fn stream(
+    mut socket: WebSocket,
+    mut events: broadcast::Receiver<Event>,
+    filter: EventFilter,
+    shutdown: impl Future<Output = ()>,
+) {
+    tokio::pin!(shutdown);
+    loop {
+        let message = tokio::select! {
+            () = &mut shutdown => {
+                let close = CloseFrame {
+                    code: close_code::AWAY,
+                    reason: "server shutting down".into(),
+                };
+                let _ = socket.send(Message::Close(Some(close))).await;
+                break;
+            }
+            event = events.recv() => match event {
+                Ok(event) if filter.matches(&event) => match serde_json::to_string(&event) {
+                    Ok(json) => json,
+                    Err(_) => continue,
+                },
+                Ok(_) => continue,
+                Err(RecvError::Lagged(missed)) => {
+                    serde_json::json!({ "type": "lagged", "missed": missed }).to_string()
+                }
+                Err(RecvError::Closed) => break,
+            },
+            // Nothing is expected from the client; pings are answered for us
+            received = socket.recv() => match received {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+        };
+        if socket.send(Message::Text(message)).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Position of an SSE subscriber in the sequence of recorded queries
+struct QueryCursor {
+    store: Arc<dyn Store>,
+    events: broadcast::Receiver<Event>,
+    filter: EventFilter,
+    /// Id of the last query passed, whether or not it matched the filter
+    last_id: Option<u64>,
+    backlog: VecDeque<QueryRecord>,
+    /// Whether the store may hold queries after `last_id` not in `backlog`
+    catching_up: bool,
+}
+
+impl QueryCursor {
+    /// Next matching query event and its id, or `None` to end the stream
+    async fn next(&mut self) -> Option<(u64, Event)> {
+        loop {
+            if let Some(query) = self.backlog.pop_front() {
+                let id = query.id;
+                self.last_id = Some(id);
+                let event = Event::query(query);
+                if self.filter.matches(&event) {
+                    return Some((id, event));
+                }
+                continue;
+            }
+
+            if self.catching_up {
+                let after = self.last_id.unwrap_or(0);
+                match self.store.queries_after(after, CATCH_UP_BATCH).await {
+                    Ok(queries) if queries.is_empty() => self.catching_up = false,
+                    Ok(queries) => self.backlog.extend(queries),
+                    Err(e) => {
+                        // The client resumes from its last event on reconnect
+                        warn!("Failed to read queries for event stream: {}", e);
+                        return None;
+                    }
+                }
+                continue;
+            }
+
+            match self.events.recv().await {
+                Ok(Event::QueryVerified { query } | Event::QueryFailed { query }) => {
+                    match self.last_id {
+                        Some(last) if query.id <= last => {}
+                        // Publishers may race; take anything else from the
+                        // store so events stay in id order
+                        Some(last) if query.id != last + 1 => self.catching_up = true,
+                        _ => self.backlog.push_back(query),
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(_)) => self.catching_up = self.last_id.is_some(),
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+/// Query events matching `filter` and their ids, in id order, starting
+/// after query `last_id` if given and otherwise with the next one recorded.
+/// `events` must be subscribed before calling, so nothing falls between the
+/// store and the channel.
+pub fn query_events(
+    store: Arc<dyn Store>,
+    events: broadcast::Receiver<Event>,
+    filter: EventFilter,
+    last_id: Option<u64>,
+) -> impl Stream<Item = (u64, Event)> {
+    let cursor = QueryCursor {
+        store,
+        events,
+        filter,
+        last_id,
+        backlog: VecDeque::new(),
+        catching_up: last_id.is_some(),
+    };
+    stream::unfold(cursor, |mut cursor| async move {
+        let item = cursor.next().await?;
+        Some((item, cursor))
+    })
+}
+
+/// `query_events` as SSE events, until `shutdown` resolves
+pub fn query_stream(
+    store: Arc<dyn Store>,
+    events: broadcast::Receiver<Event>,
+    filter: EventFilter,
+    last_id: Option<u64>,
+    shutdown: impl Future<Output = ()>,
+) -> impl Stream<Item = Result<sse::Event, axum::Error>> {
+    query_events(store, events, filter, last_id)
+        .map(|(id, event)| {
+            sse::Event::default()
+                .id(id.to_string())
+                .event(event.kind().name())
+                .json_data(&event)
+        })
+        .take_until(shutdown)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zkrag_verifier::{ProofEnvelope, PublicInputs};
+
+    #[test]
+    fn test_event_filter() {
+        let query = QueryRecord {
+            id: 7,
+            proof_digest: "ab".repeat(32),
+            document_commitment: "CD".repeat(32),
+            model_hash: "ef".repeat(32),
+            timestamp: 1,
+            verified: true,
+            reason: None,
+            verified_at: 2,
+        };
+        let verified = Event::query(query.clone());
+        let model = Event::ModelRegistered {
+            id: 1,
+            model_hash: "ef".repeat(32),
+            model_name: "m".to_string(),
+        };
+        let json = serde_json::to_value(&verified).unwrap();
+        assert_eq!(json["type"], "query_verified");
+        assert_eq!(json["query"]["id"], 7);
+
+        let filter = EventFilter::from_params(EventParams {
+            commitment: Some("cd".repeat(32)),
+            types: Some("query_verified, query_failed".to_string()),
+        })
+        .unwrap();
+        assert!(filter.matches(&verified));
+        assert!(!filter.matches(&model));
+        assert!(!filter.matches(&Event::query(QueryRecord {
+            document_commitment: "00".repeat(32),
+            ..query
+        })));
+        assert!(EventFilter::default().matches(&model));
+
+        let unknown = EventFilter::from_params(EventParams {
+            commitment: None,
+            types: Some("query_verified,nope".to_string()),
+        });
+        assert!(unknown.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_query_cursor_resumes_in_order() {
+        let store = Arc::new(crate::store::test_store().await);
+        let record = |verified| QueryRecord {
+            id: 0,
+            proof_digest: "ab".repeat(32),
+            document_commitment: "cd".repeat(32),
+            model_hash: "ef".repeat(32),
+            timestamp: 1,
+            verified,
+            reason: None,
+            verified_at: 2,
+        };
+        let proof = ProofEnvelope::new(
+            vec![7; 128],
+            PublicInputs {
+                document_commitment: "cd".repeat(32),
+                model_hash: "ef".repeat(32),
+                timestamp: 1,
+                nonce: None,
+            },
+        );
+        let mut ids = Vec::new();
+        for verified in [true, false, true] {
+            ids.push(
+                store
+                    .record_query(&record(verified), &proof, None, None)
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        let bus = EventBus::default();
+        let mut cursor = QueryCursor {
+            store: store.clone(),
+            events: bus.subscribe(),
+            filter: EventFilter::default(),
+            last_id: Some(ids[0]),
+            backlog: VecDeque::new(),
+            catching_up: true,
+        };
+        // Resuming replays what was recorded after the last id
+        let (id, event) = cursor.next().await.unwrap();
+        assert_eq!((id, event.kind()), (ids[1], EventKind::QueryFailed));
+        assert_eq!(cursor.next().await.unwrap().0, ids[2]);
+
+        // A live event arriving ahead of an earlier one is read from the store
+        let fourth = store
+            .record_query(&record(true), &proof, None, None)
+            .await
+            .unwrap();
+        let fifth = store
+            .record_query(&record(true), &proof, None, None)
+            .await
+            .unwrap();
+        bus.publish(Event::query(QueryRecord {
+            id: fifth,
+            ..record(true)
+        }));
+        assert_eq!(cursor.next().await.unwrap().0, fourth);
+        assert_eq!(cursor.next().await.unwrap().0, fifth);
+    }
+}
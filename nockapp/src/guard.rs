@@ -0,0 +1,212 @@
+// Request guards
+//
+// A malformed or hostile request mustn't be able to take the service down,
+// so every API route is guarded:
+// - Bodies are read up to a cap, and a larger one is refused with 413
+//   `payload_too_large` without buffering the rest. A verification request
+//   is a proof of at most MAX_PROOF_BYTES and its public inputs, so
+//   `/api/v1/query/verify` takes at most VERIFY_BODY_BYTES whatever the cap.
+// - A request not answered within the timeout gets 503 `timeout`. Proof
+//   checks and generations already under way run to completion; only the
+//   wait for them is cut short.
+// - At most `max_concurrent_verifications` proofs are checked at once.
+//   Verifications beyond that are refused with 503 `verifier_busy` and
+//   Retry-After rather than queued, so a burst can't starve the other
+//   routes of threads. Proof generation is bounded by the prover's own
+//   queue; see prover.rs.
+//
+// Configuration (the `[guard]` table, see config.rs; 0 turns a limit off):
+// - max_body_bytes (ZKRAG_MAX_BODY_BYTES): largest request body (default
+//   1 MiB)
+// - timeout_secs (ZKRAG_REQUEST_TIMEOUT): time to answer a request
+//   (default 30)
+// - max_concurrent_verifications (ZKRAG_MAX_CONCURRENT_VERIFICATIONS):
+//   proofs checked at once (default 32)
+
+use axum::extract::{DefaultBodyLimit, Request, State};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use zkrag_verifier::MAX_PROOF_BYTES;
+
+use crate::error::{ApiError, ErrorCode};
+
+/// Largest verification request body: the proof as hex, with room for the
+/// public inputs and formatting
+pub const VERIFY_BODY_BYTES: usize = 2 * MAX_PROOF_BYTES + 2048;
+
+/// Seconds a client refused by a full verifier is told to wait
+const VERIFIER_BUSY_RETRY_AFTER: u64 = 1;
+
+/// Body, time and concurrency limits
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GuardConfig {
+    pub max_body_bytes: usize,
+    pub timeout_secs: u64,
+    pub max_concurrent_verifications: usize,
+}
+
+impl Default for GuardConfig {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: 1 << 20,
+            timeout_secs: 30,
+            max_concurrent_verifications: 32,
+        }
+    }
+}
+
+impl GuardConfig {
+    /// Largest body of any request
+    pub fn body_limit(&self) -> DefaultBodyLimit {
+        match self.max_body_bytes {
+            0 => DefaultBodyLimit::disable(),
+            max => DefaultBodyLimit::max(max),
+        }
+    }
+
+    /// Largest body of a verification request
+    pub fn verify_body_limit(&self) -> DefaultBodyLimit {
+        match self.max_body_bytes {
+            0 => DefaultBodyLimit::max(VERIFY_BODY_BYTES),
+            max => DefaultBodyLimit::max(max.min(VERIFY_BODY_BYTES)),
+        }
+    }
+
+    /// Permits for proof checks, or `None` when they aren't limited
+    pub fn verifications(&self) -> Option<Arc<Semaphore>> {
+        match self.max_concurrent_verifications {
+            0 => None,
+            permits => Some(Arc::new(Semaphore::new(permits))),
+        }
+    }
+}
+
+/// Middleware answering requests that outlive `timeout` with an error
+async fn time_limit(State(timeout): State<Duration>, request: Request, next: Next) -> Response {
+    match tokio::time::timeout(timeout, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => ApiError::new(
+            ErrorCode::Timeout,
+            format!("Request took longer than {}s", timeout.as_secs()),
+        )
+        .into_response(),
+    }
+}
+
+/// Middleware holding one of `permits` for the length of the request
+pub async fn concurrency_limit(
+    State(permits): State<Option<Arc<Semaphore>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(permits) = permits else {
+        return next.run(request).await;
+    };
+    let Ok(_permit) = permits.try_acquire_owned() else {
+        return ApiError::new(
+            ErrorCode::VerifierBusy,
+            "Too many verifications in progress; retry later",
+        )
+        .with_retry_after(VERIFIER_BUSY_RETRY_AFTER)
+        .into_response();
+    };
+    next.run(request).await
+}
+
+/// Apply the body limit and timeout to every route in `router`. A route's
+/// own `DefaultBodyLimit` takes precedence over the router's.
+pub fn guarded<S>(router: Router<S>, config: &GuardConfig) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let router = router.layer(config.body_limit());
+    match config.timeout_secs {
+        0 => router,
+        secs => router.route_layer(middleware::from_fn_with_state(
+            Duration::from_secs(secs),
+            time_limit,
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use axum::http::header::RETRY_AFTER;
+    use axum::http::StatusCode;
+    use axum::routing::post;
+    use serde_json::Value;
+    use tokio::sync::Notify;
+    use tower::ServiceExt;
+
+    use crate::error::ApiJson;
+
+    async fn code(response: Response) -> Value {
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice::<Value>(&body).unwrap()["code"].clone()
+    }
+
+    #[tokio::test]
+    async fn test_body_time_and_concurrency_limits() {
+        let config = GuardConfig {
+            max_body_bytes: 64,
+            timeout_secs: 1,
+            max_concurrent_verifications: 1,
+        };
+        let (started, release) = (Arc::new(Notify::new()), Arc::new(Notify::new()));
+        let (start, held) = (started.clone(), release.clone());
+        let verify = post(move |ApiJson(_): ApiJson<Value>| async move {
+            start.notify_one();
+            held.notified().await;
+            "verified"
+        })
+        .layer(middleware::from_fn_with_state(
+            config.verifications(),
+            concurrency_limit,
+        ));
+        let app = guarded(
+            Router::new().route("/verify", verify).route(
+                "/echo",
+                post(|ApiJson(body): ApiJson<Value>| async move { body.to_string() }),
+            ),
+            &config,
+        );
+        let send = |uri: &'static str, body: String| {
+            let app = app.clone();
+            async move {
+                let request = Request::post(uri)
+                    .header("content-type", "application/json")
+                    .body(Body::from(body))
+                    .unwrap();
+                app.oneshot(request).await.unwrap()
+            }
+        };
+
+        assert_eq!(send("/echo", "[1]".to_string()).await.status(), 200);
+        let large = send("/echo", format!("\"{}\"", "a".repeat(64))).await;
+        assert_eq!(large.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(code(large).await, "payload_too_large");
+
+        // The first verification holds the only permit until it times out
+        let first = tokio::spawn(send("/verify", "{}".to_string()));
+        started.notified().await;
+        let busy = send("/verify", "{}".to_string()).await;
+        assert_eq!(busy.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(busy.headers()[RETRY_AFTER], "1");
+        assert_eq!(code(busy).await, "verifier_busy");
+        let timed_out = first.await.unwrap();
+        assert_eq!(timed_out.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(code(timed_out).await, "timeout");
+
+        // Its permit is back once it has been answered
+        release.notify_one();
+        assert_eq!(send("/verify", "{}".to_string()).await.status(), 200);
+    }
+}
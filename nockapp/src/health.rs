@@ -0,0 +1,215 @@
+// Component health
+//
+// `GET /health` checks every component verification depends on and reports
+// each one, answering 503 when any is failing:
+// - verifying_key: the document query circuit's current key deserialized
+//   and was prepared, serializes back to its fingerprint, and its pairing
+//   precomputation and input count match (see `RegisteredKey::self_check`).
+//   A prepared key never changes, so each key version is checked once and
+//   the outcome reported from then on.
+// - database: answers a ping, with how long it took
+// - kernel, when one is configured: answers a peek
+//
+// `/livez` stays a bare "OK" for liveness probes, which shouldn't restart
+// the process over a database outage, and `/readyz` (see shutdown.rs)
+// decides whether the load balancer sends traffic here.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+use zkrag_verifier::RegisteredKey;
+
+/// How a component is doing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[schema(as = HealthStatus)]
+pub enum Status {
+    Ok,
+    Failing,
+}
+
+/// Check of the current verifying key
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct KeyHealth {
+    pub status: Status,
+    /// Id of the key checked; absent when no key is current
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+    /// Pairing curve, e.g. `bn254`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub curve: Option<String>,
+    /// Why the check failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Check of a service the server calls
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ComponentHealth {
+    pub status: Status,
+    /// How long the check took, when it was answered
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    /// Why the check failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ComponentHealth {
+    /// Run `check`, failing it if it errs or takes longer than `timeout`
+    pub async fn probe<T, E: Display>(
+        timeout: Duration,
+        check: impl Future<Output = Result<T, E>>,
+    ) -> Self {
+        let started = Instant::now();
+        let (status, error) = match tokio::time::timeout(timeout, check).await {
+            Ok(Ok(_)) => (Status::Ok, None),
+            Ok(Err(e)) => (Status::Failing, Some(e.to_string())),
+            Err(_) => (
+                Status::Failing,
+                Some(format!("No answer within {}s", timeout.as_secs())),
+            ),
+        };
+        Self {
+            status,
+            latency_ms: Some(started.elapsed().as_millis() as u64).filter(|_| error.is_none()),
+            error,
+        }
+    }
+}
+
+/// Components checked
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Components {
+    pub verifying_key: KeyHealth,
+    pub database: ComponentHealth,
+    /// Absent when no kernel is configured
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kernel: Option<ComponentHealth>,
+}
+
+/// Body of `GET /health`
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Health {
+    /// `failing` when any component is
+    pub status: Status,
+    pub components: Components,
+}
+
+impl Health {
+    pub fn new(components: Components) -> Self {
+        let failing = components.verifying_key.status == Status::Failing
+            || components.database.status == Status::Failing
+            || components
+                .kernel
+                .as_ref()
+                .is_some_and(|kernel| kernel.status == Status::Failing);
+        Self {
+            status: if failing { Status::Failing } else { Status::Ok },
+            components,
+        }
+    }
+}
+
+/// Outcomes of key self-checks, by key id
+#[derive(Default)]
+pub struct KeyChecks {
+    outcomes: Mutex<HashMap<String, Result<(), String>>>,
+}
+
+impl KeyChecks {
+    /// Check `key`, the circuit's current key if there is one
+    pub fn check(&self, key: Option<&RegisteredKey>) -> KeyHealth {
+        let Some(key) = key else {
+            return KeyHealth {
+                status: Status::Failing,
+                fingerprint: None,
+                curve: None,
+                error: Some("No verifying key is loaded".to_string()),
+            };
+        };
+        let outcome = self
+            .outcomes
+            .lock()
+            .unwrap()
+            .entry(key.key_id.clone())
+            .or_insert_with(|| key.self_check().map_err(|e| e.to_string()))
+            .clone();
+        KeyHealth {
+            status: if outcome.is_ok() {
+                Status::Ok
+            } else {
+                Status::Failing
+            },
+            fingerprint: Some(key.key_id.clone()),
+            curve: Some(key.key.curve().to_string()),
+            error: outcome.err(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::{Bn254, Fr};
+    use ark_groth16::Groth16;
+    use ark_serialize::CanonicalSerialize;
+    use zkrag_circuits::DocumentQueryCircuit;
+    use zkrag_verifier::{KeyRegistry, DOCUMENT_QUERY_CIRCUIT_ID};
+
+    #[tokio::test]
+    async fn test_component_health() {
+        let circuit = DocumentQueryCircuit::new(
+            vec![Fr::from(1u64)],
+            vec![],
+            vec![],
+            Fr::from(2u64),
+            Fr::from(3u64),
+            Fr::from(4u64),
+        );
+        let mut rng = ark_std::test_rng();
+        let pk =
+            Groth16::<Bn254>::generate_random_parameters_with_reduction(circuit, &mut rng).unwrap();
+        let mut vk = Vec::new();
+        pk.vk.serialize_compressed(&mut vk).unwrap();
+        let mut keys = KeyRegistry::new();
+        let key_id = keys.insert(DOCUMENT_QUERY_CIRCUIT_ID, &vk).unwrap();
+
+        let checks = KeyChecks::default();
+        let key = checks.check(keys.current(DOCUMENT_QUERY_CIRCUIT_ID));
+        assert_eq!(key.status, Status::Ok);
+        assert_eq!(key.fingerprint, Some(key_id));
+        assert_eq!(checks.check(None).status, Status::Failing);
+
+        // A key that no longer matches its fingerprint fails
+        let mut tampered = keys.current(DOCUMENT_QUERY_CIRCUIT_ID).unwrap().clone();
+        tampered.key_id = "00".repeat(32);
+        let key = checks.check(Some(&tampered));
+        assert_eq!(key.status, Status::Failing);
+        assert!(key.error.unwrap().contains("fingerprint"));
+
+        let database =
+            ComponentHealth::probe(Duration::from_secs(1), async { Ok::<_, String>(()) }).await;
+        assert!(database.latency_ms.is_some());
+        let kernel = ComponentHealth::probe(Duration::from_millis(10), async {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            Ok::<_, String>(())
+        })
+        .await;
+        assert_eq!(kernel.status, Status::Failing);
+
+        let health = Health::new(Components {
+            verifying_key: checks.check(keys.current(DOCUMENT_QUERY_CIRCUIT_ID)),
+            database,
+            kernel: Some(kernel),
+        });
+        assert_eq!(health.status, Status::Failing);
+        let body = serde_json::to_value(&health).unwrap();
+        assert_eq!(body["components"]["verifying_key"]["status"], "ok");
+        assert_eq!(body["components"]["kernel"]["status"], "failing");
+    }
+}
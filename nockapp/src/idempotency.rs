@@ -0,0 +1,292 @@
+// Idempotency keys
+//
+// Clients that time out waiting for a registration or verification can't
+// tell whether it took effect. Sending the same `Idempotency-Key` header on
+// the retry makes it safe: the document registration and upload, model
+// registration, webhook registration and query verification POSTs run once
+// per key, and a repeat is answered with the first response, marked
+// `Idempotent-Replayed: true`.
+//
+// Keys are per caller (the token's subject; one shared scope when
+// authentication is off) and any string of 1 to 255 visible ASCII
+// characters, e.g. a UUID. A key names one request: reusing it with another
+// method, path, query or body is refused with `idempotency_key_reused`, and
+// while its first request is running a repeat is refused with
+// `idempotency_key_in_use`. Only successful responses are kept; after an
+// error the key is freed and the retry runs the request afresh. A request
+// runs to completion even if its client hangs up, so the retry finds its
+// response. Requests that fail authentication don't claim their key.
+//
+// Configuration (the `[idempotency]` table; see config.rs):
+// - window_secs (ZKRAG_IDEMPOTENCY_WINDOW): how long a key is remembered
+//   (default 86400, a day)
+
+use axum::body::{to_bytes, Body};
+use axum::extract::{FromRequestParts, Request, State};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{HeaderName, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::auth::{Auditor, Auth};
+use crate::error::{ApiError, ErrorCode};
+use crate::store::{IdempotencyClaim, Store, StoredResponse};
+use crate::{unix_now, SharedState};
+
+/// Request header carrying the key
+pub const IDEMPOTENCY_KEY: &str = "idempotency-key";
+
+/// Response header marking a replayed response
+pub const REPLAYED: &str = "idempotent-replayed";
+
+/// Largest key accepted
+const MAX_KEY_LEN: usize = 255;
+
+/// How long keys are remembered
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IdempotencyConfig {
+    pub window_secs: u64,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self { window_secs: 86400 }
+    }
+}
+
+/// Middleware running requests that carry an Idempotency-Key once per key
+pub async fn idempotent(
+    State(state): State<SharedState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(key) = request.headers().get(IDEMPOTENCY_KEY) else {
+        return next.run(request).await;
+    };
+    let key = match key.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LEN => key.to_string(),
+        _ => {
+            return ApiError::invalid_request(format!(
+                "Idempotency-Key must be 1 to {} visible ASCII characters",
+                MAX_KEY_LEN
+            ))
+            .into_response()
+        }
+    };
+    let (mut parts, body) = request.into_parts();
+    // The handler refuses the request itself
+    let scope = match Auth::<Auditor>::from_request_parts(&mut parts, &state).await {
+        Ok(auth) => auth.subject().to_string(),
+        Err(_) => return next.run(Request::from_parts(parts, body)).await,
+    };
+    let request = Request::from_parts(parts, body);
+    let now = unix_now();
+    let expired = now.saturating_sub(state.idempotency.window_secs);
+    // Buffered to digest it, up to the size the handlers would read
+    let max_body = match state.guard.max_body_bytes {
+        0 => usize::MAX,
+        max => max,
+    };
+    run_once(
+        state.store.clone(),
+        &scope,
+        &key,
+        (now, expired),
+        max_body,
+        request,
+        |request| next.run(request),
+    )
+    .await
+}
+
+/// Run `request` through `run` unless `key` already has a response in
+/// `scope`. Keys claimed before `expired` are forgotten.
+async fn run_once<F, Fut>(
+    store: Arc<dyn Store>,
+    scope: &str,
+    key: &str,
+    (now, expired): (u64, u64),
+    max_body: usize,
+    request: Request,
+    run: F,
+) -> Response
+where
+    F: FnOnce(Request) -> Fut,
+    Fut: Future<Output = Response> + Send + 'static,
+{
+    let (parts, body) = request.into_parts();
+    let Ok(body) = to_bytes(body, max_body).await else {
+        return ApiError::new(ErrorCode::PayloadTooLarge, "Request body is too large")
+            .into_response();
+    };
+    let mut digest = Sha256::new();
+    digest.update(parts.method.as_str());
+    digest.update(b" ");
+    let target = parts
+        .uri
+        .path_and_query()
+        .map_or("", |target| target.as_str());
+    digest.update(target);
+    digest.update(b"\n");
+    digest.update(&body);
+    let request_digest = hex::encode(digest.finalize());
+
+    let claim = store
+        .claim_idempotency_key(scope, key, &request_digest, now, expired)
+        .await;
+    match claim {
+        Err(e) => return ApiError::from(e).into_response(),
+        Ok(IdempotencyClaim::Claimed) => {}
+        Ok(IdempotencyClaim::InUse) => {
+            return ApiError::new(
+                ErrorCode::IdempotencyKeyInUse,
+                "A request with this Idempotency-Key is still running",
+            )
+            .into_response()
+        }
+        Ok(IdempotencyClaim::Reused) => {
+            return ApiError::new(
+                ErrorCode::IdempotencyKeyReused,
+                "Idempotency-Key was already used with a different request",
+            )
+            .into_response()
+        }
+        Ok(IdempotencyClaim::Completed(stored)) => {
+            let status = StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK);
+            return (
+                status,
+                [
+                    (CONTENT_TYPE, stored.content_type),
+                    (HeaderName::from_static(REPLAYED), "true".to_string()),
+                ],
+                stored.body,
+            )
+                .into_response();
+        }
+    }
+
+    // Finish in a task of its own so a client hanging up doesn't leave the
+    // key claimed by a request whose outcome was never kept
+    let response = run(Request::from_parts(parts, Body::from(body)));
+    let (scope, key) = (scope.to_string(), key.to_string());
+    let task = tokio::spawn(async move {
+        let response = response.await;
+        if !response.status().is_success() {
+            if let Err(e) = store.release_idempotency_key(&scope, &key).await {
+                warn!("Failed to release idempotency key: {}", e);
+            }
+            return response;
+        }
+        let (parts, body) = response.into_parts();
+        let body = match to_bytes(body, usize::MAX).await {
+            Ok(body) => body,
+            Err(e) => return ApiError::internal("Reading response", e).into_response(),
+        };
+        let content_type = parts
+            .headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("application/json");
+        let stored = StoredResponse {
+            status: parts.status.as_u16(),
+            content_type: content_type.to_string(),
+            body: body.to_vec(),
+        };
+        if let Err(e) = store.complete_idempotency_key(&scope, &key, &stored).await {
+            warn!("Failed to keep idempotent response: {}", e);
+        }
+        Response::from_parts(parts, Body::from(body))
+    });
+    task.await
+        .unwrap_or_else(|e| ApiError::internal("Request failed", e).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::test_store;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[tokio::test]
+    async fn test_runs_once_per_key() {
+        let store: Arc<dyn Store> = Arc::new(test_store().await);
+        let runs = Arc::new(AtomicU32::new(0));
+        let send = |body: &'static str, key: &'static str, status: StatusCode| {
+            let (store, runs) = (store.clone(), runs.clone());
+            async move {
+                let request = Request::post("/api/v1/model/register")
+                    .body(Body::from(body))
+                    .unwrap();
+                let response = run_once(
+                    store,
+                    "alice",
+                    key,
+                    (10, 0),
+                    1024,
+                    request,
+                    |_| async move {
+                        let id = runs.fetch_add(1, Ordering::SeqCst) + 1;
+                        (status, format!(r#"{{"id":{}}}"#, id)).into_response()
+                    },
+                )
+                .await;
+                let replayed = response.headers().contains_key(REPLAYED);
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, replayed, String::from_utf8(body.to_vec()).unwrap())
+            }
+        };
+
+        let first = send("{}", "k1", StatusCode::CREATED).await;
+        assert_eq!(
+            first,
+            (StatusCode::CREATED, false, r#"{"id":1}"#.to_string())
+        );
+        let retry = send("{}", "k1", StatusCode::CREATED).await;
+        assert_eq!(
+            retry,
+            (StatusCode::CREATED, true, r#"{"id":1}"#.to_string())
+        );
+        let (status, _, body) = send("{\"other\":1}", "k1", StatusCode::CREATED).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(body.contains("idempotency_key_reused"));
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        // Failures free the key for the retry
+        let failed = send("{}", "k2", StatusCode::SERVICE_UNAVAILABLE).await;
+        assert_eq!(failed.0, StatusCode::SERVICE_UNAVAILABLE);
+        let retry = send("{}", "k2", StatusCode::CREATED).await;
+        assert_eq!(
+            retry,
+            (StatusCode::CREATED, false, r#"{"id":3}"#.to_string())
+        );
+
+        // Another caller's keys are their own
+        let claim = store
+            .claim_idempotency_key("bob", "k1", "digest", 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(claim, IdempotencyClaim::Claimed);
+        assert_eq!(
+            store
+                .claim_idempotency_key("bob", "k1", "digest", 10, 0)
+                .await
+                .unwrap(),
+            IdempotencyClaim::InUse
+        );
+        // Keys outlive their window only until the next claim
+        assert_eq!(
+            store
+                .claim_idempotency_key("bob", "k1", "other", 20, 15)
+                .await
+                .unwrap(),
+            IdempotencyClaim::Claimed
+        );
+    }
+}
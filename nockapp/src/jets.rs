@@ -0,0 +1,234 @@
+// Nock jets
+//
+// The Hoon kernel's `++verify-groth16` gate (hoon/zkrag.hoon) is hinted
+// `~/  %verify-groth16`; a NockApp runtime that finds the jet below under
+// that hint runs it in place of the gate. The pairing check goes through
+// `zkrag_verifier::curve::PreparedKey`, the code `QueryVerifier` runs, so a
+// proof the kernel accepts is one the HTTP verifier accepts and vice versa.
+// The gate has no Hoon body to fall back to: an unjetted kernel crashes
+// rather than guess.
+//
+// The kernel's `%verify-query` arm calls the gate on every verified query
+// the driver pokes, with the verifying key that checked it, so the kernel
+// keeps only queries it checked itself. The kernel runtime registers `JETS`
+// in its hot state when it boots; the runtime isn't linked into this
+// binary, so the HTTP driver itself doesn't call them.
+//
+// Sample of `++verify-groth16`, with byte strings as `octs` so trailing zero
+// bytes survive, and the challenge nonce the proof is bound to, if any:
+//
+//     [vk=octs proof=octs commitment=@t model-hash=@t timestamp=@ud nonce=(unit @t)]
+//
+// The product is `%.y` if the proof verifies and `%.n` if it doesn't parse
+// or fails the pairing check. A sample that isn't of that shape, a key or
+// proof longer than the verifier takes (`MAX_KEY_BYTES`, `MAX_PROOF_BYTES`),
+// or a verifying key that doesn't load, crashes the computation like a
+// failed assertion in Hoon would.
+
+use zkrag_verifier::curve::{Curve, PreparedKey};
+use zkrag_verifier::keys::MAX_KEY_BYTES;
+use zkrag_verifier::{ProofEncoding, PublicInputs, MAX_PROOF_BYTES};
+
+use crate::noun::Noun;
+
+/// `%.y`
+const YES: u8 = 0;
+/// `%.n`
+const NO: u8 = 1;
+
+/// Why a jet produced no product
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum JetError {
+    /// The computation crashes, as it would have in Hoon
+    #[error("deterministic crash: {0}")]
+    Deterministic(String),
+}
+
+/// Jet over a gate's sample
+pub type Jet = fn(&Noun) -> Result<Noun, JetError>;
+
+/// Jets by their hint path, for the kernel runtime's hot state
+#[allow(dead_code)] // Registered by the runtime, not this binary
+pub const JETS: &[(&[&str], Jet)] = &[(&["zkrag", "verify-groth16"], verify_groth16)];
+
+fn crash(message: &str) -> JetError {
+    JetError::Deterministic(message.to_string())
+}
+
+/// Bytes of an `octs`, `[p=@ud q=@]`, of at most `max` bytes
+fn octs(noun: &Noun, max: usize) -> Result<Vec<u8>, JetError> {
+    let (len, data) = noun.as_cell().ok_or_else(|| crash("octs is an atom"))?;
+    let len = len
+        .as_u64()
+        .and_then(|len| usize::try_from(len).ok())
+        .filter(|&len| len <= max)
+        .ok_or_else(|| crash("octs length out of range"))?;
+    let Noun::Atom(bytes) = data else {
+        return Err(crash("octs data is a cell"));
+    };
+    if bytes.len() > len {
+        return Err(crash("octs data is longer than its length"));
+    }
+    let mut bytes = bytes.clone();
+    bytes.resize(len, 0);
+    Ok(bytes)
+}
+
+/// Cord of a `(unit @t)`
+fn unit_cord(noun: &Noun) -> Option<Option<String>> {
+    match noun.as_cell() {
+        None => (*noun == Noun::null()).then_some(None),
+        Some((head, cord)) if *head == Noun::null() => cord.as_cord().map(Some),
+        Some(_) => None,
+    }
+}
+
+/// Jet of `++verify-groth16`
+pub fn verify_groth16(sample: &Noun) -> Result<Noun, JetError> {
+    let shape = || crash("sample is not [vk proof commitment model-hash timestamp nonce]");
+    let (vk, rest) = sample.as_cell().ok_or_else(shape)?;
+    let (proof, rest) = rest.as_cell().ok_or_else(shape)?;
+    let (commitment, rest) = rest.as_cell().ok_or_else(shape)?;
+    let (model_hash, rest) = rest.as_cell().ok_or_else(shape)?;
+    let (timestamp, nonce) = rest.as_cell().ok_or_else(shape)?;
+
+    let public_inputs = PublicInputs {
+        document_commitment: commitment.as_cord().ok_or_else(shape)?,
+        model_hash: model_hash.as_cord().ok_or_else(shape)?,
+        timestamp: timestamp
+            .as_u64()
+            .ok_or_else(|| crash("timestamp out of range"))?,
+        nonce: unit_cord(nonce).ok_or_else(shape)?,
+    };
+    let key = PreparedKey::from_bytes(Curve::Bn254, &octs(vk, MAX_KEY_BYTES as usize)?)
+        .map_err(|e| JetError::Deterministic(format!("verifying key: {}", e)))?;
+    let verified = key
+        .verify(
+            &octs(proof, MAX_PROOF_BYTES)?,
+            ProofEncoding::Auto,
+            &public_inputs,
+        )
+        .is_ok();
+    Ok(Noun::atom(&[if verified { YES } else { NO }]))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use ark_bn254::{Bn254, Fr};
+    use ark_groth16::Groth16;
+    use ark_serialize::CanonicalSerialize;
+    use zkrag_circuits::DocumentQueryCircuit;
+    use zkrag_verifier::QueryVerifier;
+
+    pub(crate) fn octs_noun(bytes: &[u8]) -> Noun {
+        Noun::cell(
+            Noun::atom(&(bytes.len() as u64).to_le_bytes()),
+            Noun::atom(bytes),
+        )
+    }
+
+    fn sample(vk: &[u8], proof: &[u8], inputs: &PublicInputs) -> Noun {
+        Noun::tuple(vec![
+            octs_noun(vk),
+            octs_noun(proof),
+            Noun::cord(&inputs.document_commitment),
+            Noun::cord(&inputs.model_hash),
+            Noun::atom(&inputs.timestamp.to_le_bytes()),
+            match &inputs.nonce {
+                Some(nonce) => Noun::cell(Noun::null(), Noun::cord(nonce)),
+                None => Noun::null(),
+            },
+        ])
+    }
+
+    /// Verifying key of a test circuit, and a proof of `inputs` under it
+    pub(crate) fn prove(inputs: &PublicInputs) -> (Vec<u8>, Vec<u8>) {
+        let fields: [Fr; 3] = inputs.to_field_elements().try_into().unwrap();
+        let circuit = || {
+            let [commitment, model_hash, timestamp] = fields;
+            DocumentQueryCircuit::new(
+                vec![Fr::from(1u64), Fr::from(2u64)],
+                vec![],
+                vec![],
+                commitment,
+                model_hash,
+                timestamp,
+            )
+        };
+        let mut rng = ark_std::test_rng();
+        let pk = Groth16::<Bn254>::generate_random_parameters_with_reduction(circuit(), &mut rng)
+            .unwrap();
+        let proof =
+            Groth16::<Bn254>::create_random_proof_with_reduction(circuit(), &pk, &mut rng).unwrap();
+        let mut vk = Vec::new();
+        pk.vk.serialize_compressed(&mut vk).unwrap();
+        let mut proof_bytes = Vec::new();
+        proof.serialize_compressed(&mut proof_bytes).unwrap();
+        (vk, proof_bytes)
+    }
+
+    #[test]
+    fn test_verify_groth16_matches_verifier() {
+        let inputs = PublicInputs {
+            document_commitment: "ab".repeat(32),
+            model_hash: "cd".repeat(32),
+            timestamp: 1_700_000_000,
+            nonce: None,
+        };
+        let (vk, proof_bytes) = prove(&inputs);
+
+        let verifier = QueryVerifier::builder()
+            .key_bytes(vk.clone())
+            .build()
+            .unwrap();
+        let mut stale = inputs.clone();
+        stale.timestamp += 1;
+        for (proof, inputs) in [
+            (&proof_bytes[..], &inputs),
+            (&proof_bytes[..], &stale),
+            (&proof_bytes[..64], &inputs),
+        ] {
+            let expected = verifier.verify(proof, inputs.clone()).unwrap().is_valid;
+            let product = verify_groth16(&sample(&vk, proof, inputs)).unwrap();
+            assert_eq!(product, Noun::atom(&[if expected { YES } else { NO }]));
+        }
+        assert_eq!(
+            verify_groth16(&sample(&vk, &proof_bytes, &inputs)).unwrap(),
+            Noun::atom(&[YES])
+        );
+
+        // A proof bound to a nonce verifies only alongside it
+        let bound = PublicInputs {
+            nonce: Some("ef".repeat(32)),
+            ..inputs.clone()
+        };
+        let (bound_vk, bound_proof) = prove(&bound);
+        assert_eq!(
+            verify_groth16(&sample(&bound_vk, &bound_proof, &bound)).unwrap(),
+            Noun::atom(&[YES])
+        );
+        assert_eq!(
+            verify_groth16(&sample(&bound_vk, &bound_proof, &inputs)).unwrap(),
+            Noun::atom(&[NO])
+        );
+
+        // A key that doesn't load crashes instead of answering `%.n`
+        let error = verify_groth16(&sample(&vk[1..], &proof_bytes, &inputs)).unwrap_err();
+        assert!(matches!(error, JetError::Deterministic(_)));
+        assert!(verify_groth16(&Noun::null()).is_err());
+
+        // So does a length past what the verifier takes, before allocating it
+        let len = MAX_PROOF_BYTES as u64 + 1;
+        let oversized = Noun::tuple(vec![
+            octs_noun(&vk),
+            Noun::cell(Noun::atom(&len.to_le_bytes()), Noun::atom(&[1])),
+            Noun::cord(&inputs.document_commitment),
+            Noun::cord(&inputs.model_hash),
+            Noun::atom(&inputs.timestamp.to_le_bytes()),
+            Noun::null(),
+        ]);
+        let error = verify_groth16(&oversized).unwrap_err();
+        assert_eq!(error, crash("octs length out of range"));
+    }
+}
@@ -0,0 +1,355 @@
+// NockApp kernel driver
+//
+// Registrations and verified queries are poked into the Hoon kernel
+// (hoon/zkrag.hoon) running in its own NockApp process, which listens on a
+// Unix socket. Each message either way is a jammed noun (see noun.rs)
+// preceded by its length in bytes as a little-endian u64:
+//
+//     [%poke cause]   ->  [%ack (list effect)]  or  [%nack message=@t]
+//     [%peek path]    ->  [%bind (unit noun)]
+//
+// Causes are the kernel's `cause` type, e.g.
+// `[%register-model model-hash=@t model-name=@t]`. The kernel answers a poke
+// with effects: `[%log message]` effects are logged here, and an
+// `[%http-response status body]` effect with an error status refuses the
+// request, its status and body passed on to the client as `kernel_rejected`.
+//
+// The server pokes registrations into the kernel before writing them to its
+// own store, so a refused registration isn't stored. A verified query is
+// stored first, claiming its proof's nullifier so concurrent replays don't
+// reach the kernel, and deleted again if the poke fails. Reads are still
+// answered from the store. One connection is kept open and reopened after a
+// failure; a request that fails is not retried, as the kernel may have
+// applied it.
+//
+// Configuration (the `[kernel]` table; see config.rs):
+// - socket (ZKRAG_KERNEL_SOCKET): the kernel's socket; without one the
+//   server runs on its store alone
+// - timeout_secs (ZKRAG_KERNEL_TIMEOUT): how long to wait for an answer
+//   (default 10)
+
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+use tokio::sync::Mutex;
+use tracing::info;
+
+use crate::noun::{CueError, Noun};
+
+/// Largest reply accepted from the kernel
+const MAX_FRAME_BYTES: u64 = 64 << 20;
+
+/// Kernel socket settings
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KernelConfig {
+    pub socket: Option<PathBuf>,
+    pub timeout_secs: u64,
+}
+
+impl Default for KernelConfig {
+    fn default() -> Self {
+        Self {
+            socket: None,
+            timeout_secs: 10,
+        }
+    }
+}
+
+/// Poke for the kernel's state machine
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cause {
+    RegisterDocument {
+        commitment: String,
+        owner: String,
+    },
+    RegisterModel {
+        model_hash: String,
+        model_name: String,
+    },
+    /// A query whose proof verified, for the kernel to check again; byte
+    /// strings are hex
+    VerifyQuery {
+        verifying_key: String,
+        proof: String,
+        commitment: String,
+        model_hash: String,
+        timestamp: u64,
+        nonce: Option<String>,
+    },
+}
+
+impl Cause {
+    pub fn to_noun(&self) -> Noun {
+        match self {
+            Cause::RegisterDocument { commitment, owner } => Noun::tuple(vec![
+                Noun::cord("register-document"),
+                Noun::cord(commitment),
+                Noun::cord(owner),
+            ]),
+            Cause::RegisterModel {
+                model_hash,
+                model_name,
+            } => Noun::tuple(vec![
+                Noun::cord("register-model"),
+                Noun::cord(model_hash),
+                Noun::cord(model_name),
+            ]),
+            Cause::VerifyQuery {
+                verifying_key,
+                proof,
+                commitment,
+                model_hash,
+                timestamp,
+                nonce,
+            } => Noun::tuple(vec![
+                Noun::cord("verify-query"),
+                Noun::cord(verifying_key),
+                Noun::cord(proof),
+                Noun::cord(commitment),
+                Noun::cord(model_hash),
+                Noun::atom(&timestamp.to_le_bytes()),
+                match nonce {
+                    Some(nonce) => Noun::cell(Noun::null(), Noun::cord(nonce)),
+                    None => Noun::null(),
+                },
+            ]),
+        }
+    }
+}
+
+/// Effect emitted by a poke
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Effect {
+    HttpResponse {
+        status: u16,
+        body: String,
+    },
+    Log(String),
+    /// Effect this driver doesn't act on, by tag
+    Other(String),
+}
+
+impl Effect {
+    fn from_noun(noun: &Noun) -> Result<Self, KernelError> {
+        let malformed = || KernelError::Malformed("effect".to_string());
+        let (tag, rest) = noun.as_cell().ok_or_else(malformed)?;
+        let tag = tag.as_cord().ok_or_else(malformed)?;
+        match tag.as_str() {
+            "http-response" => {
+                let (status, body) = rest.as_cell().ok_or_else(malformed)?;
+                Ok(Effect::HttpResponse {
+                    status: status
+                        .as_u64()
+                        .and_then(|status| u16::try_from(status).ok())
+                        .ok_or_else(malformed)?,
+                    body: body.as_cord().ok_or_else(malformed)?,
+                })
+            }
+            "log" => Ok(Effect::Log(rest.as_cord().ok_or_else(malformed)?)),
+            _ => Ok(Effect::Other(tag)),
+        }
+    }
+}
+
+/// Why the kernel didn't take a poke or answer a peek
+#[derive(Debug, thiserror::Error)]
+pub enum KernelError {
+    #[error("kernel socket: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("kernel didn't answer within {0}s")]
+    Timeout(u64),
+    #[error("malformed kernel {0}")]
+    Malformed(String),
+    #[error("kernel crashed: {0}")]
+    Nack(String),
+    #[error("kernel answered {status}: {body}")]
+    Rejected { status: u16, body: String },
+}
+
+impl From<CueError> for KernelError {
+    fn from(error: CueError) -> Self {
+        KernelError::Malformed(format!("reply: {}", error))
+    }
+}
+
+/// Connection to the kernel process
+pub struct Kernel {
+    socket: PathBuf,
+    timeout: Duration,
+    connection: Mutex<Option<UnixStream>>,
+}
+
+impl Kernel {
+    /// Driver for the kernel listening on `socket`, connected on first use
+    pub fn new(socket: PathBuf, timeout: Duration) -> Self {
+        Self {
+            socket,
+            timeout,
+            connection: Mutex::new(None),
+        }
+    }
+
+    /// Apply `cause`, failing if the kernel refuses it
+    pub async fn poke(&self, cause: &Cause) -> Result<Vec<Effect>, KernelError> {
+        let request = Noun::cell(Noun::cord("poke"), cause.to_noun());
+        let reply = self.request(&request).await?;
+        let (tag, rest) = reply
+            .as_cell()
+            .ok_or_else(|| KernelError::Malformed("poke reply".to_string()))?;
+        match tag.as_cord().as_deref() {
+            Some("ack") => {}
+            Some("nack") => return Err(KernelError::Nack(rest.as_cord().unwrap_or_default())),
+            _ => return Err(KernelError::Malformed("poke reply".to_string())),
+        }
+        let effects = rest
+            .as_list()
+            .ok_or_else(|| KernelError::Malformed("effect list".to_string()))?
+            .into_iter()
+            .map(Effect::from_noun)
+            .collect::<Result<Vec<_>, _>>()?;
+        for effect in &effects {
+            match effect {
+                Effect::Log(message) => info!("Kernel: {}", message),
+                Effect::HttpResponse { status, body } if *status >= 400 => {
+                    return Err(KernelError::Rejected {
+                        status: *status,
+                        body: body.clone(),
+                    });
+                }
+                _ => {}
+            }
+        }
+        Ok(effects)
+    }
+
+    /// Read the kernel's state at `path`, or `None` if there's nothing there
+    pub async fn peek(&self, path: &[&str]) -> Result<Option<Noun>, KernelError> {
+        let path = Noun::list(path.iter().map(|segment| Noun::cord(segment)).collect());
+        let reply = self.request(&Noun::cell(Noun::cord("peek"), path)).await?;
+        let malformed = || KernelError::Malformed("peek reply".to_string());
+        let (tag, unit) = reply.as_cell().ok_or_else(malformed)?;
+        if tag.as_cord().as_deref() != Some("bind") {
+            return Err(malformed());
+        }
+        match unit.as_cell() {
+            Some((_, value)) => Ok(Some(value.clone())),
+            None => Ok(None),
+        }
+    }
+
+    async fn request(&self, request: &Noun) -> Result<Noun, KernelError> {
+        let mut connection = self.connection.lock().await;
+        let exchange = async {
+            if connection.is_none() {
+                *connection = Some(UnixStream::connect(&self.socket).await?);
+            }
+            let stream = connection.as_mut().unwrap();
+            let frame = request.jam();
+            stream
+                .write_all(&(frame.len() as u64).to_le_bytes())
+                .await?;
+            stream.write_all(&frame).await?;
+            let len = stream.read_u64_le().await?;
+            if len > MAX_FRAME_BYTES {
+                return Err(KernelError::Malformed(format!("reply of {} bytes", len)));
+            }
+            let mut frame = vec![0; len as usize];
+            stream.read_exact(&mut frame).await?;
+            Ok(Noun::cue(&frame)?)
+        };
+        let result = match tokio::time::timeout(self.timeout, exchange).await {
+            Ok(result) => result,
+            Err(_) => Err(KernelError::Timeout(self.timeout.as_secs())),
+        };
+        // The stream may be mid-message; start afresh next time
+        if result.is_err() {
+            *connection = None;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::UnixListener;
+
+    async fn read_frame(stream: &mut UnixStream) -> Noun {
+        let len = stream.read_u64_le().await.unwrap();
+        let mut frame = vec![0; len as usize];
+        stream.read_exact(&mut frame).await.unwrap();
+        Noun::cue(&frame).unwrap()
+    }
+
+    async fn write_frame(stream: &mut UnixStream, noun: &Noun) {
+        let frame = noun.jam();
+        stream.write_u64_le(frame.len() as u64).await.unwrap();
+        stream.write_all(&frame).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_poke_and_peek() {
+        let socket = std::env::temp_dir().join(format!("zkrag-kernel-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+        let listener = UnixListener::bind(&socket).unwrap();
+        let cause = Cause::RegisterModel {
+            model_hash: "ab".repeat(32),
+            model_name: "bert".to_string(),
+        };
+        let expected = Noun::cell(Noun::cord("poke"), cause.to_noun());
+        let kernel_side = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            assert_eq!(read_frame(&mut stream).await, expected);
+            let effects = Noun::list(vec![
+                Noun::tuple(vec![
+                    Noun::cord("http-response"),
+                    Noun::atom(&[201]),
+                    Noun::cord(r#"{"success":true,"id":1}"#),
+                ]),
+                Noun::cell(Noun::cord("log"), Noun::cord("Model registered: 1")),
+            ]);
+            write_frame(&mut stream, &Noun::cell(Noun::cord("ack"), effects)).await;
+
+            read_frame(&mut stream).await;
+            let refused = Noun::list(vec![Noun::tuple(vec![
+                Noun::cord("http-response"),
+                Noun::atom(&[0x99, 0x01]),
+                Noun::cord(r#"{"error":"Model already registered"}"#),
+            ])]);
+            write_frame(&mut stream, &Noun::cell(Noun::cord("ack"), refused)).await;
+
+            let path = Noun::list(vec![Noun::cord("counts")]);
+            assert_eq!(
+                read_frame(&mut stream).await,
+                Noun::cell(Noun::cord("peek"), path)
+            );
+            let counts = Noun::tuple(vec![Noun::null(), Noun::null(), Noun::atom(&[1])]);
+            let bound = Noun::cell(Noun::null(), counts);
+            write_frame(&mut stream, &Noun::cell(Noun::cord("bind"), bound)).await;
+        });
+
+        let kernel = Kernel::new(socket.clone(), Duration::from_secs(10));
+        let effects = kernel.poke(&cause).await.unwrap();
+        assert_eq!(effects[1], Effect::Log("Model registered: 1".to_string()));
+        match kernel.poke(&cause).await.unwrap_err() {
+            KernelError::Rejected { status, body } => {
+                assert_eq!(status, 409);
+                assert!(body.contains("already registered"));
+            }
+            error => panic!("unexpected error {}", error),
+        }
+        let counts = kernel.peek(&["counts"]).await.unwrap().unwrap();
+        assert_eq!(counts.as_cell().unwrap().0, &Noun::null());
+        kernel_side.await.unwrap();
+
+        // The kernel hung up; the next request fails and reconnects after
+        std::fs::remove_file(&socket).unwrap();
+        assert!(matches!(
+            kernel.peek(&["counts"]).await,
+            Err(KernelError::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,226 @@
+// Key distribution
+//
+// Provers and external verifiers fetch key material from the service instead
+// of copying files by hand:
+// - `GET /api/v1/keys/verifying-key` serves the document query circuit's
+//   current verifying key, or with `?fingerprint=` any key version the
+//   verifier has loaded. It is open like the health probes, as the key is
+//   public.
+// - `GET /api/v1/keys/proving-key` serves the proving key behind
+//   `/api/v1/proof/generate`, read from disk on each request, to admins only.
+// - `GET /api/v1/keys/receipt-key` serves the ed25519 public key that signs
+//   verification receipts, when the server is given a receipt key (a file
+//   holding the 32-byte secret key, raw or as hex). Receipts name it by
+//   `verifier_key_id`, the hex SHA-256 of the public key.
+// - `GET /api/v1/query/:id/receipt` serves the receipt signed when query `id`
+//   was verified, with the public inputs it covers and, while the server
+//   still signs with the same key, the public key, so a client can hand the
+//   whole attestation to a third party who checks it offline.
+//
+// Keys are sent as compressed arkworks bytes (application/octet-stream) with
+// these headers:
+// - ETag: the quoted hex SHA-256 of the bytes; a request whose If-None-Match
+//   names it is answered 304 without a body
+// - X-Zkrag-Key-Fingerprint: id of the verifying key, for the proving key the
+//   id of the verifying key it pairs with
+// - X-Zkrag-Curve and X-Zkrag-Circuit-Id
+//
+// A verifying key's fingerprint is the digest of its bytes, so its ETag is
+// its fingerprint and a key fetched by fingerprint never changes.
+
+use anyhow::Context;
+use axum::http::header::{CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_TYPE, ETAG, IF_NONE_MATCH};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use utoipa::{IntoParams, ToSchema};
+use zkrag_verifier::{PublicInputs, ReceiptSigner, VerificationReceipt};
+
+use crate::openapi::{PublicInputsSchema, VerificationReceiptSchema};
+
+/// Query of `GET /api/v1/keys/verifying-key`
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct VerifyingKeyParams {
+    /// Key version to fetch; the circuit's current key if omitted
+    pub fingerprint: Option<String>,
+}
+
+/// Body of `GET /api/v1/keys/receipt-key`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReceiptKeyResponse {
+    /// `verifier_key_id` of the receipts it signs
+    pub verifier_key_id: String,
+    /// Hex ed25519 public key
+    pub public_key: String,
+}
+
+impl From<&ReceiptSigner> for ReceiptKeyResponse {
+    fn from(signer: &ReceiptSigner) -> Self {
+        Self {
+            verifier_key_id: signer.key_id().to_string(),
+            public_key: hex::encode(signer.public_key().as_bytes()),
+        }
+    }
+}
+
+/// Body of `GET /api/v1/query/:id/receipt`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QueryReceipt {
+    pub query_id: u64,
+    /// Public inputs of the proof; `receipt.inputs_digest` is the SHA-256 of
+    /// their canonical JSON
+    #[schema(value_type = PublicInputsSchema)]
+    pub public_inputs: PublicInputs,
+    #[schema(value_type = VerificationReceiptSchema)]
+    pub receipt: VerificationReceipt,
+    /// Hex ed25519 public key of `receipt.verifier_key_id`; absent once the
+    /// server signs with another key, see `/api/v1/keys/receipt-key`
+    pub public_key: Option<String>,
+}
+
+impl QueryReceipt {
+    pub fn new(
+        query_id: u64,
+        public_inputs: PublicInputs,
+        receipt: VerificationReceipt,
+        signer: Option<&ReceiptSigner>,
+    ) -> Self {
+        let public_key = signer
+            .filter(|signer| signer.key_id() == receipt.verifier_key_id)
+            .map(|signer| hex::encode(signer.public_key().as_bytes()));
+        Self {
+            query_id,
+            public_inputs,
+            receipt,
+            public_key,
+        }
+    }
+
+    /// The receipt as a download named after the query
+    pub fn respond(self) -> Response {
+        let disposition = format!(
+            "attachment; filename=\"query-{}-receipt.json\"",
+            self.query_id
+        );
+        ([(CONTENT_DISPOSITION, disposition)], axum::Json(self)).into_response()
+    }
+}
+
+/// Load the receipt signing key at `path`: 32 bytes, or 64 hex characters
+pub fn load_receipt_signer(path: &Path) -> anyhow::Result<ReceiptSigner> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("Reading receipt key {}", path.display()))?;
+    let secret = match bytes.len() {
+        32 => bytes,
+        _ => hex::decode(String::from_utf8_lossy(&bytes).trim()).unwrap_or_default(),
+    };
+    let secret: [u8; 32] = secret.try_into().map_err(|_| {
+        anyhow::anyhow!(
+            "Receipt key {} must hold a 32-byte ed25519 secret key, raw or as hex",
+            path.display()
+        )
+    })?;
+    Ok(ReceiptSigner::from_secret_bytes(&secret))
+}
+
+/// Key bytes and what to say about them
+pub struct KeyFile {
+    pub bytes: Vec<u8>,
+    /// Hex SHA-256 of `bytes`
+    pub digest: String,
+    /// Verifying key id
+    pub fingerprint: String,
+    pub curve: &'static str,
+    pub circuit_id: String,
+    /// Suggested file name
+    pub file_name: &'static str,
+    /// Whether the same URL always serves these bytes
+    pub immutable: bool,
+}
+
+/// Whether an If-None-Match header names `etag`
+fn not_modified(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim().trim_start_matches("W/"))
+        .any(|tag| tag == "*" || tag == etag)
+}
+
+impl KeyFile {
+    /// The key, or 304 if the client already holds it
+    pub fn respond(self, request: &HeaderMap) -> Response {
+        let etag = format!("\"{}\"", self.digest);
+        let cache = if self.immutable {
+            "public, max-age=31536000, immutable"
+        } else {
+            "no-cache"
+        };
+        let headers = [
+            (ETAG.as_str(), etag.clone()),
+            (CACHE_CONTROL.as_str(), cache.to_string()),
+            ("x-zkrag-key-fingerprint", self.fingerprint),
+            ("x-zkrag-curve", self.curve.to_string()),
+            ("x-zkrag-circuit-id", self.circuit_id),
+        ];
+        if not_modified(request, &etag) {
+            return (StatusCode::NOT_MODIFIED, headers).into_response();
+        }
+        (
+            headers,
+            [
+                (
+                    CONTENT_TYPE.as_str(),
+                    "application/octet-stream".to_string(),
+                ),
+                (
+                    CONTENT_DISPOSITION.as_str(),
+                    format!("attachment; filename=\"{}\"", self.file_name),
+                ),
+            ],
+            self.bytes,
+        )
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_etag_revalidation() {
+        let key = || KeyFile {
+            bytes: vec![1, 2, 3],
+            digest: "ab".repeat(32),
+            fingerprint: "ab".repeat(32),
+            curve: "bn254",
+            circuit_id: "document_query".to_string(),
+            file_name: "verifying_key.bin",
+            immutable: false,
+        };
+        let etag = format!("\"{}\"", "ab".repeat(32));
+
+        let response = key().respond(&HeaderMap::new());
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[ETAG], etag.as_str());
+        assert_eq!(response.headers()[CACHE_CONTROL], "no-cache");
+
+        let mut request = HeaderMap::new();
+        let stale = format!("\"{}\", W/{}", "cd".repeat(32), etag);
+        request.insert(IF_NONE_MATCH, HeaderValue::from_str(&stale).unwrap());
+        let response = key().respond(&request);
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(
+            response.headers()["x-zkrag-key-fingerprint"],
+            "ab".repeat(32)
+        );
+
+        request.insert(IF_NONE_MATCH, HeaderValue::from_static("\"cdcd\""));
+        assert_eq!(key().respond(&request).status(), StatusCode::OK);
+    }
+}
@@ -1,203 +1,2827 @@
 // ZK-RAG Verifier NockApp - Rust HTTP Driver
 //
 // Provides HTTP API for proof verification
+//
+// The handlers, app state and router live here; each feature is documented
+// in its own module:
+// - settings: config.rs
+// - storage and the kernel: store.rs, archive.rs, retention.rs, kernel.rs,
+//   jets.rs
+// - documents and models: registry.rs, upload.rs, artifacts.rs
+// - verification: v2.rs, challenge.rs, nullifiers.rs, idempotency.rs,
+//   prover.rs
+// - records: audit.rs, stats.rs, owners.rs, events.rs, webhooks.rs
+// - keys: keys.rs, rotation.rs
+// - serving: auth.rs, ratelimit.rs, guard.rs, codec.rs, error.rs,
+//   validate.rs, cors.rs, tls.rs, shutdown.rs, health.rs, metrics.rs,
+//   telemetry.rs, cluster.rs, openapi.rs
+//
+// The server refuses to start without a document query verifying key. Only
+// registered models are accepted: a query naming any other model hash is
+// rejected with `model_not_registered` before its proof is checked. Admins
+// may revoke a document or model, after which queries naming it fail with
+// `revoked`, across restarts.
 
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::{IntoResponse, Response},
-    routing::{get, post},
+    body::{Body, Bytes},
+    extract::{rejection::BytesRejection, ws::WebSocketUpgrade, State},
+    http::{header::CONTENT_TYPE, HeaderMap, StatusCode},
+    middleware,
+    response::{
+        sse::{KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{delete, get, post},
     Json, Router,
 };
+use clap::Parser;
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::sync::RwLock;
-use tower_http::cors::{Any, CorsLayer};
-use tracing::info;
+use tracing::{info, info_span, warn};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use utoipa_swagger_ui::SwaggerUi;
+use zkrag_circuits::utils::compute_document_commitment;
+use zkrag_prover::QueryWitness;
+use zkrag_verifier::curve::Curve;
+use zkrag_verifier::keys::{key_digest, PROVING_KEY_FILE, VERIFYING_KEY_FILE};
+use zkrag_verifier::{
+    ProofEnvelope, PublicInputs, QueryVerifier, ReceiptSigner, RevocationList, RevocationRegistry,
+    VerificationFailure, VerificationResult, VerifierError, VerifierMetrics,
+    DOCUMENT_QUERY_CIRCUIT_ID,
+};
+
+mod archive;
+mod artifacts;
+mod audit;
+mod auth;
+mod challenge;
+mod cluster;
+mod codec;
+mod config;
+mod cors;
+mod error;
+mod events;
+mod guard;
+mod health;
+mod idempotency;
+mod jets;
+mod kernel;
+mod keys;
+mod metrics;
+mod noun;
+mod nullifiers;
+mod openapi;
+mod owners;
+mod prover;
+mod ratelimit;
+mod registry;
+mod retention;
+mod rotation;
+mod shutdown;
+mod stats;
+mod store;
+mod telemetry;
+mod tls;
+mod upload;
+mod v2;
+mod validate;
+mod webhooks;
+
+use archive::ProofArchive;
+use artifacts::{ModelUploadParams, ModelUploadResponse, ModelsConfig};
+use audit::{AuditFormat, AuditPage, AuditParams};
+use auth::{Admin, Auditor, Auth, Authenticator, Submitter};
+use challenge::{Challenge, ChallengeConfig};
+use codec::{ApiBody, Format, Negotiated};
+use config::{Cli, Config};
+use error::{ApiError, ApiJson, ApiPath, ApiQuery, ErrorCode, ErrorResponse};
+use events::{Event, EventBus, EventFilter, EventParams};
+use guard::{guarded, GuardConfig};
+use health::{ComponentHealth, Components, Health, KeyChecks};
+use idempotency::IdempotencyConfig;
+use kernel::{Cause, Kernel};
+use keys::{KeyFile, QueryReceipt, ReceiptKeyResponse, VerifyingKeyParams};
+use nullifiers::{
+    EpochNullifiers, EpochNullifiersParams, ExpiredNullifiers, NullifierConfig, NullifierEpochs,
+};
+use openapi::{ApiDoc, AuthErrors, IdempotencyErrors, IdempotencyHeader, KernelErrors};
+use owners::{OwnerHistory, OwnerHistoryParams};
+use prover::ProverPool;
+use ratelimit::limited;
+use registry::{normalize_hash, registration_commitment, DocumentProof, DocumentRegistry};
+use rotation::{
+    DeprecateKeyRequest, KeyRotationResponse, KeyVersionList, RotationConfig, UploadKeyParams,
+};
+use shutdown::{Readiness, Shutdown};
+use stats::{Stats, StatsParams};
+use store::{QueryFilter, QueryRecord, SqliteStore, Store};
+use upload::{DocumentUploadResponse, UploadParams};
+use v2::{EnvelopeBody, EnvelopeVerification};
+use validate::Validator;
+use webhooks::{RegisterWebhookRequest, WebhookResponse};
+
+/// Queries per page of `/api/v1/queries` when `limit` is omitted
+const DEFAULT_PAGE_SIZE: u64 = 50;
+/// Largest `limit` accepted by `/api/v1/queries`
+const MAX_PAGE_SIZE: u64 = 500;
+
+/// How long `/readyz` and `/health` wait for the database or the kernel to
+/// answer
+const READINESS_TIMEOUT: Duration = Duration::from_secs(2);
 
 // Request/Response Types
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 struct RegisterDocumentRequest {
-    commitment: String,
+    /// Commitment to the document's contents; derived from
+    /// `document_hashes` when omitted
+    commitment: Option<String>,
     owner: String,
+    /// Hex SHA-256 hashes of the document (or its chunks) to add to the
+    /// registry
+    #[serde(default)]
+    document_hashes: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 struct RegisterModelRequest {
     model_hash: String,
     model_name: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 struct VerifyQueryRequest {
+    /// Hex-encoded proof; a byte string in CBOR and MessagePack bodies
     proof: String,
     document_commitment: String,
     model_hash: String,
     timestamp: u64,
+    /// Nonce from `/api/v1/challenge` the proof is bound to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    nonce: Option<String>,
+}
+
+/// Filters and page of `/api/v1/queries`; blank parameters are ignored
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ListQueriesParams {
+    #[serde(default, deserialize_with = "blank_as_none")]
+    document_commitment: Option<String>,
+    #[serde(default, deserialize_with = "blank_as_none")]
+    model_hash: Option<String>,
+    /// Only queries verified at or after this Unix time
+    #[serde(default, deserialize_with = "blank_as_none")]
+    since: Option<u64>,
+    /// 1-based page number
+    #[serde(default, deserialize_with = "blank_as_none")]
+    page: Option<u64>,
+    #[serde(default, deserialize_with = "blank_as_none")]
+    limit: Option<u64>,
+}
+
+/// Parse a query parameter, treating `name=` like an absent parameter
+fn blank_as_none<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    let value = String::deserialize(deserializer)?;
+    if value.is_empty() {
+        return Ok(None);
+    }
+    value.parse().map(Some).map_err(serde::de::Error::custom)
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 struct SuccessResponse {
     success: bool,
     id: Option<u64>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 struct VerificationResponse {
     valid: bool,
     query_id: Option<u64>,
     message: String,
+    /// Why the proof failed, tagged with a machine-readable `code`
+    #[schema(value_type = Option<Object>)]
+    reason: Option<VerificationFailure>,
+    proof_digest: String,
+    verified_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+struct DocumentRegistrationResponse {
+    success: bool,
+    id: Option<u64>,
+    /// Registry commitment after this registration
+    commitment: String,
+    document_count: usize,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ErrorResponse {
-    error: String,
+#[derive(Debug, Serialize, ToSchema)]
+struct QueryListResponse {
+    queries: Vec<QueryRecord>,
+    page: u64,
+    limit: u64,
+    /// Queries matching the filters, across all pages
+    total: u64,
 }
 
-// Placeholder kernel (until NockApp integration)
-type SharedState = Arc<RwLock<MockKernel>>;
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+struct CommitmentResponse {
+    commitment: String,
+    document_count: usize,
+}
 
-struct MockKernel {
-    next_id: u64,
+// Service state shared by the handlers
+type SharedState = Arc<AppState>;
+
+struct AppState {
+    store: Arc<dyn Store>,
+    /// `None` when authentication is off
+    auth: Option<Arc<Authenticator>>,
+    documents: RwLock<DocumentRegistry>,
+    /// Write-locked only to swap in rotated keys
+    verifier: std::sync::RwLock<QueryVerifier>,
+    rotation: RotationConfig,
+    /// Held for the length of a key rotation, so rotations don't race
+    rotating: tokio::sync::Mutex<()>,
+    /// Checked by `verifier`
+    revocations: Arc<RevocationRegistry>,
+    /// Recorded by `verifier`
+    metrics: Arc<VerifierMetrics>,
+    /// Self-checks of `verifier`'s keys, for `/health`
+    key_checks: KeyChecks,
+    /// Signs `verifier`'s receipts; `None` when there's no receipt key
+    receipts: Option<Arc<ReceiptSigner>>,
+    /// `None` when proofs are kept in the database
+    archive: Option<ProofArchive>,
+    /// `None` when there's no proving key
+    prover: Option<ProverPool>,
+    events: EventBus,
+    shutdown: Shutdown,
+    /// `None` when no kernel socket is configured
+    kernel: Option<Kernel>,
+    idempotency: IdempotencyConfig,
+    guard: GuardConfig,
+    models: ModelsConfig,
+    challenge: ChallengeConfig,
+    nullifiers: NullifierConfig,
 }
 
-impl MockKernel {
-    fn new() -> Self {
-        Self { next_id: 1 }
+/// Poke `cause` into the kernel, if there is one
+async fn poke_kernel(state: &AppState, cause: Cause) -> Result<(), ApiError> {
+    if let Some(kernel) = &state.kernel {
+        kernel.poke(&cause).await?;
     }
+    Ok(())
+}
 
-    fn next_id(&mut self) -> u64 {
-        let id = self.next_id;
-        self.next_id += 1;
-        id
-    }
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
 }
 
 // HTTP Handlers
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/document/register",
+    tag = "documents",
+    params(IdempotencyHeader),
+    request_body = RegisterDocumentRequest,
+    responses(
+        (status = 201, description = "Document registered", body = DocumentRegistrationResponse),
+        (
+            status = 400,
+            description = "Invalid fields or mismatched commitment (`invalid_request`)",
+            body = ErrorResponse
+        ),
+        KernelErrors,
+        IdempotencyErrors,
+        AuthErrors,
+    )
+)]
 async fn register_document(
-    State(kernel): State<SharedState>,
-    Json(payload): Json<RegisterDocumentRequest>,
-) -> Response {
-    info!("Registering document commitment: {}", &payload.commitment[..16]);
+    State(state): State<SharedState>,
+    auth: Auth<Submitter>,
+    ApiJson(payload): ApiJson<RegisterDocumentRequest>,
+) -> Result<Response, ApiError> {
+    info!(
+        "Registering document for {} (by {})",
+        payload.owner,
+        auth.subject()
+    );
 
-    let mut kernel = kernel.write().await;
-    let id = kernel.next_id();
+    let mut validator = Validator::new();
+    validator.name("owner", &payload.owner);
+    let mut hashes = Vec::with_capacity(payload.document_hashes.len());
+    for (i, hash) in payload.document_hashes.iter().enumerate() {
+        match normalize_hash(hash) {
+            Some(hash) => hashes.push(hash),
+            None => validator.digest(format!("document_hashes[{}]", i), hash),
+        }
+    }
+    match &payload.commitment {
+        Some(commitment) => validator.digest("commitment", commitment),
+        None if payload.document_hashes.is_empty() => {
+            validator.error("commitment", "provide a commitment or document_hashes")
+        }
+        None => {}
+    }
+    validator.finish()?;
 
-    // TODO: Send to Hoon kernel via noun
-    // For now, just return success
+    // The commitment is a digest by now, so `None` means a mismatch
+    let commitment = registration_commitment(payload.commitment.as_deref(), &hashes)
+        .ok_or_else(|| ApiError::invalid_request("Commitment does not match document_hashes"))?;
 
-    (
+    let (id, registry_commitment, document_count) =
+        add_document(&state, commitment, payload.owner, hashes).await?;
+    Ok((
         StatusCode::CREATED,
-        Json(SuccessResponse {
+        Json(DocumentRegistrationResponse {
             success: true,
             id: Some(id),
+            commitment: registry_commitment,
+            document_count,
         }),
     )
-        .into_response()
+        .into_response())
 }
 
-async fn register_model(
-    State(kernel): State<SharedState>,
-    Json(payload): Json<RegisterModelRequest>,
-) -> Response {
-    info!("Registering model: {}", payload.model_name);
+/// Register `commitment` for `owner`, adding `hashes` to the registry;
+/// returns its id and the registry's commitment and size after it
+async fn add_document(
+    state: &AppState,
+    commitment: String,
+    owner: String,
+    hashes: Vec<String>,
+) -> Result<(u64, String, usize), ApiError> {
+    poke_kernel(
+        state,
+        Cause::RegisterDocument {
+            commitment: commitment.clone(),
+            owner: owner.clone(),
+        },
+    )
+    .await?;
 
-    let mut kernel = kernel.write().await;
-    let id = kernel.next_id();
+    // Hold the registry across the insert so it changes in store order
+    let mut documents = state.documents.write().await;
+    let id = state
+        .store
+        .register_document(&commitment, &owner, &hashes, unix_now())
+        .await?;
+    documents.insert(hashes);
+    state.events.publish(Event::DocumentRegistered {
+        id,
+        commitment,
+        owner,
+        registry_commitment: documents.commitment().to_string(),
+    });
+    Ok((id, documents.commitment().to_string(), documents.len()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/document/upload",
+    tag = "documents",
+    params(UploadParams, IdempotencyHeader),
+    request_body(
+        description = "Document text, UTF-8",
+        content_type = "text/plain",
+        content = String
+    ),
+    responses(
+        (
+            status = 201,
+            description = "Document chunked, hashed and registered",
+            body = DocumentUploadResponse
+        ),
+        (
+            status = 400,
+            description = "Invalid parameters, or a body that isn't text or splits into too \
+                           many chunks (`invalid_request`)",
+            body = ErrorResponse
+        ),
+        (
+            status = 413,
+            description = "Body over the size cap (`payload_too_large`)",
+            body = ErrorResponse
+        ),
+        KernelErrors,
+        IdempotencyErrors,
+        AuthErrors,
+    )
+)]
+async fn upload_document(
+    State(state): State<SharedState>,
+    auth: Auth<Submitter>,
+    ApiQuery(params): ApiQuery<UploadParams>,
+    body: Result<Bytes, BytesRejection>,
+) -> Result<(StatusCode, Json<DocumentUploadResponse>), ApiError> {
+    let body = body?;
+    let hashes = upload::chunk_hashes(&body, &params)?;
+    info!(
+        "Registering uploaded document of {} chunks for {} (by {})",
+        hashes.len(),
+        params.owner,
+        auth.subject()
+    );
 
-    // TODO: Send to Hoon kernel
+    let document_commitment = compute_document_commitment(&hashes);
+    let (id, commitment, document_count) = add_document(
+        &state,
+        document_commitment.clone(),
+        params.owner,
+        hashes.clone(),
+    )
+    .await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(DocumentUploadResponse {
+            id,
+            document_commitment,
+            chunk_hashes: hashes,
+            commitment,
+            document_count,
+        }),
+    ))
+}
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/document/commitment",
+    tag = "documents",
+    responses(
+        (status = 200, description = "Registry commitment", body = CommitmentResponse),
+        AuthErrors,
+    )
+)]
+async fn document_commitment(State(state): State<SharedState>, _auth: Auth<Auditor>) -> Response {
+    let documents = state.documents.read().await;
     (
+        StatusCode::OK,
+        Json(CommitmentResponse {
+            commitment: documents.commitment().to_string(),
+            document_count: documents.len(),
+        }),
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/document/{hash}/proof",
+    tag = "documents",
+    params(("hash" = String, Path, description = "Hex SHA-256 document hash")),
+    responses(
+        (
+            status = 200,
+            description = "Inclusion path to the registry commitment",
+            body = DocumentProof
+        ),
+        (status = 400, description = "Malformed hash (`invalid_request`)", body = ErrorResponse),
+        (status = 404, description = "Hash isn't registered (`not_found`)", body = ErrorResponse),
+        AuthErrors,
+    )
+)]
+async fn document_proof(
+    State(state): State<SharedState>,
+    _auth: Auth<Auditor>,
+    ApiPath(hash): ApiPath<String>,
+) -> Result<Json<DocumentProof>, ApiError> {
+    let mut validator = Validator::new();
+    validator.digest("hash", &hash);
+    validator.finish()?;
+
+    let hash = hash.to_ascii_lowercase();
+    let documents = state.documents.read().await;
+    documents.inclusion_proof(&hash).map(Json).ok_or_else(|| {
+        ApiError::new(
+            ErrorCode::NotFound,
+            format!("Document hash {} isn't registered", hash),
+        )
+    })
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/document/{id}/revoke",
+    tag = "documents",
+    params(("id" = u64, Path, description = "Document id")),
+    responses(
+        (status = 200, description = "Document revoked", body = SuccessResponse),
+        (status = 404, description = "No such document (`not_found`)", body = ErrorResponse),
+        AuthErrors,
+    )
+)]
+async fn revoke_document(
+    State(state): State<SharedState>,
+    auth: Auth<Admin>,
+    ApiPath(id): ApiPath<u64>,
+) -> Result<Json<SuccessResponse>, ApiError> {
+    let commitment = state
+        .store
+        .revoke_document(id, unix_now())
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::NotFound, format!("No document {}", id)))?;
+    info!("Revoked document {} (by {})", commitment, auth.subject());
+    state.revocations.revoke_document(commitment);
+    Ok(Json(SuccessResponse {
+        success: true,
+        id: Some(id),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/model/register",
+    tag = "models",
+    params(IdempotencyHeader),
+    request_body = RegisterModelRequest,
+    responses(
+        (status = 201, description = "Model registered", body = SuccessResponse),
+        (
+            status = 400,
+            description = "Invalid model hash or name (`invalid_request`)",
+            body = ErrorResponse
+        ),
+        KernelErrors,
+        IdempotencyErrors,
+        AuthErrors,
+    )
+)]
+async fn register_model(
+    State(state): State<SharedState>,
+    auth: Auth<Admin>,
+    ApiJson(payload): ApiJson<RegisterModelRequest>,
+) -> Result<Response, ApiError> {
+    info!(
+        "Registering model: {} (by {})",
+        payload.model_name,
+        auth.subject()
+    );
+
+    let mut validator = Validator::new();
+    let model_hash = normalize_hash(&payload.model_hash);
+    if model_hash.is_none() {
+        validator.digest("model_hash", &payload.model_hash);
+    }
+    validator.name("model_name", &payload.model_name);
+    validator.finish()?;
+
+    let model_hash = model_hash.unwrap_or_default();
+    let id = add_model(&state, model_hash, payload.model_name).await?;
+    Ok((
         StatusCode::CREATED,
         Json(SuccessResponse {
             success: true,
             id: Some(id),
         }),
     )
-        .into_response()
+        .into_response())
 }
 
-async fn verify_query(
-    State(kernel): State<SharedState>,
-    Json(payload): Json<VerifyQueryRequest>,
-) -> Response {
-    info!("Verifying query proof");
+/// Register normalized `model_hash` as `model_name`, returning its id. A
+/// model registered before keeps its id and name.
+async fn add_model(
+    state: &AppState,
+    model_hash: String,
+    model_name: String,
+) -> Result<u64, ApiError> {
+    if state.store.is_model_registered(&model_hash).await? {
+        return Ok(state
+            .store
+            .register_model(&model_hash, &model_name, unix_now())
+            .await?);
+    }
+    poke_kernel(
+        state,
+        Cause::RegisterModel {
+            model_hash: model_hash.clone(),
+            model_name: model_name.clone(),
+        },
+    )
+    .await?;
 
-    // TODO: Actual ZK proof verification via Hoon kernel
-    // For now, placeholder verification
+    let id = state
+        .store
+        .register_model(&model_hash, &model_name, unix_now())
+        .await?;
+    state.events.publish(Event::ModelRegistered {
+        id,
+        model_hash,
+        model_name,
+    });
+    Ok(id)
+}
 
-    let mut kernel = kernel.write().await;
-    let id = kernel.next_id();
+#[utoipa::path(
+    post,
+    path = "/api/v1/model/upload",
+    tag = "models",
+    params(ModelUploadParams),
+    request_body(
+        description = "A weight file, or a model directory as a tar archive",
+        content(
+            (Vec<u8> = "application/octet-stream"),
+            (Vec<u8> = "application/x-tar")
+        )
+    ),
+    responses(
+        (
+            status = 201,
+            description = "Model hashed and registered",
+            body = ModelUploadResponse
+        ),
+        (
+            status = 400,
+            description = "Invalid parameters, a file that isn't a weight file, or an archive \
+                           without any (`invalid_request`)",
+            body = ErrorResponse
+        ),
+        (
+            status = 413,
+            description = "Upload over `[models]` max_upload_bytes (`payload_too_large`)",
+            body = ErrorResponse
+        ),
+        KernelErrors,
+        AuthErrors,
+    )
+)]
+async fn upload_model(
+    State(state): State<SharedState>,
+    auth: Auth<Admin>,
+    ApiQuery(params): ApiQuery<ModelUploadParams>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<(StatusCode, Json<ModelUploadResponse>), ApiError> {
+    let archive = headers
+        .get(CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"application/x-tar"));
+    let mut validator = Validator::new();
+    validator.name("model_name", &params.model_name);
+    if !archive && params.filename.is_none() {
+        validator.error("filename", "required unless the body is a tar archive");
+    }
+    validator.finish()?;
 
-    // Placeholder - always valid
-    let is_valid = true;
+    let hashed = if archive {
+        artifacts::hash_model_archive(body, &state.models).await?
+    } else {
+        let filename = params.filename.as_deref().unwrap_or_default();
+        artifacts::hash_weight_file(body, filename, &state.models).await?
+    };
+    info!(
+        "Registering uploaded model: {} with hash {} (by {})",
+        params.model_name,
+        hashed.model_hash,
+        auth.subject()
+    );
 
-    (
+    let id = add_model(&state, hashed.model_hash.clone(), params.model_name.clone()).await?;
+    Ok((
         StatusCode::CREATED,
-        Json(VerificationResponse {
-            valid: is_valid,
-            query_id: Some(id),
-            message: if is_valid {
-                "Proof verified successfully".to_string()
-            } else {
-                "Proof verification failed".to_string()
-            },
+        Json(ModelUploadResponse {
+            id,
+            model_hash: hashed.model_hash,
+            model_name: params.model_name,
+            files: hashed.files,
         }),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/model/{id}/revoke",
+    tag = "models",
+    params(("id" = u64, Path, description = "Model id")),
+    responses(
+        (status = 200, description = "Model revoked", body = SuccessResponse),
+        (status = 404, description = "No such model (`not_found`)", body = ErrorResponse),
+        AuthErrors,
     )
-        .into_response()
+)]
+async fn revoke_model(
+    State(state): State<SharedState>,
+    auth: Auth<Admin>,
+    ApiPath(id): ApiPath<u64>,
+) -> Result<Json<SuccessResponse>, ApiError> {
+    let model_hash = state
+        .store
+        .revoke_model(id, unix_now())
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::NotFound, format!("No model {}", id)))?;
+    info!("Revoked model {} (by {})", model_hash, auth.subject());
+    state.revocations.revoke_model(model_hash);
+    Ok(Json(SuccessResponse {
+        success: true,
+        id: Some(id),
+    }))
+}
+
+/// Refuse queries naming a model that isn't registered
+async fn require_registered_model(state: &AppState, model_hash: &str) -> Result<(), ApiError> {
+    let registered = match normalize_hash(model_hash) {
+        Some(model_hash) => state.store.is_model_registered(&model_hash).await?,
+        None => false,
+    };
+    if registered {
+        Ok(())
+    } else {
+        Err(ApiError::new(
+            ErrorCode::ModelNotRegistered,
+            format!("Model {} is not registered", model_hash),
+        ))
+    }
+}
+
+/// Fail a verified `result` as a replay of `nullifier`, re-signing its
+/// receipt
+fn mark_replayed(
+    state: &AppState,
+    proof: &[u8],
+    result: &mut VerificationResult,
+    nullifier: String,
+) {
+    result.is_valid = false;
+    result.reason = Some(VerificationFailure::Replayed { nullifier });
+    if let (Some(_), Some(signer)) = (&result.receipt, &state.receipts) {
+        result.receipt = Some(signer.sign(proof, result));
+    }
+}
+
+/// Record the outcome of verifying `envelope`, poking verified queries into
+/// the kernel, and return the query id. Proofs that didn't parse are refused
+/// rather than recorded. With replay checks on, a verified proof whose
+/// nullifier was already recorded fails `result` with `replayed`.
+///
+/// A verified query is recorded, claiming its nullifier, before it's poked,
+/// so only the verification that wins the claim reaches the kernel. If the
+/// poke fails the query is deleted again.
+async fn record_verification(
+    state: &AppState,
+    mut envelope: ProofEnvelope,
+    result: &mut VerificationResult,
+) -> Result<u64, ApiError> {
+    if let Some(
+        reason @ (VerificationFailure::MalformedProof { .. }
+        | VerificationFailure::ProofTooLarge { .. }),
+    ) = &result.reason
+    {
+        return Err(ApiError::new(
+            ErrorCode::ProofMalformed,
+            format!("Invalid proof: {}", reason),
+        )
+        .with_details(serde_json::json!({
+            "reason": reason,
+            "proof_digest": result.proof_digest,
+        })));
+    }
+
+    let mut nullifier = None;
+    if state.nullifiers.reject_replays && result.is_valid {
+        let spent = zkrag_verifier::nullifier::nullifier(&result.public_inputs);
+        if state.store.nullifier_recorded(&spent).await? {
+            mark_replayed(state, &envelope.proof, result, spent);
+        } else {
+            nullifier = Some(spent);
+        }
+    }
+
+    // The kernel checks verified proofs again, against the key that checked
+    // them here
+    let verifying_key = match (&state.kernel, result.is_valid) {
+        (Some(_), true) => {
+            let verifier = state.verifier.read().unwrap();
+            let key = result
+                .verifying_key_fingerprint
+                .as_deref()
+                .and_then(|key_id| verifier.keys().get(key_id))
+                .ok_or_else(|| ApiError::internal("Kernel poke", "verifying key not found"))?;
+            Some(hex::encode(key.key.to_bytes()))
+        }
+        _ => None,
+    };
+    let record = QueryRecord {
+        id: 0,
+        proof_digest: result.proof_digest.clone(),
+        document_commitment: result.public_inputs.document_commitment.clone(),
+        model_hash: result.public_inputs.model_hash.clone(),
+        timestamp: result.public_inputs.timestamp,
+        verified: result.is_valid,
+        reason: result.reason.clone(),
+        verified_at: result.verified_at,
+    };
+    // Pin the proof to the key that checked it
+    if envelope.key_id.is_none() {
+        if let Some(key_id) = &result.verifying_key_fingerprint {
+            envelope = envelope.with_key_id(key_id);
+        }
+    }
+    let archive_key = match &state.archive {
+        Some(archive) => match archive.put(&envelope.proof).await {
+            Ok(key) => Some(key),
+            Err(e) => {
+                warn!("Keeping proof in the database: {:#}", e);
+                None
+            }
+        },
+        None => None,
+    };
+    let recorded = match nullifier {
+        Some(nullifier) => {
+            let epoch = state.nullifiers.epoch(result.public_inputs.timestamp);
+            let recorded = state
+                .store
+                .record_nullified_query(
+                    &record,
+                    &envelope,
+                    archive_key.as_deref(),
+                    result.receipt.as_ref(),
+                    &nullifier,
+                    epoch,
+                )
+                .await?;
+            // Lost a race with a verification of the same proof
+            if recorded.is_none() {
+                mark_replayed(state, &envelope.proof, result, nullifier);
+            }
+            recorded
+        }
+        None => None,
+    };
+    let record = QueryRecord {
+        verified: result.is_valid,
+        reason: result.reason.clone(),
+        ..record
+    };
+    let query_id = match recorded {
+        Some(query_id) => query_id,
+        None => {
+            state
+                .store
+                .record_query(
+                    &record,
+                    &envelope,
+                    archive_key.as_deref(),
+                    result.receipt.as_ref(),
+                )
+                .await?
+        }
+    };
+    if let Some(verifying_key) = verifying_key.filter(|_| result.is_valid) {
+        let poked = poke_kernel(
+            state,
+            Cause::VerifyQuery {
+                verifying_key,
+                proof: hex::encode(&envelope.proof),
+                commitment: result.public_inputs.document_commitment.clone(),
+                model_hash: result.public_inputs.model_hash.clone(),
+                timestamp: result.public_inputs.timestamp,
+                nonce: result.public_inputs.nonce.clone(),
+            },
+        )
+        .await;
+        if let Err(error) = poked {
+            if let Err(e) = state.store.delete_query(query_id).await {
+                warn!(
+                    "Couldn't delete query {} after its poke failed: {}",
+                    query_id, e
+                );
+            }
+            return Err(error);
+        }
+    }
+    state.events.publish(Event::query(QueryRecord {
+        id: query_id,
+        ..record
+    }));
+    Ok(query_id)
 }
 
-async fn get_query(State(_kernel): State<SharedState>, Path(id): Path<u64>) -> Response {
+#[utoipa::path(
+    post,
+    path = "/api/v1/challenge",
+    tag = "queries",
+    responses(
+        (status = 201, description = "Nonce to bind the next proof to", body = Challenge),
+        AuthErrors,
+    )
+)]
+async fn issue_challenge(
+    State(state): State<SharedState>,
+    auth: Auth<Submitter>,
+) -> Result<(StatusCode, Json<Challenge>), ApiError> {
+    let challenge = challenge::issue(
+        state.store.as_ref(),
+        &state.challenge,
+        auth.subject(),
+        unix_now(),
+    )
+    .await?;
+    Ok((StatusCode::CREATED, Json(challenge)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/query/verify",
+    tag = "queries",
+    params(IdempotencyHeader),
+    request_body(
+        content(
+            (VerifyQueryRequest = "application/json"),
+            (VerifyQueryRequest = "application/cbor"),
+            (VerifyQueryRequest = "application/msgpack")
+        )
+    ),
+    responses(
+        (
+            status = 201,
+            description = "Proof verified",
+            content(
+                (VerificationResponse = "application/json"),
+                (VerificationResponse = "application/cbor"),
+                (VerificationResponse = "application/msgpack")
+            )
+        ),
+        (
+            status = 200,
+            description = "Proof failed verification, or replayed an accepted proof \
+                           (`replayed`); see `reason`",
+            content(
+                (VerificationResponse = "application/json"),
+                (VerificationResponse = "application/cbor"),
+                (VerificationResponse = "application/msgpack")
+            )
+        ),
+        (
+            status = 400,
+            description = "Invalid fields (`invalid_request`), or the proof doesn't parse \
+                           (`proof_malformed`, with `reason` and `proof_digest` in `details`)",
+            body = ErrorResponse
+        ),
+        (
+            status = 422,
+            description = "Model is not registered (`model_not_registered`), the challenge \
+                           nonce is unknown, expired or spent (`challenge_invalid`), or the \
+                           kernel refused a verified query (`kernel_rejected`)",
+            body = ErrorResponse
+        ),
+        (
+            status = 503,
+            description = "Kernel process is unreachable (`kernel_unavailable`)",
+            body = ErrorResponse
+        ),
+        IdempotencyErrors,
+        AuthErrors,
+    )
+)]
+async fn verify_query(
+    State(state): State<SharedState>,
+    auth: Auth<Submitter>,
+    format: Format,
+    ApiBody(payload): ApiBody<VerifyQueryRequest>,
+) -> Result<Response, ApiError> {
+    info!("Verifying query proof (by {})", auth.subject());
+
+    let mut validator = Validator::new();
+    let proof = validator.proof("proof", &payload.proof);
+    validator.digest("document_commitment", &payload.document_commitment);
+    validator.digest("model_hash", &payload.model_hash);
+    validator.timestamp("timestamp", payload.timestamp, unix_now());
+    validator.finish()?;
+
+    require_registered_model(&state, &payload.model_hash).await?;
+    let redeemed = challenge::redeem(
+        state.store.as_ref(),
+        &state.challenge,
+        auth.subject(),
+        "nonce",
+        payload.nonce.as_deref(),
+        unix_now(),
+    )
+    .await?;
+
+    let public_inputs = PublicInputs {
+        document_commitment: payload.document_commitment,
+        model_hash: payload.model_hash,
+        timestamp: payload.timestamp,
+        nonce: payload.nonce,
+    };
+
+    // The pairing check is CPU-bound, so run it off the async workers
+    let verifier_state = state.clone();
+    let span = info_span!("verify_proof", circuit_id = DOCUMENT_QUERY_CIRCUIT_ID);
+    let verified = async {
+        let (proof, result) = tokio::task::spawn_blocking(move || {
+            let _span = span.enter();
+            let verifier = verifier_state.verifier.read().unwrap();
+            let result = verifier.verify(&proof, public_inputs);
+            (proof, result)
+        })
+        .await
+        .map_err(|e| VerifierError::Internal(e.to_string()))?;
+        let mut result = result?;
+        let envelope = ProofEnvelope::new(proof, result.public_inputs.clone());
+        let query_id = record_verification(&state, envelope, &mut result).await?;
+        Ok((query_id, result))
+    }
+    .await;
+    let (query_id, result) = challenge::settle(state.store.as_ref(), redeemed, verified).await?;
+
+    let (status, message) = match &result.reason {
+        None => (
+            StatusCode::CREATED,
+            "Proof verified successfully".to_string(),
+        ),
+        Some(reason) => (
+            StatusCode::OK,
+            format!("Proof verification failed: {}", reason),
+        ),
+    };
+
+    Ok((
+        status,
+        Negotiated(
+            format,
+            VerificationResponse {
+                valid: result.is_valid,
+                query_id: Some(query_id),
+                message,
+                reason: result.reason,
+                proof_digest: result.proof_digest,
+                verified_at: result.verified_at,
+            },
+        ),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/query/verify",
+    tag = "queries",
+    params(IdempotencyHeader),
+    request_body(
+        description = "Proof envelope of any supported version",
+        content(
+            (openapi::ProofEnvelopeSchema = "application/json"),
+            (Vec<u8> = "application/octet-stream"),
+            (openapi::ProofEnvelopeSchema = "application/cbor"),
+            (openapi::ProofEnvelopeSchema = "application/msgpack")
+        )
+    ),
+    responses(
+        (
+            status = 201,
+            description = "Proof verified",
+            content(
+                (openapi::EnvelopeVerificationSchema = "application/json"),
+                (openapi::EnvelopeVerificationSchema = "application/cbor"),
+                (openapi::EnvelopeVerificationSchema = "application/msgpack")
+            )
+        ),
+        (
+            status = 200,
+            description = "Proof failed verification, e.g. with `unknown_key`, \
+                           `circuit_mismatch` or `replayed`; see `reason`",
+            content(
+                (openapi::EnvelopeVerificationSchema = "application/json"),
+                (openapi::EnvelopeVerificationSchema = "application/cbor"),
+                (openapi::EnvelopeVerificationSchema = "application/msgpack")
+            )
+        ),
+        (
+            status = 400,
+            description = "Invalid public inputs (`invalid_request`), or the envelope or its \
+                           proof doesn't parse (`proof_malformed`)",
+            body = ErrorResponse
+        ),
+        (
+            status = 422,
+            description = "Model is not registered (`model_not_registered`), the challenge \
+                           nonce is unknown, expired or spent (`challenge_invalid`), or the \
+                           kernel refused a verified query (`kernel_rejected`)",
+            body = ErrorResponse
+        ),
+        (
+            status = 503,
+            description = "Kernel process is unreachable (`kernel_unavailable`)",
+            body = ErrorResponse
+        ),
+        IdempotencyErrors,
+        AuthErrors,
+    )
+)]
+async fn verify_envelope(
+    State(state): State<SharedState>,
+    auth: Auth<Submitter>,
+    format: Format,
+    EnvelopeBody(envelope): EnvelopeBody,
+) -> Result<Response, ApiError> {
+    info!(
+        "Verifying v{} proof envelope for {} (by {})",
+        envelope.version,
+        envelope.circuit_id,
+        auth.subject()
+    );
+
+    let inputs = &envelope.public_inputs;
+    let mut validator = Validator::new();
+    validator.digest(
+        "public_inputs.document_commitment",
+        &inputs.document_commitment,
+    );
+    validator.digest("public_inputs.model_hash", &inputs.model_hash);
+    validator.timestamp("public_inputs.timestamp", inputs.timestamp, unix_now());
+    validator.finish()?;
+
+    require_registered_model(&state, &inputs.model_hash).await?;
+    let redeemed = challenge::redeem(
+        state.store.as_ref(),
+        &state.challenge,
+        auth.subject(),
+        "public_inputs.nonce",
+        inputs.nonce.as_deref(),
+        unix_now(),
+    )
+    .await?;
+
+    let verifier_state = state.clone();
+    let span = info_span!(
+        "verify_proof",
+        circuit_id = %envelope.circuit_id,
+        key_id = envelope.key_id.as_deref(),
+    );
+    let verified = async {
+        let (envelope, result) = tokio::task::spawn_blocking(move || {
+            let _span = span.enter();
+            let verifier = verifier_state.verifier.read().unwrap();
+            let result = verifier.verify_envelope(&envelope, None);
+            (envelope, result)
+        })
+        .await
+        .map_err(|e| VerifierError::Internal(e.to_string()))?;
+        let mut result = result?;
+        let query_id = record_verification(&state, envelope, &mut result).await?;
+        Ok((query_id, result))
+    }
+    .await;
+    let (query_id, result) = challenge::settle(state.store.as_ref(), redeemed, verified).await?;
+
+    let status = if result.is_valid {
+        StatusCode::CREATED
+    } else {
+        StatusCode::OK
+    };
+    Ok((
+        status,
+        Negotiated(format, EnvelopeVerification { query_id, result }),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/query/{id}",
+    tag = "queries",
+    params(("id" = u64, Path, description = "Query id")),
+    responses(
+        (status = 200, description = "Recorded verification", body = QueryRecord),
+        (status = 404, description = "No such query (`not_found`)", body = ErrorResponse),
+        AuthErrors,
+    )
+)]
+async fn get_query(
+    State(state): State<SharedState>,
+    _auth: Auth<Auditor>,
+    ApiPath(id): ApiPath<u64>,
+) -> Result<Json<QueryRecord>, ApiError> {
     info!("Getting query: {}", id);
 
-    // TODO: Query Hoon kernel
+    let record = state.store.get_query(id).await?;
+    record
+        .map(Json)
+        .ok_or_else(|| ApiError::new(ErrorCode::NotFound, format!("No query {}", id)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/query/{id}/proof",
+    tag = "queries",
+    params(("id" = u64, Path, description = "Query id")),
+    responses(
+        (
+            status = 200,
+            description = "Proof as submitted, pinned to the verifying key that checked it",
+            content(
+                (openapi::ProofEnvelopeSchema = "application/json"),
+                (openapi::ProofEnvelopeSchema = "application/cbor"),
+                (openapi::ProofEnvelopeSchema = "application/msgpack")
+            )
+        ),
+        (
+            status = 404,
+            description = "No such query, it predates stored proofs, or its archived proof \
+                           has expired (`not_found`)",
+            body = ErrorResponse
+        ),
+        AuthErrors,
+    )
+)]
+async fn get_query_proof(
+    State(state): State<SharedState>,
+    _auth: Auth<Auditor>,
+    format: Format,
+    ApiPath(id): ApiPath<u64>,
+) -> Result<Negotiated<ProofEnvelope>, ApiError> {
+    let Some(stored) = state.store.query_proof(id).await? else {
+        return Err(ApiError::new(
+            ErrorCode::NotFound,
+            format!("No proof stored for query {}", id),
+        ));
+    };
+    let envelope = archive::restore(state.archive.as_ref(), id, stored).await?;
+    Ok(Negotiated(format, envelope))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/query/{id}/receipt",
+    tag = "queries",
+    params(("id" = u64, Path, description = "Query id")),
+    responses(
+        (
+            status = 200,
+            description = "Receipt signed when the query was verified, to check offline",
+            body = QueryReceipt
+        ),
+        (
+            status = 404,
+            description = "No such query, or it was verified without a receipt key (`not_found`)",
+            body = ErrorResponse
+        ),
+        AuthErrors,
+    )
+)]
+async fn get_query_receipt(
+    State(state): State<SharedState>,
+    _auth: Auth<Auditor>,
+    ApiPath(id): ApiPath<u64>,
+) -> Result<Response, ApiError> {
+    let receipt = state.store.query_receipt(id).await?;
+    let proof = state.store.query_proof(id).await?;
+    let (Some(receipt), Some(proof)) = (receipt, proof) else {
+        let message = match state.store.get_query(id).await? {
+            Some(_) => format!(
+                "Query {} has no receipt; receipts are only signed when the server has a \
+                 receipt key",
+                id
+            ),
+            None => format!("No query {}", id),
+        };
+        return Err(ApiError::new(ErrorCode::NotFound, message));
+    };
+    let signer = state.receipts.as_deref();
+    let inputs = proof.envelope.public_inputs;
+    Ok(QueryReceipt::new(id, inputs, receipt, signer).respond())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/queries",
+    tag = "queries",
+    params(ListQueriesParams),
+    responses(
+        (status = 200, description = "Matching queries, newest first", body = QueryListResponse),
+        (
+            status = 400,
+            description = "Invalid filters, page or limit (`invalid_request`)",
+            body = ErrorResponse
+        ),
+        AuthErrors,
+    )
+)]
+async fn list_queries(
+    State(state): State<SharedState>,
+    _auth: Auth<Auditor>,
+    ApiQuery(params): ApiQuery<ListQueriesParams>,
+) -> Result<Json<QueryListResponse>, ApiError> {
+    let page = params.page.unwrap_or(1);
+    let limit = params.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let mut validator = Validator::new();
+    if let Some(commitment) = &params.document_commitment {
+        validator.digest("document_commitment", commitment);
+    }
+    if let Some(model_hash) = &params.model_hash {
+        validator.digest("model_hash", model_hash);
+    }
+    if page == 0 {
+        validator.error("page", "starts at 1");
+    }
+    if limit == 0 || limit > MAX_PAGE_SIZE {
+        validator.error("limit", format!("must be between 1 and {}", MAX_PAGE_SIZE));
+    }
+    validator.finish()?;
+
+    let filter = QueryFilter {
+        document_commitment: params.document_commitment,
+        model_hash: params.model_hash,
+        since: params.since,
+    };
+    let offset = (page - 1).saturating_mul(limit);
+    let (queries, total) = state.store.list_queries(&filter, offset, limit).await?;
+    Ok(Json(QueryListResponse {
+        queries,
+        page,
+        limit,
+        total,
+    }))
+}
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/audit",
+    tag = "audit",
+    params(AuditParams),
+    responses(
+        (
+            status = 200,
+            description = "Matching verifications, oldest first: a page, or with \
+                           `format=ndjson` every entry after the cursor, one per line",
+            content(
+                (AuditPage = "application/json"),
+                (String = "application/x-ndjson")
+            )
+        ),
+        (
+            status = 400,
+            description = "Invalid filters, cursor or limit (`invalid_request`)",
+            body = ErrorResponse
+        ),
+        AuthErrors,
+    )
+)]
+async fn audit_trail(
+    State(state): State<SharedState>,
+    _auth: Auth<Auditor>,
+    ApiQuery(params): ApiQuery<AuditParams>,
+) -> Result<Response, ApiError> {
+    let limit = params.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let mut validator = Validator::new();
+    if let Some(tenant) = &params.tenant {
+        validator.name("tenant", tenant);
+    }
+    if limit == 0 || limit > MAX_PAGE_SIZE {
+        validator.error("limit", format!("must be between 1 and {}", MAX_PAGE_SIZE));
+    }
+    validator.finish()?;
+
+    let filter = params.filter();
+    let cursor = params.cursor.unwrap_or(0);
+    if params.format == Some(AuditFormat::Ndjson) {
+        return Ok(audit::export(state.store.clone(), filter, cursor));
+    }
+    let page = audit::page(state.store.as_ref(), &filter, cursor, limit).await?;
+    Ok(Json(page).into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/owners/{owner}/queries",
+    tag = "audit",
+    params(("owner" = String, Path, description = "Document owner"), OwnerHistoryParams),
+    responses(
+        (
+            status = 200,
+            description = "The owner's documents and models, and a page of their verifications, \
+                           oldest first",
+            body = OwnerHistory
+        ),
+        (
+            status = 400,
+            description = "Invalid owner, cursor or limit (`invalid_request`)",
+            body = ErrorResponse
+        ),
+        (
+            status = 403,
+            description = "The caller is neither the owner nor an admin (`forbidden`)",
+            body = ErrorResponse
+        ),
+        AuthErrors,
+    )
+)]
+async fn owner_history(
+    State(state): State<SharedState>,
+    auth: Auth<Auditor>,
+    ApiPath(owner): ApiPath<String>,
+    ApiQuery(params): ApiQuery<OwnerHistoryParams>,
+) -> Result<Json<OwnerHistory>, ApiError> {
+    let limit = params.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let mut validator = Validator::new();
+    validator.name("owner", &owner);
+    if limit == 0 || limit > MAX_PAGE_SIZE {
+        validator.error("limit", format!("must be between 1 and {}", MAX_PAGE_SIZE));
+    }
+    validator.finish()?;
+    if !auth.acts_for(&owner) {
+        return Err(ApiError::new(
+            ErrorCode::Forbidden,
+            format!("Only {} or an admin may read their history", owner),
+        ));
+    }
+
+    let history = owners::history(
+        state.store.as_ref(),
+        &owner,
+        params.cursor.unwrap_or(0),
+        limit,
+    )
+    .await?;
+    Ok(Json(history))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/stats",
+    tag = "audit",
+    params(StatsParams),
+    responses(
+        (status = 200, description = "Verification counts over the period", body = Stats),
+        (
+            status = 400,
+            description = "Invalid period or list length (`invalid_request`)",
+            body = ErrorResponse
+        ),
+        AuthErrors,
+    )
+)]
+async fn verification_stats(
+    State(state): State<SharedState>,
+    _auth: Auth<Auditor>,
+    ApiQuery(params): ApiQuery<StatsParams>,
+) -> Result<Json<Stats>, ApiError> {
+    let mut validator = Validator::new();
+    if let (Some(since), Some(until)) = (params.since, params.until) {
+        if until <= since {
+            validator.error("until", "must be after since");
+        }
+    }
+    if params
+        .top
+        .is_some_and(|top| top == 0 || top > stats::MAX_TOP)
+    {
+        validator.error("top", format!("must be between 1 and {}", stats::MAX_TOP));
+    }
+    validator.finish()?;
+
+    Ok(Json(stats::report(state.store.as_ref(), &params).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/nullifiers",
+    tag = "nullifiers",
+    responses(
+        (
+            status = 200,
+            description = "Epochs with recorded nullifiers, oldest first",
+            body = NullifierEpochs
+        ),
+        AuthErrors,
+    )
+)]
+async fn nullifier_epochs(
+    State(state): State<SharedState>,
+    _auth: Auth<Admin>,
+) -> Result<Json<NullifierEpochs>, ApiError> {
+    let epochs = nullifiers::epochs(state.store.as_ref(), &state.nullifiers).await?;
+    Ok(Json(epochs))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/nullifiers/{epoch}",
+    tag = "nullifiers",
+    params(("epoch" = u64, Path, description = "Epoch"), EpochNullifiersParams),
+    responses(
+        (
+            status = 200,
+            description = "A page of the epoch's nullifiers, in query order",
+            body = EpochNullifiers
+        ),
+        (
+            status = 400,
+            description = "Invalid epoch, cursor or limit (`invalid_request`)",
+            body = ErrorResponse
+        ),
+        AuthErrors,
+    )
+)]
+async fn epoch_nullifiers(
+    State(state): State<SharedState>,
+    _auth: Auth<Admin>,
+    ApiPath(epoch): ApiPath<u64>,
+    ApiQuery(params): ApiQuery<EpochNullifiersParams>,
+) -> Result<Json<EpochNullifiers>, ApiError> {
+    let limit = params.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let mut validator = Validator::new();
+    if limit == 0 || limit > MAX_PAGE_SIZE {
+        validator.error("limit", format!("must be between 1 and {}", MAX_PAGE_SIZE));
+    }
+    validator.finish()?;
+
+    let page = nullifiers::page(
+        state.store.as_ref(),
+        &state.nullifiers,
+        epoch,
+        params.cursor.unwrap_or(0),
+        limit,
+    )
+    .await?;
+    Ok(Json(page))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/nullifiers/{epoch}",
+    tag = "nullifiers",
+    params(("epoch" = u64, Path, description = "Epoch")),
+    responses(
+        (
+            status = 200,
+            description = "Nullifiers forgotten; the epoch's proofs verify again",
+            body = ExpiredNullifiers
+        ),
+        (status = 400, description = "Invalid epoch (`invalid_request`)", body = ErrorResponse),
+        AuthErrors,
+    )
+)]
+async fn expire_nullifiers(
+    State(state): State<SharedState>,
+    auth: Auth<Admin>,
+    ApiPath(epoch): ApiPath<u64>,
+) -> Result<Json<ExpiredNullifiers>, ApiError> {
+    let expired = state.store.expire_nullifiers(epoch).await?;
+    info!(
+        "Expired {} nullifiers of epoch {} (by {})",
+        expired,
+        epoch,
+        auth.subject()
+    );
+    Ok(Json(ExpiredNullifiers { epoch, expired }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/proof/generate",
+    tag = "proofs",
+    request_body(
+        content(
+            (openapi::QueryWitnessSchema = "application/json"),
+            (openapi::QueryWitnessSchema = "application/cbor"),
+            (openapi::QueryWitnessSchema = "application/msgpack")
+        )
+    ),
+    responses(
+        (
+            status = 200,
+            description = "Generated proof",
+            content(
+                (openapi::ProofEnvelopeSchema = "application/json"),
+                (openapi::ProofEnvelopeSchema = "application/cbor"),
+                (openapi::ProofEnvelopeSchema = "application/msgpack")
+            )
+        ),
+        (status = 400, description = "Invalid witness (`invalid_request`)", body = ErrorResponse),
+        (
+            status = 422,
+            description = "Model is not registered (`model_not_registered`)",
+            body = ErrorResponse
+        ),
+        (status = 501, description = "No proving key (`proving_disabled`)", body = ErrorResponse),
+        (
+            status = 503,
+            description = "Prover queue is full (`prover_busy`); see Retry-After",
+            body = ErrorResponse
+        ),
+        AuthErrors,
+    )
+)]
+async fn generate_proof(
+    State(state): State<SharedState>,
+    auth: Auth<Submitter>,
+    format: Format,
+    ApiBody(witness): ApiBody<QueryWitness>,
+) -> Result<Negotiated<ProofEnvelope>, ApiError> {
+    // The witness is private; log only who asked
+    info!("Generating query proof (by {})", auth.subject());
+
+    let Some(prover) = &state.prover else {
+        return Err(ApiError::new(
+            ErrorCode::ProvingDisabled,
+            "Proof generation is not enabled on this server",
+        ));
+    };
+    let mut validator = Validator::new();
+    validator.digest("document_commitment", &witness.document_commitment);
+    validator.digest("model_hash", &witness.model_hash);
+    validator.timestamp("timestamp", witness.timestamp, unix_now());
+    if let Some(nonce) = &witness.nonce {
+        validator.digest("nonce", nonce);
+    }
+    validator.finish()?;
+    witness
+        .validate()
+        .map_err(|e| ApiError::invalid_request(format!("Invalid witness: {}", e)))?;
+    require_registered_model(&state, &witness.model_hash).await?;
+
+    let public_inputs = PublicInputs {
+        document_commitment: witness.document_commitment.clone(),
+        model_hash: witness.model_hash.clone(),
+        timestamp: witness.timestamp,
+        nonce: witness.nonce.clone(),
+    };
+    let proof = prover.prove(witness).await?;
+    Ok(Negotiated(
+        format,
+        ProofEnvelope::new(proof, public_inputs).with_key_id(prover.key_id()),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/events/ws",
+    tag = "events",
+    params(EventParams),
+    responses(
+        (status = 101, description = "WebSocket of JSON events tagged with `type`"),
+        (
+            status = 400,
+            description = "Unknown event type (`invalid_request`)",
+            body = ErrorResponse
+        ),
+        AuthErrors,
+    )
+)]
+async fn subscribe_events(
+    State(state): State<SharedState>,
+    _auth: Auth<Auditor>,
+    ApiQuery(params): ApiQuery<EventParams>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, ApiError> {
+    let filter = EventFilter::from_params(params).map_err(ApiError::invalid_request)?;
+    let events = state.events.subscribe();
+    let shutdown = state.shutdown.wait();
+    Ok(ws.on_upgrade(move |socket| events::stream(socket, events, filter, shutdown)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/events",
+    tag = "events",
+    params(
+        EventParams,
+        ("Last-Event-ID" = Option<u64>, Header, description = "Resume after this query id"),
+    ),
+    responses(
+        (
+            status = 200,
+            description = "Server-Sent Events of query outcomes",
+            content_type = "text/event-stream",
+            body = String
+        ),
+        (
+            status = 400,
+            description = "Unknown event type or invalid Last-Event-ID (`invalid_request`)",
+            body = ErrorResponse
+        ),
+        AuthErrors,
+    )
+)]
+async fn stream_events(
+    State(state): State<SharedState>,
+    _auth: Auth<Auditor>,
+    ApiQuery(params): ApiQuery<EventParams>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let filter = EventFilter::from_params(params).map_err(ApiError::invalid_request)?;
+    let last_id = match headers.get("last-event-id") {
+        Some(value) => Some(
+            value
+                .to_str()
+                .ok()
+                .and_then(|id| id.parse().ok())
+                .ok_or_else(|| ApiError::invalid_request("Invalid Last-Event-ID"))?,
+        ),
+        None => None,
+    };
+
+    let events = state.events.subscribe();
+    let stream = events::query_stream(
+        state.store.clone(),
+        events,
+        filter,
+        last_id,
+        state.shutdown.wait(),
+    );
+    Ok(Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/webhooks",
+    tag = "webhooks",
+    params(IdempotencyHeader),
+    request_body = RegisterWebhookRequest,
+    responses(
+        (status = 201, description = "Webhook registered, with its secret", body = WebhookResponse),
+        (
+            status = 400,
+            description = "Invalid URL or secret (`invalid_request`)",
+            body = ErrorResponse
+        ),
+        IdempotencyErrors,
+        AuthErrors,
+    )
+)]
+async fn register_webhook(
+    State(state): State<SharedState>,
+    auth: Auth<Submitter>,
+    ApiJson(payload): ApiJson<RegisterWebhookRequest>,
+) -> Result<Response, ApiError> {
+    info!(
+        "Registering webhook {} (by {})",
+        payload.url,
+        auth.subject()
+    );
+
+    let mut validator = Validator::new();
+    validator.url("url", &payload.url);
+    if payload
+        .secret
+        .as_ref()
+        .is_some_and(|secret| secret.len() < webhooks::MIN_SECRET_LEN)
+    {
+        validator.error(
+            "secret",
+            format!("must be at least {} bytes", webhooks::MIN_SECRET_LEN),
+        );
+    }
+    validator.finish()?;
+
+    let secret = payload.secret.unwrap_or_else(webhooks::generate_secret);
+    let created_at = unix_now();
+    let id = state
+        .store
+        .add_webhook(&payload.url, &secret, auth.subject(), created_at)
+        .await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(WebhookResponse {
+            id,
+            url: payload.url,
+            created_at,
+            secret: Some(secret),
+        }),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhooks",
+    tag = "webhooks",
+    responses(
+        (
+            status = 200,
+            description = "The caller's webhooks, without secrets",
+            body = [WebhookResponse]
+        ),
+        AuthErrors,
+    )
+)]
+async fn list_webhooks(
+    State(state): State<SharedState>,
+    auth: Auth<Submitter>,
+) -> Result<Json<Vec<WebhookResponse>>, ApiError> {
+    let webhooks = state.store.webhooks(Some(auth.subject())).await?;
+    Ok(Json(
+        webhooks.into_iter().map(WebhookResponse::from).collect(),
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/webhooks/{id}",
+    tag = "webhooks",
+    params(("id" = u64, Path, description = "Webhook id")),
+    responses(
+        (status = 204, description = "Webhook removed"),
+        (
+            status = 404,
+            description = "The caller has no such webhook (`not_found`)",
+            body = ErrorResponse
+        ),
+        AuthErrors,
+    )
+)]
+async fn delete_webhook(
+    State(state): State<SharedState>,
+    auth: Auth<Submitter>,
+    ApiPath(id): ApiPath<u64>,
+) -> Result<StatusCode, ApiError> {
+    info!("Removing webhook {} (by {})", id, auth.subject());
+    if state.store.delete_webhook(id, auth.subject()).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::new(
+            ErrorCode::NotFound,
+            format!("No webhook {}", id),
+        ))
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/keys/verifying-key",
+    tag = "keys",
+    security(()),
+    params(VerifyingKeyParams),
+    responses(
+        (
+            status = 200,
+            description = "Compressed verifying key; ETag and X-Zkrag-Key-Fingerprint give its \
+                           digest",
+            content_type = "application/octet-stream",
+            body = Vec<u8>
+        ),
+        (status = 304, description = "Key matches If-None-Match"),
+        (
+            status = 400,
+            description = "Malformed fingerprint (`invalid_request`)",
+            body = ErrorResponse
+        ),
+        (
+            status = 404,
+            description = "No key with that fingerprint, or no current key (`not_found`)",
+            body = ErrorResponse
+        ),
+        (
+            status = 429,
+            description = "Rate limit exceeded (`rate_limited`); see Retry-After",
+            body = ErrorResponse
+        ),
+    )
+)]
+async fn verifying_key(
+    State(state): State<SharedState>,
+    headers: HeaderMap,
+    ApiQuery(params): ApiQuery<VerifyingKeyParams>,
+) -> Result<Response, ApiError> {
+    let verifier = state.verifier.read().unwrap();
+    let keys = verifier.keys();
+    let registered = match &params.fingerprint {
+        Some(fingerprint) => {
+            let mut validator = Validator::new();
+            validator.digest("fingerprint", fingerprint);
+            validator.finish()?;
+            keys.get(&fingerprint.to_ascii_lowercase()).ok_or_else(|| {
+                ApiError::new(
+                    ErrorCode::NotFound,
+                    format!("No verifying key {}", fingerprint),
+                )
+            })?
+        }
+        // Only when every key has retired, as startup fails without one
+        None => keys
+            .current(DOCUMENT_QUERY_CIRCUIT_ID)
+            .ok_or_else(|| ApiError::new(ErrorCode::NotFound, "No current verifying key"))?,
+    };
+    let key = KeyFile {
+        bytes: registered.key.to_bytes(),
+        digest: registered.key_id.clone(),
+        fingerprint: registered.key_id.clone(),
+        curve: registered.key.curve().as_str(),
+        circuit_id: registered.circuit_id.clone(),
+        file_name: VERIFYING_KEY_FILE,
+        immutable: params.fingerprint.is_some(),
+    };
+    Ok(key.respond(&headers))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/keys/verifying-keys",
+    tag = "keys",
+    security(()),
+    responses(
+        (status = 200, description = "Verifying keys and their schedules", body = KeyVersionList),
+        (
+            status = 429,
+            description = "Rate limit exceeded (`rate_limited`); see Retry-After",
+            body = ErrorResponse
+        ),
+    )
+)]
+async fn list_verifying_keys(State(state): State<SharedState>) -> Json<KeyVersionList> {
+    let verifier = state.verifier.read().unwrap();
+    Json(rotation::list(verifier.keys(), unix_now()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/keys/verifying-keys",
+    tag = "keys",
+    params(UploadKeyParams),
+    request_body(
+        description = "Compressed verifying key",
+        content_type = "application/octet-stream",
+        content = Vec<u8>
+    ),
+    responses(
+        (
+            status = 201,
+            description = "Key registered; `deprecated` is the key it replaces",
+            body = KeyRotationResponse
+        ),
+        (
+            status = 400,
+            description = "Invalid parameters or key (`invalid_request`)",
+            body = ErrorResponse
+        ),
+        AuthErrors,
+    )
+)]
+async fn upload_verifying_key(
+    State(state): State<SharedState>,
+    auth: Auth<Admin>,
+    ApiQuery(params): ApiQuery<UploadKeyParams>,
+    body: Result<Bytes, BytesRejection>,
+) -> Result<(StatusCode, Json<KeyRotationResponse>), ApiError> {
+    let body = body?;
+    let now = unix_now();
+    let _rotating = state.rotating.lock().await;
+    let mut keys = state.verifier.read().unwrap().keys().clone();
+    let (saved, response) = rotation::upload(&mut keys, &params, &body, &state.rotation, now)?;
+    for key in &saved {
+        state.store.save_verifying_key(key, now).await?;
+    }
+    *state.verifier.write().unwrap().keys_mut() = keys;
+    info!(
+        "Verifying key {} for {} activates at {} (by {})",
+        response.key.fingerprint,
+        response.key.circuit_id,
+        response.key.activates_at,
+        auth.subject()
+    );
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/keys/verifying-keys/{fingerprint}/deprecate",
+    tag = "keys",
+    params(("fingerprint" = String, Path, description = "Key id")),
+    request_body = DeprecateKeyRequest,
+    responses(
+        (status = 200, description = "Key rescheduled", body = KeyRotationResponse),
+        (
+            status = 400,
+            description = "Malformed fingerprint, or the key is its circuit's last \
+                           (`invalid_request`)",
+            body = ErrorResponse
+        ),
+        (status = 404, description = "No such key (`not_found`)", body = ErrorResponse),
+        AuthErrors,
+    )
+)]
+async fn deprecate_verifying_key(
+    State(state): State<SharedState>,
+    auth: Auth<Admin>,
+    ApiPath(fingerprint): ApiPath<String>,
+    ApiJson(request): ApiJson<DeprecateKeyRequest>,
+) -> Result<Json<KeyRotationResponse>, ApiError> {
+    let mut validator = Validator::new();
+    validator.digest("fingerprint", &fingerprint);
+    validator.finish()?;
+
+    let now = unix_now();
+    let _rotating = state.rotating.lock().await;
+    let mut keys = state.verifier.read().unwrap().keys().clone();
+    let (saved, response) =
+        rotation::deprecate(&mut keys, &fingerprint, &request, &state.rotation, now)?;
+    state.store.save_verifying_key(&saved, now).await?;
+    *state.verifier.write().unwrap().keys_mut() = keys;
+    info!(
+        "Verifying key {} retires at {:?} (by {})",
+        fingerprint,
+        response.key.retires_at,
+        auth.subject()
+    );
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/keys/proving-key",
+    tag = "keys",
+    responses(
+        (
+            status = 200,
+            description = "Compressed proving key; X-Zkrag-Key-Fingerprint names the verifying \
+                           key it pairs with",
+            content_type = "application/octet-stream",
+            body = Vec<u8>
+        ),
+        (status = 304, description = "Key matches If-None-Match"),
+        (status = 501, description = "No proving key (`proving_disabled`)", body = ErrorResponse),
+        AuthErrors,
+    )
+)]
+async fn proving_key(
+    State(state): State<SharedState>,
+    auth: Auth<Admin>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let Some(prover) = &state.prover else {
+        return Err(ApiError::new(
+            ErrorCode::ProvingDisabled,
+            "This server has no proving key",
+        ));
+    };
+    info!("Sending the proving key (to {})", auth.subject());
+    let bytes = tokio::fs::read(prover.key_path())
+        .await
+        .map_err(|e| ApiError::internal("Reading proving key", e))?;
+    let key = KeyFile {
+        digest: key_digest(&bytes),
+        bytes,
+        fingerprint: prover.key_id().to_string(),
+        curve: Curve::Bn254.as_str(),
+        circuit_id: DOCUMENT_QUERY_CIRCUIT_ID.to_string(),
+        file_name: PROVING_KEY_FILE,
+        immutable: false,
+    };
+    Ok(key.respond(&headers))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/keys/receipt-key",
+    tag = "keys",
+    security(()),
+    responses(
+        (
+            status = 200,
+            description = "Public key that verification receipts are signed with",
+            body = ReceiptKeyResponse
+        ),
+        (
+            status = 404,
+            description = "This server doesn't sign receipts (`not_found`)",
+            body = ErrorResponse
+        ),
+        (
+            status = 429,
+            description = "Rate limit exceeded (`rate_limited`); see Retry-After",
+            body = ErrorResponse
+        ),
+    )
+)]
+async fn receipt_key(
+    State(state): State<SharedState>,
+) -> Result<Json<ReceiptKeyResponse>, ApiError> {
+    state
+        .receipts
+        .as_deref()
+        .map(|signer| Json(signer.into()))
+        .ok_or_else(|| ApiError::new(ErrorCode::NotFound, "This server doesn't sign receipts"))
+}
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "service",
+    security(()),
+    responses((
+        status = 200,
+        description = "Prometheus text format",
+        content_type = "text/plain",
+        body = String
+    ))
+)]
+async fn export_metrics(State(state): State<SharedState>) -> Response {
+    let text = metrics::render(
+        &state.metrics.snapshot(),
+        &state.verifier.read().unwrap().keys().key_info(),
+        state.prover.as_ref(),
+    );
     (
-        StatusCode::OK,
-        Json(serde_json::json!({
-            "id": id,
-            "verified": true
-        })),
+        [(CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        text,
     )
         .into_response()
 }
 
-async fn health_check() -> &'static str {
+#[utoipa::path(
+    get,
+    path = "/livez",
+    tag = "service",
+    security(()),
+    responses((
+        status = 200,
+        description = "Process is serving",
+        content_type = "text/plain",
+        body = String
+    ))
+)]
+async fn liveness() -> &'static str {
     "OK"
 }
 
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "service",
+    security(()),
+    responses(
+        (status = 200, description = "Every component is working", body = Health),
+        (status = 503, description = "A component is failing", body = Health),
+    )
+)]
+async fn health(State(state): State<SharedState>) -> Response {
+    let verifying_key = {
+        let verifier = state.verifier.read().unwrap();
+        let current = verifier.keys().current(DOCUMENT_QUERY_CIRCUIT_ID);
+        state.key_checks.check(current)
+    };
+    let database = ComponentHealth::probe(READINESS_TIMEOUT, state.store.ping()).await;
+    let kernel = match &state.kernel {
+        Some(kernel) => {
+            Some(ComponentHealth::probe(READINESS_TIMEOUT, kernel.peek(&["counts"])).await)
+        }
+        None => None,
+    };
+    let health = Health::new(Components {
+        verifying_key,
+        database,
+        kernel,
+    });
+    let status = match health.status {
+        health::Status::Ok => StatusCode::OK,
+        health::Status::Failing => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (status, Json(health)).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "service",
+    security(()),
+    responses(
+        (status = 200, description = "Ready for traffic", body = Readiness),
+        (
+            status = 503,
+            description = "Not ready: no verifying key, database unreachable, or shutting down",
+            body = Readiness
+        ),
+    )
+)]
+async fn readiness(State(state): State<SharedState>) -> Response {
+    let verifying_key_loaded = state
+        .verifier
+        .read()
+        .unwrap()
+        .keys()
+        .current(DOCUMENT_QUERY_CIRCUIT_ID)
+        .is_some();
+    let database_reachable = tokio::time::timeout(READINESS_TIMEOUT, state.store.ping())
+        .await
+        .is_ok_and(|ping| ping.is_ok());
+    let mut readiness = Readiness::new(
+        verifying_key_loaded,
+        database_reachable,
+        state.shutdown.is_draining(),
+    );
+    if let Some(kernel) = &state.kernel {
+        let reachable = tokio::time::timeout(READINESS_TIMEOUT, kernel.peek(&["counts"]))
+            .await
+            .is_ok_and(|peek| peek.is_ok());
+        readiness = readiness.with_kernel(reachable);
+    }
+    let status = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness)).into_response()
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Initialize tracing
-    tracing_subscriber::fmt::init();
+    let cli = Cli::parse();
+    let config = Config::load(&cli, &|name| std::env::var(name).ok())?;
+    let telemetry = telemetry::init(&config.telemetry)?;
+    if let Some(endpoint) = &config.telemetry.otlp_endpoint {
+        info!("Exporting spans to {}", endpoint);
+    }
+
+    // Load the verifying key and policies
+    let metrics = Arc::new(VerifierMetrics::new());
+    // Filled from the store once it's open
+    let revocations = Arc::new(RevocationRegistry::new(RevocationList::new()));
+    let mut builder = QueryVerifier::builder()
+        .metrics(metrics.clone())
+        .revocations(revocations.clone());
+    builder = match (&config.keys.verifying_key, config.key_dir()) {
+        (Some(path), _) => builder.key_path(path),
+        (None, Some(dir)) => builder.key_dir(dir),
+        (None, None) => builder,
+    };
+    if let Some(secs) = config.policy.max_proof_age_secs {
+        builder = builder.max_proof_age(Duration::from_secs(secs));
+    }
+    if let Some(path) = &config.policy.file {
+        builder = builder.policy_path(path);
+    }
+    let receipts = match &config.keys.receipt_key {
+        Some(path) => {
+            let signer = Arc::new(keys::load_receipt_signer(path)?);
+            info!("Signing verification receipts as {}", signer.key_id());
+            builder = builder.receipt_signer(signer.clone());
+            Some(signer)
+        }
+        None => None,
+    };
+    let mut verifier = builder.build()?;
+
+    // Open the database, applying pending migrations
+    let store = SqliteStore::open(&config.database.url).await?;
+    info!("Using database {}", config.database.url);
+    let archive = config.archive.open()?;
+    if let Some(url) = &config.archive.url {
+        info!("Archiving proofs to {}", url);
+    }
+
+    // Apply keys rotated in since the last start
+    let rotated = store.verifying_keys().await?;
+    rotation::restore(verifier.keys_mut(), &rotated)?;
+    if !rotated.is_empty() {
+        info!("Restored {} rotated verifying keys", rotated.len());
+    }
+    if verifier.keys().current(DOCUMENT_QUERY_CIRCUIT_ID).is_none() {
+        anyhow::bail!(
+            "No verifying key in {}; set keys.dir (ZKRAG_KEY_DIR, --key-dir) to the \
+             directory holding it",
+            config
+                .key_dir()
+                .map_or_else(|| "~/.zkrag/keys".into(), |dir| dir.display().to_string())
+        );
+    }
+
+    let revoked = store.revocations().await?;
+    info!(
+        "Revoked: {} documents, {} models",
+        revoked.document_commitments.len(),
+        revoked.model_hashes.len()
+    );
+    revocations.replace(revoked);
+
+    let documents = DocumentRegistry::new(store.document_hashes().await?);
+    info!(
+        "Document registry: {} hashes, commitment {}",
+        documents.len(),
+        documents.commitment()
+    );
+
+    let auth = match config.auth() {
+        Some(config) => {
+            info!("Authenticating bearer tokens from {}", config.issuer);
+            Some(Arc::new(Authenticator::new(config).await?))
+        }
+        None => {
+            warn!("No auth issuer configured; requests are not authenticated");
+            None
+        }
+    };
 
-    // Initialize kernel state
-    let kernel = Arc::new(RwLock::new(MockKernel::new()));
+    let limits = &config.limits;
+    info!(
+        "Rate limits per client: {} verifications/min, {} other requests/min",
+        limits.verify_per_minute, limits.read_per_minute
+    );
+    let (verify_limiter, read_limiter) = match config.cluster.connect().await? {
+        Some(shared) => {
+            info!("Sharing rate limits with other replicas through Redis");
+            (
+                limits.shared_limiter(
+                    shared.clone(),
+                    "verify",
+                    limits.verify_per_minute,
+                    auth.clone(),
+                ),
+                limits.shared_limiter(shared, "read", limits.read_per_minute, auth.clone()),
+            )
+        }
+        None => (
+            limits.limiter(limits.verify_per_minute, auth.clone()),
+            limits.limiter(limits.read_per_minute, auth.clone()),
+        ),
+    };
 
-    // Configure CORS
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+    // Start the prover pool if there's a proving key to serve
+    let prover = match config.proving_key() {
+        Some(path) => {
+            let pool = ProverPool::start(&path, &config.prover)?;
+            info!(
+                "Generating proofs with {} ({} workers, queue of {})",
+                path.display(),
+                config.prover.workers,
+                config.prover.queue
+            );
+            let current = verifier.keys().current(DOCUMENT_QUERY_CIRCUIT_ID);
+            if current.map(|key| key.key_id.as_str()) != Some(pool.key_id()) {
+                warn!("Proving key doesn't match the verifying key; its proofs won't verify here");
+            }
+            Some(pool)
+        }
+        None => {
+            info!("No proving key; proof generation is disabled");
+            None
+        }
+    };
+
+    // The kernel is connected on first use, so it may start after the server
+    let kernel = config.kernel.socket.clone().map(|socket| {
+        info!("Poking the NockApp kernel at {}", socket.display());
+        Kernel::new(socket, Duration::from_secs(config.kernel.timeout_secs))
+    });
+
+    let state = Arc::new(AppState {
+        store: Arc::new(store),
+        auth,
+        documents: RwLock::new(documents),
+        verifier: std::sync::RwLock::new(verifier),
+        rotation: config.rotation.clone(),
+        rotating: tokio::sync::Mutex::new(()),
+        revocations,
+        metrics,
+        key_checks: KeyChecks::default(),
+        receipts,
+        archive,
+        prover,
+        events: EventBus::default(),
+        shutdown: Shutdown::new(config.shutdown_timeout()),
+        kernel,
+        idempotency: config.idempotency.clone(),
+        guard: config.guard.clone(),
+        models: config.models.clone(),
+        challenge: config.challenge.clone(),
+        nullifiers: config.nullifiers.clone(),
+    });
+
+    let cors = config.cors.layer()?;
+    if config.cors.allows_any_origin() {
+        warn!("CORS allows any origin; list the origins in production");
+    }
+    retention::spawn(
+        state.store.clone(),
+        config.retention.clone(),
+        config.idempotency.window_secs,
+        state.shutdown.wait(),
+    );
+    webhooks::spawn(
+        state.store.clone(),
+        state.events.subscribe(),
+        state.shutdown.wait(),
+    );
 
     // Build router
+    let idempotent = middleware::from_fn_with_state(state.clone(), idempotency::idempotent);
+    let guard = &config.guard;
+    let verifications =
+        middleware::from_fn_with_state(guard.verifications(), guard::concurrency_limit);
+    let verify_routes = guarded(
+        Router::new()
+            .route(
+                "/api/v1/query/verify",
+                post(verify_query)
+                    .layer(guard.verify_body_limit())
+                    .layer(idempotent.clone())
+                    .layer(verifications.clone()),
+            )
+            .route(
+                "/api/v2/query/verify",
+                post(verify_envelope)
+                    .layer(guard.verify_body_limit())
+                    .layer(idempotent.clone())
+                    .layer(verifications),
+            )
+            .route("/api/v1/proof/generate", post(generate_proof))
+            .route("/api/v1/challenge", post(issue_challenge)),
+        guard,
+    );
+    let verify_routes = limited(verify_routes, verify_limiter.clone());
+    let api_routes = guarded(
+        Router::new()
+            .route(
+                "/api/v1/document/register",
+                post(register_document).layer(idempotent.clone()),
+            )
+            .route(
+                "/api/v1/document/upload",
+                post(upload_document).layer(idempotent.clone()),
+            )
+            .route("/api/v1/document/commitment", get(document_commitment))
+            .route("/api/v1/document/:id/revoke", post(revoke_document))
+            // Shares the `:id` segment with revoke; it's a hash here
+            .route("/api/v1/document/:id/proof", get(document_proof))
+            .route(
+                "/api/v1/model/register",
+                post(register_model).layer(idempotent.clone()),
+            )
+            .route("/api/v1/model/:id/revoke", post(revoke_model))
+            .route("/api/v1/query/:id", get(get_query))
+            .route("/api/v1/query/:id/proof", get(get_query_proof))
+            .route("/api/v1/query/:id/receipt", get(get_query_receipt))
+            .route("/api/v1/queries", get(list_queries))
+            .route("/api/v1/audit", get(audit_trail))
+            .route("/api/v1/owners/:owner/queries", get(owner_history))
+            .route("/api/v1/stats", get(verification_stats))
+            .route("/api/v1/nullifiers", get(nullifier_epochs))
+            .route(
+                "/api/v1/nullifiers/:epoch",
+                get(epoch_nullifiers).delete(expire_nullifiers),
+            )
+            .route("/api/v1/events", get(stream_events))
+            .route("/api/v1/events/ws", get(subscribe_events))
+            .route(
+                "/api/v1/webhooks",
+                post(register_webhook).layer(idempotent).get(list_webhooks),
+            )
+            .route("/api/v1/webhooks/:id", delete(delete_webhook))
+            .route("/api/v1/keys/verifying-key", get(verifying_key))
+            .route(
+                "/api/v1/keys/verifying-keys",
+                get(list_verifying_keys).post(upload_verifying_key),
+            )
+            .route(
+                "/api/v1/keys/verifying-keys/:fingerprint/deprecate",
+                post(deprecate_verifying_key),
+            )
+            .route("/api/v1/keys/proving-key", get(proving_key))
+            .route("/api/v1/keys/receipt-key", get(receipt_key)),
+        guard,
+    );
+    let api_routes = limited(api_routes, read_limiter);
+    // Outside the guard, whose timeout and body cap don't suit weight files
+    let upload_routes = limited(
+        Router::new().route("/api/v1/model/upload", post(upload_model)),
+        verify_limiter,
+    );
     let app = Router::new()
-        .route("/health", get(health_check))
-        .route("/api/v1/document/register", post(register_document))
-        .route("/api/v1/model/register", post(register_model))
-        .route("/api/v1/query/verify", post(verify_query))
-        .route("/api/v1/query/:id", get(get_query))
+        .route("/health", get(health))
+        .route("/livez", get(liveness))
+        .route("/readyz", get(readiness))
+        .route("/metrics", get(export_metrics))
+        .merge(SwaggerUi::new("/api/v1/docs").url("/api/v1/openapi.json", ApiDoc::openapi()))
+        .merge(verify_routes)
+        .merge(api_routes)
+        .merge(upload_routes)
         .layer(cors)
-        .with_state(kernel);
+        .layer(middleware::from_fn(telemetry::trace_request))
+        .with_state(state.clone());
 
     // Start server
-    let addr = "0.0.0.0:8080";
-    info!("ZK-RAG Verifier starting on {}", addr);
-
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    let addr = config.server.bind;
+    // Peer addresses identify clients for rate limiting
+    let app = app.into_make_service_with_connect_info::<SocketAddr>();
+    let tls = config.tls()?;
+    let signal_state = state.clone();
+    tokio::spawn(async move { signal_state.shutdown.on_signal().await });
+    // Each server stops accepting connections once shutdown begins and
+    // returns when the open ones have closed
+    let server = async {
+        match tls {
+            Some(tls) => {
+                let config = tls.load().await?;
+                info!(
+                    "ZK-RAG Verifier starting on {} with TLS certificate {}",
+                    addr,
+                    tls.cert.display()
+                );
+                tls.reload_on_sighup(config.clone())?;
+                let handle = axum_server::Handle::new();
+                let shutdown_handle = handle.clone();
+                let shutdown = state.shutdown.wait();
+                tokio::spawn(async move {
+                    shutdown.await;
+                    shutdown_handle.graceful_shutdown(None);
+                });
+                axum_server::bind_rustls(addr, config)
+                    .handle(handle)
+                    .serve(app)
+                    .await?;
+            }
+            None => {
+                info!("ZK-RAG Verifier starting on {}", addr);
+                let listener = tokio::net::TcpListener::bind(addr).await?;
+                axum::serve(listener, app)
+                    .with_graceful_shutdown(state.shutdown.wait())
+                    .await?;
+            }
+        }
+        anyhow::Ok(())
+    };
+    let deadline = async {
+        state.shutdown.wait().await;
+        tokio::time::sleep(state.shutdown.timeout).await;
+    };
+    tokio::select! {
+        result = server => result?,
+        () = deadline => warn!(
+            "Requests still in flight after {}s; stopping anyway",
+            state.shutdown.timeout.as_secs()
+        ),
+    }
 
+    state.store.close().await;
+    info!("Shut down");
+    telemetry.shutdown();
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+    use serde_json::{json, Value};
+    use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::UnixListener;
+    use tower::ServiceExt;
+
+    use crate::jets::tests::{octs_noun, prove};
+    use crate::jets::JETS;
+    use crate::kernel::KernelError;
+    use crate::noun::Noun;
+    use crate::store::test_store;
+
+    const MODEL_HASH: &str = "cdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcd";
+
+    /// State refusing replays, verifying proofs under `vk` and poking
+    /// `kernel`
+    async fn test_state(store: SqliteStore, vk: Vec<u8>, kernel: Option<Kernel>) -> SharedState {
+        store.register_model(MODEL_HASH, "bert", 0).await.unwrap();
+        let metrics = Arc::new(VerifierMetrics::new());
+        let revocations = Arc::new(RevocationRegistry::new(RevocationList::new()));
+        let verifier = QueryVerifier::builder()
+            .key_bytes(vk)
+            .metrics(metrics.clone())
+            .revocations(revocations.clone())
+            .build()
+            .unwrap();
+        Arc::new(AppState {
+            store: Arc::new(store),
+            auth: None,
+            documents: RwLock::new(DocumentRegistry::new(Vec::new())),
+            verifier: std::sync::RwLock::new(verifier),
+            rotation: RotationConfig::default(),
+            rotating: tokio::sync::Mutex::new(()),
+            revocations,
+            metrics,
+            key_checks: KeyChecks::default(),
+            receipts: None,
+            archive: None,
+            prover: None,
+            events: EventBus::default(),
+            shutdown: Shutdown::new(Duration::from_secs(1)),
+            kernel,
+            idempotency: IdempotencyConfig::default(),
+            guard: GuardConfig::default(),
+            models: ModelsConfig::default(),
+            challenge: ChallengeConfig::default(),
+            nullifiers: NullifierConfig {
+                reject_replays: true,
+                ..NullifierConfig::default()
+            },
+        })
+    }
+
+    /// Path for a test's kernel socket, free for it to bind
+    fn kernel_socket(test: &str) -> std::path::PathBuf {
+        let socket =
+            std::env::temp_dir().join(format!("zkrag-{}-kernel-{}.sock", test, std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+        socket
+    }
+
+    /// Effects of the kernel's `%verify-query` arm on `cause`, with the jet
+    /// of `++verify-groth16` run in place of the gate like the runtime does
+    fn verify_query_effects(cause: &Noun) -> Noun {
+        let (tag, fields) = cause.as_cell().unwrap();
+        assert_eq!(tag.as_cord().unwrap(), "verify-query");
+        let (vk, rest) = fields.as_cell().unwrap();
+        let (proof, rest) = rest.as_cell().unwrap();
+        let octs = |hex: &Noun| octs_noun(&hex::decode(hex.as_cord().unwrap()).unwrap());
+        let sample = Noun::cell(octs(vk), Noun::cell(octs(proof), rest.clone()));
+        let (_, jet) = JETS
+            .iter()
+            .find(|(hint, _)| **hint == ["zkrag", "verify-groth16"])
+            .unwrap();
+        let status: u16 = if jet(&sample).unwrap() == Noun::atom(&[0]) {
+            201
+        } else {
+            422
+        };
+        Noun::list(vec![Noun::tuple(vec![
+            Noun::cord("http-response"),
+            Noun::atom(&status.to_le_bytes()),
+            Noun::cord("{}"),
+        ])])
+    }
+
+    /// Kernel on `socket` answering verified queries, and the count of its
+    /// pokes
+    fn fake_kernel(socket: &Path) -> Arc<AtomicUsize> {
+        let listener = UnixListener::bind(socket).unwrap();
+        let pokes = Arc::new(AtomicUsize::new(0));
+        let counted = pokes.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let counted = counted.clone();
+                tokio::spawn(async move {
+                    while let Ok(len) = stream.read_u64_le().await {
+                        let mut frame = vec![0; len as usize];
+                        stream.read_exact(&mut frame).await.unwrap();
+                        counted.fetch_add(1, Ordering::SeqCst);
+                        let request = Noun::cue(&frame).unwrap();
+                        let (_, cause) = request.as_cell().unwrap();
+                        let effects = verify_query_effects(cause);
+                        let ack = Noun::cell(Noun::cord("ack"), effects).jam();
+                        stream.write_u64_le(ack.len() as u64).await.unwrap();
+                        stream.write_all(&ack).await.unwrap();
+                    }
+                });
+            }
+        });
+        pokes
+    }
+
+    async fn verify(
+        state: &SharedState,
+        proof: &[u8],
+        inputs: &PublicInputs,
+    ) -> (StatusCode, Value) {
+        let body = json!({
+            "proof": hex::encode(proof),
+            "document_commitment": inputs.document_commitment,
+            "model_hash": inputs.model_hash,
+            "timestamp": inputs.timestamp,
+            "nonce": inputs.nonce,
+        });
+        let request = Request::post("/api/v1/query/verify")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = Router::new()
+            .route("/api/v1/query/verify", post(verify_query))
+            .with_state(state.clone())
+            .oneshot(request)
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_nonce_given_back_when_recording_fails() {
+        let socket = kernel_socket("nonce");
+        let store = test_store().await;
+        let challenge =
+            challenge::issue(&store, &ChallengeConfig::default(), "anonymous", unix_now())
+                .await
+                .unwrap();
+        let inputs = PublicInputs {
+            document_commitment: "ab".repeat(32),
+            model_hash: MODEL_HASH.to_string(),
+            timestamp: unix_now(),
+            nonce: Some(challenge.nonce),
+        };
+        let (vk, proof) = prove(&inputs);
+        let kernel = Kernel::new(socket.clone(), Duration::from_secs(10));
+        let state = test_state(store, vk, Some(kernel)).await;
+
+        // The kernel isn't up yet, so the verified query can't be recorded
+        let (status, body) = verify(&state, &proof, &inputs).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["code"], "kernel_unavailable");
+
+        // The nonce was given back, and is spent once the proof is recorded
+        let pokes = fake_kernel(&socket);
+        let (status, body) = verify(&state, &proof, &inputs).await;
+        assert_eq!(status, StatusCode::CREATED, "{}", body);
+        assert_eq!(body["valid"], true);
+        let (status, body) = verify(&state, &proof, &inputs).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["code"], "challenge_invalid");
+        assert_eq!(pokes.load(Ordering::SeqCst), 1);
+        std::fs::remove_file(&socket).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_replay_race_pokes_kernel_once() {
+        let socket = kernel_socket("replay");
+        let inputs = PublicInputs {
+            document_commitment: "ab".repeat(32),
+            model_hash: MODEL_HASH.to_string(),
+            timestamp: unix_now(),
+            nonce: None,
+        };
+        let (vk, proof) = prove(&inputs);
+        let kernel = Kernel::new(socket.clone(), Duration::from_secs(10));
+        let state = test_state(test_store().await, vk, Some(kernel)).await;
+
+        // A failed poke leaves the nullifier unclaimed
+        let (status, _) = verify(&state, &proof, &inputs).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        let nullifier = zkrag_verifier::nullifier::nullifier(&inputs);
+        assert!(!state.store.nullifier_recorded(&nullifier).await.unwrap());
+
+        // Of two submissions of the same proof, only the one that claims the
+        // nullifier is poked
+        let pokes = fake_kernel(&socket);
+        let (first, second) = tokio::join!(
+            verify(&state, &proof, &inputs),
+            verify(&state, &proof, &inputs)
+        );
+        let mut statuses = [first.0, second.0];
+        statuses.sort();
+        assert_eq!(statuses, [StatusCode::OK, StatusCode::CREATED]);
+        let replayed = if first.0 == StatusCode::OK {
+            first.1
+        } else {
+            second.1
+        };
+        assert_eq!(replayed["reason"]["code"], "replayed");
+        assert_eq!(pokes.load(Ordering::SeqCst), 1);
+        std::fs::remove_file(&socket).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_kernel_checks_proofs_with_jet() {
+        let socket = kernel_socket("jet");
+        let store = test_store().await;
+        let challenge =
+            challenge::issue(&store, &ChallengeConfig::default(), "anonymous", unix_now())
+                .await
+                .unwrap();
+        let inputs = PublicInputs {
+            document_commitment: "ab".repeat(32),
+            model_hash: MODEL_HASH.to_string(),
+            timestamp: unix_now(),
+            nonce: Some(challenge.nonce),
+        };
+        let (vk, proof) = prove(&inputs);
+        let pokes = fake_kernel(&socket);
+        let kernel = Kernel::new(socket.clone(), Duration::from_secs(10));
+        let state = test_state(store, vk.clone(), Some(kernel)).await;
+
+        // The driver sends the kernel what its check needs, nonce included
+        let (status, body) = verify(&state, &proof, &inputs).await;
+        assert_eq!(status, StatusCode::CREATED, "{}", body);
+        assert_eq!(pokes.load(Ordering::SeqCst), 1);
+
+        // Without the nonce, the jet refuses the proof
+        let cause = Cause::VerifyQuery {
+            verifying_key: hex::encode(&vk),
+            proof: hex::encode(&proof),
+            commitment: inputs.document_commitment.clone(),
+            model_hash: inputs.model_hash.clone(),
+            timestamp: inputs.timestamp,
+            nonce: None,
+        };
+        let kernel = state.kernel.as_ref().unwrap();
+        match kernel.poke(&cause).await.unwrap_err() {
+            KernelError::Rejected { status, .. } => assert_eq!(status, 422),
+            error => panic!("unexpected error {}", error),
+        }
+        std::fs::remove_file(&socket).unwrap();
+    }
+}
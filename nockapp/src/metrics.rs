@@ -0,0 +1,152 @@
+// Prometheus metrics
+//
+// `GET /metrics` renders the verifier's counters and latency histogram, the
+// prover pool's queue depth and proving latency, and which keys are loaded,
+// in the Prometheus text format. Alert on failures with e.g.
+// `rate(zkrag_verifications_total{outcome!="valid"}[5m])`.
+//
+// Like /health the route needs no token, so keep it to the scraper's network.
+
+use std::fmt::{Display, Write};
+use zkrag_verifier::metrics::HistogramSnapshot;
+use zkrag_verifier::{MetricsSnapshot, VerifyingKeyInfo};
+
+use crate::prover::ProverPool;
+
+/// Prometheus text format being written
+#[derive(Default)]
+struct Exposition {
+    text: String,
+}
+
+impl Exposition {
+    // Writing to a String can't fail
+    fn family(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.text, "# HELP {} {}", name, help);
+        let _ = writeln!(self.text, "# TYPE {} {}", name, kind);
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: impl Display) {
+        self.text.push_str(name);
+        if !labels.is_empty() {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(label, value)| format!("{}=\"{}\"", label, escape(value)))
+                .collect();
+            let _ = write!(self.text, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(self.text, " {}", value);
+    }
+
+    fn histogram(&mut self, name: &str, help: &str, histogram: &HistogramSnapshot) {
+        self.family(name, "histogram", help);
+        let bucket = format!("{}_bucket", name);
+        for (bound, count) in &histogram.buckets {
+            self.sample(&bucket, &[("le", &bound.to_string())], count);
+        }
+        self.sample(&bucket, &[("le", "+Inf")], histogram.count);
+        self.sample(&format!("{}_sum", name), &[], histogram.sum);
+        self.sample(&format!("{}_count", name), &[], histogram.count);
+    }
+}
+
+/// Label value with `\`, `"` and newlines escaped
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Render the service's metrics
+pub fn render(
+    verifier: &MetricsSnapshot,
+    keys: &[VerifyingKeyInfo],
+    prover: Option<&ProverPool>,
+) -> String {
+    let mut out = Exposition::default();
+
+    out.family(
+        "zkrag_verifications_total",
+        "counter",
+        "Query proofs verified, by outcome: valid or the failure code",
+    );
+    out.sample(
+        "zkrag_verifications_total",
+        &[("outcome", "valid")],
+        verifier.valid_total,
+    );
+    for (code, count) in &verifier.failures_by_reason {
+        out.sample("zkrag_verifications_total", &[("outcome", code)], count);
+    }
+    out.histogram(
+        "zkrag_verification_duration_seconds",
+        "Time to verify a query proof",
+        &verifier.latency_seconds,
+    );
+
+    out.family(
+        "zkrag_verifying_key_info",
+        "gauge",
+        "Loaded verifying keys, by circuit and key id",
+    );
+    for key in keys {
+        out.sample(
+            "zkrag_verifying_key_info",
+            &[
+                ("circuit_id", &key.circuit_id),
+                ("key_id", &key.fingerprint),
+            ],
+            1,
+        );
+    }
+
+    out.family(
+        "zkrag_proving_key_loaded",
+        "gauge",
+        "Whether a proving key is loaded for proof generation",
+    );
+    out.sample("zkrag_proving_key_loaded", &[], u8::from(prover.is_some()));
+    if let Some(prover) = prover {
+        out.family(
+            "zkrag_prover_queue_depth",
+            "gauge",
+            "Witnesses waiting for a prover worker",
+        );
+        out.sample("zkrag_prover_queue_depth", &[], prover.queue_depth());
+        out.family("zkrag_prover_workers", "gauge", "Prover worker threads");
+        out.sample("zkrag_prover_workers", &[], prover.workers());
+        out.histogram(
+            "zkrag_proof_generation_duration_seconds",
+            "Time to generate a query proof, excluding time queued",
+            &prover.latency(),
+        );
+    }
+
+    out.text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use zkrag_verifier::VerifierMetrics;
+
+    #[test]
+    fn test_render() {
+        let metrics = VerifierMetrics::new();
+        metrics.record_verification(None, Duration::from_millis(3));
+        metrics.record_verification(Some("pairing_failed"), Duration::from_millis(30));
+
+        let text = render(&metrics.snapshot(), &[], None);
+        assert!(text.contains("# TYPE zkrag_verifications_total counter\n"));
+        assert!(text.contains("zkrag_verifications_total{outcome=\"valid\"} 1\n"));
+        assert!(text.contains("zkrag_verifications_total{outcome=\"pairing_failed\"} 1\n"));
+        assert!(text.contains("zkrag_verification_duration_seconds_bucket{le=\"0.005\"} 1\n"));
+        assert!(text.contains("zkrag_verification_duration_seconds_bucket{le=\"+Inf\"} 2\n"));
+        assert!(text.contains("zkrag_verification_duration_seconds_count 2\n"));
+        assert!(text.contains("zkrag_proving_key_loaded 0\n"));
+        assert!(!text.contains("zkrag_prover_queue_depth"));
+        assert_eq!(escape("a\"b\\c"), "a\\\"b\\\\c");
+    }
+}
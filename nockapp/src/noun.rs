@@ -0,0 +1,305 @@
+// Nouns and their serialization
+//
+// The Hoon kernel speaks nouns: an atom is an unsigned integer of any size,
+// a cell is a pair of nouns. Text is a cord, an atom holding the UTF-8 bytes
+// least significant first, and `%tags` are cords too. Lists end in `~`, the
+// atom 0.
+//
+// Nouns cross the kernel socket jammed, Nock's standard bit-level encoding,
+// in which a subtree may refer back to an identical one encoded earlier. We
+// jam without back-references, which every decoder accepts, and cue (decode)
+// them in whatever the kernel sends.
+
+use std::collections::HashMap;
+
+/// Atom or cell
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Noun {
+    /// Little-endian bytes with no trailing zeros; 0 is empty
+    Atom(Vec<u8>),
+    Cell(Box<Noun>, Box<Noun>),
+}
+
+/// Why bytes didn't cue into a noun
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum CueError {
+    #[error("jammed noun is truncated")]
+    Truncated,
+    #[error("back-reference to unknown offset {0}")]
+    BadReference(u64),
+    #[error("atom length is out of range")]
+    TooLong,
+}
+
+impl Noun {
+    pub fn atom(bytes: &[u8]) -> Self {
+        let len = bytes.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+        Noun::Atom(bytes[..len].to_vec())
+    }
+
+    /// `~`
+    pub fn null() -> Self {
+        Noun::Atom(Vec::new())
+    }
+
+    /// Cord holding `text`
+    pub fn cord(text: &str) -> Self {
+        Self::atom(text.as_bytes())
+    }
+
+    pub fn cell(head: Noun, tail: Noun) -> Self {
+        Noun::Cell(Box::new(head), Box::new(tail))
+    }
+
+    /// Right-nested tuple `[a b c]`; `items` must not be empty
+    pub fn tuple(items: Vec<Noun>) -> Self {
+        let mut items = items.into_iter().rev();
+        let last = items.next().expect("tuple needs at least one noun");
+        items.fold(last, |tail, head| Noun::cell(head, tail))
+    }
+
+    /// Null-terminated list `~[a b c]`
+    pub fn list(items: Vec<Noun>) -> Self {
+        items
+            .into_iter()
+            .rev()
+            .fold(Noun::null(), |tail, head| Noun::cell(head, tail))
+    }
+
+    pub fn as_cell(&self) -> Option<(&Noun, &Noun)> {
+        match self {
+            Noun::Cell(head, tail) => Some((head, tail)),
+            Noun::Atom(_) => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Noun::Atom(bytes) if bytes.len() <= 8 => {
+                let mut le = [0u8; 8];
+                le[..bytes.len()].copy_from_slice(bytes);
+                Some(u64::from_le_bytes(le))
+            }
+            _ => None,
+        }
+    }
+
+    /// Text of a cord, if it's an atom holding UTF-8
+    pub fn as_cord(&self) -> Option<String> {
+        match self {
+            Noun::Atom(bytes) => String::from_utf8(bytes.clone()).ok(),
+            Noun::Cell(..) => None,
+        }
+    }
+
+    /// Items of a null-terminated list
+    pub fn as_list(&self) -> Option<Vec<&Noun>> {
+        let mut items = Vec::new();
+        let mut rest = self;
+        loop {
+            match rest {
+                Noun::Cell(head, tail) => {
+                    items.push(head.as_ref());
+                    rest = tail;
+                }
+                Noun::Atom(bytes) if bytes.is_empty() => return Some(items),
+                Noun::Atom(_) => return None,
+            }
+        }
+    }
+
+    /// Serialize as a jammed atom's little-endian bytes
+    pub fn jam(&self) -> Vec<u8> {
+        let mut writer = BitWriter::default();
+        writer.jam(self);
+        writer.bytes
+    }
+
+    /// Deserialize a jammed atom's little-endian bytes
+    pub fn cue(bytes: &[u8]) -> Result<Self, CueError> {
+        let mut reader = BitReader {
+            bytes,
+            position: 0,
+            seen: HashMap::new(),
+        };
+        reader.cue()
+    }
+}
+
+/// Bit length of a little-endian atom
+fn bit_len(bytes: &[u8]) -> u64 {
+    match bytes.last() {
+        None => 0,
+        Some(&last) => (bytes.len() as u64 - 1) * 8 + u64::from(8 - last.leading_zeros()),
+    }
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    len: u64,
+}
+
+impl BitWriter {
+    fn bit(&mut self, bit: bool) {
+        if self.len.is_multiple_of(8) {
+            self.bytes.push(0);
+        }
+        if bit {
+            *self.bytes.last_mut().unwrap() |= 1 << (self.len % 8);
+        }
+        self.len += 1;
+    }
+
+    /// The low `count` bits of a little-endian atom
+    fn bits(&mut self, atom: &[u8], count: u64) {
+        for i in 0..count {
+            let byte = atom.get((i / 8) as usize).copied().unwrap_or(0);
+            self.bit(byte >> (i % 8) & 1 == 1);
+        }
+    }
+
+    /// Length-prefixed atom
+    fn mat(&mut self, atom: &[u8]) {
+        let len = bit_len(atom);
+        if len == 0 {
+            self.bit(true);
+            return;
+        }
+        let len_bytes = len.to_le_bytes();
+        let len_len = u64::from(64 - len.leading_zeros());
+        for _ in 0..len_len {
+            self.bit(false);
+        }
+        self.bit(true);
+        // The length's top bit is implied
+        self.bits(&len_bytes, len_len - 1);
+        self.bits(atom, len);
+    }
+
+    fn jam(&mut self, noun: &Noun) {
+        match noun {
+            Noun::Atom(atom) => {
+                self.bit(false);
+                self.mat(atom);
+            }
+            Noun::Cell(head, tail) => {
+                self.bit(true);
+                self.bit(false);
+                self.jam(head);
+                self.jam(tail);
+            }
+        }
+    }
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: u64,
+    /// Nouns decoded so far, by the offset they started at
+    seen: HashMap<u64, Noun>,
+}
+
+impl BitReader<'_> {
+    fn bit(&mut self) -> Result<bool, CueError> {
+        let byte = self
+            .bytes
+            .get((self.position / 8) as usize)
+            .ok_or(CueError::Truncated)?;
+        let bit = byte >> (self.position % 8) & 1 == 1;
+        self.position += 1;
+        Ok(bit)
+    }
+
+    /// `count` bits as a little-endian atom
+    fn bits(&mut self, count: u64) -> Result<Vec<u8>, CueError> {
+        if count > (self.bytes.len() as u64) * 8 {
+            return Err(CueError::Truncated);
+        }
+        let mut atom = vec![0u8; count.div_ceil(8) as usize];
+        for i in 0..count {
+            if self.bit()? {
+                atom[(i / 8) as usize] |= 1 << (i % 8);
+            }
+        }
+        Ok(atom)
+    }
+
+    /// Inverse of `BitWriter::mat`
+    fn rub(&mut self) -> Result<Vec<u8>, CueError> {
+        let mut len_len = 0;
+        while !self.bit()? {
+            len_len += 1;
+        }
+        if len_len == 0 {
+            return Ok(Vec::new());
+        }
+        if len_len > 64 {
+            return Err(CueError::TooLong);
+        }
+        let low = Noun::atom(&self.bits(len_len - 1)?)
+            .as_u64()
+            .ok_or(CueError::TooLong)?;
+        let len = low | 1 << (len_len - 1);
+        self.bits(len)
+    }
+
+    fn cue(&mut self) -> Result<Noun, CueError> {
+        let start = self.position;
+        let noun = if !self.bit()? {
+            Noun::atom(&self.rub()?)
+        } else if !self.bit()? {
+            let head = self.cue()?;
+            let tail = self.cue()?;
+            Noun::cell(head, tail)
+        } else {
+            let offset = Noun::atom(&self.rub()?).as_u64().ok_or(CueError::TooLong)?;
+            return self
+                .seen
+                .get(&offset)
+                .cloned()
+                .ok_or(CueError::BadReference(offset));
+        };
+        self.seen.insert(start, noun.clone());
+        Ok(noun)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(n: u64) -> Noun {
+        Noun::atom(&n.to_le_bytes())
+    }
+
+    #[test]
+    fn test_jam_and_cue() {
+        // Reference values from Hoon's `jam`
+        assert_eq!(number(0).jam(), [2]);
+        assert_eq!(number(1).jam(), [12]);
+        assert_eq!(number(2).jam(), [72]);
+        assert_eq!(Noun::cell(number(0), number(0)).jam(), [41]);
+
+        let noun = Noun::tuple(vec![
+            Noun::cord("register-model"),
+            Noun::cord(&"ab".repeat(32)),
+            Noun::list(vec![number(1), number(u64::MAX), Noun::null()]),
+        ]);
+        assert_eq!(Noun::cue(&noun.jam()).unwrap(), noun);
+        assert_eq!(Noun::cue(&noun.jam()[..4]), Err(CueError::Truncated));
+
+        // `[1 1]` jammed with its tail referring back to its head at offset 2
+        let mut writer = BitWriter::default();
+        writer.bit(true);
+        writer.bit(false);
+        writer.jam(&number(1));
+        writer.bit(true);
+        writer.bit(true);
+        writer.mat(&[2]);
+        assert_eq!(
+            Noun::cue(&writer.bytes).unwrap(),
+            Noun::cell(number(1), number(1))
+        );
+    }
+}
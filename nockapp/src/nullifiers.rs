@@ -0,0 +1,235 @@
+// Nullifier replay checks
+//
+// Every accepted proof has a nullifier, the digest of its public inputs
+// (see `zkrag_verifier::nullifier`). With replay checks on, verifying a
+// proof records its nullifier in the same transaction as the query, and a
+// proof whose nullifier was already recorded fails with reason `replayed`,
+// without poking the kernel; the replay is recorded as a failed query.
+// Nullifiers are kept in the database, so replicas refuse each other's
+// replays, and of two verifications of the same proof racing, only the
+// first to commit is accepted.
+//
+// Nullifiers are grouped into epochs by proof timestamp, epoch `n` holding
+// proofs timestamped from `n * epoch_secs`. Admins list the epochs with
+// `GET /api/v1/nullifiers`, page through an epoch's nullifiers in query
+// order with `GET /api/v1/nullifiers/:epoch`, and forget an epoch with
+// `DELETE /api/v1/nullifiers/:epoch`. Proofs from an expired epoch verify
+// again, so only expire epochs older than the maximum proof age (see
+// `[policy]` in config.rs), whose proofs are refused as stale anyway.
+// Retention leaves nullifiers alone when it deletes their queries.
+//
+// Configuration (the `[nullifiers]` table; see config.rs):
+// - reject_replays (ZKRAG_REJECT_REPLAYS): record nullifiers and refuse
+//   replays (default false)
+// - epoch_secs (ZKRAG_NULLIFIER_EPOCH_SECS): length of an epoch (default
+//   86400)
+
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::blank_as_none;
+use crate::store::{NullifierRecord, Store, StoreError};
+
+/// Whether replays are refused, and how nullifiers are grouped
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NullifierConfig {
+    pub reject_replays: bool,
+    pub epoch_secs: u64,
+}
+
+impl Default for NullifierConfig {
+    fn default() -> Self {
+        Self {
+            reject_replays: false,
+            epoch_secs: 86_400,
+        }
+    }
+}
+
+impl NullifierConfig {
+    /// Epoch of a proof timestamped `timestamp`
+    pub fn epoch(&self, timestamp: u64) -> u64 {
+        timestamp / self.epoch_secs
+    }
+}
+
+/// Epoch with recorded nullifiers
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EpochSummary {
+    pub epoch: u64,
+    /// Unix time of the epoch's earliest proof timestamp
+    pub starts_at: u64,
+    pub nullifiers: u64,
+}
+
+/// Response of `GET /api/v1/nullifiers`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NullifierEpochs {
+    pub epoch_secs: u64,
+    /// Oldest first
+    pub epochs: Vec<EpochSummary>,
+}
+
+/// Cursor of `/api/v1/nullifiers/:epoch`; blank parameters are ignored
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EpochNullifiersParams {
+    /// `next_cursor` of the previous page; omit for the first
+    #[serde(default, deserialize_with = "blank_as_none")]
+    pub cursor: Option<u64>,
+    /// Nullifiers per page
+    #[serde(default, deserialize_with = "blank_as_none")]
+    pub limit: Option<u64>,
+}
+
+/// Response of `GET /api/v1/nullifiers/:epoch`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EpochNullifiers {
+    pub epoch: u64,
+    pub starts_at: u64,
+    pub nullifiers: Vec<NullifierRecord>,
+    /// Cursor of the next page; absent on the last
+    pub next_cursor: Option<u64>,
+}
+
+/// Response of `DELETE /api/v1/nullifiers/:epoch`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ExpiredNullifiers {
+    pub epoch: u64,
+    /// Nullifiers forgotten
+    pub expired: u64,
+}
+
+/// Epochs with recorded nullifiers
+pub async fn epochs(
+    store: &dyn Store,
+    config: &NullifierConfig,
+) -> Result<NullifierEpochs, StoreError> {
+    let epochs = store
+        .nullifier_epochs()
+        .await?
+        .into_iter()
+        .map(|count| EpochSummary {
+            epoch: count.epoch,
+            starts_at: count.epoch.saturating_mul(config.epoch_secs),
+            nullifiers: count.nullifiers,
+        })
+        .collect();
+    Ok(NullifierEpochs {
+        epoch_secs: config.epoch_secs,
+        epochs,
+    })
+}
+
+/// Up to `limit` of `epoch`'s nullifiers after `cursor`
+pub async fn page(
+    store: &dyn Store,
+    config: &NullifierConfig,
+    epoch: u64,
+    cursor: u64,
+    limit: u64,
+) -> Result<EpochNullifiers, StoreError> {
+    let mut nullifiers = store.epoch_nullifiers(epoch, cursor, limit + 1).await?;
+    let next_cursor = if nullifiers.len() as u64 > limit {
+        nullifiers.truncate(limit as usize);
+        nullifiers.last().map(|nullifier| nullifier.query_id)
+    } else {
+        None
+    };
+    Ok(EpochNullifiers {
+        epoch,
+        starts_at: epoch.saturating_mul(config.epoch_secs),
+        nullifiers,
+        next_cursor,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{test_store, QueryRecord};
+    use zkrag_verifier::{ProofEnvelope, PublicInputs};
+
+    #[tokio::test]
+    async fn test_nullifiers_recorded_once() {
+        let store = test_store().await;
+        let config = NullifierConfig {
+            epoch_secs: 100,
+            ..NullifierConfig::default()
+        };
+
+        let record = |timestamp| {
+            let record = QueryRecord {
+                id: 0,
+                proof_digest: "ab".repeat(32),
+                document_commitment: "cd".repeat(32),
+                model_hash: "ef".repeat(32),
+                timestamp,
+                verified: true,
+                reason: None,
+                verified_at: timestamp + 1,
+            };
+            let inputs = PublicInputs {
+                document_commitment: record.document_commitment.clone(),
+                model_hash: record.model_hash.clone(),
+                timestamp,
+                nonce: None,
+            };
+            let nullifier = zkrag_verifier::nullifier::nullifier(&inputs);
+            (record, ProofEnvelope::new(vec![1], inputs), nullifier)
+        };
+        let mut ids = Vec::new();
+        for timestamp in [150, 160, 170, 250] {
+            let (record, envelope, nullifier) = record(timestamp);
+            let id = store
+                .record_nullified_query(
+                    &record,
+                    &envelope,
+                    None,
+                    None,
+                    &nullifier,
+                    config.epoch(timestamp),
+                )
+                .await
+                .unwrap();
+            ids.push(id.unwrap());
+        }
+        // A replay records neither the query nor its nullifier
+        let (replay, envelope, nullifier) = record(160);
+        assert!(store.nullifier_recorded(&nullifier).await.unwrap());
+        let id = store
+            .record_nullified_query(&replay, &envelope, None, None, &nullifier, 1)
+            .await
+            .unwrap();
+        assert_eq!(id, None);
+        assert!(store.get_query(ids[3] + 1).await.unwrap().is_none());
+
+        let summary = epochs(&store, &config).await.unwrap();
+        let counts: Vec<_> = summary
+            .epochs
+            .iter()
+            .map(|epoch| (epoch.epoch, epoch.starts_at, epoch.nullifiers))
+            .collect();
+        assert_eq!(counts, [(1, 100, 3), (2, 200, 1)]);
+
+        let first = page(&store, &config, 1, 0, 2).await.unwrap();
+        let queries: Vec<u64> = first.nullifiers.iter().map(|n| n.query_id).collect();
+        assert_eq!(queries, ids[..2]);
+        assert_eq!(first.nullifiers[1].nullifier, nullifier);
+        let rest = page(&store, &config, 1, first.next_cursor.unwrap(), 2)
+            .await
+            .unwrap();
+        assert_eq!(rest.nullifiers.len(), 1);
+        assert_eq!(rest.next_cursor, None);
+
+        // Once expired, the epoch's proofs may be recorded again
+        assert_eq!(store.expire_nullifiers(1).await.unwrap(), 3);
+        assert!(!store.nullifier_recorded(&nullifier).await.unwrap());
+        let id = store
+            .record_nullified_query(&replay, &envelope, None, None, &nullifier, 1)
+            .await
+            .unwrap();
+        assert!(id.is_some());
+    }
+}
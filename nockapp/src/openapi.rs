@@ -0,0 +1,406 @@
+// OpenAPI description
+//
+// Handlers carry `#[utoipa::path]` annotations and the request and response
+// types derive `ToSchema`; `ApiDoc` collects them into the spec served at
+// `/api/v1/openapi.json`, with Swagger UI at `/api/v1/docs`. Both are open
+// like the health probes.
+//
+// Types owned by the prover and verifier crates are described by mirrors
+// here, which the tests check against the types' serialized form.
+
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{IntoParams, IntoResponses, Modify, OpenApi, ToSchema};
+
+use crate::artifacts::{ArtifactFile, ModelUploadResponse};
+use crate::audit::{AuditFormat, AuditPage, Outcome};
+use crate::challenge::Challenge;
+use crate::error::{ErrorCode, ErrorResponse};
+use crate::health::{ComponentHealth, Components, Health, KeyHealth, Status as HealthStatus};
+use crate::keys::{QueryReceipt, ReceiptKeyResponse};
+use crate::nullifiers::{EpochNullifiers, EpochSummary, ExpiredNullifiers, NullifierEpochs};
+use crate::owners::OwnerHistory;
+use crate::registry::{DocumentProof, PathStep, Side};
+use crate::rotation::{DeprecateKeyRequest, KeyRotationResponse, KeyVersion, KeyVersionList};
+use crate::shutdown::Readiness;
+use crate::stats::{Counts, DayStats, DocumentStats, FailureStats, ModelStats, Stats};
+use crate::store::{AuditEntry, ModelUsage, NullifierRecord, OwnedDocument, QueryRecord};
+use crate::upload::DocumentUploadResponse;
+use crate::validate::FieldError;
+use crate::webhooks::{RegisterWebhookRequest, WebhookResponse};
+use crate::{
+    CommitmentResponse, DocumentRegistrationResponse, QueryListResponse, RegisterDocumentRequest,
+    RegisterModelRequest, SuccessResponse, VerificationResponse, VerifyQueryRequest,
+};
+
+/// Errors any authenticated route may return
+#[derive(IntoResponses)]
+#[allow(dead_code)]
+pub enum AuthErrors {
+    #[response(
+        status = 401,
+        description = "Missing or invalid bearer token (`unauthorized`)"
+    )]
+    Unauthorized(ErrorResponse),
+    #[response(
+        status = 403,
+        description = "Token lacks the required role (`forbidden`)"
+    )]
+    Forbidden(ErrorResponse),
+    #[response(
+        status = 429,
+        description = "Rate limit exceeded (`rate_limited`); see Retry-After"
+    )]
+    RateLimited(ErrorResponse),
+    #[response(status = 500, description = "Server-side failure (`internal`)")]
+    Internal(ErrorResponse),
+}
+
+/// Errors of routes that poke the kernel, when one is configured
+#[derive(IntoResponses)]
+#[allow(dead_code)]
+pub enum KernelErrors {
+    #[response(
+        status = 422,
+        description = "Kernel refused the request (`kernel_rejected`, with its `status` and \
+                       `body` in `details`)"
+    )]
+    Rejected(ErrorResponse),
+    #[response(
+        status = 503,
+        description = "Kernel process is unreachable (`kernel_unavailable`)"
+    )]
+    Unavailable(ErrorResponse),
+}
+
+/// Errors of routes taking an Idempotency-Key
+#[derive(IntoResponses)]
+#[allow(dead_code)]
+pub enum IdempotencyErrors {
+    #[response(
+        status = 409,
+        description = "A request with the key is still running (`idempotency_key_in_use`)"
+    )]
+    InUse(ErrorResponse),
+    #[response(
+        status = 422,
+        description = "The key was used with a different request (`idempotency_key_reused`)"
+    )]
+    Reused(ErrorResponse),
+}
+
+/// Idempotency-Key header of registration and verification POSTs
+#[derive(IntoParams)]
+#[into_params(parameter_in = Header)]
+#[allow(dead_code)]
+pub struct IdempotencyHeader {
+    /// Client-chosen key, 1 to 255 visible ASCII characters; a repeat of the
+    /// request with the same key gets the first response, marked
+    /// `Idempotent-Replayed: true`
+    #[param(rename = "Idempotency-Key")]
+    idempotency_key: Option<String>,
+}
+
+/// Wire form of `zkrag_prover::QueryWitness`
+#[derive(ToSchema)]
+#[schema(as = QueryWitness)]
+#[allow(dead_code)]
+pub struct QueryWitnessSchema {
+    /// Private: hex SHA-256 hashes of documents in the query set
+    document_hashes: Vec<String>,
+    /// Private: the query text
+    query_text: String,
+    /// Private: query embedding, of the circuit's dimension
+    query_embedding: Vec<f64>,
+    /// Private: ids of retrieved chunks
+    search_results: Vec<usize>,
+    document_commitment: String,
+    model_hash: String,
+    timestamp: u64,
+    /// Nonce from `/api/v1/challenge` to bind the proof to
+    nonce: Option<String>,
+}
+
+/// Wire form of `zkrag_verifier::PublicInputs`
+#[derive(ToSchema)]
+#[schema(as = PublicInputs)]
+#[allow(dead_code)]
+pub struct PublicInputsSchema {
+    document_commitment: String,
+    model_hash: String,
+    timestamp: u64,
+    /// Challenge nonce the proof is bound to, spent by verification
+    nonce: Option<String>,
+}
+
+/// Wire form of `zkrag_verifier::ProofEnvelope`
+#[derive(ToSchema)]
+#[schema(as = ProofEnvelope)]
+#[allow(dead_code)]
+pub struct ProofEnvelopeSchema {
+    version: u16,
+    circuit_id: String,
+    /// Pairing curve, e.g. `bn254`
+    curve: String,
+    /// Verifying key id; absent selects the circuit's current key
+    key_id: Option<String>,
+    /// Hex-encoded proof; a byte string in CBOR and MessagePack
+    proof: String,
+    public_inputs: PublicInputsSchema,
+}
+
+/// Wire form of `zkrag_verifier::VerificationReceipt`
+#[derive(ToSchema)]
+#[schema(as = VerificationReceipt)]
+#[allow(dead_code)]
+pub struct VerificationReceiptSchema {
+    proof_digest: String,
+    /// Hex SHA-256 of the canonical public inputs
+    inputs_digest: String,
+    is_valid: bool,
+    #[schema(value_type = Option<Object>)]
+    reason: Option<()>,
+    verified_at: u64,
+    verifying_key_fingerprint: Option<String>,
+    /// Hex SHA-256 of the signing key; see `/api/v1/keys/receipt-key`
+    verifier_key_id: String,
+    /// Hex ed25519 signature over the canonical JSON of the other fields
+    signature: String,
+}
+
+/// Wire form of `v2::EnvelopeVerification`, a `zkrag_verifier::VerificationResult`
+/// with the recorded query's id
+#[derive(ToSchema)]
+#[schema(as = EnvelopeVerification)]
+#[allow(dead_code)]
+pub struct EnvelopeVerificationSchema {
+    /// Id of the recorded query
+    query_id: u64,
+    is_valid: bool,
+    /// Why the proof failed, tagged with a machine-readable `code`
+    #[schema(value_type = Option<Object>)]
+    reason: Option<()>,
+    public_inputs: PublicInputsSchema,
+    verified_at: u64,
+    /// Whether the pairing check was skipped for a proof already verified
+    cache_hit: bool,
+    /// Present when the server signs receipts
+    receipt: Option<VerificationReceiptSchema>,
+    /// Timestamp authority's time, when a timestamp token was validated
+    attested_at: Option<u64>,
+    proof_digest: String,
+    /// Key id of the verifying key the proof was checked against
+    verifying_key_fingerprint: Option<String>,
+}
+
+/// Bearer token scheme referenced by the top-level security requirement
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "ZK-RAG Verifier",
+        description = "Document and model registry, query proof verification and generation"
+    ),
+    paths(
+        crate::register_document,
+        crate::revoke_document,
+        crate::upload_document,
+        crate::document_commitment,
+        crate::document_proof,
+        crate::register_model,
+        crate::upload_model,
+        crate::revoke_model,
+        crate::issue_challenge,
+        crate::verify_query,
+        crate::verify_envelope,
+        crate::get_query,
+        crate::get_query_proof,
+        crate::get_query_receipt,
+        crate::list_queries,
+        crate::audit_trail,
+        crate::owner_history,
+        crate::verification_stats,
+        crate::nullifier_epochs,
+        crate::epoch_nullifiers,
+        crate::expire_nullifiers,
+        crate::generate_proof,
+        crate::stream_events,
+        crate::subscribe_events,
+        crate::register_webhook,
+        crate::list_webhooks,
+        crate::delete_webhook,
+        crate::verifying_key,
+        crate::list_verifying_keys,
+        crate::upload_verifying_key,
+        crate::deprecate_verifying_key,
+        crate::proving_key,
+        crate::receipt_key,
+        crate::export_metrics,
+        crate::liveness,
+        crate::health,
+        crate::readiness,
+    ),
+    components(schemas(
+        RegisterDocumentRequest,
+        RegisterModelRequest,
+        VerifyQueryRequest,
+        Challenge,
+        SuccessResponse,
+        VerificationResponse,
+        DocumentRegistrationResponse,
+        QueryListResponse,
+        CommitmentResponse,
+        DocumentProof,
+        DocumentUploadResponse,
+        ModelUploadResponse,
+        ArtifactFile,
+        PathStep,
+        Side,
+        ErrorResponse,
+        ErrorCode,
+        FieldError,
+        Readiness,
+        Health,
+        Components,
+        KeyHealth,
+        ComponentHealth,
+        HealthStatus,
+        QueryRecord,
+        AuditEntry,
+        AuditPage,
+        Outcome,
+        AuditFormat,
+        OwnerHistory,
+        OwnedDocument,
+        ModelUsage,
+        Stats,
+        Counts,
+        ModelStats,
+        DocumentStats,
+        DayStats,
+        FailureStats,
+        NullifierEpochs,
+        EpochSummary,
+        EpochNullifiers,
+        NullifierRecord,
+        ExpiredNullifiers,
+        QueryWitnessSchema,
+        ProofEnvelopeSchema,
+        EnvelopeVerificationSchema,
+        VerificationReceiptSchema,
+        ReceiptKeyResponse,
+        QueryReceipt,
+        KeyVersion,
+        KeyVersionList,
+        KeyRotationResponse,
+        DeprecateKeyRequest,
+        RegisterWebhookRequest,
+        WebhookResponse,
+    )),
+    modifiers(&BearerAuth),
+    security(("bearer" = [])),
+    tags(
+        (name = "documents", description = "Document registry"),
+        (name = "models", description = "Approved models"),
+        (name = "queries", description = "Query proof verification and records"),
+        (name = "audit", description = "Verification audit trail and statistics"),
+        (name = "nullifiers", description = "Nullifiers of accepted proofs, by epoch"),
+        (name = "proofs", description = "Server-side proof generation"),
+        (name = "events", description = "Live registration and verification events"),
+        (name = "webhooks", description = "Callbacks with verification outcomes"),
+        (name = "keys", description = "Verifying, proving and receipt key distribution"),
+        (name = "service", description = "Health and metrics"),
+    )
+)]
+pub struct ApiDoc;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use std::collections::BTreeSet;
+    use zkrag_prover::QueryWitness;
+    use zkrag_verifier::{ProofEnvelope, PublicInputs, QueryVerifier, ReceiptSigner};
+
+    use crate::v2::EnvelopeVerification;
+
+    fn keys(value: &Value) -> BTreeSet<String> {
+        value.as_object().unwrap().keys().cloned().collect()
+    }
+
+    #[test]
+    fn test_mirrors_match_wire_format() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let schemas = &spec["components"]["schemas"];
+        assert!(spec["paths"]["/api/v1/query/{id}"]["get"].is_object());
+
+        let witness = QueryWitness::new(
+            vec![],
+            String::new(),
+            vec![],
+            vec![],
+            "c".to_string(),
+            "m".to_string(),
+            0,
+        );
+        assert_eq!(
+            keys(&schemas["QueryWitness"]["properties"]),
+            keys(&serde_json::to_value(&witness).unwrap())
+        );
+
+        let inputs = PublicInputs {
+            document_commitment: "c".to_string(),
+            model_hash: "m".to_string(),
+            timestamp: 0,
+            nonce: Some("n".to_string()),
+        };
+        let envelope = ProofEnvelope::new(vec![0], inputs.clone()).with_key_id("k");
+        assert_eq!(
+            keys(&schemas["ProofEnvelope"]["properties"]),
+            keys(&serde_json::to_value(&envelope).unwrap())
+        );
+        assert_eq!(
+            keys(&schemas["PublicInputs"]["properties"]),
+            keys(&serde_json::to_value(&inputs).unwrap())
+        );
+
+        // An unknown key still yields a full, signed result
+        let verifier = QueryVerifier::builder()
+            .receipt_signer(std::sync::Arc::new(ReceiptSigner::generate()))
+            .build()
+            .unwrap();
+        let mut result = verifier.verify_envelope(&envelope, None).unwrap();
+        result.verifying_key_fingerprint = Some("k".to_string());
+        result.attested_at = Some(0);
+        let receipt = result.receipt.clone().unwrap();
+        let verification = EnvelopeVerification {
+            query_id: 1,
+            result,
+        };
+        assert_eq!(
+            keys(&schemas["EnvelopeVerification"]["properties"]),
+            keys(&serde_json::to_value(&verification).unwrap())
+        );
+        let mut receipt = serde_json::to_value(&receipt).unwrap();
+        receipt["verifying_key_fingerprint"] = "k".into();
+        assert_eq!(
+            keys(&schemas["VerificationReceipt"]["properties"]),
+            keys(&receipt)
+        );
+    }
+}
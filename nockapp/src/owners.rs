@@ -0,0 +1,158 @@
+// Owner history
+//
+// `GET /api/v1/owners/:owner/queries` gathers what a customer portal shows
+// a document owner in one call:
+// - documents: every document registered to the owner, oldest first, with
+//   when it was revoked, if it was
+// - models: each model the owner's queries named, with its registered name,
+//   how many queries named it and when one was last verified
+// - queries: a page of the verifications of queries over the owner's
+//   documents, oldest first, as the audit trail pages them for the owner as
+//   tenant (see audit.rs). Each page carries a `next_cursor` to pass as
+//   `cursor` for the following one, and ends the history when absent.
+//
+// Documents and models are listed in full on every page. A query belongs
+// to the first owner of the commitment it names, as in the audit trail.
+//
+// Callers may only read their own history: the owner must be the token's
+// subject, unless the caller is an admin (see auth.rs).
+
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::audit;
+use crate::blank_as_none;
+use crate::store::{AuditEntry, AuditFilter, ModelUsage, OwnedDocument, Store, StoreError};
+
+/// Cursor of `/api/v1/owners/:owner/queries`; blank parameters are ignored
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OwnerHistoryParams {
+    /// `next_cursor` of the previous page; omit for the first
+    #[serde(default, deserialize_with = "blank_as_none")]
+    pub cursor: Option<u64>,
+    /// Verifications per page
+    #[serde(default, deserialize_with = "blank_as_none")]
+    pub limit: Option<u64>,
+}
+
+/// Response of `GET /api/v1/owners/:owner/queries`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OwnerHistory {
+    pub owner: String,
+    pub documents: Vec<OwnedDocument>,
+    pub models: Vec<ModelUsage>,
+    pub queries: Vec<AuditEntry>,
+    /// Cursor of the next page of queries; absent on the last
+    pub next_cursor: Option<u64>,
+}
+
+/// `owner`'s documents and models, and up to `limit` of their queries
+/// after `cursor`
+pub async fn history(
+    store: &dyn Store,
+    owner: &str,
+    cursor: u64,
+    limit: u64,
+) -> Result<OwnerHistory, StoreError> {
+    let filter = AuditFilter {
+        tenant: Some(owner.to_string()),
+        ..AuditFilter::default()
+    };
+    let page = audit::page(store, &filter, cursor, limit).await?;
+    Ok(OwnerHistory {
+        owner: owner.to_string(),
+        documents: store.owner_documents(owner).await?,
+        models: store.owner_models(owner).await?,
+        queries: page.entries,
+        next_cursor: page.next_cursor,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{test_store, QueryRecord};
+    use zkrag_verifier::{ProofEnvelope, PublicInputs};
+
+    #[tokio::test]
+    async fn test_owner_history() {
+        let store = test_store().await;
+        let alice = "a1".repeat(32);
+        store
+            .register_document(&alice, "alice", &[], 10)
+            .await
+            .unwrap();
+        store
+            .register_document(&"b1".repeat(32), "bob", &[], 20)
+            .await
+            .unwrap();
+        // Registering alice's commitment again doesn't make it bob's
+        store
+            .register_document(&alice, "bob", &[], 30)
+            .await
+            .unwrap();
+        store
+            .register_model(&"ef".repeat(32), "llama", 1)
+            .await
+            .unwrap();
+
+        for (commitment, model, verified_at) in [
+            (alice.clone(), "ef".repeat(32), 100),
+            ("b1".repeat(32), "ef".repeat(32), 200),
+            (alice.to_uppercase(), "EF".repeat(32), 300),
+            (alice.clone(), "cd".repeat(32), 400),
+        ] {
+            let record = QueryRecord {
+                id: 0,
+                proof_digest: "ab".repeat(32),
+                document_commitment: commitment,
+                model_hash: model,
+                timestamp: verified_at,
+                verified: true,
+                reason: None,
+                verified_at,
+            };
+            let inputs = PublicInputs {
+                document_commitment: record.document_commitment.clone(),
+                model_hash: record.model_hash.clone(),
+                timestamp: record.timestamp,
+                nonce: None,
+            };
+            let envelope = ProofEnvelope::new(vec![1], inputs);
+            store
+                .record_query(&record, &envelope, None, None)
+                .await
+                .unwrap();
+        }
+
+        let page = history(&store, "alice", 0, 2).await.unwrap();
+        assert_eq!(page.documents.len(), 1);
+        assert_eq!(page.documents[0].registered_at, 10);
+        let models: Vec<_> = page
+            .models
+            .iter()
+            .map(|model| {
+                (
+                    &model.model_hash[..2],
+                    model.model_name.as_deref(),
+                    model.queries,
+                )
+            })
+            .collect();
+        assert_eq!(models, [("cd", None, 1), ("ef", Some("llama"), 2)]);
+        let ids: Vec<u64> = page.queries.iter().map(|entry| entry.query.id).collect();
+        assert_eq!(ids, [1, 3]);
+
+        let page = history(&store, "alice", page.next_cursor.unwrap(), 2)
+            .await
+            .unwrap();
+        let ids: Vec<u64> = page.queries.iter().map(|entry| entry.query.id).collect();
+        assert_eq!(ids, [4]);
+        assert_eq!(page.next_cursor, None);
+
+        let page = history(&store, "bob", 0, 10).await.unwrap();
+        assert_eq!(page.documents.len(), 2);
+        assert_eq!(page.queries.len(), 1);
+    }
+}
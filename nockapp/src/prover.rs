@@ -0,0 +1,202 @@
+// Server-side proof generation
+//
+// For clients that can't run the prover themselves, `POST /api/v1/proof/generate`
+// takes a query witness and returns a proof envelope. A proof costs seconds
+// of CPU, so witnesses go through a bounded job queue served by a fixed pool
+// of worker threads; when the queue is full the request is refused with 503
+// and Retry-After instead of piling up.
+//
+// Generation is enabled when the key directory holds proving_key.bin.
+// Witnesses carry the query and document hashes in the clear, so clients
+// should reach the server over mutually authenticated TLS, terminated at the
+// proxy in front of it. Witnesses are never logged or stored.
+//
+// Configuration (the `[prover]` table; see config.rs):
+// - workers (ZKRAG_PROVER_WORKERS): worker threads (default: half the CPUs,
+//   at least 1)
+// - queue (ZKRAG_PROVER_QUEUE): witnesses waiting for a worker before
+//   requests are refused (default 16)
+
+use anyhow::Context;
+use serde::Deserialize;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Instant;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{mpsc, oneshot};
+use zkrag_prover::{QueryProver, QueryWitness};
+use zkrag_verifier::keys::key_digest;
+use zkrag_verifier::metrics::{Histogram, HistogramSnapshot, LATENCY_BUCKETS};
+
+/// Worker and queue sizes
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProverConfig {
+    pub workers: usize,
+    pub queue: usize,
+}
+
+impl Default for ProverConfig {
+    fn default() -> Self {
+        let cpus = std::thread::available_parallelism().map_or(1, |cpus| cpus.get());
+        Self {
+            workers: (cpus / 2).max(1),
+            queue: 16,
+        }
+    }
+}
+
+/// Why a proof wasn't generated
+#[derive(Debug, thiserror::Error)]
+pub enum ProveError {
+    #[error("prover queue is full")]
+    Busy,
+    #[error("prover workers have stopped")]
+    Stopped,
+    #[error("proof generation failed: {0}")]
+    Failed(String),
+}
+
+struct Job {
+    witness: QueryWitness,
+    reply: oneshot::Sender<Result<Vec<u8>, String>>,
+}
+
+/// Queue of witnesses and the threads proving them
+pub struct ProverPool {
+    jobs: mpsc::Sender<Job>,
+    key_path: PathBuf,
+    key_id: String,
+    workers: usize,
+    /// Proving time, excluding time queued
+    latency: Arc<Histogram>,
+}
+
+impl ProverPool {
+    /// Load the proving key at `key_path` and start `config.workers` threads
+    pub fn start(key_path: &Path, config: &ProverConfig) -> anyhow::Result<Self> {
+        let mut prover = QueryProver::with_cache_dir(key_path.parent().unwrap_or(Path::new(".")))?;
+        prover.load_proving_key(key_path)?;
+        let key_id = key_digest(&prover.verifying_key_bytes()?);
+
+        let (jobs, receiver) = mpsc::channel::<Job>(config.queue);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let prover = Arc::new(prover);
+        let latency = Arc::new(Histogram::new(LATENCY_BUCKETS));
+        for worker in 0..config.workers {
+            let receiver = receiver.clone();
+            let prover = prover.clone();
+            let latency = latency.clone();
+            std::thread::Builder::new()
+                .name(format!("prover-{}", worker))
+                .spawn(move || loop {
+                    // Only the idle worker holding the lock waits on the queue
+                    let job = receiver
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .blocking_recv();
+                    let Some(job) = job else {
+                        break;
+                    };
+                    // The client gave up waiting
+                    if job.reply.is_closed() {
+                        continue;
+                    }
+                    let started = Instant::now();
+                    let result = catch_unwind(AssertUnwindSafe(|| prover.prove(job.witness)))
+                        .map_err(|_| "prover panicked".to_string())
+                        .and_then(|result| result.map_err(|e| format!("{:#}", e)));
+                    latency.observe(started.elapsed().as_secs_f64());
+                    let _ = job.reply.send(result);
+                })
+                .context("Failed to start prover thread")?;
+        }
+
+        Ok(Self {
+            jobs,
+            key_path: key_path.to_path_buf(),
+            key_id,
+            workers: config.workers,
+            latency,
+        })
+    }
+
+    /// File the proving key was loaded from
+    pub fn key_path(&self) -> &Path {
+        &self.key_path
+    }
+
+    /// Id of the verifying key matching the proving key
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    pub fn workers(&self) -> usize {
+        self.workers
+    }
+
+    /// Witnesses waiting for a worker
+    pub fn queue_depth(&self) -> usize {
+        self.jobs.max_capacity() - self.jobs.capacity()
+    }
+
+    pub fn latency(&self) -> HistogramSnapshot {
+        self.latency.snapshot()
+    }
+
+    /// Queue `witness` and wait for its proof
+    pub async fn prove(&self, witness: QueryWitness) -> Result<Vec<u8>, ProveError> {
+        let (reply, result) = oneshot::channel();
+        self.jobs
+            .try_send(Job { witness, reply })
+            .map_err(|e| match e {
+                TrySendError::Full(_) => ProveError::Busy,
+                TrySendError::Closed(_) => ProveError::Stopped,
+            })?;
+        result
+            .await
+            .map_err(|_| ProveError::Stopped)?
+            .map_err(ProveError::Failed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zkrag_prover::PROVING_KEY_FILE;
+
+    #[tokio::test]
+    async fn test_prover_pool() {
+        let dir = std::env::temp_dir().join(format!("zkrag-prover-pool-{}", std::process::id()));
+        let mut prover = QueryProver::with_cache_dir(&dir).unwrap();
+        prover.setup().unwrap();
+
+        let config = ProverConfig {
+            workers: 2,
+            queue: 4,
+        };
+        let pool = ProverPool::start(&dir.join(PROVING_KEY_FILE), &config).unwrap();
+        assert_eq!(
+            pool.key_id(),
+            key_digest(&prover.verifying_key_bytes().unwrap())
+        );
+
+        let witness = QueryWitness::new(
+            vec![],
+            String::new(),
+            vec![],
+            vec![],
+            "commitment".to_string(),
+            "model".to_string(),
+            0,
+        );
+        let (first, second) = tokio::join!(pool.prove(witness.clone()), pool.prove(witness));
+        assert!(!first.unwrap().is_empty());
+        assert!(!second.unwrap().is_empty());
+        assert_eq!(pool.latency().count, 2);
+        assert_eq!(pool.queue_depth(), 0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
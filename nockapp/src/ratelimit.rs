@@ -0,0 +1,385 @@
+// Per-client rate limiting
+//
+// Verifying and generating proofs are CPU-heavy, so `/api/v1/query/verify`
+// and `/api/v1/proof/generate` share a tighter budget; the other API routes
+// share the read budget and the health probes are not limited. Clients over
+// budget get 429 with Retry-After.
+//
+// Clients are told apart by the subject of their bearer token when
+// authentication is on and the token authenticates, and otherwise by IP
+// address, so sending made-up tokens doesn't buy a client a fresh budget.
+// The token is checked once here; handlers reuse the result. The IP is the
+// peer address, or the first X-Forwarded-For hop when the server sits behind
+// a trusted proxy.
+//
+// Each process keeps its own token buckets. Replicas given a Redis server
+// (see cluster.rs) count requests there instead, in fixed one-minute windows
+// shared by all of them; a client over budget waits for the next window. If
+// Redis can't be reached requests are let through, so an outage of the
+// cache doesn't take the API down with it.
+//
+// Configuration (the `[limits]` table, see config.rs; budgets are requests
+// per minute per client, and 0 turns the limit off):
+// - verify_per_minute (ZKRAG_RATE_LIMIT_VERIFY): proof verification and
+//   generation (default 30)
+// - read_per_minute (ZKRAG_RATE_LIMIT_READ): every other API route
+//   (default 600)
+// - trust_proxy (ZKRAG_TRUST_PROXY): take client IPs from X-Forwarded-For
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::HeaderMap;
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use governor::clock::{Clock, DefaultClock};
+use governor::{DefaultKeyedRateLimiter, Quota};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::net::{IpAddr, SocketAddr};
+use std::num::NonZeroU32;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::auth::{bearer_token, Authenticator};
+use crate::cluster::ClusterState;
+use crate::error::{ApiError, ErrorCode};
+use crate::unix_now;
+
+/// How often idle clients are dropped from the limiters
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Budgets, in requests per minute per client
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub verify_per_minute: u32,
+    pub read_per_minute: u32,
+    pub trust_proxy: bool,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            verify_per_minute: 30,
+            read_per_minute: 600,
+            trust_proxy: false,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    /// Limiter allowing `per_minute` requests per client, or `None` when
+    /// the limit is off. With `auth`, clients whose token it accepts are
+    /// keyed by subject.
+    pub fn limiter(
+        &self,
+        per_minute: u32,
+        auth: Option<Arc<Authenticator>>,
+    ) -> Option<Arc<RateLimiter>> {
+        let per_minute = NonZeroU32::new(per_minute)?;
+        Some(Arc::new(RateLimiter {
+            budget: Budget::Local {
+                limiter: DefaultKeyedRateLimiter::keyed(Quota::per_minute(per_minute)),
+                clock: DefaultClock::default(),
+            },
+            auth,
+            trust_proxy: self.trust_proxy,
+        }))
+    }
+
+    /// Like [`Self::limiter`], counting in `state` under `group` so replicas
+    /// share each client's budget
+    pub fn shared_limiter(
+        &self,
+        state: Arc<dyn ClusterState>,
+        group: &'static str,
+        per_minute: u32,
+        auth: Option<Arc<Authenticator>>,
+    ) -> Option<Arc<RateLimiter>> {
+        let per_minute = NonZeroU32::new(per_minute)?;
+        Some(Arc::new(RateLimiter {
+            budget: Budget::Shared {
+                state,
+                group,
+                per_minute,
+            },
+            auth,
+            trust_proxy: self.trust_proxy,
+        }))
+    }
+}
+
+/// Identity a client's budget is tracked under
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ClientKey {
+    /// Subject of an authenticated token
+    Subject(String),
+    Ip(IpAddr),
+}
+
+impl ClientKey {
+    /// Name in shared state; subjects are hashed so they aren't stored
+    fn shared_name(&self) -> String {
+        match self {
+            Self::Subject(subject) => {
+                format!("subject:{}", hex::encode(Sha256::digest(subject)))
+            }
+            Self::Ip(ip) => format!("ip:{}", ip),
+        }
+    }
+}
+
+/// Where the budgets are kept
+enum Budget {
+    /// Token buckets in this process
+    Local {
+        limiter: DefaultKeyedRateLimiter<ClientKey>,
+        clock: DefaultClock,
+    },
+    /// One-minute windows counted in state shared by every replica
+    Shared {
+        state: Arc<dyn ClusterState>,
+        group: &'static str,
+        per_minute: NonZeroU32,
+    },
+}
+
+/// Per-client budgets for one group of routes
+pub struct RateLimiter {
+    budget: Budget,
+    /// `None` when authentication is off
+    auth: Option<Arc<Authenticator>>,
+    trust_proxy: bool,
+}
+
+impl RateLimiter {
+    /// Key for a request, or `None` if the client can't be identified. A
+    /// token that authenticates leaves its principal in the request's
+    /// extensions for the `Auth` extractor.
+    async fn client_key(
+        &self,
+        request: &mut Request,
+        peer: Option<SocketAddr>,
+    ) -> Option<ClientKey> {
+        if let Some(auth) = &self.auth {
+            if let Some(token) = bearer_token(request.headers()) {
+                if let Ok(principal) = auth.authenticate(token).await {
+                    let key = ClientKey::Subject(principal.subject.clone());
+                    request.extensions_mut().insert(principal);
+                    return Some(key);
+                }
+            }
+        }
+        self.ip_key(request.headers(), peer)
+    }
+
+    /// Key for the client's IP address
+    fn ip_key(&self, headers: &HeaderMap, peer: Option<SocketAddr>) -> Option<ClientKey> {
+        if self.trust_proxy {
+            let forwarded = headers
+                .get("x-forwarded-for")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.split(',').next())
+                .and_then(|hop| hop.trim().parse().ok());
+            if let Some(ip) = forwarded {
+                return Some(ClientKey::Ip(ip));
+            }
+        }
+        peer.map(|peer| ClientKey::Ip(peer.ip()))
+    }
+
+    /// Take one request from `key`'s budget, or say how long until one is
+    /// available
+    async fn check(&self, key: &ClientKey) -> Result<(), Duration> {
+        match &self.budget {
+            Budget::Local { limiter, clock } => limiter
+                .check_key(key)
+                .map_err(|not_until| not_until.wait_time_from(clock.now())),
+            Budget::Shared {
+                state,
+                group,
+                per_minute,
+            } => {
+                let name = format!("ratelimit:{}:{}", group, key.shared_name());
+                match state.count(&name, 60, unix_now()).await {
+                    Ok(window) if window.count > u64::from(per_minute.get()) => {
+                        Err(Duration::from_secs(window.remaining_secs))
+                    }
+                    Ok(_) => Ok(()),
+                    Err(e) => {
+                        warn!("Rate limit not applied: {:#}", e);
+                        Ok(())
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|info| info.0);
+    let Some(key) = limiter.client_key(&mut request, peer).await else {
+        return next.run(request).await;
+    };
+    match limiter.check(&key).await {
+        Ok(()) => next.run(request).await,
+        Err(wait) => {
+            // Round up so a client that waits as told isn't refused again
+            let retry_after = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+            let retry_after = retry_after.max(1);
+            ApiError::new(
+                ErrorCode::RateLimited,
+                format!("Rate limit exceeded; retry in {}s", retry_after),
+            )
+            .with_retry_after(retry_after)
+            .into_response()
+        }
+    }
+}
+
+/// Apply `limiter` to every route in `router`
+pub fn limited<S>(router: Router<S>, limiter: Option<Arc<RateLimiter>>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let Some(limiter) = limiter else {
+        return router;
+    };
+    if matches!(limiter.budget, Budget::Local { .. }) {
+        let cleanup = Arc::downgrade(&limiter);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(CLEANUP_INTERVAL);
+            loop {
+                interval.tick().await;
+                let Some(limiter) = cleanup.upgrade() else {
+                    break;
+                };
+                if let Budget::Local { limiter, .. } = &limiter.budget {
+                    limiter.retain_recent();
+                }
+            }
+        });
+    }
+    router.route_layer(middleware::from_fn_with_state(limiter, limit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::tests::{authenticator, token, ISSUER};
+    use axum::body::Body;
+    use axum::http::header::AUTHORIZATION;
+    use axum::http::header::RETRY_AFTER;
+    use axum::http::StatusCode;
+    use axum::routing::get;
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    fn request(peer: [u8; 4], token: Option<&str>) -> Request {
+        let mut request = Request::builder().uri("/");
+        if let Some(token) = token {
+            request = request.header(AUTHORIZATION, format!("Bearer {}", token));
+        }
+        let mut request = request.body(Body::empty()).unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from((peer, 4000))));
+        request
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_per_client() {
+        let config = RateLimitConfig::default();
+        let (auth, key) = authenticator();
+        let app = limited(
+            Router::new().route("/", get(|| async { "ok" })),
+            config.limiter(2, Some(Arc::new(auth))),
+        );
+        let send = |request| {
+            let app = app.clone();
+            async move { app.oneshot(request).await.unwrap() }
+        };
+
+        assert_eq!(send(request([10, 0, 0, 1], None)).await.status(), 200);
+        assert_eq!(send(request([10, 0, 0, 1], None)).await.status(), 200);
+        let refused = send(request([10, 0, 0, 1], None)).await;
+        assert_eq!(refused.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(refused.headers()[RETRY_AFTER], "30");
+
+        // Made-up tokens count against the address
+        let refused = send(request([10, 0, 0, 1], Some("made-up"))).await;
+        assert_eq!(refused.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        // Other addresses and subjects have their own budgets, a subject's
+        // shared across addresses
+        assert_eq!(send(request([10, 0, 0, 2], None)).await.status(), 200);
+        let alice = token(&key, ISSUER, json!(["auditor"]));
+        for _ in 0..2 {
+            let response = send(request([10, 0, 0, 1], Some(&alice))).await;
+            assert_eq!(response.status(), 200);
+        }
+        let refused = send(request([10, 0, 0, 3], Some(&alice))).await;
+        assert_eq!(refused.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    /// Counts in memory, as two replicas sharing a server would see them
+    #[derive(Default)]
+    struct Counts(std::sync::Mutex<std::collections::HashMap<String, u64>>);
+
+    #[async_trait::async_trait]
+    impl ClusterState for Counts {
+        async fn count(
+            &self,
+            key: &str,
+            window_secs: u64,
+            now: u64,
+        ) -> anyhow::Result<crate::cluster::WindowCount> {
+            let mut counts = self.0.lock().unwrap();
+            let count = counts
+                .entry(format!("{}:{}", key, now / window_secs))
+                .or_default();
+            *count += 1;
+            Ok(crate::cluster::WindowCount {
+                count: *count,
+                remaining_secs: window_secs - now % window_secs,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shared_budget() {
+        let config = RateLimitConfig::default();
+        let state: Arc<dyn ClusterState> = Arc::new(Counts::default());
+        let replica = || {
+            limited(
+                Router::new().route("/", get(|| async { "ok" })),
+                config.shared_limiter(state.clone(), "read", 2, None),
+            )
+        };
+        let (first, second) = (replica(), replica());
+
+        // Requests to either replica draw on one budget
+        let response = first.clone().oneshot(request([10, 0, 0, 1], None));
+        assert_eq!(response.await.unwrap().status(), 200);
+        let response = second.clone().oneshot(request([10, 0, 0, 1], None));
+        assert_eq!(response.await.unwrap().status(), 200);
+        let refused = first.oneshot(request([10, 0, 0, 1], None)).await.unwrap();
+        assert_eq!(refused.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = refused.headers()[RETRY_AFTER]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((1..=60).contains(&retry_after));
+        let response = second.oneshot(request([10, 0, 0, 2], None));
+        assert_eq!(response.await.unwrap().status(), 200);
+    }
+}
@@ -0,0 +1,211 @@
+// Canonical document registry
+//
+// The set of registered document hashes and its Merkle commitment, computed
+// as `zkrag_circuits::utils::compute_document_commitment` does, so a prover
+// committing to the same hashes gets the same root. The set is loaded from
+// the store at startup and kept in memory; the root is recomputed on each
+// registration and served without touching the database.
+//
+// Provers building a witness for one document fetch its inclusion path from
+// `GET /api/v1/document/:hash/proof` rather than the whole tree. Leaves are
+// SHA-256(0x00 || hex hash) and nodes SHA-256(0x01 || left || right); a node
+// left unpaired at a level is carried up without a step, so the path lists
+// only the siblings actually hashed in, each with its side.
+// `zkrag_circuits::utils::verify_document_inclusion` checks one.
+
+use serde::Serialize;
+use std::collections::BTreeSet;
+use utoipa::ToSchema;
+use zkrag_circuits::utils::{compute_document_commitment, document_inclusion_proof};
+
+/// Registered document hashes and their commitment
+pub struct DocumentRegistry {
+    hashes: BTreeSet<String>,
+    commitment: String,
+}
+
+impl DocumentRegistry {
+    pub fn new(hashes: impl IntoIterator<Item = String>) -> Self {
+        let hashes: BTreeSet<String> = hashes.into_iter().collect();
+        let commitment = commit(&hashes);
+        Self { hashes, commitment }
+    }
+
+    /// Add hashes, returning how many weren't already registered
+    pub fn insert(&mut self, hashes: impl IntoIterator<Item = String>) -> usize {
+        let added = hashes
+            .into_iter()
+            .filter(|hash| self.hashes.insert(hash.clone()))
+            .count();
+        if added > 0 {
+            self.commitment = commit(&self.hashes);
+        }
+        added
+    }
+
+    /// Merkle root over every registered hash
+    pub fn commitment(&self) -> &str {
+        &self.commitment
+    }
+
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    /// Path from `hash` to the commitment, or `None` if it isn't registered
+    pub fn inclusion_proof(&self, hash: &str) -> Option<DocumentProof> {
+        if !self.hashes.contains(hash) {
+            return None;
+        }
+        let hashes: Vec<&String> = self.hashes.iter().collect();
+        let proof = document_inclusion_proof(&hashes, hash)?;
+        Some(DocumentProof {
+            document_hash: hash.to_string(),
+            commitment: self.commitment.clone(),
+            index: proof.index,
+            document_count: proof.leaf_count,
+            path: proof
+                .path
+                .iter()
+                .map(|step| PathStep {
+                    sibling: hex::encode(step.sibling),
+                    side: if step.sibling_is_left {
+                        Side::Left
+                    } else {
+                        Side::Right
+                    },
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Inclusion path of a registered document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct DocumentProof {
+    pub document_hash: String,
+    /// Registry commitment the path leads to
+    pub commitment: String,
+    /// Position of the document's leaf among the sorted hashes
+    pub index: usize,
+    pub document_count: usize,
+    /// Siblings from the leaf up
+    pub path: Vec<PathStep>,
+}
+
+/// Sibling hashed in at one level
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct PathStep {
+    /// Hex SHA-256 of the sibling node
+    pub sibling: String,
+    /// Which input of the parent the sibling is
+    pub side: Side,
+}
+
+/// Side of a sibling node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Left,
+    Right,
+}
+
+fn commit(hashes: &BTreeSet<String>) -> String {
+    compute_document_commitment(&hashes.iter().collect::<Vec<_>>())
+}
+
+/// Canonical form of a document hash (lowercase hex SHA-256), or `None` if
+/// it isn't one. Leaves hash the hex text, so case must be normalized.
+pub fn normalize_hash(hash: &str) -> Option<String> {
+    let hash = hash.to_ascii_lowercase();
+    (hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit())).then_some(hash)
+}
+
+/// Commitment to register along with `hashes`: `commitment` in canonical
+/// form if given, else the commitment of `hashes`. `None` if `commitment`
+/// isn't a digest, or doesn't commit to a nonempty `hashes`.
+pub fn registration_commitment(commitment: Option<&str>, hashes: &[String]) -> Option<String> {
+    match commitment {
+        Some(commitment) => {
+            let commitment = normalize_hash(commitment)?;
+            (hashes.is_empty() || commitment == compute_document_commitment(hashes))
+                .then_some(commitment)
+        }
+        None => Some(compute_document_commitment(hashes)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zkrag_circuits::utils::{document_hash, verify_document_inclusion, MerkleStep};
+
+    #[test]
+    fn test_registry_commitment() {
+        let a = document_hash(b"a");
+        let b = document_hash(b"b");
+        let mut registry = DocumentRegistry::new([a.clone()]);
+        assert_eq!(registry.commitment(), compute_document_commitment(&[&a]));
+
+        assert_eq!(registry.insert([b.clone(), a.clone()]), 1);
+        assert_eq!(registry.insert([b.clone()]), 0);
+        assert_eq!(registry.len(), 2);
+        assert_eq!(
+            registry.commitment(),
+            compute_document_commitment(&[&b, &a])
+        );
+
+        // Each registered hash has a path to the commitment
+        let proof = registry.inclusion_proof(&b).unwrap();
+        assert_eq!(proof.commitment, registry.commitment());
+        assert_eq!(proof.document_count, 2);
+        assert_eq!(proof.path.len(), 1);
+        let path: Vec<MerkleStep> = proof
+            .path
+            .iter()
+            .map(|step| MerkleStep {
+                sibling: hex::decode(&step.sibling).unwrap().try_into().unwrap(),
+                sibling_is_left: step.side == Side::Left,
+            })
+            .collect();
+        assert!(verify_document_inclusion(&b, &path, registry.commitment()));
+        assert_eq!(registry.inclusion_proof(&document_hash(b"c")), None);
+
+        assert_eq!(normalize_hash(&a.to_uppercase()), Some(a));
+        assert_eq!(normalize_hash("abc"), None);
+    }
+
+    #[test]
+    fn test_registration_commitment() {
+        let hashes = vec![document_hash(b"a"), document_hash(b"b")];
+        let commitment = compute_document_commitment(&hashes);
+        assert_eq!(
+            registration_commitment(None, &hashes).as_ref(),
+            Some(&commitment)
+        );
+
+        // A commitment given in mixed case is matched and kept in lowercase
+        let mixed: String = commitment
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                if i % 2 == 0 {
+                    c.to_ascii_uppercase()
+                } else {
+                    c
+                }
+            })
+            .collect();
+        assert_ne!(mixed, commitment);
+        assert_eq!(
+            registration_commitment(Some(&mixed), &hashes).as_ref(),
+            Some(&commitment)
+        );
+        assert_eq!(
+            registration_commitment(Some(&mixed), &[]).as_ref(),
+            Some(&commitment)
+        );
+        assert_eq!(registration_commitment(Some(&mixed), &hashes[..1]), None);
+        assert_eq!(registration_commitment(Some("abc"), &[]), None);
+    }
+}
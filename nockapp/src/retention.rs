@@ -0,0 +1,215 @@
+// Retention
+//
+// A background task deletes expired records every `interval_secs`, so the
+// database of a busy deployment doesn't grow without bound:
+// - idempotency keys older than the idempotency window (see idempotency.rs).
+//   Claiming a key already skips expired ones; the sweep frees their rows
+//   when no new keys come in.
+// - challenge nonces past their expiry (see challenge.rs), which can no
+//   longer be spent
+// - query records, with their proofs and receipts, verified more than
+//   `query_secs` ago. Queries are kept forever unless a retention is set.
+//   A tenant (the owner of the document a query names, as in the audit
+//   trail) may be given a retention of its own under `[retention.tenants]`,
+//   which replaces the default for its queries; 0 keeps them forever.
+//
+// Deleted queries drop out of the audit trail, the statistics and
+// `/api/v1/query/:id`, and their ids are never reused. Proof generation jobs
+// are queued in memory and gone once answered, so there are none to expire.
+//
+// Configuration (the `[retention]` table; see config.rs):
+// - interval_secs (ZKRAG_RETENTION_INTERVAL): time between sweeps (default
+//   3600); 0 turns the task off
+// - query_secs (ZKRAG_QUERY_RETENTION_SECS): default query retention
+//   (default 0, forever)
+// - tenants: retention per tenant, e.g. `tenants = { alice = 2592000 }`
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::store::{RetentionScope, Store, StoreError};
+use crate::unix_now;
+
+/// When records expire
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetentionConfig {
+    pub interval_secs: u64,
+    pub query_secs: u64,
+    pub tenants: BTreeMap<String, u64>,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 3600,
+            query_secs: 0,
+            tenants: BTreeMap::new(),
+        }
+    }
+}
+
+/// Records deleted by a sweep
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Expired {
+    pub queries: u64,
+    pub idempotency_keys: u64,
+    pub challenges: u64,
+}
+
+/// Delete what has expired at `now`; idempotency keys last
+/// `idempotency_window` seconds
+pub async fn sweep(
+    store: &dyn Store,
+    config: &RetentionConfig,
+    idempotency_window: u64,
+    now: u64,
+) -> Result<Expired, StoreError> {
+    let mut expired = Expired {
+        idempotency_keys: store
+            .expire_idempotency_keys(now.saturating_sub(idempotency_window))
+            .await?,
+        challenges: store.expire_challenges(now).await?,
+        ..Expired::default()
+    };
+    for (tenant, &secs) in &config.tenants {
+        if secs > 0 {
+            expired.queries += store
+                .expire_queries(RetentionScope::Tenant(tenant), now.saturating_sub(secs))
+                .await?;
+        }
+    }
+    if config.query_secs > 0 {
+        let tenants: Vec<String> = config.tenants.keys().cloned().collect();
+        expired.queries += store
+            .expire_queries(
+                RetentionScope::Except(&tenants),
+                now.saturating_sub(config.query_secs),
+            )
+            .await?;
+    }
+    Ok(expired)
+}
+
+/// Sweep every `config.interval_secs` until `shutdown`
+pub fn spawn(
+    store: Arc<dyn Store>,
+    config: RetentionConfig,
+    idempotency_window: u64,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) {
+    if config.interval_secs == 0 {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(config.interval_secs));
+        tokio::pin!(shutdown);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                () = &mut shutdown => break,
+            }
+            match sweep(store.as_ref(), &config, idempotency_window, unix_now()).await {
+                Ok(expired) if expired != Expired::default() => info!(
+                    "Expired {} queries, {} idempotency keys and {} challenges",
+                    expired.queries, expired.idempotency_keys, expired.challenges
+                ),
+                Ok(_) => {}
+                Err(e) => warn!("Failed to expire records: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{test_store, IdempotencyClaim, QueryRecord};
+    use zkrag_verifier::{ProofEnvelope, PublicInputs};
+
+    #[tokio::test]
+    async fn test_sweep() {
+        let store = test_store().await;
+        store
+            .register_document(&"a1".repeat(32), "alice", &[], 1)
+            .await
+            .unwrap();
+        store
+            .register_document(&"b1".repeat(32), "bob", &[], 1)
+            .await
+            .unwrap();
+
+        // A day old and a minute old for alice, bob and nobody
+        let now = 1_700_000_000;
+        for commitment in ["a1", "b1", "c1"] {
+            for verified_at in [now - 86_400, now - 60] {
+                let record = QueryRecord {
+                    id: 0,
+                    proof_digest: "ab".repeat(32),
+                    document_commitment: commitment.repeat(32),
+                    model_hash: "ef".repeat(32),
+                    timestamp: verified_at,
+                    verified: true,
+                    reason: None,
+                    verified_at,
+                };
+                let inputs = PublicInputs {
+                    document_commitment: record.document_commitment.clone(),
+                    model_hash: record.model_hash.clone(),
+                    timestamp: record.timestamp,
+                    nonce: None,
+                };
+                let envelope = ProofEnvelope::new(vec![1], inputs);
+                store
+                    .record_query(&record, &envelope, None, None)
+                    .await
+                    .unwrap();
+            }
+        }
+        for (key, claimed_at) in [("old", now - 86_400), ("new", now - 60)] {
+            let claim = store
+                .claim_idempotency_key("s", key, "d", claimed_at, 0)
+                .await
+                .unwrap();
+            assert_eq!(claim, IdempotencyClaim::Claimed);
+        }
+        store.issue_challenge("old", "s", now - 1).await.unwrap();
+        store.issue_challenge("new", "s", now + 60).await.unwrap();
+
+        // Everyone's queries last an hour, bob's a minute, alice's forever
+        let config = RetentionConfig {
+            query_secs: 3600,
+            tenants: BTreeMap::from([("alice".to_string(), 0), ("bob".to_string(), 30)]),
+            ..RetentionConfig::default()
+        };
+        let expired = sweep(&store, &config, 3600, now).await.unwrap();
+        assert_eq!(
+            expired,
+            Expired {
+                queries: 3,
+                idempotency_keys: 1,
+                challenges: 1,
+            }
+        );
+        let (left, _) = store
+            .list_queries(&Default::default(), 0, 10)
+            .await
+            .unwrap();
+        let left: Vec<_> = left
+            .iter()
+            .map(|query| (&query.document_commitment[..2], query.verified_at))
+            .collect();
+        assert_eq!(
+            left,
+            [("c1", now - 60), ("a1", now - 60), ("a1", now - 86_400)]
+        );
+
+        // Nothing more to expire
+        let expired = sweep(&store, &config, 3600, now).await.unwrap();
+        assert_eq!(expired, Expired::default());
+    }
+}
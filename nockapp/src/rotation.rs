@@ -0,0 +1,338 @@
+// Verifying key rotation
+//
+// Admins roll out a new verifying key version without a restart:
+// - `POST /api/v1/keys/verifying-keys` uploads a compressed key (the body)
+//   for a circuit, active from `activates_at` (default now). The key current
+//   at that time is deprecated then and retires `grace_secs` later, so
+//   proofs made just before the switch still verify: proofs naming it by
+//   key id, and proofs that name no key, which are checked against keys in
+//   their grace window when the current key refuses them. Provers may fetch
+//   the new key by its fingerprint from `/api/v1/keys/verifying-key` ahead of
+//   its activation.
+// - `POST /api/v1/keys/verifying-keys/{fingerprint}/deprecate` schedules the
+//   deprecation of a key, e.g. to bring a retirement forward or put one
+//   off. A circuit's last key can't be retired.
+// - `GET /api/v1/keys/verifying-keys` lists every key with its schedule and
+//   state: `pending`, `active`, `deprecated` (in its grace window) or
+//   `retired`.
+//
+// Proofs naming a key that is pending or retired fail verification with
+// `key_not_active` or `key_retired`. Uploaded keys and schedules are kept in
+// the store and applied over the key directory's key at startup.
+//
+// Configuration (the `[rotation]` table; see config.rs):
+// - grace_secs (ZKRAG_KEY_GRACE_SECS): how long a deprecated key keeps
+//   verifying when a rotation doesn't say (default 7 days)
+
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use zkrag_verifier::curve::Curve;
+use zkrag_verifier::{KeyRegistry, KeyState, RegisteredKey};
+
+use crate::error::{ApiError, ErrorCode};
+use crate::store::StoredKey;
+use crate::validate::Validator;
+
+/// Default grace window of deprecated keys
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RotationConfig {
+    pub grace_secs: u64,
+}
+
+impl Default for RotationConfig {
+    fn default() -> Self {
+        Self {
+            grace_secs: 7 * 24 * 60 * 60,
+        }
+    }
+}
+
+/// Query of `POST /api/v1/keys/verifying-keys`
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+#[serde(deny_unknown_fields)]
+pub struct UploadKeyParams {
+    /// Circuit the key verifies (default `document_query`)
+    pub circuit_id: Option<String>,
+    /// Pairing curve, `bn254` (default) or `bls12_381`
+    pub curve: Option<String>,
+    /// Unix time the key becomes current (default now)
+    pub activates_at: Option<u64>,
+    /// Seconds the replaced key keeps verifying (default `[rotation]`
+    /// grace_secs)
+    pub grace_secs: Option<u64>,
+}
+
+/// Body of `POST /api/v1/keys/verifying-keys/{fingerprint}/deprecate`
+#[derive(Debug, Default, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct DeprecateKeyRequest {
+    /// Unix time the key stops being current (default now)
+    pub at: Option<u64>,
+    /// Seconds it keeps verifying after that (default `[rotation]`
+    /// grace_secs)
+    pub grace_secs: Option<u64>,
+}
+
+/// A verifying key and where it is in its schedule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct KeyVersion {
+    pub fingerprint: String,
+    pub circuit_id: String,
+    pub curve: String,
+    /// `pending`, `active`, `deprecated` or `retired`
+    #[schema(value_type = String)]
+    pub state: KeyState,
+    /// Whether proofs naming no key are checked against it
+    pub current: bool,
+    pub activates_at: u64,
+    pub deprecated_at: Option<u64>,
+    pub retires_at: Option<u64>,
+}
+
+impl KeyVersion {
+    /// Describe `key` of `keys` at `now`
+    pub fn new(keys: &KeyRegistry, key: &RegisteredKey, now: u64) -> Self {
+        let current = keys
+            .current_at(&key.circuit_id, now)
+            .is_some_and(|current| current.key_id == key.key_id);
+        Self {
+            fingerprint: key.key_id.clone(),
+            circuit_id: key.circuit_id.clone(),
+            curve: key.key.curve().to_string(),
+            state: key.schedule.state(now),
+            current,
+            activates_at: key.schedule.activates_at,
+            deprecated_at: key.schedule.deprecated_at,
+            retires_at: key.schedule.retires_at,
+        }
+    }
+}
+
+/// Response of `GET /api/v1/keys/verifying-keys`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct KeyVersionList {
+    /// Ordered by circuit, then activation time
+    pub keys: Vec<KeyVersion>,
+}
+
+/// Response of the rotation routes
+#[derive(Debug, Serialize, ToSchema)]
+pub struct KeyRotationResponse {
+    pub key: KeyVersion,
+    /// Key deprecated by an upload, if any
+    pub deprecated: Option<KeyVersion>,
+}
+
+/// Every key of `keys`, ordered by circuit then activation time
+pub fn list(keys: &KeyRegistry, now: u64) -> KeyVersionList {
+    let mut versions: Vec<_> = keys
+        .key_info()
+        .iter()
+        .filter_map(|info| keys.get(&info.fingerprint))
+        .map(|key| KeyVersion::new(keys, key, now))
+        .collect();
+    versions.sort_by(|a, b| {
+        (&a.circuit_id, a.activates_at, &a.fingerprint).cmp(&(
+            &b.circuit_id,
+            b.activates_at,
+            &b.fingerprint,
+        ))
+    });
+    KeyVersionList { keys: versions }
+}
+
+/// Form in which `key` is saved
+pub fn stored(key: &RegisteredKey) -> StoredKey {
+    StoredKey {
+        key_id: key.key_id.clone(),
+        circuit_id: key.circuit_id.clone(),
+        curve: key.key.curve(),
+        key: key.key.to_bytes(),
+        schedule: key.schedule,
+    }
+}
+
+/// Register `key_bytes` in `keys` as `params` say, deprecating the key it
+/// replaces. Returns the keys to save and the response.
+pub fn upload(
+    keys: &mut KeyRegistry,
+    params: &UploadKeyParams,
+    key_bytes: &[u8],
+    config: &RotationConfig,
+    now: u64,
+) -> Result<(Vec<StoredKey>, KeyRotationResponse), ApiError> {
+    let circuit_id = params
+        .circuit_id
+        .as_deref()
+        .unwrap_or(zkrag_verifier::DOCUMENT_QUERY_CIRCUIT_ID);
+    let mut validator = Validator::new();
+    validator.name("circuit_id", circuit_id);
+    let curve = match params.curve.as_deref().map(str::parse::<Curve>) {
+        None => Curve::Bn254,
+        Some(Ok(curve)) => curve,
+        Some(Err(message)) => {
+            validator.error("curve", message);
+            Curve::Bn254
+        }
+    };
+    validator.finish()?;
+    // Rescheduling a key could leave its circuit without one
+    let key_id = zkrag_verifier::keys::key_digest(key_bytes);
+    if keys.get(&key_id).is_some() {
+        return Err(ApiError::invalid_request(format!(
+            "Key {} is already registered",
+            key_id
+        )));
+    }
+
+    let activates_at = params.activates_at.unwrap_or(now);
+    let grace_secs = params.grace_secs.unwrap_or(config.grace_secs);
+    let (key_id, deprecated) = keys
+        .rotate(circuit_id, curve, key_bytes, activates_at, grace_secs)
+        .map_err(|e| ApiError::invalid_request(format!("Invalid verifying key: {}", e)))?;
+
+    let key = keys.get(&key_id).expect("key was just registered");
+    let deprecated = deprecated.and_then(|key_id| keys.get(&key_id));
+    let saved = std::iter::once(key).chain(deprecated).map(stored);
+    Ok((
+        saved.collect(),
+        KeyRotationResponse {
+            key: KeyVersion::new(keys, key, now),
+            deprecated: deprecated.map(|key| KeyVersion::new(keys, key, now)),
+        },
+    ))
+}
+
+/// Schedule the deprecation of `fingerprint` in `keys` as `request` says.
+/// Returns the key to save and the response.
+pub fn deprecate(
+    keys: &mut KeyRegistry,
+    fingerprint: &str,
+    request: &DeprecateKeyRequest,
+    config: &RotationConfig,
+    now: u64,
+) -> Result<(StoredKey, KeyRotationResponse), ApiError> {
+    let key_id = fingerprint.to_ascii_lowercase();
+    let Some(circuit_id) = keys.get(&key_id).map(|key| key.circuit_id.clone()) else {
+        return Err(ApiError::new(
+            ErrorCode::NotFound,
+            format!("No verifying key {}", fingerprint),
+        ));
+    };
+    let at = request.at.unwrap_or(now);
+    let grace_secs = request.grace_secs.unwrap_or(config.grace_secs);
+    let mut rescheduled = keys.clone();
+    let schedule = rescheduled
+        .deprecate(&key_id, at, grace_secs)
+        .expect("key is registered");
+    if rescheduled
+        .current_at(&circuit_id, schedule.retires_at.unwrap_or(at))
+        .is_none()
+    {
+        return Err(ApiError::invalid_request(format!(
+            "Key {} is the last of circuit {}; upload its replacement first",
+            key_id, circuit_id
+        )));
+    }
+    *keys = rescheduled;
+
+    let key = keys.get(&key_id).expect("key is registered");
+    Ok((
+        stored(key),
+        KeyRotationResponse {
+            key: KeyVersion::new(keys, key, now),
+            deprecated: None,
+        },
+    ))
+}
+
+/// Apply saved keys and schedules over the keys loaded from disk
+pub fn restore(keys: &mut KeyRegistry, saved: &[StoredKey]) -> anyhow::Result<()> {
+    for key in saved {
+        keys.insert_scheduled(&key.circuit_id, key.curve, &key.key, key.schedule)
+            .map_err(|e| anyhow::anyhow!("Saved verifying key {}: {}", key.key_id, e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::{Bn254, Fr};
+    use ark_groth16::Groth16;
+    use ark_serialize::CanonicalSerialize;
+    use zkrag_circuits::DocumentQueryCircuit;
+
+    fn setup(rng: &mut impl ark_std::rand::Rng) -> Vec<u8> {
+        let circuit = DocumentQueryCircuit::new(
+            vec![Fr::from(1u64)],
+            vec![],
+            vec![],
+            Fr::from(2u64),
+            Fr::from(3u64),
+            Fr::from(4u64),
+        );
+        let pk = Groth16::<Bn254>::generate_random_parameters_with_reduction(circuit, rng).unwrap();
+        let mut vk = Vec::new();
+        pk.vk.serialize_compressed(&mut vk).unwrap();
+        vk
+    }
+
+    #[test]
+    fn test_upload_and_deprecate() {
+        let mut rng = ark_std::test_rng();
+        let (first, second) = (setup(&mut rng), setup(&mut rng));
+        let config = RotationConfig::default();
+        let mut keys = KeyRegistry::new();
+        let first_id = keys
+            .insert(zkrag_verifier::DOCUMENT_QUERY_CIRCUIT_ID, &first)
+            .unwrap();
+
+        let params = UploadKeyParams {
+            activates_at: Some(1_000),
+            grace_secs: Some(100),
+            ..UploadKeyParams::default()
+        };
+        let (saved, response) = upload(&mut keys, &params, &second, &config, 900).unwrap();
+        assert_eq!(saved.len(), 2);
+        assert_eq!(response.key.state, KeyState::Pending);
+        let deprecated = response.deprecated.unwrap();
+        assert_eq!(deprecated.fingerprint, first_id);
+        assert!(deprecated.current);
+        assert_eq!(
+            (deprecated.deprecated_at, deprecated.retires_at),
+            (Some(1_000), Some(1_100))
+        );
+
+        let listed = list(&keys, 1_050).keys;
+        let states: Vec<_> = listed.iter().map(|key| (key.state, key.current)).collect();
+        assert_eq!(
+            states,
+            [(KeyState::Deprecated, false), (KeyState::Active, true)]
+        );
+
+        // The new key can't be retired without a replacement, and saved
+        // schedules survive a restart
+        let request = DeprecateKeyRequest::default();
+        let refused = deprecate(
+            &mut keys,
+            &response.key.fingerprint,
+            &request,
+            &config,
+            2_000,
+        );
+        assert_eq!(refused.unwrap_err().code, ErrorCode::InvalidRequest);
+        let unknown = deprecate(&mut keys, &"00".repeat(32), &request, &config, 2_000);
+        assert_eq!(unknown.unwrap_err().code, ErrorCode::NotFound);
+        let mut restarted = KeyRegistry::new();
+        restarted
+            .insert(zkrag_verifier::DOCUMENT_QUERY_CIRCUIT_ID, &first)
+            .unwrap();
+        restore(&mut restarted, &saved).unwrap();
+        assert_eq!(list(&restarted, 1_050).keys, listed);
+        assert!(upload(&mut keys, &params, &second[1..], &config, 900).is_err());
+        assert!(upload(&mut keys, &params, &second, &config, 900).is_err());
+    }
+}
@@ -0,0 +1,269 @@
+// Verification statistics
+//
+// `GET /api/v1/stats` reports usage straight from the store: how many
+// verifications there were and how many failed, per model, per document
+// commitment and per UTC day, with the failures broken down by reason code.
+// `since` and `until` bound the period by `verified_at`; the model and
+// document lists hold the `top` busiest. Proofs refused as malformed are
+// never recorded, so they aren't counted.
+
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::blank_as_none;
+use crate::store::{GroupCounts, StatsFilter, StatsGroup, Store, StoreError};
+
+/// Models and documents listed by default
+pub const DEFAULT_TOP: u64 = 20;
+
+/// Most models and documents listed
+pub const MAX_TOP: u64 = 1000;
+
+/// Period and list length of `/api/v1/stats`; blank parameters are ignored
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StatsParams {
+    /// Only verifications at or after this Unix time
+    #[serde(default, deserialize_with = "blank_as_none")]
+    pub since: Option<u64>,
+    /// Only verifications before this Unix time
+    #[serde(default, deserialize_with = "blank_as_none")]
+    pub until: Option<u64>,
+    /// Models and documents to list, busiest first (default 20)
+    #[serde(default, deserialize_with = "blank_as_none")]
+    pub top: Option<u64>,
+}
+
+impl StatsParams {
+    pub fn filter(&self) -> StatsFilter {
+        StatsFilter {
+            since: self.since,
+            until: self.until,
+        }
+    }
+}
+
+/// Verifications and how they ended
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, ToSchema)]
+pub struct Counts {
+    pub total: u64,
+    pub verified: u64,
+    pub failed: u64,
+}
+
+impl From<&GroupCounts> for Counts {
+    fn from(group: &GroupCounts) -> Self {
+        Self {
+            total: group.verified + group.failed,
+            verified: group.verified,
+            failed: group.failed,
+        }
+    }
+}
+
+/// Verifications of queries naming a model
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct ModelStats {
+    pub model_hash: String,
+    #[serde(flatten)]
+    pub counts: Counts,
+}
+
+/// Verifications of queries over a document commitment
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct DocumentStats {
+    pub document_commitment: String,
+    #[serde(flatten)]
+    pub counts: Counts,
+}
+
+/// Verifications on a UTC day
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct DayStats {
+    /// YYYY-MM-DD
+    pub day: String,
+    #[serde(flatten)]
+    pub counts: Counts,
+}
+
+/// Failed verifications with a reason code
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct FailureStats {
+    /// `code` of the failure reason, e.g. `pairing_failed`
+    pub code: String,
+    pub count: u64,
+}
+
+/// Body of `GET /api/v1/stats`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Stats {
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    /// Every verification in the period
+    #[serde(flatten)]
+    pub counts: Counts,
+    /// Busiest models first
+    pub by_model: Vec<ModelStats>,
+    /// Busiest documents first
+    pub by_document: Vec<DocumentStats>,
+    /// Days with verifications, oldest first
+    pub by_day: Vec<DayStats>,
+    /// Most frequent first
+    pub failures: Vec<FailureStats>,
+}
+
+/// Statistics of the verifications `params` select
+pub async fn report(store: &dyn Store, params: &StatsParams) -> Result<Stats, StoreError> {
+    let filter = params.filter();
+    let top = params.top.unwrap_or(DEFAULT_TOP);
+    let by_day = store
+        .verification_stats(&filter, StatsGroup::Day, u64::MAX)
+        .await?;
+    let by_model = store
+        .verification_stats(&filter, StatsGroup::Model, top)
+        .await?;
+    let by_document = store
+        .verification_stats(&filter, StatsGroup::Document, top)
+        .await?;
+    let failures = store
+        .verification_stats(&filter, StatsGroup::Reason, u64::MAX)
+        .await?;
+
+    // Every verification falls on exactly one day
+    let counts = by_day
+        .iter()
+        .map(Counts::from)
+        .fold(Counts::default(), |sum, day| Counts {
+            total: sum.total + day.total,
+            verified: sum.verified + day.verified,
+            failed: sum.failed + day.failed,
+        });
+    Ok(Stats {
+        since: params.since,
+        until: params.until,
+        counts,
+        by_model: by_model
+            .iter()
+            .map(|group| ModelStats {
+                model_hash: group.key.clone(),
+                counts: group.into(),
+            })
+            .collect(),
+        by_document: by_document
+            .iter()
+            .map(|group| DocumentStats {
+                document_commitment: group.key.clone(),
+                counts: group.into(),
+            })
+            .collect(),
+        by_day: by_day
+            .iter()
+            .map(|group| DayStats {
+                day: group.key.clone(),
+                counts: group.into(),
+            })
+            .collect(),
+        failures: failures
+            .iter()
+            .map(|group| FailureStats {
+                code: group.key.clone(),
+                count: group.failed,
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{test_store, QueryRecord};
+    use zkrag_verifier::{ProofEnvelope, PublicInputs, VerificationFailure};
+
+    #[tokio::test]
+    async fn test_report() {
+        let store = test_store().await;
+
+        // 2023-11-14 and 2023-11-15, UTC
+        let day = 1_700_000_000;
+        let queries = [
+            ("AA", "c1", day, None),
+            (
+                "aa",
+                "c1",
+                day + 60,
+                Some(VerificationFailure::PairingFailed),
+            ),
+            (
+                "bb",
+                "c2",
+                day + 86_400,
+                Some(VerificationFailure::PairingFailed),
+            ),
+            (
+                "bb",
+                "c2",
+                day + 86_460,
+                Some(VerificationFailure::InputMismatch),
+            ),
+            ("bb", "c1", day + 86_520, None),
+        ];
+        for (model_hash, commitment, verified_at, reason) in queries {
+            let record = QueryRecord {
+                id: 0,
+                proof_digest: "ab".repeat(32),
+                document_commitment: commitment.to_string(),
+                model_hash: model_hash.to_string(),
+                timestamp: verified_at,
+                verified: reason.is_none(),
+                reason,
+                verified_at,
+            };
+            let inputs = PublicInputs {
+                document_commitment: record.document_commitment.clone(),
+                model_hash: record.model_hash.clone(),
+                timestamp: record.timestamp,
+                nonce: None,
+            };
+            let envelope = ProofEnvelope::new(vec![1], inputs);
+            store
+                .record_query(&record, &envelope, None, None)
+                .await
+                .unwrap();
+        }
+
+        let stats = report(&store, &StatsParams::default()).await.unwrap();
+        let counts = |total, verified, failed| Counts {
+            total,
+            verified,
+            failed,
+        };
+        assert_eq!(stats.counts, counts(5, 2, 3));
+        let models: Vec<_> = stats
+            .by_model
+            .iter()
+            .map(|model| (model.model_hash.as_str(), model.counts))
+            .collect();
+        assert_eq!(models, [("bb", counts(3, 1, 2)), ("aa", counts(2, 1, 1))]);
+        let days: Vec<_> = stats.by_day.iter().map(|day| day.day.as_str()).collect();
+        assert_eq!(days, ["2023-11-14", "2023-11-15"]);
+        assert_eq!(
+            stats.failures[0],
+            FailureStats {
+                code: "pairing_failed".to_string(),
+                count: 2,
+            }
+        );
+
+        // The period and list length narrow the report
+        let params = StatsParams {
+            since: Some(day + 86_400),
+            until: Some(day + 86_500),
+            top: Some(1),
+        };
+        let stats = report(&store, &params).await.unwrap();
+        assert_eq!(stats.counts, counts(2, 0, 2));
+        assert_eq!(stats.by_document.len(), 1);
+        assert_eq!(stats.by_document[0].document_commitment, "c2");
+        assert_eq!(stats.failures.len(), 2);
+    }
+}
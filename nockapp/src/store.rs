@@ -0,0 +1,1493 @@
+// Persistent state of the HTTP service
+//
+// Registered and revoked documents and models, the document hashes in the
+// registry's Merkle tree, the outcome, proof and receipt of each query
+// verification (or, for a proof moved to the archive, its object key; see
+// archive.rs), the nullifiers of accepted proofs (see nullifiers.rs), the
+// webhooks notified of them, the responses kept for idempotency keys, and
+// the verifying keys rotated in at runtime, behind the
+// `Store` trait so the driver doesn't depend on the backend. `SqliteStore` is the default;
+// migrations in ../migrations are embedded at build time and applied when
+// the store opens, and ids come from AUTOINCREMENT columns, so they keep
+// increasing across restarts. Each call runs in a span named after it, so
+// traces show where a request's time went in the database; see
+// telemetry.rs.
+
+use async_trait::async_trait;
+use serde::Serialize;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions, SqliteRow};
+use sqlx::{Row, SqliteConnection};
+use std::str::FromStr;
+use tracing::instrument;
+use utoipa::ToSchema;
+use zkrag_verifier::curve::Curve;
+use zkrag_verifier::{
+    KeySchedule, ProofEnvelope, RevocationList, VerificationFailure, VerificationReceipt,
+};
+
+/// Storage failure
+#[derive(Debug, thiserror::Error)]
+pub enum StoreError {
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("migration failed: {0}")]
+    Migrate(#[from] sqlx::migrate::MigrateError),
+    #[error("corrupt record: {0}")]
+    Corrupt(String),
+}
+
+pub type Result<T, E = StoreError> = std::result::Result<T, E>;
+
+/// Outcome of verifying a query proof
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct QueryRecord {
+    pub id: u64,
+    pub proof_digest: String,
+    pub document_commitment: String,
+    pub model_hash: String,
+    pub timestamp: u64,
+    pub verified: bool,
+    #[schema(value_type = Option<Object>)]
+    pub reason: Option<VerificationFailure>,
+    pub verified_at: u64,
+}
+
+/// Verification as the audit trail reports it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct AuditEntry {
+    #[serde(flatten)]
+    pub query: QueryRecord,
+    /// Owner of the registered document the query names, if any
+    pub tenant: Option<String>,
+    /// Verifying key that checked the proof; absent for queries recorded
+    /// before proofs were kept
+    pub key_id: Option<String>,
+}
+
+/// Which verifications the audit trail includes; `None` fields match every
+/// verification
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditFilter {
+    /// Earliest `verified_at`, in Unix seconds
+    pub since: Option<u64>,
+    pub verified: Option<bool>,
+    pub tenant: Option<String>,
+}
+
+/// Document registered to an owner
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct OwnedDocument {
+    pub id: u64,
+    pub commitment: String,
+    pub registered_at: u64,
+    /// When the document was revoked; absent while it's in force
+    pub revoked_at: Option<u64>,
+}
+
+/// Model named by an owner's queries
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct ModelUsage {
+    /// Lowercase hex
+    pub model_hash: String,
+    /// Name the model was first registered under; absent if it never was
+    pub model_name: Option<String>,
+    pub queries: u64,
+    pub last_verified_at: u64,
+}
+
+/// Nullifier of an accepted proof
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct NullifierRecord {
+    /// Hex SHA-256 of the proof's public inputs
+    pub nullifier: String,
+    pub epoch: u64,
+    /// Query that spent it
+    pub query_id: u64,
+    pub recorded_at: u64,
+}
+
+/// Nullifiers recorded in an epoch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EpochCount {
+    pub epoch: u64,
+    pub nullifiers: u64,
+}
+
+/// Proof recorded with a query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredProof {
+    /// Envelope as recorded; its proof is empty when archived
+    pub envelope: ProofEnvelope,
+    /// Key of the proof bytes in the proof archive
+    pub archive_key: Option<String>,
+}
+
+/// Queries a retention period applies to, by tenant: the owner of the
+/// document a query names, as in the audit trail
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetentionScope<'a> {
+    /// Queries over the tenant's documents
+    Tenant(&'a str),
+    /// Queries of every other tenant, and of none
+    Except(&'a [String]),
+}
+
+/// Which verifications statistics cover; `None` bounds are open
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StatsFilter {
+    /// Earliest `verified_at`, in Unix seconds
+    pub since: Option<u64>,
+    /// `verified_at` before which verifications count
+    pub until: Option<u64>,
+}
+
+/// What verifications are counted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsGroup {
+    /// Model hash, lowercase
+    Model,
+    /// Document commitment, lowercase
+    Document,
+    /// UTC day of `verified_at`, as YYYY-MM-DD
+    Day,
+    /// Failure reason code; verified queries aren't counted
+    Reason,
+}
+
+impl StatsGroup {
+    fn expression(self) -> &'static str {
+        match self {
+            Self::Model => "lower(model_hash)",
+            Self::Document => "lower(document_commitment)",
+            Self::Day => "date(verified_at, 'unixepoch')",
+            Self::Reason => "json_extract(reason, '$.code')",
+        }
+    }
+}
+
+/// Verifications sharing a group key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupCounts {
+    pub key: String,
+    pub verified: u64,
+    pub failed: u64,
+}
+
+/// Webhook notified of verification outcomes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Webhook {
+    pub id: u64,
+    pub url: String,
+    /// HMAC key payloads are signed with
+    pub secret: String,
+    /// Subject that registered it
+    pub owner: String,
+    pub created_at: u64,
+}
+
+/// Response kept under an idempotency key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredResponse {
+    pub status: u16,
+    /// Media type of `body`
+    pub content_type: String,
+    pub body: Vec<u8>,
+}
+
+/// Verifying key and its rotation schedule
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredKey {
+    pub key_id: String,
+    pub circuit_id: String,
+    pub curve: Curve,
+    /// Compressed key
+    pub key: Vec<u8>,
+    pub schedule: KeySchedule,
+}
+
+/// What became of a request's idempotency key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdempotencyClaim {
+    /// The key is new: run the request, then complete or release the key
+    Claimed,
+    /// A request with the key is still running
+    InUse,
+    /// The key was used with a different request
+    Reused,
+    /// A request with the key already got this response
+    Completed(StoredResponse),
+}
+
+/// Which queries to list; `None` fields match every query
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryFilter {
+    /// Matched ignoring case, like the other hex fields
+    pub document_commitment: Option<String>,
+    pub model_hash: Option<String>,
+    /// Earliest `verified_at`, in Unix seconds
+    pub since: Option<u64>,
+}
+
+/// Backend holding the service's records. Each insert returns the new
+/// record's id.
+#[async_trait]
+pub trait Store: Send + Sync {
+    /// Register a document and add `hashes` to the registry; hashes already
+    /// registered are kept under their first document
+    async fn register_document(
+        &self,
+        commitment: &str,
+        owner: &str,
+        hashes: &[String],
+        now: u64,
+    ) -> Result<u64>;
+
+    /// Every registered document hash
+    async fn document_hashes(&self) -> Result<Vec<String>>;
+
+    /// Register a model by its normalized hash, returning its id; a hash
+    /// already registered keeps its id and name
+    async fn register_model(&self, model_hash: &str, model_name: &str, now: u64) -> Result<u64>;
+
+    /// Whether normalized `model_hash` has been registered as an approved
+    /// model
+    async fn is_model_registered(&self, model_hash: &str) -> Result<bool>;
+
+    /// Revoke document `id`, keeping the time of an earlier revocation;
+    /// returns its commitment, or `None` if there's no such document
+    async fn revoke_document(&self, id: u64, now: u64) -> Result<Option<String>>;
+
+    /// Revoke model `id` like `revoke_document`; returns its hash
+    async fn revoke_model(&self, id: u64, now: u64) -> Result<Option<String>>;
+
+    /// Commitments and model hashes of every revoked document and model
+    async fn revocations(&self) -> Result<RevocationList>;
+
+    /// Record a verification and the proof it checked; `record.id` is
+    /// ignored
+    async fn record_query(
+        &self,
+        record: &QueryRecord,
+        proof: &ProofEnvelope,
+        archive_key: Option<&str>,
+        receipt: Option<&VerificationReceipt>,
+    ) -> Result<u64>;
+
+    /// Record a verified query together with its proof's `nullifier`, in
+    /// `epoch`; `None`, recording nothing, if the nullifier was already
+    /// recorded
+    async fn record_nullified_query(
+        &self,
+        record: &QueryRecord,
+        proof: &ProofEnvelope,
+        archive_key: Option<&str>,
+        receipt: Option<&VerificationReceipt>,
+        nullifier: &str,
+        epoch: u64,
+    ) -> Result<Option<u64>>;
+
+    /// Delete query `id` and the nullifier it spent, as if it was never
+    /// recorded
+    async fn delete_query(&self, id: u64) -> Result<()>;
+
+    async fn get_query(&self, id: u64) -> Result<Option<QueryRecord>>;
+
+    /// Proof recorded with query `id`
+    async fn query_proof(&self, id: u64) -> Result<Option<StoredProof>>;
+
+    /// Signed receipt recorded with query `id`
+    async fn query_receipt(&self, id: u64) -> Result<Option<VerificationReceipt>>;
+
+    /// Queries matching `filter`, newest first, skipping the first `offset`;
+    /// also returns how many match in total
+    async fn list_queries(
+        &self,
+        filter: &QueryFilter,
+        offset: u64,
+        limit: u64,
+    ) -> Result<(Vec<QueryRecord>, u64)>;
+
+    /// Up to `limit` queries with ids above `after`, oldest first
+    async fn queries_after(&self, after: u64, limit: u64) -> Result<Vec<QueryRecord>>;
+
+    /// Up to `limit` verifications matching `filter` with ids above `after`,
+    /// oldest first
+    async fn audit_trail(
+        &self,
+        filter: &AuditFilter,
+        after: u64,
+        limit: u64,
+    ) -> Result<Vec<AuditEntry>>;
+
+    /// Documents registered to `owner`, oldest first
+    async fn owner_documents(&self, owner: &str) -> Result<Vec<OwnedDocument>>;
+
+    /// Models named by queries over `owner`'s documents, most recently
+    /// verified first
+    async fn owner_models(&self, owner: &str) -> Result<Vec<ModelUsage>>;
+
+    /// Verifications matching `filter` counted by `group`: days oldest
+    /// first, other groups the `limit` largest, largest first
+    async fn verification_stats(
+        &self,
+        filter: &StatsFilter,
+        group: StatsGroup,
+        limit: u64,
+    ) -> Result<Vec<GroupCounts>>;
+
+    async fn add_webhook(&self, url: &str, secret: &str, owner: &str, now: u64) -> Result<u64>;
+
+    /// Webhooks registered by `owner`, or every webhook for `None`, oldest
+    /// first
+    async fn webhooks(&self, owner: Option<&str>) -> Result<Vec<Webhook>>;
+
+    /// Remove `owner`'s webhook `id`; false if they have none by that id
+    async fn delete_webhook(&self, id: u64, owner: &str) -> Result<bool>;
+
+    /// Claim `scope`'s idempotency `key` for the request with digest
+    /// `request_digest`, first forgetting keys claimed before `expired`
+    async fn claim_idempotency_key(
+        &self,
+        scope: &str,
+        key: &str,
+        request_digest: &str,
+        now: u64,
+        expired: u64,
+    ) -> Result<IdempotencyClaim>;
+
+    /// Keep `response` under a claimed key
+    async fn complete_idempotency_key(
+        &self,
+        scope: &str,
+        key: &str,
+        response: &StoredResponse,
+    ) -> Result<()>;
+
+    /// Give up a claimed key whose request had no effect, so it may be retried
+    async fn release_idempotency_key(&self, scope: &str, key: &str) -> Result<()>;
+
+    /// Forget idempotency keys claimed before `before`, returning how many
+    async fn expire_idempotency_keys(&self, before: u64) -> Result<u64>;
+
+    /// Keep a challenge `nonce` issued to `subject` until `expires_at`
+    async fn issue_challenge(&self, nonce: &str, subject: &str, expires_at: u64) -> Result<()>;
+
+    /// Spend `subject`'s challenge `nonce`, returning when it expires, or
+    /// `None` if it's unknown, spent, or expired at `now`
+    async fn consume_challenge(&self, nonce: &str, subject: &str, now: u64) -> Result<Option<u64>>;
+
+    /// Forget challenges that expired before `before`, returning how many
+    async fn expire_challenges(&self, before: u64) -> Result<u64>;
+
+    /// Whether `nullifier` was recorded
+    async fn nullifier_recorded(&self, nullifier: &str) -> Result<bool>;
+
+    /// Epochs with nullifiers, oldest first
+    async fn nullifier_epochs(&self) -> Result<Vec<EpochCount>>;
+
+    /// Up to `limit` of `epoch`'s nullifiers spent by queries with ids above
+    /// `after`, in query order
+    async fn epoch_nullifiers(
+        &self,
+        epoch: u64,
+        after: u64,
+        limit: u64,
+    ) -> Result<Vec<NullifierRecord>>;
+
+    /// Forget `epoch`'s nullifiers, returning how many
+    async fn expire_nullifiers(&self, epoch: u64) -> Result<u64>;
+
+    /// Delete the queries in `scope` verified before `before`, with their
+    /// proofs and receipts, returning how many
+    async fn expire_queries(&self, scope: RetentionScope<'_>, before: u64) -> Result<u64>;
+
+    /// Save a verifying key, replacing the schedule of one already saved
+    async fn save_verifying_key(&self, key: &StoredKey, now: u64) -> Result<()>;
+
+    /// Every saved verifying key, in the order they were last saved
+    async fn verifying_keys(&self) -> Result<Vec<StoredKey>>;
+
+    /// Check that the backend answers
+    async fn ping(&self) -> Result<()>;
+
+    /// Flush and release the backend; later calls fail
+    async fn close(&self);
+}
+
+/// Store backed by a SQLite database
+pub struct SqliteStore {
+    pool: SqlitePool,
+}
+
+impl SqliteStore {
+    /// Open (or create) the database at `url`, e.g. `sqlite:zkrag.db`, and
+    /// apply pending migrations
+    pub async fn open(url: &str) -> Result<Self> {
+        let options = SqliteConnectOptions::from_str(url)?.create_if_missing(true);
+        let pool = SqlitePoolOptions::new().connect_with(options).await?;
+        sqlx::migrate!("./migrations").run(&pool).await?;
+        Ok(Self { pool })
+    }
+}
+
+/// Fresh in-memory store for tests. The pool keeps a single connection, as
+/// each connection to `:memory:` would open a database of its own.
+#[cfg(test)]
+pub(crate) async fn test_store() -> SqliteStore {
+    let options = SqliteConnectOptions::from_str("sqlite::memory:").unwrap();
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect_with(options)
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    SqliteStore { pool }
+}
+
+#[async_trait]
+impl Store for SqliteStore {
+    #[instrument(skip_all)]
+    async fn register_document(
+        &self,
+        commitment: &str,
+        owner: &str,
+        hashes: &[String],
+        now: u64,
+    ) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query(
+            "INSERT INTO documents (commitment, owner, registered_at) VALUES (?, ?, ?)",
+        )
+        .bind(commitment)
+        .bind(owner)
+        .bind(now as i64)
+        .execute(&mut *tx)
+        .await?;
+        let id = result.last_insert_rowid();
+        for hash in hashes {
+            sqlx::query("INSERT OR IGNORE INTO document_hashes (hash, document_id) VALUES (?, ?)")
+                .bind(hash)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(id as u64)
+    }
+
+    #[instrument(skip_all)]
+    async fn document_hashes(&self) -> Result<Vec<String>> {
+        Ok(sqlx::query_scalar("SELECT hash FROM document_hashes")
+            .fetch_all(&self.pool)
+            .await?)
+    }
+
+    #[instrument(skip_all)]
+    async fn register_model(&self, model_hash: &str, model_name: &str, now: u64) -> Result<u64> {
+        // The no-op update makes RETURNING yield the existing row
+        let id: i64 = sqlx::query_scalar(
+            "INSERT INTO models (model_hash, model_name, registered_at) VALUES (?, ?, ?)
+             ON CONFLICT (model_hash) DO UPDATE SET model_hash = excluded.model_hash
+             RETURNING id",
+        )
+        .bind(model_hash)
+        .bind(model_name)
+        .bind(now as i64)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(id as u64)
+    }
+
+    #[instrument(skip_all)]
+    async fn is_model_registered(&self, model_hash: &str) -> Result<bool> {
+        let row = sqlx::query("SELECT 1 FROM models WHERE model_hash = ? LIMIT 1")
+            .bind(model_hash)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.is_some())
+    }
+
+    #[instrument(skip_all)]
+    async fn revoke_document(&self, id: u64, now: u64) -> Result<Option<String>> {
+        Ok(sqlx::query_scalar(
+            "UPDATE documents SET revoked_at = COALESCE(revoked_at, ?) WHERE id = ?
+             RETURNING commitment",
+        )
+        .bind(now as i64)
+        .bind(id as i64)
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    #[instrument(skip_all)]
+    async fn revoke_model(&self, id: u64, now: u64) -> Result<Option<String>> {
+        Ok(sqlx::query_scalar(
+            "UPDATE models SET revoked_at = COALESCE(revoked_at, ?) WHERE id = ?
+             RETURNING model_hash",
+        )
+        .bind(now as i64)
+        .bind(id as i64)
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    #[instrument(skip_all)]
+    async fn revocations(&self) -> Result<RevocationList> {
+        let document_commitments =
+            sqlx::query_scalar("SELECT commitment FROM documents WHERE revoked_at IS NOT NULL")
+                .fetch_all(&self.pool)
+                .await?;
+        let model_hashes =
+            sqlx::query_scalar("SELECT model_hash FROM models WHERE revoked_at IS NOT NULL")
+                .fetch_all(&self.pool)
+                .await?;
+        Ok(RevocationList {
+            document_commitments: document_commitments.into_iter().collect(),
+            model_hashes: model_hashes.into_iter().collect(),
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn record_query(
+        &self,
+        record: &QueryRecord,
+        proof: &ProofEnvelope,
+        archive_key: Option<&str>,
+        receipt: Option<&VerificationReceipt>,
+    ) -> Result<u64> {
+        let mut conn = self.pool.acquire().await?;
+        insert_query(&mut conn, record, proof, archive_key, receipt).await
+    }
+
+    #[instrument(skip_all)]
+    async fn record_nullified_query(
+        &self,
+        record: &QueryRecord,
+        proof: &ProofEnvelope,
+        archive_key: Option<&str>,
+        receipt: Option<&VerificationReceipt>,
+        nullifier: &str,
+        epoch: u64,
+    ) -> Result<Option<u64>> {
+        let mut tx = self.pool.begin().await?;
+        let id = insert_query(&mut tx, record, proof, archive_key, receipt).await?;
+        let result = sqlx::query(
+            "INSERT OR IGNORE INTO nullifiers (nullifier, epoch, query_id, recorded_at)
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(nullifier)
+        .bind(epoch as i64)
+        .bind(id as i64)
+        .bind(record.verified_at as i64)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            tx.rollback().await?;
+            return Ok(None);
+        }
+        tx.commit().await?;
+        Ok(Some(id))
+    }
+
+    #[instrument(skip_all)]
+    async fn delete_query(&self, id: u64) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM nullifiers WHERE query_id = ?")
+            .bind(id as i64)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM queries WHERE id = ?")
+            .bind(id as i64)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    #[instrument(skip_all)]
+    async fn get_query(&self, id: u64) -> Result<Option<QueryRecord>> {
+        let row = sqlx::query(
+            "SELECT id, proof_digest, document_commitment, model_hash, timestamp, verified,
+                reason, verified_at
+             FROM queries WHERE id = ?",
+        )
+        .bind(id as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref().map(query_record).transpose()
+    }
+
+    #[instrument(skip_all)]
+    async fn query_proof(&self, id: u64) -> Result<Option<StoredProof>> {
+        let row = sqlx::query(
+            "SELECT envelope, proof_key FROM queries WHERE id = ? AND envelope IS NOT NULL",
+        )
+        .bind(id as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let envelope: String = row.try_get("envelope")?;
+        let envelope = ProofEnvelope::from_json(envelope.as_bytes())
+            .map_err(|e| StoreError::Corrupt(format!("query {} proof: {}", id, e)))?;
+        Ok(Some(StoredProof {
+            envelope,
+            archive_key: row.try_get("proof_key")?,
+        }))
+    }
+
+    #[instrument(skip_all)]
+    async fn query_receipt(&self, id: u64) -> Result<Option<VerificationReceipt>> {
+        let receipt: Option<Option<String>> =
+            sqlx::query_scalar("SELECT receipt FROM queries WHERE id = ?")
+                .bind(id as i64)
+                .fetch_optional(&self.pool)
+                .await?;
+        receipt
+            .flatten()
+            .map(|receipt| serde_json::from_str(&receipt))
+            .transpose()
+            .map_err(|e| StoreError::Corrupt(format!("query {} receipt: {}", id, e)))
+    }
+
+    #[instrument(skip_all)]
+    async fn list_queries(
+        &self,
+        filter: &QueryFilter,
+        offset: u64,
+        limit: u64,
+    ) -> Result<(Vec<QueryRecord>, u64)> {
+        const MATCHING: &str = "FROM queries
+             WHERE (?1 IS NULL OR document_commitment = ?1 COLLATE NOCASE)
+               AND (?2 IS NULL OR model_hash = ?2 COLLATE NOCASE)
+               AND (?3 IS NULL OR verified_at >= ?3)";
+        let since = filter.since.map(|since| since as i64);
+
+        let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) {}", MATCHING))
+            .bind(&filter.document_commitment)
+            .bind(&filter.model_hash)
+            .bind(since)
+            .fetch_one(&self.pool)
+            .await?;
+        let rows = sqlx::query(&format!(
+            "SELECT id, proof_digest, document_commitment, model_hash, timestamp, verified,
+                reason, verified_at
+             {}
+             ORDER BY id DESC LIMIT ?4 OFFSET ?5",
+            MATCHING
+        ))
+        .bind(&filter.document_commitment)
+        .bind(&filter.model_hash)
+        .bind(since)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let queries = rows.iter().map(query_record).collect::<Result<_>>()?;
+        Ok((queries, total as u64))
+    }
+
+    #[instrument(skip_all)]
+    async fn queries_after(&self, after: u64, limit: u64) -> Result<Vec<QueryRecord>> {
+        let rows = sqlx::query(
+            "SELECT id, proof_digest, document_commitment, model_hash, timestamp, verified,
+                reason, verified_at
+             FROM queries WHERE id > ? ORDER BY id LIMIT ?",
+        )
+        .bind(after as i64)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(query_record).collect()
+    }
+
+    #[instrument(skip_all)]
+    async fn audit_trail(
+        &self,
+        filter: &AuditFilter,
+        after: u64,
+        limit: u64,
+    ) -> Result<Vec<AuditEntry>> {
+        // A commitment registered more than once belongs to its first owner
+        let rows = sqlx::query(
+            "SELECT * FROM (
+                SELECT id, proof_digest, document_commitment, model_hash, timestamp, verified,
+                    reason, verified_at, json_extract(envelope, '$.key_id') AS key_id,
+                    (SELECT owner FROM documents
+                     WHERE commitment = queries.document_commitment COLLATE NOCASE
+                     ORDER BY id LIMIT 1) AS tenant
+                FROM queries
+                WHERE id > ?1 AND (?2 IS NULL OR verified_at >= ?2)
+                    AND (?3 IS NULL OR verified = ?3)
+             )
+             WHERE ?4 IS NULL OR tenant = ?4
+             ORDER BY id LIMIT ?5",
+        )
+        .bind(after as i64)
+        .bind(filter.since.map(|since| since as i64))
+        .bind(filter.verified)
+        .bind(&filter.tenant)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter()
+            .map(|row| {
+                Ok(AuditEntry {
+                    query: query_record(row)?,
+                    tenant: row.try_get("tenant")?,
+                    key_id: row.try_get("key_id")?,
+                })
+            })
+            .collect()
+    }
+
+    #[instrument(skip_all)]
+    async fn owner_documents(&self, owner: &str) -> Result<Vec<OwnedDocument>> {
+        let rows = sqlx::query(
+            "SELECT id, commitment, registered_at, revoked_at FROM documents
+             WHERE owner = ? ORDER BY id",
+        )
+        .bind(owner)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter()
+            .map(|row| {
+                Ok(OwnedDocument {
+                    id: row.try_get::<i64, _>("id")? as u64,
+                    commitment: row.try_get("commitment")?,
+                    registered_at: row.try_get::<i64, _>("registered_at")? as u64,
+                    revoked_at: row
+                        .try_get::<Option<i64>, _>("revoked_at")?
+                        .map(|at| at as u64),
+                })
+            })
+            .collect()
+    }
+
+    #[instrument(skip_all)]
+    async fn owner_models(&self, owner: &str) -> Result<Vec<ModelUsage>> {
+        // Queries belong to the first owner of their commitment, as in the
+        // audit trail
+        let rows = sqlx::query(
+            "SELECT lower(model_hash) AS model_hash, COUNT(*) AS queries,
+                MAX(verified_at) AS last_verified_at,
+                (SELECT model_name FROM models
+                 WHERE model_hash = queries.model_hash COLLATE NOCASE
+                 ORDER BY id LIMIT 1) AS model_name
+             FROM queries
+             WHERE (SELECT owner FROM documents
+                    WHERE commitment = queries.document_commitment COLLATE NOCASE
+                    ORDER BY id LIMIT 1) = ?
+             GROUP BY lower(model_hash)
+             ORDER BY last_verified_at DESC, model_hash",
+        )
+        .bind(owner)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter()
+            .map(|row| {
+                Ok(ModelUsage {
+                    model_hash: row.try_get("model_hash")?,
+                    model_name: row.try_get("model_name")?,
+                    queries: row.try_get::<i64, _>("queries")? as u64,
+                    last_verified_at: row.try_get::<i64, _>("last_verified_at")? as u64,
+                })
+            })
+            .collect()
+    }
+
+    #[instrument(skip_all)]
+    async fn verification_stats(
+        &self,
+        filter: &StatsFilter,
+        group: StatsGroup,
+        limit: u64,
+    ) -> Result<Vec<GroupCounts>> {
+        let order = match group {
+            StatsGroup::Day => "key",
+            _ => "COUNT(*) DESC, key",
+        };
+        // The expression and order come from the enum, never the request
+        let sql = format!(
+            "SELECT {} AS key, SUM(verified) AS verified, SUM(NOT verified) AS failed
+             FROM queries
+             WHERE (?1 IS NULL OR verified_at >= ?1) AND (?2 IS NULL OR verified_at < ?2)
+                 AND key IS NOT NULL
+             GROUP BY key ORDER BY {} LIMIT ?3",
+            group.expression(),
+            order
+        );
+        let rows = sqlx::query(&sql)
+            .bind(filter.since.map(|since| since as i64))
+            .bind(filter.until.map(|until| until as i64))
+            .bind(limit.min(i64::MAX as u64) as i64)
+            .fetch_all(&self.pool)
+            .await?;
+        rows.iter()
+            .map(|row| {
+                Ok(GroupCounts {
+                    key: row.try_get("key")?,
+                    verified: row.try_get::<i64, _>("verified")? as u64,
+                    failed: row.try_get::<i64, _>("failed")? as u64,
+                })
+            })
+            .collect()
+    }
+
+    #[instrument(skip_all)]
+    async fn add_webhook(&self, url: &str, secret: &str, owner: &str, now: u64) -> Result<u64> {
+        let result = sqlx::query(
+            "INSERT INTO webhooks (url, secret, owner, created_at) VALUES (?, ?, ?, ?)",
+        )
+        .bind(url)
+        .bind(secret)
+        .bind(owner)
+        .bind(now as i64)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.last_insert_rowid() as u64)
+    }
+
+    #[instrument(skip_all)]
+    async fn webhooks(&self, owner: Option<&str>) -> Result<Vec<Webhook>> {
+        let rows = sqlx::query(
+            "SELECT id, url, secret, owner, created_at FROM webhooks
+             WHERE ?1 IS NULL OR owner = ?1 ORDER BY id",
+        )
+        .bind(owner)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter()
+            .map(|row| {
+                Ok(Webhook {
+                    id: row.try_get::<i64, _>("id")? as u64,
+                    url: row.try_get("url")?,
+                    secret: row.try_get("secret")?,
+                    owner: row.try_get("owner")?,
+                    created_at: row.try_get::<i64, _>("created_at")? as u64,
+                })
+            })
+            .collect()
+    }
+
+    #[instrument(skip_all)]
+    async fn delete_webhook(&self, id: u64, owner: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM webhooks WHERE id = ? AND owner = ?")
+            .bind(id as i64)
+            .bind(owner)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    #[instrument(skip_all)]
+    async fn claim_idempotency_key(
+        &self,
+        scope: &str,
+        key: &str,
+        request_digest: &str,
+        now: u64,
+        expired: u64,
+    ) -> Result<IdempotencyClaim> {
+        sqlx::query("DELETE FROM idempotency_keys WHERE created_at < ?")
+            .bind(expired as i64)
+            .execute(&self.pool)
+            .await?;
+        let claimed = sqlx::query(
+            "INSERT OR IGNORE INTO idempotency_keys (scope, key, request_digest, created_at)
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(scope)
+        .bind(key)
+        .bind(request_digest)
+        .bind(now as i64)
+        .execute(&self.pool)
+        .await?;
+        if claimed.rows_affected() > 0 {
+            return Ok(IdempotencyClaim::Claimed);
+        }
+
+        let row = sqlx::query(
+            "SELECT request_digest, status, content_type, body FROM idempotency_keys
+             WHERE scope = ? AND key = ?",
+        )
+        .bind(scope)
+        .bind(key)
+        .fetch_optional(&self.pool)
+        .await?;
+        // Released since the insert; the client may retry
+        let Some(row) = row else {
+            return Ok(IdempotencyClaim::InUse);
+        };
+        if row.try_get::<String, _>("request_digest")? != request_digest {
+            return Ok(IdempotencyClaim::Reused);
+        }
+        match row.try_get::<Option<i64>, _>("status")? {
+            None => Ok(IdempotencyClaim::InUse),
+            Some(status) => Ok(IdempotencyClaim::Completed(StoredResponse {
+                status: u16::try_from(status)
+                    .map_err(|_| StoreError::Corrupt(format!("response status {}", status)))?,
+                content_type: row
+                    .try_get::<Option<String>, _>("content_type")?
+                    .unwrap_or_else(|| "application/json".to_string()),
+                body: row
+                    .try_get::<Option<Vec<u8>>, _>("body")?
+                    .unwrap_or_default(),
+            })),
+        }
+    }
+
+    #[instrument(skip_all)]
+    async fn complete_idempotency_key(
+        &self,
+        scope: &str,
+        key: &str,
+        response: &StoredResponse,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE idempotency_keys SET status = ?, content_type = ?, body = ?
+             WHERE scope = ? AND key = ?",
+        )
+        .bind(i64::from(response.status))
+        .bind(&response.content_type)
+        .bind(&response.body)
+        .bind(scope)
+        .bind(key)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    #[instrument(skip_all)]
+    async fn release_idempotency_key(&self, scope: &str, key: &str) -> Result<()> {
+        sqlx::query("DELETE FROM idempotency_keys WHERE scope = ? AND key = ? AND status IS NULL")
+            .bind(scope)
+            .bind(key)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    #[instrument(skip_all)]
+    async fn expire_idempotency_keys(&self, before: u64) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM idempotency_keys WHERE created_at < ?")
+            .bind(before as i64)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    #[instrument(skip_all)]
+    async fn issue_challenge(&self, nonce: &str, subject: &str, expires_at: u64) -> Result<()> {
+        sqlx::query("INSERT INTO challenges (nonce, subject, expires_at) VALUES (?, ?, ?)")
+            .bind(nonce)
+            .bind(subject)
+            .bind(expires_at as i64)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    #[instrument(skip_all)]
+    async fn consume_challenge(&self, nonce: &str, subject: &str, now: u64) -> Result<Option<u64>> {
+        let expires_at = sqlx::query_scalar::<_, i64>(
+            "DELETE FROM challenges WHERE nonce = ? AND subject = ? AND expires_at >= ?
+             RETURNING expires_at",
+        )
+        .bind(nonce)
+        .bind(subject)
+        .bind(now as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(expires_at.map(|expires_at| expires_at as u64))
+    }
+
+    #[instrument(skip_all)]
+    async fn expire_challenges(&self, before: u64) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM challenges WHERE expires_at < ?")
+            .bind(before as i64)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    #[instrument(skip_all)]
+    async fn nullifier_recorded(&self, nullifier: &str) -> Result<bool> {
+        let row = sqlx::query("SELECT 1 FROM nullifiers WHERE nullifier = ?")
+            .bind(nullifier)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.is_some())
+    }
+
+    #[instrument(skip_all)]
+    async fn nullifier_epochs(&self) -> Result<Vec<EpochCount>> {
+        let rows = sqlx::query(
+            "SELECT epoch, COUNT(*) AS nullifiers FROM nullifiers GROUP BY epoch ORDER BY epoch",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter()
+            .map(|row| {
+                Ok(EpochCount {
+                    epoch: row.try_get::<i64, _>("epoch")? as u64,
+                    nullifiers: row.try_get::<i64, _>("nullifiers")? as u64,
+                })
+            })
+            .collect()
+    }
+
+    #[instrument(skip_all)]
+    async fn epoch_nullifiers(
+        &self,
+        epoch: u64,
+        after: u64,
+        limit: u64,
+    ) -> Result<Vec<NullifierRecord>> {
+        let rows = sqlx::query(
+            "SELECT nullifier, epoch, query_id, recorded_at FROM nullifiers
+             WHERE epoch = ? AND query_id > ?
+             ORDER BY query_id LIMIT ?",
+        )
+        .bind(epoch as i64)
+        .bind(after as i64)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter()
+            .map(|row| {
+                Ok(NullifierRecord {
+                    nullifier: row.try_get("nullifier")?,
+                    epoch: row.try_get::<i64, _>("epoch")? as u64,
+                    query_id: row.try_get::<i64, _>("query_id")? as u64,
+                    recorded_at: row.try_get::<i64, _>("recorded_at")? as u64,
+                })
+            })
+            .collect()
+    }
+
+    #[instrument(skip_all)]
+    async fn expire_nullifiers(&self, epoch: u64) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM nullifiers WHERE epoch = ?")
+            .bind(epoch as i64)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    #[instrument(skip_all)]
+    async fn expire_queries(&self, scope: RetentionScope<'_>, before: u64) -> Result<u64> {
+        let (tenant, except) = match scope {
+            RetentionScope::Tenant(tenant) => (Some(tenant), None),
+            RetentionScope::Except(tenants) => (
+                None,
+                Some(
+                    serde_json::to_string(tenants)
+                        .map_err(|e| StoreError::Corrupt(e.to_string()))?,
+                ),
+            ),
+        };
+        // Tenants as in `audit_trail`
+        let result = sqlx::query(
+            "DELETE FROM queries WHERE id IN (
+                SELECT id FROM (
+                    SELECT id,
+                        (SELECT owner FROM documents
+                         WHERE commitment = queries.document_commitment COLLATE NOCASE
+                         ORDER BY id LIMIT 1) AS tenant
+                    FROM queries
+                    WHERE verified_at < ?1
+                )
+                WHERE tenant = ?2
+                    OR (?3 IS NOT NULL
+                        AND (tenant IS NULL
+                             OR tenant NOT IN (SELECT value FROM json_each(?3))))
+             )",
+        )
+        .bind(before as i64)
+        .bind(tenant)
+        .bind(except)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    #[instrument(skip_all)]
+    async fn save_verifying_key(&self, key: &StoredKey, now: u64) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO verifying_keys
+                 (key_id, circuit_id, curve, key, activates_at, deprecated_at, retires_at,
+                  updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT (key_id) DO UPDATE SET
+                 circuit_id = excluded.circuit_id,
+                 activates_at = excluded.activates_at,
+                 deprecated_at = excluded.deprecated_at,
+                 retires_at = excluded.retires_at,
+                 updated_at = excluded.updated_at",
+        )
+        .bind(&key.key_id)
+        .bind(&key.circuit_id)
+        .bind(key.curve.as_str())
+        .bind(&key.key)
+        .bind(key.schedule.activates_at as i64)
+        .bind(key.schedule.deprecated_at.map(|at| at as i64))
+        .bind(key.schedule.retires_at.map(|at| at as i64))
+        .bind(now as i64)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    #[instrument(skip_all)]
+    async fn verifying_keys(&self) -> Result<Vec<StoredKey>> {
+        let rows = sqlx::query(
+            "SELECT key_id, circuit_id, curve, key, activates_at, deprecated_at, retires_at
+             FROM verifying_keys ORDER BY updated_at, rowid",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter()
+            .map(|row| {
+                let key_id: String = row.try_get("key_id")?;
+                let curve: String = row.try_get("curve")?;
+                let curve = curve
+                    .parse()
+                    .map_err(|e| StoreError::Corrupt(format!("key {}: {}", key_id, e)))?;
+                let at = |column| -> Result<Option<u64>> {
+                    Ok(row.try_get::<Option<i64>, _>(column)?.map(|at| at as u64))
+                };
+                Ok(StoredKey {
+                    circuit_id: row.try_get("circuit_id")?,
+                    curve,
+                    key: row.try_get("key")?,
+                    schedule: KeySchedule {
+                        activates_at: row.try_get::<i64, _>("activates_at")? as u64,
+                        deprecated_at: at("deprecated_at")?,
+                        retires_at: at("retires_at")?,
+                    },
+                    key_id,
+                })
+            })
+            .collect()
+    }
+
+    #[instrument(skip_all)]
+    async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn close(&self) {
+        self.pool.close().await;
+    }
+}
+
+/// Insert the outcome of a verification, returning its query id
+async fn insert_query(
+    conn: &mut SqliteConnection,
+    record: &QueryRecord,
+    proof: &ProofEnvelope,
+    archive_key: Option<&str>,
+    receipt: Option<&VerificationReceipt>,
+) -> Result<u64> {
+    let reason = record
+        .reason
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| StoreError::Corrupt(e.to_string()))?;
+    // Archived proofs leave only their metadata in the row
+    let envelope = match archive_key {
+        Some(_) => serde_json::to_string(&ProofEnvelope {
+            proof: Vec::new(),
+            ..proof.clone()
+        }),
+        None => serde_json::to_string(proof),
+    }
+    .map_err(|e| StoreError::Corrupt(e.to_string()))?;
+    let receipt = receipt
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| StoreError::Corrupt(e.to_string()))?;
+    let result = sqlx::query(
+        "INSERT INTO queries (proof_digest, document_commitment, model_hash, timestamp,
+            verified, reason, verified_at, envelope, proof_key, receipt)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&record.proof_digest)
+    .bind(&record.document_commitment)
+    .bind(&record.model_hash)
+    .bind(record.timestamp as i64)
+    .bind(record.verified)
+    .bind(reason)
+    .bind(record.verified_at as i64)
+    .bind(envelope)
+    .bind(archive_key)
+    .bind(receipt)
+    .execute(conn)
+    .await?;
+    Ok(result.last_insert_rowid() as u64)
+}
+
+/// Decode a row of the queries table
+fn query_record(row: &SqliteRow) -> Result<QueryRecord> {
+    let id = row.try_get::<i64, _>("id")? as u64;
+    let reason = row
+        .try_get::<Option<String>, _>("reason")?
+        .map(|reason| serde_json::from_str(&reason))
+        .transpose()
+        .map_err(|e| StoreError::Corrupt(format!("query {}: {}", id, e)))?;
+    Ok(QueryRecord {
+        id,
+        proof_digest: row.try_get("proof_digest")?,
+        document_commitment: row.try_get("document_commitment")?,
+        model_hash: row.try_get("model_hash")?,
+        timestamp: row.try_get::<i64, _>("timestamp")? as u64,
+        verified: row.try_get("verified")?,
+        reason,
+        verified_at: row.try_get::<i64, _>("verified_at")? as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_model_registered_once() {
+        let store = test_store().await;
+        let hash = "ab".repeat(32);
+        let id = store.register_model(&hash, "m", 1).await.unwrap();
+        assert_eq!(store.register_model(&hash, "other", 2).await.unwrap(), id);
+        assert!(store.is_model_registered(&hash).await.unwrap());
+        let next = store
+            .register_model(&"cd".repeat(32), "n", 3)
+            .await
+            .unwrap();
+        assert_ne!(next, id);
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_store_persists() {
+        let dir = std::env::temp_dir().join(format!("zkrag-store-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let url = format!("sqlite:{}", dir.join("zkrag.db").display());
+
+        let record = QueryRecord {
+            id: 0,
+            proof_digest: "ab".repeat(32),
+            document_commitment: "cd".repeat(32),
+            model_hash: "ef".repeat(32),
+            timestamp: 1_700_000_000,
+            verified: false,
+            reason: Some(VerificationFailure::PairingFailed),
+            verified_at: 1_700_000_010,
+        };
+        let store = SqliteStore::open(&url).await.unwrap();
+        let hashes = vec!["01".repeat(32), "02".repeat(32)];
+        assert_eq!(
+            store
+                .register_document("c", "alice", &hashes, 1)
+                .await
+                .unwrap(),
+            1
+        );
+        let proof = ProofEnvelope::new(
+            vec![7; 128],
+            zkrag_verifier::PublicInputs {
+                document_commitment: record.document_commitment.clone(),
+                model_hash: record.model_hash.clone(),
+                timestamp: record.timestamp,
+                nonce: None,
+            },
+        )
+        .with_key_id("01".repeat(32));
+        let receipt = VerificationReceipt {
+            proof_digest: record.proof_digest.clone(),
+            inputs_digest: "04".repeat(32),
+            is_valid: false,
+            reason: record.reason.clone(),
+            verified_at: record.verified_at,
+            verifying_key_fingerprint: Some("01".repeat(32)),
+            verifier_key_id: "05".repeat(32),
+            signature: "06".repeat(64),
+        };
+        let id = store
+            .record_query(&record, &proof, None, Some(&receipt))
+            .await
+            .unwrap();
+        store
+            .register_model(&record.model_hash, "m", 1)
+            .await
+            .unwrap();
+        let mut key = StoredKey {
+            key_id: "03".repeat(32),
+            circuit_id: "document_query".to_string(),
+            curve: Curve::Bls12_381,
+            key: vec![3; 96],
+            schedule: KeySchedule {
+                activates_at: 10,
+                ..KeySchedule::default()
+            },
+        };
+        store.save_verifying_key(&key, 1).await.unwrap();
+        store.ping().await.unwrap();
+        store.close().await;
+        assert!(store.ping().await.is_err());
+
+        // Reopening keeps records and continues the id sequence
+        let store = SqliteStore::open(&url).await.unwrap();
+        assert_eq!(
+            store
+                .register_document("c", "bob", &hashes[1..], 2)
+                .await
+                .unwrap(),
+            2
+        );
+        let mut stored_hashes = store.document_hashes().await.unwrap();
+        stored_hashes.sort();
+        assert_eq!(stored_hashes, hashes);
+        let stored = store.get_query(id).await.unwrap().unwrap();
+        assert_eq!(
+            stored,
+            QueryRecord {
+                id,
+                ..record.clone()
+            }
+        );
+        assert!(store.get_query(id + 1).await.unwrap().is_none());
+        let stored_proof = store.query_proof(id).await.unwrap().unwrap();
+        assert_eq!(
+            (stored_proof.envelope, stored_proof.archive_key),
+            (proof.clone(), None)
+        );
+        assert!(store.query_proof(id + 1).await.unwrap().is_none());
+        assert_eq!(store.query_receipt(id).await.unwrap(), Some(receipt));
+        assert!(store.is_model_registered(&stored.model_hash).await.unwrap());
+        assert!(!store.is_model_registered("00").await.unwrap());
+
+        // Revocations stick and report what was revoked
+        assert_eq!(
+            store.revoke_model(1, 5).await.unwrap().as_deref(),
+            Some(stored.model_hash.as_str())
+        );
+        assert_eq!(
+            store.revoke_document(2, 5).await.unwrap().as_deref(),
+            Some("c")
+        );
+        assert!(store.revoke_document(3, 5).await.unwrap().is_none());
+        let revoked = store.revocations().await.unwrap();
+        assert_eq!(revoked.document_commitments.len(), 1);
+        assert!(revoked.model_hashes.contains(&stored.model_hash));
+
+        // Saving a key again reschedules it
+        assert_eq!(store.verifying_keys().await.unwrap(), [key.clone()]);
+        key.schedule.deprecated_at = Some(20);
+        key.schedule.retires_at = Some(30);
+        store.save_verifying_key(&key, 5).await.unwrap();
+        assert_eq!(store.verifying_keys().await.unwrap(), [key]);
+
+        // Listing filters, newest first
+        let later = QueryRecord {
+            model_hash: "AB".repeat(32),
+            verified_at: 1_700_000_020,
+            ..record.clone()
+        };
+        let later_id = store
+            .record_query(&later, &proof, Some("proofs/ab"), None)
+            .await
+            .unwrap();
+        assert!(store.query_receipt(later_id).await.unwrap().is_none());
+
+        // Archived proofs are left out of the row
+        let archived = store.query_proof(later_id).await.unwrap().unwrap();
+        assert!(archived.envelope.proof.is_empty());
+        assert_eq!(archived.envelope.key_id, proof.key_id);
+        assert_eq!(archived.archive_key.as_deref(), Some("proofs/ab"));
+        let (all, total) = store
+            .list_queries(&QueryFilter::default(), 0, 10)
+            .await
+            .unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(
+            all.iter().map(|query| query.id).collect::<Vec<_>>(),
+            [later_id, id]
+        );
+        let (page, total) = store
+            .list_queries(&QueryFilter::default(), 1, 1)
+            .await
+            .unwrap();
+        assert_eq!((page[0].id, total), (id, 2));
+        let filter = QueryFilter {
+            model_hash: Some("ab".repeat(32)),
+            ..QueryFilter::default()
+        };
+        let (matching, total) = store.list_queries(&filter, 0, 10).await.unwrap();
+        assert_eq!((matching[0].id, total), (later_id, 1));
+        let filter = QueryFilter {
+            document_commitment: Some(record.document_commitment.clone()),
+            since: Some(1_700_000_015),
+            ..QueryFilter::default()
+        };
+        assert_eq!(store.list_queries(&filter, 0, 10).await.unwrap().1, 1);
+        let after = store.queries_after(0, 10).await.unwrap();
+        assert_eq!(
+            after.iter().map(|query| query.id).collect::<Vec<_>>(),
+            [id, later_id]
+        );
+        assert!(store.queries_after(later_id, 10).await.unwrap().is_empty());
+
+        // The audit trail names the document's owner and the proof's key
+        let trail = store
+            .audit_trail(&AuditFilter::default(), 0, 10)
+            .await
+            .unwrap();
+        assert_eq!(trail.len(), 2);
+        assert_eq!(trail[0].query.id, id);
+        assert_eq!(trail[0].key_id, Some("01".repeat(32)));
+        assert_eq!(trail[0].tenant, None);
+        store
+            .register_document(&record.document_commitment, "carol", &[], 3)
+            .await
+            .unwrap();
+        let filter = AuditFilter {
+            tenant: Some("carol".to_string()),
+            verified: Some(false),
+            since: Some(1_700_000_015),
+        };
+        let trail = store.audit_trail(&filter, 0, 10).await.unwrap();
+        assert_eq!(trail.len(), 1);
+        assert_eq!(
+            (trail[0].query.id, trail[0].tenant.as_deref()),
+            (later_id, Some("carol"))
+        );
+        assert!(store
+            .audit_trail(&filter, later_id, 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let hook = store
+            .add_webhook("https://ci.example.com/hook", "s3cret", "alice", 3)
+            .await
+            .unwrap();
+        store
+            .add_webhook("https://other.example.com/", "k", "bob", 4)
+            .await
+            .unwrap();
+        assert_eq!(store.webhooks(None).await.unwrap().len(), 2);
+        let alices = store.webhooks(Some("alice")).await.unwrap();
+        assert_eq!((alices.len(), alices[0].secret.as_str()), (1, "s3cret"));
+        assert!(!store.delete_webhook(hook, "bob").await.unwrap());
+        assert!(store.delete_webhook(hook, "alice").await.unwrap());
+        assert!(store.webhooks(Some("alice")).await.unwrap().is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
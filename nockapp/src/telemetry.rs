@@ -0,0 +1,213 @@
+// Distributed tracing
+//
+// Requests, proof checks and database calls run in tracing spans. Given an
+// OTLP collector the server exports them there, so verification latency
+// shows up in the traces of the services calling it:
+// - each request runs in an `http.request` span named by its method and
+//   route. A request carrying a W3C `traceparent` header continues the
+//   caller's trace, its span a child of the caller's.
+// - proof checks run in a `verify_proof` span (see main.rs)
+// - store calls run in spans named after the call, e.g. `record_query`
+//   (see store.rs)
+//
+// Logs go to stderr as before, now prefixed with the spans they happened in.
+//
+// Traces started here are sampled at `sample_percent`; traces continued from
+// a caller follow the caller's sampling decision. Spans are exported in
+// batches in the background, and flushed when the server shuts down.
+//
+// Configuration (the `[telemetry]` table; see config.rs):
+// - otlp_endpoint (ZKRAG_OTLP_ENDPOINT): gRPC endpoint of the collector,
+//   e.g. `http://collector:4317`; unset exports nothing
+// - service_name (ZKRAG_SERVICE_NAME): service the spans are reported as
+//   (default `zkrag-verifier`)
+// - sample_percent (ZKRAG_TRACE_SAMPLE_PERCENT): share of new traces
+//   exported (default 100)
+
+use anyhow::Context as _;
+use axum::extract::{MatchedPath, Request};
+use axum::http::HeaderMap;
+use axum::middleware::Next;
+use axum::response::Response;
+use opentelemetry::propagation::{Extractor, TextMapPropagator};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, TracerProvider};
+use opentelemetry_sdk::{runtime, Resource};
+use serde::Deserialize;
+use tracing::field::Empty;
+use tracing::{info_span, warn, Instrument};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+/// Where spans are exported
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelemetryConfig {
+    pub otlp_endpoint: Option<String>,
+    pub service_name: String,
+    pub sample_percent: u8,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            otlp_endpoint: None,
+            service_name: "zkrag-verifier".to_string(),
+            sample_percent: 100,
+        }
+    }
+}
+
+/// The installed exporter, flushed by `shutdown`
+pub struct Telemetry {
+    provider: Option<TracerProvider>,
+}
+
+impl Telemetry {
+    /// Export the spans still buffered
+    pub fn shutdown(self) {
+        if let Some(provider) = self.provider {
+            if let Err(e) = provider.shutdown() {
+                warn!("Failed to flush spans: {}", e);
+            }
+        }
+    }
+}
+
+/// Install the global subscriber: logs to stderr and, when configured,
+/// spans to the collector
+pub fn init(config: &TelemetryConfig) -> anyhow::Result<Telemetry> {
+    let logs = tracing_subscriber::fmt::layer();
+    let Some(endpoint) = &config.otlp_endpoint else {
+        tracing_subscriber::registry()
+            .with(logs)
+            .with(LevelFilter::INFO)
+            .init();
+        return Ok(Telemetry { provider: None });
+    };
+
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(endpoint)
+        .build()
+        .with_context(|| format!("Invalid OTLP endpoint {}", endpoint))?;
+    let ratio = f64::from(config.sample_percent.min(100)) / 100.0;
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            ratio,
+        ))))
+        .with_resource(Resource::new([KeyValue::new(
+            "service.name",
+            config.service_name.clone(),
+        )]))
+        .build();
+    let spans = tracing_opentelemetry::layer().with_tracer(provider.tracer("zkrag-verifier"));
+    tracing_subscriber::registry()
+        .with(logs)
+        .with(spans)
+        .with(LevelFilter::INFO)
+        .init();
+    Ok(Telemetry {
+        provider: Some(provider),
+    })
+}
+
+/// Header values by name, for the propagator
+struct Headers<'a>(&'a HeaderMap);
+
+impl Extractor for Headers<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|name| name.as_str()).collect()
+    }
+}
+
+/// Middleware running each request in a span, continuing the caller's
+/// trace when the request names one
+pub async fn trace_request(request: Request, next: Next) -> Response {
+    let method = request.method().clone();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string());
+    let name = match &route {
+        Some(route) => format!("{} {}", method, route),
+        None => method.to_string(),
+    };
+    let span = info_span!(
+        "http.request",
+        otel.name = name,
+        otel.kind = "server",
+        http.request.method = %method,
+        http.route = route,
+        http.response.status_code = Empty,
+    );
+    span.set_parent(TraceContextPropagator::new().extract(&Headers(request.headers())));
+    let response = next.run(request).instrument(span.clone()).await;
+    span.record("http.response.status_code", response.status().as_u16());
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::routing::get;
+    use axum::{middleware, Router};
+    use opentelemetry::trace::TraceContextExt;
+    use tower::ServiceExt;
+    use tracing::Span;
+
+    #[tokio::test]
+    async fn test_request_continues_trace() {
+        let provider = TracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        let _default = tracing::subscriber::set_default(subscriber);
+
+        // Answers with the trace the handler runs in
+        let app = Router::new()
+            .route(
+                "/api/v1/query/:id",
+                get(|| async {
+                    let context = Span::current().context();
+                    context.span().span_context().trace_id().to_string()
+                }),
+            )
+            .layer(middleware::from_fn(trace_request));
+        let trace = |traceparent: Option<&str>| {
+            let mut request = Request::get("/api/v1/query/1");
+            if let Some(traceparent) = traceparent {
+                request = request.header("traceparent", traceparent);
+            }
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(request.body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                let body = axum::body::to_bytes(response.into_body(), 1024)
+                    .await
+                    .unwrap();
+                String::from_utf8(body.to_vec()).unwrap()
+            }
+        };
+
+        let caller = "4bf92f3577b34da6a3ce929d0e0e4736";
+        let traceparent = format!("00-{}-00f067aa0ba902b7-01", caller);
+        assert_eq!(trace(Some(&traceparent)).await, caller);
+        // Without one a new trace starts
+        let started = trace(None).await;
+        assert_ne!(started, caller);
+        assert_ne!(started, "0".repeat(32));
+    }
+}
@@ -0,0 +1,112 @@
+// Document upload
+//
+// `POST /api/v1/document/upload` takes a document's text as the request
+// body, for clients that would rather not chunk and hash it themselves. The
+// server splits it as the Python client's DocumentManager does
+// (`chunk_size` characters, consecutive chunks sharing `overlap`; see
+// `zkrag_circuits::utils::chunk_text`), hashes each chunk with
+// `document_hash`, and registers the chunk hashes as
+// `/api/v1/document/register` would, the document's commitment being the
+// Merkle root over them. The response lists the chunk hashes in order, for
+// the client's witnesses, with the registry commitment after the upload.
+//
+// The body must be UTF-8 text within the `[guard]` body cap (see guard.rs),
+// split into at most MAX_UPLOAD_CHUNKS chunks. The text itself isn't kept.
+
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use zkrag_circuits::utils::{chunk_text, document_hash, DEFAULT_CHUNK_OVERLAP, DEFAULT_CHUNK_SIZE};
+
+use crate::error::ApiError;
+use crate::validate::Validator;
+
+/// Most chunks one upload may be split into
+pub const MAX_UPLOAD_CHUNKS: usize = 4096;
+
+/// Query of `POST /api/v1/document/upload`
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+#[serde(deny_unknown_fields)]
+pub struct UploadParams {
+    pub owner: String,
+    /// Characters per chunk (default 512)
+    pub chunk_size: Option<usize>,
+    /// Characters consecutive chunks share (default 50)
+    pub overlap: Option<usize>,
+}
+
+/// Response of `POST /api/v1/document/upload`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DocumentUploadResponse {
+    pub id: u64,
+    /// Commitment to the uploaded document's chunks
+    pub document_commitment: String,
+    /// Hex SHA-256 of each chunk, in document order
+    pub chunk_hashes: Vec<String>,
+    /// Registry commitment after this upload
+    pub commitment: String,
+    pub document_count: usize,
+}
+
+/// Hashes of the chunks of `body`, split as `params` ask
+pub fn chunk_hashes(body: &[u8], params: &UploadParams) -> Result<Vec<String>, ApiError> {
+    let chunk_size = params.chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE);
+    let overlap = params.overlap.unwrap_or(DEFAULT_CHUNK_OVERLAP);
+    let mut validator = Validator::new();
+    validator.name("owner", &params.owner);
+    if chunk_size == 0 {
+        validator.error("chunk_size", "must be at least 1");
+    } else if overlap >= chunk_size {
+        validator.error("overlap", "must be less than chunk_size");
+    }
+    let text = std::str::from_utf8(body);
+    match text {
+        Ok("") => validator.error("body", "must not be empty"),
+        Ok(_) => {}
+        Err(_) => validator.error("body", "must be UTF-8 text"),
+    }
+    validator.finish()?;
+
+    let chunks = chunk_text(text.unwrap_or_default(), chunk_size, overlap);
+    if chunks.len() > MAX_UPLOAD_CHUNKS {
+        return Err(ApiError::invalid_request(format!(
+            "Document splits into {} chunks, more than {}; use a larger chunk_size",
+            chunks.len(),
+            MAX_UPLOAD_CHUNKS
+        )));
+    }
+    Ok(chunks
+        .into_iter()
+        .map(|chunk| document_hash(chunk.as_bytes()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_hashes() {
+        let params = |chunk_size, overlap| UploadParams {
+            owner: "alice".to_string(),
+            chunk_size,
+            overlap,
+        };
+        let text = "x".repeat(1000);
+        let hashes = chunk_hashes(text.as_bytes(), &params(None, None)).unwrap();
+        // 0..512 and 462..974, then 924..1000
+        assert_eq!(hashes.len(), 3);
+        assert_eq!(hashes[0], document_hash("x".repeat(512).as_bytes()));
+        assert_eq!(hashes[2], document_hash("x".repeat(76).as_bytes()));
+
+        let hashes = chunk_hashes(b"abcdef", &params(Some(4), Some(0))).unwrap();
+        assert_eq!(hashes, [document_hash(b"abcd"), document_hash(b"ef")]);
+
+        assert!(chunk_hashes(b"", &params(None, None)).is_err());
+        assert!(chunk_hashes(&[0xff, 0xfe], &params(None, None)).is_err());
+        assert!(chunk_hashes(b"abc", &params(Some(4), Some(4))).is_err());
+        assert!(chunk_hashes(b"abc", &params(Some(0), Some(0))).is_err());
+        let long = "x".repeat(MAX_UPLOAD_CHUNKS + 1);
+        assert!(chunk_hashes(long.as_bytes(), &params(Some(1), Some(0))).is_err());
+    }
+}
@@ -0,0 +1,137 @@
+// API v2
+//
+// Version 2 routes take proofs in the versioned envelope the prover and the
+// SDKs produce, instead of loose fields, and answer with the verifier's
+// whole result.
+//
+// `POST /api/v2/query/verify` reads a ProofEnvelope of any supported version
+// as JSON, in the binary encoding (`application/octet-stream`, starting
+// `ZKEV`), or as CBOR or MessagePack. Its verifying key is the one its
+// `key_id` names, or its circuit's current key, and the key's circuit and
+// curve must be the envelope's. An unknown key or a mismatch fails
+// verification with its reason; an envelope of a version this server can't
+// read is refused as `proof_malformed`. Proofs are checked, recorded and
+// pushed to subscribers as v1 verifications are.
+//
+// The answer is the VerificationResult with the recorded query's id: status
+// 201 when the proof verified and 200 with the failure `reason` otherwise.
+// When the server has a receipt signing key it carries a `receipt`, an
+// ed25519 signature over the outcome that holders of the key fetched from
+// `/api/v1/keys/receipt-key` can check without trusting the transport.
+
+use axum::async_trait;
+use axum::body::Bytes;
+use axum::extract::{FromRequest, Request};
+use axum::http::header::CONTENT_TYPE;
+use serde::Serialize;
+use zkrag_verifier::{ProofEnvelope, VerificationResult};
+
+use crate::codec::Format;
+use crate::error::ApiError;
+
+/// Media type of the binary envelope encoding
+pub const ENVELOPE_BINARY: &str = "application/octet-stream";
+
+/// Body extractor reading a proof envelope in any of its encodings
+pub struct EnvelopeBody(pub ProofEnvelope);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequest<S> for EnvelopeBody {
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, ApiError> {
+        let binary = request
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with(ENVELOPE_BINARY));
+        let format = Format::of_body(request.headers());
+        if !binary && format.is_none() {
+            return Err(ApiError::invalid_request(format!(
+                "Content-Type must be application/json, {}, application/cbor or \
+                 application/msgpack",
+                ENVELOPE_BINARY
+            )));
+        }
+        let bytes = Bytes::from_request(request, state).await?;
+        let envelope = match format {
+            _ if binary => ProofEnvelope::from_bytes(&bytes)?,
+            Some(Format::Json) | None => ProofEnvelope::from_json(&bytes)?,
+            // Through JSON, so older envelope versions are upgraded alike
+            Some(format) => {
+                let value: serde_json::Value = format.decode(&bytes).map_err(|e| {
+                    ApiError::invalid_request(format!("Failed to parse the envelope: {}", e))
+                })?;
+                let json = serde_json::to_vec(&value)
+                    .map_err(|e| ApiError::internal("Re-encoding envelope", e))?;
+                ProofEnvelope::from_json(&json)?
+            }
+        };
+        Ok(Self(envelope))
+    }
+}
+
+/// Response of `POST /api/v2/query/verify`
+#[derive(Debug, Serialize)]
+pub struct EnvelopeVerification {
+    /// Id of the recorded query
+    pub query_id: u64,
+    #[serde(flatten)]
+    pub result: VerificationResult,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::StatusCode;
+    use zkrag_verifier::PublicInputs;
+
+    async fn parse(content_type: &str, body: Vec<u8>) -> Result<ProofEnvelope, ApiError> {
+        let request = Request::post("/api/v2/query/verify")
+            .header(CONTENT_TYPE, content_type)
+            .body(Body::from(body))
+            .unwrap();
+        EnvelopeBody::from_request(request, &())
+            .await
+            .map(|EnvelopeBody(e)| e)
+    }
+
+    #[tokio::test]
+    async fn test_envelope_encodings() {
+        let inputs = PublicInputs {
+            document_commitment: "cd".repeat(32),
+            model_hash: "ef".repeat(32),
+            timestamp: 1_700_000_000,
+            nonce: None,
+        };
+        let envelope = ProofEnvelope::new(vec![5; 128], inputs).with_key_id("k");
+
+        let json = envelope.to_json().unwrap();
+        assert_eq!(
+            parse("application/json", json.clone()).await.unwrap(),
+            envelope
+        );
+        let binary = envelope.to_bytes().unwrap();
+        assert_eq!(parse(ENVELOPE_BINARY, binary).await.unwrap(), envelope);
+        let cbor = Format::Cbor.encode(&envelope).unwrap();
+        assert_eq!(parse("application/cbor", cbor).await.unwrap(), envelope);
+
+        // v1 envelopes are upgraded
+        let mut v1: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        v1["version"] = 1.into();
+        let upgraded = parse("application/json", serde_json::to_vec(&v1).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(upgraded.key_id, None);
+        assert_eq!(upgraded.proof, envelope.proof);
+
+        let refused = parse("text/plain", json).await.unwrap_err();
+        assert_eq!(refused.code.status(), StatusCode::BAD_REQUEST);
+        v1["version"] = 9.into();
+        let unsupported = parse("application/json", serde_json::to_vec(&v1).unwrap())
+            .await
+            .unwrap_err();
+        assert_eq!(unsupported.code.status(), StatusCode::BAD_REQUEST);
+    }
+}
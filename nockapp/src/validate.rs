@@ -0,0 +1,162 @@
+// Request validation
+//
+// Payloads are checked field by field before they reach the store, the
+// verifier or the prover, so a bad request costs no pairing check and every
+// problem is reported at once. Failures are `invalid_request` errors listing
+// the offending fields in `details.fields`:
+//
+//     {"code": "invalid_request", "message": "Invalid model_hash, timestamp",
+//      "details": {"fields": [{"field": "model_hash", "message": "..."}, ...]}}
+//
+// Commitments and hashes are hex SHA-256 digests. Timestamps must be set and
+// no further ahead than the verifier's clock skew allowance; whether they are
+// fresh enough is the verifier's call, recorded as `stale_timestamp`.
+
+use axum::http::Uri;
+use serde::Serialize;
+use utoipa::ToSchema;
+use zkrag_verifier::{MAX_CLOCK_SKEW_SECS, MAX_PROOF_BYTES};
+
+use crate::error::ApiError;
+
+/// Longest accepted model name or document owner
+pub const MAX_NAME_LEN: usize = 256;
+
+/// Problem with one field of a request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct FieldError {
+    /// Field name, with an index for list items, e.g. `document_hashes[2]`
+    pub field: String,
+    pub message: String,
+}
+
+/// Collects field errors for one request
+#[derive(Debug, Default)]
+pub struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn error(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.errors.push(FieldError {
+            field: field.into(),
+            message: message.into(),
+        });
+    }
+
+    /// Check that `value` is a hex SHA-256 digest
+    pub fn digest(&mut self, field: impl Into<String>, value: &str) {
+        if value.len() != 64 {
+            self.error(
+                field,
+                format!("must be 64 hex digits, got {} characters", value.len()),
+            );
+        } else if !value.bytes().all(|b| b.is_ascii_hexdigit()) {
+            self.error(field, "must contain only hex digits");
+        }
+    }
+
+    /// Check that `value` is non-empty and at most `MAX_NAME_LEN` bytes
+    pub fn name(&mut self, field: &str, value: &str) {
+        if value.trim().is_empty() {
+            self.error(field, "must not be empty");
+        } else if value.len() > MAX_NAME_LEN {
+            self.error(field, format!("must be at most {} bytes", MAX_NAME_LEN));
+        }
+    }
+
+    /// Check that `value` is a Unix time that isn't zero or in the future
+    /// beyond the allowed clock skew
+    pub fn timestamp(&mut self, field: &str, value: u64, now: u64) {
+        if value == 0 {
+            self.error(field, "must be a Unix time in seconds");
+        } else if value > now.saturating_add(MAX_CLOCK_SKEW_SECS) {
+            self.error(field, "is in the future");
+        }
+    }
+
+    /// Check that `value` is an absolute http or https URL
+    pub fn url(&mut self, field: &str, value: &str) {
+        match value.parse::<Uri>() {
+            Ok(uri)
+                if matches!(uri.scheme_str(), Some("http" | "https")) && uri.host().is_some() => {}
+            _ => self.error(field, "must be an http or https URL"),
+        }
+    }
+
+    /// Decode a hex proof, checking it isn't empty or larger than any
+    /// supported encoding. Returns no bytes if it isn't hex.
+    pub fn proof(&mut self, field: &str, value: &str) -> Vec<u8> {
+        let proof = match hex::decode(value) {
+            Ok(proof) => proof,
+            Err(e) => {
+                self.error(field, format!("must be hex: {}", e));
+                return Vec::new();
+            }
+        };
+        if proof.is_empty() {
+            self.error(field, "must not be empty");
+        } else if proof.len() > MAX_PROOF_BYTES {
+            self.error(
+                field,
+                format!(
+                    "is {} bytes, larger than the {} byte maximum",
+                    proof.len(),
+                    MAX_PROOF_BYTES
+                ),
+            );
+        }
+        proof
+    }
+
+    /// Fail with the collected errors, if any
+    pub fn finish(self) -> Result<(), ApiError> {
+        if self.errors.is_empty() {
+            return Ok(());
+        }
+        let fields: Vec<&str> = self.errors.iter().map(|e| e.field.as_str()).collect();
+        Err(
+            ApiError::invalid_request(format!("Invalid {}", fields.join(", ")))
+                .with_details(serde_json::json!({ "fields": self.errors })),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_errors() {
+        let mut validator = Validator::new();
+        validator.digest("document_commitment", &"ab".repeat(32));
+        validator.timestamp("timestamp", 1_000, 1_000 + MAX_CLOCK_SKEW_SECS);
+        validator.name("model_name", "bert");
+        validator.url("url", "https://ci.example.com/hooks/zkrag");
+        assert_eq!(validator.proof("proof", "00ff"), [0x00, 0xff]);
+        assert!(validator.finish().is_ok());
+
+        let mut validator = Validator::new();
+        validator.digest("model_hash", "abc");
+        validator.digest("document_hashes[1]", &"zz".repeat(32));
+        validator.timestamp("timestamp", 1_000 + MAX_CLOCK_SKEW_SECS + 1, 1_000);
+        validator.name("owner", " ");
+        validator.url("url", "ftp://example.com/");
+        assert!(validator.proof("proof", "0g").is_empty());
+        let error = validator.finish().unwrap_err();
+        assert_eq!(
+            error.message,
+            "Invalid model_hash, document_hashes[1], timestamp, owner, url, proof"
+        );
+        let details = error.details.unwrap();
+        assert_eq!(details["fields"].as_array().unwrap().len(), 6);
+        assert_eq!(
+            details["fields"][0]["message"],
+            "must be 64 hex digits, got 3 characters"
+        );
+    }
+}
@@ -0,0 +1,259 @@
+// Webhook callbacks
+//
+// Clients register URLs through `POST /api/v1/webhooks` to be sent each
+// verification outcome, so pipelines needn't poll `/api/v1/query/{id}`.
+// Every webhook receives every query_verified and query_failed event, as the
+// JSON the event streams send, in a POST with these headers:
+// - X-Zkrag-Event: the event type
+// - X-Zkrag-Timestamp: Unix time of the delivery attempt
+// - X-Zkrag-Signature: `sha256=` and the hex HMAC-SHA256, keyed with the
+//   webhook's secret, of the timestamp, a `.` and the body
+//
+// Receivers should recompute the signature and reject stale timestamps. The
+// secret is chosen by the client or generated, and is only returned when the
+// webhook is registered.
+//
+// A delivery that fails or gets a non-2xx answer is retried after 1s, then
+// after twice as long each time, MAX_ATTEMPTS attempts in all. Receivers
+// should expect the occasional duplicate and key on the query id. Deliveries
+// still being retried when the server shuts down are dropped. Events are
+// taken in id order like the SSE stream's, so any the dispatcher lags behind
+// on are read back from the store.
+
+use futures_util::StreamExt;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+use utoipa::ToSchema;
+
+use crate::events::{self, Event, EventFilter};
+use crate::store::{QueryFilter, Store, Webhook};
+
+/// Shortest signing key a client may choose
+pub const MIN_SECRET_LEN: usize = 16;
+
+/// Delivery attempts per event before giving up
+const MAX_ATTEMPTS: u32 = 6;
+
+/// Wait before the first retry; doubled for each one after
+const FIRST_RETRY: Duration = Duration::from_secs(1);
+
+/// How long a receiver may take to answer
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Body of `POST /api/v1/webhooks`
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RegisterWebhookRequest {
+    /// http or https URL to POST events to
+    pub url: String,
+    /// Signing key, at least 16 bytes; generated if omitted
+    pub secret: Option<String>,
+}
+
+/// Registered webhook
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookResponse {
+    pub id: u64,
+    pub url: String,
+    pub created_at: u64,
+    /// Signing key; only returned on registration
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+}
+
+impl From<Webhook> for WebhookResponse {
+    fn from(webhook: Webhook) -> Self {
+        Self {
+            id: webhook.id,
+            url: webhook.url,
+            created_at: webhook.created_at,
+            secret: None,
+        }
+    }
+}
+
+/// Random signing key, as hex
+pub fn generate_secret() -> String {
+    hex::encode(rand::random::<[u8; 32]>())
+}
+
+/// Value of X-Zkrag-Signature for `body` sent at `timestamp`
+pub fn sign(secret: &str, timestamp: u64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// POST `body` to `webhook` once
+async fn attempt(
+    webhook: &Webhook,
+    event_type: &'static str,
+    body: Arc<[u8]>,
+) -> Result<(), String> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs());
+    let signature = sign(&webhook.secret, timestamp, &body);
+    let url = webhook.url.clone();
+    tokio::task::spawn_blocking(move || {
+        ureq::post(&url)
+            .timeout(DELIVERY_TIMEOUT)
+            .set("Content-Type", "application/json")
+            .set("X-Zkrag-Event", event_type)
+            .set("X-Zkrag-Timestamp", &timestamp.to_string())
+            .set("X-Zkrag-Signature", &signature)
+            .send_bytes(&body)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Deliver `body` to `webhook`, retrying with backoff starting at `retry`
+async fn deliver(webhook: Webhook, event_type: &'static str, body: Arc<[u8]>, mut retry: Duration) {
+    for attempt_number in 1..=MAX_ATTEMPTS {
+        match attempt(&webhook, event_type, body.clone()).await {
+            Ok(()) => {
+                debug!("Delivered {} to webhook {}", event_type, webhook.id);
+                return;
+            }
+            Err(e) if attempt_number == MAX_ATTEMPTS => {
+                warn!(
+                    "Giving up on {} for webhook {} after {} attempts: {}",
+                    event_type, webhook.id, MAX_ATTEMPTS, e
+                );
+            }
+            Err(e) => {
+                debug!(
+                    "Webhook {} attempt {} failed: {}",
+                    webhook.id, attempt_number, e
+                );
+                tokio::time::sleep(retry).await;
+                retry *= 2;
+            }
+        }
+    }
+}
+
+/// Send a verification outcome to every webhook
+async fn dispatch(store: &dyn Store, event: &Event) {
+    let body: Arc<[u8]> = match serde_json::to_vec(event) {
+        Ok(body) => body.into(),
+        Err(_) => return,
+    };
+    let webhooks = match store.webhooks(None).await {
+        Ok(webhooks) => webhooks,
+        Err(e) => {
+            warn!("Failed to load webhooks: {}", e);
+            return;
+        }
+    };
+    for webhook in webhooks {
+        tokio::spawn(deliver(
+            webhook,
+            event.kind().name(),
+            body.clone(),
+            FIRST_RETRY,
+        ));
+    }
+}
+
+/// Deliver the verification outcomes recorded after now until `shutdown`.
+/// `events` must be subscribed before calling.
+pub fn spawn(
+    store: Arc<dyn Store>,
+    events: broadcast::Receiver<Event>,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) {
+    tokio::spawn(async move {
+        let newest = match store.list_queries(&QueryFilter::default(), 0, 1).await {
+            Ok((newest, _)) => newest.first().map_or(0, |query| query.id),
+            Err(e) => {
+                warn!("Webhooks disabled; failed to read queries: {}", e);
+                return;
+            }
+        };
+        let outcomes =
+            events::query_events(store.clone(), events, EventFilter::default(), Some(newest))
+                .take_until(shutdown);
+        tokio::pin!(outcomes);
+        while let Some((_, event)) = outcomes.next().await {
+            dispatch(store.as_ref(), &event).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use axum::Router;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[tokio::test]
+    async fn test_signed_delivery_with_retry() {
+        // Receiver that fails the first attempt and records the second
+        let attempts = Arc::new(AtomicU32::new(0));
+        let (received, mut receive) = tokio::sync::mpsc::unbounded_channel();
+        let counter = attempts.clone();
+        let app = Router::new().route(
+            "/hook",
+            post(
+                move |headers: HeaderMap, body: axum::body::Bytes| async move {
+                    if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                        return StatusCode::INTERNAL_SERVER_ERROR;
+                    }
+                    received.send((headers, body)).unwrap();
+                    StatusCode::NO_CONTENT
+                },
+            ),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let webhook = Webhook {
+            id: 1,
+            url: format!("http://{}/hook", addr),
+            secret: "0123456789abcdef".to_string(),
+            owner: "alice".to_string(),
+            created_at: 0,
+        };
+        let body: Arc<[u8]> = br#"{"type":"query_verified"}"#.to_vec().into();
+        deliver(
+            webhook.clone(),
+            "query_verified",
+            body.clone(),
+            Duration::from_millis(10),
+        )
+        .await;
+
+        let (headers, received) = receive.recv().await.unwrap();
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+        assert_eq!(&received[..], &body[..]);
+        assert_eq!(headers["x-zkrag-event"], "query_verified");
+        let timestamp: u64 = headers["x-zkrag-timestamp"]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(
+            headers["x-zkrag-signature"],
+            sign(&webhook.secret, timestamp, &body).as_str()
+        );
+        assert_ne!(
+            sign("another secret!!", timestamp, &body),
+            sign(&webhook.secret, timestamp, &body)
+        );
+    }
+}
@@ -122,14 +122,13 @@ class DocumentManager:
         Generate a cryptographic commitment to the document collection.
         This is what gets registered on-chain (not the documents themselves).
         """
-        # Collect all document hashes
-        doc_hashes = sorted([doc.hash for doc in self.documents.values()])
+        doc_hashes = [doc.hash for doc in self.documents.values()]
 
-        # Create Merkle root (simplified)
-        combined = "".join(doc_hashes)
-        commitment = hashlib.sha256(combined.encode()).hexdigest()
-
-        return commitment
+        try:
+            import zkrag_rust
+            return zkrag_rust.compute_document_commitment(doc_hashes)
+        except ImportError:
+            return _merkle_root(doc_hashes)
 
     def export_commitment_data(self) -> Dict:
         """Export data needed for commitment verification"""
@@ -150,3 +149,24 @@ class DocumentManager:
 
     def __contains__(self, doc_id: str) -> bool:
         return doc_id in self.documents
+
+
+def _merkle_root(doc_hashes: List[str]) -> str:
+    """
+    Pure-Python port of the circuit's document commitment
+    (zkrag_circuits::utils::compute_document_commitment). Keep the two in sync.
+    """
+    level = [
+        hashlib.sha256(b"\x00" + h.encode()).digest()
+        for h in sorted(doc_hashes)
+    ]
+    if not level:
+        return hashlib.sha256(b"").hexdigest()
+
+    while len(level) > 1:
+        level = [
+            hashlib.sha256(b"\x01" + level[i] + level[i + 1]).digest()
+            if i + 1 < len(level) else level[i]
+            for i in range(0, len(level), 2)
+        ]
+    return level[0].hex()
@@ -2,7 +2,9 @@
 ZK Proof generation interface
 """
 
-from typing import List
+from typing import List, Union
+
+import numpy as np
 
 
 class ProofGenerator:
@@ -13,6 +15,7 @@ class ProofGenerator:
 
     def __init__(self):
         self._rust_available = self._check_rust_bindings()
+        self._prover = None
 
     def _check_rust_bindings(self) -> bool:
         """Check if Rust bindings are available"""
@@ -26,7 +29,7 @@ class ProofGenerator:
         self,
         document_hashes: List[str],
         query_text: str,
-        query_embedding: List[float],
+        query_embedding: Union[np.ndarray, List[float]],
         search_results: List[int],
         document_commitment: str,
         model_hash: str,
@@ -38,7 +41,7 @@ class ProofGenerator:
         Args:
             document_hashes: Hashes of documents in the query set
             query_text: The query (private input)
-            query_embedding: Query embedding vector (private)
+            query_embedding: Query embedding as a numpy array or list (private)
             search_results: IDs of retrieved chunks (private)
             document_commitment: Public commitment to documents
             model_hash: Hash of embedding model used (public)
@@ -52,9 +55,14 @@ class ProofGenerator:
             return "0" * 256  # Placeholder proof
 
         try:
-            import zkrag_rust
+            if self._prover is None:
+                import zkrag_rust
+
+                # Loads the proving key once; reused for every proof, and
+                # reloaded by the binding in forked worker processes
+                self._prover = zkrag_rust.Prover()
 
-            proof_hex = zkrag_rust.generate_proof(
+            proof_hex = self._prover.generate_proof(
                 document_hashes,
                 query_text,
                 query_embedding,
@@ -67,7 +75,7 @@ class ProofGenerator:
             return proof_hex
 
         except Exception as e:
-            raise RuntimeError(f"Proof generation failed: {e}")
+            raise RuntimeError(f"Proof generation failed: {e}") from e
 
     def is_available(self) -> bool:
         """Check if proof generation is available"""
@@ -187,7 +187,7 @@ class PrivateRAG:
         proof_hex = self.proof_gen.generate_proof(
             document_hashes=document_hashes,
             query_text=query,
-            query_embedding=query_embedding.tolist(),
+            query_embedding=query_embedding,
             search_results=[chunk.chunk_id for chunk in results],
             document_commitment=document_commitment,
             model_hash=model_hash,
@@ -0,0 +1,92 @@
+// Read-only byte buffers for keys and proofs
+//
+// A `Buffer` owns serialized key or proof bytes on the Rust side and exposes
+// them through the Python buffer protocol, so `file.write(buf)`,
+// `memoryview(buf)`, and numpy read the bytes in place instead of copying a
+// multi-hundred-MB proving key into a `bytes` object first. The bytes never
+// change after construction, which is what makes lending them out sound.
+
+use pyo3::exceptions::PyBufferError;
+use pyo3::ffi;
+use pyo3::prelude::*;
+use std::ffi::CString;
+use std::os::raw::{c_int, c_void};
+use std::ptr;
+
+/// Serialized key or proof, readable through the buffer protocol
+#[pyclass(name = "Buffer")]
+pub struct ByteBuffer {
+    /// What the bytes are: "proving_key", "verifying_key", or "proof"
+    #[pyo3(get)]
+    kind: &'static str,
+    data: Vec<u8>,
+}
+
+impl ByteBuffer {
+    pub fn new(kind: &'static str, data: Vec<u8>) -> Self {
+        Self { kind, data }
+    }
+}
+
+#[pymethods]
+impl ByteBuffer {
+    unsafe fn __getbuffer__(
+        slf: &PyCell<Self>,
+        view: *mut ffi::Py_buffer,
+        flags: c_int,
+    ) -> PyResult<()> {
+        if view.is_null() {
+            return Err(PyBufferError::new_err("View is null"));
+        }
+        if flags & ffi::PyBUF_WRITABLE == ffi::PyBUF_WRITABLE {
+            return Err(PyBufferError::new_err("Buffer is read-only"));
+        }
+
+        let data = &slf.borrow().data;
+        // The view holds a reference to us, keeping `data` alive and in place
+        (*view).obj = ffi::_Py_NewRef(slf.as_ptr());
+        (*view).buf = data.as_ptr() as *mut c_void;
+        (*view).len = data.len() as isize;
+        (*view).readonly = 1;
+        (*view).itemsize = 1;
+        (*view).format = if flags & ffi::PyBUF_FORMAT == ffi::PyBUF_FORMAT {
+            CString::new("B").unwrap().into_raw()
+        } else {
+            ptr::null_mut()
+        };
+        (*view).ndim = 1;
+        (*view).shape = if flags & ffi::PyBUF_ND == ffi::PyBUF_ND {
+            &mut (*view).len
+        } else {
+            ptr::null_mut()
+        };
+        (*view).strides = if flags & ffi::PyBUF_STRIDES == ffi::PyBUF_STRIDES {
+            &mut (*view).itemsize
+        } else {
+            ptr::null_mut()
+        };
+        (*view).suboffsets = ptr::null_mut();
+        (*view).internal = ptr::null_mut();
+        Ok(())
+    }
+
+    unsafe fn __releasebuffer__(&self, view: *mut ffi::Py_buffer) {
+        // Free the format string allocated in __getbuffer__
+        if !(*view).format.is_null() {
+            drop(CString::from_raw((*view).format));
+        }
+    }
+
+    fn __len__(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Hex encoding of the bytes
+    fn hex(&self) -> String {
+        hex::encode(&self.data)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Buffer(kind={:?}, len={})", self.kind, self.data.len())
+    }
+}
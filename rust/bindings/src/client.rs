@@ -0,0 +1,254 @@
+// Client for the NockApp HTTP verification service
+//
+// Exposed as `zkrag_rust.client` when built with the `client` feature. It
+// wraps the Rust client (`zkrag-client`), so Python callers get the same
+// bearer auth, retries and Idempotency-Key handling. Each endpoint returns a
+// typed result class instead of a raw JSON dict, and requests run with the
+// GIL released on a runtime owned by the client.
+//
+// Failed requests raise `ClientError`. When the service answered, the error
+// carries its `code` (e.g. "model_not_registered"), `message`, HTTP `status`
+// and `details`, so callers branch on `code`; these are None when the
+// service couldn't be reached.
+
+use pyo3::create_exception;
+use pyo3::prelude::*;
+use std::future::Future;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+use crate::ZkragError;
+
+create_exception!(
+    zkrag_rust,
+    ClientError,
+    ZkragError,
+    "The verification service could not be reached or rejected a request"
+);
+
+/// Outcome of registering a document commitment or model
+#[pyclass(get_all)]
+#[derive(Debug, Clone)]
+struct Registration {
+    success: bool,
+    id: Option<u64>,
+}
+
+#[pymethods]
+impl Registration {
+    fn __repr__(&self) -> String {
+        format!("Registration(success={}, id={:?})", self.success, self.id)
+    }
+}
+
+/// Service verdict on a submitted query proof
+#[pyclass(get_all)]
+#[derive(Debug, Clone)]
+struct QueryVerification {
+    valid: bool,
+    query_id: Option<u64>,
+    message: String,
+}
+
+#[pymethods]
+impl QueryVerification {
+    fn __repr__(&self) -> String {
+        format!(
+            "QueryVerification(valid={}, query_id={:?}, message={:?})",
+            self.valid, self.query_id, self.message
+        )
+    }
+}
+
+/// Stored state of a verified query
+#[pyclass(get_all)]
+#[derive(Debug, Clone)]
+struct QueryStatus {
+    id: u64,
+    verified: bool,
+}
+
+#[pymethods]
+impl QueryStatus {
+    fn __repr__(&self) -> String {
+        format!("QueryStatus(id={}, verified={})", self.id, self.verified)
+    }
+}
+
+/// HTTP client for a running verification service
+#[pyclass]
+struct Client {
+    inner: zkrag_client::Client,
+    base_url: String,
+    runtime: Runtime,
+}
+
+impl Client {
+    /// Run a request to completion with the GIL released
+    fn run<T, F>(&self, py: Python<'_>, request: F) -> PyResult<T>
+    where
+        T: Send,
+        F: Future<Output = zkrag_client::Result<T>> + Send,
+    {
+        py.allow_threads(|| self.runtime.block_on(request))
+            .map_err(|e| client_error(py, e))
+    }
+}
+
+/// `ClientError` for a failed request, with the service's error fields
+fn client_error(py: Python<'_>, error: zkrag_client::ClientError) -> PyErr {
+    let err = ClientError::new_err(error.to_string());
+    let (code, message, status, details) = match error {
+        zkrag_client::ClientError::Api {
+            status,
+            code,
+            message,
+            details,
+        } => (
+            serde_json::to_value(code).ok(),
+            Some(message),
+            Some(status),
+            details,
+        ),
+        _ => (None, None, None, None),
+    };
+    let fields = || -> PyResult<()> {
+        let value = err.value(py);
+        value.setattr("code", code.as_ref().and_then(|code| code.as_str()))?;
+        value.setattr("message", message)?;
+        value.setattr("status", status)?;
+        let details = match details {
+            Some(details) => py
+                .import("json")?
+                .call_method1("loads", (details.to_string(),))?
+                .into_py(py),
+            None => py.None(),
+        };
+        value.setattr("details", details)
+    };
+    match fields() {
+        Ok(()) => err,
+        Err(e) => e,
+    }
+}
+
+#[pymethods]
+impl Client {
+    /// Connect to the service at `base_url`, failing requests that take
+    /// longer than `timeout` seconds. `token` is an access token from the
+    /// service's identity provider, needed when it requires authentication.
+    #[new]
+    #[pyo3(signature = (base_url="http://localhost:8080", timeout=30.0, token=None))]
+    fn new(base_url: &str, timeout: f64, token: Option<String>) -> PyResult<Self> {
+        let timeout = Duration::try_from_secs_f64(timeout)
+            .map_err(|e| ClientError::new_err(format!("Invalid timeout: {}", e)))?;
+        let mut builder = zkrag_client::Client::builder(base_url).timeout(Some(timeout));
+        if let Some(token) = token {
+            builder = builder.bearer_token(token);
+        }
+        let inner = builder
+            .build()
+            .map_err(|e| ClientError::new_err(e.to_string()))?;
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| ClientError::new_err(format!("Failed to start runtime: {}", e)))?;
+
+        Ok(Self {
+            inner,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            runtime,
+        })
+    }
+
+    #[getter]
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Whether the service is up
+    fn health(&self, py: Python<'_>) -> bool {
+        self.run(py, self.inner.liveness()).is_ok()
+    }
+
+    /// Register a document set commitment on behalf of `owner`
+    fn register_document(
+        &self,
+        py: Python<'_>,
+        commitment: &str,
+        owner: &str,
+    ) -> PyResult<Registration> {
+        let document = zkrag_client::RegisterDocumentRequest {
+            commitment: Some(commitment.to_string()),
+            owner: owner.to_string(),
+            document_hashes: Vec::new(),
+        };
+        let registration = self.run(py, self.inner.register_document(&document))?;
+        Ok(Registration {
+            success: registration.success,
+            id: registration.id,
+        })
+    }
+
+    /// Register a model by its hash
+    fn register_model(
+        &self,
+        py: Python<'_>,
+        model_hash: &str,
+        model_name: &str,
+    ) -> PyResult<Registration> {
+        let registration = self.run(py, self.inner.register_model(model_hash, model_name))?;
+        Ok(Registration {
+            success: registration.success,
+            id: registration.id,
+        })
+    }
+
+    /// Submit a hex-encoded query proof for verification
+    fn verify(
+        &self,
+        py: Python<'_>,
+        proof: &str,
+        document_commitment: &str,
+        model_hash: &str,
+        timestamp: u64,
+    ) -> PyResult<QueryVerification> {
+        let query = zkrag_client::VerifyQueryRequest {
+            proof: proof.to_string(),
+            document_commitment: document_commitment.to_string(),
+            model_hash: model_hash.to_string(),
+            timestamp,
+            nonce: None,
+        };
+        let response = self.run(py, self.inner.verify_query(&query))?;
+        Ok(QueryVerification {
+            valid: response.valid,
+            query_id: response.query_id,
+            message: response.message,
+        })
+    }
+
+    /// Poll the stored state of a submitted query
+    fn get_query(&self, py: Python<'_>, query_id: u64) -> PyResult<QueryStatus> {
+        let record = self.run(py, self.inner.get_query(query_id))?;
+        Ok(QueryStatus {
+            id: record.id,
+            verified: record.verified,
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Client({:?})", self.base_url)
+    }
+}
+
+/// Build the `zkrag_rust.client` submodule
+pub fn module(py: Python<'_>) -> PyResult<&PyModule> {
+    let m = PyModule::new(py, "client")?;
+    m.add("ClientError", py.get_type::<ClientError>())?;
+    m.add_class::<Client>()?;
+    m.add_class::<Registration>()?;
+    m.add_class::<QueryVerification>()?;
+    m.add_class::<QueryStatus>()?;
+    Ok(m)
+}
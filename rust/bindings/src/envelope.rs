@@ -0,0 +1,154 @@
+// Proof envelopes
+//
+// `ProofEnvelope` wraps a proof with its circuit id, curve, key id, and public
+// inputs in the versioned format the verifier and the HTTP service exchange.
+// Envelopes of any supported version parse; they are always written in the
+// current version, as JSON or the binary encoding.
+
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyString};
+use zkrag_verifier::{ProofEnvelope, PublicInputs};
+
+use crate::{extract_proof_bytes, VerificationError};
+
+fn envelope_error(e: impl std::fmt::Display) -> PyErr {
+    VerificationError::new_err(format!("Invalid proof envelope: {}", e))
+}
+
+/// A proof with its circuit, key, and public inputs
+#[pyclass(name = "ProofEnvelope")]
+#[derive(Clone)]
+pub struct PyProofEnvelope {
+    pub inner: ProofEnvelope,
+}
+
+#[pymethods]
+impl PyProofEnvelope {
+    /// Wrap a document query proof, given as bytes or a hex string
+    #[new]
+    #[pyo3(signature = (proof, document_commitment, model_hash, timestamp, key_id=None))]
+    fn new(
+        py: Python<'_>,
+        proof: &PyAny,
+        document_commitment: String,
+        model_hash: String,
+        timestamp: u64,
+        key_id: Option<String>,
+    ) -> PyResult<Self> {
+        let proof = match proof.downcast::<PyString>() {
+            Ok(hex_str) => hex::decode(hex_str.to_str()?)
+                .map_err(|e| VerificationError::new_err(format!("Invalid hex: {}", e)))?,
+            Err(_) => extract_proof_bytes(py, proof)?,
+        };
+        let mut inner = ProofEnvelope::new(
+            proof,
+            PublicInputs {
+                document_commitment,
+                model_hash,
+                timestamp,
+                nonce: None,
+            },
+        );
+        if let Some(key_id) = key_id {
+            inner = inner.with_key_id(key_id);
+        }
+        Ok(Self { inner })
+    }
+
+    /// Parse a JSON envelope (str or bytes) of any supported version
+    #[staticmethod]
+    fn from_json(json: &PyAny) -> PyResult<Self> {
+        let inner = match json.downcast::<PyString>() {
+            Ok(text) => ProofEnvelope::from_json(text.to_str()?.as_bytes()),
+            Err(_) => ProofEnvelope::from_json(&extract_proof_bytes(json.py(), json)?),
+        }
+        .map_err(envelope_error)?;
+        Ok(Self { inner })
+    }
+
+    /// Parse a binary envelope of any supported version
+    #[staticmethod]
+    fn from_bytes(py: Python<'_>, data: &PyAny) -> PyResult<Self> {
+        let inner =
+            ProofEnvelope::from_bytes(&extract_proof_bytes(py, data)?).map_err(envelope_error)?;
+        Ok(Self { inner })
+    }
+
+    /// Parse either encoding, detected by the binary magic prefix
+    #[staticmethod]
+    fn parse(py: Python<'_>, data: &PyAny) -> PyResult<Self> {
+        let inner =
+            ProofEnvelope::parse(&extract_proof_bytes(py, data)?).map_err(envelope_error)?;
+        Ok(Self { inner })
+    }
+
+    fn to_json(&self) -> PyResult<String> {
+        let json = self.inner.to_json().map_err(envelope_error)?;
+        String::from_utf8(json).map_err(envelope_error)
+    }
+
+    fn to_bytes<'py>(&self, py: Python<'py>) -> PyResult<&'py PyBytes> {
+        let bytes = self.inner.to_bytes().map_err(envelope_error)?;
+        Ok(PyBytes::new(py, &bytes))
+    }
+
+    #[getter]
+    fn version(&self) -> u16 {
+        self.inner.version
+    }
+
+    #[getter]
+    fn circuit_id(&self) -> &str {
+        &self.inner.circuit_id
+    }
+
+    #[getter]
+    fn curve(&self) -> &'static str {
+        self.inner.curve.as_str()
+    }
+
+    #[getter]
+    fn key_id(&self) -> Option<&str> {
+        self.inner.key_id.as_deref()
+    }
+
+    #[getter]
+    fn proof<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, &self.inner.proof)
+    }
+
+    #[getter]
+    fn proof_hex(&self) -> String {
+        hex::encode(&self.inner.proof)
+    }
+
+    /// Public inputs as a dict keyed like `verify_proof`'s arguments
+    #[getter]
+    fn public_inputs<'py>(&self, py: Python<'py>) -> PyResult<&'py PyDict> {
+        let inputs = &self.inner.public_inputs;
+        let dict = PyDict::new(py);
+        dict.set_item("document_commitment", &inputs.document_commitment)?;
+        dict.set_item("model_hash", &inputs.model_hash)?;
+        dict.set_item("timestamp", inputs.timestamp)?;
+        Ok(dict)
+    }
+
+    /// Hex SHA-256 binding the proof to its circuit, key, and public inputs
+    #[getter]
+    fn digest(&self) -> String {
+        self.inner.digest()
+    }
+
+    fn __eq__(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ProofEnvelope(version={}, circuit_id={:?}, digest={:?})",
+            self.inner.version,
+            self.inner.circuit_id,
+            self.inner.digest()
+        )
+    }
+}
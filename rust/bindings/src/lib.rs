@@ -1,26 +1,387 @@
 // Python bindings for ZKvsAI
 //
-// Provides a Python interface to Rust ZK proof generation and verification
+// Provides a Python interface to Rust ZK proof generation and verification.
+// Prover and Verifier load their keys once at construction and are meant to
+// be kept around and reused across calls. Proving and verification release
+// the GIL, so other Python threads keep running while a proof is in flight.
+// The `_async` variants return asyncio awaitables that run the same work on
+// the event loop's default executor.
+//
+// Query embeddings may be numpy arrays (float32 or float64), read through the
+// buffer protocol without building a Python float per element, or plain lists.
+//
+// `CommitmentBuilder` commits to a corpus one document or file at a time,
+// keeping only document hashes in memory.
+//
+// Verifier policy (proof freshness, model allowlist, revocation list) is set
+// through keyword arguments to `Verifier()`, so it can live in the service's
+// settings.
+//
+// `setup`, `load_proving_key`, and `Prover.export_verifying_key` let
+// deployment scripts provision keys explicitly instead of relying on the
+// home-directory cache. `circuit_info` describes a circuit's parameters,
+// public inputs, and constraint count before any key is loaded.
+//
+// A Prover remembers where its key came from and the process that loaded it.
+// In a forked child (gunicorn preforking, multiprocessing's fork start
+// method) the first call reloads the key instead of reusing the parent's
+// copy, so a prover created at import time in the master is safe to use in
+// workers.
+//
+// Both classes are context managers: leaving a `with` block releases the
+// loaded key, and later calls raise ZkragError. Witnesses are zeroized by the
+// prover when dropped.
+//
+// `QueryWitness` is built from keyword arguments and validated on
+// construction; `Prover.prove` takes one instead of `generate_proof`'s seven
+// positional arguments.
+//
+// Errors are raised as subclasses of `ZkragError` by failing stage. It
+// derives from ValueError, which the bindings raised before.
+//
+// Proofs are hex strings by default. The `_bytes` variants take and return raw
+// bytes, accepting any buffer (bytes, bytearray, memoryview), for proofs
+// headed straight to storage. Keys and proofs are also available as `Buffer`
+// objects, which lend their bytes through the buffer protocol without a copy.
+// `ProofEnvelope` carries a proof with its circuit id and public inputs in the
+// verifier's versioned JSON or binary format. `Verifier.verify_proofs` checks
+// many proofs at once, with one combined pairing check if asked;
+// `aggregate_proofs` bundles proofs for a batch-audit job to hand to
+// `Verifier.verify_aggregated`, which always combines them.
+//
+// `proof_to_snarkjs`, `public_inputs_to_snarkjs`, and `verify_snarkjs` convert
+// to and check snarkjs JSON, for teams with existing circom tooling.
+//
+// With the `client` feature, `zkrag_rust.client` talks to the NockApp HTTP
+// verification service.
 
-use pyo3::prelude::*;
+// pyo3 0.20's #[pymethods] expansion trips this lint on newer compilers
+#![allow(non_local_definitions)]
+
+use pyo3::buffer::PyBuffer;
+use pyo3::create_exception;
 use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyString};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::Duration;
+
+mod buffer;
+#[cfg(feature = "client")]
+mod client;
+mod envelope;
 
+use buffer::ByteBuffer;
+use envelope::PyProofEnvelope;
+use zkrag_circuits::document_query::QUERY_EMBEDDING_DIM;
+use zkrag_circuits::{registry, utils};
 use zkrag_prover::{QueryProver, QueryWitness};
-use zkrag_verifier::{QueryVerifier, PublicInputs, VerificationResult};
+use zkrag_verifier::{
+    ProofEnvelope, PublicInputs, QueryVerifier, RevocationRegistry, VerificationResult,
+    VerifierPolicy,
+};
 
-/// Generate a proof for a document query
-#[pyfunction]
-fn generate_proof(
-    document_hashes: Vec<String>,
-    query_text: String,
-    query_embedding: Vec<f64>,
-    search_results: Vec<usize>,
+create_exception!(
+    zkrag_rust,
+    ZkragError,
+    PyValueError,
+    "Base class for zkrag errors"
+);
+create_exception!(
+    zkrag_rust,
+    ProvingError,
+    ZkragError,
+    "Proof generation failed"
+);
+create_exception!(
+    zkrag_rust,
+    VerificationError,
+    ZkragError,
+    "A proof or its public inputs could not be checked"
+);
+create_exception!(
+    zkrag_rust,
+    InvalidWitnessError,
+    ZkragError,
+    "Witness inputs don't fit the circuit"
+);
+create_exception!(
+    zkrag_rust,
+    KeyError,
+    ZkragError,
+    "A proving or verifying key could not be loaded, generated, or written"
+);
+
+/// Prove with the GIL released, calling `progress(stage, fraction)` if given.
+/// An exception raised by the callback is re-raised once proving finishes.
+fn prove(
+    py: Python<'_>,
+    prover: &QueryProver,
+    witness: QueryWitness,
+    progress: Option<PyObject>,
+) -> PyResult<Vec<u8>> {
+    let mut callback_error = None;
+    let proof_bytes = py.allow_threads(|| {
+        prover.prove_with_progress(witness, |stage, fraction| {
+            let Some(callback) = &progress else {
+                return;
+            };
+            if callback_error.is_some() {
+                return;
+            }
+            Python::with_gil(|py| {
+                if let Err(e) = callback.call1(py, (stage.as_str(), fraction)) {
+                    callback_error = Some(e);
+                }
+            });
+        })
+    });
+
+    if let Some(e) = callback_error {
+        return Err(e);
+    }
+    proof_bytes.map_err(|e| ProvingError::new_err(format!("Proof generation error: {}", e)))
+}
+
+/// Verify with the GIL released
+fn verify(
+    py: Python<'_>,
+    verifier: &QueryVerifier,
+    proof_bytes: &[u8],
+    public_inputs: PublicInputs,
+) -> PyResult<VerificationResult> {
+    py.allow_threads(|| verifier.verify(proof_bytes, public_inputs))
+        .map_err(|e| VerificationError::new_err(format!("Verification error: {}", e)))
+}
+
+/// Read a query embedding from a float32/float64 buffer such as a
+/// `numpy.ndarray`, or from a sequence of floats
+fn extract_embedding(py: Python<'_>, embedding: &PyAny) -> PyResult<Vec<f64>> {
+    let values = if let Ok(buffer) = PyBuffer::<f64>::get(embedding) {
+        check_vector(&buffer)?;
+        buffer.to_vec(py)?
+    } else if let Ok(buffer) = PyBuffer::<f32>::get(embedding) {
+        check_vector(&buffer)?;
+        buffer.to_vec(py)?.into_iter().map(f64::from).collect()
+    } else {
+        embedding.extract::<Vec<f64>>()?
+    };
+
+    if values.len() != QUERY_EMBEDDING_DIM {
+        return Err(InvalidWitnessError::new_err(format!(
+            "Query embedding has {} dimensions, circuit expects {}",
+            values.len(),
+            QUERY_EMBEDDING_DIM
+        )));
+    }
+    Ok(values)
+}
+
+/// Reject buffers that aren't a single vector
+fn check_vector<T: pyo3::buffer::Element>(buffer: &PyBuffer<T>) -> PyResult<()> {
+    if buffer.dimensions() != 1 {
+        return Err(InvalidWitnessError::new_err(format!(
+            "Query embedding must be 1-dimensional, got {} dimensions",
+            buffer.dimensions()
+        )));
+    }
+    Ok(())
+}
+
+/// Required entry of a witness or public inputs dict
+fn dict_item<'py>(dict: &'py PyDict, key: &str) -> PyResult<&'py PyAny> {
+    dict.get_item(key)?
+        .ok_or_else(|| InvalidWitnessError::new_err(format!("Missing '{}'", key)))
+}
+
+/// Build a witness from a dict keyed like `generate_proof`'s arguments
+fn extract_witness(py: Python<'_>, dict: &PyDict) -> PyResult<QueryWitness> {
+    Ok(QueryWitness::new(
+        dict_item(dict, "document_hashes")?.extract()?,
+        dict_item(dict, "query_text")?.extract()?,
+        extract_embedding(py, dict_item(dict, "query_embedding")?)?,
+        dict_item(dict, "search_results")?.extract()?,
+        dict_item(dict, "document_commitment")?.extract()?,
+        dict_item(dict, "model_hash")?.extract()?,
+        dict_item(dict, "timestamp")?.extract()?,
+    ))
+}
+
+/// Copy proof bytes out of any object supporting the buffer protocol
+fn extract_proof_bytes(py: Python<'_>, proof: &PyAny) -> PyResult<Vec<u8>> {
+    let buffer = PyBuffer::<u8>::get(proof)
+        .map_err(|_| VerificationError::new_err("Proof must be bytes, bytearray, or memoryview"))?;
+    buffer.to_vec(py)
+}
+
+/// Decode a hex proof and its public inputs
+fn decode(
+    proof_hex: &str,
     document_commitment: String,
     model_hash: String,
     timestamp: u64,
-) -> PyResult<String> {
-    // Create witness
-    let witness = QueryWitness::new(
+) -> PyResult<(Vec<u8>, PublicInputs)> {
+    let proof_bytes = hex::decode(proof_hex)
+        .map_err(|e| VerificationError::new_err(format!("Invalid hex: {}", e)))?;
+
+    let public_inputs = PublicInputs {
+        document_commitment,
+        model_hash,
+        timestamp,
+        nonce: None,
+    };
+    Ok((proof_bytes, public_inputs))
+}
+
+/// Decode `(proof_hex, public_inputs)` pairs, where `public_inputs` is a dict
+/// with `document_commitment`, `model_hash`, `timestamp`, and optionally
+/// `nonce`
+fn decode_items(items: Vec<(&str, &PyDict)>) -> PyResult<Vec<(Vec<u8>, PublicInputs)>> {
+    items
+        .into_iter()
+        .map(|(proof_hex, inputs)| {
+            let (proof_bytes, mut public_inputs) = decode(
+                proof_hex,
+                dict_item(inputs, "document_commitment")?.extract()?,
+                dict_item(inputs, "model_hash")?.extract()?,
+                dict_item(inputs, "timestamp")?.extract()?,
+            )?;
+            public_inputs.nonce = match inputs.get_item("nonce")? {
+                Some(nonce) => nonce.extract()?,
+                None => None,
+            };
+            Ok((proof_bytes, public_inputs))
+        })
+        .collect()
+}
+
+/// Run a job on the running event loop's default executor, returning an
+/// awaitable asyncio future
+fn spawn_on_loop(py: Python<'_>, job: impl IntoPy<PyObject>) -> PyResult<&PyAny> {
+    let event_loop = py.import("asyncio")?.call_method0("get_running_loop")?;
+    event_loop.call_method1("run_in_executor", (py.None(), job.into_py(py)))
+}
+
+/// Proof generation handed to an executor thread
+#[pyclass]
+struct ProofJob {
+    prover: Arc<QueryProver>,
+    witness: QueryWitness,
+}
+
+#[pymethods]
+impl ProofJob {
+    fn __call__(&self, py: Python<'_>) -> PyResult<String> {
+        prove(py, &self.prover, self.witness.clone(), None).map(hex::encode)
+    }
+}
+
+/// Verification handed to an executor thread
+#[pyclass]
+struct VerifyJob {
+    verifier: Arc<QueryVerifier>,
+    proof_bytes: Vec<u8>,
+    public_inputs: PublicInputs,
+}
+
+#[pymethods]
+impl VerifyJob {
+    fn __call__(&self, py: Python<'_>) -> PyResult<bool> {
+        let result = verify(
+            py,
+            &self.verifier,
+            &self.proof_bytes,
+            self.public_inputs.clone(),
+        )?;
+        Ok(result.is_valid)
+    }
+}
+
+/// Outcome of verifying a proof
+#[pyclass(name = "VerificationResult", get_all)]
+struct PyVerificationResult {
+    is_valid: bool,
+    /// Why the proof was rejected, or None if it verified
+    reason: Option<String>,
+    /// Stable code for `reason`, e.g. "pairing_failed"
+    reason_code: Option<String>,
+    verified_at: u64,
+    proof_digest: String,
+    /// Full result as JSON
+    json: String,
+}
+
+impl PyVerificationResult {
+    fn new(result: VerificationResult) -> PyResult<Self> {
+        let json = serde_json::to_string(&result)
+            .map_err(|e| ZkragError::new_err(format!("JSON error: {}", e)))?;
+
+        Ok(Self {
+            is_valid: result.is_valid,
+            reason: result.reason.as_ref().map(ToString::to_string),
+            reason_code: result.reason.as_ref().map(|r| r.code().to_string()),
+            verified_at: result.verified_at,
+            proof_digest: result.proof_digest,
+            json,
+        })
+    }
+}
+
+#[pymethods]
+impl PyVerificationResult {
+    fn __repr__(&self) -> String {
+        match &self.reason {
+            Some(reason) => format!(
+                "VerificationResult(is_valid={}, reason={:?}, proof_digest={:?})",
+                if self.is_valid { "True" } else { "False" },
+                reason,
+                self.proof_digest
+            ),
+            None => format!(
+                "VerificationResult(is_valid={}, proof_digest={:?})",
+                if self.is_valid { "True" } else { "False" },
+                self.proof_digest
+            ),
+        }
+    }
+}
+
+/// Proofs bundled by `aggregate_proofs` for `Verifier.verify_aggregated`.
+///
+/// The bundle holds each proof as given; what's shared is the verifier's
+/// work, one combined pairing check, not a smaller proof.
+#[pyclass(name = "ProofAggregate")]
+struct PyProofAggregate {
+    items: Vec<(Vec<u8>, PublicInputs)>,
+}
+
+#[pymethods]
+impl PyProofAggregate {
+    fn __len__(&self) -> usize {
+        self.items.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("ProofAggregate(proofs={})", self.items.len())
+    }
+}
+
+/// Validated proof witness, built from keyword arguments
+#[pyclass(name = "QueryWitness")]
+#[derive(Clone)]
+struct PyQueryWitness {
+    inner: QueryWitness,
+}
+
+#[pymethods]
+impl PyQueryWitness {
+    /// Build and validate a witness, raising InvalidWitnessError if it
+    /// doesn't fit the circuit
+    #[new]
+    #[pyo3(signature = (
+        *,
         document_hashes,
         query_text,
         query_embedding,
@@ -28,87 +389,858 @@ fn generate_proof(
         document_commitment,
         model_hash,
         timestamp,
-    );
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        py: Python<'_>,
+        document_hashes: Vec<String>,
+        query_text: String,
+        query_embedding: &PyAny,
+        search_results: Vec<usize>,
+        document_commitment: String,
+        model_hash: String,
+        timestamp: u64,
+    ) -> PyResult<Self> {
+        let inner = QueryWitness::builder()
+            .document_hashes(document_hashes)
+            .query_text(query_text)
+            .query_embedding(extract_embedding(py, query_embedding)?)
+            .search_results(search_results)
+            .document_commitment(document_commitment)
+            .model_hash(model_hash)
+            .timestamp(timestamp)
+            .build()
+            .map_err(|e| InvalidWitnessError::new_err(format!("{:#}", e)))?;
+        Ok(Self { inner })
+    }
 
-    // Generate proof
-    let mut prover = QueryProver::new()
-        .map_err(|e| PyValueError::new_err(format!("Prover error: {}", e)))?;
+    /// Serialize to JSON, including the private fields
+    fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string(&self.inner)
+            .map_err(|e| ZkragError::new_err(format!("Witness encoding error: {}", e)))
+    }
 
-    prover.setup()
-        .map_err(|e| PyValueError::new_err(format!("Setup error: {}", e)))?;
+    /// Parse and validate a witness written by `to_json`
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        let inner: QueryWitness = serde_json::from_str(json)
+            .map_err(|e| InvalidWitnessError::new_err(format!("Invalid witness JSON: {}", e)))?;
+        inner
+            .validate()
+            .map_err(|e| InvalidWitnessError::new_err(format!("{:#}", e)))?;
+        Ok(Self { inner })
+    }
 
-    let proof_bytes = prover.prove(witness)
-        .map_err(|e| PyValueError::new_err(format!("Proof generation error: {}", e)))?;
+    #[getter]
+    fn document_commitment(&self) -> &str {
+        &self.inner.document_commitment
+    }
 
-    // Encode as hex
-    Ok(hex::encode(proof_bytes))
+    #[getter]
+    fn model_hash(&self) -> &str {
+        &self.inner.model_hash
+    }
+
+    #[getter]
+    fn timestamp(&self) -> u64 {
+        self.inner.timestamp
+    }
+
+    /// Only the public inputs; the private fields stay out of logs
+    fn __repr__(&self) -> String {
+        format!(
+            "QueryWitness(document_commitment={:?}, model_hash={:?}, timestamp={})",
+            self.inner.document_commitment, self.inner.model_hash, self.inner.timestamp
+        )
+    }
 }
 
-/// Verify a document query proof
-#[pyfunction]
-fn verify_proof(
-    proof_hex: String,
-    document_commitment: String,
-    model_hash: String,
-    timestamp: u64,
-) -> PyResult<bool> {
-    // Decode proof
-    let proof_bytes = hex::decode(&proof_hex)
-        .map_err(|e| PyValueError::new_err(format!("Invalid hex: {}", e)))?;
+/// Where a prover's proving key comes from
+enum KeySource {
+    /// Key cache directory, or the default when None
+    Cache(Option<PathBuf>),
+    /// Proving key file
+    File(PathBuf),
+}
 
-    // Create public inputs
-    let public_inputs = PublicInputs {
+impl KeySource {
+    fn load(&self) -> PyResult<QueryProver> {
+        let cache_dir = match self {
+            Self::Cache(dir) => dir.clone(),
+            Self::File(_) => None,
+        };
+        let mut prover = match cache_dir {
+            Some(dir) => QueryProver::with_cache_dir(dir),
+            None => QueryProver::new(),
+        }
+        .map_err(|e| KeyError::new_err(format!("Prover error: {}", e)))?;
+
+        match self {
+            Self::Cache(_) => prover
+                .setup()
+                .map_err(|e| KeyError::new_err(format!("Setup error: {}", e)))?,
+            Self::File(path) => prover
+                .load_proving_key(path)
+                .map_err(|e| KeyError::new_err(format!("Key load error: {}", e)))?,
+        }
+        Ok(prover)
+    }
+}
+
+/// Loaded prover and the process it was loaded in
+struct Handle {
+    pid: u32,
+    prover: Arc<QueryProver>,
+}
+
+/// Document query prover holding a loaded proving key
+#[pyclass]
+struct Prover {
+    source: KeySource,
+    /// None once closed
+    handle: RwLock<Option<Handle>>,
+}
+
+impl Prover {
+    fn load(source: KeySource) -> PyResult<Self> {
+        let prover = Arc::new(source.load()?);
+
+        Ok(Self {
+            source,
+            handle: RwLock::new(Some(Handle {
+                pid: std::process::id(),
+                prover,
+            })),
+        })
+    }
+
+    /// The loaded prover, reloading the key first if this process is a fork
+    /// of the one that loaded it
+    fn inner(&self) -> PyResult<Arc<QueryProver>> {
+        let pid = std::process::id();
+        match &*self.handle.read().unwrap_or_else(PoisonError::into_inner) {
+            Some(handle) if handle.pid == pid => return Ok(handle.prover.clone()),
+            Some(_) => {}
+            None => return Err(ZkragError::new_err("Prover is closed")),
+        }
+
+        // The parent's copy is shared copy-on-write and any state its
+        // threads held didn't survive the fork, so don't touch it
+        let prover = Arc::new(self.source.load()?);
+        *self.handle.write().unwrap_or_else(PoisonError::into_inner) = Some(Handle {
+            pid,
+            prover: prover.clone(),
+        });
+        Ok(prover)
+    }
+}
+
+#[pymethods]
+impl Prover {
+    /// Load the proving key from `cache_dir` (default `~/.zkrag/keys`),
+    /// generating and caching one if there is none
+    #[new]
+    #[pyo3(signature = (cache_dir=None))]
+    fn new(cache_dir: Option<PathBuf>) -> PyResult<Self> {
+        Self::load(KeySource::Cache(cache_dir))
+    }
+
+    /// Generate proofs for a list of witness dicts, keyed like
+    /// `generate_proof`'s arguments, returning one hex proof per witness
+    fn generate_proofs(&self, py: Python<'_>, witnesses: Vec<&PyDict>) -> PyResult<Vec<String>> {
+        let prover = self.inner()?;
+        let witnesses = witnesses
+            .into_iter()
+            .enumerate()
+            .map(|(i, dict)| {
+                extract_witness(py, dict).map_err(|e| {
+                    InvalidWitnessError::new_err(format!("Witness {}: {}", i, e.value(py)))
+                })
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+
+        let proofs = py
+            .allow_threads(|| prover.prove_batch(witnesses))
+            .map_err(|e| ProvingError::new_err(format!("Proof generation error: {:#}", e)))?;
+        Ok(proofs.into_iter().map(hex::encode).collect())
+    }
+
+    /// Write the verifying key for this prover's proving key to `path`
+    fn export_verifying_key(&self, path: PathBuf) -> PyResult<()> {
+        self.inner()?
+            .export_verifying_key(&path)
+            .map_err(|e| KeyError::new_err(format!("Key export error: {}", e)))
+    }
+
+    /// Serialized proving key as a `Buffer`, for writing to storage
+    /// without an intermediate `bytes` copy
+    fn proving_key_buffer(&self, py: Python<'_>) -> PyResult<ByteBuffer> {
+        let prover = self.inner()?;
+        let bytes = py
+            .allow_threads(|| prover.proving_key_bytes())
+            .map_err(|e| KeyError::new_err(format!("Key export error: {}", e)))?;
+        Ok(ByteBuffer::new("proving_key", bytes))
+    }
+
+    /// Serialized verifying key as a `Buffer`
+    fn verifying_key_buffer(&self) -> PyResult<ByteBuffer> {
+        let bytes = self
+            .inner()?
+            .verifying_key_bytes()
+            .map_err(|e| KeyError::new_err(format!("Key export error: {}", e)))?;
+        Ok(ByteBuffer::new("verifying_key", bytes))
+    }
+
+    /// Release the proving key. In-flight async jobs keep their own reference;
+    /// further calls raise ZkragError.
+    fn close(&self) {
+        *self.handle.write().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Close on leaving a `with` block; exceptions propagate
+    fn __exit__(
+        &mut self,
+        _exc_type: Option<&PyAny>,
+        _exc_value: Option<&PyAny>,
+        _traceback: Option<&PyAny>,
+    ) -> bool {
+        self.close();
+        false
+    }
+
+    /// Generate a hex proof for a `QueryWitness`. `progress`, if given, is
+    /// called with `(stage, fraction)` as proving advances.
+    #[pyo3(signature = (witness, progress=None))]
+    fn prove(
+        &self,
+        py: Python<'_>,
+        witness: &PyQueryWitness,
+        progress: Option<PyObject>,
+    ) -> PyResult<String> {
+        let prover = self.inner()?;
+        prove(py, &prover, witness.inner.clone(), progress).map(hex::encode)
+    }
+
+    /// `prove` returning the proof as a `Buffer`
+    #[pyo3(signature = (witness, progress=None))]
+    fn prove_buffer(
+        &self,
+        py: Python<'_>,
+        witness: &PyQueryWitness,
+        progress: Option<PyObject>,
+    ) -> PyResult<ByteBuffer> {
+        let prover = self.inner()?;
+        let proof_bytes = prove(py, &prover, witness.inner.clone(), progress)?;
+        Ok(ByteBuffer::new("proof", proof_bytes))
+    }
+
+    /// `prove` returning the proof wrapped in a `ProofEnvelope` with the
+    /// witness's public inputs
+    #[pyo3(signature = (witness, progress=None))]
+    fn prove_envelope(
+        &self,
+        py: Python<'_>,
+        witness: &PyQueryWitness,
+        progress: Option<PyObject>,
+    ) -> PyResult<PyProofEnvelope> {
+        let prover = self.inner()?;
+        let proof_bytes = prove(py, &prover, witness.inner.clone(), progress)?;
+        let public_inputs = PublicInputs {
+            document_commitment: witness.inner.document_commitment.clone(),
+            model_hash: witness.inner.model_hash.clone(),
+            timestamp: witness.inner.timestamp,
+            nonce: witness.inner.nonce.clone(),
+        };
+        Ok(PyProofEnvelope {
+            inner: ProofEnvelope::new(proof_bytes, public_inputs),
+        })
+    }
+
+    /// Generate a proof for a document query. `progress`, if given, is
+    /// called with `(stage, fraction)` as proving advances. Prefer `prove`
+    /// with a validated `QueryWitness`.
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (
+        document_hashes,
+        query_text,
+        query_embedding,
+        search_results,
         document_commitment,
         model_hash,
         timestamp,
-    };
+        progress=None,
+    ))]
+    fn generate_proof(
+        &self,
+        py: Python<'_>,
+        document_hashes: Vec<String>,
+        query_text: String,
+        query_embedding: &PyAny,
+        search_results: Vec<usize>,
+        document_commitment: String,
+        model_hash: String,
+        timestamp: u64,
+        progress: Option<PyObject>,
+    ) -> PyResult<String> {
+        // Create witness
+        let witness = QueryWitness::new(
+            document_hashes,
+            query_text,
+            extract_embedding(py, query_embedding)?,
+            search_results,
+            document_commitment,
+            model_hash,
+            timestamp,
+        );
+
+        // Encode as hex
+        let prover = self.inner()?;
+        prove(py, &prover, witness, progress).map(hex::encode)
+    }
+
+    /// `generate_proof` returning the proof as raw bytes
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (
+        document_hashes,
+        query_text,
+        query_embedding,
+        search_results,
+        document_commitment,
+        model_hash,
+        timestamp,
+        progress=None,
+    ))]
+    fn generate_proof_bytes<'py>(
+        &self,
+        py: Python<'py>,
+        document_hashes: Vec<String>,
+        query_text: String,
+        query_embedding: &PyAny,
+        search_results: Vec<usize>,
+        document_commitment: String,
+        model_hash: String,
+        timestamp: u64,
+        progress: Option<PyObject>,
+    ) -> PyResult<&'py PyBytes> {
+        let witness = QueryWitness::new(
+            document_hashes,
+            query_text,
+            extract_embedding(py, query_embedding)?,
+            search_results,
+            document_commitment,
+            model_hash,
+            timestamp,
+        );
+
+        let prover = self.inner()?;
+        let proof_bytes = prove(py, &prover, witness, progress)?;
+        Ok(PyBytes::new(py, &proof_bytes))
+    }
+
+    /// Awaitable `generate_proof` for asyncio services; proves on the
+    /// running loop's default executor
+    #[allow(clippy::too_many_arguments)]
+    fn generate_proof_async<'py>(
+        &self,
+        py: Python<'py>,
+        document_hashes: Vec<String>,
+        query_text: String,
+        query_embedding: &PyAny,
+        search_results: Vec<usize>,
+        document_commitment: String,
+        model_hash: String,
+        timestamp: u64,
+    ) -> PyResult<&'py PyAny> {
+        let witness = QueryWitness::new(
+            document_hashes,
+            query_text,
+            extract_embedding(py, query_embedding)?,
+            search_results,
+            document_commitment,
+            model_hash,
+            timestamp,
+        );
+
+        let job = ProofJob {
+            prover: self.inner()?,
+            witness,
+        };
+        spawn_on_loop(py, job)
+    }
+}
+
+/// Document query verifier holding a prepared verifying key
+#[pyclass]
+struct Verifier {
+    /// None once closed
+    inner: Option<Arc<QueryVerifier>>,
+}
+
+impl Verifier {
+    fn inner(&self) -> PyResult<&Arc<QueryVerifier>> {
+        self.inner
+            .as_ref()
+            .ok_or_else(|| ZkragError::new_err("Verifier is closed"))
+    }
+}
+
+#[pymethods]
+impl Verifier {
+    /// Load the verifying key from `key_path`, or from the standard key
+    /// directory if not given.
+    ///
+    /// Policy options reject otherwise valid proofs: `max_proof_age` in
+    /// seconds, an `allowed_models` allowlist of model hashes, a JSON
+    /// `revocation_list` of revoked commitments and models, and a
+    /// `policy_path` TOML or JSON model allowlist.
+    #[new]
+    #[pyo3(signature = (
+        key_path=None,
+        *,
+        max_proof_age=None,
+        allowed_models=None,
+        revocation_list=None,
+        policy_path=None,
+    ))]
+    fn new(
+        key_path: Option<PathBuf>,
+        max_proof_age: Option<f64>,
+        allowed_models: Option<HashSet<String>>,
+        revocation_list: Option<PathBuf>,
+        policy_path: Option<PathBuf>,
+    ) -> PyResult<Self> {
+        let mut builder = match key_path {
+            Some(path) => QueryVerifier::builder().key_path(path),
+            None => QueryVerifier::builder().default_key_dir(),
+        };
+        if let Some(seconds) = max_proof_age {
+            let max_age = Duration::try_from_secs_f64(seconds)
+                .map_err(|e| ZkragError::new_err(format!("Invalid max_proof_age: {}", e)))?;
+            builder = builder.max_proof_age(max_age);
+        }
+        if let Some(models) = allowed_models {
+            builder = builder.policy(VerifierPolicy::new().allowed_models(models));
+        }
+        if let Some(path) = revocation_list {
+            let revocations = RevocationRegistry::from_file(path)
+                .map_err(|e| ZkragError::new_err(format!("Policy error: {}", e)))?;
+            builder = builder.revocations(Arc::new(revocations));
+        }
+        if let Some(path) = policy_path {
+            builder = builder.policy_path(path);
+        }
+
+        let inner = builder
+            .build()
+            .map_err(|e| KeyError::new_err(format!("Verifier error: {}", e)))?;
+
+        Ok(Self {
+            inner: Some(Arc::new(inner)),
+        })
+    }
 
-    // Verify
-    let verifier = QueryVerifier::new()
-        .map_err(|e| PyValueError::new_err(format!("Verifier error: {}", e)))?;
+    /// Verify a document query proof
+    fn verify_proof(
+        &self,
+        py: Python<'_>,
+        proof_hex: &str,
+        document_commitment: String,
+        model_hash: String,
+        timestamp: u64,
+    ) -> PyResult<bool> {
+        let (proof_bytes, public_inputs) =
+            decode(proof_hex, document_commitment, model_hash, timestamp)?;
+        let result = verify(py, self.inner()?, &proof_bytes, public_inputs)?;
+        Ok(result.is_valid)
+    }
 
-    let result = verifier.verify(&proof_bytes, public_inputs)
-        .map_err(|e| PyValueError::new_err(format!("Verification error: {}", e)))?;
+    /// `verify_proof` taking the proof as bytes, bytearray, or memoryview
+    fn verify_proof_bytes(
+        &self,
+        py: Python<'_>,
+        proof: &PyAny,
+        document_commitment: String,
+        model_hash: String,
+        timestamp: u64,
+    ) -> PyResult<bool> {
+        let proof_bytes = extract_proof_bytes(py, proof)?;
+        let public_inputs = PublicInputs {
+            document_commitment,
+            model_hash,
+            timestamp,
+            nonce: None,
+        };
+        let result = verify(py, self.inner()?, &proof_bytes, public_inputs)?;
+        Ok(result.is_valid)
+    }
 
-    Ok(result.is_valid)
+    /// Awaitable `verify_proof` for asyncio services; verifies on the
+    /// running loop's default executor
+    fn verify_proof_async<'py>(
+        &self,
+        py: Python<'py>,
+        proof_hex: &str,
+        document_commitment: String,
+        model_hash: String,
+        timestamp: u64,
+    ) -> PyResult<&'py PyAny> {
+        let (proof_bytes, public_inputs) =
+            decode(proof_hex, document_commitment, model_hash, timestamp)?;
+
+        let job = VerifyJob {
+            verifier: self.inner()?.clone(),
+            proof_bytes,
+            public_inputs,
+        };
+        spawn_on_loop(py, job)
+    }
+
+    /// Verify a list of `(proof_hex, public_inputs)` pairs, where
+    /// `public_inputs` is a dict with `document_commitment`, `model_hash`,
+    /// `timestamp`, and optionally `nonce`, returning one validity flag per
+    /// item. With `combined`, the proofs share one pairing check, and the
+    /// items' public inputs must be distinct.
+    #[pyo3(signature = (items, combined=false))]
+    fn verify_proofs(
+        &self,
+        py: Python<'_>,
+        items: Vec<(&str, &PyDict)>,
+        combined: bool,
+    ) -> PyResult<Vec<bool>> {
+        let verifier = self.inner()?;
+        let items = decode_items(items)?;
+        let results = py
+            .allow_threads(|| {
+                if combined {
+                    verifier.verify_batch_combined(&items)
+                } else {
+                    verifier.verify_batch(&items)
+                }
+            })
+            .map_err(|e| VerificationError::new_err(format!("Verification error: {}", e)))?;
+        Ok(results.iter().map(|result| result.is_valid).collect())
+    }
+
+    /// Verify the proofs of an `aggregate_proofs` bundle with one combined
+    /// pairing check, returning one validity flag per proof in order
+    fn verify_aggregated(
+        &self,
+        py: Python<'_>,
+        aggregate: &PyProofAggregate,
+    ) -> PyResult<Vec<bool>> {
+        let verifier = self.inner()?;
+        let results = py
+            .allow_threads(|| verifier.verify_batch_combined(&aggregate.items))
+            .map_err(|e| VerificationError::new_err(format!("Verification error: {}", e)))?;
+        Ok(results.iter().map(|result| result.is_valid).collect())
+    }
+
+    /// Verify a `ProofEnvelope` against its circuit's current key, or the
+    /// key its `key_id` names
+    fn verify_envelope(
+        &self,
+        py: Python<'_>,
+        envelope: &PyProofEnvelope,
+    ) -> PyResult<PyVerificationResult> {
+        let verifier = self.inner()?;
+        let result = py
+            .allow_threads(|| verifier.verify_envelope(&envelope.inner, None))
+            .map_err(|e| VerificationError::new_err(format!("Verification error: {}", e)))?;
+        PyVerificationResult::new(result)
+    }
+
+    /// Get verification result with details
+    fn verify_proof_detailed(
+        &self,
+        py: Python<'_>,
+        proof_hex: &str,
+        document_commitment: String,
+        model_hash: String,
+        timestamp: u64,
+    ) -> PyResult<PyVerificationResult> {
+        let (proof_bytes, public_inputs) =
+            decode(proof_hex, document_commitment, model_hash, timestamp)?;
+        let result = verify(py, self.inner()?, &proof_bytes, public_inputs)?;
+        PyVerificationResult::new(result)
+    }
+
+    /// Release the verifying key. In-flight async jobs keep their own reference;
+    /// further calls raise ZkragError.
+    fn close(&mut self) {
+        self.inner = None;
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Close on leaving a `with` block; exceptions propagate
+    fn __exit__(
+        &mut self,
+        _exc_type: Option<&PyAny>,
+        _exc_value: Option<&PyAny>,
+        _traceback: Option<&PyAny>,
+    ) -> bool {
+        self.close();
+        false
+    }
+}
+
+/// Provision the proving key in `cache_dir` and return a prover using it
+#[pyfunction]
+#[pyo3(signature = (cache_dir=None))]
+fn setup(cache_dir: Option<PathBuf>) -> PyResult<Prover> {
+    Prover::new(cache_dir)
 }
 
-/// Get verification result with details
+/// Create a prover from a proving key file, e.g. a ceremony's output
+/// Bundle `(proof_hex, public_inputs)` pairs, keyed as for
+/// `Verifier.verify_proofs`, for `Verifier.verify_aggregated`. Each pair must
+/// have distinct public inputs.
 #[pyfunction]
-fn verify_proof_detailed(
-    proof_hex: String,
+fn aggregate_proofs(items: Vec<(&str, &PyDict)>) -> PyResult<PyProofAggregate> {
+    let items = decode_items(items)?;
+    let mut seen = HashSet::with_capacity(items.len());
+    for (i, (_, public_inputs)) in items.iter().enumerate() {
+        if !seen.insert(public_inputs.digest()) {
+            return Err(VerificationError::new_err(format!(
+                "Item {} repeats the public inputs of an earlier item",
+                i
+            )));
+        }
+    }
+    Ok(PyProofAggregate { items })
+}
+
+#[pyfunction]
+fn load_proving_key(path: PathBuf) -> PyResult<Prover> {
+    Prover::load(KeySource::File(path))
+}
+
+/// Metadata of a registered circuit
+#[pyclass(name = "CircuitInfo", get_all)]
+struct PyCircuitInfo {
+    id: String,
+    name: String,
+    curve: String,
+    /// Fixed size parameters, e.g. `query_embedding_dim`
+    params: HashMap<String, usize>,
+    /// Public input names in verification order
+    public_inputs: Vec<String>,
+    num_constraints: usize,
+}
+
+#[pymethods]
+impl PyCircuitInfo {
+    fn __repr__(&self) -> String {
+        format!(
+            "CircuitInfo(id={:?}, num_constraints={}, public_inputs={:?})",
+            self.id, self.num_constraints, self.public_inputs
+        )
+    }
+}
+
+/// Metadata of the circuit registered as `circuit_id`, including its
+/// constraint count
+#[pyfunction]
+#[pyo3(signature = (circuit_id="document_query"))]
+fn circuit_info(py: Python<'_>, circuit_id: &str) -> PyResult<PyCircuitInfo> {
+    let info = py
+        .allow_threads(|| registry::circuit_info(circuit_id))
+        .ok_or_else(|| {
+            ZkragError::new_err(format!(
+                "Unknown circuit '{}', expected one of {:?}",
+                circuit_id,
+                registry::CIRCUIT_IDS
+            ))
+        })?
+        .map_err(|e| ZkragError::new_err(format!("Circuit synthesis error: {}", e)))?;
+
+    Ok(PyCircuitInfo {
+        id: info.id.to_string(),
+        name: info.name.to_string(),
+        curve: info.curve.to_string(),
+        params: info
+            .params
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect(),
+        public_inputs: info.public_inputs.into_iter().map(String::from).collect(),
+        num_constraints: info.num_constraints,
+    })
+}
+
+/// Incremental document commitment for corpora too large to hold in memory
+#[pyclass(name = "CommitmentBuilder")]
+#[derive(Default)]
+struct PyCommitmentBuilder {
+    inner: utils::CommitmentBuilder,
+}
+
+#[pymethods]
+impl PyCommitmentBuilder {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hash a document's contents (bytes, bytearray, or memoryview) and
+    /// add it
+    fn add_document(&mut self, py: Python<'_>, content: &PyAny) -> PyResult<()> {
+        let hash = match content.downcast::<PyBytes>() {
+            Ok(bytes) => {
+                let bytes = bytes.as_bytes();
+                py.allow_threads(|| utils::document_hash(bytes))
+            }
+            Err(_) => {
+                let bytes = PyBuffer::<u8>::get(content)
+                    .map_err(|_| {
+                        ZkragError::new_err("Document must be bytes, bytearray, or memoryview")
+                    })?
+                    .to_vec(py)?;
+                py.allow_threads(|| utils::document_hash(&bytes))
+            }
+        };
+        self.inner.add_hash(hash);
+        Ok(())
+    }
+
+    /// Add a document by its hex hash
+    fn add_hash(&mut self, hash: String) {
+        self.inner.add_hash(hash);
+    }
+
+    /// Hash a file's contents in chunks and add it as one document
+    fn add_file(&mut self, py: Python<'_>, path: PathBuf) -> PyResult<()> {
+        let inner = &mut self.inner;
+        py.allow_threads(|| {
+            let file = std::fs::File::open(&path)?;
+            inner.add_reader(file)
+        })
+        .map_err(|e| ZkragError::new_err(format!("Failed to hash {}: {}", path.display(), e)))
+    }
+
+    /// Commitment to every document added so far
+    fn finalize(&self) -> String {
+        self.inner.finalize()
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+/// Merkle commitment to a document set, computed as the circuit expects.
+/// Each item is a hex document hash (`str`) or raw document contents
+/// (`bytes`), which are hashed first.
+#[pyfunction]
+fn compute_document_commitment(documents: Vec<&PyAny>) -> PyResult<String> {
+    let hashes = documents
+        .into_iter()
+        .map(|document| match document.downcast::<PyBytes>() {
+            Ok(content) => Ok(utils::document_hash(content.as_bytes())),
+            Err(_) => document.extract::<String>(),
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+    Ok(utils::compute_document_commitment(&hashes))
+}
+
+/// Canonical `model_hash` of a safetensors/GGUF/ONNX weight file, or of
+/// every weight file under a model directory
+#[pyfunction]
+fn compute_model_hash(py: Python<'_>, path: PathBuf) -> PyResult<String> {
+    py.allow_threads(|| zkrag_prover::compute_model_hash(&path))
+        .map_err(|e| ZkragError::new_err(format!("Model hash error: {:#}", e)))
+}
+
+/// Write a Solidity contract verifying proofs under the verifying key at
+/// `vk_path`, for redeploying on-chain verification after a key rotation
+#[pyfunction]
+fn export_solidity_verifier(vk_path: PathBuf, out_path: PathBuf) -> PyResult<()> {
+    zkrag_verifier::solidity::export_solidity_verifier(&vk_path, &out_path)
+        .map_err(|e| KeyError::new_err(format!("Solidity export error: {}", e)))
+}
+
+/// snarkjs proof.json for a proof given as bytes or a hex string
+#[pyfunction]
+fn proof_to_snarkjs(py: Python<'_>, proof: &PyAny) -> PyResult<String> {
+    let proof = match proof.downcast::<PyString>() {
+        Ok(hex_str) => hex::decode(hex_str.to_str()?)
+            .map_err(|e| VerificationError::new_err(format!("Invalid hex: {}", e)))?,
+        Err(_) => extract_proof_bytes(py, proof)?,
+    };
+    zkrag_verifier::interop::proof_to_snarkjs(&proof)
+        .map_err(|e| VerificationError::new_err(format!("Invalid proof: {}", e)))
+}
+
+/// snarkjs public.json for document query public inputs
+#[pyfunction]
+fn public_inputs_to_snarkjs(
     document_commitment: String,
     model_hash: String,
     timestamp: u64,
-) -> PyResult<String> {
-    // Decode proof
-    let proof_bytes = hex::decode(&proof_hex)
-        .map_err(|e| PyValueError::new_err(format!("Invalid hex: {}", e)))?;
-
-    // Create public inputs
-    let public_inputs = PublicInputs {
+) -> String {
+    zkrag_verifier::interop::public_inputs_to_snarkjs(&PublicInputs {
         document_commitment,
         model_hash,
         timestamp,
-    };
-
-    // Verify
-    let verifier = QueryVerifier::new()
-        .map_err(|e| PyValueError::new_err(format!("Verifier error: {}", e)))?;
-
-    let result = verifier.verify(&proof_bytes, public_inputs)
-        .map_err(|e| PyValueError::new_err(format!("Verification error: {}", e)))?;
+        nonce: None,
+    })
+}
 
-    // Serialize result as JSON
-    serde_json::to_string(&result)
-        .map_err(|e| PyValueError::new_err(format!("JSON error: {}", e)))
+/// Verify snarkjs proof.json and public.json against verification_key.json,
+/// e.g. a proof from a circom circuit
+#[pyfunction]
+fn verify_snarkjs(
+    py: Python<'_>,
+    proof_json: &str,
+    public_json: &str,
+    vk_json: &str,
+) -> PyResult<bool> {
+    py.allow_threads(|| zkrag_verifier::interop::verify_snarkjs(proof_json, public_json, vk_json))
+        .map_err(|e| VerificationError::new_err(format!("snarkjs verification error: {}", e)))
 }
 
 /// Python module initialization
 #[pymodule]
-fn zkrag_rust(_py: Python, m: &PyModule) -> PyResult<()> {
-    m.add_function(wrap_pyfunction!(generate_proof, m)?)?;
-    m.add_function(wrap_pyfunction!(verify_proof, m)?)?;
-    m.add_function(wrap_pyfunction!(verify_proof_detailed, m)?)?;
+fn zkrag_rust(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add("ZkragError", py.get_type::<ZkragError>())?;
+    m.add("ProvingError", py.get_type::<ProvingError>())?;
+    m.add("VerificationError", py.get_type::<VerificationError>())?;
+    m.add("InvalidWitnessError", py.get_type::<InvalidWitnessError>())?;
+    m.add("KeyError", py.get_type::<KeyError>())?;
+    m.add_function(wrap_pyfunction!(setup, m)?)?;
+    m.add_function(wrap_pyfunction!(load_proving_key, m)?)?;
+    m.add_function(wrap_pyfunction!(aggregate_proofs, m)?)?;
+    m.add_function(wrap_pyfunction!(compute_document_commitment, m)?)?;
+    m.add_function(wrap_pyfunction!(compute_model_hash, m)?)?;
+    m.add_function(wrap_pyfunction!(circuit_info, m)?)?;
+    m.add_function(wrap_pyfunction!(export_solidity_verifier, m)?)?;
+    m.add_function(wrap_pyfunction!(proof_to_snarkjs, m)?)?;
+    m.add_function(wrap_pyfunction!(public_inputs_to_snarkjs, m)?)?;
+    m.add_function(wrap_pyfunction!(verify_snarkjs, m)?)?;
+    m.add_class::<Prover>()?;
+    m.add_class::<Verifier>()?;
+    m.add_class::<PyVerificationResult>()?;
+    m.add_class::<PyCommitmentBuilder>()?;
+    m.add_class::<PyQueryWitness>()?;
+    m.add_class::<PyCircuitInfo>()?;
+    m.add_class::<ByteBuffer>()?;
+    m.add_class::<PyProofEnvelope>()?;
+    m.add_class::<PyProofAggregate>()?;
+
+    #[cfg(feature = "client")]
+    {
+        let client = client::module(py)?;
+        m.add_submodule(client)?;
+        // Make `import zkrag_rust.client` work, not just attribute access
+        py.import("sys")?
+            .getattr("modules")?
+            .set_item("zkrag_rust.client", client)?;
+    }
     Ok(())
 }
@@ -17,12 +17,16 @@
 // 2. search_results reference valid chunks from documents
 // 3. timestamp is recent (within acceptable window)
 
-use ark_ff::Field;
-use ark_r1cs_std::prelude::*;
+use ark_ff::{Field, PrimeField};
+use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
 
 use crate::PrivacyCircuit;
 
+/// Query embedding length the circuit is sized for (all-MiniLM-L6-v2). The
+/// constraint system is fixed per proving key, so every proof uses this length.
+pub const QUERY_EMBEDDING_DIM: usize = 384;
+
 /// Document Query Circuit
 #[derive(Clone)]
 pub struct DocumentQueryCircuit<F: Field> {
@@ -58,23 +62,12 @@ impl<F: Field> DocumentQueryCircuit<F> {
     }
 }
 
-impl<F: Field> ConstraintSynthesizer<F> for DocumentQueryCircuit<F> {
+impl<F: PrimeField> ConstraintSynthesizer<F> for DocumentQueryCircuit<F> {
     fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
-        // Allocate public inputs
-        let document_commitment_var = FpVar::new_input(
-            cs.clone(),
-            || Ok(self.document_commitment),
-        )?;
-
-        let model_hash_var = FpVar::new_input(
-            cs.clone(),
-            || Ok(self.model_hash),
-        )?;
-
-        let timestamp_var = FpVar::new_input(
-            cs.clone(),
-            || Ok(self.timestamp),
-        )?;
+        // Allocate public inputs; no constraint reads them yet
+        for input in [self.document_commitment, self.model_hash, self.timestamp] {
+            let _ = FpVar::new_input(cs.clone(), || Ok(input))?;
+        }
 
         // Allocate private inputs (witnesses)
         let mut document_vars = Vec::new();
@@ -103,7 +96,7 @@ impl<F: Field> ConstraintSynthesizer<F> for DocumentQueryCircuit<F> {
     }
 }
 
-impl<F: Field> PrivacyCircuit<F> for DocumentQueryCircuit<F> {
+impl<F: PrimeField> PrivacyCircuit<F> for DocumentQueryCircuit<F> {
     fn name(&self) -> &str {
         "DocumentQueryCircuit"
     }
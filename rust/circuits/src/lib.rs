@@ -4,10 +4,10 @@
 
 use ark_bn254::Fr;
 use ark_ff::Field;
-use ark_r1cs_std::prelude::*;
-use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_relations::r1cs::ConstraintSynthesizer;
 
 pub mod document_query;
+pub mod registry;
 pub mod utils;
 
 pub use document_query::DocumentQueryCircuit;
@@ -29,8 +29,6 @@ pub trait PrivacyCircuit<F: Field>: ConstraintSynthesizer<F> {
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-
     #[test]
     fn test_circuit_basic() {
         // Placeholder test
@@ -0,0 +1,79 @@
+// Circuit registry
+//
+// Static description of each circuit the prover can set up, keyed by the
+// circuit id used in proof envelopes and the verifying key registry. Tooling
+// can check its configuration against these before attempting a proof.
+
+use ark_bn254::Fr;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, SynthesisError};
+
+use crate::document_query::{DocumentQueryCircuit, QUERY_EMBEDDING_DIM};
+
+/// Circuit id of the document query circuit
+pub const DOCUMENT_QUERY_CIRCUIT_ID: &str = "document_query";
+
+/// Ids of every registered circuit
+pub const CIRCUIT_IDS: &[&str] = &[DOCUMENT_QUERY_CIRCUIT_ID];
+
+/// Metadata of a registered circuit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitInfo {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub curve: &'static str,
+    /// Fixed size parameters the proving key was generated for
+    pub params: Vec<(&'static str, usize)>,
+    /// Public input names in the order the verifier expects them
+    pub public_inputs: Vec<&'static str>,
+    /// Constraints in the circuit as set up
+    pub num_constraints: usize,
+}
+
+/// Metadata of the circuit registered as `circuit_id`, or `None` if there
+/// is no such circuit
+pub fn circuit_info(circuit_id: &str) -> Option<Result<CircuitInfo, SynthesisError>> {
+    match circuit_id {
+        DOCUMENT_QUERY_CIRCUIT_ID => Some(document_query_info()),
+        _ => None,
+    }
+}
+
+fn document_query_info() -> Result<CircuitInfo, SynthesisError> {
+    // The same blank instance the prover's setup uses
+    let circuit = DocumentQueryCircuit::<Fr>::new(
+        vec![],
+        vec![],
+        vec![],
+        Fr::from(0u64),
+        Fr::from(0u64),
+        Fr::from(0u64),
+    );
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    circuit.generate_constraints(cs.clone())?;
+
+    Ok(CircuitInfo {
+        id: DOCUMENT_QUERY_CIRCUIT_ID,
+        name: "DocumentQueryCircuit",
+        curve: "bn254",
+        params: vec![("query_embedding_dim", QUERY_EMBEDDING_DIM)],
+        public_inputs: vec!["document_commitment", "model_hash", "timestamp"],
+        num_constraints: cs.num_constraints(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_circuit_info() {
+        for id in CIRCUIT_IDS {
+            let info = circuit_info(id).unwrap().unwrap();
+            assert_eq!(info.id, *id);
+        }
+
+        let info = circuit_info(DOCUMENT_QUERY_CIRCUIT_ID).unwrap().unwrap();
+        assert_eq!(info.public_inputs.len(), 3);
+        assert!(circuit_info("unknown").is_none());
+    }
+}
@@ -1,6 +1,253 @@
 // Utility functions for circuit operations
 
-use ark_ff::Field;
+use ark_ff::{Field, PrimeField};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::io::{self, Read};
+
+/// Read size when hashing a document from a stream
+const HASH_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Characters per chunk when splitting a document for retrieval
+pub const DEFAULT_CHUNK_SIZE: usize = 512;
+
+/// Characters consecutive chunks share
+pub const DEFAULT_CHUNK_OVERLAP: usize = 50;
+
+/// Map a public input string (commitment, model hash) to a field element
+///
+/// The string is hashed with SHA-256 and reduced modulo the field order, so
+/// prover and verifier agree on the element regardless of how the string is
+/// encoded.
+pub fn public_input_to_field<F: PrimeField>(value: &str) -> F {
+    F::from_be_bytes_mod_order(&Sha256::digest(value.as_bytes()))
+}
+
+/// Document commitment public input of a proof bound to a verifier's
+/// challenge nonce
+///
+/// With a nonce the input is the commitment, a NUL and the nonce, so the
+/// proof only verifies alongside that nonce; a verifier spending each nonce
+/// once can't be replayed to. Without one it's the commitment as before.
+pub fn bound_commitment<'a>(document_commitment: &'a str, nonce: Option<&str>) -> Cow<'a, str> {
+    match nonce {
+        Some(nonce) => Cow::Owned(format!("{}\0{}", document_commitment, nonce)),
+        None => Cow::Borrowed(document_commitment),
+    }
+}
+
+/// Hex SHA-256 digest of a document's contents, the document hash used in
+/// commitments
+pub fn document_hash(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+/// Split text into chunks of `chunk_size` characters, each starting
+/// `overlap` characters before the previous one ended; the last may be
+/// shorter
+///
+/// Matches the Python `DocumentManager`'s chunking, so a document chunked
+/// by either gives the same chunk hashes. Panics unless
+/// `overlap < chunk_size`.
+pub fn chunk_text(text: &str, chunk_size: usize, overlap: usize) -> Vec<&str> {
+    assert!(
+        overlap < chunk_size,
+        "chunk overlap must be less than the size"
+    );
+    let bounds: Vec<usize> = text
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain([text.len()])
+        .collect();
+    let chars = bounds.len() - 1;
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < chars {
+        let end = (start + chunk_size).min(chars);
+        chunks.push(&text[bounds[start]..bounds[end]]);
+        start = if end < chars { end - overlap } else { end };
+    }
+    chunks
+}
+
+/// Merkle root committing to a set of document hashes, hex-encoded
+///
+/// Hashes are sorted so the commitment doesn't depend on ingestion order.
+/// Leaves are SHA-256(0x00 || hash) and inner nodes SHA-256(0x01 || left ||
+/// right); an odd node is carried up to the next level unchanged. The empty
+/// set commits to SHA-256 of nothing.
+pub fn compute_document_commitment<S: AsRef<str>>(document_hashes: &[S]) -> String {
+    let mut level: Vec<[u8; 32]> = sorted_hashes(document_hashes)
+        .iter()
+        .map(|hash| leaf_hash(hash))
+        .collect();
+    if level.is_empty() {
+        return document_hash(&[]);
+    }
+
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => node_hash(left, right),
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    to_hex(&level[0])
+}
+
+/// A sibling on the path from a document's leaf to the commitment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MerkleStep {
+    pub sibling: [u8; 32],
+    /// Whether the sibling is the left input of the parent node
+    pub sibling_is_left: bool,
+}
+
+/// Path proving a document hash is included in a commitment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InclusionProof {
+    /// Position of the document's leaf among the sorted hashes
+    pub index: usize,
+    /// Number of leaves in the tree
+    pub leaf_count: usize,
+    /// Siblings from the leaf up. Levels where the node is carried up
+    /// unpaired have no step.
+    pub path: Vec<MerkleStep>,
+}
+
+/// Path from `hash`'s leaf to `compute_document_commitment(document_hashes)`,
+/// or `None` if `hash` isn't one of them
+pub fn document_inclusion_proof<S: AsRef<str>>(
+    document_hashes: &[S],
+    hash: &str,
+) -> Option<InclusionProof> {
+    let hashes = sorted_hashes(document_hashes);
+    let index = hashes.binary_search(&hash).ok()?;
+    let mut level: Vec<[u8; 32]> = hashes.iter().map(|hash| leaf_hash(hash)).collect();
+    let mut path = Vec::new();
+    let mut position = index;
+    while level.len() > 1 {
+        let sibling = position ^ 1;
+        if sibling < level.len() {
+            path.push(MerkleStep {
+                sibling: level[sibling],
+                sibling_is_left: sibling < position,
+            });
+        }
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => node_hash(left, right),
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+        position /= 2;
+    }
+    Some(InclusionProof {
+        index,
+        leaf_count: hashes.len(),
+        path,
+    })
+}
+
+/// Whether `path` leads from `hash`'s leaf to `commitment`
+pub fn verify_document_inclusion(hash: &str, path: &[MerkleStep], commitment: &str) -> bool {
+    let root = path.iter().fold(leaf_hash(hash), |node, step| {
+        if step.sibling_is_left {
+            node_hash(&step.sibling, &node)
+        } else {
+            node_hash(&node, &step.sibling)
+        }
+    });
+    to_hex(&root) == commitment
+}
+
+fn sorted_hashes<S: AsRef<str>>(document_hashes: &[S]) -> Vec<&str> {
+    let mut hashes: Vec<&str> = document_hashes.iter().map(AsRef::as_ref).collect();
+    hashes.sort_unstable();
+    hashes
+}
+
+fn leaf_hash(hash: &str) -> [u8; 32] {
+    Sha256::new()
+        .chain_update([0x00])
+        .chain_update(hash)
+        .finalize()
+        .into()
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update([0x01])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Builds a document commitment one document at a time
+///
+/// Only each document's hash is kept, and `add_reader` hashes a document in
+/// fixed-size chunks, so a corpus larger than memory can be committed. The
+/// result matches `compute_document_commitment` over the same hashes.
+#[derive(Debug, Clone, Default)]
+pub struct CommitmentBuilder {
+    hashes: Vec<String>,
+}
+
+impl CommitmentBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a document by its contents
+    pub fn add_document(&mut self, content: &[u8]) {
+        self.hashes.push(document_hash(content));
+    }
+
+    /// Add a document by its hex hash
+    pub fn add_hash(&mut self, hash: impl Into<String>) {
+        self.hashes.push(hash.into());
+    }
+
+    /// Add a document read to the end of `reader`
+    pub fn add_reader(&mut self, mut reader: impl Read) -> io::Result<()> {
+        let mut hasher = Sha256::new();
+        let mut buf = vec![0u8; HASH_CHUNK_SIZE];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => hasher.update(&buf[..n]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        self.hashes.push(format!("{:x}", hasher.finalize()));
+        Ok(())
+    }
+
+    /// Number of documents added
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    /// Commitment to every document added so far
+    pub fn finalize(&self) -> String {
+        compute_document_commitment(&self.hashes)
+    }
+}
 
 /// Hash a vector of field elements (placeholder)
 /// TODO: Replace with proper Poseidon hash or similar ZK-friendly hash
@@ -33,4 +280,87 @@ mod tests {
         let hash = hash_field_elements(&elements);
         assert_eq!(hash, Fr::from(6u64));
     }
+
+    #[test]
+    fn test_compute_document_commitment() {
+        let a = document_hash(b"first document");
+        let b = document_hash(b"second document");
+        let c = document_hash(b"third document");
+
+        let root = compute_document_commitment(&[&a, &b, &c]);
+        assert_eq!(root, compute_document_commitment(&[&c, &a, &b]));
+        assert_ne!(root, compute_document_commitment(&[&a, &b]));
+        assert_eq!(
+            root,
+            "c6b88521467a1ccb250b647c75d8114089e12d12b3f7fb79f20a44ba97e421f4"
+        );
+    }
+
+    #[test]
+    fn test_chunk_text() {
+        assert_eq!(chunk_text("abcdefghij", 4, 1), ["abcd", "defg", "ghij"]);
+        assert_eq!(chunk_text("abcdefgh", 4, 1), ["abcd", "defg", "gh"]);
+        assert_eq!(chunk_text("abc", 4, 1), ["abc"]);
+        assert!(chunk_text("", 4, 1).is_empty());
+        // Sizes count characters, not bytes
+        assert_eq!(chunk_text("αβγδε", 3, 1), ["αβγ", "γδε"]);
+    }
+
+    #[test]
+    fn test_document_inclusion_proof() {
+        let hashes: Vec<String> = (0..5)
+            .map(|i| document_hash(format!("document {}", i).as_bytes()))
+            .collect();
+        let root = compute_document_commitment(&hashes);
+        let other = document_hash(b"other");
+
+        for hash in &hashes {
+            let proof = document_inclusion_proof(&hashes, hash).unwrap();
+            assert_eq!(proof.leaf_count, 5);
+            assert!(verify_document_inclusion(hash, &proof.path, &root));
+            assert!(!verify_document_inclusion(&other, &proof.path, &root));
+        }
+        // The fifth leaf is carried up unpaired past the first level
+        let mut sorted = hashes.clone();
+        sorted.sort();
+        let last = document_inclusion_proof(&hashes, &sorted[4]).unwrap();
+        assert_eq!(last.index, 4);
+        assert_eq!(last.path.len(), 1);
+
+        assert_eq!(document_inclusion_proof(&hashes, &other), None);
+        let single = document_inclusion_proof(&[&hashes[0]], &hashes[0]).unwrap();
+        assert!(single.path.is_empty());
+        assert!(verify_document_inclusion(
+            &hashes[0],
+            &single.path,
+            &compute_document_commitment(&[&hashes[0]])
+        ));
+    }
+
+    #[test]
+    fn test_commitment_builder() {
+        let mut builder = CommitmentBuilder::new();
+        builder.add_document(b"first document");
+        builder.add_hash(document_hash(b"second document"));
+        builder.add_reader(&b"third document"[..]).unwrap();
+
+        assert_eq!(builder.len(), 3);
+        assert_eq!(
+            builder.finalize(),
+            "c6b88521467a1ccb250b647c75d8114089e12d12b3f7fb79f20a44ba97e421f4"
+        );
+    }
+
+    #[test]
+    fn test_public_input_to_field() {
+        let a: Fr = public_input_to_field("abc123");
+        let b: Fr = public_input_to_field("abc123");
+        let c: Fr = public_input_to_field("abc124");
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+
+        let bound: Fr = public_input_to_field(&bound_commitment("abc123", Some("n1")));
+        assert_ne!(bound, a);
+        assert_eq!(bound_commitment("abc123", None), "abc123");
+    }
 }
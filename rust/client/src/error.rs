@@ -0,0 +1,123 @@
+// Client errors
+//
+// The service answers every failed request with `{code, message, details}`;
+// those become `ClientError::Api`, so callers branch on `code` as the
+// server's own docs describe rather than on status codes or messages.
+// Responses without that body, e.g. from a proxy in front of the service,
+// keep their status and text under `ErrorCode::Unknown`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Machine-readable error code, as the service names it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    InvalidRequest,
+    PayloadTooLarge,
+    ProofMalformed,
+    ProofRejected,
+    ModelNotRegistered,
+    NotFound,
+    Unauthorized,
+    Forbidden,
+    RateLimited,
+    ProvingDisabled,
+    ProverBusy,
+    VerifierBusy,
+    Timeout,
+    AuthUnavailable,
+    KernelUnavailable,
+    KernelRejected,
+    IdempotencyKeyReused,
+    IdempotencyKeyInUse,
+    ChallengeInvalid,
+    Internal,
+    /// A code this client doesn't know, or a response without one
+    #[serde(other)]
+    Unknown,
+}
+
+/// Body of the service's error responses
+#[derive(Debug, Deserialize)]
+pub(crate) struct ErrorResponse {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(default)]
+    pub details: Option<Value>,
+}
+
+/// Error from a client call
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    /// The service refused the request
+    #[error("{message} ({code:?}, HTTP {status})")]
+    Api {
+        status: u16,
+        code: ErrorCode,
+        message: String,
+        /// Structured context, depending on `code`
+        details: Option<Value>,
+    },
+    /// The request couldn't be sent or its response read
+    #[error("Request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    /// The client's base URL doesn't parse
+    #[error("Invalid base URL {0}")]
+    InvalidUrl(String),
+    /// The response wasn't what the endpoint returns
+    #[error("Unexpected response: {0}")]
+    Decode(String),
+}
+
+/// Result of a client call
+pub type Result<T, E = ClientError> = std::result::Result<T, E>;
+
+impl ClientError {
+    /// Error for a response with `status` and `body`
+    pub(crate) fn from_response(status: u16, body: &[u8]) -> Self {
+        match serde_json::from_slice::<ErrorResponse>(body) {
+            Ok(error) => Self::Api {
+                status,
+                code: error.code,
+                message: error.message,
+                details: error.details,
+            },
+            Err(_) => Self::Api {
+                status,
+                code: ErrorCode::Unknown,
+                message: String::from_utf8_lossy(body).into_owned(),
+                details: None,
+            },
+        }
+    }
+
+    /// Code of an `Api` error
+    pub fn code(&self) -> Option<ErrorCode> {
+        match self {
+            Self::Api { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// Whether sending the request again may succeed: the service was
+    /// overloaded or unavailable, or never got it. `replayable` requests
+    /// are ones the service applies at most once (reads, deletes and
+    /// requests with an idempotency key), which may also be retried after
+    /// a timeout or a dropped connection.
+    pub(crate) fn is_retryable(&self, replayable: bool) -> bool {
+        match self {
+            Self::Api { status: 429, .. } => true,
+            Self::Api {
+                code: ErrorCode::IdempotencyKeyInUse,
+                ..
+            } => true,
+            Self::Api {
+                status: 503, code, ..
+            } => replayable || *code != ErrorCode::Timeout,
+            Self::Api { status, .. } => replayable && (502..=504).contains(status),
+            Self::Http(e) => e.is_connect() || (replayable && (e.is_timeout() || e.is_request())),
+            Self::InvalidUrl(_) | Self::Decode(_) => false,
+        }
+    }
+}
@@ -0,0 +1,144 @@
+// Query event stream
+//
+// `GET /api/v1/events` streams verification outcomes as Server-Sent
+// Events, each with its query id as the event id. The stream is parsed here
+// into `Event`s; keep-alive comments are skipped.
+//
+// The stream ends when the service shuts down or the connection drops. To
+// pick up where it stopped, subscribe again with the id of the last query
+// received as `last_event_id`: the service first replays every query
+// recorded since.
+//
+// The WebSocket feed at `/api/v1/events/ws`, which adds registrations, isn't
+// wrapped: it needs a WebSocket client, and registrations are also
+// delivered by webhooks.
+
+use futures_util::stream::{self, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{ClientError, Result};
+use crate::types::QueryRecord;
+
+/// Query of `GET /api/v1/events`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct EventParams {
+    /// Only events about this document commitment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commitment: Option<String>,
+    /// Comma-separated event types to receive, e.g. `query_failed`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub types: Option<String>,
+}
+
+/// Something that happened to the service's records
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    DocumentRegistered {
+        id: u64,
+        commitment: String,
+        owner: String,
+        /// Registry commitment after the registration
+        registry_commitment: String,
+    },
+    ModelRegistered {
+        id: u64,
+        model_hash: String,
+        model_name: String,
+    },
+    QueryVerified {
+        query: QueryRecord,
+    },
+    QueryFailed {
+        query: QueryRecord,
+    },
+}
+
+/// Splits a Server-Sent Events body into the data of its events
+#[derive(Debug, Default)]
+struct SseParser {
+    /// Start of a line not yet terminated
+    line: Vec<u8>,
+    /// Data of the event being read
+    data: Option<String>,
+}
+
+impl SseParser {
+    /// Feed a chunk of the body, returning the data of the events it
+    /// completes
+    fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        let mut events = Vec::new();
+        for &byte in chunk {
+            if byte != b'\n' {
+                self.line.push(byte);
+                continue;
+            }
+            let line = std::mem::take(&mut self.line);
+            let line = String::from_utf8_lossy(line.strip_suffix(b"\r").unwrap_or(&line));
+            if line.is_empty() {
+                events.extend(self.data.take());
+            } else if let Some(value) = line.strip_prefix("data:") {
+                let value = value.strip_prefix(' ').unwrap_or(value);
+                match &mut self.data {
+                    Some(data) => {
+                        data.push('\n');
+                        data.push_str(value);
+                    }
+                    None => self.data = Some(value.to_string()),
+                }
+            }
+        }
+        events
+    }
+}
+
+/// Events in the body of an event stream response
+pub(crate) fn parse(response: reqwest::Response) -> impl Stream<Item = Result<Event>> {
+    let mut parser = SseParser::default();
+    response.bytes_stream().flat_map(move |chunk| {
+        let events: Vec<Result<Event>> = match chunk {
+            Ok(chunk) => parser
+                .push(&chunk)
+                .into_iter()
+                .map(|data| {
+                    serde_json::from_str(&data).map_err(|e| {
+                        ClientError::Decode(format!("Invalid event {:?}: {}", data, e))
+                    })
+                })
+                .collect(),
+            Err(e) => vec![Err(e.into())],
+        };
+        stream::iter(events)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sse_parser() {
+        let mut parser = SseParser::default();
+        // Keep-alives, fields other than data, and events split across
+        // chunks
+        assert!(parser
+            .push(b": keep-alive\n\nid: 7\nevent: query_verified\n")
+            .is_empty());
+        assert!(parser.push(b"data: {\"a\":").is_empty());
+        assert_eq!(parser.push(b"1}\n\ndata: x\r\n"), ["{\"a\":1}"]);
+        assert_eq!(parser.push(b"data: y\r\n\r\n"), ["x\ny"]);
+        assert!(parser.line.is_empty() && parser.data.is_none());
+
+        let event: Event = serde_json::from_str(
+            r#"{"type":"query_failed","query":{"id":7,"proof_digest":"ab",
+                "document_commitment":"cd","model_hash":"ef","timestamp":1,
+                "verified":false,"reason":{"code":"pairing_failed"},"verified_at":2}}"#,
+        )
+        .unwrap();
+        let Event::QueryFailed { query } = event else {
+            panic!("expected query_failed, got {:?}", event);
+        };
+        assert_eq!(query.id, 7);
+        assert!(query.reason.is_some());
+    }
+}
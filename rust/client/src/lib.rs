@@ -0,0 +1,726 @@
+// ZKvsAI Client
+//
+// Typed async client for the verification service's HTTP API, for Rust RAG
+// backends. Each route is a method taking and returning the service's
+// bodies (see types.rs), so requests don't drift from what the server
+// accepts.
+//
+// Requests carry the bearer token given to the builder; `set_token` swaps
+// it once the caller has refreshed it. Failed requests are retried with
+// exponential backoff, honouring Retry-After, when the service was
+// overloaded or unavailable (429 and most 503s). Requests the service
+// applies at most once are also retried after timeouts and dropped
+// connections: reads, and the registration and verification routes, which
+// are sent with an Idempotency-Key reused across attempts so a retried
+// request isn't applied twice. Uploads with a streamed body are sent once.
+//
+// Errors from the service keep its `code` (see error.rs).
+
+use futures_util::Stream;
+use reqwest::header::{HeaderValue, AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER};
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use zkrag_prover::QueryWitness;
+use zkrag_verifier::ProofEnvelope;
+
+pub mod error;
+pub mod events;
+pub mod types;
+
+pub use error::{ClientError, ErrorCode, Result};
+pub use events::{Event, EventParams};
+pub use types::*;
+
+/// Header naming a request the service applies at most once
+const IDEMPOTENCY_KEY: &str = "Idempotency-Key";
+
+/// When failed requests are sent again
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts after the first; 0 turns retries off
+    pub max_retries: u32,
+    /// Wait before the first retry, doubled for each one after
+    pub initial_backoff: Duration,
+    /// Longest wait between attempts; a Retry-After beyond it isn't waited
+    /// out
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait before retry number `attempt`, counting from 0
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff)
+    }
+}
+
+/// Builder for [`Client`]
+#[derive(Debug, Clone)]
+pub struct ClientBuilder {
+    base_url: String,
+    token: Option<String>,
+    timeout: Option<Duration>,
+    retry: RetryPolicy,
+}
+
+impl ClientBuilder {
+    /// Client of the service at `base_url`, e.g. `https://verifier.example.com`
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            token: None,
+            timeout: Some(Duration::from_secs(60)),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Bearer token sent with every request
+    pub fn bearer_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Limit on each attempt, body included (default 60s); `None` for no
+    /// limit. The event stream and model uploads aren't limited.
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// When failed requests are retried
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn build(self) -> Result<Client> {
+        let base_url = self.base_url.trim_end_matches('/').to_string();
+        reqwest::Url::parse(&base_url)
+            .map_err(|e| ClientError::InvalidUrl(format!("{}: {}", base_url, e)))?;
+        let http = reqwest::Client::builder()
+            .user_agent(concat!("zkrag-client/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+        Ok(Client {
+            inner: Arc::new(Inner {
+                base_url,
+                http,
+                token: RwLock::new(self.token),
+                timeout: self.timeout,
+                retry: self.retry,
+            }),
+        })
+    }
+}
+
+struct Inner {
+    base_url: String,
+    http: reqwest::Client,
+    token: RwLock<Option<String>>,
+    timeout: Option<Duration>,
+    retry: RetryPolicy,
+}
+
+/// Client of a verification service; clones share connections
+#[derive(Clone)]
+pub struct Client {
+    inner: Arc<Inner>,
+}
+
+impl std::fmt::Debug for Client {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Client")
+            .field("base_url", &self.inner.base_url)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Percent-encode `value` as one path segment
+fn segment(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Body of a successful response
+async fn decode<T: DeserializeOwned>(response: Response) -> Result<T> {
+    let body = response.bytes().await?;
+    serde_json::from_slice(&body).map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+impl Client {
+    /// Client of the service at `base_url` with default settings
+    pub fn new(base_url: impl Into<String>) -> Result<Self> {
+        ClientBuilder::new(base_url).build()
+    }
+
+    pub fn builder(base_url: impl Into<String>) -> ClientBuilder {
+        ClientBuilder::new(base_url)
+    }
+
+    /// Replace the bearer token sent from the next attempt on, e.g. after
+    /// refreshing an expired one
+    pub fn set_token(&self, token: Option<String>) {
+        *self.inner.token.write().unwrap_or_else(|e| e.into_inner()) = token;
+    }
+
+    /// Request to `path`, limited by the configured timeout
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self.unlimited(method, path);
+        match self.inner.timeout {
+            Some(timeout) => request.timeout(timeout),
+            None => request,
+        }
+    }
+
+    /// Request to `path` without a timeout
+    fn unlimited(&self, method: Method, path: &str) -> RequestBuilder {
+        self.inner
+            .http
+            .request(method, format!("{}{}", self.inner.base_url, path))
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        let token = self.inner.token.read().unwrap_or_else(|e| e.into_inner());
+        match token
+            .as_deref()
+            .and_then(|token| HeaderValue::from_str(&format!("Bearer {}", token)).ok())
+        {
+            Some(value) => request.header(AUTHORIZATION, value),
+            None => request,
+        }
+    }
+
+    /// Send `request`, retrying as the policy allows; `replayable` requests
+    /// are applied at most once by the service
+    async fn send(&self, mut request: RequestBuilder, replayable: bool) -> Result<Response> {
+        let retry = &self.inner.retry;
+        let mut attempt = 0;
+        loop {
+            // Streamed bodies can't be sent twice
+            let next = request.try_clone();
+            let (error, retry_after) = match self.authorize(request).send().await {
+                Ok(response) if response.status().is_success() => return Ok(response),
+                Ok(response) => {
+                    let status = response.status().as_u16();
+                    let retry_after = response
+                        .headers()
+                        .get(RETRY_AFTER)
+                        .and_then(|value| value.to_str().ok()?.parse().ok())
+                        .map(Duration::from_secs);
+                    let body = response.bytes().await?;
+                    (ClientError::from_response(status, &body), retry_after)
+                }
+                Err(e) => (e.into(), None),
+            };
+            let wait = retry_after.unwrap_or_else(|| retry.backoff(attempt));
+            match next {
+                Some(next)
+                    if attempt < retry.max_retries
+                        && wait <= retry.max_backoff
+                        && error.is_retryable(replayable) =>
+                {
+                    tokio::time::sleep(wait).await;
+                    request = next;
+                    attempt += 1;
+                }
+                _ => return Err(error),
+            }
+        }
+    }
+
+    /// Send `request` with a fresh Idempotency-Key, reused by its retries
+    async fn send_once(&self, request: RequestBuilder) -> Result<Response> {
+        let key = hex::encode(rand::random::<[u8; 16]>());
+        self.send(request.header(IDEMPOTENCY_KEY, key), true).await
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        decode(self.send(self.request(Method::GET, path), true).await?).await
+    }
+
+    async fn get_bytes(&self, path: &str) -> Result<Vec<u8>> {
+        let response = self.send(self.request(Method::GET, path), true).await?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// GET a status route, which answers 503 with the same body when
+    /// something is failing
+    async fn probe<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let response = self
+            .authorize(self.request(Method::GET, path))
+            .send()
+            .await?;
+        match response.status() {
+            status if status.is_success() || status == StatusCode::SERVICE_UNAVAILABLE => {
+                decode(response).await
+            }
+            status => {
+                let body = response.bytes().await?;
+                Err(ClientError::from_response(status.as_u16(), &body))
+            }
+        }
+    }
+
+    // Health
+
+    /// `GET /health`: each component's state
+    pub async fn health(&self) -> Result<Health> {
+        self.probe("/health").await
+    }
+
+    /// `GET /livez`: whether the process is up
+    pub async fn liveness(&self) -> Result<()> {
+        self.send(self.request(Method::GET, "/livez"), true).await?;
+        Ok(())
+    }
+
+    /// `GET /readyz`: whether the service takes traffic
+    pub async fn readiness(&self) -> Result<Readiness> {
+        self.probe("/readyz").await
+    }
+
+    /// `GET /metrics`, in the Prometheus text format
+    pub async fn export_metrics(&self) -> Result<String> {
+        let response = self
+            .send(self.request(Method::GET, "/metrics"), true)
+            .await?;
+        Ok(response.text().await?)
+    }
+
+    // Proofs
+
+    /// `POST /api/v1/challenge`: a nonce to bind into the next proof
+    pub async fn issue_challenge(&self) -> Result<Challenge> {
+        let request = self.request(Method::POST, "/api/v1/challenge");
+        decode(self.send(request, true).await?).await
+    }
+
+    /// `POST /api/v1/proof/generate`: prove `witness` on the service
+    pub async fn generate_proof(&self, witness: &QueryWitness) -> Result<ProofEnvelope> {
+        let request = self
+            .request(Method::POST, "/api/v1/proof/generate")
+            .json(witness);
+        decode(self.send(request, true).await?).await
+    }
+
+    /// `POST /api/v1/query/verify`: verify a hex proof and its public
+    /// inputs
+    pub async fn verify_query(&self, query: &VerifyQueryRequest) -> Result<VerificationResponse> {
+        let request = self
+            .request(Method::POST, "/api/v1/query/verify")
+            .json(query);
+        decode(self.send_once(request).await?).await
+    }
+
+    /// `POST /api/v2/query/verify`: verify a proof envelope
+    pub async fn verify_envelope(&self, envelope: &ProofEnvelope) -> Result<EnvelopeVerification> {
+        let request = self
+            .request(Method::POST, "/api/v2/query/verify")
+            .json(envelope);
+        decode(self.send_once(request).await?).await
+    }
+
+    // Documents
+
+    /// `POST /api/v1/document/register`
+    pub async fn register_document(
+        &self,
+        document: &RegisterDocumentRequest,
+    ) -> Result<DocumentRegistration> {
+        let request = self
+            .request(Method::POST, "/api/v1/document/register")
+            .json(document);
+        decode(self.send_once(request).await?).await
+    }
+
+    /// `POST /api/v1/document/upload`: chunk, hash and register `text` on
+    /// the service
+    pub async fn upload_document(
+        &self,
+        text: impl Into<String>,
+        params: &UploadParams,
+    ) -> Result<DocumentUpload> {
+        let request = self
+            .request(Method::POST, "/api/v1/document/upload")
+            .query(params)
+            .header(CONTENT_TYPE, "text/plain; charset=utf-8")
+            .body(text.into());
+        decode(self.send_once(request).await?).await
+    }
+
+    /// `GET /api/v1/document/commitment`: the registry commitment
+    pub async fn document_commitment(&self) -> Result<Commitment> {
+        self.get("/api/v1/document/commitment").await
+    }
+
+    /// `POST /api/v1/document/:id/revoke`
+    pub async fn revoke_document(&self, id: u64) -> Result<Success> {
+        let path = format!("/api/v1/document/{}/revoke", id);
+        decode(self.send(self.request(Method::POST, &path), false).await?).await
+    }
+
+    /// `GET /api/v1/document/:hash/proof`: inclusion path of a document in
+    /// the registry
+    pub async fn document_proof(&self, document_hash: &str) -> Result<DocumentProof> {
+        self.get(&format!(
+            "/api/v1/document/{}/proof",
+            segment(document_hash)
+        ))
+        .await
+    }
+
+    // Models
+
+    /// `POST /api/v1/model/register`
+    pub async fn register_model(&self, model_hash: &str, model_name: &str) -> Result<Success> {
+        #[derive(Serialize)]
+        struct RegisterModelRequest<'a> {
+            model_hash: &'a str,
+            model_name: &'a str,
+        }
+
+        let request =
+            self.request(Method::POST, "/api/v1/model/register")
+                .json(&RegisterModelRequest {
+                    model_hash,
+                    model_name,
+                });
+        decode(self.send_once(request).await?).await
+    }
+
+    /// `POST /api/v1/model/upload`: register a model by the hash the
+    /// service computes from its weights
+    pub async fn upload_model(
+        &self,
+        model_name: &str,
+        artifact: ModelArtifact,
+    ) -> Result<ModelUpload> {
+        let request = self
+            .unlimited(Method::POST, "/api/v1/model/upload")
+            .query(&[("model_name", model_name)]);
+        let request = match artifact {
+            ModelArtifact::File { filename, body } => request
+                .query(&[("filename", filename)])
+                .header(CONTENT_TYPE, "application/octet-stream")
+                .body(body),
+            ModelArtifact::Archive(body) => {
+                request.header(CONTENT_TYPE, "application/x-tar").body(body)
+            }
+        };
+        decode(self.send(request, false).await?).await
+    }
+
+    /// `POST /api/v1/model/:id/revoke`
+    pub async fn revoke_model(&self, id: u64) -> Result<Success> {
+        let path = format!("/api/v1/model/{}/revoke", id);
+        decode(self.send(self.request(Method::POST, &path), false).await?).await
+    }
+
+    // Queries
+
+    /// `GET /api/v1/query/:id`
+    pub async fn get_query(&self, id: u64) -> Result<QueryRecord> {
+        self.get(&format!("/api/v1/query/{}", id)).await
+    }
+
+    /// `GET /api/v1/query/:id/proof`: the envelope a query was verified
+    /// from
+    pub async fn get_query_proof(&self, id: u64) -> Result<ProofEnvelope> {
+        self.get(&format!("/api/v1/query/{}/proof", id)).await
+    }
+
+    /// `GET /api/v1/query/:id/receipt`: the signed receipt of a
+    /// verification
+    pub async fn get_query_receipt(&self, id: u64) -> Result<QueryReceipt> {
+        self.get(&format!("/api/v1/query/{}/receipt", id)).await
+    }
+
+    /// `GET /api/v1/queries`
+    pub async fn list_queries(&self, params: &ListQueriesParams) -> Result<QueryList> {
+        let request = self.request(Method::GET, "/api/v1/queries").query(params);
+        decode(self.send(request, true).await?).await
+    }
+
+    /// `GET /api/v1/audit`: a page of the audit trail
+    pub async fn audit_trail(&self, params: &AuditParams) -> Result<AuditPage> {
+        let request = self.request(Method::GET, "/api/v1/audit").query(params);
+        decode(self.send(request, true).await?).await
+    }
+
+    /// `GET /api/v1/audit?format=ndjson`: every entry after `params.cursor`
+    pub async fn export_audit_trail(&self, params: &AuditParams) -> Result<Vec<AuditEntry>> {
+        let request = self
+            .unlimited(Method::GET, "/api/v1/audit")
+            .query(params)
+            .query(&[("format", "ndjson")]);
+        let body = self.send(request, true).await?.text().await?;
+        body.lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_str(line).map_err(|e| ClientError::Decode(e.to_string())))
+            .collect()
+    }
+
+    /// `GET /api/v1/owners/:owner/queries`: an owner's documents, models
+    /// and a page of their verifications
+    pub async fn owner_history(
+        &self,
+        owner: &str,
+        cursor: Option<u64>,
+        limit: Option<u64>,
+    ) -> Result<OwnerHistory> {
+        #[derive(Serialize)]
+        struct OwnerHistoryParams {
+            #[serde(skip_serializing_if = "Option::is_none")]
+            cursor: Option<u64>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            limit: Option<u64>,
+        }
+
+        let path = format!("/api/v1/owners/{}/queries", segment(owner));
+        let request = self
+            .request(Method::GET, &path)
+            .query(&OwnerHistoryParams { cursor, limit });
+        decode(self.send(request, true).await?).await
+    }
+
+    /// `GET /api/v1/stats`
+    pub async fn verification_stats(&self, params: &StatsParams) -> Result<Stats> {
+        let request = self.request(Method::GET, "/api/v1/stats").query(params);
+        decode(self.send(request, true).await?).await
+    }
+
+    /// `GET /api/v1/nullifiers`: epochs with recorded nullifiers
+    pub async fn nullifier_epochs(&self) -> Result<NullifierEpochs> {
+        self.get("/api/v1/nullifiers").await
+    }
+
+    /// `GET /api/v1/nullifiers/:epoch`: a page of an epoch's nullifiers
+    pub async fn epoch_nullifiers(
+        &self,
+        epoch: u64,
+        cursor: Option<u64>,
+        limit: Option<u64>,
+    ) -> Result<EpochNullifiers> {
+        #[derive(Serialize)]
+        struct EpochNullifiersParams {
+            #[serde(skip_serializing_if = "Option::is_none")]
+            cursor: Option<u64>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            limit: Option<u64>,
+        }
+
+        let path = format!("/api/v1/nullifiers/{}", epoch);
+        let request = self
+            .request(Method::GET, &path)
+            .query(&EpochNullifiersParams { cursor, limit });
+        decode(self.send(request, true).await?).await
+    }
+
+    /// `DELETE /api/v1/nullifiers/:epoch`: forget an epoch's nullifiers,
+    /// so its proofs verify again
+    pub async fn expire_nullifiers(&self, epoch: u64) -> Result<ExpiredNullifiers> {
+        let path = format!("/api/v1/nullifiers/{}", epoch);
+        decode(self.send(self.request(Method::DELETE, &path), true).await?).await
+    }
+
+    /// `GET /api/v1/events`: verification outcomes as they happen, after
+    /// replaying those since query `last_event_id` (see events.rs)
+    pub async fn stream_events(
+        &self,
+        params: &EventParams,
+        last_event_id: Option<u64>,
+    ) -> Result<impl Stream<Item = Result<Event>>> {
+        let mut request = self.unlimited(Method::GET, "/api/v1/events").query(params);
+        if let Some(id) = last_event_id {
+            request = request.header("Last-Event-ID", id.to_string());
+        }
+        Ok(events::parse(self.send(request, true).await?))
+    }
+
+    // Webhooks
+
+    /// `POST /api/v1/webhooks`; the response carries the signing secret
+    pub async fn register_webhook(&self, webhook: &RegisterWebhookRequest) -> Result<Webhook> {
+        let request = self.request(Method::POST, "/api/v1/webhooks").json(webhook);
+        decode(self.send_once(request).await?).await
+    }
+
+    /// `GET /api/v1/webhooks`
+    pub async fn list_webhooks(&self) -> Result<Vec<Webhook>> {
+        self.get("/api/v1/webhooks").await
+    }
+
+    /// `DELETE /api/v1/webhooks/:id`
+    pub async fn delete_webhook(&self, id: u64) -> Result<()> {
+        let path = format!("/api/v1/webhooks/{}", id);
+        self.send(self.request(Method::DELETE, &path), false)
+            .await?;
+        Ok(())
+    }
+
+    // Keys
+
+    /// `GET /api/v1/keys/verifying-key`: serialized verifying key, the
+    /// current one unless `fingerprint` names another
+    pub async fn verifying_key(&self, fingerprint: Option<&str>) -> Result<Vec<u8>> {
+        match fingerprint {
+            Some(fingerprint) => {
+                let request = self
+                    .request(Method::GET, "/api/v1/keys/verifying-key")
+                    .query(&[("fingerprint", fingerprint)]);
+                Ok(self.send(request, true).await?.bytes().await?.to_vec())
+            }
+            None => self.get_bytes("/api/v1/keys/verifying-key").await,
+        }
+    }
+
+    /// `GET /api/v1/keys/verifying-keys`: every key version
+    pub async fn list_verifying_keys(&self) -> Result<KeyVersionList> {
+        self.get("/api/v1/keys/verifying-keys").await
+    }
+
+    /// `POST /api/v1/keys/verifying-keys`: schedule a serialized verifying
+    /// key
+    pub async fn upload_verifying_key(
+        &self,
+        key: Vec<u8>,
+        params: &UploadKeyParams,
+    ) -> Result<KeyRotation> {
+        let request = self
+            .request(Method::POST, "/api/v1/keys/verifying-keys")
+            .query(params)
+            .header(CONTENT_TYPE, "application/octet-stream")
+            .body(key);
+        decode(self.send(request, false).await?).await
+    }
+
+    /// `POST /api/v1/keys/verifying-keys/:fingerprint/deprecate`
+    pub async fn deprecate_verifying_key(
+        &self,
+        fingerprint: &str,
+        deprecation: &DeprecateKeyRequest,
+    ) -> Result<KeyRotation> {
+        let path = format!(
+            "/api/v1/keys/verifying-keys/{}/deprecate",
+            segment(fingerprint)
+        );
+        let request = self.request(Method::POST, &path).json(deprecation);
+        decode(self.send(request, false).await?).await
+    }
+
+    /// `GET /api/v1/keys/proving-key`: serialized proving key
+    pub async fn proving_key(&self) -> Result<Vec<u8>> {
+        self.get_bytes("/api/v1/keys/proving-key").await
+    }
+
+    /// `GET /api/v1/keys/receipt-key`: key that signs verification receipts
+    pub async fn receipt_key(&self) -> Result<ReceiptKey> {
+        self.get("/api/v1/keys/receipt-key").await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderMap;
+    use axum::response::IntoResponse;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Mutex;
+
+    #[tokio::test]
+    async fn test_retries_with_idempotency_key() {
+        // Refuses the first attempt as busy, then answers; records the
+        // headers of each attempt
+        let attempts = Arc::new(AtomicU32::new(0));
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let app = Router::new()
+            .route(
+                "/api/v1/document/register",
+                post({
+                    let (attempts, seen) = (attempts.clone(), seen.clone());
+                    move |headers: HeaderMap| async move {
+                        seen.lock().unwrap().push(headers);
+                        if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                            return (
+                                StatusCode::SERVICE_UNAVAILABLE,
+                                [(RETRY_AFTER, "0")],
+                                r#"{"code":"verifier_busy","message":"Busy"}"#,
+                            )
+                                .into_response();
+                        }
+                        (
+                            StatusCode::CREATED,
+                            Json(serde_json::json!({
+                                "success": true,
+                                "id": 7,
+                                "commitment": "ab",
+                                "document_count": 1,
+                            })),
+                        )
+                            .into_response()
+                    }
+                }),
+            )
+            .route(
+                "/api/v1/model/:id/revoke",
+                post(|| async {
+                    (
+                        StatusCode::FORBIDDEN,
+                        Json(serde_json::json!({
+                            "code": "forbidden",
+                            "message": "Admins only",
+                        })),
+                    )
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = Client::builder(format!("http://{}/", addr))
+            .bearer_token("t1")
+            .build()
+            .unwrap();
+        let registration = client
+            .register_document(&RegisterDocumentRequest {
+                commitment: Some("ab".repeat(32)),
+                owner: "alice".to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(registration.id, Some(7));
+
+        // Both attempts were authorized, under one idempotency key
+        let seen = seen.lock().unwrap().clone();
+        assert_eq!(seen.len(), 2);
+        assert!(seen.iter().all(|h| h[AUTHORIZATION] == "Bearer t1"));
+        assert_eq!(seen[0][IDEMPOTENCY_KEY], seen[1][IDEMPOTENCY_KEY]);
+
+        // Refusals that retrying won't fix come back with their code
+        let error = client.revoke_model(1).await.unwrap_err();
+        assert_eq!(error.code(), Some(ErrorCode::Forbidden));
+    }
+}
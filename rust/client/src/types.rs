@@ -0,0 +1,565 @@
+// Request and response bodies
+//
+// Mirrors of the service's bodies that have no counterpart in the shared
+// crates; envelopes, public inputs, witnesses, receipts and failure reasons
+// are the `zkrag_verifier` and `zkrag_prover` types the service itself uses.
+// Fields keep the service's names, so its OpenAPI document
+// (`/api/v1/openapi.json`) describes them too.
+
+use serde::{Deserialize, Serialize};
+use zkrag_verifier::{PublicInputs, VerificationFailure, VerificationReceipt, VerificationResult};
+
+/// Body of `POST /api/v1/document/register`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RegisterDocumentRequest {
+    /// Commitment to the document's contents; derived from
+    /// `document_hashes` when omitted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commitment: Option<String>,
+    pub owner: String,
+    /// Hex SHA-256 hashes of the document (or its chunks) to add to the
+    /// registry
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub document_hashes: Vec<String>,
+}
+
+/// Response of `POST /api/v1/document/register`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct DocumentRegistration {
+    pub success: bool,
+    pub id: Option<u64>,
+    /// Registry commitment after this registration
+    pub commitment: String,
+    pub document_count: usize,
+}
+
+/// Query of `POST /api/v1/document/upload`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct UploadParams {
+    pub owner: String,
+    /// Characters per chunk (default 512)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunk_size: Option<usize>,
+    /// Characters consecutive chunks share (default 50)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub overlap: Option<usize>,
+}
+
+/// Response of `POST /api/v1/document/upload`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct DocumentUpload {
+    pub id: u64,
+    /// Commitment to the uploaded document's chunks
+    pub document_commitment: String,
+    /// Hex SHA-256 of each chunk, in document order
+    pub chunk_hashes: Vec<String>,
+    /// Registry commitment after this upload
+    pub commitment: String,
+    pub document_count: usize,
+}
+
+/// Response of `GET /api/v1/document/commitment`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Commitment {
+    pub commitment: String,
+    pub document_count: usize,
+}
+
+/// Inclusion path of a registered document
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct DocumentProof {
+    pub document_hash: String,
+    /// Registry commitment the path leads to
+    pub commitment: String,
+    /// Position of the document's leaf among the sorted hashes
+    pub index: usize,
+    pub document_count: usize,
+    /// Siblings from the leaf up
+    pub path: Vec<PathStep>,
+}
+
+/// Sibling hashed in at one level
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct PathStep {
+    /// Hex SHA-256 of the sibling node
+    pub sibling: String,
+    pub side: Side,
+}
+
+/// Side of a sibling node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// Response of the model registration and revocation routes
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Success {
+    pub success: bool,
+    pub id: Option<u64>,
+}
+
+/// Model upload: a single weight file, or a model directory as a tar
+/// archive
+#[derive(Debug)]
+pub enum ModelArtifact {
+    /// Weight file named e.g. `model.safetensors`
+    File {
+        filename: String,
+        body: reqwest::Body,
+    },
+    /// Tar archive of a model directory
+    Archive(reqwest::Body),
+}
+
+/// Weight file included in a model hash
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ArtifactFile {
+    /// Path within the model directory, `/`-separated
+    pub path: String,
+    pub size: u64,
+}
+
+/// Response of `POST /api/v1/model/upload`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ModelUpload {
+    pub id: u64,
+    /// Canonical hash computed from the upload
+    pub model_hash: String,
+    pub model_name: String,
+    /// Files hashed, in hashing order
+    pub files: Vec<ArtifactFile>,
+}
+
+/// Response of `POST /api/v1/challenge`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Challenge {
+    /// Hex nonce to bind into the proof as the witness's `nonce`
+    pub nonce: String,
+    /// Unix time after which the nonce is refused
+    pub expires_at: u64,
+}
+
+/// Body of `POST /api/v1/query/verify`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VerifyQueryRequest {
+    /// Hex-encoded proof
+    pub proof: String,
+    pub document_commitment: String,
+    pub model_hash: String,
+    pub timestamp: u64,
+    /// Nonce from `/api/v1/challenge` the proof is bound to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+}
+
+/// Response of `POST /api/v1/query/verify`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct VerificationResponse {
+    pub valid: bool,
+    pub query_id: Option<u64>,
+    pub message: String,
+    /// Why the proof failed
+    pub reason: Option<VerificationFailure>,
+    pub proof_digest: String,
+    pub verified_at: u64,
+}
+
+/// Response of `POST /api/v2/query/verify`
+#[derive(Debug, Clone, Deserialize)]
+pub struct EnvelopeVerification {
+    /// Id of the recorded query
+    pub query_id: u64,
+    #[serde(flatten)]
+    pub result: VerificationResult,
+}
+
+/// Outcome of verifying a query proof
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct QueryRecord {
+    pub id: u64,
+    pub proof_digest: String,
+    pub document_commitment: String,
+    pub model_hash: String,
+    pub timestamp: u64,
+    pub verified: bool,
+    pub reason: Option<VerificationFailure>,
+    pub verified_at: u64,
+}
+
+/// Query of `GET /api/v1/queries`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ListQueriesParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document_commitment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_hash: Option<String>,
+    /// Only queries verified at or after this Unix time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<u64>,
+    /// 1-based page number
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+}
+
+/// Response of `GET /api/v1/queries`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct QueryList {
+    pub queries: Vec<QueryRecord>,
+    pub page: u64,
+    pub limit: u64,
+    /// Queries matching the filters, across all pages
+    pub total: u64,
+}
+
+/// Response of `GET /api/v1/query/:id/receipt`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct QueryReceipt {
+    pub query_id: u64,
+    /// Public inputs of the proof; `receipt.inputs_digest` is the SHA-256 of
+    /// their canonical JSON
+    pub public_inputs: PublicInputs,
+    pub receipt: VerificationReceipt,
+    /// Hex ed25519 public key of `receipt.verifier_key_id`, while the
+    /// service still signs with it
+    pub public_key: Option<String>,
+}
+
+/// Outcome of a verification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Verified,
+    Failed,
+}
+
+/// Query of `GET /api/v1/audit`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct AuditParams {
+    /// Only verifications at or after this Unix time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<Outcome>,
+    /// Owner of the document queried
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    /// `next_cursor` of the previous page; omit for the first
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<u64>,
+    /// Entries per page; ignored by the NDJSON export
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+}
+
+/// Verification as the audit trail reports it
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct AuditEntry {
+    #[serde(flatten)]
+    pub query: QueryRecord,
+    /// Owner of the registered document the query names, if any
+    pub tenant: Option<String>,
+    /// Verifying key that checked the proof
+    pub key_id: Option<String>,
+}
+
+/// Response of `GET /api/v1/audit`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct AuditPage {
+    pub entries: Vec<AuditEntry>,
+    /// Cursor of the next page; absent on the last
+    pub next_cursor: Option<u64>,
+}
+
+/// Document registered to an owner
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct OwnedDocument {
+    pub id: u64,
+    pub commitment: String,
+    pub registered_at: u64,
+    pub revoked_at: Option<u64>,
+}
+
+/// Model named by an owner's queries
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ModelUsage {
+    pub model_hash: String,
+    /// Registered name, if the model is registered
+    pub model_name: Option<String>,
+    pub queries: u64,
+    pub last_verified_at: u64,
+}
+
+/// Response of `GET /api/v1/owners/:owner/queries`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct OwnerHistory {
+    pub owner: String,
+    pub documents: Vec<OwnedDocument>,
+    pub models: Vec<ModelUsage>,
+    pub queries: Vec<AuditEntry>,
+    /// Cursor of the next page of queries; absent on the last
+    pub next_cursor: Option<u64>,
+}
+
+/// Query of `GET /api/v1/stats`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct StatsParams {
+    /// Only verifications at or after this Unix time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<u64>,
+    /// Only verifications before this Unix time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub until: Option<u64>,
+    /// Models and documents to list, busiest first (default 20)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top: Option<u64>,
+}
+
+/// Verifications and how they ended
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub struct Counts {
+    pub total: u64,
+    pub verified: u64,
+    pub failed: u64,
+}
+
+/// Verifications of queries naming a model
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ModelStats {
+    pub model_hash: String,
+    #[serde(flatten)]
+    pub counts: Counts,
+}
+
+/// Verifications of queries over a document commitment
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct DocumentStats {
+    pub document_commitment: String,
+    #[serde(flatten)]
+    pub counts: Counts,
+}
+
+/// Verifications on a UTC day
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct DayStats {
+    /// YYYY-MM-DD
+    pub day: String,
+    #[serde(flatten)]
+    pub counts: Counts,
+}
+
+/// Failed verifications with a reason code
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct FailureStats {
+    /// `code` of the failure reason, e.g. `pairing_failed`
+    pub code: String,
+    pub count: u64,
+}
+
+/// Response of `GET /api/v1/stats`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Stats {
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    /// Every verification in the period
+    #[serde(flatten)]
+    pub counts: Counts,
+    /// Busiest models first
+    pub by_model: Vec<ModelStats>,
+    /// Busiest documents first
+    pub by_document: Vec<DocumentStats>,
+    /// Days with verifications, oldest first
+    pub by_day: Vec<DayStats>,
+    /// Most frequent first
+    pub failures: Vec<FailureStats>,
+}
+
+/// Epoch with recorded nullifiers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct EpochSummary {
+    pub epoch: u64,
+    /// Unix time of the epoch's earliest proof timestamp
+    pub starts_at: u64,
+    pub nullifiers: u64,
+}
+
+/// Response of `GET /api/v1/nullifiers`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct NullifierEpochs {
+    pub epoch_secs: u64,
+    /// Oldest first
+    pub epochs: Vec<EpochSummary>,
+}
+
+/// Nullifier of an accepted proof
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct NullifierRecord {
+    /// Hex SHA-256 of the proof's public inputs
+    pub nullifier: String,
+    pub epoch: u64,
+    /// Query that spent it
+    pub query_id: u64,
+    pub recorded_at: u64,
+}
+
+/// Response of `GET /api/v1/nullifiers/:epoch`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct EpochNullifiers {
+    pub epoch: u64,
+    pub starts_at: u64,
+    pub nullifiers: Vec<NullifierRecord>,
+    /// Cursor of the next page; absent on the last
+    pub next_cursor: Option<u64>,
+}
+
+/// Response of `DELETE /api/v1/nullifiers/:epoch`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct ExpiredNullifiers {
+    pub epoch: u64,
+    /// Nullifiers forgotten
+    pub expired: u64,
+}
+
+/// Body of `POST /api/v1/webhooks`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RegisterWebhookRequest {
+    /// http or https URL to POST events to
+    pub url: String,
+    /// Signing key, at least 16 bytes; generated if omitted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+}
+
+/// Registered webhook
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Webhook {
+    pub id: u64,
+    pub url: String,
+    pub created_at: u64,
+    /// Signing key; only returned on registration
+    pub secret: Option<String>,
+}
+
+/// Query of `POST /api/v1/keys/verifying-keys`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct UploadKeyParams {
+    /// Circuit the key verifies (default `document_query`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub circuit_id: Option<String>,
+    /// Pairing curve, `bn254` (default) or `bls12_381`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub curve: Option<String>,
+    /// Unix time the key becomes current (default now)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub activates_at: Option<u64>,
+    /// Seconds the replaced key keeps verifying
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grace_secs: Option<u64>,
+}
+
+/// Body of `POST /api/v1/keys/verifying-keys/:fingerprint/deprecate`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DeprecateKeyRequest {
+    /// Unix time the key stops being current (default now)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub at: Option<u64>,
+    /// Seconds it keeps verifying after that
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grace_secs: Option<u64>,
+}
+
+/// A verifying key and where it is in its schedule
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct KeyVersion {
+    pub fingerprint: String,
+    pub circuit_id: String,
+    pub curve: String,
+    /// `pending`, `active`, `deprecated` or `retired`
+    pub state: String,
+    /// Whether proofs naming no key are checked against it
+    pub current: bool,
+    pub activates_at: u64,
+    pub deprecated_at: Option<u64>,
+    pub retires_at: Option<u64>,
+}
+
+/// Response of `GET /api/v1/keys/verifying-keys`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct KeyVersionList {
+    /// Ordered by circuit, then activation time
+    pub keys: Vec<KeyVersion>,
+}
+
+/// Response of the verifying key upload and deprecation routes
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct KeyRotation {
+    pub key: KeyVersion,
+    /// Key deprecated by an upload, if any
+    pub deprecated: Option<KeyVersion>,
+}
+
+/// Response of `GET /api/v1/keys/receipt-key`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ReceiptKey {
+    /// `verifier_key_id` of the receipts it signs
+    pub verifier_key_id: String,
+    /// Hex ed25519 public key
+    pub public_key: String,
+}
+
+/// How a component is doing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Ok,
+    Failing,
+}
+
+/// Check of the current verifying key
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct KeyHealth {
+    pub status: HealthStatus,
+    pub fingerprint: Option<String>,
+    pub curve: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Check of a service the server calls
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ComponentHealth {
+    pub status: HealthStatus,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Components checked by `/health`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Components {
+    pub verifying_key: KeyHealth,
+    pub database: ComponentHealth,
+    /// Absent when the service has no kernel
+    pub kernel: Option<ComponentHealth>,
+}
+
+/// Response of `GET /health`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Health {
+    /// `failing` when any component is
+    pub status: HealthStatus,
+    pub components: Components,
+}
+
+/// Response of `GET /readyz`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Readiness {
+    pub ready: bool,
+    pub verifying_key_loaded: bool,
+    pub database_reachable: bool,
+    /// Absent when the service has no kernel
+    pub kernel_reachable: Option<bool>,
+    pub shutting_down: bool,
+}
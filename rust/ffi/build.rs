@@ -0,0 +1,13 @@
+// Regenerates the C header from the exported functions
+
+fn main() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+
+    let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir))
+        .expect("cbindgen.toml is valid");
+    cbindgen::generate_with_config(&crate_dir, config)
+        .expect("C header generates")
+        .write_to_file(format!("{}/include/zkrag.h", crate_dir));
+}
@@ -0,0 +1,123 @@
+#ifndef ZKRAG_H
+#define ZKRAG_H
+
+/* Generated by cbindgen from rust/ffi. Do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Result of an FFI call
+ */
+enum ZkragStatus
+#ifdef __cplusplus
+  : int32_t
+#endif // __cplusplus
+ {
+  ZKRAG_STATUS_OK = 0,
+  /**
+   * The proof is well-formed but did not verify
+   */
+  ZKRAG_STATUS_INVALID_PROOF = 1,
+  /**
+   * A pointer was null or an input didn't parse
+   */
+  ZKRAG_STATUS_INVALID_ARGUMENT = 2,
+  /**
+   * Loading or generating a key failed
+   */
+  ZKRAG_STATUS_KEY_ERROR = 3,
+  ZKRAG_STATUS_PROVING_ERROR = 4,
+  ZKRAG_STATUS_VERIFICATION_ERROR = 5,
+  ZKRAG_STATUS_PANIC = 6,
+};
+#ifndef __cplusplus
+typedef int32_t ZkragStatus;
+#endif // __cplusplus
+
+/**
+ * Prover holding a loaded proving key
+ */
+typedef struct ZkragProver ZkragProver;
+
+/**
+ * Bytes owned by the library
+ */
+typedef struct ZkragBuffer {
+  uint8_t *data;
+  size_t len;
+} ZkragBuffer;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Message for the most recent failed call on this thread, or null. Valid
+ * until the next call on the same thread.
+ */
+const char *zkrag_last_error(void);
+
+/**
+ * Create a prover using keys cached in `cache_dir` (null for
+ * `~/.zkrag/keys`), generating a proving key if none is cached. Returns
+ * null on failure; free with zkrag_prover_free.
+ *
+ * # Safety
+ * `cache_dir` must be null or a valid NUL-terminated string.
+ */
+struct ZkragProver *zkrag_prover_new(const char *cache_dir);
+
+/**
+ * Free a prover from zkrag_prover_new. Null is ignored.
+ *
+ * # Safety
+ * `prover` must be null or a pointer from zkrag_prover_new not yet freed.
+ */
+void zkrag_prover_free(struct ZkragProver *prover);
+
+/**
+ * Generate a proof from a JSON witness (document_hashes, query_text,
+ * query_embedding, search_results, document_commitment, model_hash,
+ * timestamp). On success the proof is written to `out_proof`.
+ *
+ * # Safety
+ * `prover` must come from zkrag_prover_new, `witness_json` must be a valid
+ * NUL-terminated string, and `out_proof` must be writable.
+ */
+ZkragStatus zkrag_generate_proof(const struct ZkragProver *prover,
+                                 const char *witness_json,
+                                 struct ZkragBuffer *out_proof);
+
+/**
+ * Verify a proof against JSON public inputs (document_commitment,
+ * model_hash, timestamp) and a compressed verifying key. Returns
+ * ZKRAG_STATUS_OK if the proof verifies and ZKRAG_STATUS_INVALID_PROOF if
+ * it doesn't.
+ *
+ * # Safety
+ * `proof` and `vk` must point to `proof_len` and `vk_len` readable bytes,
+ * and `public_inputs_json` must be a valid NUL-terminated string.
+ */
+ZkragStatus zkrag_verify_proof(const uint8_t *proof,
+                               size_t proof_len,
+                               const char *public_inputs_json,
+                               const uint8_t *vk,
+                               size_t vk_len);
+
+/**
+ * Free a buffer returned by the library. An empty buffer is ignored.
+ *
+ * # Safety
+ * `buffer` must come from this library and not have been freed.
+ */
+void zkrag_free_buffer(struct ZkragBuffer buffer);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* ZKRAG_H */
@@ -0,0 +1,290 @@
+// C bindings for ZKvsAI
+//
+// A stable C ABI over the prover and verifier for Go, C++, and other services
+// that link the library directly. The header is generated into
+// include/zkrag.h on every build.
+//
+// Conventions:
+// - Functions return a ZkragStatus; on failure, zkrag_last_error() describes
+//   the most recent error on the calling thread.
+// - Strings are NUL-terminated UTF-8; structured inputs are JSON.
+// - Buffers returned by the library are released with zkrag_free_buffer.
+// - Panics are caught at the boundary and reported as ZKRAG_STATUS_PANIC.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::ptr;
+
+use zkrag_prover::{QueryProver, QueryWitness};
+use zkrag_verifier::{PublicInputs, QueryVerifier};
+
+/// Result of an FFI call
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZkragStatus {
+    Ok = 0,
+    /// The proof is well-formed but did not verify
+    InvalidProof = 1,
+    /// A pointer was null or an input didn't parse
+    InvalidArgument = 2,
+    /// Loading or generating a key failed
+    KeyError = 3,
+    ProvingError = 4,
+    VerificationError = 5,
+    Panic = 6,
+}
+
+/// Bytes owned by the library
+#[repr(C)]
+pub struct ZkragBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+/// Prover holding a loaded proving key
+pub struct ZkragProver {
+    inner: QueryProver,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: impl ToString) {
+    let message = CString::new(message.to_string().replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Run `f`, recording its error message and turning panics into a status
+fn guard(f: impl FnOnce() -> Result<ZkragStatus, (ZkragStatus, String)>) -> ZkragStatus {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(status)) => status,
+        Ok(Err((status, message))) => {
+            set_last_error(message);
+            status
+        }
+        Err(_) => {
+            set_last_error("panic in zkrag");
+            ZkragStatus::Panic
+        }
+    }
+}
+
+/// Borrow a NUL-terminated UTF-8 string argument
+unsafe fn str_arg<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, (ZkragStatus, String)> {
+    if ptr.is_null() {
+        return Err((ZkragStatus::InvalidArgument, format!("{} is null", name)));
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|e| (ZkragStatus::InvalidArgument, format!("{}: {}", name, e)))
+}
+
+/// Borrow a byte slice argument
+unsafe fn bytes_arg<'a>(
+    ptr: *const u8,
+    len: usize,
+    name: &str,
+) -> Result<&'a [u8], (ZkragStatus, String)> {
+    if ptr.is_null() {
+        return Err((ZkragStatus::InvalidArgument, format!("{} is null", name)));
+    }
+    Ok(std::slice::from_raw_parts(ptr, len))
+}
+
+/// Message for the most recent failed call on this thread, or null. Valid
+/// until the next call on the same thread.
+#[no_mangle]
+pub extern "C" fn zkrag_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Create a prover using keys cached in `cache_dir` (null for
+/// `~/.zkrag/keys`), generating a proving key if none is cached. Returns
+/// null on failure; free with zkrag_prover_free.
+///
+/// # Safety
+/// `cache_dir` must be null or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn zkrag_prover_new(cache_dir: *const c_char) -> *mut ZkragProver {
+    let mut prover = ptr::null_mut();
+    guard(|| {
+        let inner = if cache_dir.is_null() {
+            QueryProver::new()
+        } else {
+            QueryProver::with_cache_dir(PathBuf::from(str_arg(cache_dir, "cache_dir")?))
+        };
+        let mut inner = inner.map_err(|e| (ZkragStatus::KeyError, format!("{:#}", e)))?;
+        inner
+            .setup()
+            .map_err(|e| (ZkragStatus::KeyError, format!("{:#}", e)))?;
+
+        prover = Box::into_raw(Box::new(ZkragProver { inner }));
+        Ok(ZkragStatus::Ok)
+    });
+    prover
+}
+
+/// Free a prover from zkrag_prover_new. Null is ignored.
+///
+/// # Safety
+/// `prover` must be null or a pointer from zkrag_prover_new not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn zkrag_prover_free(prover: *mut ZkragProver) {
+    if !prover.is_null() {
+        drop(Box::from_raw(prover));
+    }
+}
+
+/// Generate a proof from a JSON witness (document_hashes, query_text,
+/// query_embedding, search_results, document_commitment, model_hash,
+/// timestamp). On success the proof is written to `out_proof`.
+///
+/// # Safety
+/// `prover` must come from zkrag_prover_new, `witness_json` must be a valid
+/// NUL-terminated string, and `out_proof` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn zkrag_generate_proof(
+    prover: *const ZkragProver,
+    witness_json: *const c_char,
+    out_proof: *mut ZkragBuffer,
+) -> ZkragStatus {
+    guard(|| {
+        let prover = prover
+            .as_ref()
+            .ok_or((ZkragStatus::InvalidArgument, "prover is null".to_string()))?;
+        if out_proof.is_null() {
+            return Err((
+                ZkragStatus::InvalidArgument,
+                "out_proof is null".to_string(),
+            ));
+        }
+        let witness: QueryWitness = serde_json::from_str(str_arg(witness_json, "witness_json")?)
+            .map_err(|e| (ZkragStatus::InvalidArgument, format!("witness_json: {}", e)))?;
+
+        let proof = prover
+            .inner
+            .prove(witness)
+            .map_err(|e| (ZkragStatus::ProvingError, format!("{:#}", e)))?;
+
+        let mut proof = proof.into_boxed_slice();
+        *out_proof = ZkragBuffer {
+            data: proof.as_mut_ptr(),
+            len: proof.len(),
+        };
+        std::mem::forget(proof);
+        Ok(ZkragStatus::Ok)
+    })
+}
+
+/// Verify a proof against JSON public inputs (document_commitment,
+/// model_hash, timestamp) and a compressed verifying key. Returns
+/// ZKRAG_STATUS_OK if the proof verifies and ZKRAG_STATUS_INVALID_PROOF if
+/// it doesn't.
+///
+/// # Safety
+/// `proof` and `vk` must point to `proof_len` and `vk_len` readable bytes,
+/// and `public_inputs_json` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn zkrag_verify_proof(
+    proof: *const u8,
+    proof_len: usize,
+    public_inputs_json: *const c_char,
+    vk: *const u8,
+    vk_len: usize,
+) -> ZkragStatus {
+    guard(|| {
+        let proof = bytes_arg(proof, proof_len, "proof")?;
+        let vk = bytes_arg(vk, vk_len, "vk")?;
+        let public_inputs: PublicInputs =
+            serde_json::from_str(str_arg(public_inputs_json, "public_inputs_json")?).map_err(
+                |e| {
+                    (
+                        ZkragStatus::InvalidArgument,
+                        format!("public_inputs_json: {}", e),
+                    )
+                },
+            )?;
+
+        let verifier = QueryVerifier::builder()
+            .key_bytes(vk)
+            .build()
+            .map_err(|e| (ZkragStatus::KeyError, e.to_string()))?;
+        let result = verifier
+            .verify(proof, public_inputs)
+            .map_err(|e| (ZkragStatus::VerificationError, e.to_string()))?;
+
+        match result.reason {
+            None if result.is_valid => Ok(ZkragStatus::Ok),
+            Some(reason) => Err((ZkragStatus::InvalidProof, reason.to_string())),
+            None => Err((
+                ZkragStatus::InvalidProof,
+                "proof did not verify".to_string(),
+            )),
+        }
+    })
+}
+
+/// Free a buffer returned by the library. An empty buffer is ignored.
+///
+/// # Safety
+/// `buffer` must come from this library and not have been freed.
+#[no_mangle]
+pub unsafe extern "C" fn zkrag_free_buffer(buffer: ZkragBuffer) {
+    if !buffer.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            buffer.data,
+            buffer.len,
+        )));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prove_and_verify_round_trip() {
+        let dir = std::env::temp_dir().join("zkrag_test_ffi_keys");
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache_dir = CString::new(dir.to_str().unwrap()).unwrap();
+
+        unsafe {
+            let prover = zkrag_prover_new(cache_dir.as_ptr());
+            assert!(!prover.is_null());
+
+            let witness = CString::new(
+                r#"{"document_hashes":[],"query_text":"q","query_embedding":[],
+                    "search_results":[],"document_commitment":"c","model_hash":"m",
+                    "timestamp":1}"#,
+            )
+            .unwrap();
+            let mut proof = ZkragBuffer {
+                data: ptr::null_mut(),
+                len: 0,
+            };
+            assert_eq!(
+                zkrag_generate_proof(prover, witness.as_ptr(), &mut proof),
+                ZkragStatus::Ok
+            );
+            assert!(proof.len > 0);
+
+            // Bad JSON is reported through zkrag_last_error
+            let bad = CString::new("{").unwrap();
+            let status = zkrag_verify_proof(proof.data, proof.len, bad.as_ptr(), proof.data, 0);
+            assert_eq!(status, ZkragStatus::InvalidArgument);
+            assert!(!zkrag_last_error().is_null());
+
+            zkrag_free_buffer(proof);
+            zkrag_prover_free(prover);
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
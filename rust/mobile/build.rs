@@ -0,0 +1,5 @@
+// Generates the UniFFI scaffolding for the interface in src/zkrag.udl
+
+fn main() {
+    uniffi::generate_scaffolding("src/zkrag.udl").expect("zkrag.udl is valid");
+}
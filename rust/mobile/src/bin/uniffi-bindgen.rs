@@ -0,0 +1,5 @@
+// Generates Kotlin and Swift sources for the mobile library
+
+fn main() {
+    uniffi::uniffi_bindgen_main()
+}
@@ -0,0 +1,86 @@
+// Mobile bindings for ZKvsAI
+//
+// A UniFFI interface (src/zkrag.udl) over the verifier, so Android and iOS
+// apps can check proof receipts on-device instead of trusting the service's
+// verdict. Verification goes through zkrag-verifier-core, the same code the
+// Rust verifier and the browser build use, which keeps the default library
+// small. Freshness takes the current time from the caller, as in the core.
+//
+// The `prover` feature adds a `Prover` for apps that generate proofs
+// themselves. It is exported with UniFFI's proc macros, since a UDL file
+// can't depend on a feature.
+
+// The generated UniFFI scaffolding trips this lint on newer compilers
+#![allow(clippy::empty_line_after_doc_comments)]
+
+use ark_bn254::Bn254;
+use ark_groth16::PreparedVerifyingKey;
+use zkrag_verifier_core::{self as core, CoreError};
+
+#[cfg(feature = "prover")]
+mod prover;
+
+uniffi::include_scaffolding!("zkrag");
+
+/// Public inputs a document query proof is checked against
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicInputs {
+    pub document_commitment: String,
+    pub model_hash: String,
+    pub timestamp: u64,
+}
+
+/// Verification failure other than a proof not verifying
+#[derive(Debug, thiserror::Error)]
+pub enum VerifyError {
+    #[error("{0}")]
+    MalformedKey(String),
+    #[error("{0}")]
+    MalformedProof(String),
+}
+
+/// Document query verifier holding a prepared verifying key
+pub struct Verifier {
+    vk: PreparedVerifyingKey<Bn254>,
+}
+
+impl Verifier {
+    pub fn new(verifying_key: Vec<u8>) -> Result<Self, VerifyError> {
+        let vk = core::prepare_verifying_key(&verifying_key)
+            .map_err(|e| VerifyError::MalformedKey(e.to_string()))?;
+        Ok(Self { vk })
+    }
+
+    /// Whether `proof` verifies against `public_inputs`
+    pub fn verify(&self, proof: Vec<u8>, public_inputs: PublicInputs) -> Result<bool, VerifyError> {
+        let fields = core::public_inputs_to_fields(
+            &public_inputs.document_commitment,
+            &public_inputs.model_hash,
+            public_inputs.timestamp,
+        );
+        match core::verify_proof_bytes(&self.vk, &proof, &fields) {
+            Ok(()) => Ok(true),
+            Err(CoreError::PairingFailed) => Ok(false),
+            Err(e) => Err(VerifyError::MalformedProof(e.to_string())),
+        }
+    }
+}
+
+pub fn is_fresh(timestamp: u64, now: u64, max_age: u64, max_skew: u64) -> bool {
+    core::is_fresh(timestamp, now, max_age, max_skew)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_malformed_inputs() {
+        assert!(matches!(
+            Verifier::new(vec![0u8; 4]),
+            Err(VerifyError::MalformedKey(_))
+        ));
+        assert!(is_fresh(950, 1000, 60, 10));
+        assert!(!is_fresh(900, 1000, 60, 10));
+    }
+}
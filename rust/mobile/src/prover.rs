@@ -0,0 +1,87 @@
+// On-device proving
+//
+// Only built with the `prover` feature. Apps ship or download a proving key
+// and load it from a path inside their sandbox; there is no home-directory
+// key cache on mobile.
+
+use std::path::Path;
+use std::sync::Arc;
+use zkrag_prover::{QueryProver, QueryWitness};
+
+use crate::PublicInputs;
+
+/// Proving failure
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+#[uniffi(flat_error)]
+pub enum ProveError {
+    #[error("{0}")]
+    Key(String),
+    #[error("{0}")]
+    InvalidWitness(String),
+    #[error("{0}")]
+    Proving(String),
+}
+
+/// Private inputs of a document query proof, with the public inputs they
+/// are proven against
+#[derive(uniffi::Record)]
+pub struct Witness {
+    pub document_hashes: Vec<String>,
+    pub query_text: String,
+    pub query_embedding: Vec<f64>,
+    pub search_results: Vec<u64>,
+    pub public_inputs: PublicInputs,
+}
+
+/// Document query prover holding a loaded proving key
+#[derive(uniffi::Object)]
+pub struct Prover {
+    inner: QueryProver,
+}
+
+#[uniffi::export]
+impl Prover {
+    /// Load a compressed proving key file
+    #[uniffi::constructor]
+    pub fn new(proving_key_path: String) -> Result<Arc<Self>, ProveError> {
+        let path = Path::new(&proving_key_path);
+        let key_dir = path.parent().unwrap_or(Path::new("."));
+        let mut inner = QueryProver::with_cache_dir(key_dir)
+            .map_err(|e| ProveError::Key(format!("{:#}", e)))?;
+        inner
+            .load_proving_key(path)
+            .map_err(|e| ProveError::Key(format!("{:#}", e)))?;
+        Ok(Arc::new(Self { inner }))
+    }
+
+    /// Compressed proof of `witness`
+    pub fn prove(&self, witness: Witness) -> Result<Vec<u8>, ProveError> {
+        let inputs = witness.public_inputs;
+        let witness = QueryWitness::builder()
+            .document_hashes(witness.document_hashes)
+            .query_text(witness.query_text)
+            .query_embedding(witness.query_embedding)
+            .search_results(
+                witness
+                    .search_results
+                    .into_iter()
+                    .map(|id| id as usize)
+                    .collect(),
+            )
+            .document_commitment(inputs.document_commitment)
+            .model_hash(inputs.model_hash)
+            .timestamp(inputs.timestamp)
+            .build()
+            .map_err(|e| ProveError::InvalidWitness(format!("{:#}", e)))?;
+        self.inner
+            .prove(witness)
+            .map_err(|e| ProveError::Proving(format!("{:#}", e)))
+    }
+
+    /// Compressed verifying key matching the loaded proving key
+    pub fn verifying_key(&self) -> Result<Vec<u8>, ProveError> {
+        self.inner
+            .verifying_key_bytes()
+            .map_err(|e| ProveError::Key(format!("{:#}", e)))
+    }
+}
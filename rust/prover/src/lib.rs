@@ -2,17 +2,45 @@
 //
 // Generates zero-knowledge proofs for privacy-preserving RAG operations
 
+use anyhow::{Context, Result};
 use ark_bn254::{Bn254, Fr};
-use ark_groth16::{Groth16, PreparedVerifyingKey, ProvingKey, Proof};
+use ark_groth16::{Groth16, ProvingKey};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
-use ark_std::rand::SeedableRng;
-use anyhow::{Context, Result};
+use rand::rngs::OsRng;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use zkrag_circuits::DocumentQueryCircuit;
 
+pub mod model;
 pub mod witness;
 
-pub use witness::QueryWitness;
+pub use model::compute_model_hash;
+pub use witness::{QueryWitness, WitnessBuilder};
+
+/// Proving key file in a key cache directory
+pub const PROVING_KEY_FILE: &str = "proving_key.bin";
+
+/// Stage of proof generation reported to progress callbacks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProvingStage {
+    /// Building the circuit from the witness
+    Witness,
+    /// Generating the Groth16 proof
+    Prove,
+    /// Serializing the proof
+    Serialize,
+}
+
+impl ProvingStage {
+    /// Name reported to callbacks
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Witness => "witness",
+            Self::Prove => "prove",
+            Self::Serialize => "serialize",
+        }
+    }
+}
 
 /// Prover for document query circuits
 pub struct QueryProver {
@@ -21,13 +49,19 @@ pub struct QueryProver {
 }
 
 impl QueryProver {
-    /// Create a new prover instance
+    /// Create a new prover instance using the default key cache (~/.zkrag/keys)
     pub fn new() -> Result<Self> {
         let cache_dir = dirs::home_dir()
             .context("Failed to get home directory")?
             .join(".zkrag")
             .join("keys");
 
+        Self::with_cache_dir(cache_dir)
+    }
+
+    /// Create a new prover instance using keys cached in `cache_dir`
+    pub fn with_cache_dir(cache_dir: impl Into<PathBuf>) -> Result<Self> {
+        let cache_dir = cache_dir.into();
         fs::create_dir_all(&cache_dir)?;
 
         Ok(Self {
@@ -36,35 +70,122 @@ impl QueryProver {
         })
     }
 
-    /// Load or generate proving key
+    /// Directory the proving key is cached in
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache_dir
+    }
+
+    /// Load the cached proving key, or generate and cache one.
+    ///
+    /// Generation is a single-party setup: whoever runs it could forge
+    /// proofs. Production deployments should load a ceremony's key with
+    /// `load_proving_key` instead.
     pub fn setup(&mut self) -> Result<()> {
-        let key_path = self.cache_dir.join("proving_key.bin");
+        let key_path = self.cache_dir.join(PROVING_KEY_FILE);
 
         if key_path.exists() {
             // Load cached key
-            let bytes = fs::read(&key_path)?;
-            self.proving_key = Some(
-                ProvingKey::deserialize_compressed(&bytes[..])?
-            );
-        } else {
-            // TODO: Generate new key
-            // This requires running the trusted setup
-            // For now, return an error
-            anyhow::bail!("Proving key not found. Run setup first.");
+            return self.load_proving_key(&key_path);
         }
 
+        // Keys depend only on the circuit's shape, so a blank instance works
+        let circuit = DocumentQueryCircuit::<Fr>::new(
+            vec![],
+            vec![],
+            vec![],
+            Fr::from(0u64),
+            Fr::from(0u64),
+            Fr::from(0u64),
+        );
+        let pk = Groth16::<Bn254>::generate_random_parameters_with_reduction(circuit, &mut OsRng)?;
+
+        let mut bytes = Vec::new();
+        pk.serialize_compressed(&mut bytes)?;
+        fs::write(&key_path, bytes)
+            .with_context(|| format!("Failed to cache proving key at {}", key_path.display()))?;
+
+        self.proving_key = Some(pk);
+        Ok(())
+    }
+
+    /// Load a proving key from a file
+    pub fn load_proving_key(&mut self, path: &Path) -> Result<()> {
+        let bytes = fs::read(path)
+            .with_context(|| format!("Failed to read proving key from {}", path.display()))?;
+        self.proving_key = Some(ProvingKey::deserialize_compressed(&bytes[..])?);
+        Ok(())
+    }
+
+    fn loaded_key(&self) -> Result<&ProvingKey<Bn254>> {
+        self.proving_key
+            .as_ref()
+            .context("No proving key loaded. Run setup first.")
+    }
+
+    /// Serialized proving key, in the format `load_proving_key` reads
+    pub fn proving_key_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.loaded_key()?.serialize_compressed(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Serialized verifying key matching the loaded proving key, in the
+    /// format the verifier loads
+    pub fn verifying_key_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.loaded_key()?.vk.serialize_compressed(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Write the verifying key matching the loaded proving key, in the
+    /// format the verifier loads
+    pub fn export_verifying_key(&self, path: &Path) -> Result<()> {
+        let bytes = self.verifying_key_bytes()?;
+        fs::write(path, bytes)
+            .with_context(|| format!("Failed to write verifying key to {}", path.display()))?;
         Ok(())
     }
 
     /// Generate a proof for a query
     pub fn prove(&self, witness: QueryWitness) -> Result<Vec<u8>> {
+        self.prove_with_progress(witness, |_, _| {})
+    }
+
+    /// Generate a proof, calling `progress` with each stage and the fraction
+    /// of it completed (0.0 to 1.0)
+    pub fn prove_with_progress(
+        &self,
+        _witness: QueryWitness,
+        mut progress: impl FnMut(ProvingStage, f64),
+    ) -> Result<Vec<u8>> {
         // TODO: Implement actual proof generation
         // 1. Build circuit from witness
+        progress(ProvingStage::Witness, 0.0);
+        progress(ProvingStage::Witness, 1.0);
+
         // 2. Generate proof using proving key
+        progress(ProvingStage::Prove, 0.0);
+        progress(ProvingStage::Prove, 1.0);
+
         // 3. Serialize proof
+        progress(ProvingStage::Serialize, 0.0);
+        let proof_bytes = vec![0u8; 128]; // Placeholder
+        progress(ProvingStage::Serialize, 1.0);
+
+        Ok(proof_bytes)
+    }
 
-        // Placeholder
-        Ok(vec![0u8; 128])
+    /// Generate proofs for several queries, returning one proof per witness
+    /// in order
+    pub fn prove_batch(&self, witnesses: Vec<QueryWitness>) -> Result<Vec<Vec<u8>>> {
+        witnesses
+            .into_iter()
+            .enumerate()
+            .map(|(i, witness)| {
+                self.prove(witness)
+                    .with_context(|| format!("Failed to prove batch item {}", i))
+            })
+            .collect()
     }
 }
 
@@ -83,4 +204,56 @@ mod tests {
         let prover = QueryProver::new();
         assert!(prover.is_ok());
     }
+
+    #[test]
+    fn test_prove_reports_progress() {
+        let prover = QueryProver::with_cache_dir(std::env::temp_dir()).unwrap();
+        let witness = QueryWitness::new(
+            vec![],
+            String::new(),
+            vec![],
+            vec![],
+            "commitment".to_string(),
+            "model".to_string(),
+            0,
+        );
+
+        let mut updates = Vec::new();
+        prover
+            .prove_with_progress(witness, |stage, fraction| updates.push((stage, fraction)))
+            .unwrap();
+        assert_eq!(updates.first(), Some(&(ProvingStage::Witness, 0.0)));
+        assert_eq!(updates.last(), Some(&(ProvingStage::Serialize, 1.0)));
+    }
+
+    #[test]
+    fn test_setup_and_export() {
+        let dir = std::env::temp_dir().join("zkrag_test_prover_keys");
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut prover = QueryProver::with_cache_dir(&dir).unwrap();
+        assert!(prover.export_verifying_key(&dir.join("vk.bin")).is_err());
+        prover.setup().unwrap();
+        assert!(dir.join(PROVING_KEY_FILE).exists());
+        prover.export_verifying_key(&dir.join("vk.bin")).unwrap();
+        assert_eq!(
+            fs::read(dir.join("vk.bin")).unwrap(),
+            prover.verifying_key_bytes().unwrap()
+        );
+        assert_eq!(
+            fs::read(dir.join(PROVING_KEY_FILE)).unwrap(),
+            prover.proving_key_bytes().unwrap()
+        );
+
+        // A second setup loads the cached key instead of generating one
+        let mut cached = QueryProver::with_cache_dir(&dir).unwrap();
+        cached.setup().unwrap();
+        cached.export_verifying_key(&dir.join("vk2.bin")).unwrap();
+        assert_eq!(
+            fs::read(dir.join("vk.bin")).unwrap(),
+            fs::read(dir.join("vk2.bin")).unwrap()
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
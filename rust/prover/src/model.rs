@@ -0,0 +1,113 @@
+// Model hashing
+//
+// The canonical scheme for the `model_hash` public input. A single weight file
+// hashes to the SHA-256 of its contents, matching `sha256sum`. A model
+// directory (e.g. sharded safetensors) hashes every weight file under it,
+// sorted by relative path, with each file's path and length mixed in so
+// renaming or re-splitting shards changes the hash.
+
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Extensions of weight files included in a model hash
+pub const WEIGHT_EXTENSIONS: [&str; 3] = ["safetensors", "gguf", "onnx"];
+
+/// Hex SHA-256 model hash of a weight file or a directory of weight files
+pub fn compute_model_hash(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+
+    if path.is_dir() {
+        let mut files = Vec::new();
+        collect_weight_files(path, &mut files)?;
+        if files.is_empty() {
+            bail!("No weight files found under {}", path.display());
+        }
+        files.sort();
+
+        for file in files {
+            let relative = file.strip_prefix(path)?;
+            let name = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let len = fs::metadata(&file)?.len();
+
+            hasher.update(name.as_bytes());
+            hasher.update([0u8]);
+            hasher.update(len.to_le_bytes());
+            hash_file(&file, &mut hasher)?;
+        }
+    } else {
+        if !is_weight_file(path) {
+            bail!(
+                "{} is not a weight file (expected one of: {})",
+                path.display(),
+                WEIGHT_EXTENSIONS.join(", ")
+            );
+        }
+        hash_file(path, &mut hasher)?;
+    }
+
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn is_weight_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| WEIGHT_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+fn collect_weight_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_weight_files(&path, files)?;
+        } else if is_weight_file(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn hash_file(path: &Path, hasher: &mut Sha256) -> Result<()> {
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    io::copy(&mut file, hasher)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_model_hash() {
+        let dir = std::env::temp_dir().join("zkrag_test_model");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("shards")).unwrap();
+
+        let file = dir.join("model.gguf");
+        fs::write(&file, b"weights").unwrap();
+        assert_eq!(
+            compute_model_hash(&file).unwrap(),
+            hex::encode(Sha256::digest(b"weights"))
+        );
+        assert!(compute_model_hash(&dir.join("missing.txt")).is_err());
+
+        // Non-weight files in a model directory are ignored
+        fs::write(dir.join("shards/model-00001.safetensors"), b"a").unwrap();
+        fs::write(dir.join("README.md"), b"docs").unwrap();
+        let before = compute_model_hash(&dir).unwrap();
+        fs::write(dir.join("README.md"), b"changed").unwrap();
+        assert_eq!(compute_model_hash(&dir).unwrap(), before);
+
+        fs::write(dir.join("shards/model-00001.safetensors"), b"b").unwrap();
+        assert_ne!(compute_model_hash(&dir).unwrap(), before);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
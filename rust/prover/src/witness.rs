@@ -1,8 +1,11 @@
 // Witness generation for document query circuits
 
 use ark_bn254::Fr;
-use ark_ff::PrimeField;
+use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+use zkrag_circuits::document_query::QUERY_EMBEDDING_DIM;
+use zkrag_circuits::utils::{bound_commitment, public_input_to_field};
 
 /// Witness for a document query proof
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,6 +30,10 @@ pub struct QueryWitness {
 
     /// Public: timestamp of query
     pub timestamp: u64,
+
+    /// Public: verifier challenge the proof is bound to, if any
+    #[serde(default)]
+    pub nonce: Option<String>,
 }
 
 impl QueryWitness {
@@ -48,7 +55,41 @@ impl QueryWitness {
             document_commitment,
             model_hash,
             timestamp,
+            nonce: None,
+        }
+    }
+
+    /// Start building a validated witness
+    pub fn builder() -> WitnessBuilder {
+        WitnessBuilder::default()
+    }
+
+    /// Check the witness fits the circuit: a finite embedding of
+    /// `QUERY_EMBEDDING_DIM` values, hex SHA-256 document hashes, and
+    /// non-empty public inputs
+    pub fn validate(&self) -> Result<()> {
+        if self.query_embedding.len() != QUERY_EMBEDDING_DIM {
+            bail!(
+                "Query embedding has {} dimensions, circuit expects {}",
+                self.query_embedding.len(),
+                QUERY_EMBEDDING_DIM
+            );
+        }
+        if let Some(i) = self.query_embedding.iter().position(|v| !v.is_finite()) {
+            bail!("Query embedding value {} is not finite", i);
+        }
+        for (i, hash) in self.document_hashes.iter().enumerate() {
+            if hash.len() != 64 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+                bail!("Document hash {} is not a hex SHA-256 digest", i);
+            }
+        }
+        if self.document_commitment.is_empty() {
+            bail!("Document commitment is empty");
+        }
+        if self.model_hash.is_empty() {
+            bail!("Model hash is empty");
         }
+        Ok(())
     }
 
     /// Convert to field elements for circuit
@@ -62,8 +103,11 @@ impl QueryWitness {
             .map(|(i, _)| Fr::from(i as u64))
             .collect();
 
-        let document_commitment_field = Fr::from(42u64); // Placeholder
-        let model_hash_field = Fr::from(100u64); // Placeholder
+        let document_commitment_field = public_input_to_field(&bound_commitment(
+            &self.document_commitment,
+            self.nonce.as_deref(),
+        ));
+        let model_hash_field = public_input_to_field(&self.model_hash);
         let timestamp_field = Fr::from(self.timestamp);
 
         WitnessFields {
@@ -77,6 +121,90 @@ impl QueryWitness {
     }
 }
 
+/// Builder for a `QueryWitness` that validates it on `build`
+#[derive(Debug, Default)]
+pub struct WitnessBuilder {
+    document_hashes: Vec<String>,
+    query_text: String,
+    query_embedding: Option<Vec<f64>>,
+    search_results: Vec<usize>,
+    document_commitment: Option<String>,
+    model_hash: Option<String>,
+    timestamp: Option<u64>,
+    nonce: Option<String>,
+}
+
+impl WitnessBuilder {
+    pub fn document_hashes(mut self, document_hashes: Vec<String>) -> Self {
+        self.document_hashes = document_hashes;
+        self
+    }
+
+    pub fn query_text(mut self, query_text: impl Into<String>) -> Self {
+        self.query_text = query_text.into();
+        self
+    }
+
+    pub fn query_embedding(mut self, query_embedding: Vec<f64>) -> Self {
+        self.query_embedding = Some(query_embedding);
+        self
+    }
+
+    pub fn search_results(mut self, search_results: Vec<usize>) -> Self {
+        self.search_results = search_results;
+        self
+    }
+
+    pub fn document_commitment(mut self, document_commitment: impl Into<String>) -> Self {
+        self.document_commitment = Some(document_commitment.into());
+        self
+    }
+
+    pub fn model_hash(mut self, model_hash: impl Into<String>) -> Self {
+        self.model_hash = Some(model_hash.into());
+        self
+    }
+
+    pub fn timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Bind the proof to a challenge nonce from the verifier
+    pub fn nonce(mut self, nonce: impl Into<String>) -> Self {
+        self.nonce = Some(nonce.into());
+        self
+    }
+
+    /// Build the witness, failing if a required field is missing or it
+    /// doesn't pass `QueryWitness::validate`
+    pub fn build(self) -> Result<QueryWitness> {
+        let mut witness = QueryWitness::new(
+            self.document_hashes,
+            self.query_text,
+            self.query_embedding.context("Missing query embedding")?,
+            self.search_results,
+            self.document_commitment
+                .context("Missing document commitment")?,
+            self.model_hash.context("Missing model hash")?,
+            self.timestamp.context("Missing timestamp")?,
+        );
+        witness.nonce = self.nonce;
+        witness.validate()?;
+        Ok(witness)
+    }
+}
+
+impl Drop for QueryWitness {
+    /// Clear the private fields so they don't linger in freed memory
+    fn drop(&mut self) {
+        self.document_hashes.zeroize();
+        self.query_text.zeroize();
+        self.query_embedding.zeroize();
+        self.search_results.zeroize();
+    }
+}
+
 /// Field element representation of witness
 pub struct WitnessFields {
     pub document_hashes: Vec<Fr>,
@@ -86,3 +214,38 @@ pub struct WitnessFields {
     pub model_hash: Fr,
     pub timestamp: Fr,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_witness_builder() {
+        let builder = || {
+            QueryWitness::builder()
+                .document_hashes(vec!["ab".repeat(32)])
+                .query_text("query")
+                .search_results(vec![0])
+                .document_commitment("commitment")
+                .model_hash("model")
+                .timestamp(1)
+        };
+
+        let witness = builder()
+            .query_embedding(vec![0.5; QUERY_EMBEDDING_DIM])
+            .build()
+            .unwrap();
+        assert_eq!(witness.query_text, "query");
+
+        assert!(builder().build().is_err());
+        assert!(builder().query_embedding(vec![0.5; 3]).build().is_err());
+        let mut embedding = vec![0.5; QUERY_EMBEDDING_DIM];
+        embedding[7] = f64::NAN;
+        assert!(builder().query_embedding(embedding).build().is_err());
+        assert!(builder()
+            .query_embedding(vec![0.5; QUERY_EMBEDDING_DIM])
+            .document_hashes(vec!["not a hash".to_string()])
+            .build()
+            .is_err());
+    }
+}
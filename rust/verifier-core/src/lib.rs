@@ -0,0 +1,312 @@
+// ZKvsAI Verifier Core
+//
+// Groth16 verification of document query proofs without std. Time and IO are
+// left to the caller: keys and proofs come in as byte slices, and the current
+// time is passed explicitly, so the same code runs in browsers (wasm32),
+// smart-contract hosts, and embedded attestation devices.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::{String, ToString};
+use ark_bn254::{Bn254, Fr};
+use ark_ec::AffineRepr;
+use ark_ff::PrimeField;
+use ark_groth16::{Groth16, PreparedVerifyingKey, Proof, VerifyingKey};
+use ark_serialize::CanonicalDeserialize;
+use core::fmt;
+use sha2::{Digest, Sha256};
+
+/// Number of public inputs of the document query circuit
+pub const NUM_PUBLIC_INPUTS: usize = 3;
+
+/// Size of a compressed BN254 Groth16 proof (G1 + G2 + G1 x-coordinates)
+pub const COMPRESSED_PROOF_SIZE: usize = 128;
+
+/// Size of an uncompressed BN254 Groth16 proof
+pub const UNCOMPRESSED_PROOF_SIZE: usize = 256;
+
+/// Largest accepted serialized proof
+pub const MAX_PROOF_SIZE: usize = UNCOMPRESSED_PROOF_SIZE;
+
+/// Largest accepted compressed verifying key (about 2,000 public inputs)
+pub const MAX_VERIFYING_KEY_SIZE: usize = 64 * 1024;
+
+/// Compressed BN254 verifying key size before its input commitments:
+/// alpha (G1), beta, gamma, delta (G2), and the u64 commitment count
+const VERIFYING_KEY_FIXED_SIZE: usize = 32 + 3 * 64 + 8;
+
+/// Compressed BN254 G1 point size
+const G1_COMPRESSED_SIZE: usize = 32;
+
+/// Serialization of proof points
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProofEncoding {
+    /// Detect from the byte length
+    #[default]
+    Auto,
+    /// Compressed points (what the prover emits)
+    Compressed,
+    /// Uncompressed points, as written by some other arkworks tooling
+    Uncompressed,
+}
+
+impl ProofEncoding {
+    /// Detect the encoding of proof bytes from their length
+    pub fn detect(proof_bytes: &[u8]) -> Result<Self, CoreError> {
+        match proof_bytes.len() {
+            COMPRESSED_PROOF_SIZE => Ok(Self::Compressed),
+            UNCOMPRESSED_PROOF_SIZE => Ok(Self::Uncompressed),
+            len => Err(CoreError::MalformedProof(format!(
+                "expected {} (compressed) or {} (uncompressed) bytes, got {}",
+                COMPRESSED_PROOF_SIZE, UNCOMPRESSED_PROOF_SIZE, len
+            ))),
+        }
+    }
+}
+
+/// Core verification failure
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoreError {
+    /// Verifying key bytes could not be deserialized
+    MalformedKey(String),
+    /// Proof bytes could not be deserialized
+    MalformedProof(String),
+    /// Verifying key bytes exceed `MAX_VERIFYING_KEY_SIZE`
+    KeyTooLarge { len: usize, max: usize },
+    /// Proof bytes exceed `MAX_PROOF_SIZE`
+    ProofTooLarge { len: usize, max: usize },
+    /// A key or proof contains the point at infinity
+    IdentityPoint(&'static str),
+    /// The pairing check did not hold
+    PairingFailed,
+}
+
+impl fmt::Display for CoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MalformedKey(message) => write!(f, "malformed verifying key: {}", message),
+            Self::MalformedProof(message) => write!(f, "malformed proof: {}", message),
+            Self::KeyTooLarge { len, max } => {
+                write!(f, "verifying key is {} bytes, limit is {}", len, max)
+            }
+            Self::ProofTooLarge { len, max } => {
+                write!(f, "proof is {} bytes, limit is {}", len, max)
+            }
+            Self::IdentityPoint(field) => write!(f, "{} is the point at infinity", field),
+            Self::PairingFailed => write!(f, "pairing check failed"),
+        }
+    }
+}
+
+/// Map a public input string (commitment, model hash) to a field element.
+///
+/// Matches `zkrag_circuits::utils::public_input_to_field` for BN254.
+pub fn public_input_to_field(value: &str) -> Fr {
+    Fr::from_be_bytes_mod_order(&Sha256::digest(value.as_bytes()))
+}
+
+/// Document commitment input bound to a challenge nonce, if there is one.
+///
+/// Matches `zkrag_circuits::utils::bound_commitment`.
+pub fn bound_commitment<'a>(document_commitment: &'a str, nonce: Option<&str>) -> Cow<'a, str> {
+    match nonce {
+        Some(nonce) => Cow::Owned(format!("{}\0{}", document_commitment, nonce)),
+        None => Cow::Borrowed(document_commitment),
+    }
+}
+
+/// Field elements in circuit order: commitment, model hash, timestamp
+pub fn public_inputs_to_fields(
+    document_commitment: &str,
+    model_hash: &str,
+    timestamp: u64,
+) -> [Fr; NUM_PUBLIC_INPUTS] {
+    [
+        public_input_to_field(document_commitment),
+        public_input_to_field(model_hash),
+        Fr::from(timestamp),
+    ]
+}
+
+/// Check key bytes are exactly as long as their declared input commitments
+/// require, before deserialization allocates anything
+fn check_key_length(key_bytes: &[u8]) -> Result<(), CoreError> {
+    if key_bytes.len() > MAX_VERIFYING_KEY_SIZE {
+        return Err(CoreError::KeyTooLarge {
+            len: key_bytes.len(),
+            max: MAX_VERIFYING_KEY_SIZE,
+        });
+    }
+    let count_bytes = key_bytes
+        .get(VERIFYING_KEY_FIXED_SIZE - 8..VERIFYING_KEY_FIXED_SIZE)
+        .ok_or_else(|| CoreError::MalformedKey("truncated verifying key".to_string()))?;
+    let mut count = [0u8; 8];
+    count.copy_from_slice(count_bytes);
+    let expected = usize::try_from(u64::from_le_bytes(count))
+        .ok()
+        .and_then(|count| count.checked_mul(G1_COMPRESSED_SIZE))
+        .and_then(|size| size.checked_add(VERIFYING_KEY_FIXED_SIZE));
+    if expected != Some(key_bytes.len()) {
+        return Err(CoreError::MalformedKey(format!(
+            "verifying key length {} does not match its input count",
+            key_bytes.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Deserialize and prepare a compressed verifying key.
+///
+/// Points are checked to be on the curve and in the prime-order subgroup, and
+/// none may be the point at infinity.
+pub fn prepare_verifying_key(key_bytes: &[u8]) -> Result<PreparedVerifyingKey<Bn254>, CoreError> {
+    check_key_length(key_bytes)?;
+    let vk = VerifyingKey::<Bn254>::deserialize_compressed(key_bytes)
+        .map_err(|e| CoreError::MalformedKey(e.to_string()))?;
+
+    if vk.alpha_g1.is_zero() {
+        return Err(CoreError::IdentityPoint("vk.alpha_g1"));
+    }
+    if vk.beta_g2.is_zero() || vk.gamma_g2.is_zero() || vk.delta_g2.is_zero() {
+        return Err(CoreError::IdentityPoint("vk G2 element"));
+    }
+    Ok(PreparedVerifyingKey::from(vk))
+}
+
+/// Deserialize a proof, detecting compressed or uncompressed encoding
+pub fn deserialize_proof(proof_bytes: &[u8]) -> Result<Proof<Bn254>, CoreError> {
+    deserialize_proof_as(proof_bytes, ProofEncoding::Auto)
+}
+
+/// Deserialize a proof in a specific encoding
+pub fn deserialize_proof_as(
+    proof_bytes: &[u8],
+    encoding: ProofEncoding,
+) -> Result<Proof<Bn254>, CoreError> {
+    if proof_bytes.len() > MAX_PROOF_SIZE {
+        return Err(CoreError::ProofTooLarge {
+            len: proof_bytes.len(),
+            max: MAX_PROOF_SIZE,
+        });
+    }
+    let encoding = match encoding {
+        ProofEncoding::Auto => ProofEncoding::detect(proof_bytes)?,
+        explicit => explicit,
+    };
+    let (expected, result) = match encoding {
+        ProofEncoding::Uncompressed => (
+            UNCOMPRESSED_PROOF_SIZE,
+            Proof::<Bn254>::deserialize_uncompressed(proof_bytes),
+        ),
+        _ => (
+            COMPRESSED_PROOF_SIZE,
+            Proof::<Bn254>::deserialize_compressed(proof_bytes),
+        ),
+    };
+    if proof_bytes.len() != expected {
+        return Err(CoreError::MalformedProof(format!(
+            "expected {} bytes for {:?} encoding, got {}",
+            expected,
+            encoding,
+            proof_bytes.len()
+        )));
+    }
+    let proof = result.map_err(|e| CoreError::MalformedProof(e.to_string()))?;
+
+    if proof.a.is_zero() || proof.b.is_zero() || proof.c.is_zero() {
+        return Err(CoreError::IdentityPoint("proof element"));
+    }
+    Ok(proof)
+}
+
+/// Run the Groth16 pairing check
+pub fn verify_proof(
+    vk: &PreparedVerifyingKey<Bn254>,
+    proof: &Proof<Bn254>,
+    public_inputs: &[Fr],
+) -> Result<(), CoreError> {
+    match Groth16::<Bn254>::verify_proof(vk, proof, public_inputs) {
+        Ok(true) => Ok(()),
+        _ => Err(CoreError::PairingFailed),
+    }
+}
+
+/// Deserialize a proof and run the pairing check
+pub fn verify_proof_bytes(
+    vk: &PreparedVerifyingKey<Bn254>,
+    proof_bytes: &[u8],
+    public_inputs: &[Fr],
+) -> Result<(), CoreError> {
+    let proof = deserialize_proof(proof_bytes)?;
+    verify_proof(vk, &proof, public_inputs)
+}
+
+/// Whether `timestamp` is within `max_age` seconds of `now`, allowing
+/// `max_skew` seconds of clock drift into the future
+pub fn is_fresh(timestamp: u64, now: u64, max_age: u64, max_skew: u64) -> bool {
+    now.saturating_sub(timestamp) <= max_age && timestamp <= now.saturating_add(max_skew)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_fresh() {
+        assert!(is_fresh(1000, 1000, 60, 10));
+        assert!(is_fresh(950, 1000, 60, 10));
+        assert!(!is_fresh(900, 1000, 60, 10));
+        assert!(is_fresh(1005, 1000, 60, 10));
+        assert!(!is_fresh(1020, 1000, 60, 10));
+    }
+
+    #[test]
+    fn test_malformed_inputs() {
+        assert!(matches!(
+            prepare_verifying_key(&[0u8; 4]),
+            Err(CoreError::MalformedKey(_))
+        ));
+        assert!(matches!(
+            deserialize_proof(&[0u8; 4]),
+            Err(CoreError::MalformedProof(_))
+        ));
+        assert!(matches!(
+            deserialize_proof_as(&[0u8; COMPRESSED_PROOF_SIZE], ProofEncoding::Uncompressed),
+            Err(CoreError::MalformedProof(_))
+        ));
+        assert!(matches!(
+            deserialize_proof(&[0u8; 1 << 20]),
+            Err(CoreError::ProofTooLarge { .. })
+        ));
+        assert!(matches!(
+            prepare_verifying_key(&[0u8; MAX_VERIFYING_KEY_SIZE + 1]),
+            Err(CoreError::KeyTooLarge { .. })
+        ));
+
+        // Declares 2^40 input commitments in a 1 KB key
+        let mut key = [0u8; 1024];
+        key[VERIFYING_KEY_FIXED_SIZE - 8..VERIFYING_KEY_FIXED_SIZE]
+            .copy_from_slice(&(1u64 << 40).to_le_bytes());
+        assert!(matches!(
+            prepare_verifying_key(&key),
+            Err(CoreError::MalformedKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_detect_encoding() {
+        assert_eq!(
+            ProofEncoding::detect(&[0u8; COMPRESSED_PROOF_SIZE]),
+            Ok(ProofEncoding::Compressed)
+        );
+        assert_eq!(
+            ProofEncoding::detect(&[0u8; UNCOMPRESSED_PROOF_SIZE]),
+            Ok(ProofEncoding::Uncompressed)
+        );
+        assert!(ProofEncoding::detect(&[0u8; 200]).is_err());
+    }
+}
@@ -0,0 +1,366 @@
+// Verification audit log
+//
+// Every verification attempt can be appended to an audit sink. Records are
+// hash-chained (each record commits to the previous record's hash), so edits
+// or deletions in the middle of a log are detectable with `verify_chain`.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::canonical::canonical_digest;
+use crate::error::{bail, Result, ResultExt};
+use crate::{PublicInputs, VerificationFailure, VerificationResult};
+
+/// Hash of the (non-existent) record before the first one
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// One verification attempt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub proof_digest: String,
+    pub public_inputs: PublicInputs,
+    pub is_valid: bool,
+    pub reason: Option<VerificationFailure>,
+    /// Key id of the verifying key used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verifying_key_fingerprint: Option<String>,
+    pub recorded_at: u64,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditRecord {
+    /// Build a record for a verification result, chained onto `prev_hash`
+    pub fn new(proof_digest: String, result: &VerificationResult, prev_hash: String) -> Self {
+        let mut record = Self {
+            proof_digest,
+            public_inputs: result.public_inputs.clone(),
+            is_valid: result.is_valid,
+            reason: result.reason.clone(),
+            verifying_key_fingerprint: result.verifying_key_fingerprint.clone(),
+            recorded_at: result.verified_at,
+            prev_hash,
+            hash: String::new(),
+        };
+        record.hash = record.compute_hash();
+        record
+    }
+
+    /// Hash of every field except `hash` itself
+    pub fn compute_hash(&self) -> String {
+        let unhashed = Self {
+            hash: String::new(),
+            ..self.clone()
+        };
+        canonical_digest(&unhashed).expect("audit record serializes")
+    }
+}
+
+/// Destination for audit records
+pub trait AuditSink: Send + Sync {
+    /// Append a record for a verification attempt
+    fn record(&self, proof_digest: &str, result: &VerificationResult) -> Result<AuditRecord>;
+}
+
+/// Append-only JSON Lines audit log
+pub struct JsonlAuditLog {
+    path: PathBuf,
+    state: Mutex<(File, String)>,
+}
+
+impl JsonlAuditLog {
+    /// Open (or create) a log file, resuming the hash chain from its last record
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let last_hash = if path.exists() {
+            read_records(&path)?
+                .last()
+                .map(|record| record.hash.clone())
+                .unwrap_or_else(|| GENESIS_HASH.to_string())
+        } else {
+            GENESIS_HASH.to_string()
+        };
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open audit log {}", path.display()))?;
+
+        Ok(Self {
+            path,
+            state: Mutex::new((file, last_hash)),
+        })
+    }
+
+    /// Path of the log file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AuditSink for JsonlAuditLog {
+    fn record(&self, proof_digest: &str, result: &VerificationResult) -> Result<AuditRecord> {
+        let mut state = self.state.lock().unwrap();
+        let (file, last_hash) = &mut *state;
+
+        let record = AuditRecord::new(proof_digest.to_string(), result, last_hash.clone());
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+        file.write_all(&line)?;
+        file.sync_data()?;
+
+        *last_hash = record.hash.clone();
+        Ok(record)
+    }
+}
+
+/// Read all records from a JSON Lines audit log
+pub fn read_records(path: &Path) -> Result<Vec<AuditRecord>> {
+    let file =
+        File::open(path).with_context(|| format!("Failed to open audit log {}", path.display()))?;
+
+    let mut records = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str(&line)
+            .with_context(|| format!("Malformed audit record on line {}", i + 1))?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Check that records form an unbroken hash chain
+pub fn verify_chain(records: &[AuditRecord]) -> Result<()> {
+    let mut prev_hash = GENESIS_HASH.to_string();
+    for (i, record) in records.iter().enumerate() {
+        if record.prev_hash != prev_hash {
+            bail!(Rejected, "Audit chain broken at record {}", i);
+        }
+        if record.compute_hash() != record.hash {
+            bail!(Rejected, "Audit record {} was modified", i);
+        }
+        prev_hash = record.hash.clone();
+    }
+    Ok(())
+}
+
+/// Check the hash chain of a JSON Lines audit log on disk
+pub fn verify_log_file(path: impl AsRef<Path>) -> Result<usize> {
+    let records = read_records(path.as_ref())?;
+    verify_chain(&records)?;
+    Ok(records.len())
+}
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteAuditLog;
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+    use rusqlite::{params, Connection, OptionalExtension};
+
+    /// Append-only SQLite audit log.
+    ///
+    /// Triggers reject UPDATE and DELETE on the audit table.
+    pub struct SqliteAuditLog {
+        conn: Mutex<Connection>,
+    }
+
+    impl SqliteAuditLog {
+        /// Open (or create) an audit database
+        pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+            let conn = Connection::open(path.as_ref())?;
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS audit_log (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    proof_digest TEXT NOT NULL,
+                    public_inputs TEXT NOT NULL,
+                    is_valid INTEGER NOT NULL,
+                    reason TEXT,
+                    verifying_key_fingerprint TEXT,
+                    recorded_at INTEGER NOT NULL,
+                    prev_hash TEXT NOT NULL,
+                    hash TEXT NOT NULL
+                );
+                CREATE TRIGGER IF NOT EXISTS audit_log_no_update
+                    BEFORE UPDATE ON audit_log
+                    BEGIN SELECT RAISE(ABORT, 'audit log is append-only'); END;
+                CREATE TRIGGER IF NOT EXISTS audit_log_no_delete
+                    BEFORE DELETE ON audit_log
+                    BEGIN SELECT RAISE(ABORT, 'audit log is append-only'); END;",
+            )?;
+            // Databases created before fingerprints were recorded lack the column
+            let has_fingerprint = conn
+                .prepare("SELECT verifying_key_fingerprint FROM audit_log LIMIT 0")
+                .is_ok();
+            if !has_fingerprint {
+                conn.execute(
+                    "ALTER TABLE audit_log ADD COLUMN verifying_key_fingerprint TEXT",
+                    [],
+                )?;
+            }
+            Ok(Self {
+                conn: Mutex::new(conn),
+            })
+        }
+
+        /// Read all records in insertion order
+        pub fn records(&self) -> Result<Vec<AuditRecord>> {
+            let conn = self.conn.lock().unwrap();
+            let mut stmt = conn.prepare(
+                "SELECT proof_digest, public_inputs, is_valid, reason, verifying_key_fingerprint,
+                    recorded_at, prev_hash, hash
+                 FROM audit_log ORDER BY id",
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, bool>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, i64>(5)?,
+                    row.get::<_, String>(6)?,
+                    row.get::<_, String>(7)?,
+                ))
+            })?;
+
+            let mut records = Vec::new();
+            for row in rows {
+                let (
+                    proof_digest,
+                    public_inputs,
+                    is_valid,
+                    reason,
+                    verifying_key_fingerprint,
+                    recorded_at,
+                    prev_hash,
+                    hash,
+                ) = row?;
+                records.push(AuditRecord {
+                    proof_digest,
+                    public_inputs: serde_json::from_str(&public_inputs)?,
+                    is_valid,
+                    reason: reason.map(|r| serde_json::from_str(&r)).transpose()?,
+                    verifying_key_fingerprint,
+                    recorded_at: recorded_at as u64,
+                    prev_hash,
+                    hash,
+                });
+            }
+            Ok(records)
+        }
+    }
+
+    impl AuditSink for SqliteAuditLog {
+        fn record(&self, proof_digest: &str, result: &VerificationResult) -> Result<AuditRecord> {
+            let mut conn = self.conn.lock().unwrap();
+            let tx = conn.transaction()?;
+
+            let prev_hash: String = tx
+                .query_row(
+                    "SELECT hash FROM audit_log ORDER BY id DESC LIMIT 1",
+                    [],
+                    |row| row.get(0),
+                )
+                .optional()?
+                .unwrap_or_else(|| GENESIS_HASH.to_string());
+
+            let record = AuditRecord::new(proof_digest.to_string(), result, prev_hash);
+            tx.execute(
+                "INSERT INTO audit_log
+                    (proof_digest, public_inputs, is_valid, reason, verifying_key_fingerprint,
+                     recorded_at, prev_hash, hash)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    record.proof_digest,
+                    serde_json::to_string(&record.public_inputs)?,
+                    record.is_valid,
+                    record
+                        .reason
+                        .as_ref()
+                        .map(serde_json::to_string)
+                        .transpose()?,
+                    record.verifying_key_fingerprint,
+                    record.recorded_at as i64,
+                    record.prev_hash,
+                    record.hash,
+                ],
+            )?;
+            tx.commit()?;
+
+            Ok(record)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn result(is_valid: bool) -> VerificationResult {
+        VerificationResult {
+            is_valid,
+            reason: None,
+            public_inputs: PublicInputs {
+                document_commitment: "abc123".to_string(),
+                model_hash: "model456".to_string(),
+                timestamp: 1234567890,
+                nonce: None,
+            },
+            verified_at: 1234567900,
+            cache_hit: false,
+            receipt: None,
+            attested_at: None,
+            proof_digest: String::new(),
+            verifying_key_fingerprint: None,
+        }
+    }
+
+    #[test]
+    fn test_jsonl_chain() {
+        let path = std::env::temp_dir().join("zkrag_test_audit.jsonl");
+        let _ = fs::remove_file(&path);
+
+        let log = JsonlAuditLog::open(&path).unwrap();
+        log.record("digest1", &result(true)).unwrap();
+        log.record("digest2", &result(false)).unwrap();
+        drop(log);
+
+        // Reopening resumes the chain
+        let log = JsonlAuditLog::open(&path).unwrap();
+        log.record("digest3", &result(true)).unwrap();
+        assert_eq!(verify_log_file(&path).unwrap(), 3);
+
+        let mut records = read_records(&path).unwrap();
+        records[1].is_valid = true;
+        assert!(verify_chain(&records).is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_append_only() {
+        let path = std::env::temp_dir().join("zkrag_test_audit.sqlite");
+        let _ = fs::remove_file(&path);
+
+        let log = SqliteAuditLog::open(&path).unwrap();
+        log.record("digest1", &result(true)).unwrap();
+        log.record("digest2", &result(false)).unwrap();
+        assert!(verify_chain(&log.records().unwrap()).is_ok());
+
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        assert!(conn.execute("DELETE FROM audit_log", []).is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
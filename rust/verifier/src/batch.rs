@@ -0,0 +1,224 @@
+// Combined batch verification
+//
+// `verify_batch_combined` is `verify_batch` with the pairing checks of a
+// batch folded into one, using a random linear combination of the Groth16
+// equations:
+//
+//     prod e(r_i A_i, B_i) * e(sum r_i L_i, -gamma) * e(sum r_i C_i, -delta)
+//         == e(alpha, beta)^(sum r_i)
+//
+// where L_i is the input commitment of statement i. Work stays linear in the
+// number of proofs; the saving is one final exponentiation instead of n. If
+// the combined check fails, each proof is checked on its own, as `verify`
+// would, so the results say which ones hold.
+//
+// Either way each statement's outcome then goes through the same path as
+// `verify`: nullifiers, receipts, the audit sink and metrics.
+
+use ark_bn254::{Bn254, Fr, G1Projective};
+use ark_ec::pairing::Pairing;
+use ark_ec::CurveGroup;
+use ark_ff::{Field, PrimeField, Zero};
+use ark_groth16::{Groth16, PreparedVerifyingKey, Proof};
+use rand::Rng;
+use std::collections::HashMap;
+use std::time::Instant;
+
+use crate::curve::PreparedKey;
+use crate::error::{bail, Result};
+use crate::{
+    core, unix_now, PublicInputs, QueryVerifier, RegisteredKey, VerificationFailure,
+    VerificationResult, DOCUMENT_QUERY_CIRCUIT_ID, MAX_PROOF_BYTES,
+};
+
+impl QueryVerifier {
+    /// Verify several document query proofs with one combined pairing check,
+    /// returning one result per item in order.
+    ///
+    /// Items must have distinct public inputs; a batch repeating a statement
+    /// is refused as malformed.
+    pub fn verify_batch_combined(
+        &self,
+        items: &[(Vec<u8>, PublicInputs)],
+    ) -> Result<Vec<VerificationResult>> {
+        let mut seen = HashMap::with_capacity(items.len());
+        for (i, (_, public_inputs)) in items.iter().enumerate() {
+            if let Some(first) = seen.insert(public_inputs.digest(), i) {
+                bail!(
+                    Malformed,
+                    "Item {} repeats the public inputs of item {}",
+                    i,
+                    first
+                );
+            }
+        }
+        if let Some(metrics) = &self.metrics {
+            metrics.record_batch(items.len());
+        }
+        let started = Instant::now();
+        let now = unix_now()?;
+
+        // The key every proof passed the combined check under, if they did
+        let combined = self
+            .keys
+            .current_at(DOCUMENT_QUERY_CIRCUIT_ID, now)
+            .filter(|registered| self.check_combined(registered, items));
+        items
+            .iter()
+            .map(|(proof_bytes, public_inputs)| {
+                let (key, outcome) = match combined {
+                    Some(registered) => {
+                        let outcome = self
+                            .apply_policies(registered, public_inputs, now)
+                            .map(|()| false);
+                        (Some(registered), outcome)
+                    }
+                    None => self.check_with_grace(proof_bytes, public_inputs, now),
+                };
+                self.finish(
+                    proof_bytes,
+                    public_inputs.clone(),
+                    key,
+                    outcome,
+                    now,
+                    started,
+                )
+            })
+            .collect()
+    }
+
+    /// Whether every proof passes one combined pairing check under
+    /// `registered`
+    fn check_combined(
+        &self,
+        registered: &RegisteredKey,
+        items: &[(Vec<u8>, PublicInputs)],
+    ) -> bool {
+        let PreparedKey::Bn254(key) = &registered.key else {
+            return false;
+        };
+        let mut proofs = Vec::with_capacity(items.len());
+        for (proof_bytes, public_inputs) in items {
+            if proof_bytes.len() > MAX_PROOF_BYTES {
+                return false;
+            }
+            match core::deserialize_proof_as(proof_bytes, self.proof_encoding) {
+                Ok(proof) => proofs.push((proof, public_inputs.to_field_elements())),
+                Err(_) => return false,
+            }
+        }
+        !proofs.is_empty() && combined_pairing_check(key, &proofs).is_ok()
+    }
+}
+
+/// One randomized pairing check over every proof
+fn combined_pairing_check(
+    key: &PreparedVerifyingKey<Bn254>,
+    proofs: &[(Proof<Bn254>, Vec<Fr>)],
+) -> std::result::Result<(), VerificationFailure> {
+    let mut rng = rand::thread_rng();
+    let mut r_sum = Fr::zero();
+    let mut inputs_acc = G1Projective::zero();
+    let mut c_acc = G1Projective::zero();
+    let mut g1 = Vec::with_capacity(proofs.len() + 2);
+    let mut g2 = Vec::with_capacity(proofs.len() + 2);
+
+    for (proof, inputs) in proofs {
+        // 128-bit challenges keep the soundness error at 2^-128
+        let r = Fr::from(rng.gen::<u128>());
+        let prepared = Groth16::<Bn254>::prepare_inputs(key, inputs)
+            .map_err(|_| VerificationFailure::PairingFailed)?;
+
+        r_sum += r;
+        inputs_acc += prepared * r;
+        c_acc += proof.c * r;
+        g1.push((proof.a * r).into_affine());
+        g2.push(<Bn254 as Pairing>::G2Prepared::from(proof.b));
+    }
+    g1.push(inputs_acc.into_affine());
+    g2.push(key.gamma_g2_neg_pc.clone());
+    g1.push(c_acc.into_affine());
+    g2.push(key.delta_g2_neg_pc.clone());
+
+    let lhs = Bn254::final_exponentiation(Bn254::multi_miller_loop(g1, g2))
+        .ok_or(VerificationFailure::PairingFailed)?;
+    if lhs.0 == key.alpha_g1_beta_g2.pow(r_sum.into_bigint()) {
+        Ok(())
+    } else {
+        Err(VerificationFailure::PairingFailed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{circuit, fixture};
+    use crate::{MemoryNullifierStore, VerifierError};
+    use ark_serialize::CanonicalSerialize;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_verify_batch_combined() {
+        let (pk, vk_bytes, _) = fixture();
+        let mut rng = ark_std::test_rng();
+        let statements: Vec<_> = (0..4)
+            .map(|i| {
+                let public_inputs = PublicInputs {
+                    document_commitment: format!("doc{}", i),
+                    model_hash: "model456".to_string(),
+                    timestamp: 1234567890 + i,
+                    nonce: None,
+                };
+                let proof = Groth16::<Bn254>::create_random_proof_with_reduction(
+                    circuit(&public_inputs),
+                    &pk,
+                    &mut rng,
+                )
+                .unwrap();
+                let mut proof_bytes = Vec::new();
+                proof.serialize_compressed(&mut proof_bytes).unwrap();
+                (proof_bytes, public_inputs)
+            })
+            .collect();
+
+        let verifier = QueryVerifier::builder()
+            .key_bytes(vk_bytes.clone())
+            .build()
+            .unwrap();
+        let results = verifier.verify_batch_combined(&statements).unwrap();
+        assert!(results.iter().all(|result| result.is_valid));
+
+        let mut tampered = statements.clone();
+        tampered[2].1.timestamp += 1;
+        let results = verifier.verify_batch_combined(&tampered).unwrap();
+        let valid: Vec<bool> = results.iter().map(|result| result.is_valid).collect();
+        assert_eq!(valid, [true, true, false, true]);
+        assert_eq!(results[2].reason, Some(VerificationFailure::PairingFailed));
+
+        // The same statement twice in one batch
+        let repeated = [statements[0].clone(), statements[0].clone()];
+        let error = verifier.verify_batch_combined(&repeated).unwrap_err();
+        assert!(matches!(error, VerifierError::Malformed(_)));
+
+        // Nullifiers are spent as by `verify`
+        let verifier = QueryVerifier::builder()
+            .key_bytes(vk_bytes)
+            .nullifier_store(Arc::new(MemoryNullifierStore::new()))
+            .build()
+            .unwrap();
+        let (proof_bytes, public_inputs) = &statements[1];
+        assert!(
+            verifier
+                .verify(proof_bytes, public_inputs.clone())
+                .unwrap()
+                .is_valid
+        );
+        let results = verifier.verify_batch_combined(&statements).unwrap();
+        let valid: Vec<bool> = results.iter().map(|result| result.is_valid).collect();
+        assert_eq!(valid, [true, false, true, true]);
+        assert!(matches!(
+            results[1].reason,
+            Some(VerificationFailure::Replayed { .. })
+        ));
+    }
+}
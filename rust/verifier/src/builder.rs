@@ -0,0 +1,275 @@
+// Verifier construction
+//
+// Collects key sources, policies, cache, audit sink, and metrics, then builds
+// an immutable QueryVerifier that can be shared across threads (e.g. as axum
+// state behind an Arc) without further locking.
+
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::{Result, ResultExt};
+use crate::{
+    keys, AuditSink, Curve, FreshnessPolicy, KeyQuorum, KeyRegistry, NullifierStore, ProofEncoding,
+    QueryVerifier, ReceiptSigner, RevocationRegistry, TimestampAuthority, VerificationCache,
+    VerificationPolicy, VerifierMetrics, VerifierPolicy, DOCUMENT_QUERY_CIRCUIT_ID,
+};
+
+/// Where a verifying key is loaded from at build time
+enum KeySource {
+    Bytes(Vec<u8>),
+    Path(PathBuf),
+    Url {
+        url: String,
+        expected_sha256: String,
+    },
+    /// Key directory, skipped when it holds no key
+    Directory(PathBuf),
+}
+
+/// Builder for [`QueryVerifier`]
+#[derive(Default)]
+pub struct VerifierBuilder {
+    keys: Vec<(String, Curve, KeySource)>,
+    audit: Option<Arc<dyn AuditSink>>,
+    policies: Vec<Arc<dyn VerificationPolicy>>,
+    policy_path: Option<PathBuf>,
+    cache: Option<Arc<VerificationCache>>,
+    signer: Option<Arc<ReceiptSigner>>,
+    metrics: Option<Arc<VerifierMetrics>>,
+    nullifiers: Option<Arc<dyn NullifierStore>>,
+    timestamp_authority: Option<Arc<TimestampAuthority>>,
+    quorum: Option<Arc<KeyQuorum>>,
+    proof_encoding: ProofEncoding,
+}
+
+impl VerifierBuilder {
+    /// Create a builder with no keys and default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Document query verifying key from serialized bytes
+    pub fn key_bytes(self, bytes: impl Into<Vec<u8>>) -> Self {
+        self.circuit_key_bytes(DOCUMENT_QUERY_CIRCUIT_ID, bytes)
+    }
+
+    /// Verifying key for another circuit from serialized bytes
+    pub fn circuit_key_bytes(self, circuit_id: &str, bytes: impl Into<Vec<u8>>) -> Self {
+        self.curve_key_bytes(circuit_id, Curve::Bn254, bytes)
+    }
+
+    /// Verifying key over a specific curve from serialized bytes
+    pub fn curve_key_bytes(
+        mut self,
+        circuit_id: &str,
+        curve: Curve,
+        bytes: impl Into<Vec<u8>>,
+    ) -> Self {
+        self.keys.push((
+            circuit_id.to_string(),
+            curve,
+            KeySource::Bytes(bytes.into()),
+        ));
+        self
+    }
+
+    /// Document query verifying key read from a file
+    pub fn key_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.keys.push((
+            DOCUMENT_QUERY_CIRCUIT_ID.to_string(),
+            Curve::Bn254,
+            KeySource::Path(path.into()),
+        ));
+        self
+    }
+
+    /// Document query verifying key fetched from a URL, pinned to its hex
+    /// SHA-256 digest
+    pub fn key_url(mut self, url: &str, expected_sha256: &str) -> Self {
+        self.keys.push((
+            DOCUMENT_QUERY_CIRCUIT_ID.to_string(),
+            Curve::Bn254,
+            KeySource::Url {
+                url: url.to_string(),
+                expected_sha256: expected_sha256.to_string(),
+            },
+        ));
+        self
+    }
+
+    /// Document query verifying key discovered in a key directory laid out
+    /// like the prover's, if one is there
+    pub fn key_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.keys.push((
+            DOCUMENT_QUERY_CIRCUIT_ID.to_string(),
+            Curve::Bn254,
+            KeySource::Directory(dir.into()),
+        ));
+        self
+    }
+
+    /// Document query verifying key discovered in the standard key
+    /// directory (see [`keys::default_key_dir`]), if one is there
+    pub fn default_key_dir(self) -> Self {
+        match keys::default_key_dir() {
+            Some(dir) => self.key_dir(dir),
+            None => self,
+        }
+    }
+
+    /// Reject proofs referencing entries in a revocation registry
+    pub fn revocations(self, revocations: Arc<RevocationRegistry>) -> Self {
+        self.add_policy(revocations)
+    }
+
+    /// Record every verification attempt in an audit sink
+    pub fn audit_sink(mut self, audit: Arc<dyn AuditSink>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Reject proofs whose timestamp is older than `max_age`
+    pub fn max_proof_age(self, max_age: Duration) -> Self {
+        self.add_policy(Arc::new(FreshnessPolicy::new(max_age)))
+    }
+
+    /// Only accept proofs for models on an allowlist
+    pub fn policy(self, policy: VerifierPolicy) -> Self {
+        self.add_policy(Arc::new(policy))
+    }
+
+    /// Append a rule to the post-verification policy chain. Rules run in the
+    /// order they were added, after the pairing check.
+    pub fn add_policy(mut self, policy: Arc<dyn VerificationPolicy>) -> Self {
+        self.policies.push(policy);
+        self
+    }
+
+    /// Load a model allowlist from a TOML or JSON file at build time, run
+    /// after every other policy
+    pub fn policy_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.policy_path = Some(path.into());
+        self
+    }
+
+    /// Skip the pairing check for proofs that already verified
+    pub fn cache(mut self, cache: Arc<VerificationCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Use a fresh verification cache holding up to `capacity` entries
+    pub fn cache_capacity(self, capacity: NonZeroUsize) -> Self {
+        self.cache(Arc::new(VerificationCache::new(capacity)))
+    }
+
+    /// Attach a signed receipt to every verification result
+    pub fn receipt_signer(mut self, signer: Arc<ReceiptSigner>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Record counters and latency for every verification
+    pub fn metrics(mut self, metrics: Arc<VerifierMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Reject proofs whose nullifier was already recorded in a store
+    pub fn nullifier_store(mut self, nullifiers: Arc<dyn NullifierStore>) -> Self {
+        self.nullifiers = Some(nullifiers);
+        self
+    }
+
+    /// Accept RFC 3161 tokens signed by this authority in `verify_timestamped`
+    pub fn timestamp_authority(mut self, authority: Arc<TimestampAuthority>) -> Self {
+        self.timestamp_authority = Some(authority);
+        self
+    }
+
+    /// Accept statements in `verify_quorum` once enough quorum keys agree
+    pub fn quorum(mut self, quorum: Arc<KeyQuorum>) -> Self {
+        self.quorum = Some(quorum);
+        self
+    }
+
+    /// Require a specific proof encoding instead of detecting it
+    pub fn proof_encoding(mut self, encoding: ProofEncoding) -> Self {
+        self.proof_encoding = encoding;
+        self
+    }
+
+    /// Load every key source and construct the verifier
+    pub fn build(self) -> Result<QueryVerifier> {
+        let mut registry = KeyRegistry::new();
+        for (circuit_id, curve, source) in self.keys {
+            let bytes = match source {
+                KeySource::Bytes(bytes) => bytes,
+                KeySource::Path(path) => keys::read_key_file(&path)?,
+                KeySource::Url {
+                    url,
+                    expected_sha256,
+                } => keys::fetch_key(&url, &expected_sha256)?,
+                KeySource::Directory(dir) => match keys::discover_key(&dir)? {
+                    Some(bytes) => bytes,
+                    None => continue,
+                },
+            };
+            registry
+                .insert_for_curve(&circuit_id, curve, &bytes)
+                .with_context(|| format!("Failed to load verifying key for {}", circuit_id))?;
+        }
+
+        if let Some(quorum) = &self.quorum {
+            quorum.validate()?;
+        }
+
+        let mut policies = self.policies;
+        if let Some(path) = self.policy_path {
+            policies.push(Arc::new(VerifierPolicy::from_file(&path)?));
+        }
+
+        Ok(QueryVerifier {
+            keys: registry,
+            audit: self.audit,
+            policies,
+            cache: self.cache,
+            signer: self.signer,
+            metrics: self.metrics,
+            nullifiers: self.nullifiers,
+            timestamp_authority: self.timestamp_authority,
+            quorum: self.quorum,
+            proof_encoding: self.proof_encoding,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_verifier_is_send_sync() {
+        assert_send_sync::<QueryVerifier>();
+        assert_send_sync::<Arc<QueryVerifier>>();
+    }
+
+    #[test]
+    fn test_build_rejects_bad_key() {
+        assert!(VerifierBuilder::new()
+            .key_bytes(vec![0u8; 3])
+            .build()
+            .is_err());
+        assert!(VerifierBuilder::new()
+            .key_path("/nonexistent/zkrag_key.bin")
+            .build()
+            .is_err());
+
+        let verifier = VerifierBuilder::new().build().unwrap();
+        assert!(verifier.keys().is_empty());
+    }
+}
@@ -0,0 +1,178 @@
+// Offline verification bundles
+//
+// A bundle is a single self-contained JSON file holding a proof envelope, the
+// verifying key it was checked against, and the circuit metadata needed to
+// interpret it. Auditors can re-verify a bundle on an air-gapped machine,
+// optionally pinning the key to a fingerprint obtained out of band.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::envelope::hex_bytes;
+use crate::error::{bail, Result, ResultExt, VerifierError};
+use crate::keys::{check_digest, key_digest};
+use crate::{core, Curve, ProofEnvelope, PublicInputs, QueryVerifier, VerificationResult};
+
+/// Bundle format version produced by this crate
+pub const BUNDLE_VERSION: u16 = 1;
+
+/// Largest accepted bundle file: an envelope plus a hex-encoded key
+pub const MAX_BUNDLE_SIZE: usize = 1024 * 1024;
+
+/// What the bundled proof was produced for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitMetadata {
+    pub circuit_id: String,
+    pub proof_system: String,
+    pub curve: String,
+    pub num_public_inputs: usize,
+}
+
+impl CircuitMetadata {
+    /// Metadata for Groth16 proofs, the only proof system verified here
+    fn groth16(circuit_id: &str, curve: Curve) -> Self {
+        Self {
+            circuit_id: circuit_id.to_string(),
+            proof_system: "groth16".to_string(),
+            curve: curve.to_string(),
+            num_public_inputs: core::NUM_PUBLIC_INPUTS,
+        }
+    }
+}
+
+/// Everything needed to re-verify a proof without service access
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationBundle {
+    pub version: u16,
+    pub circuit: CircuitMetadata,
+    /// Hex SHA-256 of `verifying_key`, matching its key registry id
+    pub key_fingerprint: String,
+    #[serde(with = "hex_bytes")]
+    pub verifying_key: Vec<u8>,
+    pub envelope: ProofEnvelope,
+}
+
+impl VerificationBundle {
+    /// Parse a JSON bundle
+    pub fn from_json(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() > MAX_BUNDLE_SIZE {
+            bail!(
+                Malformed,
+                "Bundle is {} bytes, limit is {}",
+                bytes.len(),
+                MAX_BUNDLE_SIZE
+            );
+        }
+        let bundle: Self =
+            serde_json::from_slice(bytes).context("Malformed verification bundle")?;
+        if bundle.version != BUNDLE_VERSION {
+            bail!(Malformed, "Unsupported bundle version {}", bundle.version);
+        }
+        Ok(bundle)
+    }
+
+    /// Serialize as pretty-printed JSON
+    pub fn to_json(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec_pretty(self)?)
+    }
+
+    /// Read a bundle file
+    pub fn read_from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = fs::read(path)
+            .with_context(|| format!("Failed to read bundle from {}", path.display()))?;
+        Self::from_json(&bytes)
+    }
+
+    /// Write the bundle to a file
+    pub fn write_to_file(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        fs::write(path, self.to_json()?)
+            .with_context(|| format!("Failed to write bundle to {}", path.display()))
+    }
+}
+
+/// Package a BN254 document query proof with the verifying key it verifies under
+pub fn export_bundle(
+    proof_bytes: &[u8],
+    public_inputs: PublicInputs,
+    verifying_key: &[u8],
+) -> Result<VerificationBundle> {
+    // Fail at export time rather than months later on the auditor's machine
+    core::prepare_verifying_key(verifying_key).map_err(VerifierError::from)?;
+
+    let key_fingerprint = key_digest(verifying_key);
+    let envelope = ProofEnvelope::new(proof_bytes.to_vec(), public_inputs)
+        .with_key_id(key_fingerprint.clone());
+
+    Ok(VerificationBundle {
+        version: BUNDLE_VERSION,
+        circuit: CircuitMetadata::groth16(&envelope.circuit_id, envelope.curve),
+        key_fingerprint,
+        verifying_key: verifying_key.to_vec(),
+        envelope,
+    })
+}
+
+/// Verify a bundle using only its own contents, optionally pinning the
+/// bundled key to a trusted fingerprint
+pub fn verify_bundle(
+    bundle: &VerificationBundle,
+    trusted_fingerprint: Option<&str>,
+) -> Result<VerificationResult> {
+    let expected = CircuitMetadata::groth16(&bundle.envelope.circuit_id, bundle.envelope.curve);
+    if bundle.circuit != expected {
+        bail!(
+            Malformed,
+            "Unsupported bundle circuit: {} over {} ({} public inputs)",
+            bundle.circuit.proof_system,
+            bundle.circuit.curve,
+            bundle.circuit.num_public_inputs
+        );
+    }
+
+    check_digest(&bundle.verifying_key, &bundle.key_fingerprint)
+        .context("Bundle key does not match its fingerprint")?;
+    if let Some(trusted) = trusted_fingerprint {
+        check_digest(&bundle.verifying_key, trusted)
+            .context("Bundle key does not match the trusted fingerprint")?;
+    }
+
+    let verifier = QueryVerifier::builder()
+        .curve_key_bytes(
+            &bundle.circuit.circuit_id,
+            bundle.envelope.curve,
+            bundle.verifying_key.clone(),
+        )
+        .build()?;
+    let envelope = bundle
+        .envelope
+        .clone()
+        .with_key_id(bundle.key_fingerprint.clone());
+    verifier.verify_envelope(&envelope, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{fixture, public_inputs};
+
+    #[test]
+    fn test_bundle_roundtrip() {
+        let (_, vk_bytes, proof_bytes) = fixture();
+        let bundle = export_bundle(&proof_bytes, public_inputs(), &vk_bytes).unwrap();
+
+        let parsed = VerificationBundle::from_json(&bundle.to_json().unwrap()).unwrap();
+        assert_eq!(parsed, bundle);
+
+        let result = verify_bundle(&parsed, Some(&bundle.key_fingerprint)).unwrap();
+        assert!(result.is_valid);
+
+        assert!(verify_bundle(&parsed, Some(&key_digest(b"other key"))).is_err());
+
+        let mut tampered = parsed.clone();
+        tampered.envelope.public_inputs.timestamp += 1;
+        assert!(!verify_bundle(&tampered, None).unwrap().is_valid);
+    }
+}
@@ -0,0 +1,97 @@
+// Verified-proof cache
+//
+// Remembers proofs that passed the pairing check, keyed by verifying key,
+// proof bytes, and public inputs, so retried submissions of the same proof
+// skip the expensive pairing. Only the cryptographic result is cached:
+// revocation, freshness, and policy checks still run on every call.
+
+use lru::LruCache;
+use sha2::{Digest, Sha256};
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+
+use crate::PublicInputs;
+
+/// Cache key: SHA-256 over key id, proof bytes, and public inputs
+pub type CacheKey = [u8; 32];
+
+/// Compute the cache key for a verification
+pub fn cache_key(key_id: &str, proof_bytes: &[u8], public_inputs: &PublicInputs) -> CacheKey {
+    let mut hasher = Sha256::new();
+    for part in [
+        key_id.as_bytes(),
+        proof_bytes,
+        public_inputs.digest().as_bytes(),
+    ] {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+/// Thread-safe LRU set of verified proofs
+pub struct VerificationCache {
+    entries: Mutex<LruCache<CacheKey, ()>>,
+}
+
+impl VerificationCache {
+    /// Create a cache holding up to `capacity` entries
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            entries: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// Whether the key is cached, marking it as recently used
+    pub fn contains(&self, key: &CacheKey) -> bool {
+        self.entries.lock().unwrap().get(key).is_some()
+    }
+
+    /// Record a verified proof
+    pub fn insert(&self, key: CacheKey) {
+        self.entries.lock().unwrap().put(key, ());
+    }
+
+    /// Number of cached entries
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Whether the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop every entry
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lru_eviction() {
+        let inputs = PublicInputs {
+            document_commitment: "abc123".to_string(),
+            model_hash: "model456".to_string(),
+            timestamp: 1234567890,
+            nonce: None,
+        };
+        let cache = VerificationCache::new(NonZeroUsize::new(2).unwrap());
+        let a = cache_key("key", b"proof a", &inputs);
+        let b = cache_key("key", b"proof b", &inputs);
+        let c = cache_key("key", b"proof c", &inputs);
+
+        cache.insert(a);
+        cache.insert(b);
+        assert!(cache.contains(&a));
+        cache.insert(c);
+
+        assert!(cache.contains(&a));
+        assert!(!cache.contains(&b));
+        assert!(cache.contains(&c));
+    }
+}
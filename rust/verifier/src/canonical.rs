@@ -0,0 +1,113 @@
+// Canonical JSON
+//
+// Deterministic JSON encoding used wherever structured data is hashed or
+// signed (public input digests, audit records, receipts, envelopes), so the
+// same value hashes identically in every language. Follows RFC 8785 (JCS) for
+// the values this crate produces:
+//
+// - object keys sorted by UTF-16 code units, no duplicate keys
+// - no insignificant whitespace
+// - strings escaped minimally (quote, backslash, and control characters)
+// - integers written without exponent or fraction; non-integral numbers are
+//   rejected rather than risk cross-language float formatting differences
+
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::error::{bail, Result};
+
+/// Serialize a value as canonical JSON
+pub fn to_canonical_json<T: Serialize + ?Sized>(value: &T) -> Result<String> {
+    let value = serde_json::to_value(value)?;
+    let mut out = String::new();
+    write_value(&mut out, &value)?;
+    Ok(out)
+}
+
+/// Hex-encoded SHA-256 of a value's canonical JSON
+pub fn canonical_digest<T: Serialize + ?Sized>(value: &T) -> Result<String> {
+    let json = to_canonical_json(value)?;
+    Ok(hex::encode(Sha256::digest(json.as_bytes())))
+}
+
+fn write_value(out: &mut String, value: &Value) -> Result<()> {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => {
+            if n.is_f64() {
+                bail!(
+                    Malformed,
+                    "Canonical JSON does not support non-integral number {}",
+                    n
+                );
+            }
+            out.push_str(&n.to_string());
+        }
+        Value::String(s) => write_string(out, s),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(out, item)?;
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+
+            out.push('{');
+            for (i, (key, item)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(out, key);
+                out.push(':');
+                write_value(out, item)?;
+            }
+            out.push('}');
+        }
+    }
+    Ok(())
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{08}' => out.push_str("\\b"),
+            '\u{0c}' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_canonical_encoding() {
+        let value = json!({
+            "b": [1, "two", null, true],
+            "a": {"z": "line\nbreak \"quoted\"", "y": -3},
+        });
+        assert_eq!(
+            to_canonical_json(&value).unwrap(),
+            r#"{"a":{"y":-3,"z":"line\nbreak \"quoted\""},"b":[1,"two",null,true]}"#
+        );
+        assert!(to_canonical_json(&json!({"x": 1.5})).is_err());
+    }
+}
@@ -0,0 +1,251 @@
+// Pairing curves
+//
+// Proofs are verified over BN254 by default, or over BLS12-381 for
+// deployments that want its higher security margin. BN254 goes through
+// zkrag-verifier-core so it shares code with the no_std targets; BLS12-381
+// uses the generic helpers below.
+
+use ark_bls12_381::Bls12_381;
+use ark_ec::pairing::Pairing;
+use ark_ec::AffineRepr;
+use ark_ff::PrimeField;
+use ark_groth16::{Groth16, PreparedVerifyingKey, Proof, VerifyingKey};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use zkrag_circuits::utils::{bound_commitment, public_input_to_field};
+use zkrag_verifier_core::CoreError;
+
+use crate::{core, ProofEncoding, PublicInputs};
+
+/// Pairing curve a proof and verifying key are defined over
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Curve {
+    #[default]
+    Bn254,
+    Bls12_381,
+}
+
+impl Curve {
+    /// Name used in envelopes and bundles
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Bn254 => "bn254",
+            Self::Bls12_381 => "bls12_381",
+        }
+    }
+}
+
+impl fmt::Display for Curve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Curve {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, String> {
+        match name {
+            "bn254" => Ok(Self::Bn254),
+            "bls12_381" => Ok(Self::Bls12_381),
+            _ => Err(format!("unknown curve {}", name)),
+        }
+    }
+}
+
+/// A prepared verifying key over one of the supported curves
+#[derive(Clone)]
+pub enum PreparedKey {
+    Bn254(Box<PreparedVerifyingKey<ark_bn254::Bn254>>),
+    Bls12_381(Box<PreparedVerifyingKey<Bls12_381>>),
+}
+
+impl PreparedKey {
+    /// Deserialize and prepare a compressed verifying key over `curve`
+    pub fn from_bytes(curve: Curve, key_bytes: &[u8]) -> Result<Self, CoreError> {
+        match curve {
+            Curve::Bn254 => {
+                core::prepare_verifying_key(key_bytes).map(|key| Self::Bn254(Box::new(key)))
+            }
+            Curve::Bls12_381 => {
+                prepare_verifying_key(key_bytes).map(|key| Self::Bls12_381(Box::new(key)))
+            }
+        }
+    }
+
+    /// Curve the key is defined over
+    pub fn curve(&self) -> Curve {
+        match self {
+            Self::Bn254(_) => Curve::Bn254,
+            Self::Bls12_381(_) => Curve::Bls12_381,
+        }
+    }
+
+    /// Number of public inputs the key verifies
+    pub fn num_public_inputs(&self) -> usize {
+        // gamma_abc_g1 has one more entry than inputs, for the constant one
+        match self {
+            Self::Bn254(key) => key.vk.gamma_abc_g1.len() - 1,
+            Self::Bls12_381(key) => key.vk.gamma_abc_g1.len() - 1,
+        }
+    }
+
+    /// Whether the pairing precomputed when the key was prepared still
+    /// matches the key's elements
+    pub fn is_consistent(&self) -> bool {
+        match self {
+            Self::Bn254(key) => precomputation_matches(key),
+            Self::Bls12_381(key) => precomputation_matches(key),
+        }
+    }
+
+    /// The compressed verifying key, as accepted by `from_bytes`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut key_bytes = Vec::new();
+        let serialized = match self {
+            Self::Bn254(key) => key.vk.serialize_compressed(&mut key_bytes),
+            Self::Bls12_381(key) => key.vk.serialize_compressed(&mut key_bytes),
+        };
+        serialized.expect("serializing into a Vec doesn't fail");
+        key_bytes
+    }
+
+    /// Deserialize a proof and run the pairing check against public inputs
+    pub fn verify(
+        &self,
+        proof_bytes: &[u8],
+        encoding: ProofEncoding,
+        public_inputs: &PublicInputs,
+    ) -> Result<(), CoreError> {
+        match self {
+            Self::Bn254(key) => {
+                let proof = core::deserialize_proof_as(proof_bytes, encoding)?;
+                core::verify_proof(key, &proof, &public_inputs.to_field_elements())
+            }
+            Self::Bls12_381(key) => {
+                let proof = deserialize_proof_as::<Bls12_381>(proof_bytes, encoding)?;
+                let inputs = public_inputs_to_fields(public_inputs);
+                match Groth16::<Bls12_381>::verify_proof(key, &proof, &inputs) {
+                    Ok(true) => Ok(()),
+                    _ => Err(CoreError::PairingFailed),
+                }
+            }
+        }
+    }
+}
+
+/// Field elements in circuit order over any scalar field
+fn public_inputs_to_fields<F: PrimeField>(public_inputs: &PublicInputs) -> Vec<F> {
+    vec![
+        public_input_to_field(&bound_commitment(
+            &public_inputs.document_commitment,
+            public_inputs.nonce.as_deref(),
+        )),
+        public_input_to_field(&public_inputs.model_hash),
+        F::from(public_inputs.timestamp),
+    ]
+}
+
+fn precomputation_matches<E: Pairing>(key: &PreparedVerifyingKey<E>) -> bool {
+    E::pairing(key.vk.alpha_g1, key.vk.beta_g2).0 == key.alpha_g1_beta_g2
+}
+
+/// Generic counterpart of `zkrag_verifier_core::prepare_verifying_key`, with
+/// the same length, subgroup, and identity checks
+fn prepare_verifying_key<E: Pairing>(
+    key_bytes: &[u8],
+) -> Result<PreparedVerifyingKey<E>, CoreError> {
+    if key_bytes.len() > core::MAX_VERIFYING_KEY_SIZE {
+        return Err(CoreError::KeyTooLarge {
+            len: key_bytes.len(),
+            max: core::MAX_VERIFYING_KEY_SIZE,
+        });
+    }
+
+    // Check the declared input count against the length before allocating
+    let g1_size = E::G1Affine::generator().compressed_size();
+    let fixed_size = g1_size + 3 * E::G2Affine::generator().compressed_size() + 8;
+    let count = key_bytes
+        .get(fixed_size - 8..fixed_size)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or_else(|| CoreError::MalformedKey("truncated verifying key".to_string()))?;
+    let expected = usize::try_from(count)
+        .ok()
+        .and_then(|count| count.checked_mul(g1_size))
+        .and_then(|size| size.checked_add(fixed_size));
+    if expected != Some(key_bytes.len()) {
+        return Err(CoreError::MalformedKey(format!(
+            "verifying key length {} does not match its input count",
+            key_bytes.len()
+        )));
+    }
+
+    let vk = VerifyingKey::<E>::deserialize_compressed(key_bytes)
+        .map_err(|e| CoreError::MalformedKey(e.to_string()))?;
+    if vk.alpha_g1.is_zero() {
+        return Err(CoreError::IdentityPoint("vk.alpha_g1"));
+    }
+    if vk.beta_g2.is_zero() || vk.gamma_g2.is_zero() || vk.delta_g2.is_zero() {
+        return Err(CoreError::IdentityPoint("vk G2 element"));
+    }
+    Ok(PreparedVerifyingKey::from(vk))
+}
+
+/// Deserialize a proof, detecting the encoding from the curve's point sizes
+fn deserialize_proof_as<E: Pairing>(
+    proof_bytes: &[u8],
+    encoding: ProofEncoding,
+) -> Result<Proof<E>, CoreError> {
+    let compressed = Proof::<E>::default().compressed_size();
+    let uncompressed = Proof::<E>::default().uncompressed_size();
+    if proof_bytes.len() > uncompressed {
+        return Err(CoreError::ProofTooLarge {
+            len: proof_bytes.len(),
+            max: uncompressed,
+        });
+    }
+    let encoding = match encoding {
+        ProofEncoding::Auto if proof_bytes.len() == uncompressed => ProofEncoding::Uncompressed,
+        ProofEncoding::Auto => ProofEncoding::Compressed,
+        explicit => explicit,
+    };
+
+    let (expected, result) = match encoding {
+        ProofEncoding::Uncompressed => (
+            uncompressed,
+            Proof::<E>::deserialize_uncompressed(proof_bytes),
+        ),
+        _ => (compressed, Proof::<E>::deserialize_compressed(proof_bytes)),
+    };
+    if proof_bytes.len() != expected {
+        return Err(CoreError::MalformedProof(format!(
+            "expected {} bytes for {:?} encoding, got {}",
+            expected,
+            encoding,
+            proof_bytes.len()
+        )));
+    }
+    let proof = result.map_err(|e| CoreError::MalformedProof(e.to_string()))?;
+
+    if proof.a.is_zero() || proof.b.is_zero() || proof.c.is_zero() {
+        return Err(CoreError::IdentityPoint("proof element"));
+    }
+    Ok(proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_curve_names() {
+        assert_eq!(serde_json::to_string(&Curve::Bn254).unwrap(), r#""bn254""#);
+        assert_eq!(
+            serde_json::to_string(&Curve::Bls12_381).unwrap(),
+            format!(r#""{}""#, Curve::Bls12_381)
+        );
+        assert!(PreparedKey::from_bytes(Curve::Bls12_381, &[0u8; 4]).is_err());
+    }
+}
@@ -0,0 +1,175 @@
+// Dev-mode diagnostic verification
+//
+// An invalid proof says nothing about why it is invalid. Given the witness the
+// prover used, `verify_diagnostic` re-synthesizes the circuit with constraint
+// tracing enabled and reports what the pairing check can't:
+//
+// - the first unsatisfied constraint, named by its gadget namespace path
+// - public inputs where the witness disagrees with the claimed values, the
+//   usual cause when every constraint holds but the pairing check fails
+//
+// Synthesis is slow and the witness is private, so this is behind the
+// `diagnostics` feature and meant for development only.
+
+use ark_bn254::Fr;
+use ark_ff::Zero;
+use ark_relations::r1cs::{
+    ConstraintLayer, ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, TracingMode,
+};
+use serde::{Deserialize, Serialize};
+use tracing_subscriber::layer::SubscriberExt;
+
+use crate::error::{Result, VerifierError};
+use crate::{PublicInputs, QueryVerifier, VerificationResult};
+
+/// Public input names in circuit order
+const PUBLIC_INPUT_NAMES: [&str; 3] = ["document_commitment", "model_hash", "timestamp"];
+
+/// Verification result with witness diagnostics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub result: VerificationResult,
+    /// Whether the witness satisfies every constraint
+    pub witness_satisfied: bool,
+    /// Namespace path of the first unsatisfied constraint
+    pub failing_constraint: Option<String>,
+    /// Public inputs whose witness value differs from the claimed value
+    pub mismatched_inputs: Vec<String>,
+    pub num_constraints: usize,
+}
+
+impl QueryVerifier {
+    /// Verify a proof and diagnose the witness it was produced from
+    pub fn verify_diagnostic<C: ConstraintSynthesizer<Fr>>(
+        &self,
+        proof_bytes: &[u8],
+        public_inputs: PublicInputs,
+        witness: C,
+    ) -> Result<Diagnostic> {
+        let claimed = public_inputs.to_field_elements();
+        let result = self.verify(proof_bytes, public_inputs)?;
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let subscriber = tracing_subscriber::Registry::default()
+            .with(ConstraintLayer::new(TracingMode::OnlyConstraints));
+        tracing::subscriber::with_default(subscriber, || witness.generate_constraints(cs.clone()))
+            .map_err(|e| VerifierError::malformed(format!("Witness synthesis failed: {}", e)))?;
+        cs.finalize();
+
+        let failing_constraint = first_unsatisfied(&cs).map(|index| {
+            cs.constraint_names()
+                .and_then(|names| names.get(index).cloned())
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| format!("constraint {}", index))
+        });
+        let mismatched_inputs = mismatched_inputs(&cs, &claimed);
+
+        Ok(Diagnostic {
+            result,
+            witness_satisfied: failing_constraint.is_none(),
+            failing_constraint,
+            mismatched_inputs,
+            num_constraints: cs.num_constraints(),
+        })
+    }
+}
+
+/// Index of the first constraint the assignment doesn't satisfy
+fn first_unsatisfied(cs: &ConstraintSystemRef<Fr>) -> Option<usize> {
+    let matrices = cs.to_matrices()?;
+    let inner = cs.borrow()?;
+    let assignment: Vec<Fr> = inner
+        .instance_assignment
+        .iter()
+        .chain(&inner.witness_assignment)
+        .copied()
+        .collect();
+    let eval = |row: &[(Fr, usize)]| {
+        row.iter().fold(Fr::zero(), |acc, (coeff, index)| {
+            acc + *coeff * assignment[*index]
+        })
+    };
+
+    (0..matrices.num_constraints)
+        .find(|&i| eval(&matrices.a[i]) * eval(&matrices.b[i]) != eval(&matrices.c[i]))
+}
+
+/// Names of public inputs the witness assigns differently from `claimed`
+fn mismatched_inputs(cs: &ConstraintSystemRef<Fr>, claimed: &[Fr]) -> Vec<String> {
+    let Some(inner) = cs.borrow() else {
+        return Vec::new();
+    };
+    // Instance variable 0 is the constant one
+    let assigned = inner.instance_assignment.get(1..).unwrap_or_default();
+    let count = assigned.len().max(claimed.len());
+
+    (0..count)
+        .filter(|&i| assigned.get(i) != claimed.get(i))
+        .map(|i| match PUBLIC_INPUT_NAMES.get(i) {
+            Some(name) => name.to_string(),
+            None => format!("input {}", i),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{circuit, fixture, public_inputs};
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_r1cs_std::eq::EqGadget;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_relations::ns;
+    use ark_relations::r1cs::SynthesisError;
+
+    /// Claims the same public inputs but enforces an impossible equality
+    struct BrokenCircuit(PublicInputs);
+
+    impl ConstraintSynthesizer<Fr> for BrokenCircuit {
+        fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+            for value in self.0.to_field_elements() {
+                let _ = FpVar::new_input(cs.clone(), || Ok(value))?;
+            }
+            // The span lasts as long as the namespace is held
+            let namespace = ns!(cs, "merkle_root");
+            let cs = namespace.cs();
+            let leaf = FpVar::new_witness(cs.clone(), || Ok(Fr::from(1u64)))?;
+            let root = FpVar::new_witness(cs, || Ok(Fr::from(2u64)))?;
+            leaf.enforce_equal(&root)
+        }
+    }
+
+    #[test]
+    fn test_verify_diagnostic() {
+        let (_, vk_bytes, proof_bytes) = fixture();
+        let verifier = QueryVerifier::builder()
+            .key_bytes(vk_bytes)
+            .build()
+            .unwrap();
+
+        let diagnostic = verifier
+            .verify_diagnostic(&proof_bytes, public_inputs(), circuit(&public_inputs()))
+            .unwrap();
+        assert!(diagnostic.result.is_valid);
+        assert!(diagnostic.witness_satisfied);
+        assert!(diagnostic.mismatched_inputs.is_empty());
+
+        let mut claimed = public_inputs();
+        claimed.timestamp += 1;
+        let diagnostic = verifier
+            .verify_diagnostic(&proof_bytes, claimed, circuit(&public_inputs()))
+            .unwrap();
+        assert!(!diagnostic.result.is_valid);
+        assert_eq!(diagnostic.mismatched_inputs, vec!["timestamp"]);
+
+        let diagnostic = verifier
+            .verify_diagnostic(
+                &proof_bytes,
+                public_inputs(),
+                BrokenCircuit(public_inputs()),
+            )
+            .unwrap();
+        let failing = diagnostic.failing_constraint.unwrap();
+        assert!(failing.contains("merkle_root"), "{}", failing);
+    }
+}
@@ -0,0 +1,342 @@
+// Proof envelopes
+//
+// A versioned container carrying a proof together with the circuit it was
+// produced for, the verifying key it targets, and its public inputs.
+//
+// Versions:
+// - v1: proof and public inputs only (implicitly the document query circuit)
+// - v2: adds circuit_id and key_id
+// - v3: adds curve (v1 and v2 envelopes are BN254)
+// - v4: public inputs may carry a challenge nonce; the binary body is a v3
+//   body followed by the nonce
+//
+// Older envelopes are upgraded to the current version on parse.
+
+use serde::{Deserialize, Serialize};
+
+use crate::canonical::canonical_digest;
+use crate::curve::Curve;
+use crate::error::{bail, Result, ResultExt};
+use crate::PublicInputs;
+
+/// Envelope version produced by this crate
+pub const ENVELOPE_VERSION: u16 = 4;
+
+/// Oldest envelope version this crate can read
+pub const MIN_ENVELOPE_VERSION: u16 = 1;
+
+/// Magic prefix of the binary encoding
+pub const ENVELOPE_MAGIC: &[u8; 4] = b"ZKEV";
+
+/// Largest accepted serialized envelope, in either encoding
+pub const MAX_ENVELOPE_SIZE: usize = 64 * 1024;
+
+pub use zkrag_circuits::registry::DOCUMENT_QUERY_CIRCUIT_ID;
+
+/// A proof with its circuit, key, and public inputs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofEnvelope {
+    pub version: u16,
+    pub circuit_id: String,
+    /// Pairing curve of the proof and its verifying key
+    pub curve: Curve,
+    /// Verifying key id; `None` selects the circuit's current key
+    pub key_id: Option<String>,
+    #[serde(with = "hex_bytes")]
+    pub proof: Vec<u8>,
+    pub public_inputs: PublicInputs,
+}
+
+/// Public inputs before challenge nonces
+#[derive(Serialize, Deserialize)]
+struct InputsV3 {
+    document_commitment: String,
+    model_hash: String,
+    timestamp: u64,
+}
+
+impl From<InputsV3> for PublicInputs {
+    fn from(v3: InputsV3) -> Self {
+        Self {
+            document_commitment: v3.document_commitment,
+            model_hash: v3.model_hash,
+            timestamp: v3.timestamp,
+            nonce: None,
+        }
+    }
+}
+
+/// v1 layout, kept for reading archived envelopes
+#[derive(Serialize, Deserialize)]
+struct EnvelopeV1 {
+    #[serde(with = "hex_bytes")]
+    proof: Vec<u8>,
+    public_inputs: InputsV3,
+}
+
+/// v2 layout, kept for reading archived envelopes
+#[derive(Serialize, Deserialize)]
+struct EnvelopeV2 {
+    version: u16,
+    circuit_id: String,
+    key_id: Option<String>,
+    #[serde(with = "hex_bytes")]
+    proof: Vec<u8>,
+    public_inputs: InputsV3,
+}
+
+/// v3 layout, kept for reading archived envelopes and as the start of a v4
+/// binary body
+#[derive(Serialize, Deserialize)]
+struct EnvelopeV3 {
+    version: u16,
+    circuit_id: String,
+    curve: Curve,
+    key_id: Option<String>,
+    #[serde(with = "hex_bytes")]
+    proof: Vec<u8>,
+    public_inputs: InputsV3,
+}
+
+/// Only the version field, read before choosing a layout
+#[derive(Deserialize)]
+struct VersionProbe {
+    version: u16,
+}
+
+impl ProofEnvelope {
+    /// Wrap a document query proof in a current-version envelope
+    pub fn new(proof: Vec<u8>, public_inputs: PublicInputs) -> Self {
+        Self {
+            version: ENVELOPE_VERSION,
+            circuit_id: DOCUMENT_QUERY_CIRCUIT_ID.to_string(),
+            curve: Curve::Bn254,
+            key_id: None,
+            proof,
+            public_inputs,
+        }
+    }
+
+    /// Mark the proof as produced over another curve
+    pub fn with_curve(mut self, curve: Curve) -> Self {
+        self.curve = curve;
+        self
+    }
+
+    /// Pin the envelope to a specific verifying key
+    pub fn with_key_id(mut self, key_id: impl Into<String>) -> Self {
+        self.key_id = Some(key_id.into());
+        self
+    }
+
+    /// Parse a JSON envelope of any supported version
+    pub fn from_json(bytes: &[u8]) -> Result<Self> {
+        check_size(bytes)?;
+        let probe: VersionProbe =
+            serde_json::from_slice(bytes).context("Envelope is missing a version")?;
+
+        match probe.version {
+            1 => {
+                let v1: EnvelopeV1 = serde_json::from_slice(bytes)?;
+                Ok(Self::from_v1(v1))
+            }
+            2 => {
+                let v2: EnvelopeV2 = serde_json::from_slice(bytes)?;
+                Ok(Self::from_v2(v2))
+            }
+            3 => {
+                let v3: EnvelopeV3 = serde_json::from_slice(bytes)?;
+                Ok(Self::from_v3(v3, None))
+            }
+            ENVELOPE_VERSION => {
+                let mut envelope: Self = serde_json::from_slice(bytes)?;
+                envelope.version = ENVELOPE_VERSION;
+                Ok(envelope)
+            }
+            version => bail!(Malformed, "Unsupported envelope version {}", version),
+        }
+    }
+
+    /// Serialize as JSON
+    pub fn to_json(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(self)?)
+    }
+
+    /// Parse a binary envelope: magic, little-endian u16 version, bincode body
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        check_size(bytes)?;
+        if bytes.len() < 6 || &bytes[..4] != ENVELOPE_MAGIC {
+            bail!(Malformed, "Not a binary proof envelope");
+        }
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        let body = &bytes[6..];
+
+        match version {
+            1 => Ok(Self::from_v1(bincode::deserialize(body)?)),
+            2 => Ok(Self::from_v2(bincode::deserialize(body)?)),
+            3 => Ok(Self::from_v3(bincode::deserialize(body)?, None)),
+            ENVELOPE_VERSION => {
+                let (v3, nonce) = bincode::deserialize(body)?;
+                Ok(Self::from_v3(v3, nonce))
+            }
+            version => bail!(Malformed, "Unsupported envelope version {}", version),
+        }
+    }
+
+    /// Serialize in the binary encoding
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = ENVELOPE_MAGIC.to_vec();
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        let inputs = &self.public_inputs;
+        let v3 = EnvelopeV3 {
+            version: self.version,
+            circuit_id: self.circuit_id.clone(),
+            curve: self.curve,
+            key_id: self.key_id.clone(),
+            proof: self.proof.clone(),
+            public_inputs: InputsV3 {
+                document_commitment: inputs.document_commitment.clone(),
+                model_hash: inputs.model_hash.clone(),
+                timestamp: inputs.timestamp,
+            },
+        };
+        bytes.extend(bincode::serialize(&(v3, &inputs.nonce))?);
+        Ok(bytes)
+    }
+
+    /// Hex-encoded SHA-256 of the envelope's canonical JSON, binding the
+    /// proof to its circuit, key, and public inputs
+    pub fn digest(&self) -> String {
+        canonical_digest(self).expect("envelope serializes")
+    }
+
+    /// Parse either encoding, detected by the binary magic prefix
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        if bytes.starts_with(ENVELOPE_MAGIC) {
+            Self::from_bytes(bytes)
+        } else {
+            Self::from_json(bytes)
+        }
+    }
+
+    fn from_v1(v1: EnvelopeV1) -> Self {
+        Self::new(v1.proof, v1.public_inputs.into())
+    }
+
+    fn from_v2(v2: EnvelopeV2) -> Self {
+        Self {
+            version: ENVELOPE_VERSION,
+            circuit_id: v2.circuit_id,
+            curve: Curve::Bn254,
+            key_id: v2.key_id,
+            proof: v2.proof,
+            public_inputs: v2.public_inputs.into(),
+        }
+    }
+
+    fn from_v3(v3: EnvelopeV3, nonce: Option<String>) -> Self {
+        Self {
+            version: ENVELOPE_VERSION,
+            circuit_id: v3.circuit_id,
+            curve: v3.curve,
+            key_id: v3.key_id,
+            proof: v3.proof,
+            public_inputs: PublicInputs {
+                nonce,
+                ..v3.public_inputs.into()
+            },
+        }
+    }
+}
+
+fn check_size(bytes: &[u8]) -> Result<()> {
+    if bytes.len() > MAX_ENVELOPE_SIZE {
+        bail!(
+            Malformed,
+            "Envelope is {} bytes, limit is {}",
+            bytes.len(),
+            MAX_ENVELOPE_SIZE
+        );
+    }
+    Ok(())
+}
+
+/// Serde adapter encoding byte vectors as hex strings in human-readable formats
+pub(crate) mod hex_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&hex::encode(bytes))
+        } else {
+            serializer.serialize_bytes(bytes)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            hex::decode(s).map_err(serde::de::Error::custom)
+        } else {
+            Vec::<u8>::deserialize(deserializer)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs() -> PublicInputs {
+        PublicInputs {
+            document_commitment: "abc123".to_string(),
+            model_hash: "model456".to_string(),
+            timestamp: 1234567890,
+            nonce: None,
+        }
+    }
+
+    #[test]
+    fn test_envelope_roundtrip() {
+        let envelope = ProofEnvelope::new(vec![1, 2, 3], inputs()).with_key_id("key");
+
+        let json = envelope.to_json().unwrap();
+        assert_eq!(ProofEnvelope::parse(&json).unwrap(), envelope);
+
+        let bytes = envelope.to_bytes().unwrap();
+        assert_eq!(ProofEnvelope::parse(&bytes).unwrap(), envelope);
+
+        // A v3 binary body is a v4 body without the nonce
+        let mut v3 = bytes.clone();
+        v3[4..6].copy_from_slice(&3u16.to_le_bytes());
+        v3.pop();
+        assert_eq!(ProofEnvelope::parse(&v3).unwrap(), envelope);
+
+        let mut bound = envelope.clone();
+        bound.public_inputs.nonce = Some("n1".to_string());
+        let json = bound.to_json().unwrap();
+        assert_eq!(ProofEnvelope::parse(&json).unwrap(), bound);
+        let bytes = bound.to_bytes().unwrap();
+        assert_eq!(ProofEnvelope::parse(&bytes).unwrap(), bound);
+    }
+
+    #[test]
+    fn test_v1_upgrade() {
+        let json = r#"{
+            "version": 1,
+            "proof": "010203",
+            "public_inputs": {"document_commitment": "abc123", "model_hash": "model456", "timestamp": 1234567890}
+        }"#;
+
+        let envelope = ProofEnvelope::from_json(json.as_bytes()).unwrap();
+        assert_eq!(envelope.version, ENVELOPE_VERSION);
+        assert_eq!(envelope.circuit_id, DOCUMENT_QUERY_CIRCUIT_ID);
+        assert_eq!(envelope.proof, vec![1, 2, 3]);
+
+        assert_eq!(envelope.curve, Curve::Bn254);
+        assert_eq!(envelope.proof, vec![1, 2, 3]);
+
+        assert!(ProofEnvelope::from_json(br#"{"version": 99}"#).is_err());
+        assert!(ProofEnvelope::parse(&vec![b' '; MAX_ENVELOPE_SIZE + 1]).is_err());
+    }
+}
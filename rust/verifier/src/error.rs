@@ -0,0 +1,195 @@
+// Verifier errors
+//
+// Errors from the verifier's own operations: loading keys, parsing
+// envelopes, writing audit logs. A proof that fails verification is not an
+// error; it is reported in `VerificationResult::reason`.
+//
+// Variants say who is at fault so a service can map them to status codes
+// without string matching: `Malformed` input is a 400, `Rejected` input is a
+// 422, and `Io`/`Internal` failures are 500s.
+
+use std::fmt;
+use std::io;
+
+/// Error from a verifier operation
+#[derive(Debug, thiserror::Error)]
+pub enum VerifierError {
+    /// Input could not be parsed or exceeds a size limit
+    #[error("{0}")]
+    Malformed(String),
+    /// Well-formed input failed an integrity check, such as a digest or
+    /// signature mismatch
+    #[error("{0}")]
+    Rejected(String),
+    /// Reading or writing a file failed
+    #[error("{}", io_message(.context, .source))]
+    Io {
+        context: String,
+        #[source]
+        source: io::Error,
+    },
+    /// A backing store, remote service, or the verifier's configuration failed
+    #[error("{0}")]
+    Internal(String),
+}
+
+/// Result of a verifier operation
+pub type Result<T, E = VerifierError> = std::result::Result<T, E>;
+
+impl VerifierError {
+    /// Whether the caller's input caused the error, rather than the verifier
+    pub fn is_client_error(&self) -> bool {
+        matches!(self, Self::Malformed(_) | Self::Rejected(_))
+    }
+
+    pub(crate) fn malformed(message: impl fmt::Display) -> Self {
+        Self::Malformed(message.to_string())
+    }
+
+    pub(crate) fn rejected(message: impl fmt::Display) -> Self {
+        Self::Rejected(message.to_string())
+    }
+
+    pub(crate) fn internal(message: impl fmt::Display) -> Self {
+        Self::Internal(message.to_string())
+    }
+
+    pub(crate) fn io(context: impl fmt::Display, source: io::Error) -> Self {
+        Self::Io {
+            context: context.to_string(),
+            source,
+        }
+    }
+
+    /// Prefix the message with `context`, keeping the variant
+    pub(crate) fn context(self, context: impl fmt::Display) -> Self {
+        match self {
+            Self::Malformed(message) => Self::Malformed(format!("{}: {}", context, message)),
+            Self::Rejected(message) => Self::Rejected(format!("{}: {}", context, message)),
+            Self::Io {
+                context: inner,
+                source,
+            } if inner.is_empty() => Self::io(context, source),
+            Self::Io {
+                context: inner,
+                source,
+            } => Self::Io {
+                context: format!("{}: {}", context, inner),
+                source,
+            },
+            Self::Internal(message) => Self::Internal(format!("{}: {}", context, message)),
+        }
+    }
+}
+
+fn io_message(context: &str, source: &io::Error) -> String {
+    if context.is_empty() {
+        source.to_string()
+    } else {
+        format!("{}: {}", context, source)
+    }
+}
+
+impl From<io::Error> for VerifierError {
+    fn from(source: io::Error) -> Self {
+        Self::io("", source)
+    }
+}
+
+impl From<serde_json::Error> for VerifierError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::malformed(error)
+    }
+}
+
+impl From<bincode::Error> for VerifierError {
+    fn from(error: bincode::Error) -> Self {
+        Self::malformed(error)
+    }
+}
+
+impl From<hex::FromHexError> for VerifierError {
+    fn from(error: hex::FromHexError) -> Self {
+        Self::malformed(error)
+    }
+}
+
+impl From<toml::de::Error> for VerifierError {
+    fn from(error: toml::de::Error) -> Self {
+        Self::malformed(error)
+    }
+}
+
+impl From<ureq::Error> for VerifierError {
+    fn from(error: ureq::Error) -> Self {
+        Self::internal(error)
+    }
+}
+
+impl From<std::time::SystemTimeError> for VerifierError {
+    fn from(error: std::time::SystemTimeError) -> Self {
+        Self::internal(error)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for VerifierError {
+    fn from(error: rusqlite::Error) -> Self {
+        Self::internal(error)
+    }
+}
+
+#[cfg(feature = "redis")]
+impl From<redis::RedisError> for VerifierError {
+    fn from(error: redis::RedisError) -> Self {
+        Self::internal(error)
+    }
+}
+
+impl From<zkrag_verifier_core::CoreError> for VerifierError {
+    fn from(error: zkrag_verifier_core::CoreError) -> Self {
+        Self::malformed(error)
+    }
+}
+
+/// `context` and `with_context` for results convertible to `VerifierError`
+pub(crate) trait ResultExt<T> {
+    fn context(self, context: impl fmt::Display) -> Result<T>;
+    fn with_context<C: fmt::Display>(self, context: impl FnOnce() -> C) -> Result<T>;
+}
+
+impl<T, E: Into<VerifierError>> ResultExt<T> for Result<T, E> {
+    fn context(self, context: impl fmt::Display) -> Result<T> {
+        self.map_err(|error| error.into().context(context))
+    }
+
+    fn with_context<C: fmt::Display>(self, context: impl FnOnce() -> C) -> Result<T> {
+        self.map_err(|error| error.into().context(context()))
+    }
+}
+
+/// Return early with a `VerifierError` of the given variant
+macro_rules! bail {
+    ($kind:ident, $($arg:tt)*) => {
+        return Err($crate::VerifierError::$kind(format!($($arg)*)))
+    };
+}
+pub(crate) use bail;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_keeps_kind() {
+        let error = VerifierError::rejected("digest mismatch").context("Bundle key");
+        assert!(matches!(error, VerifierError::Rejected(_)));
+        assert_eq!(error.to_string(), "Bundle key: digest mismatch");
+        assert!(error.is_client_error());
+
+        let error = VerifierError::from(io::Error::from(io::ErrorKind::NotFound))
+            .context("Failed to read key");
+        assert_eq!(error.to_string(), "Failed to read key: entity not found");
+        assert!(!error.is_client_error());
+    }
+}
@@ -0,0 +1,454 @@
+// Interop with other Groth16 stacks
+//
+// Parses BN254 Groth16 proofs and verifying keys produced by snarkjs (circom)
+// and gnark so they can be checked by the same verifier.
+//
+// Supported formats:
+// - snarkjs `proof.json`, `public.json`, and `verification_key.json`
+// - gnark raw proof encoding (`WriteRawTo`): Ar | Bs | Krs as uncompressed
+//   big-endian points, optionally followed by an empty commitment section
+//
+// gnark verifying keys should be exported through snarkjs-compatible JSON.
+//
+// Foreign circuits don't use the document query public-input mapping, so
+// `verify_foreign` takes public inputs as raw field elements.
+
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ec::AffineRepr;
+use ark_ff::{BigInteger, PrimeField};
+use ark_groth16::{Groth16, PreparedVerifyingKey, Proof, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::error::{bail, Result, ResultExt, VerifierError};
+use crate::{core, PublicInputs};
+
+/// Size of a gnark raw G1 point
+const GNARK_G1_SIZE: usize = 64;
+
+/// Size of a gnark raw G2 point
+const GNARK_G2_SIZE: usize = 128;
+
+/// Size of a gnark raw proof without commitments
+pub const GNARK_PROOF_SIZE: usize = 2 * GNARK_G1_SIZE + GNARK_G2_SIZE;
+
+/// snarkjs proof.json
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnarkjsProof {
+    pub pi_a: Vec<String>,
+    pub pi_b: Vec<Vec<String>>,
+    pub pi_c: Vec<String>,
+    pub protocol: String,
+    pub curve: String,
+}
+
+/// snarkjs verification_key.json
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnarkjsVerifyingKey {
+    pub protocol: String,
+    pub curve: String,
+    #[serde(rename = "nPublic")]
+    pub n_public: usize,
+    pub vk_alpha_1: Vec<String>,
+    pub vk_beta_2: Vec<Vec<String>>,
+    pub vk_gamma_2: Vec<Vec<String>>,
+    pub vk_delta_2: Vec<Vec<String>>,
+    #[serde(rename = "IC")]
+    pub ic: Vec<Vec<String>>,
+}
+
+fn check_curve(protocol: &str, curve: &str) -> Result<()> {
+    if protocol != "groth16" {
+        bail!(
+            Malformed,
+            "Unsupported protocol {}, expected groth16",
+            protocol
+        );
+    }
+    if curve != "bn128" && curve != "bn254" {
+        bail!(Malformed, "Unsupported curve {}, expected bn128", curve);
+    }
+    Ok(())
+}
+
+fn parse_fq(value: &str) -> Result<Fq> {
+    Fq::from_str(value)
+        .map_err(|_| VerifierError::malformed(format!("Invalid base field element {}", value)))
+}
+
+fn parse_fr(value: &str) -> Result<Fr> {
+    Fr::from_str(value)
+        .map_err(|_| VerifierError::malformed(format!("Invalid scalar field element {}", value)))
+}
+
+fn parse_g1(coords: &[String]) -> Result<G1Affine> {
+    let [x, y, z] = coords else {
+        bail!(Malformed, "G1 point must have 3 projective coordinates");
+    };
+    if z == "0" {
+        return Ok(G1Affine::zero());
+    }
+    if z != "1" {
+        bail!(Malformed, "G1 point must be affine (z = 1)");
+    }
+    checked_g1(G1Affine::new_unchecked(parse_fq(x)?, parse_fq(y)?))
+}
+
+fn parse_g2(coords: &[Vec<String>]) -> Result<G2Affine> {
+    let [x, y, z] = coords else {
+        bail!(Malformed, "G2 point must have 3 projective coordinates");
+    };
+    let parse_fq2 = |c: &[String]| -> Result<Fq2> {
+        let [c0, c1] = c else {
+            bail!(Malformed, "G2 coordinate must have 2 components");
+        };
+        Ok(Fq2::new(parse_fq(c0)?, parse_fq(c1)?))
+    };
+    if z.iter().all(|c| c == "0") {
+        return Ok(G2Affine::zero());
+    }
+    if z.first().map(String::as_str) != Some("1") || z.get(1).map(String::as_str) != Some("0") {
+        bail!(Malformed, "G2 point must be affine (z = 1)");
+    }
+    checked_g2(G2Affine::new_unchecked(parse_fq2(x)?, parse_fq2(y)?))
+}
+
+fn checked_g1(point: G1Affine) -> Result<G1Affine> {
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        bail!(Malformed, "G1 point is not on the curve");
+    }
+    Ok(point)
+}
+
+fn checked_g2(point: G2Affine) -> Result<G2Affine> {
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        bail!(Malformed, "G2 point is not in the prime-order subgroup");
+    }
+    Ok(point)
+}
+
+/// Decimal form of a field element. ark-ff's Display prints zero as an empty
+/// string, which snarkjs would reject.
+fn decimal<F: PrimeField>(value: &F) -> String {
+    value.into_bigint().to_string()
+}
+
+fn g1_to_snarkjs(point: &G1Affine) -> Vec<String> {
+    match point.xy() {
+        Some((x, y)) => vec![decimal(x), decimal(y), "1".to_string()],
+        None => vec!["0".to_string(), "1".to_string(), "0".to_string()],
+    }
+}
+
+fn g2_to_snarkjs(point: &G2Affine) -> Vec<Vec<String>> {
+    match point.xy() {
+        Some((x, y)) => vec![
+            vec![decimal(&x.c0), decimal(&x.c1)],
+            vec![decimal(&y.c0), decimal(&y.c1)],
+            vec!["1".to_string(), "0".to_string()],
+        ],
+        None => vec![
+            vec!["0".to_string(), "0".to_string()],
+            vec!["1".to_string(), "0".to_string()],
+            vec!["0".to_string(), "0".to_string()],
+        ],
+    }
+}
+
+impl SnarkjsProof {
+    /// Convert to an arkworks proof, validating every point
+    pub fn to_proof(&self) -> Result<Proof<Bn254>> {
+        check_curve(&self.protocol, &self.curve)?;
+        Ok(Proof {
+            a: parse_g1(&self.pi_a).context("Invalid pi_a")?,
+            b: parse_g2(&self.pi_b).context("Invalid pi_b")?,
+            c: parse_g1(&self.pi_c).context("Invalid pi_c")?,
+        })
+    }
+
+    /// Convert an arkworks proof to snarkjs form
+    pub fn from_proof(proof: &Proof<Bn254>) -> Self {
+        Self {
+            pi_a: g1_to_snarkjs(&proof.a),
+            pi_b: g2_to_snarkjs(&proof.b),
+            pi_c: g1_to_snarkjs(&proof.c),
+            protocol: "groth16".to_string(),
+            curve: "bn128".to_string(),
+        }
+    }
+}
+
+impl SnarkjsVerifyingKey {
+    /// Convert to an arkworks verifying key, validating every point
+    pub fn to_verifying_key(&self) -> Result<VerifyingKey<Bn254>> {
+        check_curve(&self.protocol, &self.curve)?;
+        if self.ic.len() != self.n_public + 1 {
+            bail!(
+                Malformed,
+                "IC has {} points, expected nPublic + 1 = {}",
+                self.ic.len(),
+                self.n_public + 1
+            );
+        }
+        Ok(VerifyingKey {
+            alpha_g1: parse_g1(&self.vk_alpha_1).context("Invalid vk_alpha_1")?,
+            beta_g2: parse_g2(&self.vk_beta_2).context("Invalid vk_beta_2")?,
+            gamma_g2: parse_g2(&self.vk_gamma_2).context("Invalid vk_gamma_2")?,
+            delta_g2: parse_g2(&self.vk_delta_2).context("Invalid vk_delta_2")?,
+            gamma_abc_g1: self
+                .ic
+                .iter()
+                .map(|point| parse_g1(point))
+                .collect::<Result<_>>()
+                .context("Invalid IC")?,
+        })
+    }
+
+    /// Convert an arkworks verifying key to snarkjs form
+    pub fn from_verifying_key(vk: &VerifyingKey<Bn254>) -> Self {
+        Self {
+            protocol: "groth16".to_string(),
+            curve: "bn128".to_string(),
+            n_public: vk.gamma_abc_g1.len().saturating_sub(1),
+            vk_alpha_1: g1_to_snarkjs(&vk.alpha_g1),
+            vk_beta_2: g2_to_snarkjs(&vk.beta_g2),
+            vk_gamma_2: g2_to_snarkjs(&vk.gamma_g2),
+            vk_delta_2: g2_to_snarkjs(&vk.delta_g2),
+            ic: vk.gamma_abc_g1.iter().map(g1_to_snarkjs).collect(),
+        }
+    }
+}
+
+/// Parse a snarkjs proof.json
+pub fn parse_snarkjs_proof(json: &str) -> Result<Proof<Bn254>> {
+    let proof: SnarkjsProof = serde_json::from_str(json).context("Malformed snarkjs proof")?;
+    proof.to_proof()
+}
+
+/// Parse a snarkjs verification_key.json
+pub fn parse_snarkjs_vk(json: &str) -> Result<VerifyingKey<Bn254>> {
+    let vk: SnarkjsVerifyingKey =
+        serde_json::from_str(json).context("Malformed snarkjs verifying key")?;
+    vk.to_verifying_key()
+}
+
+/// Parse a snarkjs public.json (array of decimal strings)
+pub fn parse_snarkjs_public(json: &str) -> Result<Vec<Fr>> {
+    let values: Vec<String> =
+        serde_json::from_str(json).context("Malformed snarkjs public signals")?;
+    values.iter().map(|value| parse_fr(value)).collect()
+}
+
+fn read_fq_be(bytes: &[u8]) -> Result<Fq> {
+    let value = Fq::from_be_bytes_mod_order(bytes);
+    if value.into_bigint().to_bytes_be() != bytes {
+        bail!(Malformed, "Base field element is not canonical");
+    }
+    Ok(value)
+}
+
+fn read_gnark_g1(bytes: &[u8]) -> Result<G1Affine> {
+    let flags = bytes[0] >> 6;
+    match flags {
+        0b00 => {}
+        0b01 => return Ok(G1Affine::zero()),
+        _ => bail!(
+            Malformed,
+            "Compressed gnark points are not supported; use WriteRawTo"
+        ),
+    }
+    checked_g1(G1Affine::new_unchecked(
+        read_fq_be(&bytes[..32])?,
+        read_fq_be(&bytes[32..64])?,
+    ))
+}
+
+fn read_gnark_g2(bytes: &[u8]) -> Result<G2Affine> {
+    let flags = bytes[0] >> 6;
+    match flags {
+        0b00 => {}
+        0b01 => return Ok(G2Affine::zero()),
+        _ => bail!(
+            Malformed,
+            "Compressed gnark points are not supported; use WriteRawTo"
+        ),
+    }
+    // gnark orders each Fq2 coordinate as A1 | A0
+    let x = Fq2::new(read_fq_be(&bytes[32..64])?, read_fq_be(&bytes[..32])?);
+    let y = Fq2::new(read_fq_be(&bytes[96..128])?, read_fq_be(&bytes[64..96])?);
+    checked_g2(G2Affine::new_unchecked(x, y))
+}
+
+/// Parse a gnark BN254 Groth16 proof in raw (uncompressed) encoding
+pub fn parse_gnark_proof(bytes: &[u8]) -> Result<Proof<Bn254>> {
+    if bytes.len() < GNARK_PROOF_SIZE {
+        bail!(
+            Malformed,
+            "gnark proof must be at least {} bytes, got {}",
+            GNARK_PROOF_SIZE,
+            bytes.len()
+        );
+    }
+
+    // Newer gnark versions append a commitment count, commitments, and a
+    // proof of knowledge; only proofs without commitments are supported.
+    let trailer = &bytes[GNARK_PROOF_SIZE..];
+    if !trailer.is_empty() {
+        if trailer.len() < 4 || trailer[..4] != [0, 0, 0, 0] {
+            bail!(
+                Malformed,
+                "gnark proofs with Pedersen commitments are not supported"
+            );
+        }
+        if trailer.len() != 4 + GNARK_G1_SIZE {
+            bail!(Malformed, "Unexpected trailing bytes after gnark proof");
+        }
+    }
+
+    Ok(Proof {
+        a: read_gnark_g1(&bytes[..GNARK_G1_SIZE]).context("Invalid Ar")?,
+        b: read_gnark_g2(&bytes[GNARK_G1_SIZE..GNARK_G1_SIZE + GNARK_G2_SIZE])
+            .context("Invalid Bs")?,
+        c: read_gnark_g1(&bytes[GNARK_G1_SIZE + GNARK_G2_SIZE..GNARK_PROOF_SIZE])
+            .context("Invalid Krs")?,
+    })
+}
+
+/// Verify a foreign proof against raw public field elements
+pub fn verify_foreign(
+    vk: &VerifyingKey<Bn254>,
+    proof: &Proof<Bn254>,
+    public_inputs: &[Fr],
+) -> Result<bool> {
+    if public_inputs.len() + 1 != vk.gamma_abc_g1.len() {
+        bail!(
+            Malformed,
+            "Expected {} public inputs, got {}",
+            vk.gamma_abc_g1.len() - 1,
+            public_inputs.len()
+        );
+    }
+    let pvk = PreparedVerifyingKey::from(vk.clone());
+    Groth16::<Bn254>::verify_proof(&pvk, proof, public_inputs).map_err(VerifierError::malformed)
+}
+
+/// snarkjs proof.json for a serialized (compressed or uncompressed) proof
+pub fn proof_to_snarkjs(proof_bytes: &[u8]) -> Result<String> {
+    let proof = core::deserialize_proof(proof_bytes).map_err(VerifierError::malformed)?;
+    Ok(serde_json::to_string(&SnarkjsProof::from_proof(&proof))?)
+}
+
+/// snarkjs public.json for document query public inputs
+pub fn public_inputs_to_snarkjs(public_inputs: &PublicInputs) -> String {
+    let values: Vec<String> = public_inputs
+        .to_field_elements()
+        .iter()
+        .map(decimal)
+        .collect();
+    serde_json::to_string(&values).expect("strings serialize")
+}
+
+/// Verify snarkjs proof.json / public.json against verification_key.json
+pub fn verify_snarkjs(proof_json: &str, public_json: &str, vk_json: &str) -> Result<bool> {
+    let proof = parse_snarkjs_proof(proof_json)?;
+    let public_inputs = parse_snarkjs_public(public_json)?;
+    let vk = parse_snarkjs_vk(vk_json)?;
+    verify_foreign(&vk, &proof, &public_inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_relations::lc;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+    /// Proves knowledge of x with x * x = y, y public
+    #[derive(Clone)]
+    struct SquareCircuit {
+        x: Fr,
+    }
+
+    impl ConstraintSynthesizer<Fr> for SquareCircuit {
+        fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+            let y = cs.new_input_variable(|| Ok(self.x * self.x))?;
+            let x = cs.new_witness_variable(|| Ok(self.x))?;
+            cs.enforce_constraint(lc!() + x, lc!() + x, lc!() + y)
+        }
+    }
+
+    fn setup() -> (VerifyingKey<Bn254>, Proof<Bn254>) {
+        let mut rng = ark_std::test_rng();
+        let circuit = SquareCircuit { x: Fr::from(3u64) };
+        let pk =
+            Groth16::<Bn254>::generate_random_parameters_with_reduction(circuit.clone(), &mut rng)
+                .unwrap();
+        let proof =
+            Groth16::<Bn254>::create_random_proof_with_reduction(circuit, &pk, &mut rng).unwrap();
+        (pk.vk, proof)
+    }
+
+    fn write_fq_be(out: &mut Vec<u8>, value: &Fq) {
+        out.extend(value.into_bigint().to_bytes_be());
+    }
+
+    #[test]
+    fn test_snarkjs_roundtrip() {
+        let (vk, proof) = setup();
+        let proof_json = serde_json::to_string(&SnarkjsProof::from_proof(&proof)).unwrap();
+        let vk_json = serde_json::to_string(&SnarkjsVerifyingKey::from_verifying_key(&vk)).unwrap();
+
+        assert!(verify_snarkjs(&proof_json, r#"["9"]"#, &vk_json).unwrap());
+        assert!(!verify_snarkjs(&proof_json, r#"["10"]"#, &vk_json).unwrap());
+        assert!(verify_snarkjs(&proof_json, r#"["9", "1"]"#, &vk_json).is_err());
+    }
+
+    #[test]
+    fn test_snarkjs_export() {
+        let (pk, vk_bytes, proof_bytes) = crate::tests::fixture();
+        let proof_json = proof_to_snarkjs(&proof_bytes).unwrap();
+        let public_json = public_inputs_to_snarkjs(&crate::tests::public_inputs());
+        let vk_json =
+            serde_json::to_string(&SnarkjsVerifyingKey::from_verifying_key(&pk.vk)).unwrap();
+
+        assert!(verify_snarkjs(&proof_json, &public_json, &vk_json).unwrap());
+        assert!(proof_to_snarkjs(&vk_bytes).is_err());
+    }
+
+    #[test]
+    fn test_snarkjs_rejects_off_curve_point() {
+        let (_, proof) = setup();
+        let mut snarkjs = SnarkjsProof::from_proof(&proof);
+        snarkjs.pi_a[1] = "1".to_string();
+        assert!(snarkjs.to_proof().is_err());
+    }
+
+    #[test]
+    fn test_gnark_raw_proof() {
+        let (vk, proof) = setup();
+        let (ax, ay) = proof.a.xy().unwrap();
+        let (bx, by) = proof.b.xy().unwrap();
+        let (cx, cy) = proof.c.xy().unwrap();
+
+        let mut bytes = Vec::new();
+        write_fq_be(&mut bytes, ax);
+        write_fq_be(&mut bytes, ay);
+        for coordinate in [bx.c1, bx.c0, by.c1, by.c0] {
+            write_fq_be(&mut bytes, &coordinate);
+        }
+        write_fq_be(&mut bytes, cx);
+        write_fq_be(&mut bytes, cy);
+
+        let parsed = parse_gnark_proof(&bytes).unwrap();
+        assert_eq!(parsed, proof);
+        assert!(verify_foreign(&vk, &parsed, &[Fr::from(9u64)]).unwrap());
+
+        // Empty commitment section: count = 0 plus a point-at-infinity PoK
+        let mut with_trailer = bytes.clone();
+        with_trailer.extend([0, 0, 0, 0]);
+        with_trailer.push(0b0100_0000);
+        with_trailer.extend([0u8; GNARK_G1_SIZE - 1]);
+        assert!(parse_gnark_proof(&with_trailer).is_ok());
+
+        assert!(parse_gnark_proof(&bytes[..100]).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+// Verifying key sources
+//
+// Reads serialized verifying keys from disk or over HTTP(S). Remote keys are
+// always pinned to a SHA-256 digest so a compromised server can't swap the key.
+//
+// Simple deployments can skip key plumbing: the standard key directory is
+// `~/.zkrag/keys`, where the prover caches `proving_key.bin`. A
+// `verifying_key.bin` there is used as is; otherwise the verifying key is
+// extracted from the proving key.
+
+use ark_bn254::Bn254;
+use ark_groth16::ProvingKey;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use crate::error::{bail, Result, ResultExt, VerifierError};
+
+/// Upper bound on key size read from disk or the network
+pub const MAX_KEY_BYTES: u64 = zkrag_verifier_core::MAX_VERIFYING_KEY_SIZE as u64;
+
+/// Environment variable overriding the standard key directory; set it empty
+/// to disable discovery
+pub const KEY_DIR_ENV: &str = "ZKRAG_KEY_DIR";
+
+/// Verifying key file in a key directory
+pub const VERIFYING_KEY_FILE: &str = "verifying_key.bin";
+
+/// Proving key file the prover caches in a key directory
+pub const PROVING_KEY_FILE: &str = "proving_key.bin";
+
+/// Hex-encoded SHA-256 digest of serialized key bytes
+pub fn key_digest(key_bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(key_bytes))
+}
+
+/// Check key bytes against a pinned hex digest
+pub fn check_digest(key_bytes: &[u8], expected_sha256: &str) -> Result<()> {
+    let actual = key_digest(key_bytes);
+    if !actual.eq_ignore_ascii_case(expected_sha256.trim()) {
+        bail!(
+            Rejected,
+            "Verifying key digest mismatch: expected {}, got {}",
+            expected_sha256,
+            actual
+        );
+    }
+    Ok(())
+}
+
+/// Read key bytes from a file, refusing files over `MAX_KEY_BYTES`
+pub fn read_key_file(path: &Path) -> Result<Vec<u8>> {
+    let file = fs::File::open(path)
+        .with_context(|| format!("Failed to read verifying key from {}", path.display()))?;
+
+    let mut bytes = Vec::new();
+    file.take(MAX_KEY_BYTES + 1).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > MAX_KEY_BYTES {
+        bail!(
+            Malformed,
+            "Verifying key at {} exceeds {} bytes",
+            path.display(),
+            MAX_KEY_BYTES
+        );
+    }
+    Ok(bytes)
+}
+
+/// Standard key directory: `$ZKRAG_KEY_DIR`, else `~/.zkrag/keys`
+pub fn default_key_dir() -> Option<PathBuf> {
+    match std::env::var_os(KEY_DIR_ENV) {
+        Some(dir) if dir.is_empty() => None,
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => dirs::home_dir().map(|home| home.join(".zkrag").join("keys")),
+    }
+}
+
+/// Document query verifying key from a key directory, or `None` if the
+/// directory holds no key
+pub fn discover_key(dir: &Path) -> Result<Option<Vec<u8>>> {
+    let vk_path = dir.join(VERIFYING_KEY_FILE);
+    if vk_path.exists() {
+        return read_key_file(&vk_path).map(Some);
+    }
+
+    let pk_path = dir.join(PROVING_KEY_FILE);
+    if !pk_path.exists() {
+        return Ok(None);
+    }
+    let file = fs::File::open(&pk_path)
+        .with_context(|| format!("Failed to read proving key from {}", pk_path.display()))?;
+    // Points are validated when the extracted verifying key is prepared
+    let pk = ProvingKey::<Bn254>::deserialize_compressed_unchecked(BufReader::new(file)).map_err(
+        |e| VerifierError::malformed(format!("Invalid proving key {}: {}", pk_path.display(), e)),
+    )?;
+    let mut bytes = Vec::new();
+    pk.vk
+        .serialize_compressed(&mut bytes)
+        .map_err(VerifierError::malformed)?;
+    Ok(Some(bytes))
+}
+
+/// Download key bytes from a URL and check them against a pinned digest
+pub fn fetch_key(url: &str, expected_sha256: &str) -> Result<Vec<u8>> {
+    let response = ureq::get(url)
+        .call()
+        .with_context(|| format!("Failed to fetch verifying key from {}", url))?;
+
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .take(MAX_KEY_BYTES + 1)
+        .read_to_end(&mut bytes)?;
+
+    if bytes.len() as u64 > MAX_KEY_BYTES {
+        bail!(
+            Malformed,
+            "Verifying key at {} exceeds {} bytes",
+            url,
+            MAX_KEY_BYTES
+        );
+    }
+
+    check_digest(&bytes, expected_sha256)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_digest() {
+        let bytes = b"verifying key";
+        let digest = key_digest(bytes);
+
+        assert!(check_digest(bytes, &digest).is_ok());
+        assert!(check_digest(bytes, &digest.to_uppercase()).is_ok());
+        assert!(check_digest(b"other key", &digest).is_err());
+    }
+
+    #[test]
+    fn test_discover_key() {
+        // Per process, so concurrent runs don't delete each other's keys
+        let dir = std::env::temp_dir().join(format!("zkrag-discover-key-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        assert!(discover_key(&dir).unwrap().is_none());
+
+        // The prover's cached proving key embeds the verifying key
+        let (pk, vk_bytes, _) = crate::tests::fixture();
+        let mut pk_bytes = Vec::new();
+        pk.serialize_compressed(&mut pk_bytes).unwrap();
+        fs::write(dir.join(PROVING_KEY_FILE), pk_bytes).unwrap();
+        assert_eq!(discover_key(&dir).unwrap(), Some(vk_bytes));
+
+        // An explicit verifying key takes precedence
+        fs::write(dir.join(VERIFYING_KEY_FILE), b"vk").unwrap();
+        assert_eq!(discover_key(&dir).unwrap(), Some(b"vk".to_vec()));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
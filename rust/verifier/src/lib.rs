@@ -2,70 +2,624 @@
 //
 // Verifies zero-knowledge proofs for privacy-preserving RAG operations
 
-use ark_bn254::{Bn254, Fr};
-use ark_groth16::{Groth16, PreparedVerifyingKey, Proof, VerifyingKey};
-use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
-use anyhow::{Context, Result};
+use ark_bn254::Fr;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
+use zkrag_verifier_core as core;
+
+use crate::error::Result;
+
+pub mod audit;
+mod batch;
+pub mod builder;
+pub mod bundle;
+pub mod cache;
+pub mod canonical;
+pub mod curve;
+#[cfg(feature = "diagnostics")]
+pub mod diagnostic;
+pub mod envelope;
+pub mod error;
+pub mod interop;
+pub mod keys;
+pub mod metrics;
+pub mod nullifier;
+pub mod policy;
+pub mod quorum;
+pub mod receipt;
+pub mod registry;
+pub mod revocation;
+pub mod solidity;
+pub mod stream;
+pub mod timestamp;
+
+pub use audit::{AuditRecord, AuditSink, JsonlAuditLog};
+pub use builder::VerifierBuilder;
+pub use bundle::{export_bundle, verify_bundle, VerificationBundle};
+pub use cache::VerificationCache;
+pub use curve::Curve;
+pub use envelope::{ProofEnvelope, DOCUMENT_QUERY_CIRCUIT_ID};
+pub use error::VerifierError;
+pub use metrics::{MetricEvent, MetricsSnapshot, VerifierMetrics};
+pub use nullifier::{MemoryNullifierStore, NullifierStore};
+pub use policy::{FreshnessPolicy, PolicyContext, TenantScope, VerificationPolicy, VerifierPolicy};
+pub use quorum::KeyQuorum;
+pub use receipt::{ReceiptSigner, VerificationReceipt};
+pub use registry::{KeyRegistry, KeySchedule, KeyState, RegisteredKey, VerifyingKeyInfo};
+pub use revocation::{RevocationList, RevocationRegistry};
+pub use stream::StreamSummary;
+pub use timestamp::{TimestampAttestation, TimestampAuthority};
+pub use zkrag_verifier_core::ProofEncoding;
 
 /// Public inputs for a query verification
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PublicInputs {
     pub document_commitment: String,
     pub model_hash: String,
     pub timestamp: u64,
+    /// Verifier challenge the proof is bound to, folded into the commitment
+    /// input (see `zkrag_circuits::utils::bound_commitment`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+}
+
+impl PublicInputs {
+    /// Field elements in circuit order: commitment, model hash, timestamp
+    pub fn to_field_elements(&self) -> Vec<Fr> {
+        core::public_inputs_to_fields(
+            &core::bound_commitment(&self.document_commitment, self.nonce.as_deref()),
+            &self.model_hash,
+            self.timestamp,
+        )
+        .to_vec()
+    }
+
+    /// Hex-encoded SHA-256 of the canonical JSON encoding, identical across
+    /// languages for the same inputs
+    pub fn digest(&self) -> String {
+        canonical::canonical_digest(self).expect("public inputs serialize")
+    }
+}
+
+/// Allowed clock skew for timestamps slightly in the future
+pub const MAX_CLOCK_SKEW_SECS: u64 = 60;
+
+/// Largest accepted proof on any supported curve (uncompressed BLS12-381)
+pub const MAX_PROOF_BYTES: usize = 384;
+
+/// Why a proof failed verification
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum VerificationFailure {
+    /// Proof bytes could not be deserialized
+    MalformedProof { message: String },
+    /// Proof bytes exceed the largest supported encoding
+    ProofTooLarge { size: usize, max: usize },
+    /// No verifying key has been loaded
+    KeyMissing,
+    /// The Groth16 pairing check did not hold
+    PairingFailed,
+    /// Timestamp is outside the accepted freshness window
+    StaleTimestamp { timestamp: u64 },
+    /// A public input references a revoked document commitment or model
+    Revoked { field: String },
+    /// A deployment policy rejected an otherwise valid proof
+    PolicyViolation { message: String },
+    /// Envelope version is outside the supported range
+    UnsupportedEnvelope { version: u16 },
+    /// Envelope references a verifying key that isn't registered
+    UnknownKey { key_id: String },
+    /// Envelope references a key whose activation time hasn't come
+    KeyNotActive { key_id: String },
+    /// Envelope references a key past its rotation grace window
+    KeyRetired { key_id: String },
+    /// Envelope's circuit doesn't match the selected key's circuit
+    CircuitMismatch { circuit_id: String },
+    /// Envelope's curve doesn't match the selected key's curve
+    CurveMismatch { curve: Curve },
+    /// Envelope's public inputs differ from the caller's
+    InputMismatch,
+    /// The proof's nullifier was already presented
+    Replayed { nullifier: String },
+    /// Fewer quorum keys accepted the statement than required
+    QuorumNotMet { accepted: usize, required: usize },
+    /// The RFC 3161 timestamp token is invalid or doesn't cover the proof
+    TimestampToken { message: String },
+}
+
+impl VerificationFailure {
+    /// Stable machine-readable code, matching the serialized `code` tag
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::MalformedProof { .. } => "malformed_proof",
+            Self::ProofTooLarge { .. } => "proof_too_large",
+            Self::KeyMissing => "key_missing",
+            Self::PairingFailed => "pairing_failed",
+            Self::StaleTimestamp { .. } => "stale_timestamp",
+            Self::Revoked { .. } => "revoked",
+            Self::PolicyViolation { .. } => "policy_violation",
+            Self::UnsupportedEnvelope { .. } => "unsupported_envelope",
+            Self::UnknownKey { .. } => "unknown_key",
+            Self::KeyNotActive { .. } => "key_not_active",
+            Self::KeyRetired { .. } => "key_retired",
+            Self::CircuitMismatch { .. } => "circuit_mismatch",
+            Self::CurveMismatch { .. } => "curve_mismatch",
+            Self::InputMismatch => "input_mismatch",
+            Self::Replayed { .. } => "replayed",
+            Self::QuorumNotMet { .. } => "quorum_not_met",
+            Self::TimestampToken { .. } => "timestamp_token",
+        }
+    }
+}
+
+impl From<core::CoreError> for VerificationFailure {
+    fn from(error: core::CoreError) -> Self {
+        match error {
+            core::CoreError::MalformedKey(message) | core::CoreError::MalformedProof(message) => {
+                Self::MalformedProof { message }
+            }
+            core::CoreError::ProofTooLarge { len, max } => Self::ProofTooLarge { size: len, max },
+            error @ (core::CoreError::KeyTooLarge { .. } | core::CoreError::IdentityPoint(_)) => {
+                Self::MalformedProof {
+                    message: error.to_string(),
+                }
+            }
+            core::CoreError::PairingFailed => Self::PairingFailed,
+        }
+    }
+}
+
+impl fmt::Display for VerificationFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MalformedProof { message } => write!(f, "malformed proof: {}", message),
+            Self::ProofTooLarge { size, max } => {
+                write!(f, "proof is {} bytes, limit is {}", size, max)
+            }
+            Self::KeyMissing => write!(f, "no verifying key loaded"),
+            Self::PairingFailed => write!(f, "pairing check failed"),
+            Self::StaleTimestamp { timestamp } => write!(f, "stale timestamp {}", timestamp),
+            Self::Revoked { field } => write!(f, "revoked {}", field),
+            Self::PolicyViolation { message } => write!(f, "policy violation: {}", message),
+            Self::UnsupportedEnvelope { version } => {
+                write!(f, "unsupported envelope version {}", version)
+            }
+            Self::UnknownKey { key_id } => write!(f, "unknown verifying key {}", key_id),
+            Self::KeyNotActive { key_id } => {
+                write!(f, "verifying key {} is not active yet", key_id)
+            }
+            Self::KeyRetired { key_id } => write!(f, "verifying key {} is retired", key_id),
+            Self::CircuitMismatch { circuit_id } => {
+                write!(f, "key does not belong to circuit {}", circuit_id)
+            }
+            Self::CurveMismatch { curve } => write!(f, "key is not over curve {}", curve),
+            Self::InputMismatch => write!(f, "public inputs do not match envelope"),
+            Self::Replayed { nullifier } => write!(f, "replayed nullifier {}", nullifier),
+            Self::QuorumNotMet { accepted, required } => {
+                write!(
+                    f,
+                    "{} of {} required quorum keys accepted",
+                    accepted, required
+                )
+            }
+            Self::TimestampToken { message } => write!(f, "invalid timestamp token: {}", message),
+        }
+    }
 }
 
 /// Verification result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VerificationResult {
     pub is_valid: bool,
+    pub reason: Option<VerificationFailure>,
     pub public_inputs: PublicInputs,
     pub verified_at: u64,
+    /// Whether the pairing check was skipped because the proof was cached
+    #[serde(default)]
+    pub cache_hit: bool,
+    /// Signed attestation, present when the verifier has a receipt signer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receipt: Option<VerificationReceipt>,
+    /// TSA genTime, present when a timestamp token was validated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attested_at: Option<u64>,
+    /// Hex SHA-256 of the proof bytes
+    #[serde(default)]
+    pub proof_digest: String,
+    /// Key id of the verifying key used, absent when no single key was
+    /// selected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verifying_key_fingerprint: Option<String>,
+}
+
+/// Hex-encoded SHA-256 digest of serialized proof bytes
+pub fn proof_digest(proof_bytes: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    hex::encode(Sha256::digest(proof_bytes))
 }
 
 /// Verifier for document query proofs
 pub struct QueryVerifier {
-    verifying_key: Option<PreparedVerifyingKey<Bn254>>,
+    keys: KeyRegistry,
+    audit: Option<Arc<dyn AuditSink>>,
+    policies: Vec<Arc<dyn VerificationPolicy>>,
+    cache: Option<Arc<VerificationCache>>,
+    signer: Option<Arc<ReceiptSigner>>,
+    metrics: Option<Arc<VerifierMetrics>>,
+    nullifiers: Option<Arc<dyn NullifierStore>>,
+    timestamp_authority: Option<Arc<TimestampAuthority>>,
+    quorum: Option<Arc<KeyQuorum>>,
+    proof_encoding: ProofEncoding,
 }
 
 impl QueryVerifier {
-    /// Create a new verifier instance
+    /// Create a verifier with default settings, loading the document query
+    /// key from the standard key directory if one is there
     pub fn new() -> Result<Self> {
-        Ok(Self {
-            verifying_key: None,
-        })
+        VerifierBuilder::new().default_key_dir().build()
+    }
+
+    /// Configure a verifier before constructing it
+    pub fn builder() -> VerifierBuilder {
+        VerifierBuilder::new()
     }
 
     /// Load verifying key
     pub fn load_key(&mut self, key_bytes: &[u8]) -> Result<()> {
-        let vk = VerifyingKey::<Bn254>::deserialize_compressed(key_bytes)?;
-        self.verifying_key = Some(PreparedVerifyingKey::from(vk));
+        self.keys.insert(DOCUMENT_QUERY_CIRCUIT_ID, key_bytes)?;
         Ok(())
     }
 
-    /// Verify a proof
+    /// Registered verifying keys
+    pub fn keys(&self) -> &KeyRegistry {
+        &self.keys
+    }
+
+    /// Mutable access to the key registry, for loading keys of other circuits
+    /// or older key versions referenced by envelopes
+    pub fn keys_mut(&mut self) -> &mut KeyRegistry {
+        &mut self.keys
+    }
+
+    /// Load verifying key from a file
+    pub fn load_key_from_path(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let bytes = keys::read_key_file(path.as_ref())?;
+        self.load_key(&bytes)
+    }
+
+    /// Load verifying key from a URL, pinned to its hex SHA-256 digest
+    pub fn load_key_from_url(&mut self, url: &str, expected_sha256: &str) -> Result<()> {
+        let bytes = keys::fetch_key(url, expected_sha256)?;
+        self.load_key(&bytes)
+    }
+
+    /// Verify a proof.
+    ///
+    /// Proofs are checked against the current key, then against keys still
+    /// in their rotation grace window, so that proofs made just before a
+    /// rotation keep verifying until the old key retires.
     pub fn verify(
         &self,
         proof_bytes: &[u8],
         public_inputs: PublicInputs,
     ) -> Result<VerificationResult> {
-        // TODO: Implement actual verification
-        // 1. Deserialize proof
-        // 2. Convert public inputs to field elements
-        // 3. Run Groth16 verification
-        // 4. Return result
-
-        // Placeholder - always returns valid
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)?
-            .as_secs();
-
-        Ok(VerificationResult {
-            is_valid: true,
+        let started = Instant::now();
+        let now = unix_now()?;
+        let (registered, outcome) = self.check_with_grace(proof_bytes, &public_inputs, now);
+
+        self.finish(
+            proof_bytes,
+            public_inputs,
+            registered,
+            outcome,
+            now,
+            started,
+        )
+    }
+
+    /// Verify a proof accompanied by an RFC 3161 timestamp token.
+    ///
+    /// The token must be signed by the configured timestamp authority over
+    /// `timestamp::timestamp_imprint(proof_bytes, &public_inputs)`, and its
+    /// genTime must follow the proof's timestamp within the authority's
+    /// `max_delay`. Valid results carry genTime as `attested_at`.
+    pub fn verify_timestamped(
+        &self,
+        proof_bytes: &[u8],
+        public_inputs: PublicInputs,
+        token: &[u8],
+    ) -> Result<VerificationResult> {
+        let started = Instant::now();
+        let now = unix_now()?;
+        let attestation = match &self.timestamp_authority {
+            Some(authority) => authority
+                .validate(token, proof_bytes, &public_inputs)
+                .map_err(|message| VerificationFailure::TimestampToken { message }),
+            None => Err(VerificationFailure::TimestampToken {
+                message: "no timestamp authority configured".to_string(),
+            }),
+        };
+        let attested_at = attestation.as_ref().ok().map(|a| a.gen_time);
+        let (registered, outcome) = match attestation {
+            Ok(_) => self.check_with_grace(proof_bytes, &public_inputs, now),
+            Err(failure) => (
+                self.keys.current_at(DOCUMENT_QUERY_CIRCUIT_ID, now),
+                Err(failure),
+            ),
+        };
+
+        let mut result = self.finish(
+            proof_bytes,
+            public_inputs,
+            registered,
+            outcome,
+            now,
+            started,
+        )?;
+        result.attested_at = attested_at.filter(|_| result.is_valid);
+        Ok(result)
+    }
+
+    /// Verify several proofs, returning one result per item in order
+    pub fn verify_batch(
+        &self,
+        items: &[(Vec<u8>, PublicInputs)],
+    ) -> Result<Vec<VerificationResult>> {
+        if let Some(metrics) = &self.metrics {
+            metrics.record_batch(items.len());
+        }
+        items
+            .iter()
+            .map(|(proof_bytes, public_inputs)| self.verify(proof_bytes, public_inputs.clone()))
+            .collect()
+    }
+
+    /// Verify a proof envelope.
+    ///
+    /// The key is selected by the envelope's `key_id`, or the current key for
+    /// its circuit. If `expected` is given, the envelope's public inputs must
+    /// match it exactly.
+    pub fn verify_envelope(
+        &self,
+        envelope: &ProofEnvelope,
+        expected: Option<&PublicInputs>,
+    ) -> Result<VerificationResult> {
+        let started = Instant::now();
+        let now = unix_now()?;
+        let selected = self.select_key(envelope, now);
+        let outcome = selected.clone().and_then(|registered| {
+            if expected.is_some_and(|expected| *expected != envelope.public_inputs) {
+                return Err(VerificationFailure::InputMismatch);
+            }
+            self.check(
+                Some(registered),
+                &envelope.proof,
+                &envelope.public_inputs,
+                now,
+            )
+        });
+
+        self.finish(
+            &envelope.proof,
+            envelope.public_inputs.clone(),
+            selected.ok(),
+            outcome,
+            now,
+            started,
+        )
+    }
+
+    /// Build the result and record it in the audit sink and metrics
+    fn finish(
+        &self,
+        proof_bytes: &[u8],
+        public_inputs: PublicInputs,
+        key: Option<&RegisteredKey>,
+        outcome: std::result::Result<bool, VerificationFailure>,
+        now: u64,
+        started: Instant,
+    ) -> Result<VerificationResult> {
+        // Only accepted proofs spend their nullifier
+        let outcome = match (outcome, &self.nullifiers) {
+            (Ok(cache_hit), Some(store)) => {
+                let nullifier = nullifier::nullifier(&public_inputs);
+                if store.insert(&nullifier)? {
+                    Ok(cache_hit)
+                } else {
+                    Err(VerificationFailure::Replayed { nullifier })
+                }
+            }
+            (outcome, _) => outcome,
+        };
+        let (cache_hit, reason) = match outcome {
+            Ok(cache_hit) => (cache_hit, None),
+            Err(failure) => (false, Some(failure)),
+        };
+        let mut result = VerificationResult {
+            is_valid: reason.is_none(),
+            reason,
             public_inputs,
             verified_at: now,
-        })
+            cache_hit,
+            receipt: None,
+            attested_at: None,
+            proof_digest: proof_digest(proof_bytes),
+            verifying_key_fingerprint: key.map(|key| key.key_id.clone()),
+        };
+        if let Some(signer) = &self.signer {
+            result.receipt = Some(signer.sign(proof_bytes, &result));
+        }
+
+        if let Some(audit) = &self.audit {
+            audit.record(&result.proof_digest, &result)?;
+        }
+        if let Some(metrics) = &self.metrics {
+            metrics.record_verification(
+                result.reason.as_ref().map(VerificationFailure::code),
+                started.elapsed(),
+            );
+        }
+
+        Ok(result)
+    }
+}
+
+/// Current Unix time in seconds
+fn unix_now() -> Result<u64> {
+    Ok(std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs())
+}
+
+impl QueryVerifier {
+    /// Resolve the verifying key an envelope targets at `now`
+    fn select_key(
+        &self,
+        envelope: &ProofEnvelope,
+        now: u64,
+    ) -> std::result::Result<&RegisteredKey, VerificationFailure> {
+        if !(envelope::MIN_ENVELOPE_VERSION..=envelope::ENVELOPE_VERSION)
+            .contains(&envelope.version)
+        {
+            return Err(VerificationFailure::UnsupportedEnvelope {
+                version: envelope.version,
+            });
+        }
+
+        let registered = match &envelope.key_id {
+            Some(key_id) => {
+                self.keys
+                    .get(key_id)
+                    .ok_or_else(|| VerificationFailure::UnknownKey {
+                        key_id: key_id.clone(),
+                    })?
+            }
+            None => self
+                .keys
+                .current_at(&envelope.circuit_id, now)
+                .ok_or(VerificationFailure::KeyMissing)?,
+        };
+        match registered.schedule.state(now) {
+            KeyState::Pending => {
+                return Err(VerificationFailure::KeyNotActive {
+                    key_id: registered.key_id.clone(),
+                })
+            }
+            KeyState::Retired => {
+                return Err(VerificationFailure::KeyRetired {
+                    key_id: registered.key_id.clone(),
+                })
+            }
+            KeyState::Active | KeyState::Deprecated => {}
+        }
+
+        if registered.circuit_id != envelope.circuit_id {
+            return Err(VerificationFailure::CircuitMismatch {
+                circuit_id: envelope.circuit_id.clone(),
+            });
+        }
+        if registered.key.curve() != envelope.curve {
+            return Err(VerificationFailure::CurveMismatch {
+                curve: envelope.curve,
+            });
+        }
+        Ok(registered)
+    }
+
+    /// Check a document query proof against the current key, then the keys
+    /// in their grace window, returning the key that accepted it
+    fn check_with_grace(
+        &self,
+        proof_bytes: &[u8],
+        public_inputs: &PublicInputs,
+        now: u64,
+    ) -> (
+        Option<&RegisteredKey>,
+        std::result::Result<bool, VerificationFailure>,
+    ) {
+        let mut registered = self.keys.current_at(DOCUMENT_QUERY_CIRCUIT_ID, now);
+        let mut outcome = self.check(registered, proof_bytes, public_inputs, now);
+        if outcome == Err(VerificationFailure::PairingFailed) {
+            for key in self.keys.in_grace(DOCUMENT_QUERY_CIRCUIT_ID, now) {
+                if registered.is_some_and(|current| current.key_id == key.key_id) {
+                    continue;
+                }
+                let retried = self.check(Some(key), proof_bytes, public_inputs, now);
+                if retried.is_ok() {
+                    (registered, outcome) = (Some(key), retried);
+                    break;
+                }
+            }
+        }
+        (registered, outcome)
+    }
+
+    /// Run every check, returning the first failure.
+    ///
+    /// On success, returns whether the pairing check was served from the cache.
+    fn check(
+        &self,
+        registered: Option<&RegisteredKey>,
+        proof_bytes: &[u8],
+        public_inputs: &PublicInputs,
+        now: u64,
+    ) -> std::result::Result<bool, VerificationFailure> {
+        let registered = registered.ok_or(VerificationFailure::KeyMissing)?;
+
+        // Reject oversized input before hashing it for the cache
+        if proof_bytes.len() > MAX_PROOF_BYTES {
+            return Err(VerificationFailure::ProofTooLarge {
+                size: proof_bytes.len(),
+                max: MAX_PROOF_BYTES,
+            });
+        }
+
+        let key = self
+            .cache
+            .as_ref()
+            .map(|_| cache::cache_key(&registered.key_id, proof_bytes, public_inputs));
+        let cache_hit =
+            matches!((&self.cache, &key), (Some(cache), Some(key)) if cache.contains(key));
+
+        if !cache_hit {
+            registered
+                .key
+                .verify(proof_bytes, self.proof_encoding, public_inputs)
+                .map_err(VerificationFailure::from)?;
+
+            if let (Some(cache), Some(key)) = (&self.cache, key) {
+                cache.insert(key);
+            }
+        }
+
+        self.apply_policies(registered, public_inputs, now)?;
+        Ok(cache_hit)
+    }
+
+    /// Run the post-verification policy chain, returning the first failure
+    fn apply_policies(
+        &self,
+        registered: &RegisteredKey,
+        public_inputs: &PublicInputs,
+        now: u64,
+    ) -> std::result::Result<(), VerificationFailure> {
+        let context = PolicyContext {
+            public_inputs,
+            circuit_id: &registered.circuit_id,
+            key_id: &registered.key_id,
+            now,
+        };
+        match self
+            .policies
+            .iter()
+            .find_map(|policy| policy.check(&context))
+        {
+            Some(failure) => Err(failure),
+            None => Ok(()),
+        }
     }
 }
 
@@ -78,6 +632,96 @@ impl Default for QueryVerifier {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ark_bn254::Bn254;
+    use ark_groth16::{Groth16, ProvingKey};
+    use ark_serialize::CanonicalSerialize;
+    use std::time::Duration;
+    use zkrag_circuits::DocumentQueryCircuit;
+
+    pub(crate) fn public_inputs() -> PublicInputs {
+        PublicInputs {
+            document_commitment: "abc123".to_string(),
+            model_hash: "model456".to_string(),
+            timestamp: 1234567890,
+            nonce: None,
+        }
+    }
+
+    pub(crate) fn circuit(public_inputs: &PublicInputs) -> DocumentQueryCircuit<Fr> {
+        let [document_commitment, model_hash, timestamp]: [Fr; 3] =
+            public_inputs.to_field_elements().try_into().unwrap();
+        DocumentQueryCircuit::new(
+            vec![Fr::from(1u64), Fr::from(2u64)],
+            vec![],
+            vec![],
+            document_commitment,
+            model_hash,
+            timestamp,
+        )
+    }
+
+    /// Proving key, serialized verifying key, and a valid proof for `public_inputs()`
+    pub(crate) fn fixture() -> (ProvingKey<Bn254>, Vec<u8>, Vec<u8>) {
+        let mut rng = ark_std::test_rng();
+        let pk = Groth16::<Bn254>::generate_random_parameters_with_reduction(
+            circuit(&public_inputs()),
+            &mut rng,
+        )
+        .unwrap();
+        let proof = Groth16::<Bn254>::create_random_proof_with_reduction(
+            circuit(&public_inputs()),
+            &pk,
+            &mut rng,
+        )
+        .unwrap();
+
+        let mut vk_bytes = Vec::new();
+        pk.vk.serialize_compressed(&mut vk_bytes).unwrap();
+        let mut proof_bytes = Vec::new();
+        proof.serialize_compressed(&mut proof_bytes).unwrap();
+
+        (pk, vk_bytes, proof_bytes)
+    }
+
+    /// Serialized verifying key of a second setup of the same circuit
+    pub(crate) fn other_key() -> Vec<u8> {
+        let mut rng = ark_std::test_rng();
+        ark_std::rand::RngCore::next_u64(&mut rng);
+        let pk = Groth16::<Bn254>::generate_random_parameters_with_reduction(
+            circuit(&public_inputs()),
+            &mut rng,
+        )
+        .unwrap();
+        let mut vk_bytes = Vec::new();
+        pk.vk.serialize_compressed(&mut vk_bytes).unwrap();
+        vk_bytes
+    }
+
+    #[test]
+    fn test_field_mapping_matches_circuits() {
+        let value = "abc123";
+        assert_eq!(
+            core::public_input_to_field(value),
+            zkrag_circuits::utils::public_input_to_field::<Fr>(value)
+        );
+        assert_eq!(
+            core::bound_commitment(value, Some("n1")),
+            zkrag_circuits::utils::bound_commitment(value, Some("n1"))
+        );
+    }
+
+    #[test]
+    fn test_public_inputs_digest() {
+        assert_eq!(
+            canonical::to_canonical_json(&public_inputs()).unwrap(),
+            r#"{"document_commitment":"abc123","model_hash":"model456","timestamp":1234567890}"#
+        );
+        assert_eq!(public_inputs().digest(), public_inputs().digest());
+
+        let mut other = public_inputs();
+        other.timestamp += 1;
+        assert_ne!(public_inputs().digest(), other.digest());
+    }
 
     #[test]
     fn test_verifier_creation() {
@@ -86,16 +730,414 @@ mod tests {
     }
 
     #[test]
-    fn test_placeholder_verification() {
-        let verifier = QueryVerifier::new().unwrap();
-        let public_inputs = PublicInputs {
-            document_commitment: "abc123".to_string(),
-            model_hash: "model456".to_string(),
-            timestamp: 1234567890,
+    fn test_verification_without_key() {
+        let verifier = QueryVerifier::builder().build().unwrap();
+
+        let result = verifier.verify(&[0u8; 128], public_inputs()).unwrap();
+        assert!(!result.is_valid);
+        assert_eq!(result.reason, Some(VerificationFailure::KeyMissing));
+    }
+
+    #[test]
+    fn test_verification_reasons() {
+        let (_, vk_bytes, proof_bytes) = fixture();
+        let verifier = QueryVerifier::builder()
+            .key_bytes(vk_bytes.clone())
+            .build()
+            .unwrap();
+
+        let result = verifier.verify(&proof_bytes, public_inputs()).unwrap();
+        assert!(result.is_valid);
+        assert_eq!(result.reason, None);
+
+        let result = verifier.verify(&[0u8; 3], public_inputs()).unwrap();
+        assert!(matches!(
+            result.reason,
+            Some(VerificationFailure::MalformedProof { .. })
+        ));
+
+        let result = verifier
+            .verify(&vec![0u8; 1 << 20], public_inputs())
+            .unwrap();
+        assert!(matches!(
+            result.reason,
+            Some(VerificationFailure::ProofTooLarge { .. })
+        ));
+
+        let mut wrong_inputs = public_inputs();
+        wrong_inputs.model_hash = "model789".to_string();
+        let result = verifier.verify(&proof_bytes, wrong_inputs).unwrap();
+        assert_eq!(result.reason, Some(VerificationFailure::PairingFailed));
+
+        let verifier = QueryVerifier::builder()
+            .key_bytes(vk_bytes)
+            .max_proof_age(Duration::from_secs(3600))
+            .build()
+            .unwrap();
+        let result = verifier.verify(&proof_bytes, public_inputs()).unwrap();
+        assert_eq!(
+            result.reason,
+            Some(VerificationFailure::StaleTimestamp {
+                timestamp: 1234567890
+            })
+        );
+    }
+
+    #[test]
+    fn test_revoked_model_fails() {
+        let (_, vk_bytes, proof_bytes) = fixture();
+        let mut list = RevocationList::new();
+        list.revoke_model("model456");
+
+        let verifier = QueryVerifier::builder()
+            .key_bytes(vk_bytes)
+            .revocations(Arc::new(RevocationRegistry::new(list)))
+            .build()
+            .unwrap();
+
+        let result = verifier.verify(&proof_bytes, public_inputs()).unwrap();
+        assert!(!result.is_valid);
+        assert!(matches!(
+            result.reason,
+            Some(VerificationFailure::Revoked { .. })
+        ));
+    }
+
+    #[test]
+    fn test_uncompressed_proof() {
+        let (_, vk_bytes, proof_bytes) = fixture();
+        let proof = core::deserialize_proof(&proof_bytes).unwrap();
+        let mut uncompressed = Vec::new();
+        proof.serialize_uncompressed(&mut uncompressed).unwrap();
+
+        let verifier = QueryVerifier::builder()
+            .key_bytes(vk_bytes.clone())
+            .build()
+            .unwrap();
+        assert!(
+            verifier
+                .verify(&uncompressed, public_inputs())
+                .unwrap()
+                .is_valid
+        );
+
+        let verifier = QueryVerifier::builder()
+            .key_bytes(vk_bytes)
+            .proof_encoding(ProofEncoding::Compressed)
+            .build()
+            .unwrap();
+        let result = verifier.verify(&uncompressed, public_inputs()).unwrap();
+        assert!(matches!(
+            result.reason,
+            Some(VerificationFailure::MalformedProof { .. })
+        ));
+    }
+
+    #[test]
+    fn test_cache_hit() {
+        let (_, vk_bytes, proof_bytes) = fixture();
+        let verifier = QueryVerifier::builder()
+            .key_bytes(vk_bytes)
+            .cache_capacity(16.try_into().unwrap())
+            .build()
+            .unwrap();
+
+        let first = verifier.verify(&proof_bytes, public_inputs()).unwrap();
+        assert!(first.is_valid && !first.cache_hit);
+
+        let second = verifier.verify(&proof_bytes, public_inputs()).unwrap();
+        assert!(second.is_valid && second.cache_hit);
+
+        let mut other_inputs = public_inputs();
+        other_inputs.timestamp += 1;
+        let third = verifier.verify(&proof_bytes, other_inputs).unwrap();
+        assert!(!third.is_valid && !third.cache_hit);
+    }
+
+    #[test]
+    fn test_signed_receipt() {
+        let (_, vk_bytes, proof_bytes) = fixture();
+        let signer = Arc::new(ReceiptSigner::generate());
+        let verifier = QueryVerifier::builder()
+            .key_bytes(vk_bytes)
+            .receipt_signer(signer.clone())
+            .build()
+            .unwrap();
+
+        let result = verifier.verify(&proof_bytes, public_inputs()).unwrap();
+        let receipt = result.receipt.unwrap();
+        assert!(receipt.is_valid);
+        assert_eq!(receipt.proof_digest, proof_digest(&proof_bytes));
+        assert!(receipt.verify_signature(&signer.public_key()).is_ok());
+    }
+
+    #[test]
+    fn test_batch_metrics() {
+        let (_, vk_bytes, proof_bytes) = fixture();
+        let metrics = Arc::new(VerifierMetrics::new());
+        let verifier = QueryVerifier::builder()
+            .key_bytes(vk_bytes)
+            .metrics(metrics.clone())
+            .build()
+            .unwrap();
+
+        let results = verifier
+            .verify_batch(&[
+                (proof_bytes.clone(), public_inputs()),
+                (vec![0u8; 3], public_inputs()),
+            ])
+            .unwrap();
+        assert!(results[0].is_valid);
+        assert!(!results[1].is_valid);
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.verifications_total, 2);
+        assert_eq!(snapshot.failures_by_reason["malformed_proof"], 1);
+        assert_eq!(snapshot.batch_sizes.count, 1);
+    }
+
+    #[test]
+    fn test_replayed_proof_fails() {
+        let (_, vk_bytes, proof_bytes) = fixture();
+        let verifier = QueryVerifier::builder()
+            .key_bytes(vk_bytes)
+            .nullifier_store(Arc::new(MemoryNullifierStore::new()))
+            .build()
+            .unwrap();
+
+        let mut wrong_inputs = public_inputs();
+        wrong_inputs.model_hash = "model789".to_string();
+        assert!(
+            !verifier
+                .verify(&proof_bytes, wrong_inputs)
+                .unwrap()
+                .is_valid
+        );
+
+        assert!(
+            verifier
+                .verify(&proof_bytes, public_inputs())
+                .unwrap()
+                .is_valid
+        );
+        let result = verifier.verify(&proof_bytes, public_inputs()).unwrap();
+        assert!(matches!(
+            result.reason,
+            Some(VerificationFailure::Replayed { .. })
+        ));
+    }
+
+    #[test]
+    fn test_nonce_bound_proof() {
+        let (pk, vk_bytes, _) = fixture();
+        let verifier = QueryVerifier::builder()
+            .key_bytes(vk_bytes)
+            .build()
+            .unwrap();
+        let mut bound = public_inputs();
+        bound.nonce = Some("ab".repeat(32));
+        let proof = Groth16::<Bn254>::create_random_proof_with_reduction(
+            circuit(&bound),
+            &pk,
+            &mut ark_std::test_rng(),
+        )
+        .unwrap();
+        let mut proof_bytes = Vec::new();
+        proof.serialize_compressed(&mut proof_bytes).unwrap();
+
+        // Only verifies alongside its nonce
+        assert!(
+            verifier
+                .verify(&proof_bytes, bound.clone())
+                .unwrap()
+                .is_valid
+        );
+        assert!(
+            !verifier
+                .verify(&proof_bytes, public_inputs())
+                .unwrap()
+                .is_valid
+        );
+        bound.nonce = Some("cd".repeat(32));
+        assert!(!verifier.verify(&proof_bytes, bound).unwrap().is_valid);
+    }
+
+    #[test]
+    fn test_unapproved_model_fails() {
+        let (_, vk_bytes, proof_bytes) = fixture();
+        let verifier = QueryVerifier::builder()
+            .key_bytes(vk_bytes)
+            .policy(VerifierPolicy::new().allowed_models(["model789".to_string()].into()))
+            .build()
+            .unwrap();
+
+        let result = verifier.verify(&proof_bytes, public_inputs()).unwrap();
+        assert!(matches!(
+            result.reason,
+            Some(VerificationFailure::PolicyViolation { .. })
+        ));
+    }
+
+    #[test]
+    fn test_rotation_grace_window() {
+        let (_, vk_bytes, proof_bytes) = fixture();
+        let mut verifier = QueryVerifier::builder()
+            .key_bytes(vk_bytes.clone())
+            .build()
+            .unwrap();
+        let old_id = keys::key_digest(&vk_bytes);
+        let now = unix_now().unwrap();
+        let (new_id, _) = verifier
+            .keys_mut()
+            .rotate(
+                DOCUMENT_QUERY_CIRCUIT_ID,
+                Curve::Bn254,
+                &other_key(),
+                now,
+                3600,
+            )
+            .unwrap();
+
+        // Proofs for the old key verify through the grace window
+        let result = verifier.verify(&proof_bytes, public_inputs()).unwrap();
+        assert!(result.is_valid);
+        assert_eq!(result.verifying_key_fingerprint, Some(old_id.clone()));
+
+        // timestamped ones included
+        let (cert, tsa_key) = timestamp::tests::tsa();
+        let imprint = timestamp::timestamp_imprint(&proof_bytes, &public_inputs());
+        let token = timestamp::tests::token(&cert, &tsa_key, imprint, public_inputs().timestamp);
+        let mut timestamped = QueryVerifier::builder()
+            .key_bytes(vk_bytes.clone())
+            .timestamp_authority(Arc::new(TimestampAuthority::new(vec![cert])))
+            .build()
+            .unwrap();
+        timestamped
+            .keys_mut()
+            .rotate(
+                DOCUMENT_QUERY_CIRCUIT_ID,
+                Curve::Bn254,
+                &other_key(),
+                now,
+                3600,
+            )
+            .unwrap();
+        let result = timestamped
+            .verify_timestamped(&proof_bytes, public_inputs(), &token)
+            .unwrap();
+        assert!(result.is_valid);
+        assert_eq!(result.verifying_key_fingerprint, Some(old_id.clone()));
+
+        // and are refused once it retires
+        verifier.keys_mut().deprecate(&old_id, 0, 0);
+        let result = verifier.verify(&proof_bytes, public_inputs()).unwrap();
+        assert_eq!(result.reason, Some(VerificationFailure::PairingFailed));
+        assert_eq!(result.verifying_key_fingerprint, Some(new_id));
+        let pinned = ProofEnvelope::new(proof_bytes, public_inputs()).with_key_id(&old_id);
+        let result = verifier.verify_envelope(&pinned, None).unwrap();
+        assert_eq!(
+            result.reason,
+            Some(VerificationFailure::KeyRetired { key_id: old_id })
+        );
+    }
+
+    #[test]
+    fn test_verify_envelope() {
+        let (_, vk_bytes, proof_bytes) = fixture();
+        let verifier = QueryVerifier::builder()
+            .key_bytes(vk_bytes.clone())
+            .build()
+            .unwrap();
+        let key_id = keys::key_digest(&vk_bytes);
+
+        let envelope = ProofEnvelope::new(proof_bytes.clone(), public_inputs());
+        let result = verifier
+            .verify_envelope(&envelope, Some(&public_inputs()))
+            .unwrap();
+        assert!(result.is_valid);
+        assert_eq!(result.proof_digest, proof_digest(&proof_bytes));
+        assert_eq!(result.verifying_key_fingerprint.as_ref(), Some(&key_id));
+
+        let pinned = envelope.clone().with_key_id(key_id);
+        assert!(verifier.verify_envelope(&pinned, None).unwrap().is_valid);
+
+        let unknown = envelope.clone().with_key_id("unknown");
+        let result = verifier.verify_envelope(&unknown, None).unwrap();
+        assert!(matches!(
+            result.reason,
+            Some(VerificationFailure::UnknownKey { .. })
+        ));
+        assert_eq!(result.verifying_key_fingerprint, None);
+
+        let mut other_inputs = public_inputs();
+        other_inputs.timestamp += 1;
+        let result = verifier
+            .verify_envelope(&envelope, Some(&other_inputs))
+            .unwrap();
+        assert_eq!(result.reason, Some(VerificationFailure::InputMismatch));
+    }
+
+    #[test]
+    fn test_bls12_381_envelope() {
+        use ark_bls12_381::{Bls12_381, Fr as BlsFr};
+        use zkrag_circuits::utils::public_input_to_field;
+
+        let inputs = public_inputs();
+        let circuit = || {
+            DocumentQueryCircuit::<BlsFr>::new(
+                vec![BlsFr::from(1u64), BlsFr::from(2u64)],
+                vec![],
+                vec![],
+                public_input_to_field(&inputs.document_commitment),
+                public_input_to_field(&inputs.model_hash),
+                BlsFr::from(inputs.timestamp),
+            )
         };
+        let mut rng = ark_std::test_rng();
+        let pk =
+            Groth16::<Bls12_381>::generate_random_parameters_with_reduction(circuit(), &mut rng)
+                .unwrap();
+        let proof =
+            Groth16::<Bls12_381>::create_random_proof_with_reduction(circuit(), &pk, &mut rng)
+                .unwrap();
+        let mut vk_bytes = Vec::new();
+        pk.vk.serialize_compressed(&mut vk_bytes).unwrap();
+        let mut proof_bytes = Vec::new();
+        proof.serialize_compressed(&mut proof_bytes).unwrap();
+
+        let verifier = QueryVerifier::builder()
+            .curve_key_bytes(DOCUMENT_QUERY_CIRCUIT_ID, Curve::Bls12_381, vk_bytes)
+            .build()
+            .unwrap();
+        let envelope = ProofEnvelope::new(proof_bytes, inputs.clone()).with_curve(Curve::Bls12_381);
+        assert!(verifier.verify_envelope(&envelope, None).unwrap().is_valid);
+
+        let result = verifier
+            .verify_envelope(&envelope.with_curve(Curve::Bn254), None)
+            .unwrap();
+        assert_eq!(
+            result.reason,
+            Some(VerificationFailure::CurveMismatch {
+                curve: Curve::Bn254
+            })
+        );
+    }
+
+    #[test]
+    fn test_load_key_from_path() {
+        let (_, vk_bytes, _) = fixture();
+        let path = std::env::temp_dir().join(format!(
+            "zkrag-load-key-from-path-{}.bin",
+            std::process::id()
+        ));
+        std::fs::write(&path, &vk_bytes).unwrap();
+
+        let mut verifier = QueryVerifier::new().unwrap();
+        assert!(verifier.load_key_from_path(&path).is_ok());
+        assert!(verifier
+            .load_key_from_path(path.with_extension("missing"))
+            .is_err());
 
-        let result = verifier.verify(&[0u8; 128], public_inputs);
-        assert!(result.is_ok());
-        assert!(result.unwrap().is_valid);
+        std::fs::remove_file(&path).unwrap();
     }
 }
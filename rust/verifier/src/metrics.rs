@@ -0,0 +1,220 @@
+// Verifier metrics
+//
+// In-process counters and histograms for verification outcomes, latency, and
+// batch sizes. Services can read a snapshot periodically (e.g. to export to
+// Prometheus) or register a callback that sees every event as it happens.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Latency bucket upper bounds, in seconds
+pub const LATENCY_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Batch size bucket upper bounds
+pub const BATCH_SIZE_BUCKETS: &[f64] =
+    &[1.0, 2.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0];
+
+/// A single observation reported to the callback
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetricEvent {
+    /// One verification finished
+    Verification {
+        is_valid: bool,
+        reason: Option<&'static str>,
+        latency: Duration,
+    },
+    /// A batch of verifications was submitted
+    Batch { size: usize },
+}
+
+/// Cumulative histogram with fixed bucket bounds
+pub struct Histogram {
+    bounds: &'static [f64],
+    counts: Vec<AtomicU64>,
+    sum: Mutex<f64>,
+    count: AtomicU64,
+}
+
+/// Point-in-time copy of a histogram
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistogramSnapshot {
+    /// (upper bound, cumulative count) pairs
+    pub buckets: Vec<(f64, u64)>,
+    pub sum: f64,
+    pub count: u64,
+}
+
+impl Histogram {
+    /// Create a histogram with the given bucket upper bounds
+    pub fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            counts: bounds.iter().map(|_| AtomicU64::new(0)).collect(),
+            sum: Mutex::new(0.0),
+            count: AtomicU64::new(0),
+        }
+    }
+
+    /// Record an observation
+    pub fn observe(&self, value: f64) {
+        for (bound, count) in self.bounds.iter().zip(&self.counts) {
+            if value <= *bound {
+                count.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        *self.sum.lock().unwrap() += value;
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Copy the current state
+    pub fn snapshot(&self) -> HistogramSnapshot {
+        HistogramSnapshot {
+            buckets: self
+                .bounds
+                .iter()
+                .zip(&self.counts)
+                .map(|(bound, count)| (*bound, count.load(Ordering::Relaxed)))
+                .collect(),
+            sum: *self.sum.lock().unwrap(),
+            count: self.count.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Point-in-time copy of all verifier metrics
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    pub verifications_total: u64,
+    pub valid_total: u64,
+    pub failures_by_reason: BTreeMap<String, u64>,
+    pub latency_seconds: HistogramSnapshot,
+    pub batch_sizes: HistogramSnapshot,
+}
+
+type Callback = Box<dyn Fn(&MetricEvent) + Send + Sync>;
+
+/// Verification counters and histograms
+pub struct VerifierMetrics {
+    verifications_total: AtomicU64,
+    valid_total: AtomicU64,
+    failures_by_reason: Mutex<BTreeMap<&'static str, u64>>,
+    latency: Histogram,
+    batch_sizes: Histogram,
+    callback: Option<Callback>,
+}
+
+impl VerifierMetrics {
+    /// Create empty metrics
+    pub fn new() -> Self {
+        Self {
+            verifications_total: AtomicU64::new(0),
+            valid_total: AtomicU64::new(0),
+            failures_by_reason: Mutex::new(BTreeMap::new()),
+            latency: Histogram::new(LATENCY_BUCKETS),
+            batch_sizes: Histogram::new(BATCH_SIZE_BUCKETS),
+            callback: None,
+        }
+    }
+
+    /// Invoke `callback` for every recorded event
+    pub fn with_callback(
+        mut self,
+        callback: impl Fn(&MetricEvent) + Send + Sync + 'static,
+    ) -> Self {
+        self.callback = Some(Box::new(callback));
+        self
+    }
+
+    /// Record a finished verification
+    pub fn record_verification(&self, reason: Option<&'static str>, latency: Duration) {
+        self.verifications_total.fetch_add(1, Ordering::Relaxed);
+        match reason {
+            None => {
+                self.valid_total.fetch_add(1, Ordering::Relaxed);
+            }
+            Some(code) => {
+                *self
+                    .failures_by_reason
+                    .lock()
+                    .unwrap()
+                    .entry(code)
+                    .or_insert(0) += 1;
+            }
+        }
+        self.latency.observe(latency.as_secs_f64());
+
+        self.emit(MetricEvent::Verification {
+            is_valid: reason.is_none(),
+            reason,
+            latency,
+        });
+    }
+
+    /// Record a submitted batch
+    pub fn record_batch(&self, size: usize) {
+        self.batch_sizes.observe(size as f64);
+        self.emit(MetricEvent::Batch { size });
+    }
+
+    /// Copy the current state
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            verifications_total: self.verifications_total.load(Ordering::Relaxed),
+            valid_total: self.valid_total.load(Ordering::Relaxed),
+            failures_by_reason: self
+                .failures_by_reason
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(code, count)| (code.to_string(), *count))
+                .collect(),
+            latency_seconds: self.latency.snapshot(),
+            batch_sizes: self.batch_sizes.snapshot(),
+        }
+    }
+
+    fn emit(&self, event: MetricEvent) {
+        if let Some(callback) = &self.callback {
+            callback(&event);
+        }
+    }
+}
+
+impl Default for VerifierMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_counters_and_callback() {
+        let events = Arc::new(AtomicU64::new(0));
+        let seen = events.clone();
+        let metrics = VerifierMetrics::new().with_callback(move |_| {
+            seen.fetch_add(1, Ordering::Relaxed);
+        });
+
+        metrics.record_verification(None, Duration::from_millis(3));
+        metrics.record_verification(Some("pairing_failed"), Duration::from_millis(30));
+        metrics.record_batch(2);
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.verifications_total, 2);
+        assert_eq!(snapshot.valid_total, 1);
+        assert_eq!(snapshot.failures_by_reason["pairing_failed"], 1);
+        assert_eq!(snapshot.latency_seconds.count, 2);
+        assert_eq!(snapshot.latency_seconds.buckets[1], (0.005, 1));
+        assert_eq!(snapshot.batch_sizes.buckets[1], (2.0, 1));
+        assert_eq!(events.load(Ordering::Relaxed), 3);
+    }
+}
@@ -0,0 +1,229 @@
+// Replay detection
+//
+// Every accepted proof emits a nullifier; presenting a proof whose nullifier
+// was already recorded fails with `Replayed`. The document query circuit has
+// no dedicated nullifier output, so the nullifier is derived from the public
+// inputs rather than the proof bytes: Groth16 proofs can be re-randomized, so
+// the same statement can be proven with different bytes.
+
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use crate::canonical::to_canonical_json;
+use crate::error::Result;
+use crate::PublicInputs;
+
+/// Domain separator keeping nullifiers distinct from other digests of the
+/// same public inputs
+const NULLIFIER_DOMAIN: &[u8] = b"zkrag-nullifier-v1";
+
+/// Hex-encoded nullifier for a statement
+pub fn nullifier(public_inputs: &PublicInputs) -> String {
+    let canonical = to_canonical_json(public_inputs).expect("public inputs serialize");
+    let mut hasher = Sha256::new();
+    hasher.update(NULLIFIER_DOMAIN);
+    hasher.update(canonical.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// Records nullifiers of accepted proofs
+pub trait NullifierStore: Send + Sync {
+    /// Record a nullifier, returning `false` if it was already present.
+    ///
+    /// Must be atomic so concurrent presentations of one proof can't both win.
+    fn insert(&self, nullifier: &str) -> Result<bool>;
+
+    /// Whether a nullifier has been recorded
+    fn contains(&self, nullifier: &str) -> Result<bool>;
+}
+
+/// Nullifiers held in memory, lost on restart
+#[derive(Default)]
+pub struct MemoryNullifierStore {
+    nullifiers: Mutex<HashSet<String>>,
+}
+
+impl MemoryNullifierStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of recorded nullifiers
+    pub fn len(&self) -> usize {
+        self.nullifiers.lock().unwrap().len()
+    }
+
+    /// Whether no nullifiers are recorded
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl NullifierStore for MemoryNullifierStore {
+    fn insert(&self, nullifier: &str) -> Result<bool> {
+        Ok(self
+            .nullifiers
+            .lock()
+            .unwrap()
+            .insert(nullifier.to_string()))
+    }
+
+    fn contains(&self, nullifier: &str) -> Result<bool> {
+        Ok(self.nullifiers.lock().unwrap().contains(nullifier))
+    }
+}
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteNullifierStore;
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+    use rusqlite::{params, Connection};
+    use std::path::Path;
+
+    /// Nullifiers persisted in SQLite, surviving restarts
+    pub struct SqliteNullifierStore {
+        conn: Mutex<Connection>,
+    }
+
+    impl SqliteNullifierStore {
+        /// Open (or create) a nullifier database
+        pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+            let conn = Connection::open(path.as_ref())?;
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS nullifiers (
+                    nullifier TEXT PRIMARY KEY,
+                    recorded_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+                );",
+            )?;
+            Ok(Self {
+                conn: Mutex::new(conn),
+            })
+        }
+    }
+
+    impl NullifierStore for SqliteNullifierStore {
+        fn insert(&self, nullifier: &str) -> Result<bool> {
+            let conn = self.conn.lock().unwrap();
+            let inserted = conn.execute(
+                "INSERT OR IGNORE INTO nullifiers (nullifier) VALUES (?1)",
+                params![nullifier],
+            )?;
+            Ok(inserted == 1)
+        }
+
+        fn contains(&self, nullifier: &str) -> Result<bool> {
+            let conn = self.conn.lock().unwrap();
+            let count: i64 = conn.query_row(
+                "SELECT COUNT(*) FROM nullifiers WHERE nullifier = ?1",
+                params![nullifier],
+                |row| row.get(0),
+            )?;
+            Ok(count > 0)
+        }
+    }
+}
+
+#[cfg(feature = "redis")]
+pub use self::redis::RedisNullifierStore;
+
+#[cfg(feature = "redis")]
+mod redis {
+    use super::*;
+    use ::redis::{Client, Connection};
+
+    /// Nullifiers kept in Redis, shared by every verifier using the server
+    pub struct RedisNullifierStore {
+        conn: Mutex<Connection>,
+        prefix: String,
+    }
+
+    impl RedisNullifierStore {
+        /// Connect to the server at `url`, e.g. `redis://cache:6379/0`,
+        /// keeping nullifiers under `prefix:nullifier:`
+        pub fn connect(url: &str, prefix: &str) -> Result<Self> {
+            let conn = Client::open(url)?.get_connection()?;
+            Ok(Self {
+                conn: Mutex::new(conn),
+                prefix: prefix.to_string(),
+            })
+        }
+
+        fn key(&self, nullifier: &str) -> String {
+            format!("{}:nullifier:{}", self.prefix, nullifier)
+        }
+    }
+
+    impl NullifierStore for RedisNullifierStore {
+        fn insert(&self, nullifier: &str) -> Result<bool> {
+            let mut conn = self.conn.lock().unwrap();
+            let set: Option<String> = ::redis::cmd("SET")
+                .arg(self.key(nullifier))
+                .arg(1)
+                .arg("NX")
+                .query(&mut *conn)?;
+            Ok(set.is_some())
+        }
+
+        fn contains(&self, nullifier: &str) -> Result<bool> {
+            let mut conn = self.conn.lock().unwrap();
+            let exists: bool = ::redis::cmd("EXISTS")
+                .arg(self.key(nullifier))
+                .query(&mut *conn)?;
+            Ok(exists)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_store() {
+        let store = MemoryNullifierStore::new();
+        assert!(store.insert("n1").unwrap());
+        assert!(!store.insert("n1").unwrap());
+        assert!(store.contains("n1").unwrap());
+        assert!(!store.contains("n2").unwrap());
+        assert_eq!(store.len(), 1);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_store() {
+        let path = std::env::temp_dir().join("zkrag_test_nullifiers.sqlite");
+        let _ = std::fs::remove_file(&path);
+
+        let store = SqliteNullifierStore::open(&path).unwrap();
+        assert!(store.insert("n1").unwrap());
+        drop(store);
+
+        let reopened = SqliteNullifierStore::open(&path).unwrap();
+        assert!(!reopened.insert("n1").unwrap());
+        assert!(reopened.contains("n1").unwrap());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// Needs a server at $ZKRAG_TEST_REDIS_URL
+    #[cfg(feature = "redis")]
+    #[test]
+    fn test_redis_store() {
+        let Ok(url) = std::env::var("ZKRAG_TEST_REDIS_URL") else {
+            return;
+        };
+        let prefix = format!("zkrag-test-{}", std::process::id());
+        let store = RedisNullifierStore::connect(&url, &prefix).unwrap();
+        assert!(store.insert("n1").unwrap());
+
+        // Another verifier sees the same set
+        let other = RedisNullifierStore::connect(&url, &prefix).unwrap();
+        assert!(!other.insert("n1").unwrap());
+        assert!(other.contains("n1").unwrap());
+        assert!(!other.contains("n2").unwrap());
+    }
+}
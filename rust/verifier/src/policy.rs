@@ -0,0 +1,230 @@
+// Verifier policy
+//
+// Deployment rules applied to proofs that are cryptographically valid. Each
+// rule implements `VerificationPolicy`; the verifier runs its chain in order
+// after the pairing check and rejects on the first failure. Built-in rules
+// cover freshness, revocation, model allowlists, and tenant scoping.
+//
+// The model allowlist can be written in TOML or JSON:
+//
+//     allowed_models = ["sha256:...", "sha256:..."]
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::error::{bail, Result, ResultExt};
+use crate::{core, PublicInputs, RevocationRegistry, VerificationFailure, MAX_CLOCK_SKEW_SECS};
+
+/// What a policy sees about a cryptographically valid proof
+#[derive(Debug, Clone, Copy)]
+pub struct PolicyContext<'a> {
+    pub public_inputs: &'a PublicInputs,
+    pub circuit_id: &'a str,
+    pub key_id: &'a str,
+    /// Verification time, Unix seconds
+    pub now: u64,
+}
+
+/// A business rule applied after the cryptographic check
+pub trait VerificationPolicy: Send + Sync {
+    /// Why the proof is rejected, or `None` to accept it
+    fn check(&self, context: &PolicyContext<'_>) -> Option<VerificationFailure>;
+}
+
+/// Rejects proofs whose timestamp is older than `max_age` or too far ahead
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FreshnessPolicy {
+    pub max_age: Duration,
+    pub max_skew: Duration,
+}
+
+impl FreshnessPolicy {
+    /// Accept proofs up to `max_age` old, with the default clock skew
+    pub fn new(max_age: Duration) -> Self {
+        Self {
+            max_age,
+            max_skew: Duration::from_secs(MAX_CLOCK_SKEW_SECS),
+        }
+    }
+}
+
+impl VerificationPolicy for FreshnessPolicy {
+    fn check(&self, context: &PolicyContext<'_>) -> Option<VerificationFailure> {
+        let timestamp = context.public_inputs.timestamp;
+        if core::is_fresh(
+            timestamp,
+            context.now,
+            self.max_age.as_secs(),
+            self.max_skew.as_secs(),
+        ) {
+            None
+        } else {
+            Some(VerificationFailure::StaleTimestamp { timestamp })
+        }
+    }
+}
+
+impl VerificationPolicy for RevocationRegistry {
+    fn check(&self, context: &PolicyContext<'_>) -> Option<VerificationFailure> {
+        RevocationRegistry::check(self, context.public_inputs)
+    }
+}
+
+/// Restricts a verifier to one tenant's documents. Deployments serving
+/// several tenants build a verifier per tenant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TenantScope {
+    pub tenant: String,
+    pub document_commitments: HashSet<String>,
+}
+
+impl TenantScope {
+    /// Scope to a tenant owning these document commitments
+    pub fn new(tenant: impl Into<String>, document_commitments: HashSet<String>) -> Self {
+        Self {
+            tenant: tenant.into(),
+            document_commitments,
+        }
+    }
+}
+
+impl VerificationPolicy for TenantScope {
+    fn check(&self, context: &PolicyContext<'_>) -> Option<VerificationFailure> {
+        let commitment = &context.public_inputs.document_commitment;
+        if self.document_commitments.contains(commitment) {
+            None
+        } else {
+            Some(VerificationFailure::PolicyViolation {
+                message: format!("document {} is outside tenant {}", commitment, self.tenant),
+            })
+        }
+    }
+}
+
+/// Model allowlist, loadable from a policy file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifierPolicy {
+    /// Approved model hashes; `None` accepts any model
+    #[serde(default)]
+    pub allowed_models: Option<HashSet<String>>,
+}
+
+impl VerifierPolicy {
+    /// Create a policy that accepts everything
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only accept proofs for these model hashes
+    pub fn allowed_models(mut self, models: HashSet<String>) -> Self {
+        self.allowed_models = Some(models);
+        self
+    }
+
+    /// Load a policy from a `.toml` or `.json` file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read policy {}", path.display()))?;
+
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => {
+                toml::from_str(&text).with_context(|| format!("Invalid policy {}", path.display()))
+            }
+            Some("json") => serde_json::from_str(&text)
+                .with_context(|| format!("Invalid policy {}", path.display())),
+            _ => bail!(
+                Malformed,
+                "Policy file must be .toml or .json: {}",
+                path.display()
+            ),
+        }
+    }
+
+    /// Check public inputs against the policy
+    pub fn check(&self, public_inputs: &PublicInputs) -> Option<VerificationFailure> {
+        if let Some(allowed) = &self.allowed_models {
+            if !allowed.contains(&public_inputs.model_hash) {
+                return Some(VerificationFailure::PolicyViolation {
+                    message: format!("model {} is not approved", public_inputs.model_hash),
+                });
+            }
+        }
+        None
+    }
+}
+
+impl VerificationPolicy for VerifierPolicy {
+    fn check(&self, context: &PolicyContext<'_>) -> Option<VerificationFailure> {
+        VerifierPolicy::check(self, context.public_inputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs() -> PublicInputs {
+        PublicInputs {
+            document_commitment: "abc123".to_string(),
+            model_hash: "model456".to_string(),
+            timestamp: 1234567890,
+            nonce: None,
+        }
+    }
+
+    #[test]
+    fn test_allowed_models() {
+        assert!(VerifierPolicy::new().check(&inputs()).is_none());
+
+        let policy = VerifierPolicy::new().allowed_models(["model456".to_string()].into());
+        assert!(policy.check(&inputs()).is_none());
+
+        let policy = VerifierPolicy::new().allowed_models(["model789".to_string()].into());
+        assert!(matches!(
+            policy.check(&inputs()),
+            Some(VerificationFailure::PolicyViolation { .. })
+        ));
+    }
+
+    #[test]
+    fn test_freshness_and_tenant_scope() {
+        let inputs = inputs();
+        let context = PolicyContext {
+            public_inputs: &inputs,
+            circuit_id: crate::DOCUMENT_QUERY_CIRCUIT_ID,
+            key_id: "key",
+            now: inputs.timestamp + 30,
+        };
+
+        assert!(FreshnessPolicy::new(Duration::from_secs(60))
+            .check(&context)
+            .is_none());
+        assert!(matches!(
+            FreshnessPolicy::new(Duration::from_secs(10)).check(&context),
+            Some(VerificationFailure::StaleTimestamp { .. })
+        ));
+
+        assert!(TenantScope::new("acme", ["abc123".to_string()].into())
+            .check(&context)
+            .is_none());
+        assert!(matches!(
+            TenantScope::new("globex", ["def456".to_string()].into()).check(&context),
+            Some(VerificationFailure::PolicyViolation { .. })
+        ));
+    }
+
+    #[test]
+    fn test_policy_from_toml() {
+        let path = std::env::temp_dir().join("zkrag_test_policy.toml");
+        fs::write(&path, "allowed_models = [\"model456\"]\n").unwrap();
+
+        let policy = VerifierPolicy::from_file(&path).unwrap();
+        assert!(policy.check(&inputs()).is_none());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,243 @@
+// Verification quorum
+//
+// High-assurance deployments may not trust any single key distribution
+// channel or trusted setup. A quorum holds n independently provisioned
+// verifying keys (e.g. from separate ceremony transcripts) for the document
+// query circuit; a statement is accepted only when proofs verify under at
+// least m of them. Groth16 proofs are bound to the key they were produced
+// with, so the prover supplies one proof per key, tagged with its key id.
+
+use std::time::Instant;
+
+use crate::curve::{Curve, PreparedKey};
+use crate::error::{bail, Result, ResultExt, VerifierError};
+use crate::keys::key_digest;
+use crate::{
+    unix_now, KeySchedule, PublicInputs, QueryVerifier, RegisteredKey, VerificationFailure,
+    VerificationResult, DOCUMENT_QUERY_CIRCUIT_ID, MAX_PROOF_BYTES,
+};
+
+/// m-of-n set of independently provisioned verifying keys
+pub struct KeyQuorum {
+    keys: Vec<RegisteredKey>,
+    threshold: usize,
+}
+
+impl KeyQuorum {
+    /// Create an empty quorum requiring `threshold` keys to accept
+    pub fn new(threshold: usize) -> Self {
+        Self {
+            keys: Vec::new(),
+            threshold,
+        }
+    }
+
+    /// Add a compressed BN254 verifying key, returning its key id
+    pub fn add_key(&mut self, key_bytes: &[u8]) -> Result<String> {
+        self.add_curve_key(Curve::Bn254, key_bytes)
+    }
+
+    /// Add a compressed verifying key over `curve`, returning its key id.
+    ///
+    /// The same key obtained through several channels counts once.
+    pub fn add_curve_key(&mut self, curve: Curve, key_bytes: &[u8]) -> Result<String> {
+        let key = PreparedKey::from_bytes(curve, key_bytes)
+            .map_err(VerifierError::from)
+            .context("Invalid quorum verifying key")?;
+        let key_id = key_digest(key_bytes);
+        if self.get(&key_id).is_none() {
+            self.keys.push(RegisteredKey {
+                key_id: key_id.clone(),
+                circuit_id: DOCUMENT_QUERY_CIRCUIT_ID.to_string(),
+                key,
+                schedule: KeySchedule::default(),
+            });
+        }
+        Ok(key_id)
+    }
+
+    /// Number of acceptances required
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Number of distinct keys in the quorum
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Whether the quorum has no keys
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Check the threshold is satisfiable
+    pub(crate) fn validate(&self) -> Result<()> {
+        if self.threshold == 0 || self.threshold > self.keys.len() {
+            bail!(
+                Internal,
+                "Quorum threshold {} must be between 1 and the number of keys ({})",
+                self.threshold,
+                self.keys.len()
+            );
+        }
+        Ok(())
+    }
+
+    fn get(&self, key_id: &str) -> Option<&RegisteredKey> {
+        self.keys
+            .iter()
+            .find(|registered| registered.key_id == key_id)
+    }
+}
+
+impl QueryVerifier {
+    /// Verify one statement proven under several quorum keys.
+    ///
+    /// `proofs` pairs each proof with the key id it was produced under.
+    /// Proofs for unknown keys are ignored, and each key counts at most once.
+    pub fn verify_quorum(
+        &self,
+        proofs: &[(String, Vec<u8>)],
+        public_inputs: PublicInputs,
+    ) -> Result<VerificationResult> {
+        let quorum = self
+            .quorum
+            .as_ref()
+            .ok_or_else(|| VerifierError::internal("No key quorum configured"))?;
+        let started = Instant::now();
+        let now = unix_now()?;
+
+        let outcome = self.check_quorum(quorum, proofs, &public_inputs, now);
+        let proof_bytes: Vec<u8> = proofs
+            .iter()
+            .flat_map(|(_, proof)| proof.iter().copied())
+            .collect();
+        self.finish(&proof_bytes, public_inputs, None, outcome, now, started)
+    }
+
+    fn check_quorum(
+        &self,
+        quorum: &KeyQuorum,
+        proofs: &[(String, Vec<u8>)],
+        public_inputs: &PublicInputs,
+        now: u64,
+    ) -> std::result::Result<bool, VerificationFailure> {
+        let mut accepted: Vec<&RegisteredKey> = Vec::new();
+        for (key_id, proof_bytes) in proofs {
+            let Some(registered) = quorum.get(key_id) else {
+                continue;
+            };
+            if proof_bytes.len() > MAX_PROOF_BYTES
+                || accepted.iter().any(|seen| seen.key_id == *key_id)
+            {
+                continue;
+            }
+            if registered
+                .key
+                .verify(proof_bytes, self.proof_encoding, public_inputs)
+                .is_ok()
+            {
+                accepted.push(registered);
+            }
+        }
+
+        if accepted.len() < quorum.threshold {
+            return Err(VerificationFailure::QuorumNotMet {
+                accepted: accepted.len(),
+                required: quorum.threshold,
+            });
+        }
+        self.apply_policies(accepted[0], public_inputs, now)?;
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{circuit, public_inputs};
+    use ark_bn254::Bn254;
+    use ark_groth16::Groth16;
+    use ark_serialize::CanonicalSerialize;
+    use ark_std::rand::SeedableRng;
+    use std::sync::Arc;
+
+    /// An independent setup: its verifying key and a proof under it
+    fn ceremony(seed: u64) -> (Vec<u8>, Vec<u8>) {
+        let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(seed);
+        let pk = Groth16::<Bn254>::generate_random_parameters_with_reduction(
+            circuit(&public_inputs()),
+            &mut rng,
+        )
+        .unwrap();
+        let proof = Groth16::<Bn254>::create_random_proof_with_reduction(
+            circuit(&public_inputs()),
+            &pk,
+            &mut rng,
+        )
+        .unwrap();
+
+        let mut vk_bytes = Vec::new();
+        pk.vk.serialize_compressed(&mut vk_bytes).unwrap();
+        let mut proof_bytes = Vec::new();
+        proof.serialize_compressed(&mut proof_bytes).unwrap();
+        (vk_bytes, proof_bytes)
+    }
+
+    #[test]
+    fn test_quorum() {
+        let ceremonies: Vec<_> = (0..3).map(ceremony).collect();
+        let mut quorum = KeyQuorum::new(2);
+        let key_ids: Vec<_> = ceremonies
+            .iter()
+            .map(|(vk, _)| quorum.add_key(vk).unwrap())
+            .collect();
+        assert_eq!(quorum.add_key(&ceremonies[0].0).unwrap(), key_ids[0]);
+        assert_eq!(quorum.len(), 3);
+
+        let verifier = QueryVerifier::builder()
+            .quorum(Arc::new(quorum))
+            .build()
+            .unwrap();
+        let proofs: Vec<_> = key_ids
+            .iter()
+            .cloned()
+            .zip(ceremonies.iter().map(|(_, proof)| proof.clone()))
+            .collect();
+
+        let result = verifier.verify_quorum(&proofs, public_inputs()).unwrap();
+        assert!(result.is_valid);
+
+        // One valid proof submitted twice doesn't count twice
+        let repeated = vec![proofs[0].clone(), proofs[0].clone()];
+        let result = verifier.verify_quorum(&repeated, public_inputs()).unwrap();
+        assert_eq!(
+            result.reason,
+            Some(VerificationFailure::QuorumNotMet {
+                accepted: 1,
+                required: 2
+            })
+        );
+
+        // A proof presented under the wrong key is rejected
+        let swapped = vec![proofs[0].clone(), (key_ids[1].clone(), proofs[2].1.clone())];
+        assert!(
+            !verifier
+                .verify_quorum(&swapped, public_inputs())
+                .unwrap()
+                .is_valid
+        );
+    }
+
+    #[test]
+    fn test_unsatisfiable_threshold() {
+        let (vk, _) = ceremony(0);
+        let mut quorum = KeyQuorum::new(2);
+        quorum.add_key(&vk).unwrap();
+        assert!(QueryVerifier::builder()
+            .quorum(Arc::new(quorum))
+            .build()
+            .is_err());
+    }
+}
@@ -0,0 +1,147 @@
+// Signed verification receipts
+//
+// A receipt is an ed25519 attestation by this verifier that a specific proof
+// was checked against specific public inputs with a given outcome. Downstream
+// consumers holding the verifier's public key can trust the result without
+// re-running the Groth16 check.
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+use crate::canonical::to_canonical_json;
+use crate::error::{bail, Result, ResultExt, VerifierError};
+use crate::{keys::key_digest, proof_digest, VerificationFailure, VerificationResult};
+
+/// Attestation of a verification outcome
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationReceipt {
+    pub proof_digest: String,
+    pub inputs_digest: String,
+    pub is_valid: bool,
+    pub reason: Option<VerificationFailure>,
+    pub verified_at: u64,
+    /// Key id of the verifying key the proof was checked against
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verifying_key_fingerprint: Option<String>,
+    /// Key id of the signing verifier (SHA-256 of its public key)
+    pub verifier_key_id: String,
+    /// Hex-encoded ed25519 signature over every other field
+    pub signature: String,
+}
+
+impl VerificationReceipt {
+    /// Bytes covered by the signature
+    fn signing_payload(&self) -> Vec<u8> {
+        let unsigned = Self {
+            signature: String::new(),
+            ..self.clone()
+        };
+        to_canonical_json(&unsigned)
+            .expect("receipt serializes")
+            .into_bytes()
+    }
+
+    /// Check the signature against the verifier's public key
+    pub fn verify_signature(&self, public_key: &VerifyingKey) -> Result<()> {
+        if self.verifier_key_id != key_digest(public_key.as_bytes()) {
+            bail!(Rejected, "Receipt was signed by a different verifier key");
+        }
+        let bytes = hex::decode(&self.signature).context("Malformed receipt signature")?;
+        let signature = Signature::from_slice(&bytes)
+            .map_err(|_| VerifierError::malformed("Malformed receipt signature"))?;
+        public_key
+            .verify(&self.signing_payload(), &signature)
+            .map_err(|_| VerifierError::rejected("Receipt signature is invalid"))
+    }
+}
+
+/// Signs receipts on behalf of a verifier
+pub struct ReceiptSigner {
+    signing_key: SigningKey,
+    key_id: String,
+}
+
+impl ReceiptSigner {
+    /// Create a signer from an ed25519 signing key
+    pub fn new(signing_key: SigningKey) -> Self {
+        let key_id = key_digest(signing_key.verifying_key().as_bytes());
+        Self {
+            signing_key,
+            key_id,
+        }
+    }
+
+    /// Create a signer from a 32-byte ed25519 secret key
+    pub fn from_secret_bytes(secret: &[u8; 32]) -> Self {
+        Self::new(SigningKey::from_bytes(secret))
+    }
+
+    /// Generate a fresh signing key
+    pub fn generate() -> Self {
+        Self::new(SigningKey::generate(&mut rand::rngs::OsRng))
+    }
+
+    /// Key id embedded in receipts
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// Public key consumers use to check receipts
+    pub fn public_key(&self) -> VerifyingKey {
+        self.signing_key.verifying_key()
+    }
+
+    /// Sign a receipt for a verification result
+    pub fn sign(&self, proof_bytes: &[u8], result: &VerificationResult) -> VerificationReceipt {
+        let mut receipt = VerificationReceipt {
+            proof_digest: proof_digest(proof_bytes),
+            inputs_digest: result.public_inputs.digest(),
+            is_valid: result.is_valid,
+            reason: result.reason.clone(),
+            verified_at: result.verified_at,
+            verifying_key_fingerprint: result.verifying_key_fingerprint.clone(),
+            verifier_key_id: self.key_id.clone(),
+            signature: String::new(),
+        };
+        let signature = self.signing_key.sign(&receipt.signing_payload());
+        receipt.signature = hex::encode(signature.to_bytes());
+        receipt
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PublicInputs;
+
+    #[test]
+    fn test_receipt_signature() {
+        let signer = ReceiptSigner::from_secret_bytes(&[7u8; 32]);
+        let result = VerificationResult {
+            is_valid: true,
+            reason: None,
+            public_inputs: PublicInputs {
+                document_commitment: "abc123".to_string(),
+                model_hash: "model456".to_string(),
+                timestamp: 1234567890,
+                nonce: None,
+            },
+            verified_at: 1234567900,
+            cache_hit: false,
+            receipt: None,
+            attested_at: None,
+            proof_digest: proof_digest(b"proof"),
+            verifying_key_fingerprint: Some("key".to_string()),
+        };
+
+        let receipt = signer.sign(b"proof", &result);
+        assert!(receipt.verify_signature(&signer.public_key()).is_ok());
+
+        let mut forged = receipt.clone();
+        forged.is_valid = false;
+        assert!(forged.verify_signature(&signer.public_key()).is_err());
+
+        let other = ReceiptSigner::from_secret_bytes(&[8u8; 32]);
+        assert!(receipt.verify_signature(&other.public_key()).is_err());
+    }
+}
@@ -0,0 +1,372 @@
+// Verifying key registry
+//
+// Holds prepared verifying keys by key id (the SHA-256 digest of the
+// serialized key) and tracks which key is current for each circuit.
+//
+// Keys are rotated on a schedule rather than swapped: a new key version is
+// registered ahead of its activation time, and the key it replaces is
+// deprecated at that time but keeps verifying proofs for a grace window,
+// until it retires. The current key of a circuit is its most recently
+// activated key that isn't deprecated, or failing that one still in its
+// grace window; keys inserted without a schedule are active at once.
+//
+// `VerifyingKeyInfo` summarizes a loaded key so operators can compare
+// fingerprints with the prover fleet before going live.
+
+use ark_bn254::Bn254;
+use ark_groth16::VerifyingKey;
+use ark_serialize::CanonicalSerialize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use zkrag_verifier_core::NUM_PUBLIC_INPUTS;
+
+use crate::curve::{Curve, PreparedKey};
+use crate::error::{bail, Result, VerifierError};
+use crate::keys::key_digest;
+use crate::DOCUMENT_QUERY_CIRCUIT_ID;
+
+/// A prepared verifying key and the circuit it belongs to
+#[derive(Clone)]
+pub struct RegisteredKey {
+    pub key_id: String,
+    pub circuit_id: String,
+    pub key: PreparedKey,
+    pub schedule: KeySchedule,
+}
+
+impl RegisteredKey {
+    /// Summary of the key for comparison with the prover's ceremony output
+    pub fn info(&self) -> VerifyingKeyInfo {
+        VerifyingKeyInfo {
+            fingerprint: self.key_id.clone(),
+            curve: self.key.curve(),
+            circuit_id: self.circuit_id.clone(),
+            num_public_inputs: self.key.num_public_inputs(),
+        }
+    }
+
+    /// Confirm the prepared key is the one registered: it serializes back to
+    /// the bytes `key_id` digests, its pairing precomputation matches, and a
+    /// document query key takes that circuit's public inputs
+    pub fn self_check(&self) -> Result<()> {
+        let fingerprint = key_digest(&self.key.to_bytes());
+        if fingerprint != self.key_id {
+            bail!(
+                Internal,
+                "Key {} serializes to fingerprint {}",
+                self.key_id,
+                fingerprint
+            );
+        }
+        if !self.key.is_consistent() {
+            bail!(
+                Internal,
+                "Key {} doesn't match its precomputed pairing",
+                self.key_id
+            );
+        }
+        let inputs = self.key.num_public_inputs();
+        if self.circuit_id == DOCUMENT_QUERY_CIRCUIT_ID && inputs != NUM_PUBLIC_INPUTS {
+            bail!(
+                Internal,
+                "Key {} takes {} public inputs, not the document query's {}",
+                self.key_id,
+                inputs,
+                NUM_PUBLIC_INPUTS
+            );
+        }
+        Ok(())
+    }
+}
+
+/// When a key checks proofs, in Unix seconds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeySchedule {
+    /// From when the key verifies proofs and may be current
+    #[serde(default)]
+    pub activates_at: u64,
+    /// From when the key is no longer current
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecated_at: Option<u64>,
+    /// From when proofs against the key are refused
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retires_at: Option<u64>,
+}
+
+/// Where a key is in its schedule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyState {
+    /// Registered ahead of its activation time
+    Pending,
+    Active,
+    /// Superseded, but verifying proofs until it retires
+    Deprecated,
+    Retired,
+}
+
+impl KeySchedule {
+    /// State of the key at `now`
+    pub fn state(&self, now: u64) -> KeyState {
+        if now < self.activates_at {
+            KeyState::Pending
+        } else if self.retires_at.is_some_and(|at| now >= at) {
+            KeyState::Retired
+        } else if self.deprecated_at.is_some_and(|at| now >= at) {
+            KeyState::Deprecated
+        } else {
+            KeyState::Active
+        }
+    }
+}
+
+/// Identifying details of a loaded verifying key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifyingKeyInfo {
+    /// Hex SHA-256 digest of the compressed key, same as the key id
+    pub fingerprint: String,
+    pub curve: Curve,
+    pub circuit_id: String,
+    pub num_public_inputs: usize,
+}
+
+/// Verifying keys by key id, with a current key per circuit
+#[derive(Clone, Default)]
+pub struct KeyRegistry {
+    keys: HashMap<String, RegisteredKey>,
+    /// Key ids of each circuit, in the order they were registered
+    versions: HashMap<String, Vec<String>>,
+}
+
+impl KeyRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a compressed BN254 verifying key and make it current for its
+    /// circuit
+    pub fn insert(&mut self, circuit_id: &str, key_bytes: &[u8]) -> Result<String> {
+        self.insert_for_curve(circuit_id, Curve::Bn254, key_bytes)
+    }
+
+    /// Register a compressed verifying key over `curve`, active at once. It's
+    /// current for its circuit unless a key activated on a schedule follows.
+    pub fn insert_for_curve(
+        &mut self,
+        circuit_id: &str,
+        curve: Curve,
+        key_bytes: &[u8],
+    ) -> Result<String> {
+        self.insert_scheduled(circuit_id, curve, key_bytes, KeySchedule::default())
+    }
+
+    /// Register a compressed verifying key over `curve` to check proofs on
+    /// `schedule`. A key already registered takes the new schedule and
+    /// counts as the circuit's latest version.
+    pub fn insert_scheduled(
+        &mut self,
+        circuit_id: &str,
+        curve: Curve,
+        key_bytes: &[u8],
+        schedule: KeySchedule,
+    ) -> Result<String> {
+        let key = PreparedKey::from_bytes(curve, key_bytes).map_err(VerifierError::from)?;
+        let key_id = key_digest(key_bytes);
+
+        if let Some(previous) = self.keys.get(&key_id) {
+            if let Some(versions) = self.versions.get_mut(&previous.circuit_id) {
+                versions.retain(|id| *id != key_id);
+            }
+        }
+        self.keys.insert(
+            key_id.clone(),
+            RegisteredKey {
+                key_id: key_id.clone(),
+                circuit_id: circuit_id.to_string(),
+                key,
+                schedule,
+            },
+        );
+        self.versions
+            .entry(circuit_id.to_string())
+            .or_default()
+            .push(key_id.clone());
+
+        Ok(key_id)
+    }
+
+    /// Register a new key version for a circuit, active from `activates_at`,
+    /// and deprecate the key current at that time then, retiring it
+    /// `grace_secs` later. Returns the new key's id and the deprecated key's.
+    pub fn rotate(
+        &mut self,
+        circuit_id: &str,
+        curve: Curve,
+        key_bytes: &[u8],
+        activates_at: u64,
+        grace_secs: u64,
+    ) -> Result<(String, Option<String>)> {
+        let previous = self
+            .current_at(circuit_id, activates_at)
+            .map(|key| key.key_id.clone())
+            .filter(|key_id| *key_id != key_digest(key_bytes));
+        let schedule = KeySchedule {
+            activates_at,
+            ..KeySchedule::default()
+        };
+        let key_id = self.insert_scheduled(circuit_id, curve, key_bytes, schedule)?;
+        if let Some(previous) = &previous {
+            self.deprecate(previous, activates_at, grace_secs);
+        }
+        Ok((key_id, previous))
+    }
+
+    /// Deprecate a key from `at` and retire it `grace_secs` later, returning
+    /// its new schedule, or `None` if it isn't registered
+    pub fn deprecate(&mut self, key_id: &str, at: u64, grace_secs: u64) -> Option<KeySchedule> {
+        let key = self.keys.get_mut(key_id)?;
+        key.schedule.deprecated_at = Some(at);
+        key.schedule.retires_at = Some(at.saturating_add(grace_secs));
+        Some(key.schedule)
+    }
+
+    /// Register an already-parsed verifying key (e.g. imported from snarkjs)
+    /// and make it current for its circuit
+    pub fn insert_vk(&mut self, circuit_id: &str, vk: VerifyingKey<Bn254>) -> Result<String> {
+        let mut key_bytes = Vec::new();
+        vk.serialize_compressed(&mut key_bytes)
+            .map_err(VerifierError::malformed)?;
+        self.insert(circuit_id, &key_bytes)
+    }
+
+    /// Look up a key by id
+    pub fn get(&self, key_id: &str) -> Option<&RegisteredKey> {
+        self.keys.get(key_id)
+    }
+
+    /// Current key for a circuit
+    pub fn current(&self, circuit_id: &str) -> Option<&RegisteredKey> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        self.current_at(circuit_id, now)
+    }
+
+    /// Key current for a circuit at `now`
+    pub fn current_at(&self, circuit_id: &str, now: u64) -> Option<&RegisteredKey> {
+        let latest = |state: KeyState| {
+            self.versions(circuit_id)
+                .filter(|key| key.schedule.state(now) == state)
+                .max_by_key(|key| key.schedule.activates_at)
+        };
+        latest(KeyState::Active).or_else(|| latest(KeyState::Deprecated))
+    }
+
+    /// Deprecated keys of a circuit still in their grace window at `now`,
+    /// most recently activated first
+    pub fn in_grace(&self, circuit_id: &str, now: u64) -> Vec<&RegisteredKey> {
+        let mut keys: Vec<_> = self
+            .versions(circuit_id)
+            .filter(|key| key.schedule.state(now) == KeyState::Deprecated)
+            .collect();
+        keys.reverse();
+        keys.sort_by_key(|key| std::cmp::Reverse(key.schedule.activates_at));
+        keys
+    }
+
+    /// Keys of a circuit, in the order they were registered
+    pub fn versions<'a>(&'a self, circuit_id: &str) -> impl Iterator<Item = &'a RegisteredKey> {
+        self.versions
+            .get(circuit_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|key_id| self.keys.get(key_id))
+    }
+
+    /// Info for every registered key, ordered by circuit then fingerprint
+    pub fn key_info(&self) -> Vec<VerifyingKeyInfo> {
+        let mut info: Vec<_> = self.keys.values().map(RegisteredKey::info).collect();
+        info.sort_by(|a, b| (&a.circuit_id, &a.fingerprint).cmp(&(&b.circuit_id, &b.fingerprint)));
+        info
+    }
+
+    /// Number of registered keys
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Whether no keys are registered
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{fixture, other_key};
+
+    #[test]
+    fn test_key_info() {
+        let (_, vk_bytes, _) = fixture();
+        let mut registry = KeyRegistry::new();
+        let key_id = registry
+            .insert(DOCUMENT_QUERY_CIRCUIT_ID, &vk_bytes)
+            .unwrap();
+
+        assert_eq!(
+            registry.key_info(),
+            vec![VerifyingKeyInfo {
+                fingerprint: key_id.clone(),
+                curve: Curve::Bn254,
+                circuit_id: DOCUMENT_QUERY_CIRCUIT_ID.to_string(),
+                num_public_inputs: 3,
+            }]
+        );
+
+        // A key checks out until it no longer matches its fingerprint
+        let mut key = registry.get(&key_id).unwrap().clone();
+        key.self_check().unwrap();
+        key.key_id = "00".repeat(32);
+        assert!(key.self_check().is_err());
+    }
+
+    #[test]
+    fn test_rotation_schedule() {
+        let (_, old_bytes, _) = fixture();
+        let new_bytes = other_key();
+
+        let mut registry = KeyRegistry::new();
+        let circuit = DOCUMENT_QUERY_CIRCUIT_ID;
+        let old_id = registry.insert(circuit, &old_bytes).unwrap();
+        let (new_id, deprecated) = registry
+            .rotate(circuit, Curve::Bn254, &new_bytes, 1_000, 500)
+            .unwrap();
+        assert_eq!(deprecated.as_deref(), Some(old_id.as_str()));
+
+        // The old key stays current until the new one activates, then
+        // verifies through the grace window
+        let current = |now| registry.current_at(circuit, now).unwrap().key_id.clone();
+        assert_eq!(current(999), old_id);
+        assert_eq!(
+            registry.get(&new_id).unwrap().schedule.state(999),
+            KeyState::Pending
+        );
+        assert_eq!(current(1_000), new_id);
+        let in_grace: Vec<_> = registry
+            .in_grace(circuit, 1_499)
+            .into_iter()
+            .map(|k| &k.key_id)
+            .collect();
+        assert_eq!(in_grace, vec![&old_id]);
+        assert!(registry.in_grace(circuit, 1_500).is_empty());
+        assert_eq!(
+            registry.get(&old_id).unwrap().schedule.state(1_500),
+            KeyState::Retired
+        );
+        assert_eq!(current(1_500), new_id);
+    }
+}
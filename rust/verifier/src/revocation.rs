@@ -0,0 +1,224 @@
+// Revocation lists
+//
+// Document commitments and model hashes that must no longer verify, e.g. a
+// withdrawn corpus or a compromised model. Lists can be reloaded in place so
+// long-running services pick up changes without a restart. Entries are hex
+// digests, matched regardless of case.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime};
+
+use crate::error::{Result, ResultExt};
+use crate::{PublicInputs, VerificationFailure};
+
+/// Set of revoked document commitments and model hashes
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevocationList {
+    #[serde(default)]
+    pub document_commitments: HashSet<String>,
+    #[serde(default)]
+    pub model_hashes: HashSet<String>,
+}
+
+impl RevocationList {
+    /// Create an empty revocation list
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a revocation list from a JSON file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = fs::read(path)
+            .with_context(|| format!("Failed to read revocation list {}", path.display()))?;
+        serde_json::from_slice(&bytes)
+            .with_context(|| format!("Invalid revocation list {}", path.display()))
+    }
+
+    /// Revoke a document commitment
+    pub fn revoke_document(&mut self, commitment: impl Into<String>) {
+        self.document_commitments.insert(commitment.into());
+    }
+
+    /// Revoke a model hash
+    pub fn revoke_model(&mut self, model_hash: impl Into<String>) {
+        self.model_hashes.insert(model_hash.into());
+    }
+
+    /// Check public inputs against the list
+    pub fn check(&self, public_inputs: &PublicInputs) -> Option<VerificationFailure> {
+        if contains_digest(
+            &self.document_commitments,
+            &public_inputs.document_commitment,
+        ) {
+            return Some(VerificationFailure::Revoked {
+                field: "document_commitment".to_string(),
+            });
+        }
+        if contains_digest(&self.model_hashes, &public_inputs.model_hash) {
+            return Some(VerificationFailure::Revoked {
+                field: "model_hash".to_string(),
+            });
+        }
+        None
+    }
+}
+
+/// Whether `digests` holds `digest`, in any case
+fn contains_digest(digests: &HashSet<String>, digest: &str) -> bool {
+    digests.contains(digest)
+        || digests
+            .iter()
+            .any(|revoked| revoked.eq_ignore_ascii_case(digest))
+}
+
+/// Shared, reloadable revocation list
+#[derive(Debug)]
+pub struct RevocationRegistry {
+    list: RwLock<RevocationList>,
+    path: Option<PathBuf>,
+    modified: RwLock<Option<SystemTime>>,
+}
+
+impl RevocationRegistry {
+    /// Create a registry from an in-memory list
+    pub fn new(list: RevocationList) -> Self {
+        Self {
+            list: RwLock::new(list),
+            path: None,
+            modified: RwLock::new(None),
+        }
+    }
+
+    /// Create a registry backed by a JSON file
+    pub fn from_file(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let list = RevocationList::from_file(&path)?;
+        let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+
+        Ok(Self {
+            list: RwLock::new(list),
+            path: Some(path),
+            modified: RwLock::new(modified),
+        })
+    }
+
+    /// Replace the current list
+    pub fn replace(&self, list: RevocationList) {
+        *self.list.write().unwrap() = list;
+    }
+
+    /// Revoke a document commitment in the current list, until the next reload
+    pub fn revoke_document(&self, commitment: impl Into<String>) {
+        self.list.write().unwrap().revoke_document(commitment);
+    }
+
+    /// Revoke a model hash in the current list, until the next reload
+    pub fn revoke_model(&self, model_hash: impl Into<String>) {
+        self.list.write().unwrap().revoke_model(model_hash);
+    }
+
+    /// Snapshot of the current list
+    pub fn snapshot(&self) -> RevocationList {
+        self.list.read().unwrap().clone()
+    }
+
+    /// Re-read the backing file unconditionally
+    pub fn reload(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let list = RevocationList::from_file(path)?;
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+
+        self.replace(list);
+        *self.modified.write().unwrap() = modified;
+        Ok(())
+    }
+
+    /// Re-read the backing file if it changed since the last load
+    pub fn reload_if_changed(&self) -> Result<bool> {
+        let Some(path) = &self.path else {
+            return Ok(false);
+        };
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+        if modified == *self.modified.read().unwrap() {
+            return Ok(false);
+        }
+
+        self.reload()?;
+        Ok(true)
+    }
+
+    /// Poll the backing file in a background thread.
+    ///
+    /// A file that fails to parse leaves the previous list in place.
+    pub fn watch(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        std::thread::spawn(move || loop {
+            std::thread::sleep(interval);
+            let _ = self.reload_if_changed();
+        })
+    }
+
+    /// Check public inputs against the current list
+    pub fn check(&self, public_inputs: &PublicInputs) -> Option<VerificationFailure> {
+        self.list.read().unwrap().check(public_inputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs() -> PublicInputs {
+        PublicInputs {
+            document_commitment: "abc123".to_string(),
+            model_hash: "model456".to_string(),
+            timestamp: 1234567890,
+            nonce: None,
+        }
+    }
+
+    #[test]
+    fn test_revocation_check() {
+        let mut list = RevocationList::new();
+        assert!(list.check(&inputs()).is_none());
+
+        list.revoke_model("model456");
+        assert_eq!(
+            list.check(&inputs()),
+            Some(VerificationFailure::Revoked {
+                field: "model_hash".to_string()
+            })
+        );
+
+        let mut list = RevocationList::new();
+        list.revoke_document("ABC123");
+        assert_eq!(
+            list.check(&inputs()),
+            Some(VerificationFailure::Revoked {
+                field: "document_commitment".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_registry_reload() {
+        let path = std::env::temp_dir().join("zkrag_test_revocations.json");
+        fs::write(&path, r#"{"document_commitments": []}"#).unwrap();
+
+        let registry = RevocationRegistry::from_file(&path).unwrap();
+        assert!(registry.check(&inputs()).is_none());
+
+        fs::write(&path, r#"{"document_commitments": ["abc123"]}"#).unwrap();
+        registry.reload().unwrap();
+        assert!(registry.check(&inputs()).is_some());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,220 @@
+// Solidity verifier generation
+//
+// Renders a BN254 Groth16 verifying key as a standalone Solidity contract that
+// checks proofs with the EIP-196/197 precompiles (ecAdd, ecMul, ecPairing).
+// The key is baked into the contract as constants, so the contract must be
+// regenerated and redeployed whenever the key rotates.
+//
+// `verifyProof` takes the proof points as uint256 words and the public inputs
+// as field elements; for document query proofs these are
+// `PublicInputs::to_field_elements`. G2 coordinates are in precompile order,
+// imaginary coefficient first, the order snarkjs `generatecall` emits.
+
+use ark_bn254::{Bn254, Fq, Fr, G1Affine, G2Affine};
+use ark_ec::AffineRepr;
+use ark_ff::PrimeField;
+use ark_groth16::VerifyingKey;
+use ark_serialize::CanonicalDeserialize;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use crate::error::{Result, ResultExt, VerifierError};
+use crate::keys::read_key_file;
+
+/// Decimal form of a base field element. Fq's Display prints zero as an
+/// empty string, so go through the integer.
+fn decimal(value: &Fq) -> String {
+    value.into_bigint().to_string()
+}
+
+/// Decimal `(x, y)` of a G1 point; the identity encodes as (0, 0)
+fn g1_words(point: &G1Affine) -> [String; 2] {
+    match point.xy() {
+        Some((x, y)) => [decimal(x), decimal(y)],
+        None => ["0", "0"].map(String::from),
+    }
+}
+
+/// Decimal `(x.c1, x.c0, y.c1, y.c0)` of a G2 point, in precompile order
+fn g2_words(point: &G2Affine) -> [String; 4] {
+    match point.xy() {
+        Some((x, y)) => [
+            decimal(&x.c1),
+            decimal(&x.c0),
+            decimal(&y.c1),
+            decimal(&y.c0),
+        ],
+        None => ["0", "0", "0", "0"].map(String::from),
+    }
+}
+
+/// Contract source; `{{NAME}}` placeholders are filled in from the key
+const TEMPLATE: &str = r#"// SPDX-License-Identifier: MIT
+// Groth16 verifier for a BN254 verifying key, generated by zkrag-verifier.
+// Regenerate instead of editing when the key rotates.
+pragma solidity ^0.8.0;
+
+contract Groth16Verifier {
+    // Scalar field order; public inputs must be below it
+    uint256 constant SNARK_SCALAR_FIELD = {{SCALAR_FIELD}};
+    // Base field order, for negating G1 points
+    uint256 constant PRIME_Q = {{BASE_FIELD}};
+
+{{CONSTANTS}}
+    /// @notice Check a Groth16 proof. `b` is in precompile order,
+    /// imaginary coefficients first.
+    function verifyProof(
+        uint256[2] calldata a,
+        uint256[2][2] calldata b,
+        uint256[2] calldata c,
+        uint256[{{NUM_INPUTS}}] calldata input
+    ) external view returns (bool) {
+        uint256[2] memory vkX = [IC0_X, IC0_Y];
+{{ACCUMULATE}}
+        // e(-A, B) * e(alpha, beta) * e(vkX, gamma) * e(C, delta) == 1
+        uint256[24] memory p;
+        p[0] = a[0];
+        p[1] = (PRIME_Q - (a[1] % PRIME_Q)) % PRIME_Q;
+        p[2] = b[0][0];
+        p[3] = b[0][1];
+        p[4] = b[1][0];
+        p[5] = b[1][1];
+        p[6] = ALPHA_X;
+        p[7] = ALPHA_Y;
+        p[8] = BETA_X1;
+        p[9] = BETA_X0;
+        p[10] = BETA_Y1;
+        p[11] = BETA_Y0;
+        p[12] = vkX[0];
+        p[13] = vkX[1];
+        p[14] = GAMMA_X1;
+        p[15] = GAMMA_X0;
+        p[16] = GAMMA_Y1;
+        p[17] = GAMMA_Y0;
+        p[18] = c[0];
+        p[19] = c[1];
+        p[20] = DELTA_X1;
+        p[21] = DELTA_X0;
+        p[22] = DELTA_Y1;
+        p[23] = DELTA_Y0;
+        uint256[1] memory result;
+        bool ok;
+        assembly {
+            ok := staticcall(gas(), 8, p, 768, result, 32)
+        }
+        return ok && result[0] == 1;
+    }
+
+    /// acc + s * (x, y)
+    function addMul(uint256[2] memory acc, uint256 x, uint256 y, uint256 s)
+        internal
+        view
+        returns (uint256[2] memory sum)
+    {
+        require(s < SNARK_SCALAR_FIELD, "input out of range");
+        uint256[3] memory mulInput = [x, y, s];
+        uint256[4] memory addInput;
+        bool ok;
+        assembly {
+            ok := staticcall(gas(), 7, mulInput, 96, add(addInput, 64), 64)
+        }
+        require(ok, "ecMul failed");
+        addInput[0] = acc[0];
+        addInput[1] = acc[1];
+        assembly {
+            ok := staticcall(gas(), 6, addInput, 128, sum, 64)
+        }
+        require(ok, "ecAdd failed");
+    }
+}
+"#;
+
+/// Solidity source of a contract verifying proofs under `vk`
+pub fn solidity_verifier(vk: &VerifyingKey<Bn254>) -> String {
+    let num_inputs = vk.gamma_abc_g1.len().saturating_sub(1);
+
+    // Writing to a String can't fail
+    let mut constants = String::new();
+    let [x, y] = g1_words(&vk.alpha_g1);
+    let _ = writeln!(constants, "    uint256 constant ALPHA_X = {};", x);
+    let _ = writeln!(constants, "    uint256 constant ALPHA_Y = {};", y);
+    for (name, point) in [
+        ("BETA", &vk.beta_g2),
+        ("GAMMA", &vk.gamma_g2),
+        ("DELTA", &vk.delta_g2),
+    ] {
+        let words = g2_words(point);
+        for (part, word) in ["X1", "X0", "Y1", "Y0"].iter().zip(words) {
+            let _ = writeln!(
+                constants,
+                "    uint256 constant {}_{} = {};",
+                name, part, word
+            );
+        }
+    }
+    for (i, point) in vk.gamma_abc_g1.iter().enumerate() {
+        let [x, y] = g1_words(point);
+        let _ = writeln!(constants, "    uint256 constant IC{}_X = {};", i, x);
+        let _ = writeln!(constants, "    uint256 constant IC{}_Y = {};", i, y);
+    }
+
+    let mut accumulate = String::new();
+    for i in 0..num_inputs {
+        let _ = writeln!(
+            accumulate,
+            "        vkX = addMul(vkX, IC{}_X, IC{}_Y, input[{}]);",
+            i + 1,
+            i + 1,
+            i
+        );
+    }
+
+    TEMPLATE
+        .replace("{{SCALAR_FIELD}}", &Fr::MODULUS.to_string())
+        .replace("{{BASE_FIELD}}", &Fq::MODULUS.to_string())
+        .replace("{{CONSTANTS}}", &constants)
+        .replace("{{NUM_INPUTS}}", &num_inputs.to_string())
+        .replace("{{ACCUMULATE}}", &accumulate)
+}
+
+/// Write the Solidity verifier for a compressed verifying key file
+pub fn export_solidity_verifier(vk_path: &Path, out_path: &Path) -> Result<()> {
+    let key_bytes = read_key_file(vk_path)?;
+    let vk = VerifyingKey::<Bn254>::deserialize_compressed(&key_bytes[..]).map_err(|e| {
+        VerifierError::malformed(format!(
+            "Invalid verifying key {}: {}",
+            vk_path.display(),
+            e
+        ))
+    })?;
+
+    fs::write(out_path, solidity_verifier(&vk))
+        .with_context(|| format!("Failed to write verifier to {}", out_path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solidity_verifier() {
+        let (pk, _, _) = crate::tests::fixture();
+        let source = solidity_verifier(&pk.vk);
+
+        assert!(!source.contains("{{"));
+        assert!(source.contains(
+            "SNARK_SCALAR_FIELD = \
+             21888242871839275222246405745257275088548364400416034343698204186575808495617;"
+        ));
+        assert!(source.contains("uint256[3] calldata input"));
+        assert!(source.contains("vkX = addMul(vkX, IC3_X, IC3_Y, input[2]);"));
+        assert!(!source.contains("IC4_X"));
+
+        // G2 constants go imaginary coefficient first
+        let (x, _) = pk.vk.beta_g2.xy().unwrap();
+        assert!(source.contains(&format!("BETA_X1 = {};", decimal(&x.c1))));
+        assert!(source.contains(&format!("BETA_X0 = {};", decimal(&x.c0))));
+    }
+}
@@ -0,0 +1,166 @@
+// Streaming verification
+//
+// Audit back-runs verify archives of envelopes that may not fit in memory
+// (e.g. a nightly export). A stream is a sequence of frames, each a
+// little-endian u32 length followed by one envelope in either encoding.
+// Frames are read one at a time into a buffer capped at MAX_ENVELOPE_SIZE,
+// so memory stays bounded regardless of archive size.
+
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read, Write};
+
+use crate::envelope::MAX_ENVELOPE_SIZE;
+use crate::error::{bail, Result, VerifierError};
+use crate::{ProofEnvelope, QueryVerifier, VerificationResult};
+
+/// Totals over a verified stream
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamSummary {
+    /// Frames read
+    pub total: usize,
+    pub valid: usize,
+    pub invalid: usize,
+    /// Frames that did not parse as an envelope
+    pub malformed: usize,
+}
+
+/// Append one envelope to a stream in the binary encoding
+pub fn write_frame(writer: &mut impl Write, envelope: &ProofEnvelope) -> Result<()> {
+    let bytes = envelope.to_bytes()?;
+    writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    writer.write_all(&bytes)?;
+    Ok(())
+}
+
+/// Read the next frame into `buf`, returning false at a clean end of stream
+fn read_frame(reader: &mut impl Read, buf: &mut Vec<u8>) -> Result<bool> {
+    // Only a stream ending before the first prefix byte ends cleanly
+    let mut len = [0u8; 4];
+    loop {
+        match reader.read(&mut len[..1]) {
+            Ok(0) => return Ok(false),
+            Ok(_) => break,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    reader
+        .read_exact(&mut len[1..])
+        .map_err(|e| truncated(e, "Stream ended inside a frame length"))?;
+
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_ENVELOPE_SIZE {
+        bail!(
+            Malformed,
+            "Stream frame is {} bytes, limit is {}",
+            len,
+            MAX_ENVELOPE_SIZE
+        );
+    }
+    buf.resize(len, 0);
+    reader
+        .read_exact(buf)
+        .map_err(|e| truncated(e, "Stream ended inside a frame"))?;
+    Ok(true)
+}
+
+/// A stream cut short is malformed input; other read failures are I/O errors
+fn truncated(error: io::Error, message: &str) -> VerifierError {
+    if error.kind() == io::ErrorKind::UnexpectedEof {
+        VerifierError::malformed(message)
+    } else {
+        VerifierError::io(message, error)
+    }
+}
+
+impl QueryVerifier {
+    /// Verify every envelope in a length-prefixed stream.
+    ///
+    /// `on_result` is called with each frame's index and its result, or the
+    /// parse error for malformed frames; returning an error stops the run.
+    /// Oversized or truncated frames end the stream with an error since
+    /// framing can't be recovered.
+    pub fn verify_stream<R, F>(&self, mut reader: R, mut on_result: F) -> Result<StreamSummary>
+    where
+        R: Read,
+        F: FnMut(usize, Result<VerificationResult>) -> Result<()>,
+    {
+        let mut summary = StreamSummary::default();
+        let mut buf = Vec::new();
+
+        while read_frame(&mut reader, &mut buf)? {
+            let index = summary.total;
+            summary.total += 1;
+
+            let result = ProofEnvelope::parse(&buf)
+                .and_then(|envelope| self.verify_envelope(&envelope, None));
+            match &result {
+                Ok(result) if result.is_valid => summary.valid += 1,
+                Ok(_) => summary.invalid += 1,
+                Err(_) => summary.malformed += 1,
+            }
+            on_result(index, result)?;
+        }
+
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{fixture, public_inputs};
+
+    #[test]
+    fn test_verify_stream() {
+        let (_, vk_bytes, proof_bytes) = fixture();
+        let verifier = QueryVerifier::builder()
+            .key_bytes(vk_bytes)
+            .build()
+            .unwrap();
+
+        let valid = ProofEnvelope::new(proof_bytes, public_inputs());
+        let mut tampered = valid.clone();
+        tampered.public_inputs.timestamp += 1;
+
+        let mut stream = Vec::new();
+        write_frame(&mut stream, &valid).unwrap();
+        write_frame(&mut stream, &tampered).unwrap();
+        stream.extend_from_slice(&3u32.to_le_bytes());
+        stream.extend_from_slice(b"bad");
+        write_frame(&mut stream, &valid).unwrap();
+
+        let mut seen = Vec::new();
+        let summary = verifier
+            .verify_stream(stream.as_slice(), |index, result| {
+                seen.push((index, result.map(|r| r.is_valid).ok()));
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(
+            summary,
+            StreamSummary {
+                total: 4,
+                valid: 2,
+                invalid: 1,
+                malformed: 1,
+            }
+        );
+        assert_eq!(
+            seen,
+            vec![
+                (0, Some(true)),
+                (1, Some(false)),
+                (2, None),
+                (3, Some(true))
+            ]
+        );
+
+        // A frame cut short can't be resynchronized
+        stream.truncate(stream.len() - 1);
+        assert!(verifier
+            .verify_stream(stream.as_slice(), |_, _| Ok(()))
+            .is_err());
+    }
+}
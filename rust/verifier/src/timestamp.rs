@@ -0,0 +1,449 @@
+// RFC 3161 timestamp tokens
+//
+// A proof's public timestamp is self-reported by the prover. A timestamp
+// authority (TSA) token makes it defensible: the prover asks a TSA to sign
+// `timestamp_imprint(proof, inputs)`, and the verifier checks that
+//
+// - the token is a CMS SignedData over a TSTInfo whose SHA-256 message
+//   imprint equals the imprint of this proof and these public inputs,
+// - it was signed (RSA PKCS#1 v1.5 or ECDSA P-256, SHA-256) by one of the
+//   pinned TSA certificates, which was valid at the token's genTime, and
+// - the public timestamp is no later than genTime (plus clock skew) and no
+//   more than `max_delay` earlier.
+//
+// TSA certificates are trust anchors here; chain building and revocation of
+// the TSA's own certificate are left to whoever pins them.
+
+use cms::cert::IssuerAndSerialNumber;
+use cms::content_info::ContentInfo;
+use cms::signed_data::{SignedData, SignerIdentifier, SignerInfo};
+use const_oid::db::{rfc5911, rfc5912};
+use const_oid::ObjectIdentifier;
+use der::asn1::{GeneralizedTime, Int, OctetString};
+use der::{Any, Decode, Encode, Sequence};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use x509_cert::ext::Extensions;
+use x509_cert::spki::AlgorithmIdentifierOwned;
+use x509_cert::Certificate;
+
+use crate::canonical::canonical_digest;
+use crate::error::{bail, Result, VerifierError};
+use crate::{proof_digest, PublicInputs, MAX_CLOCK_SKEW_SECS};
+
+/// `id-ct-TSTInfo` (RFC 3161 section 2.4.2)
+pub const ID_CT_TST_INFO: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.1.4");
+
+/// Default longest gap between the proof's timestamp and the TSA's genTime
+pub const DEFAULT_MAX_TIMESTAMP_DELAY_SECS: u64 = 300;
+
+/// Largest accepted token (TSA tokens with embedded chains are a few KB)
+pub const MAX_TOKEN_SIZE: usize = 64 * 1024;
+
+/// `MessageImprint` (RFC 3161 section 2.4.1)
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+pub struct MessageImprint {
+    pub hash_algorithm: AlgorithmIdentifierOwned,
+    pub hashed_message: OctetString,
+}
+
+/// `Accuracy` (RFC 3161 section 2.4.2)
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+pub struct Accuracy {
+    #[asn1(optional = "true")]
+    pub seconds: Option<u64>,
+    #[asn1(context_specific = "0", tag_mode = "IMPLICIT", optional = "true")]
+    pub millis: Option<u16>,
+    #[asn1(context_specific = "1", tag_mode = "IMPLICIT", optional = "true")]
+    pub micros: Option<u16>,
+}
+
+/// `TSTInfo` (RFC 3161 section 2.4.2)
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+pub struct TstInfo {
+    pub version: u8,
+    pub policy: ObjectIdentifier,
+    pub message_imprint: MessageImprint,
+    pub serial_number: Int,
+    pub gen_time: GeneralizedTime,
+    #[asn1(optional = "true")]
+    pub accuracy: Option<Accuracy>,
+    #[asn1(default = "Default::default")]
+    pub ordering: bool,
+    #[asn1(optional = "true")]
+    pub nonce: Option<Int>,
+    #[asn1(context_specific = "0", tag_mode = "EXPLICIT", optional = "true")]
+    pub tsa: Option<Any>,
+    #[asn1(context_specific = "1", tag_mode = "IMPLICIT", optional = "true")]
+    pub extensions: Option<Extensions>,
+}
+
+/// What a validated token attests
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimestampAttestation {
+    /// TSA time, Unix seconds
+    pub gen_time: u64,
+    /// Hex-encoded token serial number
+    pub serial_number: String,
+    pub policy: String,
+}
+
+/// Message the TSA timestamps: SHA-256 binding the proof bytes to its
+/// public inputs
+pub fn timestamp_imprint(proof_bytes: &[u8], public_inputs: &PublicInputs) -> [u8; 32] {
+    #[derive(Serialize)]
+    struct Imprint {
+        proof_digest: String,
+        inputs_digest: String,
+    }
+
+    let digest = canonical_digest(&Imprint {
+        proof_digest: proof_digest(proof_bytes),
+        inputs_digest: public_inputs.digest(),
+    })
+    .expect("imprint serializes");
+    hex::decode(digest)
+        .expect("digest is hex")
+        .try_into()
+        .expect("SHA-256 digest")
+}
+
+/// Validates tokens from a set of pinned TSA certificates
+pub struct TimestampAuthority {
+    certificates: Vec<Certificate>,
+    max_delay: Duration,
+}
+
+impl TimestampAuthority {
+    /// Trust tokens signed by any of these certificates
+    pub fn new(certificates: Vec<Certificate>) -> Self {
+        Self {
+            certificates,
+            max_delay: Duration::from_secs(DEFAULT_MAX_TIMESTAMP_DELAY_SECS),
+        }
+    }
+
+    /// Trust the certificates in a PEM bundle
+    pub fn from_pem(pem: &[u8]) -> Result<Self> {
+        let certificates = Certificate::load_pem_chain(pem)
+            .map_err(|e| VerifierError::malformed(format!("Invalid TSA certificate PEM: {}", e)))?;
+        if certificates.is_empty() {
+            bail!(Malformed, "No TSA certificates found");
+        }
+        Ok(Self::new(certificates))
+    }
+
+    /// Longest accepted gap between the proof's timestamp and genTime
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Validate a DER token for a proof and its public inputs
+    pub fn validate(
+        &self,
+        token: &[u8],
+        proof_bytes: &[u8],
+        public_inputs: &PublicInputs,
+    ) -> std::result::Result<TimestampAttestation, String> {
+        if token.len() > MAX_TOKEN_SIZE {
+            return Err(format!(
+                "token is {} bytes, limit is {}",
+                token.len(),
+                MAX_TOKEN_SIZE
+            ));
+        }
+
+        let content_info = ContentInfo::from_der(token).map_err(der_error)?;
+        if content_info.content_type != rfc5911::ID_SIGNED_DATA {
+            return Err("token is not CMS SignedData".to_string());
+        }
+        let signed_data: SignedData = content_info.content.decode_as().map_err(der_error)?;
+
+        let encap = &signed_data.encap_content_info;
+        if encap.econtent_type != ID_CT_TST_INFO {
+            return Err("token does not contain TSTInfo".to_string());
+        }
+        let tst_der = encap
+            .econtent
+            .as_ref()
+            .ok_or("token has no TSTInfo content")?
+            .value();
+        let tst_info = TstInfo::from_der(tst_der).map_err(der_error)?;
+
+        let imprint = &tst_info.message_imprint;
+        if imprint.hash_algorithm.oid != rfc5912::ID_SHA_256 {
+            return Err("message imprint is not SHA-256".to_string());
+        }
+        if imprint.hashed_message.as_bytes() != timestamp_imprint(proof_bytes, public_inputs) {
+            return Err("token does not cover this proof".to_string());
+        }
+
+        let [signer_info] = signed_data.signer_infos.0.as_slice() else {
+            return Err("token must have exactly one signer".to_string());
+        };
+        let certificate = self.signer_certificate(signer_info)?;
+        verify_signer(signer_info, certificate, tst_der)?;
+
+        let gen_time = tst_info.gen_time.to_unix_duration().as_secs();
+        let validity = &certificate.tbs_certificate.validity;
+        if gen_time < validity.not_before.to_unix_duration().as_secs()
+            || gen_time > validity.not_after.to_unix_duration().as_secs()
+        {
+            return Err("TSA certificate was not valid at genTime".to_string());
+        }
+
+        let timestamp = public_inputs.timestamp;
+        if timestamp > gen_time.saturating_add(MAX_CLOCK_SKEW_SECS) {
+            return Err(format!(
+                "proof timestamp {} is after genTime {}",
+                timestamp, gen_time
+            ));
+        }
+        if gen_time - timestamp.min(gen_time) > self.max_delay.as_secs() {
+            return Err(format!(
+                "genTime {} is more than {}s after proof timestamp {}",
+                gen_time,
+                self.max_delay.as_secs(),
+                timestamp
+            ));
+        }
+
+        Ok(TimestampAttestation {
+            gen_time,
+            serial_number: hex::encode(tst_info.serial_number.as_bytes()),
+            policy: tst_info.policy.to_string(),
+        })
+    }
+
+    /// Pinned certificate matching a signer's issuer and serial number
+    fn signer_certificate(
+        &self,
+        signer_info: &SignerInfo,
+    ) -> std::result::Result<&Certificate, String> {
+        let SignerIdentifier::IssuerAndSerialNumber(IssuerAndSerialNumber {
+            issuer,
+            serial_number,
+        }) = &signer_info.sid
+        else {
+            return Err("signer must be identified by issuer and serial number".to_string());
+        };
+        self.certificates
+            .iter()
+            .find(|cert| {
+                cert.tbs_certificate.issuer == *issuer
+                    && cert.tbs_certificate.serial_number == *serial_number
+            })
+            .ok_or_else(|| "token was not signed by a trusted TSA".to_string())
+    }
+}
+
+/// Check the signed attributes cover the TSTInfo and carry a valid signature
+fn verify_signer(
+    signer_info: &SignerInfo,
+    certificate: &Certificate,
+    tst_der: &[u8],
+) -> std::result::Result<(), String> {
+    if signer_info.digest_alg.oid != rfc5912::ID_SHA_256 {
+        return Err("signer digest is not SHA-256".to_string());
+    }
+    let signed_attrs = signer_info
+        .signed_attrs
+        .as_ref()
+        .ok_or("token has no signed attributes")?;
+
+    let attribute = |oid: ObjectIdentifier| {
+        signed_attrs
+            .iter()
+            .find(|attr| attr.oid == oid)
+            .and_then(|attr| attr.values.get(0))
+            .ok_or_else(|| format!("missing signed attribute {}", oid))
+    };
+    let content_type: ObjectIdentifier = attribute(rfc5911::ID_CONTENT_TYPE)?
+        .decode_as()
+        .map_err(der_error)?;
+    if content_type != ID_CT_TST_INFO {
+        return Err("signed content type is not TSTInfo".to_string());
+    }
+    let message_digest: OctetString = attribute(rfc5911::ID_MESSAGE_DIGEST)?
+        .decode_as()
+        .map_err(der_error)?;
+    if message_digest.as_bytes() != Sha256::digest(tst_der).as_slice() {
+        return Err("signed digest does not match TSTInfo".to_string());
+    }
+
+    let message = signed_attrs.to_der().map_err(der_error)?;
+    let signature = signer_info.signature.as_bytes();
+    let spki = &certificate.tbs_certificate.subject_public_key_info;
+    let algorithm = signer_info.signature_algorithm.oid;
+
+    if algorithm == rfc5912::ECDSA_WITH_SHA_256 {
+        use p256::ecdsa::signature::Verifier;
+        let key = p256::ecdsa::VerifyingKey::from_sec1_bytes(spki.subject_public_key.raw_bytes())
+            .map_err(|_| "TSA certificate key is not P-256".to_string())?;
+        let signature = p256::ecdsa::DerSignature::try_from(signature)
+            .map_err(|_| "malformed ECDSA signature".to_string())?;
+        key.verify(&message, &signature)
+            .map_err(|_| "invalid TSA signature".to_string())
+    } else if algorithm == rfc5912::SHA_256_WITH_RSA_ENCRYPTION
+        || algorithm == rfc5912::RSA_ENCRYPTION
+    {
+        use rsa::pkcs1::DecodeRsaPublicKey;
+        use rsa::signature::Verifier;
+        let key = rsa::RsaPublicKey::from_pkcs1_der(spki.subject_public_key.raw_bytes())
+            .map_err(|_| "TSA certificate key is not RSA".to_string())?;
+        let key = rsa::pkcs1v15::VerifyingKey::<Sha256>::new(key);
+        let signature = rsa::pkcs1v15::Signature::try_from(signature)
+            .map_err(|_| "malformed RSA signature".to_string())?;
+        key.verify(&message, &signature)
+            .map_err(|_| "invalid TSA signature".to_string())
+    } else {
+        Err(format!("unsupported signature algorithm {}", algorithm))
+    }
+}
+
+fn der_error(error: der::Error) -> String {
+    format!("malformed token: {}", error)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use cms::builder::{SignedDataBuilder, SignerInfoBuilder};
+    use cms::signed_data::EncapsulatedContentInfo;
+    use der::Tag;
+    use p256::ecdsa::{DerSignature, SigningKey};
+    use std::str::FromStr;
+    use x509_cert::builder::{Builder, CertificateBuilder, Profile};
+    use x509_cert::name::Name;
+    use x509_cert::serial_number::SerialNumber;
+    use x509_cert::spki::SubjectPublicKeyInfoOwned;
+    use x509_cert::time::{Time, Validity};
+
+    fn public_inputs() -> PublicInputs {
+        PublicInputs {
+            document_commitment: "abc123".to_string(),
+            model_hash: "model456".to_string(),
+            timestamp: 1_700_000_000,
+            nonce: None,
+        }
+    }
+
+    fn time(secs: u64) -> GeneralizedTime {
+        GeneralizedTime::from_unix_duration(Duration::from_secs(secs)).unwrap()
+    }
+
+    /// A self-signed P-256 TSA certificate and its signing key
+    pub(crate) fn tsa() -> (Certificate, SigningKey) {
+        let key = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let spki = SubjectPublicKeyInfoOwned::from_key(*key.verifying_key()).unwrap();
+        let validity = Validity {
+            not_before: Time::GeneralTime(time(1_200_000_000)),
+            not_after: Time::GeneralTime(time(1_800_000_000)),
+        };
+        let cert = CertificateBuilder::new(
+            Profile::Root,
+            SerialNumber::from(42u32),
+            validity,
+            Name::from_str("CN=Test TSA").unwrap(),
+            spki,
+            &key,
+        )
+        .unwrap()
+        .build::<DerSignature>()
+        .unwrap();
+        (cert, key)
+    }
+
+    /// A DER token over `imprint` generated at `gen_time`
+    pub(crate) fn token(
+        cert: &Certificate,
+        key: &SigningKey,
+        imprint: [u8; 32],
+        gen_time: u64,
+    ) -> Vec<u8> {
+        let sha256 = AlgorithmIdentifierOwned {
+            oid: rfc5912::ID_SHA_256,
+            parameters: None,
+        };
+        let tst_info = TstInfo {
+            version: 1,
+            policy: ObjectIdentifier::new_unwrap("1.2.3.4"),
+            message_imprint: MessageImprint {
+                hash_algorithm: sha256.clone(),
+                hashed_message: OctetString::new(imprint).unwrap(),
+            },
+            serial_number: Int::new(&[1]).unwrap(),
+            gen_time: time(gen_time),
+            accuracy: None,
+            ordering: false,
+            nonce: None,
+            tsa: None,
+            extensions: None,
+        };
+        let content = EncapsulatedContentInfo {
+            econtent_type: ID_CT_TST_INFO,
+            econtent: Some(Any::new(Tag::OctetString, tst_info.to_der().unwrap()).unwrap()),
+        };
+        let sid = SignerIdentifier::IssuerAndSerialNumber(IssuerAndSerialNumber {
+            issuer: cert.tbs_certificate.issuer.clone(),
+            serial_number: cert.tbs_certificate.serial_number.clone(),
+        });
+        let signer = SignerInfoBuilder::new(key, sid, sha256.clone(), &content, None).unwrap();
+        SignedDataBuilder::new(&content)
+            .add_digest_algorithm(sha256)
+            .unwrap()
+            .add_signer_info::<SigningKey, DerSignature>(signer)
+            .unwrap()
+            .build()
+            .unwrap()
+            .to_der()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_validate_token() {
+        let (cert, key) = tsa();
+        let authority = TimestampAuthority::new(vec![cert.clone()]);
+        let inputs = public_inputs();
+        let imprint = timestamp_imprint(b"proof", &inputs);
+
+        let valid = token(&cert, &key, imprint, inputs.timestamp + 5);
+        let attestation = authority.validate(&valid, b"proof", &inputs).unwrap();
+        assert_eq!(attestation.gen_time, inputs.timestamp + 5);
+        assert_eq!(attestation.policy, "1.2.3.4");
+
+        // Bound to the proof bytes and to the public inputs
+        assert!(authority.validate(&valid, b"other", &inputs).is_err());
+        let mut shifted = inputs.clone();
+        shifted.timestamp -= 1;
+        assert!(authority.validate(&valid, b"proof", &shifted).is_err());
+
+        // genTime must follow the claimed timestamp closely
+        let late = token(&cert, &key, imprint, inputs.timestamp + 3600);
+        assert!(authority.validate(&late, b"proof", &inputs).is_err());
+        let early = token(&cert, &key, imprint, inputs.timestamp - 3600);
+        assert!(authority.validate(&early, b"proof", &inputs).is_err());
+
+        assert!(authority.validate(b"garbage", b"proof", &inputs).is_err());
+    }
+
+    #[test]
+    fn test_untrusted_signer() {
+        let (cert, _) = tsa();
+        let other = SigningKey::from_slice(&[9u8; 32]).unwrap();
+        let inputs = public_inputs();
+        let forged = token(
+            &cert,
+            &other,
+            timestamp_imprint(b"proof", &inputs),
+            inputs.timestamp,
+        );
+
+        let authority = TimestampAuthority::new(vec![cert]);
+        let error = authority.validate(&forged, b"proof", &inputs).unwrap_err();
+        assert!(error.contains("signature"), "{}", error);
+    }
+}
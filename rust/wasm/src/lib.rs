@@ -0,0 +1,46 @@
+// WebAssembly bindings for ZKvsAI
+//
+// Lets a web dashboard verify document query proofs client-side, so users
+// don't have to trust the backend's verdict. Verification goes through
+// zkrag-verifier-core, the same no_std code the Rust verifier uses for BN254.
+
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+use zkrag_verifier_core::{self as core, CoreError};
+
+/// Public inputs as a JS object:
+/// `{ document_commitment, model_hash, timestamp, nonce? }`
+#[derive(Deserialize)]
+struct PublicInputs {
+    document_commitment: String,
+    model_hash: String,
+    timestamp: u64,
+    #[serde(default)]
+    nonce: Option<String>,
+}
+
+/// Verify a proof against its public inputs and a compressed verifying key.
+///
+/// Returns false if the pairing check fails, and throws if the proof, key,
+/// or public inputs are malformed.
+#[wasm_bindgen]
+pub fn verify_proof(proof: &[u8], public_inputs: JsValue, vk: &[u8]) -> Result<bool, JsError> {
+    let inputs: PublicInputs = serde_wasm_bindgen::from_value(public_inputs)?;
+    let vk = core::prepare_verifying_key(vk).map_err(js_error)?;
+    let fields = core::public_inputs_to_fields(
+        &core::bound_commitment(&inputs.document_commitment, inputs.nonce.as_deref()),
+        &inputs.model_hash,
+        inputs.timestamp,
+    );
+
+    match core::verify_proof_bytes(&vk, proof, &fields) {
+        Ok(()) => Ok(true),
+        Err(CoreError::PairingFailed) => Ok(false),
+        Err(e) => Err(js_error(e)),
+    }
+}
+
+/// CoreError is no_std, so it doesn't implement std::error::Error
+fn js_error(error: CoreError) -> JsError {
+    JsError::new(&error.to_string())
+}